@@ -0,0 +1,326 @@
+/// Model edits shared by every way of issuing them
+///
+/// Buttons, keyboard shortcuts, scripts and the network API all end up
+/// calling these functions, so an edit behaves the same whichever way it
+/// was asked for. They work on the registries alone and know nothing of
+/// the ECS.
+use crate::domain::geometry::{distance, prism_loops};
+use crate::domain::solver::{Constraint, ConstraintSet};
+use crate::domain::{
+    ElementKind, ElementRegistry, GeometryRegistry, Point, Tolerance, WallAssembly,
+};
+use std::collections::BTreeSet;
+use uuid::Uuid;
+
+/// The footprint of a straight wall centered on a line, at the start's
+/// height
+///
+/// Returns None if the line is shorter than the tolerance or the
+/// thickness is not positive.
+#[must_use]
+pub fn wall_footprint(
+    start: &Point,
+    end: &Point,
+    thickness: f32,
+    tolerance: &Tolerance,
+) -> Option<Vec<Point>> {
+    wall_layer_footprint(start, end, thickness / 2.0, -thickness / 2.0, tolerance)
+}
+
+/// The footprint of a strip alongside a line, between two offsets from
+/// it, at the start's height
+///
+/// Offsets are positive to the right of the line looking from its start
+/// to its end, seen from above, the side an assembly's first layer is on.
+/// Returns None if the line is shorter than the tolerance or the near
+/// offset is not beyond the far one.
+#[must_use]
+pub fn wall_layer_footprint(
+    start: &Point,
+    end: &Point,
+    near: f32,
+    far: f32,
+    tolerance: &Tolerance,
+) -> Option<Vec<Point>> {
+    let (dx, dz) = (end.x - start.x, end.z - start.z);
+    let length = dx.hypot(dz);
+    if length <= tolerance.linear || near <= far {
+        return None;
+    }
+    // Square to the line in plan
+    let (normal_x, normal_z) = (-dz / length, dx / length);
+    let corner = |point: &Point, offset: f32| Point {
+        x: point.x + normal_x * offset,
+        y: start.y,
+        z: point.z + normal_z * offset,
+    };
+    Some(vec![
+        corner(start, near),
+        corner(end, near),
+        corner(end, far),
+        corner(start, far),
+    ])
+}
+
+/// Create a straight wall element rising from a line and return the
+/// element's ID
+///
+/// Returns None if the line or dimensions cannot make a wall, as
+/// described in [`wall_footprint`].
+pub fn create_wall(
+    geometry_registry: &mut GeometryRegistry,
+    element_registry: &mut ElementRegistry,
+    start: &Point,
+    end: &Point,
+    thickness: f32,
+    height: f32,
+    tolerance: &Tolerance,
+) -> Option<Uuid> {
+    if height <= tolerance.linear {
+        return None;
+    }
+    let footprint = wall_footprint(start, end, thickness, tolerance)?;
+    Some(store_wall(
+        geometry_registry,
+        element_registry,
+        &footprint,
+        height,
+        "Wall",
+        tolerance,
+    ))
+}
+
+/// Raise a wall's solid from its footprint, as made by
+/// [`wall_layer_footprint`], and store its element
+///
+/// The footprint's first corner is on the wall's first face, which the
+/// element records.
+fn store_wall(
+    geometry_registry: &mut GeometryRegistry,
+    element_registry: &mut ElementRegistry,
+    footprint: &[Point],
+    height: f32,
+    name: &str,
+    tolerance: &Tolerance,
+) -> Uuid {
+    let solid =
+        geometry_registry.create_solid_from_loops(&prism_loops(footprint, height), tolerance);
+    let first_face = geometry_registry
+        .solid_vertices(&solid)
+        .into_iter()
+        .filter_map(|id| {
+            let position = &geometry_registry.vertices.get(&id)?.position;
+            Some((id, distance(position, &footprint[0])))
+        })
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(id, _)| id);
+    let id = element_registry.create_and_store(ElementKind::Wall, &solid, name);
+    if let Some(element) = element_registry.get_mut(&id) {
+        element.first_face = first_face;
+    }
+    id
+}
+
+/// Create a straight wall built of an assembly's layers, rising from a
+/// line, and return the IDs of the elements made
+///
+/// The wall is as thick as the assembly, centered on the line. Modelled
+/// whole, it is one element keeping the assembly. Modelled per layer, each
+/// layer is its own element with the layer's material, so its solid can
+/// be scheduled and edited apart from the rest.
+///
+/// Returns None if the line or dimensions cannot make a wall, as
+/// described in [`wall_footprint`], or the assembly has no thickness.
+#[allow(clippy::too_many_arguments)]
+pub fn create_layered_wall(
+    geometry_registry: &mut GeometryRegistry,
+    element_registry: &mut ElementRegistry,
+    start: &Point,
+    end: &Point,
+    assembly: &WallAssembly,
+    height: f32,
+    per_layer: bool,
+    tolerance: &Tolerance,
+) -> Option<Vec<Uuid>> {
+    if !per_layer {
+        let id = create_wall(
+            geometry_registry,
+            element_registry,
+            start,
+            end,
+            assembly.thickness(),
+            height,
+            tolerance,
+        )?;
+        if let Some(element) = element_registry.get_mut(&id) {
+            element.name = format!("Wall: {}", assembly.name);
+            element.assembly = Some(assembly.clone());
+        }
+        return Some(vec![id]);
+    }
+    if height <= tolerance.linear || assembly.thickness() <= 0.0 {
+        return None;
+    }
+    let mut walls = Vec::new();
+    for (index, (near, far)) in assembly.layer_offsets().into_iter().enumerate() {
+        let Some(footprint) = wall_layer_footprint(start, end, near, far, tolerance) else {
+            continue;
+        };
+        let layer = &assembly.layers[index];
+        let id = store_wall(
+            geometry_registry,
+            element_registry,
+            &footprint,
+            height,
+            &format!("Wall: {}", layer.name),
+            tolerance,
+        );
+        if let Some(element) = element_registry.get_mut(&id) {
+            element.material.clone_from(&layer.material);
+            element.assembly = assembly.single_layer(index);
+        }
+        walls.push(id);
+    }
+    (!walls.is_empty()).then_some(walls)
+}
+
+/// Create a path of sketch segments joining points in order and return
+/// the segments' IDs
+///
+/// Points closer than the tolerance to the one before are dropped, so a
+/// path too short to draw gives no segments.
+pub fn create_sketch_path(
+    geometry_registry: &mut GeometryRegistry,
+    points: &[Point],
+    tolerance: &Tolerance,
+) -> Vec<Uuid> {
+    let mut kept: Vec<&Point> = Vec::new();
+    for point in points {
+        if kept
+            .last()
+            .is_none_or(|last| distance(last, point) > tolerance.linear)
+        {
+            kept.push(point);
+        }
+    }
+    if kept.len() < 2 {
+        return Vec::new();
+    }
+    let vertices: Vec<Uuid> = kept
+        .into_iter()
+        .map(|point| geometry_registry.vertices.create_and_store(point.clone()))
+        .collect();
+    vertices
+        .windows(2)
+        .map(|pair| {
+            geometry_registry
+                .segments
+                .create_and_store(&pair[0], &pair[1])
+        })
+        .collect()
+}
+
+/// Move a vertex and refit the planes of the polygons using it
+///
+/// Returns the solids whose shape changed, sorted by ID, or None if the
+/// vertex is missing.
+pub fn move_vertex(
+    geometry_registry: &mut GeometryRegistry,
+    vertex_id: &Uuid,
+    position: &Point,
+) -> Option<Vec<Uuid>> {
+    geometry_registry
+        .vertices
+        .get_mut(vertex_id)?
+        .position
+        .move_to_position(position);
+    Some(refit_around(
+        geometry_registry,
+        &BTreeSet::from([*vertex_id]),
+    ))
+}
+
+/// Map the positions of a set of vertices and refit the planes of the
+/// polygons using them
+///
+/// Missing vertices are skipped. Returns the solids whose shape changed,
+/// sorted by ID.
+pub fn transform_vertices(
+    geometry_registry: &mut GeometryRegistry,
+    vertex_ids: &[Uuid],
+    map: impl Fn(&Point) -> Point,
+) -> Vec<Uuid> {
+    let mut moved = BTreeSet::new();
+    for vertex_id in vertex_ids {
+        if let Some(vertex) = geometry_registry.vertices.get_mut(vertex_id) {
+            vertex.position = map(&vertex.position);
+            moved.insert(*vertex_id);
+        }
+    }
+    refit_around(geometry_registry, &moved)
+}
+
+/// Refit the planes of the polygons using any of a set of vertices and
+/// return the solids holding those polygons, sorted by ID
+fn refit_around(
+    geometry_registry: &mut GeometryRegistry,
+    vertex_ids: &BTreeSet<Uuid>,
+) -> Vec<Uuid> {
+    let segments: Vec<Uuid> = geometry_registry
+        .segments
+        .segments
+        .values()
+        .filter(|segment| segment.vertices.iter().any(|id| vertex_ids.contains(id)))
+        .map(|segment| segment.id)
+        .collect();
+    let polygons: BTreeSet<Uuid> = geometry_registry
+        .polygons
+        .polygons
+        .values()
+        .filter(|polygon| polygon.segments.iter().any(|id| segments.contains(id)))
+        .map(|polygon| polygon.id)
+        .collect();
+    for polygon_id in &polygons {
+        geometry_registry.update_polygon_plane(polygon_id);
+    }
+    let solids: BTreeSet<Uuid> = geometry_registry
+        .solids
+        .solids
+        .values()
+        .filter(|solid| solid.polygons.iter().any(|id| polygons.contains(id)))
+        .map(|solid| solid.id)
+        .collect();
+    solids.into_iter().collect()
+}
+
+/// Add an explicit constraint to a set
+///
+/// Returns false, leaving the set unchanged, if it already holds the same
+/// constraint.
+pub fn add_constraint(constraint_set: &mut ConstraintSet, constraint: Constraint) -> bool {
+    if constraint_set
+        .explicit
+        .iter()
+        .any(|existing| existing.is_duplicate_of(&constraint))
+    {
+        return false;
+    }
+    constraint_set.explicit.push(constraint);
+    true
+}
+
+/// Remove an explicit constraint from a set
+///
+/// Returns false, leaving the set unchanged, if it holds no such
+/// constraint.
+pub fn remove_constraint(constraint_set: &mut ConstraintSet, constraint: &Constraint) -> bool {
+    let Some(place) = constraint_set
+        .explicit
+        .iter()
+        .position(|existing| existing.is_duplicate_of(constraint))
+    else {
+        return false;
+    };
+    constraint_set.explicit.remove(place);
+    true
+}
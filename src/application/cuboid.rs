@@ -1,3 +1,4 @@
+use crate::domain::validation::orient_solid_outward;
 use crate::domain::{GeometryRegistry, Point};
 use uuid::Uuid;
 
@@ -109,7 +110,11 @@ pub fn create_rectangular_solid(
         &right_face,
     ]);
 
-    // Phase 5: Return the ID of the solid
+    // Phase 5: Make the winding explicit and store each face's outward plane
+    // A closed cuboid is always orientable, so this cannot fail
+    let _ = orient_solid_outward(geometry_registry, &solid_id);
+
+    // Phase 6: Return the ID of the solid
     solid_id
 }
 
@@ -0,0 +1,246 @@
+/// Dividing facade faces into curtain walls
+///
+/// Creating a curtain grid reads the face's plane and extent, divides it
+/// by the grid's rules and places a component instance for every mullion,
+/// transom and panel. Pieces of one kind and size share a definition, so
+/// a regular grid needs only a few.
+///
+/// Regenerating grids after an edit compares each face with what its
+/// pieces were built for. When the face has moved, turned or changed
+/// size, the old pieces and the definitions only they used are removed
+/// and the grid is built again over the face as it now stands.
+use crate::domain::geometry::{newell_normal, prism_loops, rectangle_loop};
+use crate::domain::{
+    curtain_grid_pieces, new_component_definition, new_id, ComponentLibrary, CurtainGrid,
+    CurtainGridRegistry, CurtainGridSettings, GeometryRegistry, GridPiece, LinkTransform, Point,
+    Tolerance,
+};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// The category curtain wall definitions are listed under
+pub const CURTAIN_WALL_CATEGORY: &str = "Curtain Wall";
+
+/// Why a face could not be divided
+#[derive(Debug, Clone, PartialEq)]
+pub enum CurtainGridError {
+    /// The face is missing
+    MissingFace,
+    /// The face is not vertical
+    NotVertical,
+    /// The face has no width or height
+    Degenerate,
+    /// The face is already divided
+    AlreadyDivided,
+}
+
+impl std::fmt::Display for CurtainGridError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CurtainGridError::MissingFace => write!(f, "The face does not exist"),
+            CurtainGridError::NotVertical => write!(f, "Only vertical faces can be divided"),
+            CurtainGridError::Degenerate => write!(f, "The face has no width or height"),
+            CurtainGridError::AlreadyDivided => write!(f, "The face is already divided"),
+        }
+    }
+}
+
+impl std::error::Error for CurtainGridError {}
+
+/// Where a face stands: its bottom left corner seen from outside, its
+/// direction across as X and Z, and its width and height
+fn face_extent(
+    geometry: &GeometryRegistry,
+    face: &Uuid,
+    tolerance: &Tolerance,
+) -> Result<[f32; 7], CurtainGridError> {
+    let points = geometry
+        .polygon_points(face)
+        .ok_or(CurtainGridError::MissingFace)?;
+    let normal = newell_normal(&points)
+        .normalized()
+        .ok_or(CurtainGridError::Degenerate)?;
+    if normal.y.abs() > tolerance.angular.sin() {
+        return Err(CurtainGridError::NotVertical);
+    }
+    let flat = (normal.x * normal.x + normal.z * normal.z).sqrt();
+    let direction = [normal.z / flat, -normal.x / flat];
+    let along = |point: &Point| point.x * direction[0] + point.z * direction[1];
+    let fold = |values: &mut dyn Iterator<Item = f32>| {
+        values.fold([f32::MAX, f32::MIN], |[low, high], value| {
+            [low.min(value), high.max(value)]
+        })
+    };
+    let [left, right] = fold(&mut points.iter().map(along));
+    let [bottom, top] = fold(&mut points.iter().map(|point| point.y));
+    let (width, height) = (right - left, top - bottom);
+    if width <= tolerance.linear || height <= tolerance.linear {
+        return Err(CurtainGridError::Degenerate);
+    }
+    let offset = points[0].x * normal.x / flat + points[0].z * normal.z / flat;
+    Ok([
+        left * direction[0] + offset * normal.x / flat,
+        bottom,
+        left * direction[1] + offset * normal.z / flat,
+        direction[0],
+        direction[1],
+        width,
+        height,
+    ])
+}
+
+/// The key a piece's definition is shared by: its kind and its size to the
+/// millimeter
+#[allow(clippy::cast_possible_truncation)]
+fn definition_key(piece: &GridPiece) -> (&'static str, [i32; 3]) {
+    (
+        piece.kind.label(),
+        piece.size.map(|size| (size * 1000.0).round() as i32),
+    )
+}
+
+/// Place the pieces of a grid over a face, returning their solids and the
+/// definitions they were placed from
+fn build_pieces(
+    geometry: &mut GeometryRegistry,
+    library: &mut ComponentLibrary,
+    settings: &CurtainGridSettings,
+    extent: [f32; 7],
+    face: &Uuid,
+    tolerance: &Tolerance,
+) -> (Vec<Uuid>, Vec<Uuid>) {
+    let [left, bottom, near, across_x, across_z, width, height] = extent;
+    let phase = geometry
+        .solids
+        .sorted()
+        .into_iter()
+        .find(|solid| solid.polygons.contains(face))
+        .map(|solid| solid.phase);
+    let mut definitions: HashMap<(&'static str, [i32; 3]), Uuid> = HashMap::new();
+    let mut solids = Vec::new();
+    for piece in curtain_grid_pieces(settings, width, height, tolerance.linear) {
+        let definition = *definitions
+            .entry(definition_key(&piece))
+            .or_insert_with(|| {
+                let [wide, high, deep] = piece.size;
+                library.store(new_component_definition(
+                    &format!("{} {wide:.3} x {high:.3}", piece.kind.label()),
+                    CURTAIN_WALL_CATEGORY,
+                    prism_loops(&rectangle_loop(0.0, 0.0, wide, deep, 0.0), high),
+                ))
+            });
+        let [across, up, out] = piece.corner;
+        let placement = LinkTransform {
+            x: left + across * across_x - out * across_z,
+            y: bottom + up,
+            z: near + across * across_z + out * across_x,
+            rotation: (-across_z).atan2(across_x),
+        };
+        let Some(solid) = library.place(&definition, placement, geometry, tolerance) else {
+            continue;
+        };
+        if let (Some(phase), Some(made)) = (phase, geometry.solids.get_mut(&solid)) {
+            made.phase = phase;
+        }
+        solids.push(solid);
+    }
+    let mut definitions: Vec<Uuid> = definitions.into_values().collect();
+    definitions.sort();
+    (solids, definitions)
+}
+
+/// Remove the pieces of a grid and the definitions no other instance uses
+fn remove_pieces(
+    geometry: &mut GeometryRegistry,
+    library: &mut ComponentLibrary,
+    grid: &CurtainGrid,
+) {
+    for solid in &grid.solids {
+        geometry.solids.remove(solid);
+        library.remove_instance(solid);
+    }
+    for definition in &grid.definitions {
+        if library.instances_of(definition).is_empty() {
+            library.remove(definition);
+        }
+    }
+}
+
+/// Divide a vertical face into a curtain grid
+///
+/// # Errors
+/// Returns an error if the face is missing, not vertical, has no area or
+/// is already divided
+pub fn create_curtain_grid(
+    geometry: &mut GeometryRegistry,
+    library: &mut ComponentLibrary,
+    grids: &mut CurtainGridRegistry,
+    face: &Uuid,
+    settings: CurtainGridSettings,
+    tolerance: &Tolerance,
+) -> Result<Uuid, CurtainGridError> {
+    if grids.on_face(face).is_some() {
+        return Err(CurtainGridError::AlreadyDivided);
+    }
+    let extent = face_extent(geometry, face, tolerance)?;
+    let (solids, definitions) = build_pieces(geometry, library, &settings, extent, face, tolerance);
+    Ok(grids.store(CurtainGrid {
+        id: new_id(),
+        face: *face,
+        settings,
+        built_for: extent,
+        solids,
+        definitions,
+    }))
+}
+
+/// Rebuild the grids whose faces have moved or changed size
+///
+/// A grid whose face is gone or no longer vertical loses its pieces and
+/// is removed. Returns the solids removed and placed, for their meshes to
+/// be refreshed.
+pub fn regenerate_curtain_grids(
+    geometry: &mut GeometryRegistry,
+    library: &mut ComponentLibrary,
+    grids: &mut CurtainGridRegistry,
+    tolerance: &Tolerance,
+) -> Vec<Uuid> {
+    let mut changed = Vec::new();
+    let ids: Vec<Uuid> = grids.sorted().iter().map(|grid| grid.id).collect();
+    for id in ids {
+        let Some(grid) = grids.get(&id).cloned() else {
+            continue;
+        };
+        let extent = face_extent(geometry, &grid.face, tolerance).ok();
+        let unchanged = extent.is_some_and(|extent| {
+            extent
+                .iter()
+                .zip(grid.built_for)
+                .all(|(now, then)| (now - then).abs() <= tolerance.linear)
+        });
+        if unchanged {
+            continue;
+        }
+        remove_pieces(geometry, library, &grid);
+        changed.extend(grid.solids.iter().copied());
+        let Some(extent) = extent else {
+            grids.remove(&id);
+            continue;
+        };
+        let (solids, definitions) = build_pieces(
+            geometry,
+            library,
+            &grid.settings,
+            extent,
+            &grid.face,
+            tolerance,
+        );
+        changed.extend(solids.iter().copied());
+        if let Some(grid) = grids.get_mut(&id) {
+            grid.built_for = extent;
+            grid.solids = solids;
+            grid.definitions = definitions;
+        }
+    }
+    changed
+}
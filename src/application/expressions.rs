@@ -0,0 +1,283 @@
+/// Keeping properties equal to expressions over other properties
+///
+/// An expression names levels and elements by name, as described in
+/// [`crate::domain::expression`], and reads these properties of them:
+///
+/// - a level's `elevation`, which may be set;
+/// - an element's `bottom`, `top` and `height`, and the `x` and `z` of its
+///   center, from its solid; setting `bottom`, `x` or `z` moves the solid;
+/// - for the elements of a door or window, the family's `sill`, as a
+///   height like a level's, and its `offset` along the wall, both of which
+///   may be set, and its `width` and `height`.
+///
+/// Solving evaluates every constraint in dependency order and sets its
+/// property, so it is run again after each edit to keep them satisfied.
+/// Properties already within the linear tolerance of their value are left
+/// alone, so solving a satisfied model changes nothing.
+use crate::application::commands::transform_vertices;
+use crate::application::families::update_hosted_families;
+use crate::domain::geometry::Bounds;
+use crate::domain::solver::{order_expression_constraints, ExpressionConstraint};
+use crate::domain::{
+    name_key, ElementRegistry, ExpressionError, FamilyRegistry, GeometryRegistry, LevelRegistry,
+    Point, PropertyRef, Tolerance,
+};
+use std::collections::BTreeSet;
+use uuid::Uuid;
+
+/// What solving expression constraints changed
+#[derive(Debug, Clone, Default)]
+pub struct ExpressionUpdate {
+    /// The solids whose shape changed, sorted by ID
+    pub solids: Vec<Uuid>,
+    /// The properties set to new values, in the order they were set
+    pub set: Vec<PropertyRef>,
+    /// The constraints that could not be satisfied and why, in the order
+    /// they were evaluated
+    pub errors: Vec<(Uuid, ExpressionError)>,
+}
+
+/// The model an expression reads and sets properties of
+pub struct ExpressionModel<'a> {
+    /// The geometry of the elements
+    pub geometry: &'a mut GeometryRegistry,
+    /// The elements, named
+    pub elements: &'a ElementRegistry,
+    /// The doors and windows hosted in walls
+    pub families: &'a mut FamilyRegistry,
+    /// The levels, named
+    pub levels: &'a mut LevelRegistry,
+}
+
+/// What an object name refers to
+#[derive(Debug, Clone, Copy)]
+enum Object {
+    /// A level
+    Level(Uuid),
+    /// An element
+    Element(Uuid),
+}
+
+impl ExpressionModel<'_> {
+    /// The level or element with a name
+    fn object(&self, name: &str) -> Result<Object, ExpressionError> {
+        let levels = self
+            .levels
+            .sorted()
+            .into_iter()
+            .filter(|level| name_key(&level.name) == name)
+            .map(|level| Object::Level(level.id));
+        let elements = self
+            .elements
+            .sorted()
+            .into_iter()
+            .filter(|element| name_key(&element.name) == name)
+            .map(|element| Object::Element(element.id));
+        let mut found = levels.chain(elements);
+        match (found.next(), found.next()) {
+            (Some(object), None) => Ok(object),
+            (Some(_), Some(_)) => Err(ExpressionError::Ambiguous(name.to_string())),
+            (None, _) => Err(ExpressionError::UnknownObject(name.to_string())),
+        }
+    }
+
+    /// The box around an element's solid
+    fn bounds(&self, element: &Uuid) -> Option<Bounds> {
+        let solid = self.elements.get(element)?.solid;
+        let points: Vec<Point> = self
+            .geometry
+            .solid_vertices(&solid)
+            .iter()
+            .filter_map(|id| self.geometry.vertices.get(id))
+            .map(|vertex| vertex.position.clone())
+            .collect();
+        Bounds::from_points(&points)
+    }
+
+    /// The family an element is part of, if any
+    fn family_of(&self, element: &Uuid) -> Option<Uuid> {
+        self.families
+            .sorted()
+            .into_iter()
+            .find(|family| family.elements.contains(element))
+            .map(|family| family.id)
+    }
+
+    /// The height of the base of a family's host wall
+    fn host_base(&self, family: &Uuid) -> Option<f32> {
+        let host = self.families.get(family)?.host;
+        Some(self.bounds(&host)?.min.y)
+    }
+
+    /// The value of a property
+    ///
+    /// # Errors
+    /// Returns an error if the object is missing or ambiguous or has no
+    /// such property
+    pub fn read(&self, property: &PropertyRef) -> Result<f32, ExpressionError> {
+        let unknown = || ExpressionError::UnknownProperty(property.clone());
+        match self.object(&property.object)? {
+            Object::Level(id) => match property.property.as_str() {
+                "elevation" => self
+                    .levels
+                    .get(&id)
+                    .map(|level| level.elevation)
+                    .ok_or_else(unknown),
+                _ => Err(unknown()),
+            },
+            Object::Element(id) => {
+                if let Some(family) = self.family_of(&id).and_then(|id| self.families.get(&id)) {
+                    let parameters = &family.parameters;
+                    match property.property.as_str() {
+                        "sill" => {
+                            let base = self.host_base(&family.id).ok_or_else(unknown)?;
+                            return Ok(base + parameters.sill_height);
+                        }
+                        "offset" => return Ok(family.offset),
+                        "width" => return Ok(parameters.width),
+                        "height" => return Ok(parameters.height),
+                        _ => {}
+                    }
+                }
+                let bounds = self.bounds(&id).ok_or_else(unknown)?;
+                match property.property.as_str() {
+                    "bottom" => Ok(bounds.min.y),
+                    "top" => Ok(bounds.max.y),
+                    "height" => Ok(bounds.max.y - bounds.min.y),
+                    "x" => Ok(f32::midpoint(bounds.min.x, bounds.max.x)),
+                    "z" => Ok(f32::midpoint(bounds.min.z, bounds.max.z)),
+                    _ => Err(unknown()),
+                }
+            }
+        }
+    }
+
+    /// Set a property, returning the solids whose shape changed
+    ///
+    /// # Errors
+    /// Returns an error, changing nothing, if the property cannot be read
+    /// or set, or would put a door or window outside its wall
+    pub fn write(
+        &mut self,
+        property: &PropertyRef,
+        value: f32,
+        tolerance: &Tolerance,
+    ) -> Result<Vec<Uuid>, ExpressionError> {
+        let change = value - self.read(property)?;
+        let read_only = || ExpressionError::ReadOnly(property.clone());
+        match (self.object(&property.object)?, property.property.as_str()) {
+            (Object::Level(id), "elevation") => {
+                if let Some(level) = self.levels.get_mut(&id) {
+                    level.elevation = value;
+                }
+                Ok(Vec::new())
+            }
+            (Object::Element(id), name) => {
+                if let Some(family) = self.family_of(&id) {
+                    return self.write_family(property, family, change, tolerance);
+                }
+                let offset = match name {
+                    "bottom" => [0.0, change, 0.0],
+                    "x" => [change, 0.0, 0.0],
+                    "z" => [0.0, 0.0, change],
+                    _ => return Err(read_only()),
+                };
+                let solid = self.elements.get(&id).ok_or_else(read_only)?.solid;
+                let vertices = self.geometry.solid_vertices(&solid);
+                let mut solids = transform_vertices(self.geometry, &vertices, |point| Point {
+                    x: point.x + offset[0],
+                    y: point.y + offset[1],
+                    z: point.z + offset[2],
+                });
+                solids.extend(update_hosted_families(
+                    self.geometry,
+                    self.elements,
+                    self.families,
+                    tolerance,
+                ));
+                Ok(solids)
+            }
+            (Object::Level(_), _) => Err(read_only()),
+        }
+    }
+
+    /// Raise or move a door or window along its wall, keeping it inside
+    /// the wall's height
+    fn write_family(
+        &mut self,
+        property: &PropertyRef,
+        family: Uuid,
+        change: f32,
+        tolerance: &Tolerance,
+    ) -> Result<Vec<Uuid>, ExpressionError> {
+        let wall_height = self
+            .families
+            .get(&family)
+            .and_then(|family| self.bounds(&family.host))
+            .map(|bounds| bounds.max.y - bounds.min.y);
+        let Some(family) = self.families.get_mut(&family) else {
+            return Err(ExpressionError::UnknownProperty(property.clone()));
+        };
+        match property.property.as_str() {
+            "sill" => {
+                let sill = family.parameters.sill_height + change;
+                let fits = wall_height.is_some_and(|wall_height| {
+                    sill >= 0.0 && sill + family.parameters.height <= wall_height - tolerance.linear
+                });
+                if !fits {
+                    return Err(ExpressionError::OutOfRange(property.clone()));
+                }
+                family.parameters.sill_height = sill;
+                for anchor in &mut family.anchors {
+                    anchor.up += change;
+                }
+            }
+            "offset" => family.offset += change,
+            _ => return Err(ExpressionError::ReadOnly(property.clone())),
+        }
+        Ok(update_hosted_families(
+            self.geometry,
+            self.elements,
+            self.families,
+            tolerance,
+        ))
+    }
+}
+
+/// Evaluate expression constraints in dependency order and set each
+/// property to its value before the next is evaluated
+///
+/// Constraints that cannot be satisfied are reported in the update and
+/// the rest are still solved.
+///
+/// # Errors
+/// Returns an error, changing nothing, if an expression does not parse,
+/// two constraints set the same property or constraints depend on each
+/// other in a loop
+pub fn solve_expressions(
+    model: &mut ExpressionModel,
+    constraints: &[ExpressionConstraint],
+    tolerance: &Tolerance,
+) -> Result<ExpressionUpdate, ExpressionError> {
+    let mut update = ExpressionUpdate::default();
+    let mut solids = BTreeSet::new();
+    for constraint in order_expression_constraints(constraints)? {
+        let solved = constraint.expression().and_then(|expression| {
+            let value = expression.evaluate(&|property| model.read(property))?;
+            if (model.read(&constraint.target)? - value).abs() <= tolerance.linear {
+                return Ok(None);
+            }
+            model.write(&constraint.target, value, tolerance).map(Some)
+        });
+        match solved {
+            Ok(None) => {}
+            Ok(Some(changed)) => {
+                solids.extend(changed);
+                update.set.push(constraint.target.clone());
+            }
+            Err(error) => update.errors.push((constraint.id, error)),
+        }
+    }
+    update.solids = solids.into_iter().collect();
+    Ok(update)
+}
@@ -0,0 +1,517 @@
+/// Hosting door and window families in walls
+///
+/// Placing a family reads its host wall from the wall's solid, rebuilds
+/// the wall's faces around the opening and builds the family's frame and
+/// panels in it. The wall keeps its solid and the vertices at its corners,
+/// so constraints on those corners, such as those of wall joins, still
+/// hold.
+///
+/// The opening's vertices and the family's are anchored to the wall, as
+/// described in [`crate::domain::family`]. Updating families after an
+/// edit moves every anchored vertex back to its place on the wall,
+/// wherever the wall now stands.
+use crate::application::commands::move_vertex;
+use crate::domain::geometry::{convex_hull, distance, newell_normal};
+use crate::domain::{
+    family_parts, new_id, pierced_slab_loops, Across, Element, ElementKind, ElementRegistry,
+    FamilyParameters, FamilyRegistry, GeometryRegistry, HostAnchor, HostLoop, HostedFamily, Point,
+    Tolerance, PANEL_THICKNESS,
+};
+use std::collections::BTreeSet;
+use uuid::Uuid;
+
+/// Why a family could not be placed
+#[derive(Debug, Clone, PartialEq)]
+pub enum FamilyError {
+    /// The host is missing or not a straight wall on a four-sided base
+    NotAWall,
+    /// Only doors and windows can be hosted
+    NotAnOpening(ElementKind),
+    /// The dimensions leave no room for the frame or the panels
+    Dimensions(String),
+    /// The opening reaches past the wall's ends or top
+    OutsideWall,
+    /// The opening overlaps another in the same wall
+    Overlaps,
+}
+
+impl std::fmt::Display for FamilyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FamilyError::NotAWall => write!(f, "The host is not a straight wall"),
+            FamilyError::NotAnOpening(kind) => {
+                write!(f, "A {} cannot be hosted in a wall", kind.label())
+            }
+            FamilyError::Dimensions(message) => write!(f, "Cannot build the family: {message}"),
+            FamilyError::OutsideWall => write!(f, "The opening does not fit in the wall"),
+            FamilyError::Overlaps => write!(f, "The opening overlaps another in the wall"),
+        }
+    }
+}
+
+impl std::error::Error for FamilyError {}
+
+/// A wall in plan, read from its solid
+struct HostFrame {
+    /// Unit direction from the wall's start to its end, as X and Z
+    direction: [f32; 2],
+    /// Unit normal toward the wall's first face, as X and Z
+    normal: [f32; 2],
+    /// Position of the center line's start along the direction
+    start: f32,
+    /// Offset of the center line along the normal
+    center: f32,
+    /// Offset of each face from the center line, first face first
+    sides: [f32; 2],
+    /// Position of each face's ends along the direction, first face first
+    faces: [[f32; 2]; 2],
+    /// Height of the wall's base
+    base: f32,
+    /// Height of the wall's top
+    top: f32,
+}
+
+impl HostFrame {
+    /// The world point at along, up and across positions for an opening
+    /// centered at an offset from the wall's start
+    fn point(&self, offset: f32, local: [f32; 3]) -> Point {
+        let along = self.start + offset + local[0];
+        let across = self.center + local[2];
+        Point {
+            x: self.direction[0] * along + self.normal[0] * across,
+            y: self.base + local[1],
+            z: self.direction[1] * along + self.normal[1] * across,
+        }
+    }
+
+    /// The along, up and across positions of a world point
+    fn local(&self, offset: f32, point: &Point) -> [f32; 3] {
+        let plan = [point.x, point.z];
+        [
+            dot(self.direction, plan) - self.start - offset,
+            point.y - self.base,
+            dot(self.normal, plan) - self.center,
+        ]
+    }
+
+    /// How far an anchored vertex sits across the wall
+    fn across(&self, across: Across) -> f32 {
+        match across {
+            Across::Face(face) => self.sides[face.min(1)],
+            Across::Offset(offset) => offset,
+        }
+    }
+
+    /// A world point loop facing outward for an opening at an offset
+    fn loop_points(&self, offset: f32, face: &HostLoop) -> Vec<Point> {
+        let mut points: Vec<Point> = face
+            .points
+            .iter()
+            .map(|local| self.point(offset, *local))
+            .collect();
+        let [along, up, across] = face.outward;
+        let outward = [
+            self.direction[0] * along + self.normal[0] * across,
+            up,
+            self.direction[1] * along + self.normal[1] * across,
+        ];
+        let normal = newell_normal(&points);
+        if normal.x * outward[0] + normal.y * outward[1] + normal.z * outward[2] < 0.0 {
+            points.reverse();
+        }
+        points
+    }
+
+    /// Anchor a vertex at its current position, on a face if it lies on
+    /// one
+    fn anchor(&self, offset: f32, vertex: Uuid, point: &Point, tolerance: f32) -> HostAnchor {
+        let [along, up, across] = self.local(offset, point);
+        let face = (0..2).find(|face| (across - self.sides[*face]).abs() <= tolerance);
+        HostAnchor {
+            vertex,
+            along,
+            up,
+            across: face.map_or(Across::Offset(across), Across::Face),
+        }
+    }
+}
+
+/// What placing a family made
+#[derive(Debug, Clone)]
+pub struct PlacedFamily {
+    /// The placed family
+    pub family: Uuid,
+    /// The solids made or reshaped, sorted by ID
+    pub solids: Vec<Uuid>,
+}
+
+/// Place a door or window in a wall, its center at a distance along the
+/// wall from the start of the wall's center line
+///
+/// # Errors
+/// Returns an error, leaving the model unchanged, if the host is not a
+/// straight wall, the family is not a door or window, its dimensions
+/// leave no room for its frame and panels, or the opening does not fit in
+/// the wall.
+pub fn place_family(
+    geometry_registry: &mut GeometryRegistry,
+    element_registry: &mut ElementRegistry,
+    family_registry: &mut FamilyRegistry,
+    host: &Uuid,
+    offset: f32,
+    parameters: &FamilyParameters,
+    tolerance: &Tolerance,
+) -> Result<PlacedFamily, FamilyError> {
+    let wall = element_registry
+        .get(host)
+        .filter(|element| element.kind == ElementKind::Wall)
+        .ok_or(FamilyError::NotAWall)?;
+    // Read the wall the same way round as for the families already in it
+    let hint = family_registry
+        .hosted_by(host)
+        .first()
+        .map(|family| family.direction);
+    let frame =
+        host_frame(geometry_registry, wall, hint, tolerance).ok_or(FamilyError::NotAWall)?;
+    check_parameters(parameters, &frame, offset, tolerance.linear)?;
+    let [along, _] = opening(parameters, offset);
+    let overlaps = family_registry.hosted_by(host).into_iter().any(|family| {
+        let [other, _] = opening(&family.parameters, family.offset);
+        along[0] < other[1] + tolerance.linear && other[0] < along[1] + tolerance.linear
+    });
+    if overlaps {
+        return Err(FamilyError::Overlaps);
+    }
+    let (wall_solid, phase) = (
+        wall.solid,
+        geometry_registry
+            .solids
+            .get(&wall.solid)
+            .map(|solid| solid.phase)
+            .unwrap_or_default(),
+    );
+
+    let openings: Vec<[[f32; 2]; 2]> = family_registry
+        .hosted_by(host)
+        .into_iter()
+        .map(|family| opening(&family.parameters, family.offset))
+        .chain([opening(parameters, offset)])
+        .collect();
+    let mut anchors = cut_openings(
+        geometry_registry,
+        wall_solid,
+        &frame,
+        &openings,
+        offset,
+        tolerance,
+    );
+    if let Some(wall) = element_registry.get_mut(host) {
+        let polygons = geometry_registry
+            .solids
+            .get(&wall_solid)
+            .map(|solid| solid.polygons.clone())
+            .unwrap_or_default();
+        wall.face_materials
+            .retain(|polygon, _| polygons.contains(polygon));
+    }
+
+    let mut solids = BTreeSet::from([wall_solid]);
+    let mut elements = Vec::new();
+    let thickness = frame.sides[0] - frame.sides[1];
+    for part in family_parts(parameters, thickness, tolerance.linear) {
+        let loops: Vec<Vec<Point>> = part
+            .loops
+            .iter()
+            .map(|face| frame.loop_points(offset, face))
+            .collect();
+        let solid = geometry_registry.create_solid_from_loops(&loops, tolerance);
+        if let Some(made) = geometry_registry.solids.get_mut(&solid) {
+            made.phase = phase;
+        }
+        for vertex in geometry_registry.solid_vertices(&solid) {
+            if let Some(stored) = geometry_registry.vertices.get(&vertex) {
+                anchors.push(frame.anchor(offset, vertex, &stored.position, tolerance.linear));
+            }
+        }
+        elements.push(element_registry.create_and_store(parameters.kind, &solid, &part.name));
+        solids.insert(solid);
+    }
+
+    let family = family_registry.store(HostedFamily {
+        id: new_id(),
+        parameters: parameters.clone(),
+        host: *host,
+        offset,
+        direction: frame.direction,
+        elements,
+        anchors,
+    });
+    Ok(PlacedFamily {
+        family,
+        solids: solids.into_iter().collect(),
+    })
+}
+
+/// The along and up ranges of a family's opening at an offset from the
+/// start of its wall's center line
+fn opening(parameters: &FamilyParameters, offset: f32) -> [[f32; 2]; 2] {
+    [
+        [
+            offset - parameters.width / 2.0,
+            offset + parameters.width / 2.0,
+        ],
+        [
+            parameters.sill_height,
+            parameters.sill_height + parameters.height,
+        ],
+    ]
+}
+
+/// Move every family's anchored vertices back to their places on its host
+/// wall and return the solids whose shape changed, sorted by ID
+///
+/// Families whose host is gone or no longer a straight wall are left
+/// where they are.
+pub fn update_hosted_families(
+    geometry_registry: &mut GeometryRegistry,
+    element_registry: &ElementRegistry,
+    family_registry: &mut FamilyRegistry,
+    tolerance: &Tolerance,
+) -> Vec<Uuid> {
+    let mut solids = BTreeSet::new();
+    let mut ids: Vec<Uuid> = family_registry.families.keys().copied().collect();
+    ids.sort_unstable();
+    for id in ids {
+        let Some(family) = family_registry.get_mut(&id) else {
+            continue;
+        };
+        let Some(frame) = element_registry.get(&family.host).and_then(|wall| {
+            host_frame(geometry_registry, wall, Some(family.direction), tolerance)
+        }) else {
+            continue;
+        };
+        family.direction = frame.direction;
+        for anchor in &family.anchors {
+            let target = frame.point(
+                family.offset,
+                [anchor.along, anchor.up, frame.across(anchor.across)],
+            );
+            let Some(vertex) = geometry_registry.vertices.get(&anchor.vertex) else {
+                continue;
+            };
+            if distance(&vertex.position, &target) <= tolerance.linear {
+                continue;
+            }
+            solids.extend(
+                move_vertex(geometry_registry, &anchor.vertex, &target).unwrap_or_default(),
+            );
+        }
+    }
+    solids.into_iter().collect()
+}
+
+/// Check a family fits its host wall at an offset
+fn check_parameters(
+    parameters: &FamilyParameters,
+    frame: &HostFrame,
+    offset: f32,
+    tolerance: f32,
+) -> Result<(), FamilyError> {
+    if !matches!(parameters.kind, ElementKind::Door | ElementKind::Window) {
+        return Err(FamilyError::NotAnOpening(parameters.kind));
+    }
+    let [along, up] = parameters.clear_opening();
+    if parameters.panel_count == 0 {
+        return Err(FamilyError::Dimensions("it needs a panel".to_string()));
+    }
+    if parameters.frame_width <= tolerance
+        || along[1] - along[0] <= tolerance
+        || up[1] - up[0] <= tolerance
+    {
+        return Err(FamilyError::Dimensions(
+            "the frame leaves no clear opening".to_string(),
+        ));
+    }
+    if frame.sides[0] - frame.sides[1] <= PANEL_THICKNESS + tolerance {
+        return Err(FamilyError::Dimensions(
+            "the wall is thinner than its panels".to_string(),
+        ));
+    }
+    let half = parameters.width / 2.0;
+    let fits_along = frame.faces.iter().all(|[start, end]| {
+        frame.start + offset - half >= start + tolerance
+            && frame.start + offset + half <= end - tolerance
+    });
+    let fits_up = parameters.sill_height >= 0.0
+        && parameters.sill_height + parameters.height <= frame.top - frame.base - tolerance;
+    if fits_along && fits_up {
+        Ok(())
+    } else {
+        Err(FamilyError::OutsideWall)
+    }
+}
+
+/// Rebuild a wall's faces around its openings and anchor the vertices
+/// new to it to a family at an offset
+///
+/// Openings are given as along ranges from the start of the wall's center
+/// line and up ranges from its base. The wall keeps its solid, and its
+/// faces keep any vertex whose place they still have, such as those of
+/// openings cut before. The faces replaced are left for garbage
+/// collection.
+fn cut_openings(
+    geometry_registry: &mut GeometryRegistry,
+    wall_solid: Uuid,
+    frame: &HostFrame,
+    openings: &[[[f32; 2]; 2]],
+    offset: f32,
+    tolerance: &Tolerance,
+) -> Vec<HostAnchor> {
+    let relative = |[start, end]: [f32; 2]| [start - frame.start, end - frame.start];
+    let loops: Vec<Vec<Point>> = pierced_slab_loops(
+        [relative(frame.faces[0]), relative(frame.faces[1])],
+        frame.sides,
+        [0.0, frame.top - frame.base],
+        openings,
+        tolerance.linear,
+    )
+    .iter()
+    .map(|face| frame.loop_points(0.0, face))
+    .collect();
+
+    let kept = geometry_registry.solid_vertices(&wall_solid);
+    let built = geometry_registry.create_solid_from_loops(&loops, tolerance);
+    let Some(polygons) = geometry_registry
+        .solids
+        .get(&built)
+        .map(|solid| solid.polygons.clone())
+    else {
+        return Vec::new();
+    };
+    let segments: Vec<Uuid> = polygons
+        .iter()
+        .filter_map(|polygon| geometry_registry.polygons.get(polygon))
+        .flat_map(|polygon| polygon.segments.clone())
+        .collect();
+
+    let mut anchors = Vec::new();
+    for vertex in geometry_registry.solid_vertices(&built) {
+        let Some(position) = geometry_registry
+            .vertices
+            .get(&vertex)
+            .map(|stored| stored.position.clone())
+        else {
+            continue;
+        };
+        let existing = kept.iter().copied().find(|old| {
+            geometry_registry
+                .vertices
+                .get(old)
+                .is_some_and(|stored| distance(&stored.position, &position) <= tolerance.linear)
+        });
+        let Some(existing) = existing else {
+            anchors.push(frame.anchor(offset, vertex, &position, tolerance.linear));
+            continue;
+        };
+        for segment in &segments {
+            if let Some(segment) = geometry_registry.segments.get_mut(segment) {
+                segment.replace_vertex(&vertex, &existing);
+            }
+        }
+        geometry_registry.vertices.remove(&vertex);
+    }
+    geometry_registry.solids.remove(&built);
+    if let Some(solid) = geometry_registry.solids.get_mut(&wall_solid) {
+        solid.polygons = polygons;
+    }
+    anchors
+}
+
+/// Read a wall's plan from its solid
+///
+/// The wall's start and end are told apart by its first face when it
+/// records one, with the normal toward that face; otherwise the direction
+/// nearest `hint` is taken. Returns None if the outline of the wall's
+/// bottom is not four-sided or the wall has no thickness.
+fn host_frame(
+    geometry_registry: &GeometryRegistry,
+    wall: &Element,
+    hint: Option<[f32; 2]>,
+    tolerance: &Tolerance,
+) -> Option<HostFrame> {
+    let points: Vec<Point> = geometry_registry
+        .solid_vertices(&wall.solid)
+        .iter()
+        .filter_map(|id| Some(geometry_registry.vertices.get(id)?.position.clone()))
+        .collect();
+    let base = points.iter().map(|point| point.y).fold(f32::MAX, f32::min);
+    let top = points.iter().map(|point| point.y).fold(f32::MIN, f32::max);
+    let bottom: Vec<[f32; 2]> = points
+        .iter()
+        .filter(|point| (point.y - base).abs() <= tolerance.linear)
+        .map(|point| [point.x, point.z])
+        .collect();
+    let plan = convex_hull(&bottom, tolerance.linear);
+    let [a, b, c, d] = <[[f32; 2]; 4]>::try_from(plan).ok()?;
+
+    // The sides are the longer pair of opposite edges, run the same way
+    let length = |from: [f32; 2], to: [f32; 2]| (to[0] - from[0]).hypot(to[1] - from[1]);
+    let (mut lines, run) = if length(a, b) + length(d, c) >= length(b, c) + length(a, d) {
+        ([[a, b], [d, c]], length(a, b))
+    } else {
+        ([[b, c], [a, d]], length(b, c))
+    };
+    if run <= tolerance.linear {
+        return None;
+    }
+    let mut direction = [
+        (lines[0][1][0] - lines[0][0][0]) / run,
+        (lines[0][1][1] - lines[0][0][1]) / run,
+    ];
+    let normal = |direction: [f32; 2]| [-direction[1], direction[0]];
+    let center = f32::midpoint(
+        dot(normal(direction), lines[0][0]),
+        dot(normal(direction), lines[1][0]),
+    );
+    let first_face = wall
+        .first_face
+        .and_then(|id| geometry_registry.vertices.get(&id))
+        .map(|vertex| [vertex.position.x, vertex.position.z]);
+    let reverse = match (first_face, hint) {
+        (Some(point), _) => dot(normal(direction), point) < center,
+        (None, Some(hint)) => dot(direction, hint) < 0.0,
+        (None, None) => false,
+    };
+    if reverse {
+        direction = [-direction[0], -direction[1]];
+        for line in &mut lines {
+            line.reverse();
+        }
+    }
+    let normal = normal(direction);
+    // The first face is on the side the normal points to
+    if dot(normal, lines[0][0]) < dot(normal, lines[1][0]) {
+        lines.swap(0, 1);
+    }
+    let offsets = [dot(normal, lines[0][0]), dot(normal, lines[1][0])];
+    if offsets[0] - offsets[1] <= tolerance.linear {
+        return None;
+    }
+    let center = f32::midpoint(offsets[0], offsets[1]);
+    let faces = lines.map(|[from, to]| [dot(direction, from), dot(direction, to)]);
+    Some(HostFrame {
+        direction,
+        normal,
+        start: f32::midpoint(faces[0][0], faces[1][0]),
+        center,
+        sides: [offsets[0] - center, offsets[1] - center],
+        faces,
+        base,
+        top,
+    })
+}
+
+/// Dot product of two plan vectors
+fn dot(a: [f32; 2], b: [f32; 2]) -> f32 {
+    a[0] * b[0] + a[1] * b[1]
+}
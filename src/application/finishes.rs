@@ -0,0 +1,359 @@
+/// Generating floor finishes and ceilings from spaces and keeping them in
+/// step with the walls around them
+///
+/// Generating finishes for a space lays one finish over each of its flat
+/// floor faces, or hangs one below each of its flat ceiling faces. Every
+/// finish becomes a slab element of its own, named after its space.
+///
+/// Updating finishes after an edit works out each finish's outline again,
+/// as described in [`crate::domain::finish`], and rebuilds the finishes
+/// whose outline or height changed. A rebuilt finish keeps its element
+/// but gets a new solid.
+use crate::domain::geometry::{newell_normal, prism_loops};
+use crate::domain::{
+    new_id, plan_intersection, plan_line, ElementKind, ElementRegistry, FinishBound, FinishKind,
+    FinishLayer, FinishRegistry, GeometryRegistry, PlanLine, Point, SpaceFinish, Tolerance,
+};
+use uuid::Uuid;
+
+/// Why finishes could not be generated for a space
+#[derive(Debug, Clone, PartialEq)]
+pub enum FinishError {
+    /// The element is missing or not a space
+    NotASpace,
+    /// The space has no flat face for the finish to follow
+    NoFaces(FinishKind),
+    /// The finish has no thickness
+    Thickness,
+}
+
+impl std::fmt::Display for FinishError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FinishError::NotASpace => write!(f, "The element is not a space"),
+            FinishError::NoFaces(FinishKind::Floor) => write!(f, "The space has no flat floor"),
+            FinishError::NoFaces(FinishKind::Ceiling) => {
+                write!(f, "The space has no flat ceiling")
+            }
+            FinishError::Thickness => write!(f, "The finish has no thickness"),
+        }
+    }
+}
+
+impl std::error::Error for FinishError {}
+
+/// A face's unit normal in plan as X and Z, or None if it is not vertical
+fn vertical_normal(points: &[Point], tolerance: &Tolerance) -> Option<[f32; 2]> {
+    let normal = newell_normal(points).normalized()?;
+    if normal.y.abs() > tolerance.angular.sin() {
+        return None;
+    }
+    let flat = (normal.x * normal.x + normal.z * normal.z).sqrt();
+    Some([normal.x / flat, normal.z / flat])
+}
+
+/// The plan line a wall face stands on
+fn face_line(points: &[Point], normal: [f32; 2]) -> PlanLine {
+    [
+        normal[0],
+        normal[1],
+        normal[0] * points[0].x + normal[1] * points[0].z,
+    ]
+}
+
+/// The faces of a space a finish of a kind follows, with their heights
+fn finish_faces(
+    geometry: &GeometryRegistry,
+    solid: &Uuid,
+    kind: FinishKind,
+    tolerance: &Tolerance,
+) -> Vec<(Uuid, f32)> {
+    let Some(solid) = geometry.solids.get(solid) else {
+        return Vec::new();
+    };
+    let facing = match kind {
+        FinishKind::Floor => -1.0,
+        FinishKind::Ceiling => 1.0,
+    };
+    let mut faces: Vec<Uuid> = solid.polygons.clone();
+    faces.sort();
+    faces
+        .into_iter()
+        .filter_map(|face| {
+            let points = geometry.polygon_points(&face)?;
+            let normal = newell_normal(&points).normalized()?;
+            (normal.y * facing >= tolerance.angular.cos()).then(|| (face, face_height(&points)))
+        })
+        .collect()
+}
+
+/// The mean height of a face's points
+#[allow(clippy::cast_precision_loss)]
+fn face_height(points: &[Point]) -> f32 {
+    points.iter().map(|point| point.y).sum::<f32>() / points.len().max(1) as f32
+}
+
+/// The wall face an edge of a space's face runs along, if any
+fn find_bound(
+    geometry: &GeometryRegistry,
+    elements: &ElementRegistry,
+    start: &Point,
+    end: &Point,
+    tolerance: &Tolerance,
+) -> Option<FinishBound> {
+    let edge = plan_line(start, end, tolerance.linear)?;
+    let middle = [f32::midpoint(start.x, end.x), f32::midpoint(start.z, end.z)];
+    let (dx, dz) = (-edge[1], edge[0]);
+    let mut best: Option<(f32, FinishBound)> = None;
+    for wall in elements
+        .sorted()
+        .into_iter()
+        .filter(|element| element.kind == ElementKind::Wall)
+    {
+        let Some(solid) = geometry.solids.get(&wall.solid) else {
+            continue;
+        };
+        for face in &solid.polygons {
+            let Some(points) = geometry.polygon_points(face) else {
+                continue;
+            };
+            let Some(normal) = vertical_normal(&points, tolerance) else {
+                continue;
+            };
+            if (normal[0] * dx + normal[1] * dz).abs() > tolerance.angular.sin() {
+                continue;
+            }
+            let line = face_line(&points, normal);
+            let gap = (normal[0] * middle[0] + normal[1] * middle[1] - line[2]).abs();
+            let along = |x: f32, z: f32| x * dx + z * dz;
+            let (low, high) = points.iter().fold((f32::MAX, f32::MIN), |(low, high), p| {
+                let position = along(p.x, p.z);
+                (low.min(position), high.max(position))
+            });
+            let position = along(middle[0], middle[1]);
+            let beside = position >= low - tolerance.linear && position <= high + tolerance.linear;
+            if gap > tolerance.snap || !beside || best.is_some_and(|(nearest, _)| nearest <= gap) {
+                continue;
+            }
+            best = Some((
+                gap,
+                FinishBound {
+                    wall: wall.solid,
+                    face: *face,
+                    normal,
+                },
+            ));
+        }
+    }
+    best.map(|(_, bound)| bound)
+}
+
+/// Where a bound wall face now stands, finding the face again by its
+/// normal if the wall's faces were rebuilt
+fn bound_line(
+    geometry: &GeometryRegistry,
+    bound: &mut FinishBound,
+    tolerance: &Tolerance,
+) -> Option<PlanLine> {
+    if let Some(points) = geometry.polygon_points(&bound.face) {
+        let normal = vertical_normal(&points, tolerance)?;
+        bound.normal = normal;
+        return Some(face_line(&points, normal));
+    }
+    let solid = geometry.solids.get(&bound.wall)?;
+    let (face, points, normal) = solid
+        .polygons
+        .iter()
+        .filter_map(|face| {
+            let points = geometry.polygon_points(face)?;
+            let normal = vertical_normal(&points, tolerance)?;
+            Some((*face, points, normal))
+        })
+        .filter(|(_, _, normal)| {
+            normal[0] * bound.normal[0] + normal[1] * bound.normal[1] >= tolerance.angular.cos()
+        })
+        .min_by_key(|(face, _, _)| *face)?;
+    bound.face = face;
+    bound.normal = normal;
+    Some(face_line(&points, normal))
+}
+
+/// The outline of a finish over its face, its bound edges moved onto
+/// their walls
+///
+/// A face whose number of edges no longer matches the bounds is followed
+/// as it is, with its bounds dropped.
+fn finish_outline(
+    geometry: &GeometryRegistry,
+    points: &[Point],
+    bounds: &mut Vec<Option<FinishBound>>,
+    height: f32,
+    tolerance: &Tolerance,
+) -> Vec<Point> {
+    if bounds.len() != points.len() {
+        *bounds = vec![None; points.len()];
+    }
+    let count = points.len();
+    let lines: Vec<Option<PlanLine>> = bounds
+        .iter_mut()
+        .enumerate()
+        .map(|(index, bound)| {
+            let edge = || {
+                plan_line(
+                    &points[index],
+                    &points[(index + 1) % count],
+                    tolerance.linear,
+                )
+            };
+            match bound {
+                Some(bound) => bound_line(geometry, bound, tolerance).or_else(edge),
+                None => edge(),
+            }
+        })
+        .collect();
+    (0..count)
+        .map(|index| {
+            let crossing = lines[(index + count - 1) % count]
+                .zip(lines[index])
+                .and_then(|(before, after)| plan_intersection(before, after, tolerance.angular));
+            let [x, z] = crossing.unwrap_or([points[index].x, points[index].z]);
+            Point { x, y: height, z }
+        })
+        .collect()
+}
+
+/// Build a finish's solid over its outline
+fn finish_solid(
+    geometry: &mut GeometryRegistry,
+    outline: &[Point],
+    layer: &FinishLayer,
+    tolerance: &Tolerance,
+) -> Uuid {
+    let [bottom, top] = layer.heights(outline[0].y);
+    let base: Vec<Point> = outline
+        .iter()
+        .map(|point| Point {
+            x: point.x,
+            y: bottom,
+            z: point.z,
+        })
+        .collect();
+    geometry.create_solid_from_loops(&prism_loops(&base, top - bottom), tolerance)
+}
+
+/// Generate finishes of a layer for a space, one over each of its flat
+/// floor or ceiling faces, and return the new elements
+///
+/// # Errors
+/// Returns an error if the element is not a space, the space has no flat
+/// face of the kind or the layer has no thickness
+pub fn generate_space_finishes(
+    geometry: &mut GeometryRegistry,
+    elements: &mut ElementRegistry,
+    finishes: &mut FinishRegistry,
+    space: &Uuid,
+    layer: &FinishLayer,
+    tolerance: &Tolerance,
+) -> Result<Vec<Uuid>, FinishError> {
+    let element = elements
+        .get(space)
+        .filter(|element| element.kind == ElementKind::Space)
+        .ok_or(FinishError::NotASpace)?;
+    if layer.thickness <= tolerance.linear {
+        return Err(FinishError::Thickness);
+    }
+    let (space_solid, name) = (element.solid, element.name.clone());
+    let phase = geometry.solids.get(&space_solid).map(|solid| solid.phase);
+    let faces = finish_faces(geometry, &space_solid, layer.kind, tolerance);
+    if faces.is_empty() {
+        return Err(FinishError::NoFaces(layer.kind));
+    }
+    let mut made = Vec::new();
+    for (face, height) in faces {
+        let Some(points) = geometry.polygon_points(&face) else {
+            continue;
+        };
+        let mut bounds: Vec<Option<FinishBound>> = (0..points.len())
+            .map(|index| {
+                let next = &points[(index + 1) % points.len()];
+                find_bound(geometry, elements, &points[index], next, tolerance)
+            })
+            .collect();
+        let outline = finish_outline(geometry, &points, &mut bounds, height, tolerance);
+        let solid = finish_solid(geometry, &outline, layer, tolerance);
+        if let (Some(phase), Some(built)) = (phase, geometry.solids.get_mut(&solid)) {
+            built.phase = phase;
+        }
+        let id = elements.create_and_store(
+            ElementKind::Slab,
+            &solid,
+            &format!("{}: {name}", layer.kind.label()),
+        );
+        if let Some(element) = elements.get_mut(&id) {
+            element.material.clone_from(&layer.material);
+        }
+        finishes.store(SpaceFinish {
+            id: new_id(),
+            space: *space,
+            face,
+            layer: layer.clone(),
+            bounds,
+            element: id,
+            built_for: outline,
+        });
+        made.push(id);
+    }
+    Ok(made)
+}
+
+/// Rebuild the finishes whose outline or height changed after an edit
+///
+/// A finish whose space face is gone is no longer kept up to date.
+/// Returns the solids removed and built, for their meshes to be
+/// refreshed.
+pub fn update_space_finishes(
+    geometry: &mut GeometryRegistry,
+    elements: &mut ElementRegistry,
+    finishes: &mut FinishRegistry,
+    tolerance: &Tolerance,
+) -> Vec<Uuid> {
+    let mut changed = Vec::new();
+    let ids: Vec<Uuid> = finishes.sorted().iter().map(|finish| finish.id).collect();
+    for id in ids {
+        let Some(mut finish) = finishes.get(&id).cloned() else {
+            continue;
+        };
+        let (Some(points), Some(element)) = (
+            geometry.polygon_points(&finish.face),
+            elements.get(&finish.element),
+        ) else {
+            finishes.remove(&id);
+            continue;
+        };
+        let old_solid = element.solid;
+        let height = face_height(&points);
+        let outline = finish_outline(geometry, &points, &mut finish.bounds, height, tolerance);
+        let unchanged = outline.len() == finish.built_for.len()
+            && outline.iter().zip(&finish.built_for).all(|(now, then)| {
+                (now.x - then.x).abs() <= tolerance.linear
+                    && (now.y - then.y).abs() <= tolerance.linear
+                    && (now.z - then.z).abs() <= tolerance.linear
+            });
+        if !unchanged {
+            let phase = geometry.solids.get(&old_solid).map(|solid| solid.phase);
+            geometry.solids.remove(&old_solid);
+            let solid = finish_solid(geometry, &outline, &finish.layer, tolerance);
+            if let (Some(phase), Some(built)) = (phase, geometry.solids.get_mut(&solid)) {
+                built.phase = phase;
+            }
+            if let Some(element) = elements.get_mut(&finish.element) {
+                element.solid = solid;
+                element.face_materials.clear();
+            }
+            changed.extend([old_solid, solid]);
+            finish.built_for = outline;
+        }
+        finishes.store(finish);
+    }
+    changed
+}
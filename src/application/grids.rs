@@ -0,0 +1,278 @@
+/// Generating columns and walls from structural grids
+///
+/// Adding a layout to a grid generates its elements and records the
+/// layout as derived from the grid. Editing the grid then regenerates every
+/// layout derived from it, in the order the dependency graph gives, so
+/// changing a spacing moves the columns and walls on its lines without the
+/// layouts being run again by hand.
+///
+/// Regenerating keeps the elements a layout made, in order, and gives them
+/// new solids; elements are added or removed only when the number of
+/// pieces changes, as when a grid gains or loses lines.
+use crate::application::commands::wall_footprint;
+use crate::domain::geometry::{distance, prism_loops};
+use crate::domain::{
+    new_id, ElementKind, ElementRegistry, GeometryRegistry, GridDatum, GridEdit, GridFamily,
+    GridGenerator, GridLayout, GridRegistry, Point, Tolerance,
+};
+use uuid::Uuid;
+
+/// Why a grid could not be edited or a layout generated
+#[derive(Debug, Clone, PartialEq)]
+pub enum GridError {
+    /// The grid does not exist
+    MissingGrid,
+    /// The edit names a spacing the grid does not have
+    MissingSpacing,
+    /// A spacing is not longer than the linear tolerance
+    Spacing,
+    /// A column or wall would have no size
+    Size,
+    /// A wall runs along or between lines the grid does not have
+    MissingLine,
+}
+
+impl std::fmt::Display for GridError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GridError::MissingGrid => write!(f, "The grid does not exist"),
+            GridError::MissingSpacing => write!(f, "The grid does not have that spacing"),
+            GridError::Spacing => write!(f, "Grid lines must be apart"),
+            GridError::Size => write!(f, "The layout's pieces would have no size"),
+            GridError::MissingLine => write!(f, "A wall is on a line the grid does not have"),
+        }
+    }
+}
+
+impl std::error::Error for GridError {}
+
+/// A piece of a layout, ready to be built
+struct GridShape {
+    /// The kind of element it is
+    kind: ElementKind,
+    /// The element's name
+    name: String,
+    /// Its base, raised to make its solid
+    base: Vec<Point>,
+    /// Its height
+    height: f32,
+}
+
+/// The pieces of a layout on a grid, in the layout's order
+fn layout_shapes(
+    grid: &GridDatum,
+    layout: &GridLayout,
+    tolerance: &Tolerance,
+) -> Result<Vec<GridShape>, GridError> {
+    match layout {
+        GridLayout::Columns {
+            width,
+            depth,
+            height,
+        } => {
+            if [width, depth, height]
+                .iter()
+                .any(|size| **size <= tolerance.linear)
+            {
+                return Err(GridError::Size);
+            }
+            let mut shapes = Vec::new();
+            for (lettered, z) in grid.positions(GridFamily::Lettered).iter().enumerate() {
+                for (numbered, x) in grid.positions(GridFamily::Numbered).iter().enumerate() {
+                    let base = [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)]
+                        .iter()
+                        .map(|(along, across)| {
+                            grid.placement.apply(&Point {
+                                x: x + along * width / 2.0,
+                                y: 0.0,
+                                z: z + across * depth / 2.0,
+                            })
+                        })
+                        .collect();
+                    shapes.push(GridShape {
+                        kind: ElementKind::Column,
+                        name: format!(
+                            "Column {}-{}",
+                            GridFamily::Lettered.line_label(lettered),
+                            GridFamily::Numbered.line_label(numbered)
+                        ),
+                        base,
+                        height: *height,
+                    });
+                }
+            }
+            Ok(shapes)
+        }
+        GridLayout::Walls {
+            walls,
+            thickness,
+            height,
+        } => {
+            if *thickness <= tolerance.linear || *height <= tolerance.linear {
+                return Err(GridError::Size);
+            }
+            walls
+                .iter()
+                .map(|wall| {
+                    let start = grid.line_point(wall.family, wall.line, wall.span[0]);
+                    let end = grid.line_point(wall.family, wall.line, wall.span[1]);
+                    let (Some(start), Some(end)) = (start, end) else {
+                        return Err(GridError::MissingLine);
+                    };
+                    let base = wall_footprint(&start, &end, *thickness, tolerance)
+                        .ok_or(GridError::Size)?;
+                    Ok(GridShape {
+                        kind: ElementKind::Wall,
+                        name: format!("Wall: Grid {}", wall.family.line_label(wall.line)),
+                        base,
+                        height: *height,
+                    })
+                })
+                .collect()
+        }
+    }
+}
+
+/// Build a generator's pieces, reusing the elements it made before
+///
+/// Returns the solids removed and built.
+fn build_shapes(
+    geometry: &mut GeometryRegistry,
+    elements: &mut ElementRegistry,
+    generator: &mut GridGenerator,
+    shapes: Vec<GridShape>,
+    tolerance: &Tolerance,
+) -> Vec<Uuid> {
+    let mut changed = Vec::new();
+    let mut kept = Vec::with_capacity(shapes.len());
+    let mut previous = generator.elements.iter();
+    for shape in shapes {
+        let solid =
+            geometry.create_solid_from_loops(&prism_loops(&shape.base, shape.height), tolerance);
+        changed.push(solid);
+        let first_face = (shape.kind == ElementKind::Wall)
+            .then(|| {
+                geometry
+                    .solid_vertices(&solid)
+                    .into_iter()
+                    .filter_map(|id| {
+                        let position = &geometry.vertices.get(&id)?.position;
+                        Some((id, distance(position, &shape.base[0])))
+                    })
+                    .min_by(|a, b| a.1.total_cmp(&b.1))
+                    .map(|(id, _)| id)
+            })
+            .flatten();
+        let reused = previous
+            .next()
+            .copied()
+            .filter(|id| elements.get(id).is_some());
+        let id = if let Some(id) = reused {
+            if let Some(element) = elements.get_mut(&id) {
+                let phase = geometry.solids.get(&element.solid).map(|old| old.phase);
+                if let (Some(phase), Some(built)) = (phase, geometry.solids.get_mut(&solid)) {
+                    built.phase = phase;
+                }
+                geometry.solids.remove(&element.solid);
+                changed.push(element.solid);
+                element.solid = solid;
+                element.name = shape.name;
+                element.face_materials.clear();
+            }
+            id
+        } else {
+            elements.create_and_store(shape.kind, &solid, &shape.name)
+        };
+        if let Some(element) = elements.get_mut(&id) {
+            element.first_face = first_face;
+        }
+        kept.push(id);
+    }
+    for extra in previous {
+        if let Some(element) = elements.get(extra) {
+            geometry.solids.remove(&element.solid);
+            changed.push(element.solid);
+            elements.remove(extra);
+        }
+    }
+    generator.elements = kept;
+    changed
+}
+
+/// Generate a layout on a grid and keep it following the grid
+///
+/// Returns the generator's ID and the solids built.
+///
+/// # Errors
+/// Returns an error if the grid is missing, or the layout's pieces would
+/// have no size or are on lines the grid does not have; nothing is changed
+/// then
+pub fn add_grid_layout(
+    geometry: &mut GeometryRegistry,
+    elements: &mut ElementRegistry,
+    grids: &mut GridRegistry,
+    grid: &Uuid,
+    layout: GridLayout,
+    tolerance: &Tolerance,
+) -> Result<(Uuid, Vec<Uuid>), GridError> {
+    let datum = grids.grid(grid).ok_or(GridError::MissingGrid)?;
+    let shapes = layout_shapes(datum, &layout, tolerance)?;
+    let mut generator = GridGenerator {
+        id: new_id(),
+        grid: *grid,
+        layout,
+        elements: Vec::new(),
+    };
+    let built = build_shapes(geometry, elements, &mut generator, shapes, tolerance);
+    Ok((grids.store_generator(generator), built))
+}
+
+/// Edit a grid and regenerate every layout derived from it
+///
+/// Returns the solids removed and built, for their meshes to be
+/// refreshed.
+///
+/// # Errors
+/// Returns an error if the grid is missing, the edit names a spacing it
+/// does not have, a spacing would not be longer than the linear
+/// tolerance, or a layout's walls would be on lines the edit removes;
+/// nothing is changed then
+pub fn edit_grid(
+    geometry: &mut GeometryRegistry,
+    elements: &mut ElementRegistry,
+    grids: &mut GridRegistry,
+    grid: &Uuid,
+    edit: &GridEdit,
+    tolerance: &Tolerance,
+) -> Result<Vec<Uuid>, GridError> {
+    let edited = grids
+        .grid(grid)
+        .ok_or(GridError::MissingGrid)?
+        .edited(edit)
+        .ok_or(GridError::MissingSpacing)?;
+    if [GridFamily::Numbered, GridFamily::Lettered]
+        .iter()
+        .flat_map(|family| edited.spacings(*family))
+        .any(|spacing| *spacing <= tolerance.linear)
+    {
+        return Err(GridError::Spacing);
+    }
+    let mut regenerate = Vec::new();
+    for id in grids.dependencies.affected(&[*grid]) {
+        if let Some(generator) = grids.generator(&id) {
+            let shapes = layout_shapes(&edited, &generator.layout, tolerance)?;
+            regenerate.push((id, shapes));
+        }
+    }
+    grids.grids.insert(*grid, edited);
+    let mut changed = Vec::new();
+    for (id, shapes) in regenerate {
+        if let Some(generator) = grids.generator_mut(&id) {
+            changed.extend(build_shapes(
+                geometry, elements, generator, shapes, tolerance,
+            ));
+        }
+    }
+    tracing::info!(solids = changed.len(), "regenerated grid layouts");
+    Ok(changed)
+}
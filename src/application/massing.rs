@@ -0,0 +1,226 @@
+/// Converting massing solids into walls, roofs and floors
+///
+/// Converting a mass works out what each of its faces becomes, as
+/// described in [`crate::domain::massing`], and builds each element as the
+/// face pushed back into the mass by the element's thickness. The mass
+/// itself, and any element it was, is removed.
+///
+/// Walls made from neighbouring faces overlap at the mass's corners, so
+/// they are joined like walls drawn to meet. Roofs and floors are left
+/// overlapping the tops and bottoms of the walls.
+use crate::application::wall_joins::join_walls;
+use crate::domain::geometry::{distance, newell_normal, signed_volume};
+use crate::domain::solver::Constraint;
+use crate::domain::{
+    ElementKind, ElementRegistry, GeometryRegistry, MassConversionSettings, MassFaceRole, Point,
+    Tolerance,
+};
+use std::collections::{BTreeMap, BTreeSet};
+use uuid::Uuid;
+
+/// Why a mass could not be converted
+#[derive(Debug, Clone, PartialEq)]
+pub enum MassConversionError {
+    /// The solid does not exist
+    MissingSolid,
+    /// Elements of a role would have no thickness
+    Thickness(MassFaceRole),
+    /// Every face of the mass is skipped
+    NothingToConvert,
+}
+
+impl std::fmt::Display for MassConversionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MassConversionError::MissingSolid => write!(f, "The mass does not exist"),
+            MassConversionError::Thickness(role) => {
+                write!(f, "{} elements would have no thickness", role.label())
+            }
+            MassConversionError::NothingToConvert => write!(f, "Every face of the mass is skipped"),
+        }
+    }
+}
+
+impl std::error::Error for MassConversionError {}
+
+/// What converting a mass made
+#[derive(Debug, Clone, Default)]
+pub struct MassConversion {
+    /// The wall elements, one per wall face
+    pub walls: Vec<Uuid>,
+    /// The roof slab elements, one per roof face
+    pub roofs: Vec<Uuid>,
+    /// The floor slab elements, one per floor face
+    pub floors: Vec<Uuid>,
+    /// The solids removed, built and joined, sorted by ID
+    pub solids: Vec<Uuid>,
+    /// Constraints keeping the walls' joins closed under later edits
+    pub constraints: Vec<Constraint>,
+}
+
+/// The loops of a solid filling the space between a face and a copy of
+/// it pushed back along its normal by a thickness
+fn pushed_back_loops(points: &[Point], normal: &[f32; 3], thickness: f32) -> Vec<Vec<Point>> {
+    let back: Vec<Point> = points
+        .iter()
+        .map(|point| Point {
+            x: point.x - normal[0] * thickness,
+            y: point.y - normal[1] * thickness,
+            z: point.z - normal[2] * thickness,
+        })
+        .collect();
+    let count = points.len();
+    let mut loops = vec![points.to_vec(), back.iter().rev().cloned().collect()];
+    for index in 0..count {
+        let next = (index + 1) % count;
+        loops.push(vec![
+            points[next].clone(),
+            points[index].clone(),
+            back[index].clone(),
+            back[next].clone(),
+        ]);
+    }
+    if signed_volume(&loops) < 0.0 {
+        for face in &mut loops {
+            face.reverse();
+        }
+    }
+    loops
+}
+
+/// A face of a mass to be converted
+struct MassFace {
+    /// Its points
+    points: Vec<Point>,
+    /// Its unit normal, as X, Y and Z
+    normal: [f32; 3],
+    /// What it becomes
+    role: MassFaceRole,
+}
+
+/// The faces of a mass that are not skipped, in ID order
+fn mass_faces(
+    geometry: &GeometryRegistry,
+    solid: &Uuid,
+    tags: &BTreeMap<Uuid, MassFaceRole>,
+    tolerance: &Tolerance,
+) -> Option<Vec<MassFace>> {
+    let mut faces = geometry.solids.get(solid)?.polygons.clone();
+    faces.sort();
+    Some(
+        faces
+            .into_iter()
+            .filter_map(|face| {
+                let points = geometry.polygon_points(&face)?;
+                let normal = newell_normal(&points).normalized()?;
+                let role = tags
+                    .get(&face)
+                    .copied()
+                    .unwrap_or_else(|| MassFaceRole::from_normal(&normal, tolerance));
+                Some(MassFace {
+                    points,
+                    normal: [normal.x, normal.y, normal.z],
+                    role,
+                })
+            })
+            .filter(|face| face.role != MassFaceRole::Skip)
+            .collect(),
+    )
+}
+
+/// Convert a massing solid into walls, roofs and floors
+///
+/// `tags` gives the roles of faces that do not take the role their
+/// facing gives them.
+///
+/// # Errors
+/// Returns an error if the solid is missing, every face is skipped, or
+/// the elements of a role in use would have no thickness; nothing is
+/// changed then
+pub fn convert_mass(
+    geometry: &mut GeometryRegistry,
+    elements: &mut ElementRegistry,
+    solid: &Uuid,
+    tags: &BTreeMap<Uuid, MassFaceRole>,
+    settings: &MassConversionSettings,
+    tolerance: &Tolerance,
+) -> Result<MassConversion, MassConversionError> {
+    let faces =
+        mass_faces(geometry, solid, tags, tolerance).ok_or(MassConversionError::MissingSolid)?;
+    if faces.is_empty() {
+        return Err(MassConversionError::NothingToConvert);
+    }
+    for face in &faces {
+        if settings
+            .thickness(face.role)
+            .is_none_or(|thickness| thickness <= tolerance.linear)
+        {
+            return Err(MassConversionError::Thickness(face.role));
+        }
+    }
+    let phase = geometry.solids.get(solid).map(|mass| mass.phase);
+    let was: Vec<Uuid> = elements
+        .sorted()
+        .into_iter()
+        .filter(|element| element.solid == *solid)
+        .map(|element| element.id)
+        .collect();
+    for element in &was {
+        elements.remove(element);
+    }
+    geometry.solids.remove(solid);
+    let mut made = MassConversion::default();
+    let mut solids = BTreeSet::from([*solid]);
+    for MassFace {
+        points,
+        normal,
+        role,
+    } in faces
+    {
+        let thickness = settings.thickness(role).unwrap_or_default();
+        let built = geometry
+            .create_solid_from_loops(&pushed_back_loops(&points, &normal, thickness), tolerance);
+        if let (Some(phase), Some(built)) = (phase, geometry.solids.get_mut(&built)) {
+            built.phase = phase;
+        }
+        solids.insert(built);
+        match role {
+            MassFaceRole::Wall => {
+                let first_face = geometry
+                    .solid_vertices(&built)
+                    .into_iter()
+                    .filter_map(|id| {
+                        let position = &geometry.vertices.get(&id)?.position;
+                        Some((id, distance(position, &points[0])))
+                    })
+                    .min_by(|a, b| a.1.total_cmp(&b.1))
+                    .map(|(id, _)| id);
+                let name = format!("Wall: {}", settings.wall_assembly.name);
+                let id = elements.create_and_store(ElementKind::Wall, &built, &name);
+                if let Some(element) = elements.get_mut(&id) {
+                    element.assembly = Some(settings.wall_assembly.clone());
+                    element.first_face = first_face;
+                }
+                made.walls.push(id);
+            }
+            MassFaceRole::Roof | MassFaceRole::Floor => {
+                let (name, material, list) = if role == MassFaceRole::Roof {
+                    ("Roof", &settings.roof_material, &mut made.roofs)
+                } else {
+                    ("Floor", &settings.floor_material, &mut made.floors)
+                };
+                let id = elements.create_and_store(ElementKind::Slab, &built, name);
+                if let Some(element) = elements.get_mut(&id) {
+                    element.material.clone_from(material);
+                }
+                list.push(id);
+            }
+            MassFaceRole::Skip => {}
+        }
+    }
+    let joins = join_walls(geometry, elements, &made.walls, tolerance);
+    solids.extend(joins.solids);
+    made.constraints = joins.constraints;
+    made.solids = solids.into_iter().collect();
+    Ok(made)
+}
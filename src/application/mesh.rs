@@ -1,13 +1,91 @@
 /// Mesh creation module for converting domain solids into Bevy meshes
 use bevy::prelude::*;
+use std::collections::BTreeMap;
+use uuid::Uuid;
 
 use crate::domain::{GeometryRegistry, Solid};
 
 use super::triangulation::triangulate_polygon_for_rendering;
 
+/// Texture coordinates of a point on a face, projected onto the face's
+/// plane at one unit per meter
+///
+/// The u axis runs level along the face and the v axis up its slope, so
+/// brick courses and panel joints stay horizontal on walls and roofs. Level
+/// faces are mapped in plan, with u along the x axis.
+fn planar_uv(point: Vec3, normal: Vec3) -> [f32; 2] {
+    let u_axis = Vec3::Y.cross(normal).try_normalize().unwrap_or(Vec3::X);
+    let v_axis = normal.cross(u_axis).normalize_or_zero();
+    [point.dot(u_axis), point.dot(v_axis)]
+}
+
 /// Creates a Bevy mesh from a domain Solid using the provided registries
 /// This function translates our domain model into a renderable mesh with proper triangulation
+#[tracing::instrument(level = "debug", skip_all, fields(solid = %solid.id))]
 pub fn create_mesh_from_solid(solid: &Solid, geometry_registry: &GeometryRegistry) -> Mesh {
+    create_mesh_from_polygons(&solid.polygons, geometry_registry, None)
+}
+
+/// Creates a Bevy mesh of a solid's faces with a vertex color for each
+/// face, for shading analysis results per element or per face
+/// Faces `face_color` gives no color are left out; returns None if it gives
+/// none a color.
+#[tracing::instrument(level = "debug", skip_all, fields(solid = %solid.id))]
+pub fn create_colored_mesh_from_solid(
+    solid: &Solid,
+    geometry_registry: &GeometryRegistry,
+    face_color: impl Fn(&Uuid) -> Option<[f32; 4]>,
+) -> Option<Mesh> {
+    let (polygons, colors): (Vec<Uuid>, Vec<[f32; 4]>) = solid
+        .polygons
+        .iter()
+        .filter_map(|polygon_id| Some((*polygon_id, face_color(polygon_id)?)))
+        .unzip();
+    if polygons.is_empty() {
+        return None;
+    }
+    Some(create_mesh_from_polygons(
+        &polygons,
+        geometry_registry,
+        Some(&colors),
+    ))
+}
+
+/// Creates one Bevy mesh per material from a domain Solid, so faces with
+/// their own materials can be drawn with them
+/// `face_material` names the material of each polygon. Sections are
+/// ordered by material name, the faces with no material first.
+#[tracing::instrument(level = "debug", skip_all, fields(solid = %solid.id))]
+pub fn create_mesh_sections_from_solid<'a>(
+    solid: &Solid,
+    geometry_registry: &GeometryRegistry,
+    face_material: impl Fn(&Uuid) -> Option<&'a str>,
+) -> Vec<(Option<String>, Mesh)> {
+    let mut sections: BTreeMap<Option<&str>, Vec<Uuid>> = BTreeMap::new();
+    for polygon_id in &solid.polygons {
+        sections
+            .entry(face_material(polygon_id))
+            .or_default()
+            .push(*polygon_id);
+    }
+    sections
+        .into_iter()
+        .map(|(material, polygons)| {
+            (
+                material.map(str::to_string),
+                create_mesh_from_polygons(&polygons, geometry_registry, None),
+            )
+        })
+        .collect()
+}
+
+/// Builds a Bevy mesh from some of a solid's polygons, with a vertex color
+/// channel if each polygon is given a color
+fn create_mesh_from_polygons(
+    polygons: &[Uuid],
+    geometry_registry: &GeometryRegistry,
+    polygon_colors: Option<&[[f32; 4]]>,
+) -> Mesh {
     let polygon_registry = &geometry_registry.polygons;
     let segment_registry = &geometry_registry.segments;
     let vertex_registry = &geometry_registry.vertices;
@@ -22,13 +100,15 @@ pub fn create_mesh_from_solid(solid: &Solid, geometry_registry: &GeometryRegistr
     let mut positions = Vec::new(); // Vertex positions in 3D space
     let mut normals = Vec::new(); // Surface normals for lighting
     let mut uvs = Vec::new(); // Texture coordinates
+    let mut colors = Vec::new(); // Vertex colors, when asked for
     let mut indices = Vec::new(); // Triangle indices for efficient rendering
 
     // Track the current vertex index for building triangles
     let mut current_index = 0u32;
 
-    // Process each polygon in the solid to build the complete mesh
-    for polygon_id in &solid.polygons {
+    // Process each polygon to build the complete mesh
+    for (polygon_index, polygon_id) in polygons.iter().enumerate() {
+        let color = polygon_colors.and_then(|polygon_colors| polygon_colors.get(polygon_index));
         if let Some(polygon) = polygon_registry.get(polygon_id) {
             // Triangulate this polygon into renderable triangles
             let triangulated_faces = triangulate_polygon_for_rendering(
@@ -40,22 +120,21 @@ pub fn create_mesh_from_solid(solid: &Solid, geometry_registry: &GeometryRegistr
             // Process each triangulated face to add to the mesh
             for face in triangulated_faces.iter() {
                 // Add vertices for this triangle to the mesh buffers
-                for (vertex_idx, vertex) in face.vertices.iter().enumerate() {
+                for vertex in &face.vertices {
                     // Position: Convert our Vec3 to Bevy's expected format
                     positions.push([vertex.x, vertex.y, vertex.z]);
 
                     // Normal: Each vertex gets the face normal for consistent lighting
                     normals.push([face.normal.x, face.normal.y, face.normal.z]);
 
-                    // UV coordinates: Simple mapping that could be improved
-                    // This creates a basic texture coordinate pattern for each triangle
-                    let uv = match vertex_idx {
-                        0 => [0.0, 0.0], // Bottom-left of triangle
-                        1 => [1.0, 0.0], // Bottom-right of triangle
-                        2 => [0.5, 1.0], // Top of triangle
-                        _ => [0.0, 0.0], // Fallback (shouldn't happen)
-                    };
-                    uvs.push(uv);
+                    // UV coordinates: Projected onto the face at world scale, so
+                    // textures tile at the same size on every face
+                    uvs.push(planar_uv(*vertex, face.normal));
+
+                    // Color: Each vertex gets its polygon's color
+                    if let Some(color) = color {
+                        colors.push(*color);
+                    }
                 }
 
                 // Add triangle indices - these tell the GPU how to connect vertices
@@ -74,6 +153,10 @@ pub fn create_mesh_from_solid(solid: &Solid, geometry_registry: &GeometryRegistr
     mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
     mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
     mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
+    if polygon_colors.is_some() {
+        mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, colors);
+    }
+    tracing::debug!(triangles = indices.len() / 3, "built mesh");
     mesh.insert_indices(bevy::render::mesh::Indices::U32(indices));
 
     mesh
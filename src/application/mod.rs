@@ -1,30 +1,71 @@
 /// Application layer for the application
-use uuid::Uuid;
-
-use crate::domain::Solid;
+use crate::domain::{new_id, Phase, Solid};
 
 /// Selection module for the application
 /// This module contains the logic for selecting geometry in the application
 pub mod selection;
 
 /// Triangulation module for converting polygons into renderable triangles
+#[cfg(feature = "interface")]
 mod triangulation;
 
 /// Mesh creation module for converting domain solids into Bevy meshes
+#[cfg(feature = "interface")]
 mod mesh;
 
 /// Cube creation utilities for the application layer
 pub mod cuboid;
 
+/// Model edits behind the interface's commands
+pub mod commands;
+
+/// A building model behind one set of editing methods
+pub mod model;
+
+/// Properties kept equal to expressions over other properties
+pub mod expressions;
+
+/// Door and window families hosted in walls
+pub mod families;
+
+/// Curtain wall grids dividing facade faces
+pub mod curtain_wall;
+
+/// Floor finishes and ceilings following their spaces
+pub mod finishes;
+
+/// Columns and walls generated from structural grids
+pub mod grids;
+
+/// Converting massing solids into walls, roofs and floors
+pub mod massing;
+
+/// Placing components on floors and walls and keeping them there
+pub mod placement;
+
+/// Recorded operations run again when their inputs change
+pub mod provenance;
+
+/// Routing ducts, pipes and conduits and checking them for clashes
+pub mod services;
+
+/// Mitered and butted joins between walls that meet
+pub mod wall_joins;
+
 pub use cuboid::*;
-pub use mesh::create_mesh_from_solid;
+#[cfg(feature = "interface")]
+pub use mesh::{
+    create_colored_mesh_from_solid, create_mesh_from_solid, create_mesh_sections_from_solid,
+};
+pub use model::Model;
 
 /// Create a new solid
 pub fn new_solid() -> Solid {
     // Arbitrary solid for now
     let solid = Solid {
-        id: Uuid::new_v4(),
+        id: new_id(),
         polygons: vec![],
+        phase: Phase::default(),
     };
     solid
 }
@@ -0,0 +1,480 @@
+/// A building model behind one set of editing methods
+///
+/// `Model` owns the geometry registry, the elements, the tiers and each
+/// tier's constraints, so library users do not have to keep them in step
+/// by hand. Edits made through its methods are recorded and can be undone
+/// and redone. Removing an element takes the element and its solid out of
+/// the registries but leaves the solid's polygons, segments and vertices
+/// in place, so undoing the removal only has to put two items back; those
+/// primitives are cleared up by `collect_garbage`, which also ends the
+/// history.
+use crate::domain::solver::{Constraint, ConstraintError, ConstraintSet};
+use crate::domain::{
+    Element, ElementKind, ElementRegistry, Garbage, GeometryRegistry, Phase, Point, Solid, Tier,
+    TierMove, TierRegistry, Tolerance,
+};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use uuid::Uuid;
+
+/// A recorded edit, holding what is needed to undo and redo it
+enum Edit {
+    /// An element and its solid were added or taken out; undoing and
+    /// redoing both swap them between the registries and `detached`
+    Presence {
+        element: Uuid,
+        /// The tiers holding the solid while it is in the model
+        tiers: Vec<Uuid>,
+        /// The element and solid while they are out of the registries
+        detached: Option<(Element, Solid)>,
+    },
+    /// A solid changed phase
+    Phase { solid: Uuid, from: Phase, to: Phase },
+    /// An element's material changed
+    Material {
+        element: Uuid,
+        from: Option<String>,
+        to: Option<String>,
+    },
+    /// The material of one of an element's faces changed
+    FaceMaterial {
+        element: Uuid,
+        polygon: Uuid,
+        from: Option<String>,
+        to: Option<String>,
+    },
+    /// A tier was created; undoing and redoing swap it between the
+    /// registry and `detached`
+    Tier { id: Uuid, detached: Option<Tier> },
+    /// Geometry moved between tiers
+    TierMove(TierMove),
+    /// A constraint was added to the end of a tier's set
+    Constraint { tier: Uuid, constraint: Constraint },
+}
+
+/// A building model with undo history
+pub struct Model {
+    geometry: GeometryRegistry,
+    elements: ElementRegistry,
+    tiers: TierRegistry,
+    constraints: HashMap<Uuid, ConstraintSet>,
+    /// Edits in the order they were made, most recent last
+    history: Vec<Edit>,
+    /// Undone edits, most recently undone last
+    undone: Vec<Edit>,
+}
+
+impl Default for Model {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Model {
+    /// An empty model
+    #[must_use]
+    pub fn new() -> Self {
+        Self::from_parts(
+            GeometryRegistry::create_new(),
+            ElementRegistry::create_new(),
+            TierRegistry::create_new(),
+        )
+    }
+
+    /// A model around existing registries, with no constraints or history
+    #[must_use]
+    pub fn from_parts(
+        geometry: GeometryRegistry,
+        elements: ElementRegistry,
+        tiers: TierRegistry,
+    ) -> Self {
+        Self {
+            geometry,
+            elements,
+            tiers,
+            constraints: HashMap::new(),
+            history: Vec::new(),
+            undone: Vec::new(),
+        }
+    }
+
+    /// Give up the model, keeping its registries and constraints
+    #[must_use]
+    pub fn into_parts(
+        self,
+    ) -> (
+        GeometryRegistry,
+        ElementRegistry,
+        TierRegistry,
+        HashMap<Uuid, ConstraintSet>,
+    ) {
+        (self.geometry, self.elements, self.tiers, self.constraints)
+    }
+
+    /// The geometry registry
+    #[must_use]
+    pub fn geometry(&self) -> &GeometryRegistry {
+        &self.geometry
+    }
+
+    /// The element registry
+    #[must_use]
+    pub fn elements(&self) -> &ElementRegistry {
+        &self.elements
+    }
+
+    /// The tier registry
+    #[must_use]
+    pub fn tiers(&self) -> &TierRegistry {
+        &self.tiers
+    }
+
+    /// The constraints of a tier, if any were added
+    #[must_use]
+    pub fn constraints(&self, tier: &Uuid) -> Option<&ConstraintSet> {
+        self.constraints.get(tier)
+    }
+
+    /// The registries for edits the model has no method for
+    ///
+    /// Such edits are not recorded, so the undo history is cleared.
+    pub fn registries_mut(
+        &mut self,
+    ) -> (
+        &mut GeometryRegistry,
+        &mut ElementRegistry,
+        &mut TierRegistry,
+    ) {
+        self.clear_history();
+        (&mut self.geometry, &mut self.elements, &mut self.tiers)
+    }
+
+    /// The tolerance for new geometry in a tier, or the default outside one
+    fn tolerance_in(&self, tier: Option<&Uuid>) -> Tolerance {
+        tier.map_or(self.tiers.default_tolerance, |tier| {
+            self.tiers.tolerance(tier)
+        })
+    }
+
+    /// Record an edit, dropping whatever had been undone
+    fn record(&mut self, edit: Edit) {
+        self.history.push(edit);
+        self.undone.clear();
+    }
+
+    /// Add an element whose solid is built from closed point loops, and
+    /// return the element's ID
+    ///
+    /// Points are welded to the tolerance of the tier, if one is given, and
+    /// the solid joins that tier.
+    pub fn add_element(
+        &mut self,
+        kind: ElementKind,
+        name: &str,
+        loops: &[Vec<Point>],
+        tier: Option<&Uuid>,
+    ) -> Uuid {
+        let tolerance = self.tolerance_in(tier);
+        let solid = self.geometry.create_solid_from_loops(loops, &tolerance);
+        let element = self.elements.create_and_store(kind, &solid, name);
+        if let Some(tier) = tier.and_then(|tier| self.tiers.get_mut(tier)) {
+            tier.geometry.push(solid);
+        }
+        self.record(Edit::Presence {
+            element,
+            tiers: tier.copied().into_iter().collect(),
+            detached: None,
+        });
+        element
+    }
+
+    /// Take an element and its solid out of the model
+    ///
+    /// Returns false if the element or its solid is missing.
+    pub fn remove_element(&mut self, element: &Uuid) -> bool {
+        let Some(solid) = self.elements.get(element).map(|element| element.solid) else {
+            return false;
+        };
+        if self.geometry.solids.get(&solid).is_none() {
+            return false;
+        }
+        let tiers = self
+            .tiers
+            .sorted()
+            .into_iter()
+            .filter(|tier| tier.geometry.contains(&solid))
+            .map(|tier| tier.id)
+            .collect();
+        let mut edit = Edit::Presence {
+            element: *element,
+            tiers,
+            detached: None,
+        };
+        self.toggle_presence(&mut edit);
+        self.record(edit);
+        true
+    }
+
+    /// Swap an element and its solid between the registries and the edit
+    fn toggle_presence(&mut self, edit: &mut Edit) {
+        let Edit::Presence {
+            element,
+            tiers,
+            detached,
+        } = edit
+        else {
+            return;
+        };
+        if let Some((element, solid)) = detached.take() {
+            for tier in tiers.iter() {
+                if let Some(tier) = self.tiers.get_mut(tier) {
+                    tier.geometry.push(solid.id);
+                }
+            }
+            self.geometry.solids.solids.insert(solid.id, solid);
+            self.elements.elements.insert(element.id, element);
+            return;
+        }
+        let Some(solid) = self.elements.get(element).map(|element| element.solid) else {
+            return;
+        };
+        let Some(solid) = self.geometry.solids.solids.remove(&solid) else {
+            return;
+        };
+        for tier in self.tiers.tiers.values_mut() {
+            tier.geometry.retain(|id| *id != solid.id);
+        }
+        if let Some(element) = self.elements.elements.remove(element) {
+            *detached = Some((element, solid));
+        }
+    }
+
+    /// Set the phase of a solid
+    ///
+    /// Returns false if the solid is missing.
+    pub fn set_phase(&mut self, solid: &Uuid, phase: Phase) -> bool {
+        let Some(from) = self.geometry.solids.get(solid).map(|solid| solid.phase) else {
+            return false;
+        };
+        self.geometry.set_solid_phase(solid, phase);
+        self.record(Edit::Phase {
+            solid: *solid,
+            from,
+            to: phase,
+        });
+        true
+    }
+
+    /// Set or clear the material of an element
+    ///
+    /// Returns false if the element is missing.
+    pub fn set_material(&mut self, element: &Uuid, material: Option<&str>) -> bool {
+        let to = material.map(str::to_string);
+        let Some(entry) = self.elements.get_mut(element) else {
+            return false;
+        };
+        let from = std::mem::replace(&mut entry.material, to.clone());
+        self.record(Edit::Material {
+            element: *element,
+            from,
+            to,
+        });
+        true
+    }
+
+    /// Set or clear the material of one face of an element, overriding
+    /// the element's own material on that face
+    ///
+    /// Returns false if the element is missing.
+    pub fn set_face_material(
+        &mut self,
+        element: &Uuid,
+        polygon: &Uuid,
+        material: Option<&str>,
+    ) -> bool {
+        let to = material.map(str::to_string);
+        let Some(entry) = self.elements.get_mut(element) else {
+            return false;
+        };
+        let from = set_face_override(&mut entry.face_materials, polygon, to.clone());
+        self.record(Edit::FaceMaterial {
+            element: *element,
+            polygon: *polygon,
+            from,
+            to,
+        });
+        true
+    }
+
+    /// Create a tier and return its ID
+    pub fn add_tier(
+        &mut self,
+        name: &str,
+        parent: Option<&Uuid>,
+        tolerance: Option<Tolerance>,
+    ) -> Uuid {
+        let id = self
+            .tiers
+            .create_and_store(name, parent.copied(), tolerance);
+        self.record(Edit::Tier { id, detached: None });
+        id
+    }
+
+    /// Move geometry between tiers, checking the tier boundaries
+    ///
+    /// # Errors
+    /// Returns an error if the move fails, as described in
+    /// [`TierRegistry::move_geometry`]; the model is then unchanged.
+    pub fn move_to_tier(
+        &mut self,
+        geometry: &[Uuid],
+        from: &Uuid,
+        to: &Uuid,
+    ) -> Result<(), ConstraintError> {
+        let record = self
+            .tiers
+            .move_geometry(&self.geometry, geometry, from, to)?;
+        self.record(Edit::TierMove(record));
+        Ok(())
+    }
+
+    /// Add an explicit constraint to a tier's constraint set
+    pub fn add_constraint(&mut self, tier: &Uuid, constraint: Constraint) {
+        self.constraints
+            .entry(*tier)
+            .or_default()
+            .explicit
+            .push(constraint.clone());
+        self.record(Edit::Constraint {
+            tier: *tier,
+            constraint,
+        });
+    }
+
+    /// Whether there is an edit to undo
+    #[must_use]
+    pub fn can_undo(&self) -> bool {
+        !self.history.is_empty()
+    }
+
+    /// Whether there is an undone edit to redo
+    #[must_use]
+    pub fn can_redo(&self) -> bool {
+        !self.undone.is_empty()
+    }
+
+    /// Undo the most recent edit
+    ///
+    /// Returns false if there was nothing to undo.
+    pub fn undo(&mut self) -> bool {
+        let Some(edit) = self.history.pop() else {
+            return false;
+        };
+        let edit = self.reverse(edit, true);
+        self.undone.push(edit);
+        true
+    }
+
+    /// Redo the most recently undone edit
+    ///
+    /// Returns false if there was nothing to redo.
+    pub fn redo(&mut self) -> bool {
+        let Some(edit) = self.undone.pop() else {
+            return false;
+        };
+        let edit = self.reverse(edit, false);
+        self.history.push(edit);
+        true
+    }
+
+    /// Undo an edit, or redo it, and return it ready to be reversed again
+    fn reverse(&mut self, edit: Edit, undo: bool) -> Edit {
+        match edit {
+            mut presence @ Edit::Presence { .. } => {
+                self.toggle_presence(&mut presence);
+                presence
+            }
+            Edit::Phase { solid, from, to } => {
+                self.geometry
+                    .set_solid_phase(&solid, if undo { from } else { to });
+                Edit::Phase { solid, from, to }
+            }
+            Edit::Material { element, from, to } => {
+                if let Some(entry) = self.elements.get_mut(&element) {
+                    entry.material = if undo { from.clone() } else { to.clone() };
+                }
+                Edit::Material { element, from, to }
+            }
+            Edit::FaceMaterial {
+                element,
+                polygon,
+                from,
+                to,
+            } => {
+                if let Some(entry) = self.elements.get_mut(&element) {
+                    let material = if undo { from.clone() } else { to.clone() };
+                    set_face_override(&mut entry.face_materials, &polygon, material);
+                }
+                Edit::FaceMaterial {
+                    element,
+                    polygon,
+                    from,
+                    to,
+                }
+            }
+            Edit::Tier { id, detached } => {
+                let detached = match detached {
+                    Some(tier) => {
+                        self.tiers.tiers.insert(tier.id, tier);
+                        None
+                    }
+                    None => self.tiers.tiers.remove(&id),
+                };
+                Edit::Tier { id, detached }
+            }
+            Edit::TierMove(record) => {
+                if undo {
+                    self.tiers.undo_move(&record);
+                } else {
+                    self.tiers.apply_move(&record);
+                }
+                Edit::TierMove(record)
+            }
+            Edit::Constraint { tier, constraint } => {
+                let set = self.constraints.entry(tier).or_default();
+                if undo {
+                    set.explicit.pop();
+                } else {
+                    set.explicit.push(constraint.clone());
+                }
+                Edit::Constraint { tier, constraint }
+            }
+        }
+    }
+
+    /// Forget every recorded edit
+    pub fn clear_history(&mut self) {
+        self.history.clear();
+        self.undone.clear();
+    }
+
+    /// Remove the geometry nothing in the model reaches, as described in
+    /// [`GeometryRegistry::collect_garbage`], and clear the undo history
+    /// that might still refer to it
+    pub fn collect_garbage(&mut self, pinned: &HashSet<Uuid>) -> Garbage {
+        self.clear_history();
+        self.geometry
+            .collect_garbage(&self.elements, &self.tiers, pinned)
+    }
+}
+
+/// Set or clear a face's material override, returning the one it replaced
+fn set_face_override(
+    face_materials: &mut BTreeMap<Uuid, String>,
+    polygon: &Uuid,
+    material: Option<String>,
+) -> Option<String> {
+    match material {
+        Some(material) => face_materials.insert(*polygon, material),
+        None => face_materials.remove(polygon),
+    }
+}
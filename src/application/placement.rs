@@ -0,0 +1,347 @@
+/// Placing components on floors and walls and keeping them there
+///
+/// Placing a component on a face finds the solid the face belongs to and
+/// lays out the frame of that side of it, as described in
+/// [`crate::domain::placement`], then places an instance at the offset
+/// asked for in that frame. Distributing places several instances along a
+/// face at equal spacing, each centered in an equal share of its length.
+///
+/// Updating placements after an edit lays out each host's frame again and
+/// moves the instances whose hosts moved, keeping their solids. An
+/// instance whose host is gone, or no longer a floor or a wall, stays
+/// where it is and is no longer kept on it.
+use crate::application::commands::transform_vertices;
+use crate::domain::geometry::newell_normal;
+use crate::domain::{
+    equal_spacing, new_id, ComponentDefinition, ComponentLibrary, FaceFrame, FaceOffset,
+    GeometryRegistry, HostKind, HostedPlacement, HostedPlacementRegistry, LinkTransform, Point,
+    Tolerance,
+};
+use uuid::Uuid;
+
+/// Why a component could not be placed on a face
+#[derive(Debug, Clone, PartialEq)]
+pub enum PlacementError {
+    /// The component definition does not exist
+    MissingDefinition,
+    /// The face does not exist or belongs to no solid
+    MissingFace,
+    /// The face looks neither up like a floor nor sideways like a wall
+    NotAHost,
+    /// No instances were asked for
+    Count,
+    /// The instances are too wide to fit side by side along the face
+    Crowded,
+}
+
+impl std::fmt::Display for PlacementError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PlacementError::MissingDefinition => write!(f, "The component does not exist"),
+            PlacementError::MissingFace => write!(f, "The face does not exist"),
+            PlacementError::NotAHost => {
+                write!(f, "Components can only be placed on floors and walls")
+            }
+            PlacementError::Count => write!(f, "At least one instance is needed"),
+            PlacementError::Crowded => write!(f, "The instances do not fit along the face"),
+        }
+    }
+}
+
+impl std::error::Error for PlacementError {}
+
+/// The host face's points: the face itself while it exists, or else the
+/// host's face looking the same way, if the host's faces were rebuilt
+fn host_face(
+    geometry: &GeometryRegistry,
+    host: &Uuid,
+    face: &Uuid,
+    normal: [f32; 3],
+    tolerance: &Tolerance,
+) -> Option<(Uuid, Vec<Point>)> {
+    let solid = geometry.solids.get(host)?;
+    if solid.polygons.contains(face) {
+        return Some((*face, geometry.polygon_points(face)?));
+    }
+    solid
+        .polygons
+        .iter()
+        .filter_map(|face| {
+            let points = geometry.polygon_points(face)?;
+            let now = newell_normal(&points).normalized()?;
+            let facing = now.x * normal[0] + now.y * normal[1] + now.z * normal[2];
+            (facing >= tolerance.angular.cos()).then_some((*face, points))
+        })
+        .min_by_key(|(face, _)| *face)
+}
+
+/// The points of the host's faces lying in the plane of a face of it
+fn side_points(
+    geometry: &GeometryRegistry,
+    host: &Uuid,
+    points: &[Point],
+    normal: [f32; 3],
+    tolerance: &Tolerance,
+) -> Vec<Vec<Point>> {
+    let Some(solid) = geometry.solids.get(host) else {
+        return Vec::new();
+    };
+    let level = |point: &Point| point.x * normal[0] + point.y * normal[1] + point.z * normal[2];
+    let plane = level(&points[0]);
+    let mut faces: Vec<Uuid> = solid.polygons.clone();
+    faces.sort();
+    faces
+        .into_iter()
+        .filter_map(|face| geometry.polygon_points(&face))
+        .filter(|side| {
+            newell_normal(side).normalized().is_some_and(|now| {
+                now.x * normal[0] + now.y * normal[1] + now.z * normal[2] >= tolerance.angular.cos()
+            }) && (level(&side[0]) - plane).abs() <= tolerance.linear
+        })
+        .collect()
+}
+
+/// The unit direction in plan of the longest edge among some faces
+fn longest_edge(faces: &[Vec<Point>], tolerance: &Tolerance) -> Option<[f32; 2]> {
+    let (length, dx, dz) = faces
+        .iter()
+        .flat_map(|face| {
+            face.iter()
+                .zip(face.iter().cycle().skip(1))
+                .map(|(start, end)| (end.x - start.x, end.z - start.z))
+        })
+        .map(|(dx, dz)| ((dx * dx + dz * dz).sqrt(), dx, dz))
+        .fold((0.0, 0.0, 0.0), |longest, edge| {
+            if edge.0 > longest.0 + tolerance.linear {
+                edge
+            } else {
+                longest
+            }
+        });
+    (length > tolerance.linear).then(|| [dx / length, dz / length])
+}
+
+/// The frame of the side of a host a face lies on, with the face's unit
+/// normal
+fn host_frame(
+    geometry: &GeometryRegistry,
+    host: &Uuid,
+    points: &[Point],
+    tolerance: &Tolerance,
+) -> Result<(FaceFrame, [f32; 3]), PlacementError> {
+    let normal = newell_normal(points)
+        .normalized()
+        .ok_or(PlacementError::NotAHost)?;
+    let normal = [normal.x, normal.y, normal.z];
+    let kind = if normal[1] >= tolerance.angular.cos() {
+        HostKind::Floor
+    } else if normal[1].abs() <= tolerance.angular.sin() {
+        HostKind::Wall
+    } else {
+        return Err(PlacementError::NotAHost);
+    };
+    let faces = side_points(geometry, host, points, normal, tolerance);
+    let direction = match kind {
+        HostKind::Floor => longest_edge(&faces, tolerance).ok_or(PlacementError::NotAHost)?,
+        HostKind::Wall => {
+            let flat = (normal[0] * normal[0] + normal[2] * normal[2]).sqrt();
+            [normal[2] / flat, -normal[0] / flat]
+        }
+    };
+    let across = [-direction[1], direction[0]];
+    let range = |measure: &dyn Fn(&Point) -> f32| {
+        faces
+            .iter()
+            .flatten()
+            .map(measure)
+            .fold([f32::MAX, f32::MIN], |[low, high], value| {
+                [low.min(value), high.max(value)]
+            })
+    };
+    let [start, end] = range(&|point| point.x * direction[0] + point.z * direction[1]);
+    let [near, far] = range(&|point| point.x * across[0] + point.z * across[1]);
+    let [bottom, top] = range(&|point| point.y);
+    let (across_start, height, size) = match kind {
+        HostKind::Floor => (near, top, [end - start, far - near]),
+        HostKind::Wall => (near, bottom, [end - start, top - bottom]),
+    };
+    let frame = FaceFrame {
+        kind,
+        origin: [
+            start * direction[0] + across_start * across[0],
+            height,
+            start * direction[1] + across_start * across[1],
+        ],
+        direction,
+        size,
+    };
+    Ok((frame, normal))
+}
+
+/// The solid a face belongs to
+fn face_host(geometry: &GeometryRegistry, face: &Uuid) -> Option<Uuid> {
+    geometry
+        .solids
+        .sorted()
+        .into_iter()
+        .find(|solid| solid.polygons.contains(face))
+        .map(|solid| solid.id)
+}
+
+/// Place a component on a floor or a wall, at an offset in the frame of
+/// the side of the host the face lies on, and return the instance's solid
+///
+/// # Errors
+/// Returns an error if the definition or the face is missing, or the face
+/// is neither a floor nor a wall
+pub fn place_on_face(
+    geometry: &mut GeometryRegistry,
+    library: &mut ComponentLibrary,
+    placements: &mut HostedPlacementRegistry,
+    definition: &Uuid,
+    face: &Uuid,
+    offset: FaceOffset,
+    tolerance: &Tolerance,
+) -> Result<Uuid, PlacementError> {
+    if library.get(definition).is_none() {
+        return Err(PlacementError::MissingDefinition);
+    }
+    let host = face_host(geometry, face).ok_or(PlacementError::MissingFace)?;
+    let points = geometry
+        .polygon_points(face)
+        .ok_or(PlacementError::MissingFace)?;
+    let (frame, normal) = host_frame(geometry, &host, &points, tolerance)?;
+    let placement = frame.place(&offset);
+    let solid = library
+        .place(definition, placement, geometry, tolerance)
+        .ok_or(PlacementError::MissingDefinition)?;
+    let phase = geometry.solids.get(&host).map(|solid| solid.phase);
+    if let (Some(phase), Some(made)) = (phase, geometry.solids.get_mut(&solid)) {
+        made.phase = phase;
+    }
+    placements.store(HostedPlacement {
+        id: new_id(),
+        solid,
+        host,
+        face: *face,
+        normal,
+        offset,
+        built_for: placement,
+    });
+    Ok(solid)
+}
+
+/// The offsets spreading a number of copies of a component along a floor
+/// or a wall at equal spacing, for each to be placed with [`place_on_face`]
+///
+/// Each copy takes the offset given, except along the face: there the
+/// middle of the component's width sits in the middle of its equal share
+/// of the face's length.
+///
+/// # Errors
+/// Returns an error if the definition or the face is missing, the face is
+/// neither a floor nor a wall, no copies are asked for or the copies are
+/// too wide to fit side by side
+pub fn distribute_on_face(
+    geometry: &GeometryRegistry,
+    library: &ComponentLibrary,
+    definition: &Uuid,
+    face: &Uuid,
+    count: usize,
+    offset: FaceOffset,
+    tolerance: &Tolerance,
+) -> Result<Vec<FaceOffset>, PlacementError> {
+    if count == 0 {
+        return Err(PlacementError::Count);
+    }
+    let bounds = library
+        .get(definition)
+        .and_then(ComponentDefinition::bounds)
+        .ok_or(PlacementError::MissingDefinition)?;
+    let host = face_host(geometry, face).ok_or(PlacementError::MissingFace)?;
+    let points = geometry
+        .polygon_points(face)
+        .ok_or(PlacementError::MissingFace)?;
+    let (frame, _) = host_frame(geometry, &host, &points, tolerance)?;
+    let centers = equal_spacing(frame.size[0], count);
+    let width = bounds.max.x - bounds.min.x;
+    if centers
+        .first()
+        .is_some_and(|first| width > 2.0 * first + tolerance.linear)
+    {
+        return Err(PlacementError::Crowded);
+    }
+    let middle = f32::midpoint(bounds.min.x, bounds.max.x);
+    Ok(centers
+        .into_iter()
+        .map(|center| FaceOffset {
+            along: center - middle,
+            ..offset
+        })
+        .collect())
+}
+
+/// Whether two placements are the same within tolerance
+fn same_placement(first: &LinkTransform, second: &LinkTransform, tolerance: &Tolerance) -> bool {
+    (first.x - second.x).abs() <= tolerance.linear
+        && (first.y - second.y).abs() <= tolerance.linear
+        && (first.z - second.z).abs() <= tolerance.linear
+        && (first.rotation - second.rotation).sin().abs() <= tolerance.angular.sin()
+        && (first.rotation - second.rotation).cos() > 0.0
+}
+
+/// Move the instances whose hosts moved after an edit, keeping them at
+/// their offsets on their hosts
+///
+/// Returns the solids moved, for their meshes to be refreshed.
+pub fn update_hosted_placements(
+    geometry: &mut GeometryRegistry,
+    library: &mut ComponentLibrary,
+    placements: &mut HostedPlacementRegistry,
+    tolerance: &Tolerance,
+) -> Vec<Uuid> {
+    let mut changed = Vec::new();
+    let ids: Vec<Uuid> = placements
+        .sorted()
+        .iter()
+        .map(|placement| placement.id)
+        .collect();
+    for id in ids {
+        let Some(mut placement) = placements.get(&id).cloned() else {
+            continue;
+        };
+        let frame = host_face(
+            geometry,
+            &placement.host,
+            &placement.face,
+            placement.normal,
+            tolerance,
+        )
+        .and_then(|(face, points)| {
+            let (frame, normal) = host_frame(geometry, &placement.host, &points, tolerance).ok()?;
+            Some((face, frame, normal))
+        });
+        let (Some((face, frame, normal)), Some(_)) = (frame, geometry.solids.get(&placement.solid))
+        else {
+            placements.remove(&id);
+            continue;
+        };
+        placement.face = face;
+        placement.normal = normal;
+        let moved = frame.place(&placement.offset);
+        if !same_placement(&moved, &placement.built_for, tolerance) {
+            let was = placement.built_for;
+            let vertices = geometry.solid_vertices(&placement.solid);
+            transform_vertices(geometry, &vertices, |point| {
+                moved.apply(&was.unapply(point))
+            });
+            if let Some(instance) = library.instances.get_mut(&placement.solid) {
+                instance.placement = moved;
+            }
+            placement.built_for = moved;
+            changed.push(placement.solid);
+        }
+        placements.store(placement);
+    }
+    changed
+}
@@ -0,0 +1,430 @@
+/// Running recorded operations and running them again when their inputs
+/// change
+///
+/// Every operation run here is recorded in a [`ProvenanceGraph`] with the
+/// primitives it read and the solids it made. After the model changes,
+/// [`update_operations`] finds the records whose inputs moved, asks the
+/// graph for everything downstream of them and runs those again in order.
+///
+/// Running an operation again rebuilds the solids it made in place,
+/// keeping their IDs, so elements, tiers and later operations built on
+/// them keep following. A solid the operation no longer makes, as when it
+/// is suppressed or can no longer be run, is emptied rather than removed,
+/// so it comes back in place if the operation makes it again. Operations whose inputs are gone are forgotten and leave what
+/// they made as it is.
+///
+/// The history tree edits, suppressing, changing the parameters of,
+/// moving and deleting past operations, run everything downstream of the
+/// edited operation again the same way.
+use crate::domain::geometry::{
+    connected_shells, newell_normal, signed_volume, split_loops_by_plane,
+};
+use crate::domain::{GeometryRegistry, Operation, Point, ProvenanceGraph, Tolerance};
+use uuid::Uuid;
+
+/// Why an operation could not be run
+#[derive(Debug, Clone, PartialEq)]
+pub enum OperationError {
+    /// A polygon or solid it reads does not exist
+    MissingInput,
+    /// The face would be swept by no distance
+    Depth,
+    /// Nothing of the solid is on the kept side of the plane
+    EmptyCut,
+    /// Generators are run by their own registries
+    Generator,
+    /// The recorded operation does not exist
+    MissingRecord,
+    /// An operation's parameters were replaced by another kind's
+    Kind,
+    /// The operation would come before one making its inputs or after one
+    /// reading what it made
+    Order,
+}
+
+impl std::fmt::Display for OperationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OperationError::MissingInput => write!(f, "Its input does not exist"),
+            OperationError::Depth => write!(f, "The face would be swept by no distance"),
+            OperationError::EmptyCut => write!(f, "Nothing is kept on that side of the plane"),
+            OperationError::Generator => write!(f, "Generators are run by their own registries"),
+            OperationError::MissingRecord => write!(f, "The operation does not exist"),
+            OperationError::Kind => write!(f, "An operation cannot become another kind"),
+            OperationError::Order => write!(
+                f,
+                "An operation must come after the operations making its inputs"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for OperationError {}
+
+/// What running operations again after a change did
+#[derive(Debug, Clone, Default)]
+pub struct OperationUpdate {
+    /// The solids rebuilt, made or removed, for their meshes to be
+    /// refreshed
+    pub solids: Vec<Uuid>,
+    /// Records of generators whose inputs changed, for their owners to run
+    /// them again
+    pub stale_generators: Vec<Uuid>,
+    /// Records forgotten because their inputs are gone
+    pub forgotten: Vec<Uuid>,
+}
+
+/// The points of an operation's inputs, polygons and solids alike
+fn input_points(geometry: &GeometryRegistry, inputs: &[Uuid]) -> Option<Vec<Point>> {
+    let mut points = Vec::new();
+    for input in inputs {
+        if let Some(polygon) = geometry.polygon_points(input) {
+            points.extend(polygon);
+        } else {
+            points.extend(geometry.solid_loops(input)?.concat());
+        }
+    }
+    Some(points)
+}
+
+/// Whether two sets of points are the same within a tolerance
+fn same_points(now: &[Point], then: &[Point], tolerance: f32) -> bool {
+    now.len() == then.len()
+        && now.iter().zip(then).all(|(now, then)| {
+            (now.x - then.x).abs() <= tolerance
+                && (now.y - then.y).abs() <= tolerance
+                && (now.z - then.z).abs() <= tolerance
+        })
+}
+
+/// The loops of a face swept along its normal by a depth, wound outward
+fn extruded_loops(points: &[Point], depth: f32) -> Option<Vec<Vec<Point>>> {
+    let normal = newell_normal(points).normalized()?;
+    let top: Vec<Point> = points
+        .iter()
+        .map(|point| Point {
+            x: point.x + normal.x * depth,
+            y: point.y + normal.y * depth,
+            z: point.z + normal.z * depth,
+        })
+        .collect();
+    let count = points.len();
+    let mut loops = vec![points.iter().rev().cloned().collect(), top.clone()];
+    for index in 0..count {
+        let next = (index + 1) % count;
+        loops.push(vec![
+            points[index].clone(),
+            points[next].clone(),
+            top[next].clone(),
+            top[index].clone(),
+        ]);
+    }
+    if signed_volume(&loops) < 0.0 {
+        for face in &mut loops {
+            face.reverse();
+        }
+    }
+    Some(loops)
+}
+
+/// The shells an operation makes from its inputs as they are now
+fn run(
+    geometry: &GeometryRegistry,
+    operation: &Operation,
+    inputs: &[Uuid],
+    suppressed: bool,
+    tolerance: &Tolerance,
+) -> Result<Vec<Vec<Vec<Point>>>, OperationError> {
+    let first = inputs.first().ok_or(OperationError::MissingInput)?;
+    match operation {
+        Operation::Extrude { .. } if suppressed => Ok(Vec::new()),
+        Operation::Cut { .. } if suppressed => {
+            let loops = geometry
+                .solid_loops(first)
+                .ok_or(OperationError::MissingInput)?;
+            Ok(if loops.is_empty() {
+                Vec::new()
+            } else {
+                vec![loops]
+            })
+        }
+        Operation::Extrude { depth } => {
+            if depth.abs() <= tolerance.linear {
+                return Err(OperationError::Depth);
+            }
+            let points = geometry
+                .polygon_points(first)
+                .ok_or(OperationError::MissingInput)?;
+            Ok(vec![
+                extruded_loops(&points, *depth).ok_or(OperationError::MissingInput)?
+            ])
+        }
+        Operation::Cut { plane, keep_front } => {
+            let mut loops = geometry
+                .solid_loops(first)
+                .ok_or(OperationError::MissingInput)?;
+            if signed_volume(&loops) < 0.0 {
+                for face in &mut loops {
+                    face.reverse();
+                }
+            }
+            let (behind, front) = split_loops_by_plane(&loops, plane, tolerance.linear);
+            let kept = if *keep_front { front } else { behind };
+            let shells = connected_shells(&kept, tolerance.linear);
+            if shells.is_empty() {
+                return Err(OperationError::EmptyCut);
+            }
+            Ok(shells)
+        }
+        Operation::Generator { .. } => Err(OperationError::Generator),
+    }
+}
+
+/// Build shells into an operation's outputs, rebuilding the solids it made
+/// before in place, making more as needed and emptying those left over
+///
+/// Returns the solids rebuilt, made and emptied.
+fn build_outputs(
+    geometry: &mut GeometryRegistry,
+    outputs: &mut Vec<Uuid>,
+    shells: &[Vec<Vec<Point>>],
+    tolerance: &Tolerance,
+) -> Vec<Uuid> {
+    let mut changed = Vec::new();
+    for (index, shell) in shells.iter().enumerate() {
+        let built = geometry.create_solid_from_loops(shell, tolerance);
+        let existing = outputs
+            .get(index)
+            .copied()
+            .filter(|id| geometry.solids.get(id).is_some());
+        if let Some(existing) = existing {
+            let polygons = geometry
+                .solids
+                .get(&built)
+                .map(|solid| solid.polygons.clone())
+                .unwrap_or_default();
+            geometry.solids.remove(&built);
+            if let Some(solid) = geometry.solids.get_mut(&existing) {
+                solid.polygons = polygons;
+            }
+            changed.push(existing);
+        } else {
+            if index < outputs.len() {
+                outputs[index] = built;
+            } else {
+                outputs.push(built);
+            }
+            changed.push(built);
+        }
+    }
+    for extra in outputs.iter().skip(shells.len()) {
+        if let Some(solid) = geometry.solids.get_mut(extra) {
+            if !solid.polygons.is_empty() {
+                solid.polygons.clear();
+                changed.push(*extra);
+            }
+        }
+    }
+    changed
+}
+
+/// Run an operation on inputs and record it
+///
+/// Returns the record's ID and the solids made.
+///
+/// # Errors
+/// Returns an error if the operation cannot be run on the inputs, or is a
+/// generator, which is recorded with [`ProvenanceGraph::record`] by its
+/// owner instead; nothing is changed then
+pub fn run_operation(
+    geometry: &mut GeometryRegistry,
+    provenance: &mut ProvenanceGraph,
+    operation: Operation,
+    inputs: Vec<Uuid>,
+    tolerance: &Tolerance,
+) -> Result<(Uuid, Vec<Uuid>), OperationError> {
+    let shells = run(geometry, &operation, &inputs, false, tolerance)?;
+    let built_from = input_points(geometry, &inputs).ok_or(OperationError::MissingInput)?;
+    let mut outputs = Vec::new();
+    build_outputs(geometry, &mut outputs, &shells, tolerance);
+    let record = provenance.record(operation, inputs, outputs.clone(), built_from);
+    tracing::info!(solids = outputs.len(), "ran a recorded operation");
+    Ok((record, outputs))
+}
+
+/// Run again every operation whose inputs changed, and every operation
+/// downstream of them, in order
+pub fn update_operations(
+    geometry: &mut GeometryRegistry,
+    provenance: &mut ProvenanceGraph,
+    tolerance: &Tolerance,
+) -> OperationUpdate {
+    let mut update = OperationUpdate::default();
+    let mut changed = Vec::new();
+    let ids: Vec<Uuid> = provenance.sorted().iter().map(|record| record.id).collect();
+    for id in ids {
+        let Some(record) = provenance.get(&id) else {
+            continue;
+        };
+        match input_points(geometry, &record.inputs) {
+            None => {
+                provenance.remove(&id);
+                update.forgotten.push(id);
+            }
+            Some(points) if !same_points(&points, &record.built_from, tolerance.linear) => {
+                changed.extend(record.inputs.iter().copied());
+            }
+            Some(_) => {}
+        }
+    }
+    rerun(geometry, provenance, &changed, tolerance, &mut update);
+    update
+}
+
+/// Run again every operation downstream of the changed primitives, in
+/// order, noting what changed in an update
+fn rerun(
+    geometry: &mut GeometryRegistry,
+    provenance: &mut ProvenanceGraph,
+    changed: &[Uuid],
+    tolerance: &Tolerance,
+    update: &mut OperationUpdate,
+) {
+    for id in provenance.invalidated(changed) {
+        let Some(mut record) = provenance.get(&id).cloned() else {
+            continue;
+        };
+        let Some(built_from) = input_points(geometry, &record.inputs) else {
+            continue;
+        };
+        record.error = None;
+        match run(
+            geometry,
+            &record.operation,
+            &record.inputs,
+            record.suppressed,
+            tolerance,
+        ) {
+            Ok(shells) => {
+                update.solids.extend(build_outputs(
+                    geometry,
+                    &mut record.outputs,
+                    &shells,
+                    tolerance,
+                ));
+            }
+            Err(OperationError::Generator) => update.stale_generators.push(id),
+            Err(error) => {
+                tracing::warn!(%error, "could not run {} again", record.operation.label());
+                record.error = Some(error.to_string());
+                update
+                    .solids
+                    .extend(build_outputs(geometry, &mut record.outputs, &[], tolerance));
+            }
+        }
+        record.built_from = built_from;
+        provenance.store(record);
+    }
+}
+
+/// Suppress a past operation or bring it back, running it and everything
+/// downstream of it again
+///
+/// # Errors
+/// Returns an error if the record is missing
+pub fn suppress_operation(
+    geometry: &mut GeometryRegistry,
+    provenance: &mut ProvenanceGraph,
+    id: &Uuid,
+    suppressed: bool,
+    tolerance: &Tolerance,
+) -> Result<OperationUpdate, OperationError> {
+    let record = provenance
+        .records
+        .get_mut(id)
+        .ok_or(OperationError::MissingRecord)?;
+    record.suppressed = suppressed;
+    let inputs = record.inputs.clone();
+    let mut update = OperationUpdate::default();
+    rerun(geometry, provenance, &inputs, tolerance, &mut update);
+    Ok(update)
+}
+
+/// Change the parameters of a past operation, running it and everything
+/// downstream of it again
+///
+/// # Errors
+/// Returns an error if the record is missing, is a generator's, or the new
+/// operation is of another kind; nothing is changed then
+pub fn edit_operation(
+    geometry: &mut GeometryRegistry,
+    provenance: &mut ProvenanceGraph,
+    id: &Uuid,
+    operation: Operation,
+    tolerance: &Tolerance,
+) -> Result<OperationUpdate, OperationError> {
+    let record = provenance
+        .records
+        .get_mut(id)
+        .ok_or(OperationError::MissingRecord)?;
+    if matches!(record.operation, Operation::Generator { .. }) {
+        return Err(OperationError::Generator);
+    }
+    if !record.operation.same_kind(&operation) {
+        return Err(OperationError::Kind);
+    }
+    record.operation = operation;
+    let inputs = record.inputs.clone();
+    let mut update = OperationUpdate::default();
+    rerun(geometry, provenance, &inputs, tolerance, &mut update);
+    Ok(update)
+}
+
+/// Move a past operation to a place in the history
+///
+/// # Errors
+/// Returns an error if the record is missing, or would come before an
+/// operation making its inputs or after one reading what it made
+pub fn move_operation(
+    provenance: &mut ProvenanceGraph,
+    id: &Uuid,
+    place: usize,
+) -> Result<(), OperationError> {
+    if provenance.get(id).is_none() {
+        return Err(OperationError::MissingRecord);
+    }
+    if provenance.move_record(id, place) {
+        Ok(())
+    } else {
+        Err(OperationError::Order)
+    }
+}
+
+/// Delete a past operation, with the operations built on what it made
+///
+/// The solids the deleted operations made are removed. Returns them, for
+/// their meshes to be dropped.
+///
+/// # Errors
+/// Returns an error if the record is missing
+pub fn delete_operation(
+    geometry: &mut GeometryRegistry,
+    provenance: &mut ProvenanceGraph,
+    id: &Uuid,
+) -> Result<Vec<Uuid>, OperationError> {
+    let record = provenance.get(id).ok_or(OperationError::MissingRecord)?;
+    let mut deleted = vec![*id];
+    deleted.extend(provenance.invalidated(&record.outputs));
+    let mut removed = Vec::new();
+    for record in deleted {
+        if let Some(record) = provenance.get(&record) {
+            for output in &record.outputs {
+                geometry.solids.remove(output);
+                removed.push(*output);
+            }
+        }
+        provenance.remove(&record);
+    }
+    tracing::info!(solids = removed.len(), "deleted a recorded operation");
+    Ok(removed)
+}
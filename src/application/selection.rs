@@ -1,6 +1,10 @@
+use std::collections::BTreeSet;
 use uuid::Uuid;
 
+use crate::domain::GeometryRegistry;
+
 /// The types of selection the user can make
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum SelectionType {
     /// A single vertex
     Vertex,
@@ -9,13 +13,49 @@ pub enum SelectionType {
     /// A single polygon
     Polygon,
     /// A single solid
+    #[default]
     Solid,
 }
 
 /// A selection of geometry
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Selection {
     /// The ID of the selected geometry
     pub id: Uuid,
     /// The type of selection
     pub selection_type: SelectionType,
 }
+
+impl Selection {
+    /// The IDs of the vertices the selection covers, sorted
+    /// Empty if the selected geometry is missing
+    #[must_use]
+    pub fn vertices(&self, registry: &GeometryRegistry) -> Vec<Uuid> {
+        match self.selection_type {
+            SelectionType::Vertex => registry
+                .vertices
+                .get(&self.id)
+                .map(|vertex| vec![vertex.id])
+                .unwrap_or_default(),
+            SelectionType::Segment => registry
+                .segments
+                .get(&self.id)
+                .map(|segment| {
+                    let vertices: BTreeSet<Uuid> = segment.vertices.into_iter().collect();
+                    vertices.into_iter().collect()
+                })
+                .unwrap_or_default(),
+            SelectionType::Polygon => {
+                let vertices: BTreeSet<Uuid> = registry
+                    .polygons
+                    .get(&self.id)
+                    .and_then(|polygon| polygon.vertex_loop(&registry.segments))
+                    .unwrap_or_default()
+                    .into_iter()
+                    .collect();
+                vertices.into_iter().collect()
+            }
+            SelectionType::Solid => registry.solid_vertices(&self.id),
+        }
+    }
+}
@@ -0,0 +1,231 @@
+/// Routing ducts, pipes and conduits and checking them for clashes
+///
+/// Routing a run builds it as elements of its service's kind, one per
+/// straight segment and fitting. A run starting or ending on another run
+/// of the same service branches off it: the branch is trimmed back to the
+/// other run's surface and that run is rebuilt with a tee collar where
+/// the branch joins.
+///
+/// Service clashes set the runs' elements against the building's and
+/// against other services, on bounding boxes like clashes with linked
+/// models. Runs do not clash with the runs they branch off, nor with
+/// spaces. Being ordinary solids, runs also take part in clash checks
+/// against linked models.
+use crate::domain::geometry::Bounds;
+use crate::domain::{
+    measure_vector, new_service_run, service_pieces, ElementKind, ElementRegistry,
+    GeometryRegistry, Point, ServiceKind, ServiceRegistry, ServiceRun, ServiceSection, Tee,
+    Tolerance, Vector, TEE_COLLAR,
+};
+use uuid::Uuid;
+
+/// Why a run could not be routed
+#[derive(Debug, Clone, PartialEq)]
+pub enum ServiceError {
+    /// The section has no size
+    Section,
+    /// The path or its fittings do not work out
+    Layout(String),
+}
+
+impl std::fmt::Display for ServiceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ServiceError::Section => write!(f, "The section has no size"),
+            ServiceError::Layout(message) => write!(f, "Cannot lay out the run: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for ServiceError {}
+
+/// A run routed, with the solids whose meshes are out of date
+#[derive(Debug, Clone)]
+pub struct RoutedService {
+    /// The new run
+    pub run: Uuid,
+    /// The solids removed and built, the new run's and those of the runs
+    /// it branches off
+    pub solids: Vec<Uuid>,
+}
+
+/// A service element clashing with another element
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ServiceClash {
+    /// The service element
+    pub service: Uuid,
+    /// The element it clashes with, a building element or another service
+    pub other: Uuid,
+}
+
+/// Replace a run's elements with new ones built from its current layout,
+/// returning the solids removed and built
+///
+/// # Errors
+/// Returns the layout's message if the run's pieces cannot be built, in
+/// which case nothing is changed
+fn build_run(
+    geometry: &mut GeometryRegistry,
+    elements: &mut ElementRegistry,
+    run: &mut ServiceRun,
+    tolerance: &Tolerance,
+) -> Result<Vec<Uuid>, String> {
+    let pieces = service_pieces(run, tolerance)?;
+    let mut solids = Vec::new();
+    for element in run.elements.drain(..) {
+        if let Some(solid) = elements.get(&element).map(|element| element.solid) {
+            geometry.solids.remove(&solid);
+            solids.push(solid);
+        }
+        elements.remove(&element);
+    }
+    for piece in pieces {
+        let solid = geometry.create_solid_from_loops(&piece.loops, tolerance);
+        let name = format!("{} {}", run.kind.label(), piece.kind.label());
+        run.elements
+            .push(elements.create_and_store(run.kind.element_kind(), &solid, &name));
+        solids.push(solid);
+    }
+    Ok(solids)
+}
+
+/// Branch a new run off any run of its service its end lies on, adding
+/// the tee to a copy of that run
+///
+/// `at_start` picks the end: the branch leaves its start along its first
+/// segment, or its end back along its last.
+fn branch_off(
+    services: &ServiceRegistry,
+    run: &mut ServiceRun,
+    at_start: bool,
+    tolerance: &Tolerance,
+) -> Option<ServiceRun> {
+    let count = run.path.len();
+    let (end, next) = if at_start {
+        (0, 1)
+    } else {
+        (count - 1, count - 2)
+    };
+    let direction: Vector = measure_vector(&run.path[end], &run.path[next]).normalized()?;
+    let (mut main, junction) = services
+        .sorted()
+        .into_iter()
+        .filter(|main| main.kind == run.kind)
+        .find_map(|main| {
+            let junction = main.junction(&run.path[end], &direction, tolerance)?;
+            Some((main.clone(), junction))
+        })?;
+    run.path[end] = junction.point;
+    if at_start {
+        run.start_trim = junction.trim;
+    } else {
+        run.end_trim = junction.trim;
+    }
+    main.tees.push(Tee {
+        branch: run.id,
+        segment: junction.segment,
+        along: junction.along,
+        length: run.section.size() / junction.across + 2.0 * TEE_COLLAR,
+    });
+    Some(main)
+}
+
+/// Route a duct, pipe or conduit along a centerline
+///
+/// # Errors
+/// Returns an error if the section has no size, or the path, its elbows
+/// or its tees do not fit; nothing is changed then
+pub fn route_service(
+    geometry: &mut GeometryRegistry,
+    elements: &mut ElementRegistry,
+    services: &mut ServiceRegistry,
+    kind: ServiceKind,
+    section: ServiceSection,
+    path: Vec<Point>,
+    tolerance: &Tolerance,
+) -> Result<RoutedService, ServiceError> {
+    let smallest = match section {
+        ServiceSection::Round { diameter } => diameter,
+        ServiceSection::Rectangular { width, height } => width.min(height),
+    };
+    if smallest <= tolerance.linear {
+        return Err(ServiceError::Section);
+    }
+    let mut run = new_service_run(kind, section, path);
+    if run.path.len() < 2 {
+        return Err(ServiceError::Layout(
+            "a run needs at least two points".to_string(),
+        ));
+    }
+    let mut mains: Vec<ServiceRun> = Vec::new();
+    for at_start in [true, false] {
+        if let Some(main) = branch_off(services, &mut run, at_start, tolerance) {
+            match mains.iter_mut().find(|other| other.id == main.id) {
+                Some(other) => other.tees.extend(main.tees.last().copied()),
+                None => mains.push(main),
+            }
+        }
+    }
+    for layout in mains.iter().chain([&run]) {
+        service_pieces(layout, tolerance).map_err(ServiceError::Layout)?;
+    }
+    let id = run.id;
+    let mut solids = Vec::new();
+    for mut layout in mains.into_iter().chain([run]) {
+        solids.extend(
+            build_run(geometry, elements, &mut layout, tolerance).map_err(ServiceError::Layout)?,
+        );
+        services.store(layout);
+    }
+    Ok(RoutedService { run: id, solids })
+}
+
+/// Service elements clashing with building elements or with other
+/// services, sorted by service element
+#[must_use]
+pub fn service_clashes(
+    geometry: &GeometryRegistry,
+    elements: &ElementRegistry,
+    services: &ServiceRegistry,
+    tolerance: f32,
+) -> Vec<ServiceClash> {
+    let bounds = |element: &Uuid| {
+        let solid = elements.get(element)?.solid;
+        Bounds::from_points(&geometry.solid_loops(&solid)?.concat())
+    };
+    let pieces: Vec<(Uuid, Uuid, Bounds)> = services
+        .sorted()
+        .into_iter()
+        .flat_map(|run| run.elements.iter().map(move |element| (run.id, *element)))
+        .filter_map(|(run, element)| Some((run, element, bounds(&element)?)))
+        .collect();
+    let others: Vec<(Uuid, Bounds)> = elements
+        .sorted()
+        .into_iter()
+        .filter(|element| element.kind != ElementKind::Space)
+        .filter(|element| !pieces.iter().any(|(_, piece, _)| *piece == element.id))
+        .filter_map(|element| Some((element.id, bounds(&element.id)?)))
+        .collect();
+    let mut clashes = Vec::new();
+    for (index, (run, service, piece_bounds)) in pieces.iter().enumerate() {
+        for (other, other_bounds) in &others {
+            if piece_bounds.overlaps(other_bounds, tolerance) {
+                clashes.push(ServiceClash {
+                    service: *service,
+                    other: *other,
+                });
+            }
+        }
+        for (other_run, other, other_bounds) in &pieces[index + 1..] {
+            if !services.connected(run, other_run) && piece_bounds.overlaps(other_bounds, tolerance)
+            {
+                clashes.push(ServiceClash {
+                    service: *service,
+                    other: *other,
+                });
+            }
+        }
+    }
+    clashes.sort_by_key(|clash| (clash.service, clash.other));
+    clashes
+}
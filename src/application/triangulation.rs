@@ -4,7 +4,8 @@ use std::collections::HashMap;
 use std::collections::HashSet;
 use uuid::Uuid;
 
-use crate::domain::{Polygon, Segment, Vertex};
+use crate::domain::geometry::fit_plane;
+use crate::domain::{sorted_by_id, Point, Polygon, Segment, Vertex};
 
 /// Triangulated face data for rendering
 #[derive(Debug)]
@@ -33,7 +34,7 @@ pub enum WindingOrder {
 /// 1. Building a vertex connectivity graph from segments
 /// 2. Finding the circular path through connected vertices
 /// 3. Calculating proper face normals
-/// 4. Determining correct winding order for outward-facing normals
+/// 4. Determining correct winding order from the polygon's stored outward normal
 /// 5. Creating triangles with consistent orientation
 pub(crate) fn triangulate_polygon_for_rendering(
     polygon: &Polygon,
@@ -116,51 +117,34 @@ pub(crate) fn triangulate_polygon_for_rendering(
         return vec![];
     }
 
-    // Step 3: Calculate centers and determine normal direction
-    // We need to know which way the face normal should point (outward from the solid)
-    // Calculate solid center (average of all solid vertices)
-    // This gives us a reference point to determine "outward" direction
-    let mut solid_center = Vec3::ZERO;
-    let mut solid_vertex_count = 0;
-    for vertex in vertices.values() {
-        solid_center += Vec3::new(vertex.position.x, vertex.position.y, vertex.position.z);
-        solid_vertex_count += 1;
-    }
-    solid_center /= solid_vertex_count as f32;
+    // Step 3: Determine which way the face normal should point (outward from the solid)
+    // The polygon's stored plane gives this directly; polygons without one
+    // fall back to the direction from the solid center to the polygon center
+    let outward = polygon.normal().map_or_else(
+        || centroid_direction(&ordered_vertices, vertices),
+        |normal| Vec3::new(normal.x, normal.y, normal.z),
+    );
 
-    // Calculate polygon center (average of polygon vertices)
-    let polygon_center =
-        ordered_vertices.iter().fold(Vec3::ZERO, |acc, v| acc + *v) / ordered_vertices.len() as f32;
-
-    // Vector from solid center to polygon center
-    // This tells us which side of the face is "outward"
-    let solid_to_polygon = polygon_center - solid_center;
-
-    // Step 4: Use solid-to-face vector to determine correct winding order
-    // The solid-to-face vector tells us which side is "outward"
-    // We need to ensure vertices are ordered so the face normal points outward
-    // Calculate the face normal using the first three vertices
+    // Step 4: Use the outward direction to determine correct winding order
+    // Calculate the face normal from the best-fit plane of all vertices
     // This gives us the "raw" normal direction based on vertex order
-    let v0 = ordered_vertices[0];
-    let v1 = ordered_vertices[1];
-    let v2 = ordered_vertices[2];
-    let edge1 = v1 - v0;
-    let edge2 = v2 - v0;
-    let face_normal = edge1.cross(edge2).normalize();
-
-    // Check if the face normal points in the same direction as solid-to-face vector
+    let Some(face_normal) = fitted_normal(&ordered_vertices) else {
+        return vec![];
+    };
+
+    // Check if the face normal points in the same direction as the outward direction
     // If dot product is positive, normal points outward (good)
     // If dot product is negative, normal points inward (need to flip winding)
-    let normal_dot_solid_to_face = face_normal.dot(solid_to_polygon);
+    let normal_dot_outward = face_normal.dot(outward);
 
-    let winding_order = if normal_dot_solid_to_face > 0.0 {
+    let winding_order = if normal_dot_outward > 0.0 {
         WindingOrder::CounterClockwise
     } else {
         WindingOrder::Clockwise
     };
 
     // Use the corrected normal (always pointing outward)
-    let normal = if normal_dot_solid_to_face > 0.0 {
+    let normal = if normal_dot_outward > 0.0 {
         face_normal
     } else {
         -face_normal
@@ -199,3 +183,36 @@ pub(crate) fn triangulate_polygon_for_rendering(
 
     triangulated_faces
 }
+
+/// Direction from the center of all vertices to the polygon center
+///
+/// A heuristic for the outward side of polygons with no stored plane; it
+/// misfires on concave solids, where a face can lie behind the center.
+fn centroid_direction(polygon_vertices: &[Vec3], vertices: &HashMap<Uuid, Vertex>) -> Vec3 {
+    let mut solid_center = Vec3::ZERO;
+    let mut solid_vertex_count = 0;
+    for vertex in sorted_by_id(vertices) {
+        solid_center += Vec3::new(vertex.position.x, vertex.position.y, vertex.position.z);
+        solid_vertex_count += 1;
+    }
+    solid_center /= solid_vertex_count as f32;
+
+    let polygon_center =
+        polygon_vertices.iter().fold(Vec3::ZERO, |acc, v| acc + *v) / polygon_vertices.len() as f32;
+    polygon_center - solid_center
+}
+
+/// Unit normal of the best-fit plane through the vertices, following their order
+/// Returns None if the vertices are collinear or fewer than three
+fn fitted_normal(vertices: &[Vec3]) -> Option<Vec3> {
+    let points: Vec<Point> = vertices
+        .iter()
+        .map(|v| Point {
+            x: v.x,
+            y: v.y,
+            z: v.z,
+        })
+        .collect();
+    let (normal, _, _) = fit_plane(&points)?;
+    Some(Vec3::new(normal.x, normal.y, normal.z))
+}
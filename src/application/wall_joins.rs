@@ -0,0 +1,444 @@
+/// Wall joins
+///
+/// Walls drawn to meet are separate solids, which overlap or leave a notch
+/// where they meet. This pass finds walls whose center lines meet near
+/// their ends and trims or extends those ends so the walls meet cleanly:
+/// two walls ending at a corner are mitered along the line bisecting it,
+/// and a wall ending against the side of another is butted against that
+/// side. Each join also generates the constraints that keep it closed
+/// when either wall is edited later: mitered corners coincide, and a
+/// butted end stays in the plane of the face it meets.
+///
+/// Walls are read from their solids. The two long edges of the outline of
+/// the bottom give a wall's side lines, which trimming and extending
+/// leave in place, so walls joined before are found again and joining
+/// them twice changes nothing.
+use crate::application::commands::move_vertex;
+use crate::domain::geometry::convex_hull;
+use crate::domain::solver::{Constraint, ConstraintKind, ConstraintReference};
+use crate::domain::{ElementKind, ElementRegistry, GeometryRegistry, Point, Tolerance};
+use std::collections::BTreeSet;
+use uuid::Uuid;
+
+/// Smallest angle in degrees between two walls that join; walls nearer
+/// parallel are left alone
+const MIN_JOIN_ANGLE_DEGREES: f32 = 5.0;
+
+/// How two walls join
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WallJoinKind {
+    /// Both walls end at the corner, cut along the line bisecting it
+    Miter,
+    /// The first wall ends against the side of the second, which runs on
+    Butt,
+}
+
+/// A join between two walls
+#[derive(Debug, Clone, PartialEq)]
+pub struct WallJoin {
+    /// How the walls join
+    pub kind: WallJoinKind,
+    /// The wall elements; for a butt joint, the one that ends first
+    pub walls: [Uuid; 2],
+}
+
+/// What joining walls did
+#[derive(Debug, Clone, Default)]
+pub struct WallJoins {
+    /// The joins found
+    pub joins: Vec<WallJoin>,
+    /// The solids whose shape changed, sorted by ID
+    pub solids: Vec<Uuid>,
+    /// Constraints keeping the joins closed under later edits
+    pub constraints: Vec<Constraint>,
+}
+
+/// A wall in plan: its side lines and where it ends
+struct WallPlan {
+    /// The wall element
+    element: Uuid,
+    /// Unit direction from the wall's start to its end, as X and Z
+    direction: [f32; 2],
+    /// Unit normal to the direction, as X and Z
+    normal: [f32; 2],
+    /// Offset of each side line along the normal
+    sides: [f32; 2],
+    /// Position of the start and the end along the direction
+    ends: [f32; 2],
+    /// The vertices at each end on each side, bottom and top
+    corners: [[Vec<Uuid>; 2]; 2],
+}
+
+impl WallPlan {
+    /// Offset of the center line along the normal
+    fn center(&self) -> f32 {
+        f32::midpoint(self.sides[0], self.sides[1])
+    }
+
+    /// Distance between the side lines
+    fn thickness(&self) -> f32 {
+        (self.sides[0] - self.sides[1]).abs()
+    }
+
+    /// The point on the center line at a position along the direction
+    fn axis_point(&self, along: f32) -> [f32; 2] {
+        let center = self.center();
+        [
+            self.direction[0] * along + self.normal[0] * center,
+            self.direction[1] * along + self.normal[1] * center,
+        ]
+    }
+
+    /// The end nearest a position along the direction, if within reach
+    fn end_near(&self, along: f32, reach: f32) -> Option<usize> {
+        let end = usize::from((along - self.ends[1]).abs() < (along - self.ends[0]).abs());
+        ((along - self.ends[end]).abs() <= reach).then_some(end)
+    }
+
+    /// Whether a position along the direction lies between the ends
+    fn spans(&self, along: f32) -> bool {
+        along > self.ends[0].min(self.ends[1]) && along < self.ends[0].max(self.ends[1])
+    }
+}
+
+/// Join walls to the other walls they meet
+///
+/// Every wall in `walls` is joined to each other wall whose center line
+/// meets its own near an end. Walls whose solids are not straight prisms
+/// on a four-sided base are skipped.
+pub fn join_walls(
+    geometry_registry: &mut GeometryRegistry,
+    element_registry: &ElementRegistry,
+    walls: &[Uuid],
+    tolerance: &Tolerance,
+) -> WallJoins {
+    let all: Vec<Uuid> = element_registry
+        .sorted()
+        .into_iter()
+        .filter(|element| element.kind == ElementKind::Wall)
+        .map(|element| element.id)
+        .collect();
+    let mut joins = WallJoins::default();
+    let mut solids = BTreeSet::new();
+    let mut joining: Vec<Uuid> = walls.to_vec();
+    joining.sort();
+    for wall in &joining {
+        for other in &all {
+            // Pairs of walls both being joined are taken once
+            if other == wall || (joining.contains(other) && other < wall) {
+                continue;
+            }
+            // Plans are read again for each pair, as joins move wall ends
+            let (Some(first), Some(second)) = (
+                wall_plan(geometry_registry, element_registry, wall, tolerance),
+                wall_plan(geometry_registry, element_registry, other, tolerance),
+            ) else {
+                continue;
+            };
+            if let Some(join) = join_pair(geometry_registry, &first, &second, tolerance) {
+                solids.extend(join.solids);
+                joins.constraints.extend(join.constraints);
+                joins.joins.push(join.join);
+            }
+        }
+    }
+    joins.solids = solids.into_iter().collect();
+    joins
+}
+
+/// A join made between two walls
+struct PairJoin {
+    /// The join
+    join: WallJoin,
+    /// The solids whose shape changed
+    solids: Vec<Uuid>,
+    /// Constraints keeping it closed
+    constraints: Vec<Constraint>,
+}
+
+/// Join two walls if their center lines meet near an end of either
+fn join_pair(
+    geometry_registry: &mut GeometryRegistry,
+    first: &WallPlan,
+    second: &WallPlan,
+    tolerance: &Tolerance,
+) -> Option<PairJoin> {
+    let sine = first.direction[0] * second.direction[1] - first.direction[1] * second.direction[0];
+    if sine.abs() < MIN_JOIN_ANGLE_DEGREES.to_radians().sin() {
+        return None;
+    }
+    let meeting = meet(first.normal, first.center(), second.normal, second.center())?;
+    let along = |plan: &WallPlan| dot(plan.direction, meeting);
+    let reach = first.thickness().max(second.thickness()) + tolerance.linear;
+    match (
+        first.end_near(along(first), reach),
+        second.end_near(along(second), reach),
+    ) {
+        (Some(first_end), Some(second_end)) => miter(
+            geometry_registry,
+            [(first, first_end), (second, second_end)],
+            meeting,
+            tolerance,
+        ),
+        (Some(end), None) if second.spans(along(second)) => {
+            butt(geometry_registry, first, end, second, meeting, tolerance)
+        }
+        (None, Some(end)) if first.spans(along(first)) => {
+            butt(geometry_registry, second, end, first, meeting, tolerance)
+        }
+        _ => None,
+    }
+}
+
+/// Miter the meeting ends of two walls
+///
+/// Each wall's outer side, the one facing away from the other wall, is
+/// carried to where it meets the other's outer side, and likewise the
+/// inner sides, so both ends lie on the line between those corners.
+fn miter(
+    geometry_registry: &mut GeometryRegistry,
+    walls: [(&WallPlan, usize); 2],
+    meeting: [f32; 2],
+    tolerance: &Tolerance,
+) -> Option<PairJoin> {
+    // The side of each wall facing away from the body of the other
+    let outer = |(plan, _): (&WallPlan, usize), (other, other_end): (&WallPlan, usize)| {
+        let far = other.axis_point(other.ends[1 - other_end]);
+        let toward = dot(plan.normal, [far[0] - meeting[0], far[1] - meeting[1]]);
+        usize::from((plan.sides[1] - plan.center()) * toward < 0.0)
+    };
+    let outer_sides = [outer(walls[0], walls[1]), outer(walls[1], walls[0])];
+    let corner = |outer: bool| {
+        let [(first, _), (second, _)] = walls;
+        let side = |index: usize| {
+            if outer {
+                outer_sides[index]
+            } else {
+                1 - outer_sides[index]
+            }
+        };
+        meet(
+            first.normal,
+            first.sides[side(0)],
+            second.normal,
+            second.sides[side(1)],
+        )
+    };
+    let (outer_corner, inner_corner) = (corner(true)?, corner(false)?);
+
+    let mut solids = Vec::new();
+    let mut constraints = Vec::new();
+    for (target, sides) in [
+        (outer_corner, outer_sides),
+        (inner_corner, outer_sides.map(|side| 1 - side)),
+    ] {
+        let [(first, first_end), (second, second_end)] = walls;
+        let first_vertices = &first.corners[first_end][sides[0]];
+        let second_vertices = &second.corners[second_end][sides[1]];
+        for vertex in first_vertices.iter().chain(second_vertices) {
+            solids.extend(move_in_plan(geometry_registry, vertex, target, tolerance));
+        }
+        // Vertices of the two walls at the same height coincide
+        for vertex in first_vertices {
+            let height = |id: &Uuid| {
+                geometry_registry
+                    .vertices
+                    .get(id)
+                    .map_or(f32::MAX, |vertex| vertex.position.y)
+            };
+            let matching = second_vertices
+                .iter()
+                .find(|other| (height(other) - height(vertex)).abs() <= tolerance.linear);
+            if let Some(other) = matching {
+                constraints.push(Constraint {
+                    kind: ConstraintKind::Coincident,
+                    targets: vec![*vertex, *other],
+                    reference: None,
+                });
+            }
+        }
+    }
+    Some(PairJoin {
+        join: WallJoin {
+            kind: WallJoinKind::Miter,
+            walls: [walls[0].0.element, walls[1].0.element],
+        },
+        solids,
+        constraints,
+    })
+}
+
+/// Butt the end of one wall against the side of another
+///
+/// The ending wall's side lines are carried to the face of the other
+/// wall that looks toward it.
+fn butt(
+    geometry_registry: &mut GeometryRegistry,
+    ending: &WallPlan,
+    end: usize,
+    running: &WallPlan,
+    meeting: [f32; 2],
+    tolerance: &Tolerance,
+) -> Option<PairJoin> {
+    let far = ending.axis_point(ending.ends[1 - end]);
+    let toward = dot(running.normal, [far[0] - meeting[0], far[1] - meeting[1]]);
+    let face = usize::from((running.sides[1] - running.center()) * toward > 0.0);
+
+    let mut solids = Vec::new();
+    for side in 0..2 {
+        let target = meet(
+            ending.normal,
+            ending.sides[side],
+            running.normal,
+            running.sides[face],
+        )?;
+        for vertex in &ending.corners[end][side] {
+            solids.extend(move_in_plan(geometry_registry, vertex, target, tolerance));
+        }
+    }
+    // The butted end stays in the plane of the face it meets
+    let targets: Vec<Uuid> = ending.corners[end]
+        .iter()
+        .chain(running.corners.iter().map(|corners| &corners[face]))
+        .flatten()
+        .copied()
+        .collect();
+    Some(PairJoin {
+        join: WallJoin {
+            kind: WallJoinKind::Butt,
+            walls: [ending.element, running.element],
+        },
+        solids,
+        constraints: vec![Constraint {
+            kind: ConstraintKind::Coplanar,
+            targets,
+            reference: Some(ConstraintReference::SelfDefined),
+        }],
+    })
+}
+
+/// Read a wall's plan from its solid
+///
+/// The wall's base is the outline of its bottom vertices, so a door
+/// opening splitting its bottom face still reads as one wall. Returns None
+/// if the element is missing or that outline is not four-sided with two
+/// sides thicker apart than the tolerance.
+fn wall_plan(
+    geometry_registry: &GeometryRegistry,
+    element_registry: &ElementRegistry,
+    wall: &Uuid,
+    tolerance: &Tolerance,
+) -> Option<WallPlan> {
+    let solid = element_registry.get(wall)?.solid;
+    let vertices: Vec<(Uuid, Point)> = geometry_registry
+        .solid_vertices(&solid)
+        .into_iter()
+        .filter_map(|id| Some((id, geometry_registry.vertices.get(&id)?.position.clone())))
+        .collect();
+    let bottom = vertices
+        .iter()
+        .map(|(_, point)| point.y)
+        .fold(f32::MAX, f32::min);
+    let base: Vec<[f32; 2]> = vertices
+        .iter()
+        .filter(|(_, point)| (point.y - bottom).abs() <= tolerance.linear)
+        .map(|(_, point)| [point.x, point.z])
+        .collect();
+    let plan = convex_hull(&base, tolerance.linear);
+    if plan.len() != 4 {
+        return None;
+    }
+
+    // The sides are the longer pair of opposite edges, run the same way
+    let length = |a: [f32; 2], b: [f32; 2]| (b[0] - a[0]).hypot(b[1] - a[1]);
+    let [a, b, c, d] = [plan[0], plan[1], plan[2], plan[3]];
+    let ((start, end), (other_start, other_end)) =
+        if length(a, b) + length(d, c) >= length(b, c) + length(a, d) {
+            ((a, b), (d, c))
+        } else {
+            ((b, c), (a, d))
+        };
+    let run = length(start, end);
+    if run <= tolerance.linear {
+        return None;
+    }
+    let direction = [(end[0] - start[0]) / run, (end[1] - start[1]) / run];
+    let normal = [-direction[1], direction[0]];
+    let sides = [dot(normal, start), dot(normal, other_start)];
+    if (sides[0] - sides[1]).abs() <= tolerance.linear {
+        return None;
+    }
+    let ends = [
+        f32::midpoint(dot(direction, start), dot(direction, other_start)),
+        f32::midpoint(dot(direction, end), dot(direction, other_end)),
+    ];
+
+    // Vertices between the ends, such as those of openings, are not corners
+    let side_ends = [
+        [dot(direction, start), dot(direction, end)],
+        [dot(direction, other_start), dot(direction, other_end)],
+    ];
+    let mut corners: [[Vec<Uuid>; 2]; 2] = Default::default();
+    for (id, point) in vertices {
+        let point = [point.x, point.z];
+        let (along, across) = (dot(direction, point), dot(normal, point));
+        let end = usize::from((along - ends[1]).abs() < (along - ends[0]).abs());
+        let side = usize::from((across - sides[1]).abs() < (across - sides[0]).abs());
+        if (along - side_ends[side][end]).abs() <= tolerance.linear {
+            corners[end][side].push(id);
+        }
+    }
+    Some(WallPlan {
+        element: *wall,
+        direction,
+        normal,
+        sides,
+        ends,
+        corners,
+    })
+}
+
+/// Move a vertex to a point in plan, keeping its height, and return the
+/// solids whose shape changed; nothing moves if it is there already
+fn move_in_plan(
+    geometry_registry: &mut GeometryRegistry,
+    vertex: &Uuid,
+    target: [f32; 2],
+    tolerance: &Tolerance,
+) -> Vec<Uuid> {
+    let Some(current) = geometry_registry.vertices.get(vertex) else {
+        return Vec::new();
+    };
+    let position = Point {
+        x: target[0],
+        y: current.position.y,
+        z: target[1],
+    };
+    if (position.x - current.position.x).hypot(position.z - current.position.z) <= tolerance.linear
+    {
+        return Vec::new();
+    }
+    move_vertex(geometry_registry, vertex, &position).unwrap_or_default()
+}
+
+/// Where the lines of points at given offsets along two normals cross
+fn meet(
+    first_normal: [f32; 2],
+    first_offset: f32,
+    second_normal: [f32; 2],
+    second_offset: f32,
+) -> Option<[f32; 2]> {
+    let determinant = first_normal[0] * second_normal[1] - first_normal[1] * second_normal[0];
+    if determinant.abs() <= f32::EPSILON {
+        return None;
+    }
+    Some([
+        (first_offset * second_normal[1] - second_offset * first_normal[1]) / determinant,
+        (first_normal[0] * second_offset - second_normal[0] * first_offset) / determinant,
+    ])
+}
+
+/// The dot product of two plan vectors
+fn dot(a: [f32; 2], b: [f32; 2]) -> f32 {
+    a[0] * b[0] + a[1] * b[1]
+}
@@ -0,0 +1,223 @@
+/// Building massing from high-level parameters
+///
+/// A footprint loop is stacked into storeys. The envelope tier holds one
+/// storey volume per floor and acts as the boundary of a structure tier
+/// holding the floor slabs and a vertical core. The study's IDs are drawn
+/// from its seed, so generating it again gives the same model.
+use crate::domain::geometry::{centroid, prism_loops, rectangle_loop};
+use crate::domain::solver::{Constraint, ConstraintKind, ConstraintSet};
+use crate::domain::{with_deterministic_ids, GeometryRegistry, Point, TierRegistry};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// Parameters of a massing study
+#[derive(Debug, Clone)]
+pub struct MassingSettings {
+    /// Horizontal footprint loop at ground level
+    pub footprint: Vec<Point>,
+    /// Number of storeys
+    pub floors: usize,
+    /// Floor-to-floor height
+    pub floor_height: f32,
+    /// Thickness of each floor slab
+    pub slab_thickness: f32,
+    /// Core size along X, no core if zero
+    pub core_width: f32,
+    /// Core size along Z, no core if zero
+    pub core_depth: f32,
+    /// Seed the study's IDs are drawn from
+    pub seed: u64,
+}
+
+/// What a massing study generated
+#[derive(Debug, Clone)]
+pub struct Massing {
+    /// The tier holding the storey volumes
+    pub envelope_tier: Uuid,
+    /// The tier holding the slabs and core, bounded by the envelope
+    pub structure_tier: Uuid,
+    /// One storey volume per floor, from the ground up
+    pub envelope: Vec<Uuid>,
+    /// One slab per floor plus the roof, from the ground up
+    pub slabs: Vec<Uuid>,
+    /// The core running the full height, if one was requested
+    pub core: Option<Uuid>,
+    /// The constraints of each tier
+    pub constraints: HashMap<Uuid, ConstraintSet>,
+}
+
+/// A copy of a loop raised by `height` along +Y
+fn raised(points: &[Point], height: f32) -> Vec<Point> {
+    points
+        .iter()
+        .map(|point| Point {
+            x: point.x,
+            y: point.y + height,
+            z: point.z,
+        })
+        .collect()
+}
+
+/// Level constraints on a prism's bottom and top faces
+fn level_constraints(geometry_registry: &GeometryRegistry, solid_id: &Uuid) -> Vec<Constraint> {
+    let Some(solid) = geometry_registry.solids.get(solid_id) else {
+        return Vec::new();
+    };
+    solid
+        .polygons
+        .iter()
+        .take(2)
+        .map(|polygon| Constraint {
+            kind: ConstraintKind::Level,
+            targets: vec![*polygon],
+            reference: None,
+        })
+        .collect()
+}
+
+/// Generate a massing study into the registries
+///
+/// Slabs sit at the bottom of each storey, with a roof slab at the top of
+/// the last one. The core is centred on the footprint's centroid. Every
+/// slab and storey face that should stay horizontal gets a level
+/// constraint, and the structure's solids get a boundary constraint
+/// against the envelope.
+/// Every ID is drawn from the seed, so two studies with one seed should
+/// not share a registry.
+/// Returns None if the footprint has fewer than three points or there are
+/// no floors.
+#[tracing::instrument(skip_all, fields(floors = settings.floors))]
+pub fn generate_massing(
+    geometry_registry: &mut GeometryRegistry,
+    tier_registry: &mut TierRegistry,
+    parent: Option<Uuid>,
+    settings: &MassingSettings,
+) -> Option<Massing> {
+    if settings.footprint.len() < 3 || settings.floors == 0 {
+        return None;
+    }
+    Some(with_deterministic_ids(settings.seed, || {
+        build_massing(geometry_registry, tier_registry, parent, settings)
+    }))
+}
+
+/// Stack the storeys, slabs and core of a massing study
+#[allow(clippy::cast_precision_loss)]
+fn build_massing(
+    geometry_registry: &mut GeometryRegistry,
+    tier_registry: &mut TierRegistry,
+    parent: Option<Uuid>,
+    settings: &MassingSettings,
+) -> Massing {
+    let envelope_tier = tier_registry.create_and_store("Massing envelope", parent, None);
+    let structure_tier =
+        tier_registry.create_and_store("Massing structure", Some(envelope_tier), None);
+    let tolerance = tier_registry.tolerance(&structure_tier);
+    let total_height = settings.floors as f32 * settings.floor_height;
+
+    let mut envelope_constraints = ConstraintSet::default();
+    let mut envelope = Vec::with_capacity(settings.floors);
+    for floor in 0..settings.floors {
+        let base = raised(&settings.footprint, floor as f32 * settings.floor_height);
+        let solid = geometry_registry
+            .create_solid_from_loops(&prism_loops(&base, settings.floor_height), &tolerance);
+        envelope_constraints
+            .explicit
+            .extend(level_constraints(geometry_registry, &solid));
+        envelope.push(solid);
+    }
+
+    let mut structure_constraints = ConstraintSet::default();
+    let mut slabs = Vec::with_capacity(settings.floors + 1);
+    for floor in 0..=settings.floors {
+        let level =
+            (floor as f32 * settings.floor_height).min(total_height - settings.slab_thickness);
+        let base = raised(&settings.footprint, level);
+        let solid = geometry_registry
+            .create_solid_from_loops(&prism_loops(&base, settings.slab_thickness), &tolerance);
+        structure_constraints
+            .explicit
+            .extend(level_constraints(geometry_registry, &solid));
+        slabs.push(solid);
+    }
+
+    let core = (settings.core_width > 0.0 && settings.core_depth > 0.0)
+        .then(|| centroid(&settings.footprint))
+        .flatten()
+        .map(|center| {
+            let base = rectangle_loop(
+                center.x - settings.core_width / 2.0,
+                center.z - settings.core_depth / 2.0,
+                center.x + settings.core_width / 2.0,
+                center.z + settings.core_depth / 2.0,
+                center.y,
+            );
+            geometry_registry.create_solid_from_loops(&prism_loops(&base, total_height), &tolerance)
+        });
+
+    let structure: Vec<Uuid> = slabs.iter().chain(core.iter()).copied().collect();
+    structure_constraints.explicit.push(Constraint {
+        kind: ConstraintKind::Boundary,
+        targets: structure.clone(),
+        reference: None,
+    });
+
+    if let Some(tier) = tier_registry.get_mut(&envelope_tier) {
+        tier.geometry.extend(&envelope);
+    }
+    if let Some(tier) = tier_registry.get_mut(&structure_tier) {
+        tier.geometry.extend(structure);
+    }
+
+    let mut constraints = HashMap::new();
+    constraints.insert(envelope_tier, envelope_constraints);
+    constraints.insert(structure_tier, structure_constraints);
+    tracing::info!(
+        storeys = envelope.len(),
+        slabs = slabs.len(),
+        core = core.is_some(),
+        "generated massing"
+    );
+    Massing {
+        envelope_tier,
+        structure_tier,
+        envelope,
+        slabs,
+        core,
+        constraints,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn massing(seed: u64) -> Massing {
+        let settings = MassingSettings {
+            footprint: rectangle_loop(0.0, 0.0, 20.0, 12.0, 0.0),
+            floors: 3,
+            floor_height: 3.5,
+            slab_thickness: 0.3,
+            core_width: 4.0,
+            core_depth: 4.0,
+            seed,
+        };
+        generate_massing(
+            &mut GeometryRegistry::create_new(),
+            &mut TierRegistry::create_new(),
+            None,
+            &settings,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn one_seed_generates_the_same_ids() {
+        let (first, again, other) = (massing(9), massing(9), massing(10));
+        assert_eq!(first.envelope_tier, again.envelope_tier);
+        assert_eq!(first.envelope, again.envelope);
+        assert_eq!(first.slabs, again.slabs);
+        assert_eq!(first.core, again.core);
+        assert_ne!(first.envelope, other.envelope);
+    }
+}
@@ -3,6 +3,13 @@ use crate::application::create_cube_solid;
 use crate::domain::*;
 use uuid::Uuid;
 
+/// Building massing from high-level parameters
+pub mod massing;
+/// Slicing masses into stories by level datums
+pub mod stories;
+/// Procedural stress test scenes
+pub mod stress;
+
 /// Create a sample scene with a cube
 pub fn create_sample_scene(geometry_registry: &mut GeometryRegistry) -> Uuid {
     let solid_id = create_cube_solid(1.0, geometry_registry);
@@ -0,0 +1,166 @@
+/// Slicing masses into stories by level datums
+///
+/// A mass is cut by the horizontal plane of every level passing through
+/// it. Each piece becomes a solid of its own and joins the tier of the
+/// story it stands in, which is made under the mass's tier the first time
+/// a level needs one. Pieces below the lowest level stay in the mass's
+/// tier.
+///
+/// A story's gross floor area is the area of its pieces' horizontal faces
+/// looking down: the floor plates they stand on, overhangs included.
+use crate::domain::geometry::{
+    connected_shells, newell_normal, polygon_area, signed_volume, split_loops_by_plane, Bounds,
+    Plane,
+};
+use crate::domain::{GeometryRegistry, LevelRegistry, Point, TierRegistry, Tolerance, Vector};
+use uuid::Uuid;
+
+/// A piece of a mass in one story
+#[derive(Debug, Clone, PartialEq)]
+pub struct StorySlice {
+    /// The level starting the story, None below the lowest level
+    pub level: Option<Uuid>,
+    /// The piece's solid
+    pub solid: Uuid,
+    /// The area of the floor plates it stands on
+    pub gross_area: f32,
+}
+
+/// What slicing a mass into stories made
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stories {
+    /// The pieces from the lowest up
+    pub slices: Vec<StorySlice>,
+    /// Gross floor area of each story the mass reaches, as its level and
+    /// the area, from the lowest up
+    pub gross_areas: Vec<(Uuid, f32)>,
+}
+
+/// The area of a shell's horizontal faces looking down
+fn floor_plate_area(shell: &[Vec<Point>], tolerance: &Tolerance) -> f32 {
+    shell
+        .iter()
+        .filter(|face| {
+            newell_normal(face)
+                .normalized()
+                .is_some_and(|normal| -normal.y >= tolerance.angular.cos())
+        })
+        .map(|face| polygon_area(face))
+        .sum()
+}
+
+/// Slice a massing solid into one solid per story it reaches
+///
+/// The mass is taken out of the geometry and its tier and replaced by the
+/// pieces. Returns None if the solid is missing or there are no levels.
+#[tracing::instrument(skip_all)]
+pub fn slice_into_stories(
+    geometry_registry: &mut GeometryRegistry,
+    tier_registry: &mut TierRegistry,
+    level_registry: &mut LevelRegistry,
+    solid_id: &Uuid,
+    tolerance: &Tolerance,
+) -> Option<Stories> {
+    if level_registry.levels.is_empty() {
+        return None;
+    }
+    let mut loops = geometry_registry.solid_loops(solid_id)?;
+    if signed_volume(&loops) < 0.0 {
+        for face in &mut loops {
+            face.reverse();
+        }
+    }
+    let bounds = Bounds::from_points(&loops.concat())?;
+    let mut shells = Vec::new();
+    for level in level_registry.sorted() {
+        if level.elevation <= bounds.min.y + tolerance.linear
+            || level.elevation >= bounds.max.y - tolerance.linear
+        {
+            continue;
+        }
+        let plane = Plane {
+            point: Point {
+                x: 0.0,
+                y: level.elevation,
+                z: 0.0,
+            },
+            normal: Vector {
+                x: 0.0,
+                y: 1.0,
+                z: 0.0,
+            },
+        };
+        let (below, above) = split_loops_by_plane(&loops, &plane, tolerance.linear);
+        shells.extend(connected_shells(&below, tolerance.linear));
+        loops = above;
+    }
+    shells.extend(connected_shells(&loops, tolerance.linear));
+
+    let phase = geometry_registry
+        .solids
+        .get(solid_id)
+        .map(|mass| mass.phase);
+    let mass_tier = tier_registry.tier_of(solid_id);
+    geometry_registry.solids.remove(solid_id);
+    if let Some(tier) = mass_tier.and_then(|tier| tier_registry.get_mut(&tier)) {
+        tier.geometry.retain(|geometry| geometry != solid_id);
+    }
+
+    let mut slices = Vec::with_capacity(shells.len());
+    for shell in shells {
+        let bottom = shell
+            .iter()
+            .flatten()
+            .map(|point| point.y)
+            .fold(f32::MAX, f32::min);
+        let level = level_registry
+            .story_at(bottom, tolerance.linear)
+            .map(|level| level.id);
+        let solid = geometry_registry.create_solid_from_loops(&shell, tolerance);
+        if let (Some(phase), Some(made)) = (phase, geometry_registry.solids.get_mut(&solid)) {
+            made.phase = phase;
+        }
+        let tier = match level.and_then(|level| level_registry.get_mut(&level)) {
+            Some(level) => {
+                let existing = level.tier.filter(|tier| tier_registry.get(tier).is_some());
+                let tier = existing.unwrap_or_else(|| {
+                    tier_registry.create_and_store(
+                        &format!("Story: {}", level.name),
+                        mass_tier,
+                        None,
+                    )
+                });
+                level.tier = Some(tier);
+                Some(tier)
+            }
+            None => mass_tier,
+        };
+        if let Some(tier) = tier.and_then(|tier| tier_registry.get_mut(&tier)) {
+            tier.geometry.push(solid);
+        }
+        slices.push(StorySlice {
+            level,
+            solid,
+            gross_area: floor_plate_area(&shell, tolerance),
+        });
+    }
+
+    let gross_areas = level_registry
+        .sorted()
+        .into_iter()
+        .filter(|level| slices.iter().any(|slice| slice.level == Some(level.id)))
+        .map(|level| {
+            let area = slices
+                .iter()
+                .filter(|slice| slice.level == Some(level.id))
+                .map(|slice| slice.gross_area)
+                .sum();
+            (level.id, area)
+        })
+        .collect();
+    tracing::info!(slices = slices.len(), "sliced mass into stories");
+    Some(Stories {
+        slices,
+        gross_areas,
+    })
+}
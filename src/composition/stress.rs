@@ -0,0 +1,205 @@
+/// Procedural stress test scenes
+///
+/// Lays out a grid of buildings, each a stack of floor boxes in its own
+/// tier under a site tier, with constraints tying the floors together.
+/// The same settings always produce the same scene, down to its IDs,
+/// which are drawn from the seed, so benchmark and profiling runs can be
+/// compared.
+use crate::domain::geometry::{prism_loops, rectangle_loop};
+use crate::domain::solver::{Constraint, ConstraintKind, ConstraintReference, ConstraintSet};
+use crate::domain::{splitmix64, with_deterministic_ids, GeometryRegistry, TierRegistry};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// Size and shape of a generated stress scene
+#[derive(Debug, Clone)]
+pub struct StressSceneSettings {
+    /// Buildings along X
+    pub columns: usize,
+    /// Buildings along Z
+    pub rows: usize,
+    /// Most floors in one building; each building gets between one and this many
+    pub max_floors: usize,
+    /// Side length of a building's square footprint
+    pub building_width: f32,
+    /// Gap between neighbouring buildings
+    pub street_width: f32,
+    /// Height of each floor box
+    pub floor_height: f32,
+    /// Seed for the floor counts and the scene's IDs
+    pub seed: u64,
+}
+
+impl Default for StressSceneSettings {
+    fn default() -> Self {
+        Self {
+            columns: 20,
+            rows: 20,
+            max_floors: 10,
+            building_width: 12.0,
+            street_width: 8.0,
+            floor_height: 3.5,
+            seed: 1,
+        }
+    }
+}
+
+/// What a stress scene generated
+#[derive(Debug, Clone)]
+pub struct StressScene {
+    /// The tier holding every building
+    pub site_tier: Uuid,
+    /// One tier per building, in grid order
+    pub building_tiers: Vec<Uuid>,
+    /// Every floor solid, building by building from the ground up
+    pub solids: Vec<Uuid>,
+    /// The constraints of each building tier
+    pub constraints: HashMap<Uuid, ConstraintSet>,
+}
+
+impl StressScene {
+    /// The number of explicit constraints across all buildings
+    #[must_use]
+    pub fn constraint_count(&self) -> usize {
+        self.constraints
+            .values()
+            .map(|set| set.explicit.len())
+            .sum()
+    }
+}
+
+/// Generate a grid of buildings into the registries
+///
+/// Each floor's bottom face is made equilateral, and each floor's top face
+/// is made coplanar with the bottom face of the floor above it. Every ID
+/// is drawn from the seed, so two scenes with one seed should not share a
+/// registry.
+#[tracing::instrument(skip(geometry_registry, tier_registry))]
+pub fn generate_stress_scene(
+    geometry_registry: &mut GeometryRegistry,
+    tier_registry: &mut TierRegistry,
+    settings: &StressSceneSettings,
+) -> StressScene {
+    with_deterministic_ids(settings.seed, || {
+        build_stress_scene(geometry_registry, tier_registry, settings)
+    })
+}
+
+/// Lay out the buildings of a stress scene
+#[allow(clippy::cast_precision_loss, clippy::cast_possible_truncation)]
+fn build_stress_scene(
+    geometry_registry: &mut GeometryRegistry,
+    tier_registry: &mut TierRegistry,
+    settings: &StressSceneSettings,
+) -> StressScene {
+    let site_tier = tier_registry.create_and_store("Stress site", None, None);
+    let mut scene = StressScene {
+        site_tier,
+        building_tiers: Vec::with_capacity(settings.columns * settings.rows),
+        solids: Vec::new(),
+        constraints: HashMap::new(),
+    };
+    let pitch = settings.building_width + settings.street_width;
+
+    for row in 0..settings.rows {
+        for column in 0..settings.columns {
+            let index = row * settings.columns + column;
+            let name = format!("Stress building {}", index + 1);
+            let tier = tier_registry.create_and_store(&name, Some(site_tier), None);
+            let tolerance = tier_registry.tolerance(&tier);
+            let floors = splitmix64(settings.seed ^ splitmix64(index as u64)) as usize
+                % settings.max_floors.max(1)
+                + 1;
+
+            let min_x = column as f32 * pitch;
+            let min_z = -(row as f32 * pitch) - settings.building_width;
+            let mut constraints = ConstraintSet::default();
+            let mut geometry = Vec::new();
+            let mut previous_top = None;
+            for floor in 0..floors {
+                let base = rectangle_loop(
+                    min_x,
+                    min_z,
+                    min_x + settings.building_width,
+                    min_z + settings.building_width,
+                    floor as f32 * settings.floor_height,
+                );
+                let solid = geometry_registry.create_solid_from_loops(
+                    &prism_loops(&base, settings.floor_height),
+                    &tolerance,
+                );
+                let Some(polygons) = geometry_registry
+                    .solids
+                    .get(&solid)
+                    .map(|solid| solid.polygons.clone())
+                else {
+                    continue;
+                };
+
+                if let Some(bottom) = polygons.first() {
+                    if let Some(top) = previous_top {
+                        constraints.explicit.push(Constraint {
+                            kind: ConstraintKind::Coplanar,
+                            targets: vec![top, *bottom],
+                            reference: Some(ConstraintReference::SelfDefined),
+                        });
+                    }
+                    if let Some(polygon) = geometry_registry.polygons.get(bottom) {
+                        constraints.explicit.push(Constraint {
+                            kind: ConstraintKind::Equilateral,
+                            targets: polygon.segments.clone(),
+                            reference: None,
+                        });
+                    }
+                }
+                previous_top = polygons.get(1).copied();
+                geometry.push(solid);
+                geometry.extend(polygons);
+                scene.solids.push(solid);
+            }
+
+            if let Some(tier) = tier_registry.get_mut(&tier) {
+                tier.geometry.extend(geometry);
+            }
+            scene.constraints.insert(tier, constraints);
+            scene.building_tiers.push(tier);
+        }
+    }
+
+    tracing::info!(
+        buildings = scene.building_tiers.len(),
+        solids = scene.solids.len(),
+        constraints = scene.constraint_count(),
+        "generated stress scene"
+    );
+    scene
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scene(seed: u64) -> StressScene {
+        let settings = StressSceneSettings {
+            columns: 2,
+            rows: 2,
+            max_floors: 3,
+            seed,
+            ..StressSceneSettings::default()
+        };
+        generate_stress_scene(
+            &mut GeometryRegistry::create_new(),
+            &mut TierRegistry::create_new(),
+            &settings,
+        )
+    }
+
+    #[test]
+    fn one_seed_generates_the_same_ids() {
+        let (first, again, other) = (scene(3), scene(3), scene(4));
+        assert_eq!(first.site_tier, again.site_tier);
+        assert_eq!(first.building_tiers, again.building_tiers);
+        assert_eq!(first.solids, again.solids);
+        assert_ne!(first.site_tier, other.site_tier);
+    }
+}
@@ -0,0 +1,370 @@
+/// Wall layer assemblies and their section cuts
+///
+/// A wall assembly lists the layers a wall is built of, such as brick,
+/// an air gap, insulation between studs and gypsum board, each with its
+/// thickness, material and the hatch drawn where a section cuts it. A
+/// wall built from an assembly is as thick as its layers together, and
+/// can be modelled as one solid or as one solid per layer.
+///
+/// Cutting a wall in plan gives one region per layer, each filled with
+/// its layer's hatch. Regions follow the wall's ends, so mitered and
+/// butted walls are hatched to their joins.
+use crate::domain::geometry::convex_hull;
+use crate::domain::{Element, GeometryRegistry, Tolerance};
+
+/// Distance between hatch lines in meters
+const HATCH_SPACING: f32 = 0.04;
+
+/// The pattern filling a layer where a section cuts it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Hatch {
+    /// Left empty, as for air gaps and thin boards
+    #[default]
+    None,
+    /// Parallel diagonal lines, as for brick and masonry
+    Diagonal,
+    /// Diagonal lines both ways, as for concrete and block
+    CrossHatch,
+    /// A zigzag across the layer, as for batt insulation
+    Insulation,
+}
+
+impl Hatch {
+    /// Every hatch
+    pub const ALL: [Hatch; 4] = [
+        Hatch::None,
+        Hatch::Diagonal,
+        Hatch::CrossHatch,
+        Hatch::Insulation,
+    ];
+
+    /// Human-readable name of the hatch
+    #[must_use]
+    pub fn label(self) -> &'static str {
+        match self {
+            Hatch::None => "None",
+            Hatch::Diagonal => "Diagonal",
+            Hatch::CrossHatch => "Cross Hatch",
+            Hatch::Insulation => "Insulation",
+        }
+    }
+}
+
+/// One layer of a wall assembly
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WallLayer {
+    /// What the layer is, such as "Brick" or "Insulation"
+    pub name: String,
+    /// The material the layer is made of, if assigned
+    pub material: Option<String>,
+    /// Thickness of the layer in meters
+    pub thickness: f32,
+    /// The pattern filling the layer in sections
+    pub hatch: Hatch,
+}
+
+/// The layers a wall is built of
+///
+/// Layers are listed from the wall's first face, which lies to the right
+/// of its center line looking from its start to its end, seen from above.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WallAssembly {
+    /// The assembly's name
+    pub name: String,
+    /// The layers, from the first face to the second
+    pub layers: Vec<WallLayer>,
+}
+
+impl WallAssembly {
+    /// Thickness of all the layers together
+    #[must_use]
+    pub fn thickness(&self) -> f32 {
+        self.layers.iter().map(|layer| layer.thickness).sum()
+    }
+
+    /// Where each layer starts and ends across the wall, as offsets from
+    /// the center line toward the first face
+    #[must_use]
+    pub fn layer_offsets(&self) -> Vec<(f32, f32)> {
+        let mut near = self.thickness() / 2.0;
+        self.layers
+            .iter()
+            .map(|layer| {
+                let far = near - layer.thickness;
+                let offsets = (near, far);
+                near = far;
+                offsets
+            })
+            .collect()
+    }
+
+    /// An assembly of one of this assembly's layers, for a wall modelled
+    /// as one solid per layer
+    #[must_use]
+    pub fn single_layer(&self, index: usize) -> Option<WallAssembly> {
+        let layer = self.layers.get(index)?;
+        Some(WallAssembly {
+            name: format!("{}: {}", self.name, layer.name),
+            layers: vec![layer.clone()],
+        })
+    }
+}
+
+/// A layer of a standard assembly
+fn layer(name: &str, material: &str, thickness: f32, hatch: Hatch) -> WallLayer {
+    WallLayer {
+        name: name.to_string(),
+        material: Some(material.to_string()),
+        thickness,
+        hatch,
+    }
+}
+
+/// Common wall assemblies to start from
+#[must_use]
+pub fn standard_wall_assemblies() -> Vec<WallAssembly> {
+    vec![
+        WallAssembly {
+            name: "Stud Partition".to_string(),
+            layers: vec![
+                layer("Gypsum Board", "Gypsum", 0.0125, Hatch::None),
+                layer("Stud Cavity", "Timber", 0.09, Hatch::Insulation),
+                layer("Gypsum Board", "Gypsum", 0.0125, Hatch::None),
+            ],
+        },
+        WallAssembly {
+            name: "Brick Veneer on Stud".to_string(),
+            layers: vec![
+                layer("Brick", "Brick", 0.09, Hatch::Diagonal),
+                layer("Air Gap", "Air", 0.025, Hatch::None),
+                layer("Insulated Stud", "Insulation", 0.09, Hatch::Insulation),
+                layer("Gypsum Board", "Gypsum", 0.0125, Hatch::None),
+            ],
+        },
+        WallAssembly {
+            name: "Insulated Concrete".to_string(),
+            layers: vec![
+                layer("Render", "Render", 0.015, Hatch::None),
+                layer("Insulation", "Insulation", 0.1, Hatch::Insulation),
+                layer("Concrete", "Concrete", 0.2, Hatch::CrossHatch),
+            ],
+        },
+    ]
+}
+
+/// A region of a plan section, filled with its layer's hatch
+///
+/// Coordinates are model X and Z.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CutRegion {
+    /// The region's outline, a closed loop
+    pub outline: Vec<[f32; 2]>,
+    /// The material of the layer cut, if assigned
+    pub material: Option<String>,
+    /// The hatch filling the region
+    pub hatch: Hatch,
+    /// The hatch's lines, clipped to the outline
+    pub lines: Vec<[[f32; 2]; 2]>,
+}
+
+/// Cut a wall in plan at a height and hatch each of its layers
+///
+/// Layers are laid from the face holding the wall's first face vertex; a
+/// wall not recording one is laid from either face. A wall with no
+/// assembly gives one unhatched region. Returns nothing if the cut misses
+/// the wall or the outline of its bottom is not four-sided.
+#[must_use]
+pub fn wall_cut(
+    geometry_registry: &GeometryRegistry,
+    wall: &Element,
+    height: f32,
+    tolerance: &Tolerance,
+) -> Vec<CutRegion> {
+    let Some(loops) = geometry_registry.solid_loops(&wall.solid) else {
+        return Vec::new();
+    };
+    let heights = loops.iter().flatten().map(|point| point.y);
+    let (bottom, top) = (
+        heights.clone().fold(f32::MAX, f32::min),
+        heights.fold(f32::MIN, f32::max),
+    );
+    if height < bottom || height > top {
+        return Vec::new();
+    }
+    let base: Vec<[f32; 2]> = loops
+        .iter()
+        .flatten()
+        .filter(|point| (point.y - bottom).abs() <= tolerance.linear)
+        .map(|point| [point.x, point.z])
+        .collect();
+    let plan = convex_hull(&base, tolerance.linear);
+    if plan.len() != 4 {
+        return Vec::new();
+    }
+
+    // The sides are the longer pair of opposite edges, each run from the
+    // wall's start end to its end end
+    let length = |a: [f32; 2], b: [f32; 2]| (b[0] - a[0]).hypot(b[1] - a[1]);
+    let [a, b, c, d] = [plan[0], plan[1], plan[2], plan[3]];
+    let (first, second) = if length(a, b) + length(d, c) >= length(b, c) + length(a, d) {
+        ([a, b], [d, c])
+    } else {
+        ([b, c], [a, d])
+    };
+    // The first face is the side nearest the wall's first face vertex
+    let offset = |line: [[f32; 2]; 2], point: [f32; 2]| {
+        let (dx, dz) = (line[1][0] - line[0][0], line[1][1] - line[0][1]);
+        ((point[0] - line[0][0]) * dz - (point[1] - line[0][1]) * dx).abs()
+            / dx.hypot(dz).max(f32::EPSILON)
+    };
+    let flip = wall
+        .first_face
+        .and_then(|id| geometry_registry.vertices.get(&id))
+        .is_some_and(|vertex| {
+            let point = [vertex.position.x, vertex.position.z];
+            offset(second, point) < offset(first, point)
+        });
+    let (first, second) = if flip {
+        (second, first)
+    } else {
+        (first, second)
+    };
+
+    let lerp = |from: [f32; 2], to: [f32; 2], t: f32| {
+        [
+            from[0] + (to[0] - from[0]) * t,
+            from[1] + (to[1] - from[1]) * t,
+        ]
+    };
+    let layers = wall.assembly.as_ref().map_or_else(
+        || vec![(0.0, 1.0, wall.material.clone(), Hatch::None)],
+        |assembly| {
+            let total = assembly.thickness().max(f32::EPSILON);
+            let mut start = 0.0;
+            assembly
+                .layers
+                .iter()
+                .map(|layer| {
+                    let end = start + layer.thickness / total;
+                    let span = (start, end, layer.material.clone(), layer.hatch);
+                    start = end;
+                    span
+                })
+                .collect()
+        },
+    );
+    layers
+        .into_iter()
+        .map(|(near, far, material, hatch)| {
+            let near_line = [
+                lerp(first[0], second[0], near),
+                lerp(first[1], second[1], near),
+            ];
+            let far_line = [
+                lerp(first[0], second[0], far),
+                lerp(first[1], second[1], far),
+            ];
+            let outline = vec![near_line[0], near_line[1], far_line[1], far_line[0]];
+            CutRegion {
+                lines: hatch_lines(&outline, hatch),
+                outline,
+                material,
+                hatch,
+            }
+        })
+        .collect()
+}
+
+/// The lines of a hatch filling a four-sided layer region
+///
+/// The region runs from its first corner to its second along one face of
+/// the layer and back from its third to its fourth along the other.
+#[must_use]
+pub fn hatch_lines(outline: &[[f32; 2]], hatch: Hatch) -> Vec<[[f32; 2]; 2]> {
+    match hatch {
+        Hatch::None => Vec::new(),
+        Hatch::Diagonal => clipped_lines(outline, std::f32::consts::FRAC_PI_4),
+        Hatch::CrossHatch => {
+            let mut lines = clipped_lines(outline, std::f32::consts::FRAC_PI_4);
+            lines.extend(clipped_lines(outline, -std::f32::consts::FRAC_PI_4));
+            lines
+        }
+        Hatch::Insulation => zigzag(outline),
+    }
+}
+
+/// Parallel lines at an angle to the X axis, clipped to a convex outline
+fn clipped_lines(outline: &[[f32; 2]], angle: f32) -> Vec<[[f32; 2]; 2]> {
+    let (sin, cos) = angle.sin_cos();
+    let (along, across) = ([cos, sin], [-sin, cos]);
+    let offset = |point: &[f32; 2]| point[0] * across[0] + point[1] * across[1];
+    let low = outline.iter().map(offset).fold(f32::MAX, f32::min);
+    let high = outline.iter().map(offset).fold(f32::MIN, f32::max);
+
+    let mut lines = Vec::new();
+    let mut level = (low / HATCH_SPACING).floor() * HATCH_SPACING + HATCH_SPACING;
+    while level < high {
+        // Where the line crosses the outline's edges, along the line
+        let mut crossings: Vec<f32> = Vec::new();
+        for (index, start) in outline.iter().enumerate() {
+            let end = &outline[(index + 1) % outline.len()];
+            let (start_offset, end_offset) = (offset(start) - level, offset(end) - level);
+            if start_offset * end_offset > 0.0 || (start_offset - end_offset).abs() < f32::EPSILON {
+                continue;
+            }
+            let t = start_offset / (start_offset - end_offset);
+            let point = [
+                start[0] + (end[0] - start[0]) * t,
+                start[1] + (end[1] - start[1]) * t,
+            ];
+            crossings.push(point[0] * along[0] + point[1] * along[1]);
+        }
+        let from = crossings.iter().copied().fold(f32::MAX, f32::min);
+        let to = crossings.iter().copied().fold(f32::MIN, f32::max);
+        if to > from {
+            let point = |at: f32| {
+                [
+                    along[0] * at + across[0] * level,
+                    along[1] * at + across[1] * level,
+                ]
+            };
+            lines.push([point(from), point(to)]);
+        }
+        level += HATCH_SPACING;
+    }
+    lines
+}
+
+/// A zigzag between a layer's two faces, one tooth per layer thickness
+fn zigzag(outline: &[[f32; 2]]) -> Vec<[[f32; 2]; 2]> {
+    let &[near_start, near_end, far_end, far_start] = outline else {
+        return Vec::new();
+    };
+    let length = |a: [f32; 2], b: [f32; 2]| (b[0] - a[0]).hypot(b[1] - a[1]);
+    let thickness = length(near_start, far_start).min(length(near_end, far_end));
+    if thickness <= f32::EPSILON {
+        return Vec::new();
+    }
+    let run = length(near_start, near_end).max(length(far_start, far_end));
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    let teeth = ((run / thickness).round() as usize).max(1);
+    #[allow(clippy::cast_precision_loss)]
+    let point = |index: usize| {
+        let t = index as f32 / (teeth * 2) as f32;
+        let (start, end) = if index.is_multiple_of(2) {
+            (near_start, near_end)
+        } else {
+            (far_start, far_end)
+        };
+        [
+            start[0] + (end[0] - start[0]) * t,
+            start[1] + (end[1] - start[1]) * t,
+        ]
+    };
+    (0..teeth * 2)
+        .map(|index| [point(index), point(index + 1)])
+        .collect()
+}
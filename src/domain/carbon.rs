@@ -0,0 +1,208 @@
+/// Embodied carbon of the building elements
+///
+/// Each material definition carries its embodied emissions in kilograms
+/// of CO2 equivalent per square meter, per cubic meter or per item, and an
+/// element is measured in that unit like in a cost estimate. Elements with
+/// no material, or a material without a definition, are left unrated
+/// rather than counted as zero so gaps in the data stay visible.
+use crate::domain::{ElementKind, ElementRegistry, GeometryRegistry, RateUnit};
+use uuid::Uuid;
+
+/// The embodied emissions of one material
+#[derive(Debug, Clone, PartialEq)]
+pub struct MaterialCarbon {
+    /// The material name, matched ignoring case
+    pub material: String,
+    /// What the factor is given per
+    pub unit: RateUnit,
+    /// Kilograms of CO2 equivalent per unit
+    pub factor: f64,
+}
+
+/// A table of material carbon definitions
+#[derive(Debug, Clone, Default)]
+pub struct CarbonFactors {
+    /// The definitions, earlier ones winning over later duplicates
+    pub materials: Vec<MaterialCarbon>,
+}
+
+impl CarbonFactors {
+    /// Rough cradle-to-gate figures for common materials, meant as a
+    /// starting point to be replaced by project or product data
+    #[must_use]
+    pub fn with_typical_materials() -> Self {
+        let materials = [
+            ("concrete", RateUnit::Volume, 300.0),
+            ("reinforced concrete", RateUnit::Volume, 400.0),
+            ("masonry", RateUnit::Volume, 350.0),
+            ("brick", RateUnit::Volume, 400.0),
+            ("steel", RateUnit::Volume, 12_000.0),
+            ("timber", RateUnit::Volume, 150.0),
+            ("clt", RateUnit::Volume, 180.0),
+            ("glass", RateUnit::Area, 35.0),
+            ("gypsum", RateUnit::Volume, 200.0),
+            ("insulation", RateUnit::Volume, 50.0),
+        ]
+        .into_iter()
+        .map(|(material, unit, factor)| MaterialCarbon {
+            material: material.to_string(),
+            unit,
+            factor,
+        })
+        .collect();
+        Self { materials }
+    }
+
+    /// The definition of a material
+    #[must_use]
+    pub fn factor_for(&self, material: &str) -> Option<&MaterialCarbon> {
+        self.materials
+            .iter()
+            .find(|definition| definition.material.eq_ignore_ascii_case(material))
+    }
+
+    /// Add a definition, replacing any for the same material
+    pub fn add(&mut self, definition: MaterialCarbon) {
+        match self
+            .materials
+            .iter_mut()
+            .find(|existing| existing.material.eq_ignore_ascii_case(&definition.material))
+        {
+            Some(existing) => *existing = definition,
+            None => self.materials.push(definition),
+        }
+    }
+}
+
+/// The embodied emissions of one element
+#[derive(Debug, Clone)]
+pub struct CarbonLine {
+    /// The element
+    pub element: Uuid,
+    /// The element's solid
+    pub solid: Uuid,
+    /// The element's name
+    pub name: String,
+    /// The element's kind
+    pub kind: ElementKind,
+    /// The element's material
+    pub material: String,
+    /// The quantity measured, in the factor's unit
+    pub quantity: f32,
+    /// What the factor is given per
+    pub unit: RateUnit,
+    /// Kilograms of CO2 equivalent per unit
+    pub factor: f64,
+}
+
+impl CarbonLine {
+    /// Quantity times factor, in kilograms of CO2 equivalent
+    #[must_use]
+    pub fn emissions(&self) -> f64 {
+        f64::from(self.quantity) * self.factor
+    }
+}
+
+/// Embodied emissions of the model
+#[derive(Debug, Clone, Default)]
+pub struct CarbonReport {
+    /// One line per rated element, sorted by name
+    pub lines: Vec<CarbonLine>,
+    /// Elements with no material or no definition for their material
+    pub unrated: Vec<Uuid>,
+}
+
+impl CarbonReport {
+    /// Emissions of every line, in kilograms of CO2 equivalent
+    #[must_use]
+    pub fn total(&self) -> f64 {
+        self.lines.iter().map(CarbonLine::emissions).sum()
+    }
+
+    /// Emissions per element kind, leaving out kinds with no lines
+    #[must_use]
+    pub fn by_kind(&self) -> Vec<(ElementKind, f64)> {
+        ElementKind::ALL
+            .into_iter()
+            .filter_map(|kind| {
+                let lines = self.lines.iter().filter(|line| line.kind == kind);
+                let mut lines = lines.peekable();
+                lines.peek()?;
+                Some((kind, lines.map(CarbonLine::emissions).sum()))
+            })
+            .collect()
+    }
+
+    /// Each line's emissions relative to the highest, from zero to one
+    ///
+    /// Used to shade the model as a heat map.
+    #[must_use]
+    pub fn intensities(&self) -> Vec<(Uuid, f32)> {
+        let highest = self
+            .lines
+            .iter()
+            .map(CarbonLine::emissions)
+            .fold(0.0, f64::max);
+        self.lines
+            .iter()
+            .map(|line| {
+                let intensity = if highest > 0.0 {
+                    line.emissions() / highest
+                } else {
+                    0.0
+                };
+                #[allow(clippy::cast_possible_truncation)]
+                (line.solid, intensity as f32)
+            })
+            .collect()
+    }
+}
+
+/// Calculate the embodied emissions of every element with a material
+///
+/// Spaces are voids and are never rated.
+#[must_use]
+#[tracing::instrument(skip_all, fields(materials = factors.materials.len()))]
+pub fn calculate_carbon(
+    element_registry: &ElementRegistry,
+    geometry_registry: &GeometryRegistry,
+    factors: &CarbonFactors,
+) -> CarbonReport {
+    let mut report = CarbonReport::default();
+    for element in element_registry.elements.values() {
+        if element.kind == ElementKind::Space {
+            continue;
+        }
+        let Some(loops) = geometry_registry.solid_loops(&element.solid) else {
+            continue;
+        };
+        let Some((material, definition)) = element
+            .material
+            .as_ref()
+            .and_then(|material| Some((material, factors.factor_for(material)?)))
+        else {
+            report.unrated.push(element.id);
+            continue;
+        };
+        report.lines.push(CarbonLine {
+            element: element.id,
+            solid: element.solid,
+            name: element.name.clone(),
+            kind: element.kind,
+            material: material.clone(),
+            quantity: definition.unit.measure(&loops),
+            unit: definition.unit,
+            factor: definition.factor,
+        });
+    }
+    report
+        .lines
+        .sort_by(|a, b| a.name.cmp(&b.name).then(a.element.cmp(&b.element)));
+    report.unrated.sort();
+    tracing::info!(
+        lines = report.lines.len(),
+        unrated = report.unrated.len(),
+        "calculated embodied carbon"
+    );
+    report
+}
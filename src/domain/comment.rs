@@ -0,0 +1,180 @@
+/// Define the Comment type and its registry
+///
+/// A comment is a coordination issue raised on the model, in the manner of
+/// a BCF topic: who raised it and when, how far it has got, the element it
+/// is about and the camera view it was raised from, so anyone opening it
+/// sees what its author saw.
+use std::collections::HashMap;
+use std::path::PathBuf;
+use uuid::Uuid;
+
+use crate::domain::{new_detached_id, new_id, sorted_by_id, Point, Vector};
+
+/// How far a comment has got
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CommentStatus {
+    /// Raised and waiting for someone to take it on
+    #[default]
+    Open,
+    /// Being worked on
+    InProgress,
+    /// Answered, waiting for its author to agree
+    Resolved,
+    /// Finished with
+    Closed,
+}
+
+impl CommentStatus {
+    /// Every status, in the order a comment moves through them
+    pub const ALL: [CommentStatus; 4] = [
+        CommentStatus::Open,
+        CommentStatus::InProgress,
+        CommentStatus::Resolved,
+        CommentStatus::Closed,
+    ];
+
+    /// Name of the status, as shown and saved; also the BCF topic status
+    #[must_use]
+    pub fn label(self) -> &'static str {
+        match self {
+            CommentStatus::Open => "Open",
+            CommentStatus::InProgress => "In Progress",
+            CommentStatus::Resolved => "Resolved",
+            CommentStatus::Closed => "Closed",
+        }
+    }
+}
+
+/// How a viewpoint's camera projects the model
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ViewProjection {
+    /// A perspective with a vertical field of view in degrees
+    Perspective {
+        /// Vertical field of view in degrees
+        field_of_view: f32,
+    },
+    /// A parallel projection showing a height of the model in meters
+    Orthographic {
+        /// Height of the model shown in meters
+        view_height: f32,
+    },
+}
+
+/// A camera view of the model
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Viewpoint {
+    /// Position of the camera
+    pub position: Point,
+    /// Direction the camera looks in, of unit length
+    pub direction: Vector,
+    /// Direction of the top of the view, of unit length
+    pub up: Vector,
+    /// How the camera projects the model
+    pub projection: ViewProjection,
+}
+
+/// A coordination issue raised on the model
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Comment {
+    /// The unique identifier of the comment
+    pub id: Uuid,
+    /// What the comment says; its first line is its title
+    pub text: String,
+    /// Who raised the comment
+    pub author: String,
+    /// ISO 8601 time the comment was raised
+    pub timestamp: String,
+    /// How far the comment has got
+    pub status: CommentStatus,
+    /// The element the comment is about, if any
+    pub element: Option<Uuid>,
+    /// The view the comment was raised from
+    pub viewpoint: Viewpoint,
+    /// A picture of that view, such as one imported from a BCF topic
+    pub snapshot: Option<PathBuf>,
+}
+
+impl Comment {
+    /// The first line of the comment's text
+    #[must_use]
+    pub fn title(&self) -> &str {
+        self.text.lines().next().unwrap_or_default()
+    }
+}
+
+/// Create a new open comment
+#[must_use]
+pub fn new_comment(
+    text: &str,
+    author: &str,
+    timestamp: &str,
+    element: Option<Uuid>,
+    viewpoint: Viewpoint,
+) -> Comment {
+    Comment {
+        id: new_detached_id(),
+        text: text.to_string(),
+        author: author.to_string(),
+        timestamp: timestamp.to_string(),
+        status: CommentStatus::Open,
+        element,
+        viewpoint,
+        snapshot: None,
+    }
+}
+
+/// A registry of comments
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CommentRegistry {
+    /// Unique identifier for the registry
+    pub id: Uuid,
+    /// The comments in the registry
+    pub comments: HashMap<Uuid, Comment>,
+}
+
+impl CommentRegistry {
+    /// Create a new comment registry
+    #[must_use]
+    pub fn create_new() -> Self {
+        Self {
+            id: new_id(),
+            comments: HashMap::new(),
+        }
+    }
+
+    /// Store a comment and return its ID
+    pub fn store(&mut self, comment: Comment) -> Uuid {
+        let id = comment.id;
+        self.comments.insert(id, comment);
+        id
+    }
+
+    /// Remove a comment from the registry
+    pub fn remove(&mut self, id: &Uuid) {
+        self.comments.remove(id);
+    }
+
+    /// Get a reference to a comment by ID
+    #[must_use]
+    pub fn get(&self, id: &Uuid) -> Option<&Comment> {
+        self.comments.get(id)
+    }
+
+    /// Get a mutable reference to a comment by ID
+    #[must_use]
+    pub fn get_mut(&mut self, id: &Uuid) -> Option<&mut Comment> {
+        self.comments.get_mut(id)
+    }
+
+    /// The comments oldest first, those raised together ordered by ID
+    #[must_use]
+    pub fn by_time(&self) -> Vec<&Comment> {
+        let mut comments = sorted_by_id(&self.comments);
+        comments.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
+        comments
+    }
+}
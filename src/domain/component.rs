@@ -0,0 +1,160 @@
+/// Reusable components and their placed instances
+///
+/// A component definition is a named piece of geometry kept as point
+/// loops, such as a door, a chair or a basin, grouped by category. Each
+/// placement copies the loops into the geometry registry as a new solid
+/// and records which definition it came from.
+use std::collections::{BTreeMap, HashMap};
+use uuid::Uuid;
+
+use crate::domain::geometry::Bounds;
+use crate::domain::{new_id, GeometryRegistry, LinkTransform, Point, Tolerance};
+
+/// A reusable piece of geometry
+#[derive(Debug, Clone)]
+pub struct ComponentDefinition {
+    /// The unique identifier of the definition
+    pub id: Uuid,
+    /// The display name of the component
+    pub name: String,
+    /// The category it is listed under, such as `Doors` or `Furniture`
+    pub category: String,
+    /// The faces of the component in its own coordinates
+    pub loops: Vec<Vec<Point>>,
+}
+
+/// Create a new component definition
+#[must_use]
+pub fn new_component_definition(
+    name: &str,
+    category: &str,
+    loops: Vec<Vec<Point>>,
+) -> ComponentDefinition {
+    ComponentDefinition {
+        id: new_id(),
+        name: name.to_string(),
+        category: category.to_string(),
+        loops,
+    }
+}
+
+impl ComponentDefinition {
+    /// Bounds of the component in its own coordinates
+    /// Returns None if it has no geometry
+    #[must_use]
+    pub fn bounds(&self) -> Option<Bounds> {
+        let points: Vec<Point> = self.loops.iter().flatten().cloned().collect();
+        Bounds::from_points(&points)
+    }
+}
+
+/// A placed copy of a component
+#[derive(Debug, Clone, Copy)]
+pub struct ComponentInstance {
+    /// The definition it was placed from
+    pub definition: Uuid,
+    /// The solid holding the placed geometry
+    pub solid: Uuid,
+    /// Where the definition was placed
+    pub placement: LinkTransform,
+}
+
+/// A library of component definitions and their instances
+pub struct ComponentLibrary {
+    /// Unique identifier for the library
+    pub id: Uuid,
+    /// The component definitions in the library
+    pub definitions: HashMap<Uuid, ComponentDefinition>,
+    /// The placed instances, keyed by their solid
+    pub instances: HashMap<Uuid, ComponentInstance>,
+}
+
+impl ComponentLibrary {
+    /// Create a new, empty component library
+    #[must_use]
+    pub fn create_new() -> Self {
+        Self {
+            id: new_id(),
+            definitions: HashMap::new(),
+            instances: HashMap::new(),
+        }
+    }
+
+    /// Store a component definition and return its ID
+    pub fn store(&mut self, definition: ComponentDefinition) -> Uuid {
+        let id = definition.id;
+        self.definitions.insert(id, definition);
+        id
+    }
+
+    /// Get a reference to a component definition by ID
+    #[must_use]
+    pub fn get(&self, id: &Uuid) -> Option<&ComponentDefinition> {
+        self.definitions.get(id)
+    }
+
+    /// Remove a component definition, leaving its instances' solids in place
+    pub fn remove(&mut self, id: &Uuid) {
+        self.definitions.remove(id);
+    }
+
+    /// Forget an instance, leaving its solid in place
+    pub fn remove_instance(&mut self, solid: &Uuid) -> Option<ComponentInstance> {
+        self.instances.remove(solid)
+    }
+
+    /// Definitions grouped by category, both sorted by name
+    #[must_use]
+    pub fn by_category(&self) -> BTreeMap<&str, Vec<&ComponentDefinition>> {
+        let mut categories: BTreeMap<&str, Vec<&ComponentDefinition>> = BTreeMap::new();
+        for definition in self.definitions.values() {
+            categories
+                .entry(definition.category.as_str())
+                .or_default()
+                .push(definition);
+        }
+        for definitions in categories.values_mut() {
+            definitions.sort_by(|a, b| a.name.cmp(&b.name).then(a.id.cmp(&b.id)));
+        }
+        categories
+    }
+
+    /// Place a copy of a definition in the geometry registry
+    /// Returns the new solid's ID, or None if the definition is missing
+    pub fn place(
+        &mut self,
+        definition_id: &Uuid,
+        placement: LinkTransform,
+        geometry_registry: &mut GeometryRegistry,
+        tolerance: &Tolerance,
+    ) -> Option<Uuid> {
+        let definition = self.definitions.get(definition_id)?;
+        let loops: Vec<Vec<Point>> = definition
+            .loops
+            .iter()
+            .map(|face| face.iter().map(|point| placement.apply(point)).collect())
+            .collect();
+        let solid = geometry_registry.create_solid_from_loops(&loops, tolerance);
+        self.instances.insert(
+            solid,
+            ComponentInstance {
+                definition: *definition_id,
+                solid,
+                placement,
+            },
+        );
+        Some(solid)
+    }
+
+    /// The instances placed from a definition
+    #[must_use]
+    pub fn instances_of(&self, definition_id: &Uuid) -> Vec<&ComponentInstance> {
+        let mut instances: Vec<&ComponentInstance> = self
+            .instances
+            .values()
+            .filter(|instance| instance.definition == *definition_id)
+            .collect();
+        instances.sort_by_key(|instance| instance.solid);
+        instances
+    }
+}
@@ -0,0 +1,237 @@
+/// Cost estimates from assembly rates
+///
+/// A rates table prices elements by their material, their kind or both,
+/// per square meter, per cubic meter or per item. The estimate prices
+/// each element with the most specific matching rate and rolls the costs
+/// up by phase and by tier. An element's area is its largest face: the
+/// face of a wall, the plan of a slab.
+use crate::domain::geometry::{polygon_area, signed_volume};
+use crate::domain::{
+    Element, ElementKind, ElementRegistry, GeometryRegistry, Phase, PhaseFilter, Point,
+    TierRegistry,
+};
+use uuid::Uuid;
+
+/// What a rate is charged per
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RateUnit {
+    /// Per square meter of the element's largest face
+    Area,
+    /// Per cubic meter of the element's volume
+    Volume,
+    /// Per element
+    Item,
+}
+
+impl RateUnit {
+    /// Short unit label, as written in rates tables
+    #[must_use]
+    pub fn label(self) -> &'static str {
+        match self {
+            RateUnit::Area => "m2",
+            RateUnit::Volume => "m3",
+            RateUnit::Item => "item",
+        }
+    }
+
+    /// Look up a unit by its label, ignoring case
+    #[must_use]
+    pub fn from_label(label: &str) -> Option<Self> {
+        [RateUnit::Area, RateUnit::Volume, RateUnit::Item]
+            .into_iter()
+            .find(|unit| unit.label().eq_ignore_ascii_case(label))
+    }
+
+    /// The quantity of a solid in this unit, given its face loops
+    #[must_use]
+    pub fn measure(self, loops: &[Vec<Point>]) -> f32 {
+        match self {
+            RateUnit::Area => loops
+                .iter()
+                .map(|points| polygon_area(points))
+                .fold(0.0, f32::max),
+            RateUnit::Volume => signed_volume(loops).abs(),
+            RateUnit::Item => 1.0,
+        }
+    }
+}
+
+/// The price of one unit of an assembly
+#[derive(Debug, Clone, PartialEq)]
+pub struct CostRate {
+    /// The element kind the rate applies to, any kind if None
+    pub kind: Option<ElementKind>,
+    /// The material the rate applies to, ignoring case, any material if None
+    pub material: Option<String>,
+    /// What the rate is charged per
+    pub unit: RateUnit,
+    /// Price per unit
+    pub rate: f64,
+}
+
+impl CostRate {
+    /// How specifically the rate matches an element, None if it does not
+    ///
+    /// A rate naming both kind and material beats one naming the material,
+    /// which beats one naming only the kind.
+    fn specificity(&self, element: &Element) -> Option<u8> {
+        let kind = match self.kind {
+            Some(kind) if kind != element.kind => return None,
+            Some(_) => 1,
+            None => 0,
+        };
+        let material = match (&self.material, &element.material) {
+            (None, _) => 0,
+            (Some(wanted), Some(material)) if wanted.eq_ignore_ascii_case(material) => 2,
+            (Some(_), _) => return None,
+        };
+        Some(kind + material)
+    }
+}
+
+/// A table of assembly rates
+#[derive(Debug, Clone, Default)]
+pub struct RatesTable {
+    /// The rates, earlier rates winning ties
+    pub rates: Vec<CostRate>,
+}
+
+impl RatesTable {
+    /// The most specific rate matching an element
+    #[must_use]
+    pub fn rate_for(&self, element: &Element) -> Option<&CostRate> {
+        self.rates
+            .iter()
+            .filter_map(|rate| Some((rate.specificity(element)?, rate)))
+            .rev()
+            .max_by_key(|(specificity, _)| *specificity)
+            .map(|(_, rate)| rate)
+    }
+}
+
+/// The priced quantity of one element
+#[derive(Debug, Clone)]
+pub struct CostLine {
+    /// The element
+    pub element: Uuid,
+    /// The element's name
+    pub name: String,
+    /// The element's kind
+    pub kind: ElementKind,
+    /// The element's material
+    pub material: Option<String>,
+    /// The phase of the element's solid
+    pub phase: Phase,
+    /// The deepest tier holding the element's solid
+    pub tier: Option<Uuid>,
+    /// The quantity priced, in the rate's unit
+    pub quantity: f32,
+    /// What the rate is charged per
+    pub unit: RateUnit,
+    /// Price per unit
+    pub rate: f64,
+}
+
+impl CostLine {
+    /// Quantity times rate
+    #[must_use]
+    pub fn cost(&self) -> f64 {
+        f64::from(self.quantity) * self.rate
+    }
+}
+
+/// An itemized cost estimate
+#[derive(Debug, Clone, Default)]
+pub struct CostEstimate {
+    /// One line per priced element, sorted by name
+    pub lines: Vec<CostLine>,
+    /// Elements no rate matched
+    pub unpriced: Vec<Uuid>,
+}
+
+impl CostEstimate {
+    /// The cost of every line
+    #[must_use]
+    pub fn total(&self) -> f64 {
+        self.lines.iter().map(CostLine::cost).sum()
+    }
+
+    /// Cost per phase, in construction order, leaving out empty phases
+    #[must_use]
+    pub fn by_phase(&self) -> Vec<(Phase, f64)> {
+        Phase::ALL
+            .into_iter()
+            .filter_map(|phase| {
+                let lines = self.lines.iter().filter(|line| line.phase == phase);
+                let mut lines = lines.peekable();
+                lines.peek()?;
+                Some((phase, lines.map(CostLine::cost).sum()))
+            })
+            .collect()
+    }
+
+    /// Cost per tier in order of first appearance, None for elements
+    /// outside every tier
+    #[must_use]
+    pub fn by_tier(&self) -> Vec<(Option<Uuid>, f64)> {
+        let mut totals: Vec<(Option<Uuid>, f64)> = Vec::new();
+        for line in &self.lines {
+            match totals.iter_mut().find(|(tier, _)| *tier == line.tier) {
+                Some((_, total)) => *total += line.cost(),
+                None => totals.push((line.tier, line.cost())),
+            }
+        }
+        totals
+    }
+}
+
+/// Price the elements in the filtered phases with a rates table
+///
+/// Spaces are voids and are never priced.
+#[must_use]
+#[tracing::instrument(skip_all, fields(rates = table.rates.len()))]
+pub fn estimate_cost(
+    element_registry: &ElementRegistry,
+    geometry_registry: &GeometryRegistry,
+    tier_registry: &TierRegistry,
+    table: &RatesTable,
+    phases: &PhaseFilter,
+) -> CostEstimate {
+    let mut estimate = CostEstimate::default();
+    for element in element_registry.elements.values() {
+        let Some(solid) = geometry_registry.solids.get(&element.solid) else {
+            continue;
+        };
+        if element.kind == ElementKind::Space || !phases.includes(solid.phase) {
+            continue;
+        }
+        let Some(rate) = table.rate_for(element) else {
+            estimate.unpriced.push(element.id);
+            continue;
+        };
+        let loops = geometry_registry
+            .solid_loops(&element.solid)
+            .unwrap_or_default();
+        estimate.lines.push(CostLine {
+            element: element.id,
+            name: element.name.clone(),
+            kind: element.kind,
+            material: element.material.clone(),
+            phase: solid.phase,
+            tier: tier_registry.tier_of(&element.solid),
+            quantity: rate.unit.measure(&loops),
+            unit: rate.unit,
+            rate: rate.rate,
+        });
+    }
+    estimate
+        .lines
+        .sort_by(|a, b| a.name.cmp(&b.name).then(a.element.cmp(&b.element)));
+    estimate.unpriced.sort();
+    tracing::info!(
+        lines = estimate.lines.len(),
+        unpriced = estimate.unpriced.len(),
+        "estimated cost"
+    );
+    estimate
+}
@@ -0,0 +1,265 @@
+/// Curtain wall grids dividing facade faces into mullions and panels
+///
+/// A curtain grid divides a vertical face into bays, across by one rule
+/// and up by another, each either a fixed count of equal bays or a
+/// spacing. Mullions run up every grid line across, transoms run across
+/// every grid line up between them, and a panel fills each bay. All of
+/// them are centered on their grid lines, so every bay of one size gets
+/// panels of one size and they can share a component definition.
+///
+/// A grid remembers the face it divides and how big it was, so the
+/// mullions and panels can be built again when the face changes.
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::domain::{new_id, sorted_by_id};
+
+/// How a face is divided along one of its directions
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GridRule {
+    /// A number of equal bays
+    Count(usize),
+    /// Bays of a fixed width from the start, the last taking what is left
+    Spacing(f32),
+    /// The fewest equal bays no wider than a spacing
+    MaxSpacing(f32),
+}
+
+impl GridRule {
+    /// Positions of the grid lines over a length, both ends included
+    ///
+    /// A count of zero or a spacing that is not positive gives a single
+    /// bay. A last bay of a fixed spacing shorter than the tolerance is
+    /// merged into the one before.
+    #[must_use]
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_precision_loss,
+        clippy::cast_sign_loss
+    )]
+    pub fn divide(self, length: f32, tolerance: f32) -> Vec<f32> {
+        let equal = |count: usize| -> Vec<f32> {
+            let count = count.max(1);
+            (0..=count)
+                .map(|index| length * index as f32 / count as f32)
+                .collect()
+        };
+        match self {
+            GridRule::Count(count) => equal(count),
+            GridRule::MaxSpacing(spacing) if spacing > tolerance => {
+                equal((length / spacing - tolerance / spacing).ceil() as usize)
+            }
+            GridRule::Spacing(spacing) if spacing > tolerance => {
+                let bays = ((length - tolerance) / spacing).ceil().max(1.0) as usize;
+                let mut lines: Vec<f32> = (0..bays).map(|index| spacing * index as f32).collect();
+                lines.push(length);
+                lines
+            }
+            GridRule::Spacing(_) | GridRule::MaxSpacing(_) => equal(1),
+        }
+    }
+
+    /// Human-readable description of the rule
+    #[must_use]
+    pub fn label(self) -> String {
+        match self {
+            GridRule::Count(count) => format!("{count} bays"),
+            GridRule::Spacing(spacing) => format!("{spacing} m spacing"),
+            GridRule::MaxSpacing(spacing) => format!("at most {spacing} m apart"),
+        }
+    }
+}
+
+/// How a curtain grid divides its face and what it builds
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CurtainGridSettings {
+    /// How the face is divided across
+    pub across: GridRule,
+    /// How the face is divided up
+    pub up: GridRule,
+    /// Width of mullions and transoms, seen in elevation
+    pub mullion_width: f32,
+    /// How far mullions and transoms stand out from the face
+    pub mullion_depth: f32,
+    /// Thickness of the panels, set in the middle of the mullions' depth
+    pub panel_thickness: f32,
+}
+
+impl Default for CurtainGridSettings {
+    fn default() -> Self {
+        Self {
+            across: GridRule::MaxSpacing(1.5),
+            up: GridRule::MaxSpacing(1.5),
+            mullion_width: 0.05,
+            mullion_depth: 0.15,
+            panel_thickness: 0.024,
+        }
+    }
+}
+
+/// What a piece of a curtain grid is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GridPieceKind {
+    /// A vertical member up a grid line
+    Mullion,
+    /// A horizontal member across a grid line, between mullions
+    Transom,
+    /// The infill of a bay
+    Panel,
+}
+
+impl GridPieceKind {
+    /// Human-readable name of the kind
+    #[must_use]
+    pub fn label(self) -> &'static str {
+        match self {
+            GridPieceKind::Mullion => "Mullion",
+            GridPieceKind::Transom => "Transom",
+            GridPieceKind::Panel => "Panel",
+        }
+    }
+}
+
+/// A box of a curtain grid in the face's own coordinates: across the face
+/// from its left edge seen from outside, up from its bottom, and out from
+/// its plane
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GridPiece {
+    /// What the piece is
+    pub kind: GridPieceKind,
+    /// The corner nearest the face's bottom left, on or off its plane
+    pub corner: [f32; 3],
+    /// Width across, height up and depth out
+    pub size: [f32; 3],
+}
+
+/// The mullions, transoms and panels dividing a face of a width and
+/// height
+///
+/// Bays narrower than a mullion get no panel.
+#[must_use]
+pub fn curtain_grid_pieces(
+    settings: &CurtainGridSettings,
+    width: f32,
+    height: f32,
+    tolerance: f32,
+) -> Vec<GridPiece> {
+    let across = settings.across.divide(width, tolerance);
+    let up = settings.up.divide(height, tolerance);
+    let half = settings.mullion_width / 2.0;
+    let depth = settings.mullion_depth;
+    let mut pieces: Vec<GridPiece> = across
+        .iter()
+        .map(|line| GridPiece {
+            kind: GridPieceKind::Mullion,
+            corner: [line - half, 0.0, 0.0],
+            size: [settings.mullion_width, height, depth],
+        })
+        .collect();
+    for bay in across.windows(2) {
+        let clear = bay[1] - bay[0] - settings.mullion_width;
+        if clear <= tolerance {
+            continue;
+        }
+        for line in &up {
+            pieces.push(GridPiece {
+                kind: GridPieceKind::Transom,
+                corner: [bay[0] + half, (line - half).clamp(0.0, height), 0.0],
+                size: [clear, settings.mullion_width.min(height), depth],
+            });
+        }
+        for row in up.windows(2) {
+            let rise = row[1] - row[0] - settings.mullion_width;
+            if rise <= tolerance {
+                continue;
+            }
+            pieces.push(GridPiece {
+                kind: GridPieceKind::Panel,
+                corner: [
+                    bay[0] + half,
+                    row[0] + half,
+                    (depth - settings.panel_thickness) / 2.0,
+                ],
+                size: [clear, rise, settings.panel_thickness],
+            });
+        }
+    }
+    pieces
+}
+
+/// A face divided into a curtain wall
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CurtainGrid {
+    /// Unique identifier of the grid
+    pub id: Uuid,
+    /// The polygon divided
+    pub face: Uuid,
+    /// How it is divided
+    pub settings: CurtainGridSettings,
+    /// The face's bottom left corner, direction across and size when the
+    /// pieces were last built, as X, Y, Z, the X and Z of the direction,
+    /// the width and the height
+    pub built_for: [f32; 7],
+    /// The solids of the pieces built
+    pub solids: Vec<Uuid>,
+    /// The component definitions the pieces were placed from
+    pub definitions: Vec<Uuid>,
+}
+
+/// A registry of curtain grids
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CurtainGridRegistry {
+    /// Unique identifier for the registry
+    pub id: Uuid,
+    /// The grids, by ID
+    pub grids: HashMap<Uuid, CurtainGrid>,
+}
+
+impl CurtainGridRegistry {
+    /// Create a new, empty registry
+    #[must_use]
+    pub fn create_new() -> Self {
+        Self {
+            id: new_id(),
+            grids: HashMap::new(),
+        }
+    }
+
+    /// Store a grid and return its ID
+    pub fn store(&mut self, grid: CurtainGrid) -> Uuid {
+        let id = grid.id;
+        self.grids.insert(id, grid);
+        id
+    }
+
+    /// Remove a grid
+    pub fn remove(&mut self, id: &Uuid) {
+        self.grids.remove(id);
+    }
+
+    /// Get a grid by ID
+    #[must_use]
+    pub fn get(&self, id: &Uuid) -> Option<&CurtainGrid> {
+        self.grids.get(id)
+    }
+
+    /// Get a mutable reference to a grid by ID
+    pub fn get_mut(&mut self, id: &Uuid) -> Option<&mut CurtainGrid> {
+        self.grids.get_mut(id)
+    }
+
+    /// The grid dividing a face, if any
+    #[must_use]
+    pub fn on_face(&self, face: &Uuid) -> Option<&CurtainGrid> {
+        self.sorted().into_iter().find(|grid| grid.face == *face)
+    }
+
+    /// The grids in ID order
+    #[must_use]
+    pub fn sorted(&self) -> Vec<&CurtainGrid> {
+        sorted_by_id(&self.grids)
+    }
+}
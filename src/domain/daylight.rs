@@ -0,0 +1,117 @@
+/// Window-to-floor area checks for daylight
+///
+/// Building codes often ask for a glazed area of at least some fraction
+/// of a room's floor area as a simple daylight rule. A window serves every
+/// space whose volume it touches, within a short reach to cover the wall
+/// it sits in, and its glazed area is its largest face.
+use crate::domain::geometry::{polygon_area, Bounds};
+use crate::domain::{detect_spaces, ElementKind, ElementRegistry, GeometryRegistry};
+use uuid::Uuid;
+
+/// Settings for a daylight check
+#[derive(Debug, Clone)]
+pub struct DaylightSettings {
+    /// Least window area as a fraction of floor area
+    pub min_ratio: f32,
+    /// How far from a space a window may sit and still serve it
+    pub reach: f32,
+}
+
+impl Default for DaylightSettings {
+    fn default() -> Self {
+        Self {
+            min_ratio: 0.1,
+            reach: 0.5,
+        }
+    }
+}
+
+/// The daylight result for one space
+#[derive(Debug, Clone)]
+pub struct DaylightCheck {
+    /// The space element
+    pub space: Uuid,
+    /// The solid bounding the space
+    pub solid: Uuid,
+    /// The space's name
+    pub name: String,
+    /// Floor area of the space
+    pub floor_area: f32,
+    /// Total glazed area of the windows serving the space
+    pub window_area: f32,
+    /// The window elements serving the space
+    pub windows: Vec<Uuid>,
+    /// Window area divided by floor area, zero for a space without floor
+    pub ratio: f32,
+    /// Whether the ratio meets the minimum
+    pub passes: bool,
+}
+
+/// Whether two boxes come within `reach` of each other on every axis
+fn within_reach(a: &Bounds, b: &Bounds, reach: f32) -> bool {
+    a.min.x <= b.max.x + reach
+        && b.min.x <= a.max.x + reach
+        && a.min.y <= b.max.y + reach
+        && b.min.y <= a.max.y + reach
+        && a.min.z <= b.max.z + reach
+        && b.min.z <= a.max.z + reach
+}
+
+/// Check the window-to-floor ratio of every space, sorted by space name
+#[must_use]
+#[tracing::instrument(skip_all, fields(min_ratio = settings.min_ratio))]
+pub fn check_daylight(
+    element_registry: &ElementRegistry,
+    geometry_registry: &GeometryRegistry,
+    settings: &DaylightSettings,
+) -> Vec<DaylightCheck> {
+    let mut windows: Vec<(Uuid, Bounds, f32)> = element_registry
+        .elements
+        .values()
+        .filter(|element| element.kind == ElementKind::Window)
+        .filter_map(|element| {
+            let loops = geometry_registry.solid_loops(&element.solid)?;
+            let bounds = Bounds::from_points(&loops.concat())?;
+            let glazed = loops
+                .iter()
+                .map(|points| polygon_area(points))
+                .fold(0.0, f32::max);
+            Some((element.id, bounds, glazed))
+        })
+        .collect();
+    windows.sort_by_key(|(id, _, _)| *id);
+
+    let checks: Vec<DaylightCheck> = detect_spaces(element_registry, geometry_registry)
+        .into_iter()
+        .filter_map(|space| {
+            let loops = geometry_registry.solid_loops(&space.solid)?;
+            let bounds = Bounds::from_points(&loops.concat())?;
+            let serving: Vec<&(Uuid, Bounds, f32)> = windows
+                .iter()
+                .filter(|(_, window, _)| within_reach(&bounds, window, settings.reach))
+                .collect();
+            let window_area = serving.iter().map(|(_, _, area)| area).sum();
+            let ratio = if space.floor_area > 0.0 {
+                window_area / space.floor_area
+            } else {
+                0.0
+            };
+            Some(DaylightCheck {
+                space: space.element,
+                solid: space.solid,
+                name: space.name,
+                floor_area: space.floor_area,
+                window_area,
+                windows: serving.iter().map(|(id, _, _)| *id).collect(),
+                ratio,
+                passes: ratio >= settings.min_ratio,
+            })
+        })
+        .collect();
+    tracing::info!(
+        spaces = checks.len(),
+        failing = checks.iter().filter(|check| !check.passes).count(),
+        "checked daylight"
+    );
+    checks
+}
@@ -0,0 +1,384 @@
+/// Egress paths and travel distances
+///
+/// Walkable surfaces (the tops of slabs and the floors of spaces) are
+/// sampled on a plan grid. Grid points inside a wall's footprint are
+/// blocked unless a door passes through the wall there, and neighbouring
+/// points are joined where the step between them is clear. Distances are
+/// then grown outward from the exits, so each space's travel distance is
+/// the walk from its most remote point to the nearest exit.
+///
+/// Levels are not joined to each other: stairs and ramps are not modelled
+/// yet, so each storey needs an exit of its own.
+use crate::domain::geometry::{newell_normal, point_in_loop, Bounds};
+use crate::domain::{detect_spaces, ElementKind, ElementRegistry, GeometryRegistry, Point, Vector};
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+use uuid::Uuid;
+
+/// Plan loops are tested for containment looking straight down
+const UP: Vector = Vector {
+    x: 0.0,
+    y: 1.0,
+    z: 0.0,
+};
+
+/// Settings for an egress analysis
+#[derive(Debug, Clone)]
+pub struct EgressSettings {
+    /// Door elements people leave the building through
+    pub exits: Vec<Uuid>,
+    /// Spacing of the walkable grid
+    pub grid_spacing: f32,
+    /// Longest allowed travel distance to an exit
+    pub max_travel_distance: f32,
+    /// Clear height walls are checked over, above the walking surface
+    pub headroom: f32,
+}
+
+impl Default for EgressSettings {
+    fn default() -> Self {
+        Self {
+            exits: Vec::new(),
+            grid_spacing: 0.5,
+            max_travel_distance: 45.0,
+            headroom: 2.0,
+        }
+    }
+}
+
+/// Walkable grid points and the clear steps between them
+#[derive(Debug, Clone, Default)]
+pub struct EgressGraph {
+    /// Grid points on the walking surfaces
+    pub nodes: Vec<Point>,
+    /// For each node, its neighbours and the length of the step to each
+    pub edges: Vec<Vec<(usize, f32)>>,
+}
+
+/// The egress route from one space
+#[derive(Debug, Clone)]
+pub struct EgressRoute {
+    /// The space element
+    pub space: Uuid,
+    /// The space's name
+    pub name: String,
+    /// Travel distance from the space's most remote point, None if some
+    /// part of the space cannot reach an exit
+    pub distance: Option<f32>,
+    /// The route from the most remote reachable point to an exit
+    pub path: Vec<Point>,
+    /// Whether the travel distance is within the limit
+    pub within_limit: bool,
+}
+
+/// The result of an egress analysis
+#[derive(Debug, Clone, Default)]
+pub struct EgressReport {
+    /// One route per space, sorted by space name
+    pub routes: Vec<EgressRoute>,
+    /// The travel distance limit the routes were checked against
+    pub limit: f32,
+    /// Number of grid points reached by the exits
+    pub exit_nodes: usize,
+}
+
+impl EgressReport {
+    /// The routes over the limit or without a way out
+    pub fn failures(&self) -> impl Iterator<Item = &EgressRoute> {
+        self.routes.iter().filter(|route| !route.within_limit)
+    }
+}
+
+/// Whether a loop faces up (`up`) or down within 45 degrees of vertical
+fn faces_vertically(points: &[Point], up: bool) -> bool {
+    let normal = newell_normal(points);
+    let vertical = if up { normal.y } else { -normal.y };
+    vertical > 0.0 && vertical >= normal.length() * std::f32::consts::FRAC_1_SQRT_2
+}
+
+/// The plan footprint and vertical extent of an element's solid
+struct Footprint {
+    loops: Vec<Vec<Point>>,
+    bounds: Bounds,
+}
+
+impl Footprint {
+    /// The downward faces of a solid and its bounds
+    fn of_solid(geometry_registry: &GeometryRegistry, solid_id: &Uuid) -> Option<Self> {
+        let loops = geometry_registry.solid_loops(solid_id)?;
+        let bounds = Bounds::from_points(&loops.concat())?;
+        let loops = loops
+            .into_iter()
+            .filter(|points| faces_vertically(points, false))
+            .collect();
+        Some(Self { loops, bounds })
+    }
+
+    /// Whether a point at walking height `y` is inside the footprint and
+    /// the solid rises into the headroom above it
+    fn covers(&self, point: &Point, headroom: f32) -> bool {
+        self.bounds.min.y < point.y + headroom
+            && self.bounds.max.y > point.y + 0.05
+            && self
+                .loops
+                .iter()
+                .any(|points| point_in_loop(point, points, &UP))
+    }
+}
+
+/// Build the walkable grid over the slabs and space floors
+#[must_use]
+#[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss)]
+pub fn build_egress_graph(
+    element_registry: &ElementRegistry,
+    geometry_registry: &GeometryRegistry,
+    settings: &EgressSettings,
+) -> EgressGraph {
+    let spacing = settings.grid_spacing.max(0.05);
+    let footprints = |kind: ElementKind| -> Vec<Footprint> {
+        element_registry
+            .elements
+            .values()
+            .filter(|element| element.kind == kind)
+            .filter_map(|element| Footprint::of_solid(geometry_registry, &element.solid))
+            .collect()
+    };
+    let walls = footprints(ElementKind::Wall);
+    let doors = footprints(ElementKind::Door);
+    let blocked = |point: &Point| {
+        walls
+            .iter()
+            .any(|wall| wall.covers(point, settings.headroom))
+            && !doors
+                .iter()
+                .any(|door| door.covers(point, settings.headroom))
+    };
+
+    let mut surfaces = Vec::new();
+    for element in element_registry.elements.values() {
+        let Some(loops) = geometry_registry.solid_loops(&element.solid) else {
+            continue;
+        };
+        match element.kind {
+            ElementKind::Slab => surfaces.extend(
+                loops
+                    .into_iter()
+                    .filter(|points| faces_vertically(points, true)),
+            ),
+            ElementKind::Space => surfaces.extend(
+                loops
+                    .into_iter()
+                    .filter(|points| faces_vertically(points, false)),
+            ),
+            _ => {}
+        }
+    }
+
+    // Grid cells are keyed by plan position and level so surfaces sharing
+    // a level share nodes
+    let mut graph = EgressGraph::default();
+    let mut cells: HashMap<(i64, i64, i64), usize> = HashMap::new();
+    for points in &surfaces {
+        let Some(bounds) = Bounds::from_points(points) else {
+            continue;
+        };
+        let height = points.iter().map(|point| point.y).sum::<f32>() / points.len() as f32;
+        let level = (height / spacing).round() as i64;
+        let (first_x, last_x) = (
+            (bounds.min.x / spacing).ceil() as i64,
+            (bounds.max.x / spacing).floor() as i64,
+        );
+        let (first_z, last_z) = (
+            (bounds.min.z / spacing).ceil() as i64,
+            (bounds.max.z / spacing).floor() as i64,
+        );
+        for column in first_x..=last_x {
+            for row in first_z..=last_z {
+                let point = Point {
+                    x: column as f32 * spacing,
+                    y: height,
+                    z: row as f32 * spacing,
+                };
+                if cells.contains_key(&(column, row, level))
+                    || !point_in_loop(&point, points, &UP)
+                    || blocked(&point)
+                {
+                    continue;
+                }
+                cells.insert((column, row, level), graph.nodes.len());
+                graph.nodes.push(point);
+            }
+        }
+    }
+
+    graph.edges = vec![Vec::new(); graph.nodes.len()];
+    for (&(column, row, level), &from) in &cells {
+        for (step_x, step_z) in [(1, 0), (0, 1), (1, 1), (1, -1)] {
+            let Some(&to) = cells.get(&(column + step_x, row + step_z, level)) else {
+                continue;
+            };
+            let (a, b) = (&graph.nodes[from], &graph.nodes[to]);
+            let midpoint = Point {
+                x: a.x.midpoint(b.x),
+                y: a.y.midpoint(b.y),
+                z: a.z.midpoint(b.z),
+            };
+            if blocked(&midpoint) {
+                continue;
+            }
+            let length = ((a.x - b.x).powi(2) + (a.z - b.z).powi(2)).sqrt();
+            graph.edges[from].push((to, length));
+            graph.edges[to].push((from, length));
+        }
+    }
+    graph
+}
+
+/// A node waiting in the distance queue, nearest first
+#[derive(PartialEq)]
+struct Pending {
+    distance: f32,
+    node: usize,
+}
+
+impl Eq for Pending {}
+
+impl Ord for Pending {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .distance
+            .total_cmp(&self.distance)
+            .then(self.node.cmp(&other.node))
+    }
+}
+
+impl PartialOrd for Pending {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Shortest distances from any of the sources, with each node's next step
+/// towards its nearest source
+fn distances_from(graph: &EgressGraph, sources: &[usize]) -> (Vec<f32>, Vec<Option<usize>>) {
+    let mut distances = vec![f32::INFINITY; graph.nodes.len()];
+    let mut next_step = vec![None; graph.nodes.len()];
+    let mut queue = BinaryHeap::new();
+    for &source in sources {
+        distances[source] = 0.0;
+        queue.push(Pending {
+            distance: 0.0,
+            node: source,
+        });
+    }
+    while let Some(Pending { distance, node }) = queue.pop() {
+        if distance > distances[node] {
+            continue;
+        }
+        for &(neighbour, length) in &graph.edges[node] {
+            let candidate = distance + length;
+            if candidate < distances[neighbour] {
+                distances[neighbour] = candidate;
+                next_step[neighbour] = Some(node);
+                queue.push(Pending {
+                    distance: candidate,
+                    node: neighbour,
+                });
+            }
+        }
+    }
+    (distances, next_step)
+}
+
+/// Find egress routes from every space to the nearest exit
+///
+/// An exit reaches the grid points inside its door's footprint, widened by
+/// one grid step so doors in thin walls still touch the floor on both
+/// sides.
+#[must_use]
+#[tracing::instrument(skip_all, fields(exits = settings.exits.len()))]
+#[allow(clippy::cast_precision_loss)]
+pub fn analyze_egress(
+    element_registry: &ElementRegistry,
+    geometry_registry: &GeometryRegistry,
+    settings: &EgressSettings,
+) -> EgressReport {
+    let graph = build_egress_graph(element_registry, geometry_registry, settings);
+    let reach = settings.grid_spacing.max(0.05) * 1.5;
+    let exit_bounds: Vec<Bounds> = settings
+        .exits
+        .iter()
+        .filter_map(|exit| element_registry.get(exit))
+        .filter_map(|door| {
+            Bounds::from_points(&geometry_registry.solid_loops(&door.solid)?.concat())
+        })
+        .collect();
+    let sources: Vec<usize> = graph
+        .nodes
+        .iter()
+        .enumerate()
+        .filter(|(_, node)| {
+            exit_bounds.iter().any(|bounds| {
+                node.x >= bounds.min.x - reach
+                    && node.x <= bounds.max.x + reach
+                    && node.z >= bounds.min.z - reach
+                    && node.z <= bounds.max.z + reach
+                    && (node.y - bounds.min.y).abs() <= reach
+            })
+        })
+        .map(|(index, _)| index)
+        .collect();
+    let (distances, next_step) = distances_from(&graph, &sources);
+
+    let routes = detect_spaces(element_registry, geometry_registry)
+        .into_iter()
+        .map(|space| {
+            let inside: Vec<usize> = graph
+                .nodes
+                .iter()
+                .enumerate()
+                .filter(|(_, node)| {
+                    space.floors.iter().any(|points| {
+                        let height =
+                            points.iter().map(|point| point.y).sum::<f32>() / points.len() as f32;
+                        (node.y - height).abs() <= reach && point_in_loop(node, points, &UP)
+                    })
+                })
+                .map(|(index, _)| index)
+                .collect();
+            let remote = inside
+                .iter()
+                .copied()
+                .filter(|&node| distances[node].is_finite())
+                .max_by(|&a, &b| distances[a].total_cmp(&distances[b]));
+            let all_reached =
+                !inside.is_empty() && inside.iter().all(|&node| distances[node].is_finite());
+            let mut path = Vec::new();
+            let mut current = remote;
+            while let Some(node) = current {
+                path.push(graph.nodes[node].clone());
+                current = next_step[node];
+            }
+            let distance = remote.filter(|_| all_reached).map(|node| distances[node]);
+            EgressRoute {
+                space: space.element,
+                name: space.name,
+                distance,
+                path,
+                within_limit: distance
+                    .is_some_and(|distance| distance <= settings.max_travel_distance),
+            }
+        })
+        .collect();
+    let report = EgressReport {
+        routes,
+        limit: settings.max_travel_distance,
+        exit_nodes: sources.len(),
+    };
+    tracing::info!(
+        nodes = graph.nodes.len(),
+        failures = report.failures().count(),
+        "analyzed egress"
+    );
+    report
+}
@@ -0,0 +1,205 @@
+/// Define the Element type and its registry
+///
+/// An element gives a solid its building meaning: what it is (wall, slab,
+/// door...), what it is made of and what it is called. Geometry stays in
+/// the geometry registry; elements refer to their solid by ID.
+use crate::domain::{new_id, sorted_by_id, WallAssembly};
+use std::collections::{BTreeMap, HashMap};
+use uuid::Uuid;
+
+/// The kind of building element a solid represents
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ElementKind {
+    /// A vertical enclosing or dividing element
+    Wall,
+    /// A floor, roof or other horizontal plate
+    Slab,
+    /// A vertical structural member
+    Column,
+    /// A horizontal or sloping structural member
+    Beam,
+    /// An opening filler for passage
+    Door,
+    /// An opening filler for light and air
+    Window,
+    /// A room volume, bounded by the elements around it
+    Space,
+    /// A length or fitting of ductwork
+    Duct,
+    /// A length or fitting of pipework
+    Pipe,
+    /// A length or fitting of cable conduit
+    Conduit,
+    /// Any element without a more specific kind
+    #[default]
+    Generic,
+}
+
+impl ElementKind {
+    /// Every kind, structural and enclosing elements first
+    pub const ALL: [ElementKind; 11] = [
+        ElementKind::Wall,
+        ElementKind::Slab,
+        ElementKind::Column,
+        ElementKind::Beam,
+        ElementKind::Door,
+        ElementKind::Window,
+        ElementKind::Space,
+        ElementKind::Duct,
+        ElementKind::Pipe,
+        ElementKind::Conduit,
+        ElementKind::Generic,
+    ];
+
+    /// Human-readable name of the kind
+    #[must_use]
+    pub fn label(&self) -> &'static str {
+        match self {
+            ElementKind::Wall => "Wall",
+            ElementKind::Slab => "Slab",
+            ElementKind::Column => "Column",
+            ElementKind::Beam => "Beam",
+            ElementKind::Door => "Door",
+            ElementKind::Window => "Window",
+            ElementKind::Space => "Space",
+            ElementKind::Duct => "Duct",
+            ElementKind::Pipe => "Pipe",
+            ElementKind::Conduit => "Conduit",
+            ElementKind::Generic => "Element",
+        }
+    }
+
+    /// Look up a kind by its label, ignoring case
+    ///
+    /// "Generic" is accepted as well as "Element" for generic elements.
+    #[must_use]
+    pub fn from_label(label: &str) -> Option<Self> {
+        if label.eq_ignore_ascii_case("generic") {
+            return Some(ElementKind::Generic);
+        }
+        Self::ALL
+            .into_iter()
+            .find(|kind| kind.label().eq_ignore_ascii_case(label))
+    }
+}
+
+/// A building element backed by a solid
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Element {
+    /// The unique identifier of the element
+    pub id: Uuid,
+    /// The kind of element
+    pub kind: ElementKind,
+    /// The solid giving the element its shape
+    pub solid: Uuid,
+    /// The element's name
+    pub name: String,
+    /// The material the element is made of, if assigned
+    pub material: Option<String>,
+    /// Materials of single faces that differ from `material`, by polygon ID
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub face_materials: BTreeMap<Uuid, String>,
+    /// The layers the element is built of, for walls with an assembly
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub assembly: Option<WallAssembly>,
+    /// A vertex on the wall's first face, which its assembly's layers are
+    /// listed from, for walls that know it
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub first_face: Option<Uuid>,
+}
+
+impl Element {
+    /// The material of one of the element's faces: its own if it has one,
+    /// otherwise the element's
+    #[must_use]
+    pub fn face_material(&self, polygon_id: &Uuid) -> Option<&str> {
+        self.face_materials
+            .get(polygon_id)
+            .map(String::as_str)
+            .or(self.material.as_deref())
+    }
+}
+
+/// Create a new element
+#[must_use]
+pub fn new_element(kind: ElementKind, solid: &Uuid, name: &str) -> Element {
+    Element {
+        id: new_id(),
+        kind,
+        solid: *solid,
+        name: name.to_string(),
+        material: None,
+        face_materials: BTreeMap::new(),
+        assembly: None,
+        first_face: None,
+    }
+}
+
+/// A registry of elements
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ElementRegistry {
+    /// Unique identifier for the registry
+    pub id: Uuid,
+    /// The elements in the registry
+    pub elements: HashMap<Uuid, Element>,
+}
+
+impl ElementRegistry {
+    /// Create a new element registry
+    #[must_use]
+    pub fn create_new() -> Self {
+        Self {
+            id: new_id(),
+            elements: HashMap::new(),
+        }
+    }
+
+    /// Declare, store, and return the ID of an element
+    pub fn create_and_store(&mut self, kind: ElementKind, solid: &Uuid, name: &str) -> Uuid {
+        let element = new_element(kind, solid, name);
+        let id = element.id;
+        self.elements.insert(id, element);
+        id
+    }
+
+    /// Remove an element from the registry
+    pub fn remove(&mut self, id: &Uuid) {
+        self.elements.remove(id);
+    }
+
+    /// Get a reference to an element by ID
+    #[must_use]
+    pub fn get(&self, id: &Uuid) -> Option<&Element> {
+        self.elements.get(id)
+    }
+
+    /// Get a mutable reference to an element by ID
+    pub fn get_mut(&mut self, id: &Uuid) -> Option<&mut Element> {
+        self.elements.get_mut(id)
+    }
+
+    /// The elements in ID order
+    #[must_use]
+    pub fn sorted(&self) -> Vec<&Element> {
+        sorted_by_id(&self.elements)
+    }
+
+    /// The element backed by a solid, if any, the first by ID if several
+    /// share it
+    #[must_use]
+    pub fn element_of_solid(&self, solid_id: &Uuid) -> Option<&Element> {
+        self.elements
+            .values()
+            .filter(|element| element.solid == *solid_id)
+            .min_by_key(|element| element.id)
+    }
+
+    /// Get a mutable reference to the element backed by a solid, chosen
+    /// as in `element_of_solid`
+    pub fn element_of_solid_mut(&mut self, solid_id: &Uuid) -> Option<&mut Element> {
+        let id = self.element_of_solid(solid_id)?.id;
+        self.elements.get_mut(&id)
+    }
+}
@@ -0,0 +1,322 @@
+/// Thermal zones and surfaces for energy models
+///
+/// Each space is a thermal zone, and each face of a space's solid is a
+/// surface of that zone. A surface's construction is the wall, slab or
+/// other element just outside the face, and the zone on its far side, if
+/// any, is found by stepping through that element. Surfaces between two
+/// zones are kept once, so a shared wall or floor is not counted twice.
+/// Windows and doors become openings in the surface nearest to them.
+use crate::domain::geometry::{newell_normal, point_in_loop, polygon_area, Bounds, Plane};
+use crate::domain::{
+    detect_spaces, ElementKind, ElementRegistry, GeometryRegistry, Point, Space, Vector,
+};
+use uuid::Uuid;
+
+/// Distance stepped past a face when probing what lies beyond it
+const PROBE: f32 = 0.01;
+/// Height above the lowest floor still counted as on grade
+const GROUND_TOLERANCE: f32 = 0.05;
+/// Furthest an opening's centre may sit from the surface it belongs to
+const MAX_OPENING_DEPTH: f32 = 1.0;
+
+/// What a thermal surface separates
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SurfaceKind {
+    /// A wall between a zone and outdoors
+    ExteriorWall,
+    /// A wall between two zones
+    InteriorWall,
+    /// The top of a zone with nothing above
+    Roof,
+    /// A floor between two zones, kept with the zone above
+    InteriorFloor,
+    /// The floor of a zone on the ground
+    SlabOnGrade,
+    /// The floor of a zone over outdoor air
+    RaisedFloor,
+    /// An open boundary between two zones with no element in it
+    Air,
+}
+
+impl SurfaceKind {
+    /// Human-readable name of the kind
+    #[must_use]
+    pub fn label(self) -> &'static str {
+        match self {
+            SurfaceKind::ExteriorWall => "Exterior wall",
+            SurfaceKind::InteriorWall => "Interior wall",
+            SurfaceKind::Roof => "Roof",
+            SurfaceKind::InteriorFloor => "Interior floor",
+            SurfaceKind::SlabOnGrade => "Slab on grade",
+            SurfaceKind::RaisedFloor => "Raised floor",
+            SurfaceKind::Air => "Air",
+        }
+    }
+}
+
+/// A window or door in a thermal surface
+#[derive(Debug, Clone)]
+pub struct ThermalOpening {
+    /// The window or door element
+    pub element: Uuid,
+    /// Whether the element is a window or a door
+    pub kind: ElementKind,
+    /// The opening's largest face, projected onto the surface
+    pub points: Vec<Point>,
+}
+
+/// A face of a thermal zone
+#[derive(Debug, Clone)]
+pub struct ThermalSurface {
+    /// What the surface separates
+    pub kind: SurfaceKind,
+    /// The face, wound outward from the first zone
+    pub points: Vec<Point>,
+    /// The space elements on either side, the owning zone first
+    pub spaces: Vec<Uuid>,
+    /// The element forming the surface, if there is one
+    pub element: Option<Uuid>,
+    /// Windows and doors in the surface
+    pub openings: Vec<ThermalOpening>,
+}
+
+impl ThermalSurface {
+    /// Area of the surface, openings included
+    #[must_use]
+    pub fn area(&self) -> f32 {
+        polygon_area(&self.points)
+    }
+}
+
+/// A space as a thermal zone
+#[derive(Debug, Clone)]
+pub struct ThermalZone {
+    /// The space the zone is made from
+    pub space: Space,
+    /// The faces of the space's solid
+    pub shell: Vec<Vec<Point>>,
+}
+
+/// Thermal zones and their surfaces
+#[derive(Debug, Clone, Default)]
+pub struct EnergyModel {
+    /// One zone per space, sorted by space name
+    pub zones: Vec<ThermalZone>,
+    /// The zone surfaces, each shared surface once
+    pub surfaces: Vec<ThermalSurface>,
+}
+
+/// A point moved along a direction
+fn offset(point: &Point, direction: &Vector, distance: f32) -> Point {
+    Point {
+        x: point.x + direction.x * distance,
+        y: point.y + direction.y * distance,
+        z: point.z + direction.z * distance,
+    }
+}
+
+/// Whether a box holds a point
+fn contains(bounds: &Bounds, point: &Point) -> bool {
+    (bounds.min.x..=bounds.max.x).contains(&point.x)
+        && (bounds.min.y..=bounds.max.y).contains(&point.y)
+        && (bounds.min.z..=bounds.max.z).contains(&point.z)
+}
+
+/// How far a ray from a point inside a box travels before leaving it
+fn exit_distance(bounds: &Bounds, start: &Point, direction: &Vector) -> f32 {
+    [
+        (direction.x, start.x, bounds.min.x, bounds.max.x),
+        (direction.y, start.y, bounds.min.y, bounds.max.y),
+        (direction.z, start.z, bounds.min.z, bounds.max.z),
+    ]
+    .into_iter()
+    .filter(|(component, ..)| component.abs() > f32::EPSILON)
+    .map(|(component, start, min, max)| {
+        if component > 0.0 {
+            (max - start) / component
+        } else {
+            (min - start) / component
+        }
+    })
+    .fold(f32::INFINITY, f32::min)
+}
+
+/// The zone whose shell holds a point just inside one of its faces
+fn zone_at(zones: &[(ThermalZone, Vec<Plane>)], point: &Point, skip: Uuid) -> Option<Uuid> {
+    zones
+        .iter()
+        .filter(|(zone, _)| zone.space.element != skip)
+        .find(|(zone, planes)| {
+            zone.shell.iter().zip(planes).any(|(points, plane)| {
+                let distance = plane.signed_distance(point);
+                (-2.0 * PROBE..=0.0).contains(&distance)
+                    && point_in_loop(&plane.project(point), points, &plane.normal)
+            })
+        })
+        .map(|(zone, _)| zone.space.element)
+}
+
+/// Add each window and door to the nearest surface it sits in
+fn place_openings(
+    element_registry: &ElementRegistry,
+    geometry_registry: &GeometryRegistry,
+    surfaces: &mut [(ThermalSurface, Plane, f32)],
+) {
+    let mut openings: Vec<_> = element_registry
+        .elements
+        .values()
+        .filter(|element| matches!(element.kind, ElementKind::Window | ElementKind::Door))
+        .collect();
+    openings.sort_by_key(|element| element.id);
+    for element in openings {
+        let Some(loops) = geometry_registry.solid_loops(&element.solid) else {
+            continue;
+        };
+        let Some(bounds) = Bounds::from_points(&loops.concat()) else {
+            continue;
+        };
+        let Some(face) = loops
+            .iter()
+            .max_by(|a, b| polygon_area(a).total_cmp(&polygon_area(b)))
+        else {
+            continue;
+        };
+        let center = Point {
+            x: bounds.min.x.midpoint(bounds.max.x),
+            y: bounds.min.y.midpoint(bounds.max.y),
+            z: bounds.min.z.midpoint(bounds.max.z),
+        };
+        let host = surfaces
+            .iter_mut()
+            .filter(|(_, plane, thickness)| {
+                plane.signed_distance(&center).abs() <= thickness.max(MAX_OPENING_DEPTH)
+            })
+            .filter(|(surface, plane, _)| {
+                point_in_loop(&plane.project(&center), &surface.points, &plane.normal)
+            })
+            .min_by(|(_, a, _), (_, b, _)| {
+                a.signed_distance(&center)
+                    .abs()
+                    .total_cmp(&b.signed_distance(&center).abs())
+            });
+        if let Some((surface, plane, _)) = host {
+            // Openings are wound the same way as the surface holding them
+            let mut points: Vec<Point> = face.iter().map(|point| plane.project(point)).collect();
+            if newell_normal(&points).dot(&plane.normal) < 0.0 {
+                points.reverse();
+            }
+            surface.openings.push(ThermalOpening {
+                element: element.id,
+                kind: element.kind,
+                points,
+            });
+        }
+    }
+}
+
+/// Derive thermal zones from the spaces and surfaces from their faces
+///
+/// Walls, slabs and generic elements form surfaces; windows and doors
+/// become openings. Space solids are expected to be wound outward.
+#[must_use]
+#[tracing::instrument(skip_all)]
+pub fn derive_energy_model(
+    element_registry: &ElementRegistry,
+    geometry_registry: &GeometryRegistry,
+) -> EnergyModel {
+    let zones: Vec<(ThermalZone, Vec<Plane>)> = detect_spaces(element_registry, geometry_registry)
+        .into_iter()
+        .filter_map(|space| {
+            let shell = geometry_registry.solid_loops(&space.solid)?;
+            let planes = shell
+                .iter()
+                .map(|points| Plane::from_loop(points))
+                .collect::<Option<Vec<_>>>()?;
+            Some((ThermalZone { space, shell }, planes))
+        })
+        .collect();
+    let ground = zones
+        .iter()
+        .flat_map(|(zone, _)| zone.space.floors.iter().flatten())
+        .map(|point| point.y)
+        .fold(f32::INFINITY, f32::min);
+
+    let mut constructions: Vec<(Uuid, Bounds)> = element_registry
+        .elements
+        .values()
+        .filter(|element| {
+            matches!(
+                element.kind,
+                ElementKind::Wall | ElementKind::Slab | ElementKind::Generic
+            )
+        })
+        .filter_map(|element| {
+            let loops = geometry_registry.solid_loops(&element.solid)?;
+            Some((element.id, Bounds::from_points(&loops.concat())?))
+        })
+        .collect();
+    constructions.sort_by_key(|(id, _)| *id);
+
+    let vertical = std::f32::consts::FRAC_1_SQRT_2;
+    let mut surfaces = Vec::new();
+    for (zone, planes) in &zones {
+        let owner = zone.space.element;
+        for (points, plane) in zone.shell.iter().zip(planes) {
+            let normal = &plane.normal;
+            let probe = offset(&plane.point, normal, PROBE);
+            let construction = constructions
+                .iter()
+                .find(|(_, bounds)| contains(bounds, &probe));
+            let thickness = construction.map_or(0.0, |(_, bounds)| {
+                exit_distance(bounds, &probe, normal) + PROBE
+            });
+            let beyond = offset(&plane.point, normal, thickness + PROBE);
+            let neighbour = zone_at(&zones, &beyond, owner);
+            let kind = match (neighbour, construction) {
+                // Shared walls are kept by the zone with the smaller ID and
+                // shared floors by the zone above
+                (Some(other), _) if normal.y.abs() < vertical && other < owner => continue,
+                (Some(_), _) if normal.y >= vertical => continue,
+                (Some(_), None) => SurfaceKind::Air,
+                (Some(_), Some(_)) if normal.y <= -vertical => SurfaceKind::InteriorFloor,
+                (Some(_), Some(_)) => SurfaceKind::InteriorWall,
+                (None, _) if normal.y >= vertical => SurfaceKind::Roof,
+                (None, _) if normal.y <= -vertical => {
+                    if plane.point.y <= ground + GROUND_TOLERANCE {
+                        SurfaceKind::SlabOnGrade
+                    } else {
+                        SurfaceKind::RaisedFloor
+                    }
+                }
+                (None, _) => SurfaceKind::ExteriorWall,
+            };
+            surfaces.push((
+                ThermalSurface {
+                    kind,
+                    points: points.clone(),
+                    spaces: std::iter::once(owner).chain(neighbour).collect(),
+                    element: construction.map(|(id, _)| *id),
+                    openings: Vec::new(),
+                },
+                plane.clone(),
+                thickness,
+            ));
+        }
+    }
+
+    place_openings(element_registry, geometry_registry, &mut surfaces);
+
+    let model = EnergyModel {
+        zones: zones.into_iter().map(|(zone, _)| zone).collect(),
+        surfaces: surfaces
+            .into_iter()
+            .map(|(surface, _, _)| surface)
+            .collect(),
+    };
+    tracing::info!(
+        zones = model.zones.len(),
+        surfaces = model.surfaces.len(),
+        "derived energy model"
+    );
+    model
+}
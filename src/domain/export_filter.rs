@@ -0,0 +1,128 @@
+/// Export filters and the solids they collect
+///
+/// Every exporter asks the same question: which solids go in the file,
+/// and which element each one is. An export filter answers it once for
+/// all of them, by phase, by element kind, and by an optional set of
+/// solids that narrowing to the selection or to some layers (tiers)
+/// builds, less any solids left out, such as those hidden in the
+/// viewport. Solids without an element count as generic elements.
+use crate::domain::{
+    Element, ElementKind, ElementRegistry, GeometryRegistry, PhaseFilter, Solid, TierRegistry,
+};
+use std::collections::BTreeSet;
+use uuid::Uuid;
+
+/// Which solids an export includes
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ExportFilter {
+    /// Phases to export
+    pub phases: PhaseFilter,
+    /// Element kinds to export, or None for every kind
+    pub kinds: Option<Vec<ElementKind>>,
+    /// The only solids that may be exported, or None for every solid
+    pub solids: Option<BTreeSet<Uuid>>,
+    /// Solids left out whatever else matches
+    pub excluded: BTreeSet<Uuid>,
+}
+
+impl From<PhaseFilter> for ExportFilter {
+    fn from(phases: PhaseFilter) -> Self {
+        Self {
+            phases,
+            ..Self::default()
+        }
+    }
+}
+
+/// A solid an export includes, with its element if it has one
+#[derive(Clone, Copy)]
+pub struct ExportSolid<'a> {
+    /// The solid
+    pub solid: &'a Solid,
+    /// The element backed by the solid
+    pub element: Option<&'a Element>,
+}
+
+impl ExportSolid<'_> {
+    /// The element's kind, or generic for a bare solid
+    #[must_use]
+    pub fn kind(&self) -> ElementKind {
+        self.element
+            .map_or(ElementKind::Generic, |element| element.kind)
+    }
+
+    /// The element's name, or "Solid" for a bare solid
+    #[must_use]
+    pub fn name(&self) -> &str {
+        self.element
+            .map_or("Solid", |element| element.name.as_str())
+    }
+}
+
+impl ExportFilter {
+    /// Narrow the export to some solids, such as the selection
+    pub fn only(&mut self, solids: impl IntoIterator<Item = Uuid>) {
+        let solids: BTreeSet<Uuid> = solids.into_iter().collect();
+        self.solids = Some(match self.solids.take() {
+            Some(current) => current.intersection(&solids).copied().collect(),
+            None => solids,
+        });
+    }
+
+    /// Narrow the export to the solids on some layers or the layers below
+    /// them
+    pub fn on_layers(&mut self, tier_registry: &TierRegistry, layers: &[Uuid]) {
+        let solids: Vec<Uuid> = tier_registry
+            .tiers
+            .values()
+            .filter(|tier| {
+                tier_registry
+                    .lineage(&tier.id)
+                    .iter()
+                    .any(|id| layers.contains(id))
+            })
+            .flat_map(|tier| tier.geometry.iter().copied())
+            .collect();
+        self.only(solids);
+    }
+
+    /// Leave some solids out, such as those hidden in the viewport
+    pub fn exclude(&mut self, solids: impl IntoIterator<Item = Uuid>) {
+        self.excluded.extend(solids);
+    }
+
+    /// Whether a solid with an element, if any, is exported
+    #[must_use]
+    pub fn includes(&self, solid: &Solid, element: Option<&Element>) -> bool {
+        let kind = element.map_or(ElementKind::Generic, |element| element.kind);
+        self.phases.includes(solid.phase)
+            && !self.excluded.contains(&solid.id)
+            && self
+                .solids
+                .as_ref()
+                .is_none_or(|solids| solids.contains(&solid.id))
+            && self
+                .kinds
+                .as_ref()
+                .is_none_or(|kinds| kinds.contains(&kind))
+    }
+}
+
+/// The solids a filter exports, in ID order, each with its element
+#[must_use]
+pub fn collect_export_solids<'a>(
+    geometry_registry: &'a GeometryRegistry,
+    element_registry: &'a ElementRegistry,
+    filter: &ExportFilter,
+) -> Vec<ExportSolid<'a>> {
+    geometry_registry
+        .solids
+        .sorted()
+        .into_iter()
+        .map(|solid| ExportSolid {
+            solid,
+            element: element_registry.element_of_solid(&solid.id),
+        })
+        .filter(|export| filter.includes(export.solid, export.element))
+        .collect()
+}
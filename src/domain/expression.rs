@@ -0,0 +1,334 @@
+/// Arithmetic expressions over the properties of named objects
+///
+/// An expression is written the way a designer would say it, such as
+/// `level_1.elevation + 0.9`: numbers, `+`, `-`, `*`, `/`, brackets and
+/// references to a property of an object, written as the object's name
+/// and the property joined by a dot.
+///
+/// Names are matched ignoring case, with every run of characters other
+/// than letters and digits read as an underscore, so `level_1` names the
+/// level called "Level 1" and `column_a_1` the element "Column A-1".
+/// What the properties of an object are is up to whoever evaluates the
+/// expression.
+use std::fmt;
+
+/// Why an expression could not be read or evaluated
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExpressionError {
+    /// The text is not an expression
+    Syntax {
+        /// Where reading stopped, in characters from the start
+        position: usize,
+        /// What was expected there
+        message: String,
+    },
+    /// No object has the name
+    UnknownObject(String),
+    /// Several objects have the name
+    Ambiguous(String),
+    /// The object has no such property
+    UnknownProperty(PropertyRef),
+    /// The property can be read but not set
+    ReadOnly(PropertyRef),
+    /// The value would put the object somewhere it cannot be
+    OutOfRange(PropertyRef),
+    /// Something was divided by zero
+    DivisionByZero,
+    /// Several constraints set the same property
+    Overconstrained(PropertyRef),
+    /// Constraints depend on each other in a loop; the properties they set
+    Cycle(Vec<PropertyRef>),
+}
+
+impl fmt::Display for ExpressionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExpressionError::Syntax { position, message } => {
+                write!(f, "Expected {message} at character {}", position + 1)
+            }
+            ExpressionError::UnknownObject(name) => write!(f, "Nothing is called {name}"),
+            ExpressionError::Ambiguous(name) => write!(f, "Several things are called {name}"),
+            ExpressionError::UnknownProperty(property) => write!(f, "There is no {property}"),
+            ExpressionError::ReadOnly(property) => write!(f, "{property} cannot be set"),
+            ExpressionError::OutOfRange(property) => write!(f, "{property} is out of range"),
+            ExpressionError::DivisionByZero => write!(f, "Division by zero"),
+            ExpressionError::Overconstrained(property) => {
+                write!(f, "{property} is set by more than one constraint")
+            }
+            ExpressionError::Cycle(properties) => {
+                let names: Vec<String> = properties.iter().map(ToString::to_string).collect();
+                write!(f, "{} depend on each other", names.join(", "))
+            }
+        }
+    }
+}
+
+impl std::error::Error for ExpressionError {}
+
+/// The key a name is matched by: lowercase, with every run of characters
+/// other than letters and digits turned into one underscore
+#[must_use]
+pub fn name_key(name: &str) -> String {
+    let mut key = String::with_capacity(name.len());
+    for character in name.trim().chars() {
+        if character.is_alphanumeric() {
+            key.extend(character.to_lowercase());
+        } else if !key.ends_with('_') {
+            key.push('_');
+        }
+    }
+    key
+}
+
+/// A property of a named object
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PropertyRef {
+    /// The object's name, as its key
+    pub object: String,
+    /// The property's name, as its key
+    pub property: String,
+}
+
+impl PropertyRef {
+    /// A reference to a property, with both names made keys
+    #[must_use]
+    pub fn new(object: &str, property: &str) -> Self {
+        Self {
+            object: name_key(object),
+            property: name_key(property),
+        }
+    }
+}
+
+impl fmt::Display for PropertyRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.object, self.property)
+    }
+}
+
+/// An arithmetic operator between two expressions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOperator {
+    /// Addition
+    Add,
+    /// Subtraction
+    Subtract,
+    /// Multiplication
+    Multiply,
+    /// Division
+    Divide,
+}
+
+/// A parsed expression
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expression {
+    /// A number
+    Number(f32),
+    /// The value of a property
+    Property(PropertyRef),
+    /// The negative of an expression
+    Negate(Box<Expression>),
+    /// Two expressions combined by an operator
+    Binary(BinaryOperator, Box<Expression>, Box<Expression>),
+}
+
+impl Expression {
+    /// Read an expression from text
+    ///
+    /// # Errors
+    /// Returns a syntax error where the text stops being an expression
+    pub fn parse(text: &str) -> Result<Expression, ExpressionError> {
+        let mut parser = Parser {
+            characters: text.chars().collect(),
+            position: 0,
+        };
+        let expression = parser.sum()?;
+        parser.skip_spaces();
+        if parser.position < parser.characters.len() {
+            return Err(parser.expected("an operator"));
+        }
+        Ok(expression)
+    }
+
+    /// The properties the expression reads, in the order written, each
+    /// once
+    #[must_use]
+    pub fn references(&self) -> Vec<&PropertyRef> {
+        let mut references = Vec::new();
+        let mut pending = vec![self];
+        while let Some(expression) = pending.pop() {
+            match expression {
+                Expression::Number(_) => {}
+                Expression::Property(property) => {
+                    if !references.contains(&property) {
+                        references.push(property);
+                    }
+                }
+                Expression::Negate(inner) => pending.push(inner),
+                Expression::Binary(_, left, right) => {
+                    pending.push(right);
+                    pending.push(left);
+                }
+            }
+        }
+        references
+    }
+
+    /// The expression's value, reading properties through `lookup`
+    ///
+    /// # Errors
+    /// Returns the first error `lookup` gives, or a division by zero
+    pub fn evaluate(
+        &self,
+        lookup: &impl Fn(&PropertyRef) -> Result<f32, ExpressionError>,
+    ) -> Result<f32, ExpressionError> {
+        match self {
+            Expression::Number(value) => Ok(*value),
+            Expression::Property(property) => lookup(property),
+            Expression::Negate(inner) => Ok(-inner.evaluate(lookup)?),
+            Expression::Binary(operator, left, right) => {
+                let (left, right) = (left.evaluate(lookup)?, right.evaluate(lookup)?);
+                match operator {
+                    BinaryOperator::Add => Ok(left + right),
+                    BinaryOperator::Subtract => Ok(left - right),
+                    BinaryOperator::Multiply => Ok(left * right),
+                    BinaryOperator::Divide if right == 0.0 => Err(ExpressionError::DivisionByZero),
+                    BinaryOperator::Divide => Ok(left / right),
+                }
+            }
+        }
+    }
+}
+
+/// Reads an expression by recursive descent
+struct Parser {
+    /// The text's characters
+    characters: Vec<char>,
+    /// The next character to read
+    position: usize,
+}
+
+impl Parser {
+    /// A syntax error at the current position
+    fn expected(&self, message: &str) -> ExpressionError {
+        ExpressionError::Syntax {
+            position: self.position,
+            message: message.to_string(),
+        }
+    }
+
+    /// Skip spaces before the next token
+    fn skip_spaces(&mut self) {
+        while self
+            .characters
+            .get(self.position)
+            .is_some_and(|character| character.is_whitespace())
+        {
+            self.position += 1;
+        }
+    }
+
+    /// The next character after any spaces, taken if it is one of `wanted`
+    fn take(&mut self, wanted: &[char]) -> Option<char> {
+        self.skip_spaces();
+        let next = *self.characters.get(self.position)?;
+        if wanted.contains(&next) {
+            self.position += 1;
+            Some(next)
+        } else {
+            None
+        }
+    }
+
+    /// Terms added or subtracted
+    fn sum(&mut self) -> Result<Expression, ExpressionError> {
+        let mut expression = self.product()?;
+        while let Some(sign) = self.take(&['+', '-']) {
+            let operator = if sign == '+' {
+                BinaryOperator::Add
+            } else {
+                BinaryOperator::Subtract
+            };
+            expression =
+                Expression::Binary(operator, Box::new(expression), Box::new(self.product()?));
+        }
+        Ok(expression)
+    }
+
+    /// Factors multiplied or divided
+    fn product(&mut self) -> Result<Expression, ExpressionError> {
+        let mut expression = self.factor()?;
+        while let Some(sign) = self.take(&['*', '/']) {
+            let operator = if sign == '*' {
+                BinaryOperator::Multiply
+            } else {
+                BinaryOperator::Divide
+            };
+            expression =
+                Expression::Binary(operator, Box::new(expression), Box::new(self.factor()?));
+        }
+        Ok(expression)
+    }
+
+    /// A number, a property, a negated factor or a bracketed sum
+    fn factor(&mut self) -> Result<Expression, ExpressionError> {
+        if self.take(&['-']).is_some() {
+            return Ok(Expression::Negate(Box::new(self.factor()?)));
+        }
+        if self.take(&['(']).is_some() {
+            let inner = self.sum()?;
+            if self.take(&[')']).is_none() {
+                return Err(self.expected("a closing bracket"));
+            }
+            return Ok(inner);
+        }
+        let next = self.characters.get(self.position).copied();
+        match next {
+            Some(character) if character.is_ascii_digit() || character == '.' => self.number(),
+            Some(character) if character.is_alphanumeric() || character == '_' => {
+                let object = self.name();
+                if self.take(&['.']).is_none() {
+                    return Err(self.expected("a dot and a property"));
+                }
+                self.skip_spaces();
+                let property = self.name();
+                if property.is_empty() {
+                    return Err(self.expected("a property"));
+                }
+                Ok(Expression::Property(PropertyRef::new(&object, &property)))
+            }
+            _ => Err(self.expected("a number or a property")),
+        }
+    }
+
+    /// A number in decimal
+    fn number(&mut self) -> Result<Expression, ExpressionError> {
+        let start = self.position;
+        while self
+            .characters
+            .get(self.position)
+            .is_some_and(|character| character.is_ascii_digit() || *character == '.')
+        {
+            self.position += 1;
+        }
+        let text: String = self.characters[start..self.position].iter().collect();
+        text.parse().map(Expression::Number).map_err(|_| {
+            self.position = start;
+            self.expected("a number")
+        })
+    }
+
+    /// A name of letters, digits and underscores
+    fn name(&mut self) -> String {
+        let start = self.position;
+        while self
+            .characters
+            .get(self.position)
+            .is_some_and(|character| character.is_alphanumeric() || *character == '_')
+        {
+            self.position += 1;
+        }
+        self.characters[start..self.position].iter().collect()
+    }
+}
@@ -0,0 +1,190 @@
+/// Define the external ID table mapping registry items to other models
+///
+/// Models exchanged with consultants name their elements in their own
+/// way: an IFC file by `GlobalId`, a DXF drawing by entity handle. The
+/// table remembers which registry item each imported one became, so
+/// importing an updated version of the same file finds the items it made
+/// last time and updates them instead of adding a second copy.
+///
+/// One external element can become several registry items, such as a
+/// polyline split into segments, so every item records the part of its
+/// element it came from.
+///
+/// The table also keeps where each item's points were when last imported.
+/// Comparing that with the model tells the items edited here apart from
+/// those still as imported, which a refresh can safely update.
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::domain::geometry::distance;
+use crate::domain::{new_id, Point};
+
+/// The kind of file an external ID comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ExternalSource {
+    /// An IFC model, whose elements are named by `GlobalId`
+    Ifc,
+    /// A DXF drawing, whose entities are named by handle
+    Dxf,
+}
+
+impl ExternalSource {
+    /// Every kind of source
+    pub const ALL: [ExternalSource; 2] = [ExternalSource::Ifc, ExternalSource::Dxf];
+
+    /// Name of the source, as shown and saved
+    #[must_use]
+    pub fn label(self) -> &'static str {
+        match self {
+            ExternalSource::Ifc => "IFC",
+            ExternalSource::Dxf => "DXF",
+        }
+    }
+}
+
+/// The name an item has in the model it was imported from
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ExternalId {
+    /// The kind of file the item came from
+    pub source: ExternalSource,
+    /// The file the item came from, since handles are only unique within
+    /// one drawing
+    pub file: String,
+    /// The element's `GlobalId` or handle
+    pub key: String,
+    /// Which part of the element the item is, counted from zero
+    pub part: usize,
+}
+
+/// A table of the external IDs of registry items
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ExternalIdMap {
+    /// Unique identifier for the table
+    pub id: Uuid,
+    /// The external ID of each mapped registry item
+    pub entries: HashMap<Uuid, ExternalId>,
+    /// Where each mapped item's points were when it was last imported
+    pub imported: HashMap<Uuid, Vec<Point>>,
+}
+
+impl ExternalIdMap {
+    /// Create a new, empty table
+    #[must_use]
+    pub fn create_new() -> Self {
+        Self {
+            id: new_id(),
+            entries: HashMap::new(),
+            imported: HashMap::new(),
+        }
+    }
+
+    /// Record the external ID of a registry item, replacing any it had
+    pub fn assign(&mut self, item: Uuid, external: ExternalId) {
+        self.entries.insert(item, external);
+    }
+
+    /// Forget a registry item's external ID and where it was imported
+    pub fn remove(&mut self, item: &Uuid) {
+        self.entries.remove(item);
+        self.imported.remove(item);
+    }
+
+    /// Whether an item was edited here since it was last imported: its
+    /// points, in any order, are no longer within tolerance of where the
+    /// import put them
+    ///
+    /// Items imported before their points were kept count as unedited.
+    #[must_use]
+    pub fn edited_locally(&self, item: &Uuid, points: &[Point], tolerance: f32) -> bool {
+        self.imported
+            .get(item)
+            .is_some_and(|imported| !same_points(imported, points, tolerance))
+    }
+
+    /// Get the external ID of a registry item
+    #[must_use]
+    pub fn get(&self, item: &Uuid) -> Option<&ExternalId> {
+        self.entries.get(item)
+    }
+
+    /// The registry item an external ID was imported as, if any
+    ///
+    /// Should two items claim the same external ID, the one with the
+    /// smallest ID is returned, so the answer does not change between runs.
+    #[must_use]
+    pub fn find(&self, external: &ExternalId) -> Option<Uuid> {
+        self.entries
+            .iter()
+            .filter(|(_, entry)| *entry == external)
+            .map(|(item, _)| *item)
+            .min()
+    }
+
+    /// The registry items imported from a file, by external ID
+    #[must_use]
+    pub fn from_file(&self, source: ExternalSource, file: &str) -> HashMap<ExternalId, Uuid> {
+        let mut items: Vec<_> = self
+            .entries
+            .iter()
+            .filter(|(_, entry)| entry.source == source && entry.file == file)
+            .collect();
+        // Larger IDs first, so the smallest claiming an external ID wins
+        items.sort_by(|a, b| b.0.cmp(a.0));
+        items
+            .into_iter()
+            .map(|(item, entry)| (entry.clone(), *item))
+            .collect()
+    }
+
+    /// The files of a kind that items were imported from, sorted
+    #[must_use]
+    pub fn files(&self, source: ExternalSource) -> Vec<&str> {
+        let mut files: Vec<&str> = self
+            .entries
+            .values()
+            .filter(|entry| entry.source == source)
+            .map(|entry| entry.file.as_str())
+            .collect();
+        files.sort_unstable();
+        files.dedup();
+        files
+    }
+
+    /// The IFC `GlobalId` of each item imported whole from an IFC model,
+    /// for an IFC export to write back
+    #[must_use]
+    pub fn global_ids(&self) -> HashMap<Uuid, String> {
+        self.entries
+            .iter()
+            .filter(|(_, entry)| entry.source == ExternalSource::Ifc && entry.part == 0)
+            .map(|(item, entry)| (*item, entry.key.clone()))
+            .collect()
+    }
+
+    /// Drop the entries whose registry items no longer exist
+    pub fn retain_existing(&mut self, exists: impl Fn(&Uuid) -> bool) {
+        self.entries.retain(|item, _| exists(item));
+        self.imported.retain(|item, _| exists(item));
+    }
+
+    /// The mapped items sorted by registry ID
+    #[must_use]
+    pub fn sorted(&self) -> Vec<(&Uuid, &ExternalId)> {
+        let mut entries: Vec<_> = self.entries.iter().collect();
+        entries.sort_by_key(|(item, _)| **item);
+        entries
+    }
+}
+
+/// Whether two lists of points hold the same points within tolerance, in
+/// any order
+#[must_use]
+pub fn same_points(a: &[Point], b: &[Point], tolerance: f32) -> bool {
+    a.len() == b.len()
+        && a.iter()
+            .all(|point| b.iter().any(|other| distance(point, other) <= tolerance))
+        && b.iter()
+            .all(|point| a.iter().any(|other| distance(point, other) <= tolerance))
+}
@@ -0,0 +1,574 @@
+/// Parametric door and window families hosted in walls
+///
+/// A family is a door or window generated from a few parameters: its
+/// width and height, how high its sill is, how many panels fill it, how
+/// wide its frame is and which way it swings. Placed in a wall, it cuts
+/// an opening through the wall and fills it with its frame and panels.
+///
+/// A hosted family keeps its place relative to its wall rather than in the
+/// world. Every vertex of the opening, the frame and the panels is
+/// anchored at a distance along the wall from the opening's center, a
+/// height above the wall's base and a position across the wall, and when
+/// the wall moves or changes shape the anchors put them back in place.
+///
+/// Positions in a host wall are given as along, up and across: along the
+/// wall's center line from the opening's center, up from the wall's base,
+/// and across from the center line toward the wall's first face.
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::domain::{new_id, sorted_by_id, ElementKind};
+
+/// Thickness of door leaves and window sashes in meters
+pub const PANEL_THICKNESS: f32 = 0.04;
+
+/// Number of straight lines drawing a door's quarter-circle swing
+const SWING_ARC_SEGMENTS: usize = 8;
+
+/// Which way a door or window opens
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Swing {
+    /// Does not open
+    #[default]
+    Fixed,
+    /// Panels are hinged on their left, seen from the wall's first face
+    Left,
+    /// Panels are hinged on their right, seen from the wall's first face
+    Right,
+}
+
+impl Swing {
+    /// Every swing
+    pub const ALL: [Swing; 3] = [Swing::Fixed, Swing::Left, Swing::Right];
+
+    /// Human-readable name of the swing
+    #[must_use]
+    pub fn label(self) -> &'static str {
+        match self {
+            Swing::Fixed => "Fixed",
+            Swing::Left => "Left",
+            Swing::Right => "Right",
+        }
+    }
+}
+
+/// The parameters a door or window is generated from
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FamilyParameters {
+    /// Door or window
+    pub kind: ElementKind,
+    /// Width of the opening in meters, frame included
+    pub width: f32,
+    /// Height of the opening in meters, frame included
+    pub height: f32,
+    /// Height of the bottom of the opening above the wall's base
+    pub sill_height: f32,
+    /// How many panels fill the frame side by side
+    pub panel_count: usize,
+    /// Width of the frame's members, seen in elevation
+    pub frame_width: f32,
+    /// Which way the panels open
+    pub swing: Swing,
+}
+
+impl FamilyParameters {
+    /// A single door hinged on the left
+    #[must_use]
+    pub fn door() -> Self {
+        Self {
+            kind: ElementKind::Door,
+            width: 0.9,
+            height: 2.1,
+            sill_height: 0.0,
+            panel_count: 1,
+            frame_width: 0.05,
+            swing: Swing::Left,
+        }
+    }
+
+    /// A fixed window of two panes
+    #[must_use]
+    pub fn window() -> Self {
+        Self {
+            kind: ElementKind::Window,
+            width: 1.2,
+            height: 1.2,
+            sill_height: 0.9,
+            panel_count: 2,
+            frame_width: 0.06,
+            swing: Swing::Fixed,
+        }
+    }
+
+    /// Whether the frame has a bottom member; doors have none, so the
+    /// floor runs through them
+    #[must_use]
+    pub fn has_sill_member(&self) -> bool {
+        self.kind != ElementKind::Door
+    }
+
+    /// The clear opening inside the frame, as along and up ranges from the
+    /// opening's center and the wall's base
+    #[must_use]
+    pub fn clear_opening(&self) -> [[f32; 2]; 2] {
+        let bottom = if self.has_sill_member() {
+            self.sill_height + self.frame_width
+        } else {
+            self.sill_height
+        };
+        [
+            [
+                -self.width / 2.0 + self.frame_width,
+                self.width / 2.0 - self.frame_width,
+            ],
+            [bottom, self.sill_height + self.height - self.frame_width],
+        ]
+    }
+
+    /// The along range of each panel, from the opening's start end
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn panel_ranges(&self) -> Vec<[f32; 2]> {
+        let [along, _] = self.clear_opening();
+        let count = self.panel_count.max(1);
+        let step = (along[1] - along[0]) / count as f32;
+        (0..count)
+            .map(|index| {
+                let start = along[0] + step * index as f32;
+                [start, start + step]
+            })
+            .collect()
+    }
+}
+
+/// Where a hosted vertex sits across its wall
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Across {
+    /// On one of the wall's faces, 0 for the first and 1 for the second,
+    /// following it as the wall changes thickness
+    Face(usize),
+    /// At a distance from the wall's center line toward its first face
+    Offset(f32),
+}
+
+/// A vertex held in place relative to its host wall
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HostAnchor {
+    /// The vertex
+    pub vertex: Uuid,
+    /// Distance along the wall from the opening's center
+    pub along: f32,
+    /// Height above the wall's base
+    pub up: f32,
+    /// Where the vertex sits across the wall
+    pub across: Across,
+}
+
+/// A door or window placed in a wall
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HostedFamily {
+    /// Unique identifier of the placement
+    pub id: Uuid,
+    /// The parameters the family was generated from
+    pub parameters: FamilyParameters,
+    /// The wall element the family is hosted in
+    pub host: Uuid,
+    /// Distance of the opening's center from the start of the wall's
+    /// center line
+    pub offset: f32,
+    /// The wall's direction when last placed, as X and Z, which tells its
+    /// start from its end for walls not recording their first face
+    pub direction: [f32; 2],
+    /// The frame and panel elements, frame first
+    pub elements: Vec<Uuid>,
+    /// The vertices held in place, of the opening and of the elements
+    pub anchors: Vec<HostAnchor>,
+}
+
+/// A registry of hosted families
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FamilyRegistry {
+    /// Unique identifier for the registry
+    pub id: Uuid,
+    /// The families placed, by ID
+    pub families: HashMap<Uuid, HostedFamily>,
+}
+
+impl FamilyRegistry {
+    /// Create a new, empty registry
+    #[must_use]
+    pub fn create_new() -> Self {
+        Self {
+            id: new_id(),
+            families: HashMap::new(),
+        }
+    }
+
+    /// Store a placed family and return its ID
+    pub fn store(&mut self, family: HostedFamily) -> Uuid {
+        let id = family.id;
+        self.families.insert(id, family);
+        id
+    }
+
+    /// Remove a placed family
+    pub fn remove(&mut self, id: &Uuid) {
+        self.families.remove(id);
+    }
+
+    /// Get a placed family by ID
+    #[must_use]
+    pub fn get(&self, id: &Uuid) -> Option<&HostedFamily> {
+        self.families.get(id)
+    }
+
+    /// Get a mutable reference to a placed family by ID
+    pub fn get_mut(&mut self, id: &Uuid) -> Option<&mut HostedFamily> {
+        self.families.get_mut(id)
+    }
+
+    /// The families in ID order
+    #[must_use]
+    pub fn sorted(&self) -> Vec<&HostedFamily> {
+        sorted_by_id(&self.families)
+    }
+
+    /// The families hosted in a wall, in ID order
+    #[must_use]
+    pub fn hosted_by(&self, wall: &Uuid) -> Vec<&HostedFamily> {
+        self.sorted()
+            .into_iter()
+            .filter(|family| family.host == *wall)
+            .collect()
+    }
+}
+
+/// A face in a host wall, as along, up and across positions, with the
+/// direction it faces
+#[derive(Debug, Clone, PartialEq)]
+pub struct HostLoop {
+    /// The face's corners, in either winding
+    pub points: Vec<[f32; 3]>,
+    /// The direction out of the solid, as along, up and across
+    pub outward: [f32; 3],
+}
+
+/// A solid of a family, as the faces making it
+#[derive(Debug, Clone, PartialEq)]
+pub struct FamilyPart {
+    /// The name of the part's element
+    pub name: String,
+    /// The part's faces
+    pub loops: Vec<HostLoop>,
+}
+
+/// The faces of a slab across a wall with rectangular holes through it
+///
+/// The slab rises from `up[0]` to `up[1]` between its two faces at the
+/// `across` positions, each face running over its own along range, so
+/// mitered wall ends keep their shape. Each hole is an along and an up
+/// range running through both faces; a hole reaching down to the slab's
+/// bottom opens it, as a door does. Holes must not overlap.
+///
+/// Every face edge a hole's side meets is split there, so neighbouring
+/// faces share their edges whole.
+#[must_use]
+pub fn pierced_slab_loops(
+    faces: [[f32; 2]; 2],
+    across: [f32; 2],
+    up: [f32; 2],
+    holes: &[[[f32; 2]; 2]],
+    tolerance: f32,
+) -> Vec<HostLoop> {
+    let mut holes = holes.to_vec();
+    holes.sort_by(|a, b| a[0][0].total_cmp(&b[0][0]));
+    let open = |hole: &[[f32; 2]; 2]| hole[1][0] <= up[0] + tolerance;
+    let at = |face: usize, along: f32, height: f32| [along, height, across[face]];
+    let face_out = [across[0] - across[1], across[1] - across[0]];
+    let mut loops = Vec::new();
+
+    for face in 0..2 {
+        let outward = [0.0, 0.0, face_out[face]];
+        let face_loop = |points: Vec<[f32; 3]>| HostLoop { points, outward };
+        // Heights where a hole's side splits the edge of a strip beside it
+        let sides = |hole: Option<&[[f32; 2]; 2]>| match hole {
+            Some(hole) if open(hole) => vec![hole[1][1]],
+            Some(hole) => vec![hole[1][0], hole[1][1]],
+            None => Vec::new(),
+        };
+        // Full-height strips between the holes and the ends
+        for index in 0..=holes.len() {
+            let before = index.checked_sub(1).map(|before| &holes[before]);
+            let after = holes.get(index);
+            let left = before.map_or(faces[face][0], |hole| hole[0][1]);
+            let right = after.map_or(faces[face][1], |hole| hole[0][0]);
+            let mut points = vec![at(face, left, up[0]), at(face, right, up[0])];
+            points.extend(sides(after).into_iter().map(|h| at(face, right, h)));
+            points.extend([at(face, right, up[1]), at(face, left, up[1])]);
+            points.extend(sides(before).into_iter().rev().map(|h| at(face, left, h)));
+            loops.push(face_loop(points));
+        }
+        // Above and below each hole
+        for hole in &holes {
+            let [[start, end], [bottom, top]] = *hole;
+            loops.push(face_loop(vec![
+                at(face, start, top),
+                at(face, end, top),
+                at(face, end, up[1]),
+                at(face, start, up[1]),
+            ]));
+            if !open(hole) {
+                loops.push(face_loop(vec![
+                    at(face, start, up[0]),
+                    at(face, end, up[0]),
+                    at(face, end, bottom),
+                    at(face, start, bottom),
+                ]));
+            }
+        }
+    }
+
+    // A strip of the top or bottom between two positions along, through
+    // every hole side between them
+    let cuts: Vec<f32> = holes.iter().flat_map(|hole| hole[0]).collect();
+    let band = |from: [f32; 2], to: [f32; 2], height: f32| {
+        let inner = |face: usize| {
+            cuts.iter()
+                .copied()
+                .filter(move |along| {
+                    *along > from[face] + tolerance && *along < to[face] - tolerance
+                })
+                .map(move |along| at(face, along, height))
+        };
+        let mut points = vec![at(0, from[0], height)];
+        points.extend(inner(0));
+        points.extend([at(0, to[0], height), at(1, to[1], height)]);
+        points.extend(inner(1).collect::<Vec<_>>().into_iter().rev());
+        points.push(at(1, from[1], height));
+        points
+    };
+    let (starts, ends) = ([faces[0][0], faces[1][0]], [faces[0][1], faces[1][1]]);
+    loops.push(HostLoop {
+        points: band(starts, ends, up[1]),
+        outward: [0.0, 1.0, 0.0],
+    });
+    // The bottom is split by open holes
+    let mut from = starts;
+    for hole in holes.iter().filter(|hole| open(hole)) {
+        loops.push(HostLoop {
+            points: band(from, [hole[0][0]; 2], up[0]),
+            outward: [0.0, -1.0, 0.0],
+        });
+        from = [hole[0][1]; 2];
+    }
+    loops.push(HostLoop {
+        points: band(from, ends, up[0]),
+        outward: [0.0, -1.0, 0.0],
+    });
+    for (index, sign) in [(0, -1.0), (1, 1.0)] {
+        loops.push(HostLoop {
+            points: vec![
+                at(0, faces[0][index], up[0]),
+                at(1, faces[1][index], up[0]),
+                at(1, faces[1][index], up[1]),
+                at(0, faces[0][index], up[1]),
+            ],
+            outward: [sign, 0.0, 0.0],
+        });
+    }
+
+    for hole in &holes {
+        loops.extend(hole_sides(hole, open(hole), across));
+    }
+    loops
+}
+
+/// The faces lining a hole through a slab, facing into it; an open hole
+/// has no sill
+fn hole_sides(hole: &[[f32; 2]; 2], open: bool, across: [f32; 2]) -> Vec<HostLoop> {
+    let [[start, end], [bottom, top]] = *hole;
+    let at = |face: usize, along: f32, height: f32| [along, height, across[face]];
+    let across_hole = |height: f32| {
+        vec![
+            at(0, start, height),
+            at(0, end, height),
+            at(1, end, height),
+            at(1, start, height),
+        ]
+    };
+    let mut loops = vec![HostLoop {
+        points: across_hole(top),
+        outward: [0.0, -1.0, 0.0],
+    }];
+    if !open {
+        loops.push(HostLoop {
+            points: across_hole(bottom),
+            outward: [0.0, 1.0, 0.0],
+        });
+    }
+    for (along, sign) in [(start, 1.0), (end, -1.0)] {
+        loops.push(HostLoop {
+            points: vec![
+                at(0, along, bottom),
+                at(1, along, bottom),
+                at(1, along, top),
+                at(0, along, top),
+            ],
+            outward: [sign, 0.0, 0.0],
+        });
+    }
+    loops
+}
+
+/// The faces of a box between along, up and across ranges
+#[must_use]
+pub fn box_loops(along: [f32; 2], up: [f32; 2], across: [f32; 2]) -> Vec<HostLoop> {
+    let point = |a: usize, u: usize, c: usize| [along[a], up[u], across[c]];
+    let face = |points: [[f32; 3]; 4], outward: [f32; 3]| HostLoop {
+        points: points.to_vec(),
+        outward,
+    };
+    let sign = |range: [f32; 2]| if range[1] >= range[0] { 1.0 } else { -1.0 };
+    let (a, u, c) = (sign(along), sign(up), sign(across));
+    vec![
+        face(
+            [
+                point(0, 0, 0),
+                point(1, 0, 0),
+                point(1, 1, 0),
+                point(0, 1, 0),
+            ],
+            [0.0, 0.0, -c],
+        ),
+        face(
+            [
+                point(0, 0, 1),
+                point(1, 0, 1),
+                point(1, 1, 1),
+                point(0, 1, 1),
+            ],
+            [0.0, 0.0, c],
+        ),
+        face(
+            [
+                point(0, 0, 0),
+                point(0, 0, 1),
+                point(0, 1, 1),
+                point(0, 1, 0),
+            ],
+            [-a, 0.0, 0.0],
+        ),
+        face(
+            [
+                point(1, 0, 0),
+                point(1, 0, 1),
+                point(1, 1, 1),
+                point(1, 1, 0),
+            ],
+            [a, 0.0, 0.0],
+        ),
+        face(
+            [
+                point(0, 0, 0),
+                point(1, 0, 0),
+                point(1, 0, 1),
+                point(0, 0, 1),
+            ],
+            [0.0, -u, 0.0],
+        ),
+        face(
+            [
+                point(0, 1, 0),
+                point(1, 1, 0),
+                point(1, 1, 1),
+                point(0, 1, 1),
+            ],
+            [0.0, u, 0.0],
+        ),
+    ]
+}
+
+/// The frame and panels of a family in a wall of a thickness
+///
+/// The frame runs through the wall, flush with both faces; the panels are
+/// centered in it, dividing the clear opening evenly.
+#[must_use]
+pub fn family_parts(
+    parameters: &FamilyParameters,
+    thickness: f32,
+    tolerance: f32,
+) -> Vec<FamilyPart> {
+    let half = thickness / 2.0;
+    let outer = [-parameters.width / 2.0, parameters.width / 2.0];
+    let [clear_along, clear_up] = parameters.clear_opening();
+    let label = parameters.kind.label();
+    let mut parts = vec![FamilyPart {
+        name: format!("{label} Frame"),
+        loops: pierced_slab_loops(
+            [outer, outer],
+            [half, -half],
+            [
+                parameters.sill_height,
+                parameters.sill_height + parameters.height,
+            ],
+            &[[clear_along, clear_up]],
+            tolerance,
+        ),
+    }];
+    let panels = parameters.panel_ranges();
+    for (index, along) in panels.iter().enumerate() {
+        let name = if panels.len() == 1 {
+            format!("{label} Panel")
+        } else {
+            format!("{label} Panel {}", index + 1)
+        };
+        parts.push(FamilyPart {
+            name,
+            loops: box_loops(
+                *along,
+                clear_up,
+                [PANEL_THICKNESS / 2.0, -PANEL_THICKNESS / 2.0],
+            ),
+        });
+    }
+    parts
+}
+
+/// The plan symbol of a family's swing: each panel's leaf drawn open
+/// square to the wall on its first face side, and the arc it sweeps
+///
+/// Lines are given as along and across positions. Fixed families have
+/// none.
+#[must_use]
+#[allow(clippy::cast_precision_loss)]
+pub fn swing_lines(parameters: &FamilyParameters, thickness: f32) -> Vec<[[f32; 2]; 2]> {
+    if parameters.swing == Swing::Fixed {
+        return Vec::new();
+    }
+    let face = thickness / 2.0;
+    let mut lines = Vec::new();
+    for [start, end] in parameters.panel_ranges() {
+        // Seen from the first face, the wall's start is on the left
+        let (hinge, free) = if parameters.swing == Swing::Left {
+            (start, end)
+        } else {
+            (end, start)
+        };
+        let leaf = (free - hinge).abs();
+        lines.push([[hinge, face], [hinge, face + leaf]]);
+        let point = |index: usize| {
+            let angle = std::f32::consts::FRAC_PI_2 * index as f32 / SWING_ARC_SEGMENTS as f32;
+            [
+                hinge + (free - hinge) * angle.cos(),
+                face + leaf * angle.sin(),
+            ]
+        };
+        lines.extend((0..SWING_ARC_SEGMENTS).map(|index| [point(index), point(index + 1)]));
+    }
+    lines
+}
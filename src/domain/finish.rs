@@ -0,0 +1,214 @@
+/// Floor finishes and ceilings generated from spaces
+///
+/// A finish is a flat plate laid over a space's floor or hung below its
+/// ceiling: a floor finish sits an offset above the floor, a ceiling an
+/// offset below the top of the room, each as thick as its layer says.
+///
+/// A finish follows the outline of the space face it was generated from.
+/// Each edge of the outline that runs along the face of a wall is bound
+/// to that wall face, so when the wall moves the edge moves with it and
+/// the outline is rebuilt from where its edges' lines now cross.
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::domain::{new_id, sorted_by_id, Point};
+
+/// Whether a finish covers a floor or forms a ceiling
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FinishKind {
+    /// A plate laid over the floor
+    Floor,
+    /// A plate hung below the top of the room
+    Ceiling,
+}
+
+impl FinishKind {
+    /// Human-readable name of the kind
+    #[must_use]
+    pub fn label(self) -> &'static str {
+        match self {
+            FinishKind::Floor => "Floor Finish",
+            FinishKind::Ceiling => "Ceiling",
+        }
+    }
+}
+
+/// How a finish is built over its face
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FinishLayer {
+    /// Whether it covers the floor or forms the ceiling
+    pub kind: FinishKind,
+    /// Distance from the face to the near side of the finish, up from a
+    /// floor or down from a ceiling
+    pub offset: f32,
+    /// Thickness of the finish
+    pub thickness: f32,
+    /// The material it is made of, if any
+    pub material: Option<String>,
+}
+
+impl FinishLayer {
+    /// A floor finish of a thickness laid directly on the floor
+    #[must_use]
+    pub fn floor(thickness: f32) -> Self {
+        Self {
+            kind: FinishKind::Floor,
+            offset: 0.0,
+            thickness,
+            material: None,
+        }
+    }
+
+    /// A ceiling of a thickness hung a distance below the top of the room
+    #[must_use]
+    pub fn ceiling(drop: f32, thickness: f32) -> Self {
+        Self {
+            kind: FinishKind::Ceiling,
+            offset: drop,
+            thickness,
+            material: None,
+        }
+    }
+
+    /// Bottom and top heights of the finish over a face at a height
+    #[must_use]
+    pub fn heights(&self, face_height: f32) -> [f32; 2] {
+        match self.kind {
+            FinishKind::Floor => [
+                face_height + self.offset,
+                face_height + self.offset + self.thickness,
+            ],
+            FinishKind::Ceiling => [
+                face_height - self.offset - self.thickness,
+                face_height - self.offset,
+            ],
+        }
+    }
+}
+
+/// A line in plan, as the X and Z of its unit normal and its distance from
+/// the origin along that normal
+pub type PlanLine = [f32; 3];
+
+/// The plan line through two points, or None if they coincide in plan
+#[must_use]
+pub fn plan_line(start: &Point, end: &Point, tolerance: f32) -> Option<PlanLine> {
+    let (dx, dz) = (end.x - start.x, end.z - start.z);
+    let length = (dx * dx + dz * dz).sqrt();
+    if length <= tolerance {
+        return None;
+    }
+    let normal = [-dz / length, dx / length];
+    Some([
+        normal[0],
+        normal[1],
+        normal[0] * start.x + normal[1] * start.z,
+    ])
+}
+
+/// Where two plan lines cross, as X and Z, or None if they are parallel
+#[must_use]
+pub fn plan_intersection(first: PlanLine, second: PlanLine, tolerance: f32) -> Option<[f32; 2]> {
+    let determinant = first[0] * second[1] - first[1] * second[0];
+    if determinant.abs() <= tolerance {
+        return None;
+    }
+    Some([
+        (first[2] * second[1] - first[1] * second[2]) / determinant,
+        (first[0] * second[2] - first[2] * second[0]) / determinant,
+    ])
+}
+
+/// The wall face an edge of a finish's outline runs along
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FinishBound {
+    /// The wall's solid
+    pub wall: Uuid,
+    /// The face the edge runs along
+    pub face: Uuid,
+    /// The face's normal in plan when last seen, as X and Z, to find the
+    /// face again if the wall's faces are rebuilt
+    pub normal: [f32; 2],
+}
+
+/// A finish generated from a space
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SpaceFinish {
+    /// Unique identifier of the finish
+    pub id: Uuid,
+    /// The space element it was generated from
+    pub space: Uuid,
+    /// The space's floor or ceiling face it follows
+    pub face: Uuid,
+    /// How it is built
+    pub layer: FinishLayer,
+    /// The wall face each edge of the outline is bound to, if any, edge
+    /// `i` running from outline point `i` to the next
+    pub bounds: Vec<Option<FinishBound>>,
+    /// The finish element
+    pub element: Uuid,
+    /// The outline the finish was last built over, at the face's height
+    pub built_for: Vec<Point>,
+}
+
+/// A registry of finishes generated from spaces
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FinishRegistry {
+    /// Unique identifier for the registry
+    pub id: Uuid,
+    /// The finishes, by ID
+    pub finishes: HashMap<Uuid, SpaceFinish>,
+}
+
+impl FinishRegistry {
+    /// Create a new, empty registry
+    #[must_use]
+    pub fn create_new() -> Self {
+        Self {
+            id: new_id(),
+            finishes: HashMap::new(),
+        }
+    }
+
+    /// Store a finish and return its ID
+    pub fn store(&mut self, finish: SpaceFinish) -> Uuid {
+        let id = finish.id;
+        self.finishes.insert(id, finish);
+        id
+    }
+
+    /// Remove a finish
+    pub fn remove(&mut self, id: &Uuid) {
+        self.finishes.remove(id);
+    }
+
+    /// Get a finish by ID
+    #[must_use]
+    pub fn get(&self, id: &Uuid) -> Option<&SpaceFinish> {
+        self.finishes.get(id)
+    }
+
+    /// Get a mutable reference to a finish by ID
+    pub fn get_mut(&mut self, id: &Uuid) -> Option<&mut SpaceFinish> {
+        self.finishes.get_mut(id)
+    }
+
+    /// The finishes generated from a space, in ID order
+    #[must_use]
+    pub fn of_space(&self, space: &Uuid) -> Vec<&SpaceFinish> {
+        self.sorted()
+            .into_iter()
+            .filter(|finish| finish.space == *space)
+            .collect()
+    }
+
+    /// The finishes in ID order
+    #[must_use]
+    pub fn sorted(&self) -> Vec<&SpaceFinish> {
+        sorted_by_id(&self.finishes)
+    }
+}
@@ -0,0 +1,156 @@
+/// Orphaned geometry collection
+///
+/// Deleting an element or a solid leaves its polygons, segments and
+/// vertices behind in the registries. Geometry is kept while something
+/// reaches it: an element's solid, any geometry held by a tier, or an ID
+/// pinned by the caller, and everything those hold in turn. Solids reach
+/// their polygons, polygons their segments and segments their vertices.
+/// Anything else is garbage. References from outside the registries, such
+/// as component instances or constraint targets and directions, must be
+/// pinned to survive.
+use crate::domain::{ElementRegistry, GeometryRegistry, TierRegistry};
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
+
+/// Geometry no root reaches, each list sorted by ID
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Garbage {
+    /// Unreached solids
+    pub solids: Vec<Uuid>,
+    /// Unreached polygons
+    pub polygons: Vec<Uuid>,
+    /// Unreached segments
+    pub segments: Vec<Uuid>,
+    /// Unreached vertices
+    pub vertices: Vec<Uuid>,
+    /// Unreached direction variables
+    pub directions: Vec<Uuid>,
+}
+
+impl Garbage {
+    /// Number of primitives of every kind
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.solids.len()
+            + self.polygons.len()
+            + self.segments.len()
+            + self.vertices.len()
+            + self.directions.len()
+    }
+
+    /// Whether there is nothing to collect
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// The IDs in a registry map that are not in the reached set, sorted
+fn unreached<T>(map: &HashMap<Uuid, T>, reached: &HashSet<Uuid>) -> Vec<Uuid> {
+    let mut ids: Vec<Uuid> = map
+        .keys()
+        .filter(|id| !reached.contains(id))
+        .copied()
+        .collect();
+    ids.sort();
+    ids
+}
+
+impl GeometryRegistry {
+    /// List the geometry that garbage collection would remove, without
+    /// removing it
+    #[must_use]
+    pub fn find_garbage(
+        &self,
+        element_registry: &ElementRegistry,
+        tier_registry: &TierRegistry,
+        pinned: &HashSet<Uuid>,
+    ) -> Garbage {
+        let mut reached: HashSet<Uuid> = pinned.clone();
+        reached.extend(
+            element_registry
+                .elements
+                .values()
+                .map(|element| element.solid),
+        );
+        reached.extend(
+            tier_registry
+                .tiers
+                .values()
+                .flat_map(|tier| tier.geometry.iter().copied()),
+        );
+
+        // Each level only holds the one below, so one pass per level
+        // reaches everything
+        let polygons: Vec<Uuid> = self
+            .solids
+            .solids
+            .values()
+            .filter(|solid| reached.contains(&solid.id))
+            .flat_map(|solid| solid.polygons.iter().copied())
+            .collect();
+        reached.extend(polygons);
+        let segments: Vec<Uuid> = self
+            .polygons
+            .polygons
+            .values()
+            .filter(|polygon| reached.contains(&polygon.id))
+            .flat_map(|polygon| polygon.segments.iter().copied())
+            .collect();
+        reached.extend(segments);
+        let vertices: Vec<Uuid> = self
+            .segments
+            .segments
+            .values()
+            .filter(|segment| reached.contains(&segment.id))
+            .flat_map(|segment| segment.vertices)
+            .collect();
+        reached.extend(vertices);
+
+        Garbage {
+            solids: unreached(&self.solids.solids, &reached),
+            polygons: unreached(&self.polygons.polygons, &reached),
+            segments: unreached(&self.segments.segments, &reached),
+            vertices: unreached(&self.vertices.vertices, &reached),
+            directions: unreached(&self.directions.directions, &reached),
+        }
+    }
+
+    /// Remove the geometry no element, tier or pinned ID reaches and
+    /// return what was removed
+    ///
+    /// Use `find_garbage` for a dry run.
+    #[tracing::instrument(skip_all, fields(pinned = pinned.len()))]
+    pub fn collect_garbage(
+        &mut self,
+        element_registry: &ElementRegistry,
+        tier_registry: &TierRegistry,
+        pinned: &HashSet<Uuid>,
+    ) -> Garbage {
+        let garbage = self.find_garbage(element_registry, tier_registry, pinned);
+        for id in &garbage.solids {
+            self.solids.remove(id);
+        }
+        for id in &garbage.polygons {
+            self.polygons.remove(id);
+        }
+        for id in &garbage.segments {
+            self.segments.remove(id);
+        }
+        for id in &garbage.vertices {
+            self.vertices.remove(id);
+        }
+        for id in &garbage.directions {
+            self.directions.remove(id);
+        }
+        tracing::info!(
+            solids = garbage.solids.len(),
+            polygons = garbage.polygons.len(),
+            segments = garbage.segments.len(),
+            vertices = garbage.vertices.len(),
+            directions = garbage.directions.len(),
+            "collected garbage"
+        );
+        garbage
+    }
+}
@@ -0,0 +1,45 @@
+/// Axis-aligned bounding boxes
+use crate::domain::Point;
+
+/// An axis-aligned box enclosing some points
+#[derive(Debug, Clone)]
+pub struct Bounds {
+    /// The corner with the smallest coordinates
+    pub min: Point,
+    /// The corner with the largest coordinates
+    pub max: Point,
+}
+
+impl Bounds {
+    /// The smallest box enclosing the points
+    /// Returns None if there are no points
+    #[must_use]
+    pub fn from_points(points: &[Point]) -> Option<Self> {
+        let first = points.first()?;
+        let mut bounds = Self {
+            min: first.clone(),
+            max: first.clone(),
+        };
+        for point in &points[1..] {
+            bounds.min.x = bounds.min.x.min(point.x);
+            bounds.min.y = bounds.min.y.min(point.y);
+            bounds.min.z = bounds.min.z.min(point.z);
+            bounds.max.x = bounds.max.x.max(point.x);
+            bounds.max.y = bounds.max.y.max(point.y);
+            bounds.max.z = bounds.max.z.max(point.z);
+        }
+        Some(bounds)
+    }
+
+    /// Check whether two boxes overlap by more than the tolerance on every
+    /// axis; boxes that only touch do not overlap
+    #[must_use]
+    pub fn overlaps(&self, other: &Bounds, tolerance: f32) -> bool {
+        self.min.x < other.max.x - tolerance
+            && other.min.x < self.max.x - tolerance
+            && self.min.y < other.max.y - tolerance
+            && other.min.y < self.max.y - tolerance
+            && self.min.z < other.max.z - tolerance
+            && other.min.z < self.max.z - tolerance
+    }
+}
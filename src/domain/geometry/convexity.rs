@@ -0,0 +1,119 @@
+/// Convexity tests and convex decomposition
+use crate::domain::geometry::measure::newell_normal;
+use crate::domain::geometry::plane::Plane;
+use crate::domain::geometry::split::{connected_shells, split_loops_by_plane};
+use crate::domain::{measure_vector, Point};
+
+/// Upper bound on pieces produced by a decomposition
+pub const MAX_CONVEX_PIECES: usize = 256;
+
+/// Check whether a planar point loop is convex
+///
+/// Every corner must turn the same way as the loop's overall winding;
+/// collinear corners within `tolerance` are allowed.
+#[must_use]
+pub fn is_convex_polygon(points: &[Point], tolerance: f32) -> bool {
+    if points.len() < 3 {
+        return false;
+    }
+    let Some(normal) = newell_normal(points).normalized() else {
+        return false;
+    };
+    (0..points.len()).all(|index| {
+        let previous = &points[(index + points.len() - 1) % points.len()];
+        let current = &points[index];
+        let next = &points[(index + 1) % points.len()];
+        let turn = measure_vector(previous, current).cross(&measure_vector(current, next));
+        turn.dot(&normal) >= -tolerance * tolerance
+    })
+}
+
+/// First face plane of a shell that has vertices strictly on both sides
+#[must_use]
+pub fn separating_face_plane(loops: &[Vec<Point>], tolerance: f32) -> Option<Plane> {
+    loops
+        .iter()
+        .filter_map(|l| Plane::from_loop(l))
+        .find(|plane| {
+            let mut has_below = false;
+            let mut has_above = false;
+            for point in loops.iter().flatten() {
+                let distance = plane.signed_distance(point);
+                has_below |= distance < -tolerance;
+                has_above |= distance > tolerance;
+            }
+            has_below && has_above
+        })
+}
+
+/// Check whether a closed shell of point loops is convex
+///
+/// A shell is convex when every face is convex and no face plane has
+/// vertices on both of its sides.
+#[must_use]
+pub fn is_convex_solid(loops: &[Vec<Point>], tolerance: f32) -> bool {
+    !loops.is_empty()
+        && loops.iter().all(|l| is_convex_polygon(l, tolerance))
+        && separating_face_plane(loops, tolerance).is_none()
+}
+
+/// Decompose a closed, outward-wound shell into convex shells
+///
+/// Repeatedly splits non-convex pieces along a face plane that separates
+/// their vertices (a BSP built from the solid's own faces). Stops splitting
+/// once `MAX_CONVEX_PIECES` is reached, returning the remaining pieces as-is.
+#[must_use]
+pub fn convex_decomposition(loops: &[Vec<Point>], tolerance: f32) -> Vec<Vec<Vec<Point>>> {
+    let mut pending = connected_shells(loops, tolerance);
+    let mut pieces = Vec::new();
+
+    while let Some(shell) = pending.pop() {
+        if pieces.len() + pending.len() >= MAX_CONVEX_PIECES {
+            pieces.push(shell);
+            continue;
+        }
+        let Some(plane) = separating_face_plane(&shell, tolerance) else {
+            pieces.push(shell);
+            continue;
+        };
+        let (below, above) = split_loops_by_plane(&shell, &plane, tolerance);
+        if below.is_empty() || above.is_empty() {
+            pieces.push(shell);
+            continue;
+        }
+        pending.extend(connected_shells(&below, tolerance));
+        pending.extend(connected_shells(&above, tolerance));
+    }
+    pieces
+}
+
+/// The corners of the convex hull of points in a plane, counter-clockwise
+///
+/// Points within `tolerance` of a hull edge are not corners, so a run of
+/// nearly collinear points gives only its two ends.
+#[must_use]
+pub fn convex_hull(points: &[[f32; 2]], tolerance: f32) -> Vec<[f32; 2]> {
+    let mut sorted = points.to_vec();
+    sorted.sort_by(|a, b| a[0].total_cmp(&b[0]).then(a[1].total_cmp(&b[1])));
+    // Whether the path through `middle` turns left, with `middle` further
+    // than the tolerance from the line between the other two
+    let turns_left = |from: [f32; 2], middle: [f32; 2], to: [f32; 2]| {
+        let cross =
+            (middle[0] - from[0]) * (to[1] - from[1]) - (middle[1] - from[1]) * (to[0] - from[0]);
+        cross > tolerance * (to[0] - from[0]).hypot(to[1] - from[1])
+    };
+    let mut hull: Vec<[f32; 2]> = Vec::with_capacity(sorted.len() + 1);
+    for pass in [sorted.clone(), sorted.into_iter().rev().collect()] {
+        let floor = hull.len();
+        for point in pass {
+            while hull.len() >= floor + 2
+                && !turns_left(hull[hull.len() - 2], hull[hull.len() - 1], point)
+            {
+                hull.pop();
+            }
+            hull.push(point);
+        }
+        hull.pop();
+    }
+    hull
+}
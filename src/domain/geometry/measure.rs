@@ -0,0 +1,95 @@
+/// Lengths, areas and normals of point loops
+use crate::domain::{measure_vector, Point, Vector};
+
+/// Distance between two points in meters
+#[must_use]
+pub fn distance(a: &Point, b: &Point) -> f32 {
+    measure_vector(a, b).length()
+}
+
+/// Area of the triangle spanned by three points
+#[must_use]
+pub fn triangle_area(a: &Point, b: &Point, c: &Point) -> f32 {
+    measure_vector(a, b).cross(&measure_vector(a, c)).length() / 2.0
+}
+
+/// Newell's normal of a closed point loop
+///
+/// The length of the returned vector is twice the loop's area and its
+/// direction follows the loop's winding (right-hand rule). Robust for
+/// concave and slightly non-planar loops.
+#[must_use]
+pub fn newell_normal(points: &[Point]) -> Vector {
+    let mut normal = Vector {
+        x: 0.0,
+        y: 0.0,
+        z: 0.0,
+    };
+    for (index, current) in points.iter().enumerate() {
+        let next = &points[(index + 1) % points.len()];
+        normal.x += (current.y - next.y) * (current.z + next.z);
+        normal.y += (current.z - next.z) * (current.x + next.x);
+        normal.z += (current.x - next.x) * (current.y + next.y);
+    }
+    normal
+}
+
+/// Area of a closed planar point loop
+#[must_use]
+pub fn polygon_area(points: &[Point]) -> f32 {
+    newell_normal(points).length() / 2.0
+}
+
+/// Perimeter of a closed point loop
+#[must_use]
+pub fn polygon_perimeter(points: &[Point]) -> f32 {
+    points
+        .iter()
+        .enumerate()
+        .map(|(index, current)| distance(current, &points[(index + 1) % points.len()]))
+        .sum()
+}
+
+/// Average position of a set of points
+/// Returns None for an empty set
+#[must_use]
+pub fn centroid(points: &[Point]) -> Option<Point> {
+    if points.is_empty() {
+        return None;
+    }
+    #[allow(clippy::cast_precision_loss)]
+    let count = points.len() as f32;
+    Some(Point {
+        x: points.iter().map(|p| p.x).sum::<f32>() / count,
+        y: points.iter().map(|p| p.y).sum::<f32>() / count,
+        z: points.iter().map(|p| p.z).sum::<f32>() / count,
+    })
+}
+
+/// Fan triangulation of a closed point loop as index triples
+///
+/// Matches the fan used for rendering, so degenerate triangles found here
+/// are the ones the renderer would produce.
+#[must_use]
+pub fn fan_triangles(point_count: usize) -> Vec<[usize; 3]> {
+    (1..point_count.saturating_sub(1))
+        .map(|index| [0, index, index + 1])
+        .collect()
+}
+
+/// Signed volume enclosed by a set of closed point loops
+///
+/// Positive when the loops wind counter-clockwise seen from outside
+/// (normals pointing outward), negative when they point inward.
+#[must_use]
+pub fn signed_volume(loops: &[Vec<Point>]) -> f32 {
+    let mut volume = 0.0;
+    for points in loops {
+        for [a, b, c] in fan_triangles(points.len()) {
+            let (a, b, c) = (&points[a], &points[b], &points[c]);
+            volume += a.x * (b.y * c.z - b.z * c.y) - a.y * (b.x * c.z - b.z * c.x)
+                + a.z * (b.x * c.y - b.y * c.x);
+        }
+    }
+    volume / 6.0
+}
@@ -0,0 +1,27 @@
+//! Computational geometry helpers
+//!
+//! Pure measurement and analysis functions over points and polygon loops.
+//! These never touch the registries directly.
+
+/// Axis-aligned bounding boxes
+pub mod bounds;
+/// Convexity tests and convex decomposition
+pub mod convexity;
+/// Lengths, areas and normals of point loops
+pub mod measure;
+/// Planes in 3D space
+pub mod plane;
+/// Vertical prisms extruded from horizontal loops
+pub mod prism;
+/// Ray casting against triangles and point loops
+pub mod ray;
+/// Splitting shells by a plane
+pub mod split;
+
+pub use bounds::*;
+pub use convexity::*;
+pub use measure::*;
+pub use plane::*;
+pub use prism::*;
+pub use ray::*;
+pub use split::*;
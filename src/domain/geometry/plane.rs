@@ -0,0 +1,158 @@
+/// Planes in 3D space and least-squares plane fitting
+use crate::domain::geometry::measure::{centroid, newell_normal};
+use crate::domain::{measure_vector, Point, Vector};
+
+/// An infinite plane defined by a point and a unit normal
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Plane {
+    /// A point on the plane
+    pub point: Point,
+    /// The unit normal of the plane
+    pub normal: Vector,
+}
+
+impl Plane {
+    /// Plane of a closed point loop, with the normal following its winding
+    /// Returns None for degenerate loops with no area
+    #[must_use]
+    pub fn from_loop(points: &[Point]) -> Option<Plane> {
+        Some(Plane {
+            point: centroid(points)?,
+            normal: newell_normal(points).normalized()?,
+        })
+    }
+
+    /// Signed distance of a point from the plane, positive on the normal side
+    #[must_use]
+    pub fn signed_distance(&self, point: &Point) -> f32 {
+        measure_vector(&self.point, point).dot(&self.normal)
+    }
+
+    /// Distance of the plane from the origin along its normal
+    #[must_use]
+    pub fn offset(&self) -> f32 {
+        -self.signed_distance(&Point {
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+        })
+    }
+
+    /// The plane moved along its normal to an offset from the origin
+    #[must_use]
+    pub fn with_offset(&self, offset: f32) -> Plane {
+        let shift = self.normal.scaled(offset - self.offset());
+        Plane {
+            point: Point {
+                x: self.point.x + shift.x,
+                y: self.point.y + shift.y,
+                z: self.point.z + shift.z,
+            },
+            normal: self.normal.clone(),
+        }
+    }
+
+    /// Closest point on the plane
+    #[must_use]
+    pub fn project(&self, point: &Point) -> Point {
+        let offset = self.normal.scaled(self.signed_distance(point));
+        Point {
+            x: point.x - offset.x,
+            y: point.y - offset.y,
+            z: point.z - offset.z,
+        }
+    }
+}
+
+/// Fit a plane to a set of points by least squares
+///
+/// Returns the unit normal, the centroid (a point on the plane) and the RMS
+/// distance of the points from the plane. The normal is the direction of
+/// least variance of the points; when the points form a loop it is oriented
+/// to agree with the loop's Newell normal, so it follows the winding.
+/// Returns None for fewer than three points or when all points are
+/// collinear, since no single plane is defined.
+#[must_use]
+pub fn fit_plane(points: &[Point]) -> Option<(Vector, Point, f32)> {
+    if points.len() < 3 {
+        return None;
+    }
+    let center = centroid(points)?;
+
+    // Scatter matrix of the points about their centroid
+    let mut scatter = [[0.0_f32; 3]; 3];
+    for point in points {
+        let offset = [point.x - center.x, point.y - center.y, point.z - center.z];
+        for (row, &a) in offset.iter().enumerate() {
+            for (column, &b) in offset.iter().enumerate() {
+                scatter[row][column] += a * b;
+            }
+        }
+    }
+
+    let (values, vectors) = symmetric_eigen(scatter);
+    let mut order = [0, 1, 2];
+    order.sort_by(|&a, &b| values[a].total_cmp(&values[b]));
+    let spread = values[order[2]].max(f32::EPSILON);
+    if values[order[1]] <= spread * f32::EPSILON.sqrt() {
+        return None;
+    }
+
+    let smallest = order[0];
+    let mut normal = Vector {
+        x: vectors[0][smallest],
+        y: vectors[1][smallest],
+        z: vectors[2][smallest],
+    }
+    .normalized()?;
+    if newell_normal(points).dot(&normal) < 0.0 {
+        normal = normal.scaled(-1.0);
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    let count = points.len() as f32;
+    let rms = (points
+        .iter()
+        .map(|point| measure_vector(&center, point).dot(&normal).powi(2))
+        .sum::<f32>()
+        / count)
+        .sqrt();
+    Some((normal, center, rms))
+}
+
+/// Eigen decomposition of a symmetric 3x3 matrix by cyclic Jacobi rotations
+///
+/// Returns the eigenvalues and a matrix whose columns are the eigenvectors.
+fn symmetric_eigen(mut matrix: [[f32; 3]; 3]) -> ([f32; 3], [[f32; 3]; 3]) {
+    let mut vectors = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+    for _ in 0..32 {
+        let off_diagonal = matrix[0][1].powi(2) + matrix[0][2].powi(2) + matrix[1][2].powi(2);
+        if off_diagonal <= f32::MIN_POSITIVE {
+            break;
+        }
+        for (p, q) in [(0, 1), (0, 2), (1, 2)] {
+            if matrix[p][q].abs() <= f32::MIN_POSITIVE {
+                continue;
+            }
+            let theta = (matrix[q][q] - matrix[p][p]) / (2.0 * matrix[p][q]);
+            let t = theta.signum() / (theta.abs() + theta.mul_add(theta, 1.0).sqrt());
+            let c = 1.0 / t.mul_add(t, 1.0).sqrt();
+            let s = t * c;
+            for row in &mut matrix {
+                let (kp, kq) = (row[p], row[q]);
+                row[p] = c * kp - s * kq;
+                row[q] = s * kp + c * kq;
+            }
+            let (row_p, row_q) = (matrix[p], matrix[q]);
+            matrix[p] = std::array::from_fn(|k| c * row_p[k] - s * row_q[k]);
+            matrix[q] = std::array::from_fn(|k| s * row_p[k] + c * row_q[k]);
+            for row in &mut vectors {
+                let (vp, vq) = (row[p], row[q]);
+                row[p] = c * vp - s * vq;
+                row[q] = s * vp + c * vq;
+            }
+        }
+    }
+    ([matrix[0][0], matrix[1][1], matrix[2][2]], vectors)
+}
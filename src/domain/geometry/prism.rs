@@ -0,0 +1,62 @@
+/// Vertical prisms extruded from horizontal loops
+use crate::domain::geometry::newell_normal;
+use crate::domain::Point;
+
+/// The faces of a vertical prism over a horizontal base loop
+///
+/// The base is raised by `height` along +Y. Faces wind counter-clockwise
+/// seen from outside whichever way the base winds: the bottom face first,
+/// then the top, then one side per base edge in base order.
+#[must_use]
+pub fn prism_loops(base: &[Point], height: f32) -> Vec<Vec<Point>> {
+    let mut base = base.to_vec();
+    if newell_normal(&base).y < 0.0 {
+        base.reverse();
+    }
+    let raise = |point: &Point| Point {
+        x: point.x,
+        y: point.y + height,
+        z: point.z,
+    };
+
+    let mut loops = Vec::with_capacity(base.len() + 2);
+    loops.push(base.iter().rev().cloned().collect());
+    loops.push(base.iter().map(raise).collect());
+    for (index, current) in base.iter().enumerate() {
+        let next = &base[(index + 1) % base.len()];
+        loops.push(vec![
+            current.clone(),
+            next.clone(),
+            raise(next),
+            raise(current),
+        ]);
+    }
+    loops
+}
+
+/// A horizontal rectangle at height `y`, counter-clockwise seen from above
+#[must_use]
+pub fn rectangle_loop(min_x: f32, min_z: f32, max_x: f32, max_z: f32, y: f32) -> Vec<Point> {
+    vec![
+        Point {
+            x: min_x,
+            y,
+            z: max_z,
+        },
+        Point {
+            x: max_x,
+            y,
+            z: max_z,
+        },
+        Point {
+            x: max_x,
+            y,
+            z: min_z,
+        },
+        Point {
+            x: min_x,
+            y,
+            z: min_z,
+        },
+    ]
+}
@@ -0,0 +1,52 @@
+/// Ray casting against triangles and point loops
+use crate::domain::geometry::fan_triangles;
+use crate::domain::{measure_vector, Point, Vector};
+
+/// Rays this close to parallel with a triangle miss it
+const PARALLEL_EPSILON: f32 = 1e-7;
+
+/// Distance along a ray to where it crosses a triangle, from either side
+///
+/// Uses the Möller–Trumbore test. The distance is in units of the
+/// direction's length; returns None if the ray misses or the triangle
+/// lies behind the origin.
+#[must_use]
+pub fn ray_triangle_distance(
+    origin: &Point,
+    direction: &Vector,
+    [corner, second, third]: [&Point; 3],
+) -> Option<f32> {
+    let to_second = measure_vector(corner, second);
+    let to_third = measure_vector(corner, third);
+    let across = direction.cross(&to_third);
+    let determinant = to_second.dot(&across);
+    if determinant.abs() < PARALLEL_EPSILON {
+        return None;
+    }
+    let inverse = 1.0 / determinant;
+    let to_origin = measure_vector(corner, origin);
+    let second_weight = to_origin.dot(&across) * inverse;
+    if !(0.0..=1.0).contains(&second_weight) {
+        return None;
+    }
+    let lifted = to_origin.cross(&to_second);
+    let third_weight = direction.dot(&lifted) * inverse;
+    if third_weight < 0.0 || second_weight + third_weight > 1.0 {
+        return None;
+    }
+    let distance = to_third.dot(&lifted) * inverse;
+    (distance >= 0.0).then_some(distance)
+}
+
+/// Distance along a ray to where it first crosses a closed point loop
+///
+/// The loop is fanned into triangles as for rendering.
+#[must_use]
+pub fn ray_loop_distance(origin: &Point, direction: &Vector, points: &[Point]) -> Option<f32> {
+    fan_triangles(points.len())
+        .into_iter()
+        .filter_map(|[a, b, c]| {
+            ray_triangle_distance(origin, direction, [&points[a], &points[b], &points[c]])
+        })
+        .min_by(f32::total_cmp)
+}
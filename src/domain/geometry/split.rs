@@ -0,0 +1,344 @@
+/// Splitting closed shells of point loops by a plane
+///
+/// Loops are expected to wind outward (see the orientation repair). Each
+/// loop is split between both sides of the plane and the opening left on
+/// each side is closed with cap loops built from its boundary edges.
+use crate::domain::geometry::measure::{distance, newell_normal};
+use crate::domain::geometry::plane::Plane;
+use crate::domain::{measure_vector, Point, Vector};
+use std::collections::HashMap;
+
+/// Index of points welded within a tolerance
+///
+/// Used to compare loop vertices by position when matching shared edges.
+#[derive(Debug, Default)]
+pub struct PointIndex {
+    /// The distinct points, in insertion order
+    pub points: Vec<Point>,
+    tolerance: f32,
+}
+
+impl PointIndex {
+    /// Create an empty index welding points within `tolerance`
+    #[must_use]
+    pub fn new(tolerance: f32) -> Self {
+        Self {
+            points: Vec::new(),
+            tolerance,
+        }
+    }
+
+    /// Index of a point, inserting it if no point lies within tolerance
+    pub fn insert(&mut self, point: &Point) -> usize {
+        if let Some(index) = self
+            .points
+            .iter()
+            .position(|existing| distance(existing, point) <= self.tolerance)
+        {
+            return index;
+        }
+        self.points.push(point.clone());
+        self.points.len() - 1
+    }
+
+    /// Indices of a loop with consecutive duplicates removed
+    pub fn insert_loop(&mut self, points: &[Point]) -> Vec<usize> {
+        let mut indices: Vec<usize> = Vec::with_capacity(points.len());
+        for point in points {
+            let index = self.insert(point);
+            if indices.last() != Some(&index) {
+                indices.push(index);
+            }
+        }
+        while indices.len() > 1 && indices.first() == indices.last() {
+            indices.pop();
+        }
+        indices
+    }
+}
+
+/// Point at parameter `t` between `a` and `b`
+fn lerp(a: &Point, b: &Point, t: f32) -> Point {
+    Point {
+        x: a.x + (b.x - a.x) * t,
+        y: a.y + (b.y - a.y) * t,
+        z: a.z + (b.z - a.z) * t,
+    }
+}
+
+/// Where the edge between two points on opposite sides meets the plane
+///
+/// Interpolates from the lexicographically lower point so faces sharing the
+/// edge compute exactly the same crossing.
+fn crossing_point(a: &Point, a_distance: f32, b: &Point, b_distance: f32) -> Point {
+    if (a.x, a.y, a.z) <= (b.x, b.y, b.z) {
+        lerp(a, b, a_distance / (a_distance - b_distance))
+    } else {
+        lerp(b, a, b_distance / (b_distance - a_distance))
+    }
+}
+
+/// Which side of the plane a signed distance falls on: -1, 0 or 1
+fn side_of(distance: f32, tolerance: f32) -> i8 {
+    if distance < -tolerance {
+        -1
+    } else {
+        i8::from(distance > tolerance)
+    }
+}
+
+/// Chain directed edges into closed loops of indices
+fn chain_edges(edges: &[(usize, usize)]) -> Vec<Vec<usize>> {
+    let mut outgoing: HashMap<usize, Vec<usize>> = HashMap::new();
+    for &(from, to) in edges.iter().rev() {
+        outgoing.entry(from).or_default().push(to);
+    }
+    let mut loops = Vec::new();
+    for &(start, _) in edges {
+        let mut chain = Vec::new();
+        let mut current = start;
+        while let Some(next) = outgoing.get_mut(&current).and_then(Vec::pop) {
+            chain.push(current);
+            current = next;
+            if current == start {
+                break;
+            }
+        }
+        if chain.len() >= 3 && current == start {
+            loops.push(chain);
+        }
+    }
+    loops
+}
+
+/// Check whether a point in a loop's plane lies inside the loop
+///
+/// Projects onto the coordinate plane most aligned with `normal` and counts
+/// ray crossings.
+#[must_use]
+pub fn point_in_loop(point: &Point, points: &[Point], normal: &Vector) -> bool {
+    let (ax, ay, az) = (normal.x.abs(), normal.y.abs(), normal.z.abs());
+    let flatten = |p: &Point| {
+        if az >= ax && az >= ay {
+            (p.x, p.y)
+        } else if ay >= ax {
+            (p.z, p.x)
+        } else {
+            (p.y, p.z)
+        }
+    };
+    let (px, py) = flatten(point);
+    let mut inside = false;
+    for index in 0..points.len() {
+        let (x1, y1) = flatten(&points[index]);
+        let (x2, y2) = flatten(&points[(index + 1) % points.len()]);
+        if (y1 > py) != (y2 > py) && px < x1 + (py - y1) * (x2 - x1) / (y2 - y1) {
+            inside = !inside;
+        }
+    }
+    inside
+}
+
+/// Split a planar loop that crosses the plane into loops on either side
+///
+/// Crossing points are inserted on the loop's edges. Edges lying in the
+/// plane go to the side the loop's interior is on, and the stretches of the
+/// cut line inside the loop close both sides, so concave loops crossing the
+/// plane several times split into several loops.
+fn split_loop(
+    points: &[Point],
+    plane: &Plane,
+    tolerance: f32,
+) -> (Vec<Vec<Point>>, Vec<Vec<Point>>) {
+    let Some(normal) = newell_normal(points).normalized() else {
+        return (Vec::new(), Vec::new());
+    };
+
+    // Loop with crossing points inserted, tagged by side
+    let mut ring: Vec<(Point, i8)> = Vec::with_capacity(points.len() + 2);
+    for index in 0..points.len() {
+        let next = (index + 1) % points.len();
+        let current_distance = plane.signed_distance(&points[index]);
+        let next_distance = plane.signed_distance(&points[next]);
+        ring.push((points[index].clone(), side_of(current_distance, tolerance)));
+        if side_of(current_distance, tolerance) * side_of(next_distance, tolerance) < 0 {
+            ring.push((
+                crossing_point(
+                    &points[index],
+                    current_distance,
+                    &points[next],
+                    next_distance,
+                ),
+                0,
+            ));
+        }
+    }
+
+    let mut below_edges = Vec::new();
+    let mut above_edges = Vec::new();
+    let mut plane_edges = Vec::new();
+    for index in 0..ring.len() {
+        let next = (index + 1) % ring.len();
+        let (current_side, next_side) = (ring[index].1, ring[next].1);
+        if current_side.min(next_side) < 0 {
+            below_edges.push((index, next));
+        } else if current_side.max(next_side) > 0 {
+            above_edges.push((index, next));
+        } else {
+            // The interior lies to the left of an edge, looking along the normal
+            let left = normal.cross(&measure_vector(&ring[index].0, &ring[next].0));
+            if left.dot(&plane.normal) < 0.0 {
+                below_edges.push((index, next));
+            } else {
+                above_edges.push((index, next));
+            }
+            plane_edges.push((index.min(next), index.max(next)));
+        }
+    }
+
+    // Stretches of the cut line inside the loop; looking along `direction`
+    // the below side is on the left
+    let direction = normal.cross(&plane.normal);
+    let mut on_line: Vec<usize> = (0..ring.len()).filter(|&i| ring[i].1 == 0).collect();
+    on_line.sort_by(|&a, &b| {
+        let position = |i: usize| measure_vector(&plane.point, &ring[i].0).dot(&direction);
+        position(a).total_cmp(&position(b))
+    });
+    for pair in on_line.windows(2) {
+        let (from, to) = (pair[0], pair[1]);
+        let (from_point, to_point) = (&ring[from].0, &ring[to].0);
+        if plane_edges.contains(&(from.min(to), from.max(to)))
+            || distance(from_point, to_point) <= tolerance
+            || !point_in_loop(&lerp(from_point, to_point, 0.5), points, &normal)
+        {
+            continue;
+        }
+        below_edges.push((from, to));
+        above_edges.push((to, from));
+    }
+
+    let to_points = |loops: Vec<Vec<usize>>| -> Vec<Vec<Point>> {
+        loops
+            .into_iter()
+            .map(|l| l.into_iter().map(|i| ring[i].0.clone()).collect())
+            .collect()
+    };
+    (
+        to_points(chain_edges(&below_edges)),
+        to_points(chain_edges(&above_edges)),
+    )
+}
+
+/// Close the openings of a shell with cap loops
+///
+/// Directed edges whose reverse is not used by another loop form the
+/// boundary; chaining their reverses yields outward-wound caps.
+#[must_use]
+pub fn cap_openings(loops: &[Vec<Point>], tolerance: f32) -> Vec<Vec<Point>> {
+    let mut index = PointIndex::new(tolerance);
+    let indexed: Vec<Vec<usize>> = loops.iter().map(|l| index.insert_loop(l)).collect();
+
+    let mut directed: HashMap<(usize, usize), usize> = HashMap::new();
+    for vertex_loop in &indexed {
+        for (position, &from) in vertex_loop.iter().enumerate() {
+            let to = vertex_loop[(position + 1) % vertex_loop.len()];
+            *directed.entry((from, to)).or_default() += 1;
+        }
+    }
+
+    let mut boundary: Vec<(usize, usize)> = directed
+        .keys()
+        .filter(|(from, to)| !directed.contains_key(&(*to, *from)))
+        .map(|&(from, to)| (to, from))
+        .collect();
+    boundary.sort_unstable();
+    chain_edges(&boundary)
+        .into_iter()
+        .map(|cap| cap.into_iter().map(|i| index.points[i].clone()).collect())
+        .collect()
+}
+
+/// Split a closed, outward-wound shell by a plane
+///
+/// Returns the closed loops below the plane (opposite the normal) and above
+/// it. Loops lying in the plane go to the side their outward normal faces
+/// away from. Either side may come back as several disconnected shells;
+/// see `connected_shells`.
+#[must_use]
+pub fn split_loops_by_plane(
+    loops: &[Vec<Point>],
+    plane: &Plane,
+    tolerance: f32,
+) -> (Vec<Vec<Point>>, Vec<Vec<Point>>) {
+    let mut below = Vec::new();
+    let mut above = Vec::new();
+
+    for points in loops {
+        let distances: Vec<f32> = points.iter().map(|p| plane.signed_distance(p)).collect();
+        if distances.iter().all(|d| d.abs() <= tolerance) {
+            if newell_normal(points).dot(&plane.normal) > 0.0 {
+                below.push(points.clone());
+            } else {
+                above.push(points.clone());
+            }
+        } else if distances.iter().all(|&d| d <= tolerance) {
+            below.push(points.clone());
+        } else if distances.iter().all(|&d| d >= -tolerance) {
+            above.push(points.clone());
+        } else {
+            let (lower, upper) = split_loop(points, plane, tolerance);
+            below.extend(lower);
+            above.extend(upper);
+        }
+    }
+
+    below.extend(cap_openings(&below, tolerance));
+    above.extend(cap_openings(&above, tolerance));
+    (below, above)
+}
+
+/// Union-find root with path halving
+fn root(parent: &mut [usize], mut node: usize) -> usize {
+    while parent[node] != node {
+        parent[node] = parent[parent[node]];
+        node = parent[node];
+    }
+    node
+}
+
+/// Group loops into edge-connected shells
+#[must_use]
+pub fn connected_shells(loops: &[Vec<Point>], tolerance: f32) -> Vec<Vec<Vec<Point>>> {
+    let mut index = PointIndex::new(tolerance);
+    let indexed: Vec<Vec<usize>> = loops.iter().map(|l| index.insert_loop(l)).collect();
+
+    // Union-find over loops that share an undirected edge
+    let mut parent: Vec<usize> = (0..loops.len()).collect();
+    let mut edge_owner: HashMap<(usize, usize), usize> = HashMap::new();
+    for (loop_index, vertex_loop) in indexed.iter().enumerate() {
+        for (position, &from) in vertex_loop.iter().enumerate() {
+            let to = vertex_loop[(position + 1) % vertex_loop.len()];
+            let edge = (from.min(to), from.max(to));
+            if let Some(&owner) = edge_owner.get(&edge) {
+                let (a, b) = (root(&mut parent, owner), root(&mut parent, loop_index));
+                parent[a] = b;
+            } else {
+                edge_owner.insert(edge, loop_index);
+            }
+        }
+    }
+
+    let mut shells: HashMap<usize, Vec<Vec<Point>>> = HashMap::new();
+    let mut order = Vec::new();
+    for (loop_index, points) in loops.iter().enumerate() {
+        let shell = root(&mut parent, loop_index);
+        if !shells.contains_key(&shell) {
+            order.push(shell);
+        }
+        shells.entry(shell).or_default().push(points.clone());
+    }
+    order
+        .into_iter()
+        .filter_map(|shell| shells.remove(&shell))
+        .collect()
+}
@@ -0,0 +1,72 @@
+/// Project coordinate system
+///
+/// Places the model in a projected map coordinate system. The model origin
+/// sits at a map easting, northing and elevation, and the model's plan axes
+/// are rotated from the map grid by the true north rotation. Model north
+/// is -Z and up is +Y. Map coordinates are kept in f64, since eastings and
+/// northings are too large for f32 to hold to the millimeter.
+use crate::domain::Point;
+
+/// Georeference of the project
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Georeference {
+    /// Map easting of the model origin in meters
+    pub easting: f64,
+    /// Map northing of the model origin in meters
+    pub northing: f64,
+    /// Elevation of the model origin in meters
+    pub elevation: f64,
+    /// Angle in radians from map east to model +X, counter-clockwise seen
+    /// from above
+    pub rotation: f64,
+    /// EPSG code of the projected coordinate system, if known
+    pub epsg: Option<u32>,
+    /// Latitude of the site in degrees, positive north, if known
+    pub latitude: Option<f64>,
+    /// Longitude of the site in degrees, positive east, if known
+    pub longitude: Option<f64>,
+}
+
+impl Georeference {
+    /// Convert map easting, northing and elevation to a model point
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn to_model(&self, easting: f64, northing: f64, elevation: f64) -> Point {
+        let (east, north) = (easting - self.easting, northing - self.northing);
+        let (sin, cos) = self.rotation.sin_cos();
+        // Undo the rotation of the model axes on the map grid
+        let x = east * cos + north * sin;
+        let plan_north = north * cos - east * sin;
+        Point {
+            x: x as f32,
+            y: (elevation - self.elevation) as f32,
+            z: -plan_north as f32,
+        }
+    }
+
+    /// Convert a model point to map easting, northing and elevation
+    #[must_use]
+    pub fn to_map(&self, point: &Point) -> (f64, f64, f64) {
+        let (x, plan_north) = (f64::from(point.x), -f64::from(point.z));
+        let (sin, cos) = self.rotation.sin_cos();
+        (
+            self.easting + x * cos - plan_north * sin,
+            self.northing + x * sin + plan_north * cos,
+            self.elevation + f64::from(point.y),
+        )
+    }
+
+    /// Direction of model +X on the map grid as (east, north) components
+    #[must_use]
+    pub fn x_axis(&self) -> (f64, f64) {
+        let (sin, cos) = self.rotation.sin_cos();
+        (cos, sin)
+    }
+
+    /// The coordinate system name, such as `EPSG:27700`
+    #[must_use]
+    pub fn crs_name(&self) -> Option<String> {
+        self.epsg.map(|code| format!("EPSG:{code}"))
+    }
+}
@@ -0,0 +1,309 @@
+/// Structural grids and the layouts generated from them
+///
+/// A grid datum is two families of lines in plan. Numbered lines, 1, 2,
+/// 3 and so on, are spaced along the grid's local X and run along its
+/// local Z; lettered lines, A, B, C and so on, are spaced along its local
+/// Z and run along its local X. The grid's placement moves and turns both
+/// families together and sets the height layouts stand on.
+///
+/// A layout generates elements from a grid: columns at every crossing, or
+/// walls along chosen lines. Each layout is recorded in the registry's
+/// dependency graph as derived from its grid, so editing the grid finds
+/// every layout to regenerate without anyone re-running them by hand.
+use crate::domain::solver::DependencyGraph;
+use crate::domain::{new_id, sorted_by_id, LinkTransform, Point};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// One of the two families of lines of a grid
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GridFamily {
+    /// Lines 1, 2, 3 and so on, spaced along the grid's local X
+    Numbered,
+    /// Lines A, B, C and so on, spaced along the grid's local Z
+    Lettered,
+}
+
+impl GridFamily {
+    /// The other family, whose lines cross this family's
+    #[must_use]
+    pub fn other(self) -> Self {
+        match self {
+            GridFamily::Numbered => GridFamily::Lettered,
+            GridFamily::Lettered => GridFamily::Numbered,
+        }
+    }
+
+    /// The label of the line of this family at an index from zero
+    #[must_use]
+    pub fn line_label(self, index: usize) -> String {
+        match self {
+            GridFamily::Numbered => (index + 1).to_string(),
+            GridFamily::Lettered => {
+                let mut label = Vec::new();
+                let mut rest = index + 1;
+                while rest > 0 {
+                    let letter = u8::try_from((rest - 1) % 26).unwrap_or_default();
+                    label.push(char::from(b'A' + letter));
+                    rest = (rest - 1) / 26;
+                }
+                label.iter().rev().collect()
+            }
+        }
+    }
+}
+
+/// A structural grid
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GridDatum {
+    /// Unique identifier of the grid
+    pub id: Uuid,
+    /// The grid's name
+    pub name: String,
+    /// Where the first lines of both families cross, the height layouts
+    /// stand on and the turn of the grid
+    pub placement: LinkTransform,
+    /// Distances between neighbouring numbered lines, one fewer than the
+    /// lines
+    pub numbered: Vec<f32>,
+    /// Distances between neighbouring lettered lines, one fewer than the
+    /// lines
+    pub lettered: Vec<f32>,
+}
+
+/// Create a new grid
+#[must_use]
+pub fn new_grid_datum(
+    name: &str,
+    placement: LinkTransform,
+    numbered: Vec<f32>,
+    lettered: Vec<f32>,
+) -> GridDatum {
+    GridDatum {
+        id: new_id(),
+        name: name.to_string(),
+        placement,
+        numbered,
+        lettered,
+    }
+}
+
+impl GridDatum {
+    /// The spacings of a family's lines
+    #[must_use]
+    pub fn spacings(&self, family: GridFamily) -> &[f32] {
+        match family {
+            GridFamily::Numbered => &self.numbered,
+            GridFamily::Lettered => &self.lettered,
+        }
+    }
+
+    /// Mutable access to the spacings of a family's lines
+    pub fn spacings_mut(&mut self, family: GridFamily) -> &mut Vec<f32> {
+        match family {
+            GridFamily::Numbered => &mut self.numbered,
+            GridFamily::Lettered => &mut self.lettered,
+        }
+    }
+
+    /// Positions of a family's lines from the first, along the grid
+    #[must_use]
+    pub fn positions(&self, family: GridFamily) -> Vec<f32> {
+        let mut positions = vec![0.0];
+        for spacing in self.spacings(family) {
+            positions.push(positions[positions.len() - 1] + spacing);
+        }
+        positions
+    }
+
+    /// Where a numbered line crosses a lettered line, at the grid's
+    /// height, or None if either line does not exist
+    #[must_use]
+    pub fn crossing(&self, numbered: usize, lettered: usize) -> Option<Point> {
+        let x = *self.positions(GridFamily::Numbered).get(numbered)?;
+        let z = *self.positions(GridFamily::Lettered).get(lettered)?;
+        Some(self.placement.apply(&Point { x, y: 0.0, z }))
+    }
+
+    /// Where a line of a family crosses a line of the other family
+    #[must_use]
+    pub fn line_point(&self, family: GridFamily, line: usize, crossing: usize) -> Option<Point> {
+        match family {
+            GridFamily::Numbered => self.crossing(line, crossing),
+            GridFamily::Lettered => self.crossing(crossing, line),
+        }
+    }
+}
+
+/// An edit to a grid
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GridEdit {
+    /// Change one spacing of a family
+    Spacing {
+        /// The family
+        family: GridFamily,
+        /// Index of the spacing, from the first line's to the next
+        index: usize,
+        /// The new distance between the two lines
+        spacing: f32,
+    },
+    /// Replace every spacing of a family, adding or removing lines
+    Spacings {
+        /// The family
+        family: GridFamily,
+        /// The new spacings
+        spacings: Vec<f32>,
+    },
+    /// Move and turn the grid
+    Placement(LinkTransform),
+}
+
+impl GridDatum {
+    /// The grid with an edit applied, or None if the edit names a spacing
+    /// the grid does not have
+    #[must_use]
+    pub fn edited(&self, edit: &GridEdit) -> Option<GridDatum> {
+        let mut grid = self.clone();
+        match edit {
+            GridEdit::Spacing {
+                family,
+                index,
+                spacing,
+            } => *grid.spacings_mut(*family).get_mut(*index)? = *spacing,
+            GridEdit::Spacings { family, spacings } => {
+                spacings.clone_into(grid.spacings_mut(*family));
+            }
+            GridEdit::Placement(placement) => grid.placement = *placement,
+        }
+        Some(grid)
+    }
+}
+
+/// A wall along a grid line, between two lines crossing it
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GridWall {
+    /// The family of the line the wall runs along
+    pub family: GridFamily,
+    /// The line's index in its family, from zero
+    pub line: usize,
+    /// Indices of the crossing lines the wall runs from and to
+    pub span: [usize; 2],
+}
+
+/// What a layout generates from its grid
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GridLayout {
+    /// A column centered on every crossing, square to the grid
+    Columns {
+        /// Size along the grid's local X
+        width: f32,
+        /// Size along the grid's local Z
+        depth: f32,
+        /// Height above the grid
+        height: f32,
+    },
+    /// Walls centered on grid lines
+    Walls {
+        /// The walls
+        walls: Vec<GridWall>,
+        /// Thickness of every wall
+        thickness: f32,
+        /// Height above the grid
+        height: f32,
+    },
+}
+
+/// A layout generated from a grid, with the elements it last generated
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GridGenerator {
+    /// Unique identifier of the generator
+    pub id: Uuid,
+    /// The grid it generates from
+    pub grid: Uuid,
+    /// What it generates
+    pub layout: GridLayout,
+    /// The elements it generated, in the layout's order
+    pub elements: Vec<Uuid>,
+}
+
+/// A registry of grids and the layouts generated from them
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GridRegistry {
+    /// Unique identifier for the registry
+    pub id: Uuid,
+    /// The grids, by ID
+    pub grids: HashMap<Uuid, GridDatum>,
+    /// The generators, by ID
+    pub generators: HashMap<Uuid, GridGenerator>,
+    /// Which generators are derived from which grids
+    pub dependencies: DependencyGraph,
+}
+
+impl GridRegistry {
+    /// Create a new, empty registry
+    #[must_use]
+    pub fn create_new() -> Self {
+        Self {
+            id: new_id(),
+            grids: HashMap::new(),
+            generators: HashMap::new(),
+            dependencies: DependencyGraph::default(),
+        }
+    }
+
+    /// Store a grid and return its ID
+    pub fn store_grid(&mut self, grid: GridDatum) -> Uuid {
+        let id = grid.id;
+        self.grids.insert(id, grid);
+        id
+    }
+
+    /// Store a generator, recording it as derived from its grid, and
+    /// return its ID
+    pub fn store_generator(&mut self, generator: GridGenerator) -> Uuid {
+        let id = generator.id;
+        self.dependencies.add(generator.grid, id);
+        self.generators.insert(id, generator);
+        id
+    }
+
+    /// Remove a generator, leaving its elements in place
+    pub fn remove_generator(&mut self, id: &Uuid) {
+        self.generators.remove(id);
+        self.dependencies.remove(id);
+    }
+
+    /// Get a grid by ID
+    #[must_use]
+    pub fn grid(&self, id: &Uuid) -> Option<&GridDatum> {
+        self.grids.get(id)
+    }
+
+    /// Get a mutable reference to a grid by ID
+    pub fn grid_mut(&mut self, id: &Uuid) -> Option<&mut GridDatum> {
+        self.grids.get_mut(id)
+    }
+
+    /// Get a generator by ID
+    #[must_use]
+    pub fn generator(&self, id: &Uuid) -> Option<&GridGenerator> {
+        self.generators.get(id)
+    }
+
+    /// Get a mutable reference to a generator by ID
+    pub fn generator_mut(&mut self, id: &Uuid) -> Option<&mut GridGenerator> {
+        self.generators.get_mut(id)
+    }
+
+    /// The grids in ID order
+    #[must_use]
+    pub fn sorted_grids(&self) -> Vec<&GridDatum> {
+        sorted_by_id(&self.grids)
+    }
+}
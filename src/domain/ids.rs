@@ -0,0 +1,181 @@
+/// Random and deterministic ID generation, and ordering by ID
+///
+/// Every new primitive, element and registry takes its ID from `new_id`.
+/// IDs are random by default. Inside `with_deterministic_ids` they are
+/// instead drawn from a seeded sequence, so running the same procedural
+/// generator or test twice produces the same IDs and its output can be
+/// diffed or compared against a golden file. The mode belongs to the
+/// current thread, so tests running side by side do not disturb each
+/// other's sequences.
+///
+/// A recorded editing session needs the same IDs when it is replayed,
+/// but its commands are carried out by systems on whichever thread is
+/// free. While a session is recorded or replayed, IDs are therefore drawn
+/// from a seeded sequence shared by every thread, unless the current
+/// thread has its own deterministic sequence. Comments, markups and export
+/// GUIDs are made outside the recorded commands, so they take
+/// `new_detached_id` instead and leave the sequence to the commands.
+///
+/// Registries keep their items in hash maps, whose iteration order changes
+/// from run to run. Anything whose output depends on that order, such as
+/// exports, meshes and solver input, walks the items sorted by ID instead.
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::hash::BuildHasher;
+use std::sync::{Mutex, PoisonError};
+use uuid::{Builder, Uuid};
+
+thread_local! {
+    /// The seed and the count of IDs drawn, None while IDs are random
+    static SEQUENCE: Cell<Option<(u64, u64)>> = const { Cell::new(None) };
+}
+
+/// The seed and the count of IDs drawn by the session shared by every
+/// thread, None outside a recorded or replayed session
+static SESSION: Mutex<Option<(u64, u64)>> = Mutex::new(None);
+
+/// Held by tests that start sessions, so they do not draw from or end
+/// each other's
+#[cfg(test)]
+pub(crate) static SESSION_TESTS: Mutex<()> = Mutex::new(());
+
+/// A new ID, random unless deterministic IDs are on for this thread or a
+/// session is being recorded or replayed
+#[must_use]
+pub fn new_id() -> Uuid {
+    SEQUENCE.with(|sequence| match sequence.get() {
+        Some((seed, count)) => {
+            sequence.set(Some((seed, count + 1)));
+            sequence_id(seed, count)
+        }
+        None => session_id().unwrap_or_else(Uuid::new_v4),
+    })
+}
+
+/// A new ID that never draws from a session's sequence: random unless
+/// deterministic IDs are on for this thread
+///
+/// For what is made outside the recorded commands, so that making it
+/// while a session is recorded does not shift the IDs the commands get.
+#[must_use]
+pub fn new_detached_id() -> Uuid {
+    if deterministic_ids() {
+        new_id()
+    } else {
+        Uuid::new_v4()
+    }
+}
+
+/// The next ID of the shared session sequence, if a session is running
+fn session_id() -> Option<Uuid> {
+    let mut session = SESSION.lock().unwrap_or_else(PoisonError::into_inner);
+    let (seed, count) = session.as_mut()?;
+    let id = sequence_id(*seed, *count);
+    *count += 1;
+    Some(id)
+}
+
+/// Draw IDs on every thread from a sequence starting at a seed, until
+/// `end_session_ids` is called
+///
+/// A session replayed from the same model with the same seed and the same
+/// commands draws the same IDs in the same order.
+pub fn begin_session_ids(seed: u64) {
+    *SESSION.lock().unwrap_or_else(PoisonError::into_inner) = Some((seed, 0));
+}
+
+/// Go back to random IDs on threads without their own sequence
+pub fn end_session_ids() {
+    *SESSION.lock().unwrap_or_else(PoisonError::into_inner) = None;
+}
+
+/// The ID at a position in a seeded sequence
+///
+/// The seed and position are mixed so that neighbouring IDs look as
+/// unrelated as random ones, and stamped as version 4 so the IDs are
+/// indistinguishable from random IDs in files.
+#[must_use]
+pub fn sequence_id(seed: u64, position: u64) -> Uuid {
+    let high = splitmix64(seed ^ splitmix64(position));
+    let low = splitmix64(high ^ position);
+    let mut bytes = [0; 16];
+    bytes[..8].copy_from_slice(&high.to_be_bytes());
+    bytes[8..].copy_from_slice(&low.to_be_bytes());
+    Builder::from_random_bytes(bytes).into_uuid()
+}
+
+/// The `SplitMix64` step, spreading every input bit over the output
+///
+/// Seeded generators mix their seed with an index through it, as IDs are,
+/// for values that look random but repeat with the seed.
+#[must_use]
+pub fn splitmix64(value: u64) -> u64 {
+    let value = value.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let value = (value ^ (value >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    let value = (value ^ (value >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    value ^ (value >> 31)
+}
+
+/// Run a closure with IDs drawn from a sequence starting at a seed
+///
+/// The previous mode is restored afterwards, so calls may nest.
+pub fn with_deterministic_ids<R>(seed: u64, f: impl FnOnce() -> R) -> R {
+    /// Restores the previous mode even if the closure panics
+    struct Restore(Option<(u64, u64)>);
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            SEQUENCE.with(|sequence| sequence.set(self.0));
+        }
+    }
+    let _restore = Restore(SEQUENCE.with(|sequence| sequence.replace(Some((seed, 0)))));
+    f()
+}
+
+/// Whether IDs on this thread are currently deterministic
+#[must_use]
+pub fn deterministic_ids() -> bool {
+    SEQUENCE.with(|sequence| sequence.get().is_some())
+}
+
+/// The values of a map sorted by their IDs
+#[must_use]
+pub fn sorted_by_id<T, S: BuildHasher>(map: &HashMap<Uuid, T, S>) -> Vec<&T> {
+    let mut entries: Vec<(&Uuid, &T)> = map.iter().collect();
+    entries.sort_by_key(|(id, _)| **id);
+    entries.into_iter().map(|(_, value)| value).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The IDs drawn in a session started with a seed
+    fn session(seed: u64) -> Vec<Uuid> {
+        begin_session_ids(seed);
+        let ids = (0..4).map(|_| new_id()).collect();
+        end_session_ids();
+        ids
+    }
+
+    #[test]
+    fn sessions_with_one_seed_draw_the_same_ids() {
+        let _lock = SESSION_TESTS.lock().unwrap_or_else(PoisonError::into_inner);
+        let first = session(7);
+        assert_eq!(first, session(7));
+        assert_ne!(first, session(8));
+        let expected: Vec<Uuid> = (0..4).map(|position| sequence_id(7, position)).collect();
+        assert_eq!(first, expected);
+    }
+
+    #[test]
+    fn ending_a_session_restores_random_ids() {
+        let _lock = SESSION_TESTS.lock().unwrap_or_else(PoisonError::into_inner);
+        let drawn = session(7);
+        let after = [new_id(), new_id()];
+        assert_ne!(after[0], after[1]);
+        assert!(after.iter().all(|id| !drawn.contains(id)));
+        assert_ne!(after[0], sequence_id(7, 4));
+        assert!(!deterministic_ids());
+        assert_eq!(session(7), drawn);
+    }
+}
@@ -0,0 +1,93 @@
+/// Level datums and the stories between them
+///
+/// A level is a named height the building is organised by, such as
+/// "Ground" at 0 m or "Level 2" at 3.5 m. Each level starts a story that
+/// runs up to the next level; the top story runs up without limit.
+/// Geometry below the lowest level is in no story.
+use crate::domain::{new_id, sorted_by_id};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// A named height the stories are measured from
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Level {
+    /// The unique identifier of the level
+    pub id: Uuid,
+    /// The level's name
+    pub name: String,
+    /// Height of the level in meters
+    pub elevation: f32,
+    /// The tier holding the geometry of the story starting at this level,
+    /// once there is one
+    pub tier: Option<Uuid>,
+}
+
+/// A registry of levels
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LevelRegistry {
+    /// Unique identifier for the registry
+    pub id: Uuid,
+    /// The levels, by ID
+    pub levels: HashMap<Uuid, Level>,
+}
+
+impl LevelRegistry {
+    /// Create a new, empty registry
+    #[must_use]
+    pub fn create_new() -> Self {
+        Self {
+            id: new_id(),
+            levels: HashMap::new(),
+        }
+    }
+
+    /// Declare, store, and return the ID of a level
+    pub fn create_and_store(&mut self, name: &str, elevation: f32) -> Uuid {
+        let level = Level {
+            id: new_id(),
+            name: name.to_string(),
+            elevation,
+            tier: None,
+        };
+        let id = level.id;
+        self.levels.insert(id, level);
+        id
+    }
+
+    /// Remove a level
+    pub fn remove(&mut self, id: &Uuid) {
+        self.levels.remove(id);
+    }
+
+    /// Get a level by ID
+    #[must_use]
+    pub fn get(&self, id: &Uuid) -> Option<&Level> {
+        self.levels.get(id)
+    }
+
+    /// Get a mutable reference to a level by ID
+    pub fn get_mut(&mut self, id: &Uuid) -> Option<&mut Level> {
+        self.levels.get_mut(id)
+    }
+
+    /// The levels from the lowest up, levels at the same height in ID order
+    #[must_use]
+    pub fn sorted(&self) -> Vec<&Level> {
+        let mut levels = sorted_by_id(&self.levels);
+        levels.sort_by(|a, b| a.elevation.total_cmp(&b.elevation));
+        levels
+    }
+
+    /// The level starting the story a height is in, or None below the
+    /// lowest level
+    ///
+    /// A height within `tolerance` of a level is on that level.
+    #[must_use]
+    pub fn story_at(&self, height: f32, tolerance: f32) -> Option<&Level> {
+        self.sorted()
+            .into_iter()
+            .rev()
+            .find(|level| level.elevation <= height + tolerance)
+    }
+}
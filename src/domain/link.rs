@@ -0,0 +1,223 @@
+/// Linked reference models
+///
+/// A linked model is another model's geometry shown in place for
+/// reference, such as the structural model under the architectural one.
+/// Its geometry lives in its own registry, reachable only through shared
+/// references, so editing operations on the host cannot change it. It is
+/// placed by its own plan transform and takes part in clash checks.
+use std::collections::HashMap;
+use std::path::PathBuf;
+use uuid::Uuid;
+
+use crate::domain::geometry::Bounds;
+use crate::domain::{new_id, GeometryRegistry, Point};
+
+/// Placement of a linked model in the host: a turn about the vertical,
+/// then a translation
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LinkTransform {
+    /// Offset along X in meters
+    pub x: f32,
+    /// Offset along Y in meters
+    pub y: f32,
+    /// Offset along Z in meters
+    pub z: f32,
+    /// Turn in radians, counter-clockwise seen from above
+    pub rotation: f32,
+}
+
+impl LinkTransform {
+    /// Place a point of the linked model in the host
+    #[must_use]
+    pub fn apply(&self, point: &Point) -> Point {
+        let (sin, cos) = self.rotation.sin_cos();
+        Point {
+            x: self.x + point.x * cos + point.z * sin,
+            y: self.y + point.y,
+            z: self.z - point.x * sin + point.z * cos,
+        }
+    }
+
+    /// Place a point of the host in the linked model, undoing `apply`
+    #[must_use]
+    pub fn unapply(&self, point: &Point) -> Point {
+        let (sin, cos) = self.rotation.sin_cos();
+        let (x, z) = (point.x - self.x, point.z - self.z);
+        Point {
+            x: x * cos - z * sin,
+            y: point.y - self.y,
+            z: x * sin + z * cos,
+        }
+    }
+}
+
+/// A clash between a host solid and a linked solid
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Clash {
+    /// The solid in the host model
+    pub host: Uuid,
+    /// The linked model
+    pub link: Uuid,
+    /// The solid in the linked model
+    pub linked: Uuid,
+}
+
+/// A read-only reference to another model
+pub struct LinkedModel {
+    /// The unique identifier of the link
+    pub id: Uuid,
+    /// The display name of the link
+    pub name: String,
+    /// The file the linked geometry was read from
+    pub path: PathBuf,
+    /// Placement of the linked model in the host
+    pub transform: LinkTransform,
+    /// Modification time of the file when it was last read
+    pub modified: Option<std::time::SystemTime>,
+    /// The linked geometry, in the linked model's own coordinates
+    geometry: GeometryRegistry,
+}
+
+/// Create a new linked model from geometry already read from `path`
+#[must_use]
+pub fn new_linked_model(
+    name: &str,
+    path: PathBuf,
+    transform: LinkTransform,
+    geometry: GeometryRegistry,
+) -> LinkedModel {
+    LinkedModel {
+        id: new_id(),
+        name: name.to_string(),
+        path,
+        transform,
+        modified: None,
+        geometry,
+    }
+}
+
+impl LinkedModel {
+    /// The linked geometry, in the linked model's own coordinates
+    #[must_use]
+    pub fn geometry(&self) -> &GeometryRegistry {
+        &self.geometry
+    }
+
+    /// Swap in freshly read geometry, keeping the link's placement
+    pub fn replace_geometry(&mut self, geometry: GeometryRegistry) {
+        self.geometry = geometry;
+    }
+
+    /// Bounds of a linked solid as placed in the host
+    /// Returns None if the solid is missing or has no geometry
+    #[must_use]
+    pub fn placed_bounds(&self, solid_id: &Uuid) -> Option<Bounds> {
+        let points: Vec<Point> = solid_points(&self.geometry, solid_id)?
+            .iter()
+            .map(|point| self.transform.apply(point))
+            .collect();
+        Bounds::from_points(&points)
+    }
+
+    /// Host and linked solids whose bounds overlap by more than the
+    /// tolerance
+    ///
+    /// Bounding boxes make this a coarse first pass: every real clash is
+    /// reported, along with some near misses between non-boxy solids.
+    #[must_use]
+    pub fn clashes(&self, host: &GeometryRegistry, tolerance: f32) -> Vec<Clash> {
+        let linked: Vec<(Uuid, Bounds)> = self
+            .geometry
+            .solids
+            .solids
+            .keys()
+            .filter_map(|id| Some((*id, self.placed_bounds(id)?)))
+            .collect();
+        let mut clashes = Vec::new();
+        for host_id in host.solids.solids.keys() {
+            let Some(host_bounds) =
+                solid_points(host, host_id).and_then(|points| Bounds::from_points(&points))
+            else {
+                continue;
+            };
+            for (linked_id, linked_bounds) in &linked {
+                if host_bounds.overlaps(linked_bounds, tolerance) {
+                    clashes.push(Clash {
+                        host: *host_id,
+                        link: self.id,
+                        linked: *linked_id,
+                    });
+                }
+            }
+        }
+        clashes.sort_by_key(|clash| (clash.host, clash.linked));
+        clashes
+    }
+}
+
+/// A registry of linked models
+pub struct LinkRegistry {
+    /// Unique identifier for the registry
+    pub id: Uuid,
+    /// The linked models in the registry
+    pub links: HashMap<Uuid, LinkedModel>,
+}
+
+impl LinkRegistry {
+    /// Create a new link registry
+    #[must_use]
+    pub fn create_new() -> Self {
+        Self {
+            id: new_id(),
+            links: HashMap::new(),
+        }
+    }
+
+    /// Store a linked model and return its ID
+    pub fn store(&mut self, link: LinkedModel) -> Uuid {
+        let id = link.id;
+        self.links.insert(id, link);
+        id
+    }
+
+    /// Remove a linked model from the registry
+    pub fn remove(&mut self, id: &Uuid) {
+        self.links.remove(id);
+    }
+
+    /// Get a reference to a linked model by ID
+    #[must_use]
+    pub fn get(&self, id: &Uuid) -> Option<&LinkedModel> {
+        self.links.get(id)
+    }
+
+    /// Get a mutable reference to a linked model by ID
+    pub fn get_mut(&mut self, id: &Uuid) -> Option<&mut LinkedModel> {
+        self.links.get_mut(id)
+    }
+
+    /// Clashes between the host and every linked model
+    #[must_use]
+    pub fn clashes(&self, host: &GeometryRegistry, tolerance: f32) -> Vec<Clash> {
+        let mut links: Vec<&LinkedModel> = self.links.values().collect();
+        links.sort_by_key(|link| link.id);
+        links
+            .into_iter()
+            .flat_map(|link| link.clashes(host, tolerance))
+            .collect()
+    }
+}
+
+/// All vertex positions of a solid's polygons
+fn solid_points(geometry: &GeometryRegistry, solid_id: &Uuid) -> Option<Vec<Point>> {
+    let solid = geometry.solids.get(solid_id)?;
+    Some(
+        solid
+            .polygons
+            .iter()
+            .filter_map(|polygon_id| geometry.polygon_points(polygon_id))
+            .flatten()
+            .collect(),
+    )
+}
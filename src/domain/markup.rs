@@ -0,0 +1,244 @@
+/// Define the Markup type and its registry
+///
+/// A markup is a reviewer's drawing over the view: a freehand stroke, an
+/// arrow or a revision cloud. Screen markups stay where they were drawn on
+/// the screen whatever the camera does; model markups hold model points
+/// and follow the model. Either way a markup is drawn at screen size, so
+/// its shape is worked out from its points' screen positions in pixels.
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::domain::{new_detached_id, new_id, sorted_by_id, Point};
+
+/// Length of an arrow's head in pixels
+const ARROW_HEAD_LENGTH: f32 = 16.0;
+/// Angle of an arrow's head strokes to its shaft in radians
+const ARROW_HEAD_ANGLE: f32 = 0.45;
+/// Width of one scallop of a revision cloud in pixels
+const CLOUD_SCALLOP_WIDTH: f32 = 24.0;
+/// Straight pieces each scallop is drawn with
+const CLOUD_SCALLOP_STEPS: usize = 6;
+
+/// What a markup draws
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MarkupShape {
+    /// A line through every point
+    #[default]
+    Freehand,
+    /// An arrow from the first point to the last
+    Arrow,
+    /// A revision cloud around the box the points span
+    Cloud,
+}
+
+impl MarkupShape {
+    /// Every shape, in the order the markup panel cycles through them
+    pub const ALL: [MarkupShape; 3] = [
+        MarkupShape::Freehand,
+        MarkupShape::Arrow,
+        MarkupShape::Cloud,
+    ];
+
+    /// Name of the shape, as shown and saved
+    #[must_use]
+    pub fn label(self) -> &'static str {
+        match self {
+            MarkupShape::Freehand => "Freehand",
+            MarkupShape::Arrow => "Arrow",
+            MarkupShape::Cloud => "Cloud",
+        }
+    }
+}
+
+/// Where a markup's points are anchored
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MarkupPoints {
+    /// Positions on the screen, as shares of the view's width and height
+    /// from its top-left corner
+    Screen(Vec<[f32; 2]>),
+    /// Positions in the model
+    Model(Vec<Point>),
+}
+
+impl MarkupPoints {
+    /// Number of points
+    #[must_use]
+    pub fn len(&self) -> usize {
+        match self {
+            MarkupPoints::Screen(points) => points.len(),
+            MarkupPoints::Model(points) => points.len(),
+        }
+    }
+
+    /// Whether there are no points
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// A reviewer's drawing over the view
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Markup {
+    /// The unique identifier of the markup
+    pub id: Uuid,
+    /// What the markup draws
+    pub shape: MarkupShape,
+    /// The points drawn through, in the order they were drawn
+    pub points: MarkupPoints,
+}
+
+/// Create a new markup
+#[must_use]
+pub fn new_markup(shape: MarkupShape, points: MarkupPoints) -> Markup {
+    Markup {
+        id: new_detached_id(),
+        shape,
+        points,
+    }
+}
+
+/// The straight lines drawing a shape through points on the screen, in
+/// pixels
+///
+/// Arrows and clouds need at least two points; a freehand line needs two
+/// to draw anything.
+#[must_use]
+pub fn markup_lines(shape: MarkupShape, points: &[[f32; 2]]) -> Vec<[[f32; 2]; 2]> {
+    let (Some(first), Some(last)) = (points.first(), points.last()) else {
+        return Vec::new();
+    };
+    match shape {
+        MarkupShape::Freehand => points.windows(2).map(|pair| [pair[0], pair[1]]).collect(),
+        MarkupShape::Arrow => arrow_lines(*first, *last),
+        MarkupShape::Cloud => cloud_lines(points),
+    }
+}
+
+/// An arrow's shaft and the two strokes of its head
+fn arrow_lines(start: [f32; 2], end: [f32; 2]) -> Vec<[[f32; 2]; 2]> {
+    let (dx, dy) = (start[0] - end[0], start[1] - end[1]);
+    let length = dx.hypot(dy);
+    if length <= f32::EPSILON {
+        return Vec::new();
+    }
+    // The head shrinks on short arrows so it never outgrows the shaft
+    let head = ARROW_HEAD_LENGTH.min(length / 3.0);
+    let back = dy.atan2(dx);
+    let stroke = |turn: f32| {
+        let (sin, cos) = (back + turn).sin_cos();
+        [end, [end[0] + cos * head, end[1] + sin * head]]
+    };
+    vec![
+        [start, end],
+        stroke(ARROW_HEAD_ANGLE),
+        stroke(-ARROW_HEAD_ANGLE),
+    ]
+}
+
+/// The scallops of a revision cloud around the box the points span
+fn cloud_lines(points: &[[f32; 2]]) -> Vec<[[f32; 2]; 2]> {
+    let (min, max) = points.iter().fold(
+        ([f32::MAX, f32::MAX], [f32::MIN, f32::MIN]),
+        |(min, max), point| {
+            (
+                [min[0].min(point[0]), min[1].min(point[1])],
+                [max[0].max(point[0]), max[1].max(point[1])],
+            )
+        },
+    );
+    if max[0] - min[0] <= f32::EPSILON || max[1] - min[1] <= f32::EPSILON {
+        return Vec::new();
+    }
+    // Each side with the direction its scallops bulge out to
+    let sides = [
+        ([min[0], min[1]], [max[0], min[1]], [0.0, -1.0]),
+        ([max[0], min[1]], [max[0], max[1]], [1.0, 0.0]),
+        ([max[0], max[1]], [min[0], max[1]], [0.0, 1.0]),
+        ([min[0], max[1]], [min[0], min[1]], [-1.0, 0.0]),
+    ];
+    let mut lines = Vec::new();
+    for (start, end, outward) in sides {
+        let (dx, dy) = (end[0] - start[0], end[1] - start[1]);
+        let length = dx.hypot(dy);
+        #[allow(
+            clippy::cast_possible_truncation,
+            clippy::cast_sign_loss,
+            clippy::cast_precision_loss
+        )]
+        let (count, radius) = {
+            let count = (length / CLOUD_SCALLOP_WIDTH).ceil().max(1.0) as usize;
+            (count, length / count as f32 / 2.0)
+        };
+        let (along_x, along_y) = (dx / length, dy / length);
+        for scallop in 0..count {
+            #[allow(clippy::cast_precision_loss)]
+            let center_distance = radius * (2 * scallop + 1) as f32;
+            let center = [
+                start[0] + along_x * center_distance,
+                start[1] + along_y * center_distance,
+            ];
+            // Half a circle from the scallop's start to its end, bulging out
+            #[allow(clippy::cast_precision_loss)]
+            let arc: Vec<[f32; 2]> = (0..=CLOUD_SCALLOP_STEPS)
+                .map(|step| {
+                    let angle = std::f32::consts::PI * step as f32 / CLOUD_SCALLOP_STEPS as f32;
+                    let (sin, cos) = angle.sin_cos();
+                    [
+                        center[0] - along_x * radius * cos + outward[0] * radius * sin,
+                        center[1] - along_y * radius * cos + outward[1] * radius * sin,
+                    ]
+                })
+                .collect();
+            lines.extend(arc.windows(2).map(|pair| [pair[0], pair[1]]));
+        }
+    }
+    lines
+}
+
+/// A registry of markups
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MarkupRegistry {
+    /// Unique identifier for the registry
+    pub id: Uuid,
+    /// The markups in the registry
+    pub markups: HashMap<Uuid, Markup>,
+}
+
+impl MarkupRegistry {
+    /// Create a new markup registry
+    #[must_use]
+    pub fn create_new() -> Self {
+        Self {
+            id: new_id(),
+            markups: HashMap::new(),
+        }
+    }
+
+    /// Store a markup and return its ID
+    pub fn store(&mut self, markup: Markup) -> Uuid {
+        let id = markup.id;
+        self.markups.insert(id, markup);
+        id
+    }
+
+    /// Remove a markup from the registry
+    pub fn remove(&mut self, id: &Uuid) {
+        self.markups.remove(id);
+    }
+
+    /// Get a reference to a markup by ID
+    #[must_use]
+    pub fn get(&self, id: &Uuid) -> Option<&Markup> {
+        self.markups.get(id)
+    }
+
+    /// The markups sorted by ID
+    #[must_use]
+    pub fn sorted(&self) -> Vec<&Markup> {
+        sorted_by_id(&self.markups)
+    }
+}
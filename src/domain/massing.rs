@@ -0,0 +1,116 @@
+/// Massing faces and the elements they become
+///
+/// Early design works with plain massing solids. Converting a mass turns
+/// each of its faces into a building element built behind the face, so
+/// the outside of the elements stays where the mass's surface was:
+///
+/// - A wall face becomes a wall as thick as the wall assembly, its first
+///   layer on the outside.
+/// - A roof face becomes a roof slab hanging below it.
+/// - A floor face becomes a floor slab resting on it.
+///
+/// Faces are tagged by how they face unless tagged otherwise: upright
+/// faces are walls, faces looking up are roofs and faces looking down are
+/// floors. A face can be tagged to be skipped, such as where the mass
+/// meets a neighbour.
+use crate::domain::{standard_wall_assemblies, Tolerance, Vector, WallAssembly};
+
+/// What a face of a mass becomes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MassFaceRole {
+    /// An enclosing wall
+    Wall,
+    /// A roof slab
+    Roof,
+    /// A floor slab
+    Floor,
+    /// Nothing
+    Skip,
+}
+
+impl MassFaceRole {
+    /// Every role, in the order tagging a face cycles through them
+    pub const ALL: [MassFaceRole; 4] = [
+        MassFaceRole::Wall,
+        MassFaceRole::Roof,
+        MassFaceRole::Floor,
+        MassFaceRole::Skip,
+    ];
+
+    /// Human-readable name of the role
+    #[must_use]
+    pub fn label(self) -> &'static str {
+        match self {
+            MassFaceRole::Wall => "Wall",
+            MassFaceRole::Roof => "Roof",
+            MassFaceRole::Floor => "Floor",
+            MassFaceRole::Skip => "Skip",
+        }
+    }
+
+    /// The role a face with a unit normal takes unless tagged otherwise
+    ///
+    /// Faces within the angular tolerance of upright are walls; other
+    /// faces are roofs if they look up at all, as pitched roofs do, and
+    /// floors if they look down.
+    #[must_use]
+    pub fn from_normal(normal: &Vector, tolerance: &Tolerance) -> Self {
+        if normal.y.abs() <= tolerance.angular.sin() {
+            MassFaceRole::Wall
+        } else if normal.y > 0.0 {
+            MassFaceRole::Roof
+        } else {
+            MassFaceRole::Floor
+        }
+    }
+}
+
+/// What converting a mass builds its elements of
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MassConversionSettings {
+    /// The layers walls are built of, which set their thickness
+    pub wall_assembly: WallAssembly,
+    /// Thickness of roof slabs
+    pub roof_thickness: f32,
+    /// The material of roof slabs, if any
+    pub roof_material: Option<String>,
+    /// Thickness of floor slabs
+    pub floor_thickness: f32,
+    /// The material of floor slabs, if any
+    pub floor_material: Option<String>,
+}
+
+impl Default for MassConversionSettings {
+    fn default() -> Self {
+        let wall_assembly = standard_wall_assemblies()
+            .into_iter()
+            .find(|assembly| assembly.name == "Insulated Concrete")
+            .unwrap_or_else(|| WallAssembly {
+                name: "Wall".to_string(),
+                layers: Vec::new(),
+            });
+        Self {
+            wall_assembly,
+            roof_thickness: 0.3,
+            roof_material: Some("Concrete".to_string()),
+            floor_thickness: 0.25,
+            floor_material: Some("Concrete".to_string()),
+        }
+    }
+}
+
+impl MassConversionSettings {
+    /// How thick the element a face of a role becomes is, or None for
+    /// skipped faces
+    #[must_use]
+    pub fn thickness(&self, role: MassFaceRole) -> Option<f32> {
+        match role {
+            MassFaceRole::Wall => Some(self.wall_assembly.thickness()),
+            MassFaceRole::Roof => Some(self.roof_thickness),
+            MassFaceRole::Floor => Some(self.floor_thickness),
+            MassFaceRole::Skip => None,
+        }
+    }
+}
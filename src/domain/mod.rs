@@ -1,18 +1,134 @@
 use uuid::Uuid;
 
+/// Wall layer assemblies and their section cuts
+pub mod assembly;
+/// Embodied carbon of the building elements
+pub mod carbon;
+/// Coordination comments raised on the model
+pub mod comment;
+/// Reusable component definitions and placed instances
+pub mod component;
+/// Cost estimates from assembly rates
+pub mod cost;
+/// Curtain wall grids dividing facade faces
+pub mod curtain_wall;
+/// Window-to-floor area checks for daylight
+pub mod daylight;
+/// Egress paths and travel distances
+pub mod egress;
+/// Building elements giving solids their meaning
+pub mod element;
+/// Thermal zones and surfaces for energy models
+pub mod energy;
+/// Which solids exports include, by phase, kind, layer or selection
+pub mod export_filter;
+/// Arithmetic expressions over the properties of named objects
+pub mod expression;
+/// Registry items' IDs in the models they were imported from
+pub mod external_id;
+/// Parametric door and window families hosted in walls
+pub mod family;
+/// Floor finishes and ceilings generated from spaces
+pub mod finish;
+/// Orphaned geometry collection
+pub mod garbage;
+/// Computational geometry helpers
+pub mod geometry;
+/// Project coordinate system and map placement
+pub mod georeference;
+/// Structural grids and the layouts generated from them
+pub mod grid;
+/// Random and deterministic ID generation, and ordering by ID
+pub mod ids;
+/// Level datums and the stories between them
+pub mod level;
+/// Read-only reference models and clash checks
+pub mod link;
+/// Review drawings over the view
+pub mod markup;
+/// Massing faces and the elements they become
+pub mod massing;
+/// Registry-level operations built on the geometry helpers
+pub mod operations;
+/// Construction phases, phase filters and per-phase takeoff
+pub mod phase;
+/// Components kept on host floors and walls
+pub mod placement;
 /// Domain layer for the application
 /// Pure domain logic, no external dependencies, no ECS, no Bevy
 pub mod primitives;
+/// Space programs and area validation
+pub mod program;
+/// How generated geometry was made, and what to run again when it changes
+pub mod provenance;
+/// Rule-based code checking
+pub mod rules;
+/// Ducts, pipes and conduits routed along centerlines
+pub mod service;
 /// Constraint solving system
 pub mod solver;
+/// Rooms found in the model
+pub mod space;
+/// Analytical structural models
+pub mod structure;
+/// Terrain surfaces, triangulation and cut/fill
+pub mod terrain;
+/// Tier hierarchy and tolerance resolution
+pub mod tier;
+/// Geometric tolerances shared by validation, snapping, welding and solving
+pub mod tolerance;
+/// Half-edge topology derived from the registries
+pub mod topology;
+/// Scaled drawings traced on work planes
+pub mod underlay;
+/// Geometry validation pipeline
+pub mod validation;
+/// Walking through the model on foot
+pub mod walk;
 
+pub use assembly::*;
+pub use carbon::*;
+pub use comment::*;
+pub use component::*;
+pub use cost::*;
+pub use curtain_wall::*;
+pub use daylight::*;
+pub use egress::*;
+pub use element::*;
+pub use energy::*;
+pub use export_filter::*;
+pub use expression::*;
+pub use external_id::*;
+pub use family::*;
+pub use finish::*;
+pub use garbage::*;
+pub use georeference::*;
+pub use grid::*;
+pub use ids::*;
+pub use level::*;
+pub use link::*;
+pub use markup::*;
+pub use massing::*;
+pub use phase::*;
+pub use placement::*;
 pub use primitives::*;
+pub use program::*;
+pub use provenance::*;
+pub use service::*;
+pub use space::*;
+pub use structure::*;
+pub use terrain::*;
+pub use tier::*;
+pub use tolerance::*;
+pub use underlay::*;
+pub use walk::*;
 // Note: solver exports are explicit to avoid ambiguous glob re-exports
 
 /// Constant to define unit size for coordinate system
 pub const METERS_PER_UNIT: f32 = 1.0;
 
 /// A registry of all geometry objects
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct GeometryRegistry {
     /// The vertices in the registry
     pub vertices: VertexRegistry,
@@ -22,6 +138,8 @@ pub struct GeometryRegistry {
     pub polygons: PolygonRegistry,
     /// The solids in the registry
     pub solids: SolidRegistry,
+    /// The direction variables in the registry
+    pub directions: DirectionRegistry,
 }
 
 impl GeometryRegistry {
@@ -32,19 +150,19 @@ impl GeometryRegistry {
             segments: SegmentRegistry::create_new(),
             polygons: PolygonRegistry::create_new(),
             solids: SolidRegistry::create_new(),
+            directions: DirectionRegistry::create_new(),
         }
     }
-}
-
-/// A tier is a geometry scope
-/// This is the basis of the hierarchical geometry system
-/// Each tier is propagated to the next tier in a one-way relationship
 
-pub struct Tier {
-    /// The name of the tier
-    pub name: String,
-    /// The geometry associated with the tier
-    pub geometry: Vec<Uuid>,
-    /// The tolerance of the tier
-    pub tolerance: f32,
+    /// Get the ordered vertex positions of a polygon
+    /// Returns None if the polygon or any of its geometry is missing
+    #[must_use]
+    pub fn polygon_points(&self, polygon_id: &Uuid) -> Option<Vec<Point>> {
+        let polygon = self.polygons.get(polygon_id)?;
+        polygon
+            .vertex_loop(&self.segments)?
+            .iter()
+            .map(|vertex_id| self.vertices.get(vertex_id).map(|v| v.position.clone()))
+            .collect()
+    }
 }
@@ -0,0 +1,211 @@
+/// Registry-level geometry operations
+///
+/// Bridges the registries and the pure loop-based helpers in `geometry`:
+/// solids are read out as point loops, processed, and written back as new
+/// vertices, segments, polygons and solids.
+use crate::domain::geometry::{
+    convex_decomposition, distance, fit_plane, is_convex_solid, ray_loop_distance, Plane,
+    PointIndex,
+};
+use crate::domain::validation::{orient_solid_outward, OrientationError};
+use crate::domain::{GeometryRegistry, Point, Tolerance, Vector};
+use std::collections::{BTreeSet, HashMap};
+use uuid::Uuid;
+
+impl GeometryRegistry {
+    /// Get the point loops of every polygon in a solid, in polygon order
+    /// Returns None if the solid or any of its geometry is missing
+    #[must_use]
+    pub fn solid_loops(&self, solid_id: &Uuid) -> Option<Vec<Vec<Point>>> {
+        self.solids
+            .get(solid_id)?
+            .polygons
+            .iter()
+            .map(|polygon_id| self.polygon_points(polygon_id))
+            .collect()
+    }
+
+    /// The IDs of every vertex in a solid, sorted
+    /// Missing polygons and segments are skipped
+    #[must_use]
+    pub fn solid_vertices(&self, solid_id: &Uuid) -> Vec<Uuid> {
+        let Some(solid) = self.solids.get(solid_id) else {
+            return Vec::new();
+        };
+        let vertices: BTreeSet<Uuid> = solid
+            .polygons
+            .iter()
+            .filter_map(|polygon_id| self.polygons.get(polygon_id))
+            .flat_map(|polygon| polygon.segments.iter())
+            .filter_map(|segment_id| self.segments.get(segment_id))
+            .flat_map(|segment| segment.vertices)
+            .collect();
+        vertices.into_iter().collect()
+    }
+
+    /// Distance along a ray to where it first meets a solid's faces
+    /// Returns None if the ray misses or the solid is missing
+    #[must_use]
+    pub fn ray_hit_solid(
+        &self,
+        solid_id: &Uuid,
+        origin: &Point,
+        direction: &Vector,
+    ) -> Option<f32> {
+        self.solids
+            .get(solid_id)?
+            .polygons
+            .iter()
+            .filter_map(|polygon_id| self.ray_hit_polygon(polygon_id, origin, direction))
+            .min_by(f32::total_cmp)
+    }
+
+    /// Distance along a ray to where it meets a polygon
+    /// Returns None if the ray misses or the polygon is missing
+    #[must_use]
+    pub fn ray_hit_polygon(
+        &self,
+        polygon_id: &Uuid,
+        origin: &Point,
+        direction: &Vector,
+    ) -> Option<f32> {
+        ray_loop_distance(origin, direction, &self.polygon_points(polygon_id)?)
+    }
+
+    /// Create a solid from closed point loops and return its ID
+    ///
+    /// Points within the linear tolerance are welded into one vertex and edges shared
+    /// by several loops become one segment. Each polygon's segments are
+    /// stored in loop order, so the loops' winding is kept, and its plane is
+    /// fitted with the normal following that winding. Loops with fewer than
+    /// three distinct points are skipped.
+    #[tracing::instrument(skip_all, fields(loops = loops.len()))]
+    pub fn create_solid_from_loops(&mut self, loops: &[Vec<Point>], tolerance: &Tolerance) -> Uuid {
+        let mut index = PointIndex::new(tolerance.linear);
+        let indexed: Vec<Vec<usize>> = loops.iter().map(|l| index.insert_loop(l)).collect();
+        let vertex_ids: Vec<Uuid> = index
+            .points
+            .iter()
+            .map(|point| self.vertices.create_and_store(point.clone()))
+            .collect();
+
+        let mut segment_ids: HashMap<(usize, usize), Uuid> = HashMap::new();
+        let mut polygon_ids = Vec::new();
+        for vertex_loop in indexed.iter().filter(|l| l.len() >= 3) {
+            let mut polygon_segments = Vec::with_capacity(vertex_loop.len());
+            for (position, &from) in vertex_loop.iter().enumerate() {
+                let to = vertex_loop[(position + 1) % vertex_loop.len()];
+                let segment_id = *segment_ids
+                    .entry((from.min(to), from.max(to)))
+                    .or_insert_with(|| {
+                        self.segments
+                            .create_and_store(&vertex_ids[from], &vertex_ids[to])
+                    });
+                polygon_segments.push(segment_id);
+            }
+            let polygon_id = self
+                .polygons
+                .create_and_store(polygon_segments.iter().collect());
+            self.update_polygon_plane(&polygon_id);
+            polygon_ids.push(polygon_id);
+        }
+        let solid_id = self.solids.create_and_store(polygon_ids.iter().collect());
+        tracing::debug!(
+            solid = %solid_id,
+            vertices = vertex_ids.len(),
+            polygons = polygon_ids.len(),
+            "created solid from loops"
+        );
+        solid_id
+    }
+
+    /// Recompute a polygon's stored plane from its current vertex positions
+    /// Returns false if the polygon or any of its geometry is missing or degenerate
+    pub fn update_polygon_plane(&mut self, polygon_id: &Uuid) -> bool {
+        let Some(points) = self.polygon_points(polygon_id) else {
+            return false;
+        };
+        self.polygons
+            .get_mut(polygon_id)
+            .is_some_and(|polygon| polygon.update_plane(&points))
+    }
+
+    /// Flip a polygon's winding and stored normal
+    /// Returns false if the polygon is missing or does not form a single loop
+    pub fn flip_polygon(&mut self, polygon_id: &Uuid) -> bool {
+        let segments = &self.segments;
+        self.polygons
+            .get_mut(polygon_id)
+            .is_some_and(|polygon| polygon.flip(segments))
+    }
+
+    /// Project a polygon's vertices onto its best-fit plane
+    ///
+    /// Vertices shared with neighbouring polygons move with it. Returns the
+    /// RMS deviation from the plane before flattening, or None if the
+    /// polygon or any of its geometry is missing or its vertices are
+    /// collinear.
+    #[tracing::instrument(level = "debug", skip(self))]
+    pub fn flatten_polygon(&mut self, polygon_id: &Uuid) -> Option<f32> {
+        let vertex_loop = self.polygons.get(polygon_id)?.vertex_loop(&self.segments)?;
+        let points = self.polygon_points(polygon_id)?;
+        let (normal, point, rms) = fit_plane(&points)?;
+        let plane = Plane { point, normal };
+        for (vertex_id, position) in vertex_loop.iter().zip(&points) {
+            let vertex = self.vertices.get_mut(vertex_id)?;
+            vertex.position.move_to_position(&plane.project(position));
+        }
+        self.polygons.get_mut(polygon_id)?.plane = Some(plane);
+        Some(rms)
+    }
+
+    /// Find the vertex a point snaps to
+    ///
+    /// Returns the nearest vertex within the snap tolerance, the first by
+    /// ID if several are equally near, or None if no vertex is close enough.
+    #[must_use]
+    pub fn snap_to_vertex(&self, point: &Point, tolerance: &Tolerance) -> Option<Uuid> {
+        self.vertices
+            .vertices
+            .values()
+            .map(|vertex| (distance(&vertex.position, point), vertex.id))
+            .filter(|(gap, _)| *gap <= tolerance.snap)
+            .min_by(|a, b| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)))
+            .map(|(_, id)| id)
+    }
+
+    /// Check whether a solid is convex
+    /// Returns None if the solid or any of its geometry is missing
+    #[must_use]
+    pub fn is_solid_convex(&self, solid_id: &Uuid, tolerance: &Tolerance) -> Option<bool> {
+        Some(is_convex_solid(
+            &self.solid_loops(solid_id)?,
+            tolerance.linear,
+        ))
+    }
+
+    /// Decompose a solid into convex solids and return their IDs
+    ///
+    /// The solid is first oriented outward, then split along its own face
+    /// planes. A convex solid yields a single copy of itself. The original
+    /// solid is left in the registry for the caller to keep or remove.
+    ///
+    /// # Errors
+    /// Returns an error if the solid is missing or cannot be oriented.
+    pub fn decompose_solid_convex(
+        &mut self,
+        solid_id: &Uuid,
+        tolerance: &Tolerance,
+    ) -> Result<Vec<Uuid>, OrientationError> {
+        orient_solid_outward(self, solid_id)?;
+        let loops = self
+            .solid_loops(solid_id)
+            .ok_or(OrientationError::SolidNotFound {
+                solid_id: *solid_id,
+            })?;
+        Ok(convex_decomposition(&loops, tolerance.linear)
+            .iter()
+            .map(|piece| self.create_solid_from_loops(piece, tolerance))
+            .collect())
+    }
+}
@@ -0,0 +1,149 @@
+/// Construction phases for renovation work
+///
+/// Every solid belongs to a phase: existing fabric that stays, existing
+/// fabric to be demolished, or new construction. Filters pick which phases
+/// the viewport and exports include, and the takeoff sums quantities per
+/// phase.
+use crate::domain::geometry::{polygon_area, signed_volume};
+use crate::domain::GeometryRegistry;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// The construction phase of a solid
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Phase {
+    /// Existing construction that is kept
+    Existing,
+    /// Existing construction to be demolished
+    Demolished,
+    /// New construction
+    #[default]
+    New,
+}
+
+impl Phase {
+    /// Every phase, in construction order
+    pub const ALL: [Phase; 3] = [Phase::Existing, Phase::Demolished, Phase::New];
+
+    /// Human-readable name of the phase
+    #[must_use]
+    pub fn label(&self) -> &'static str {
+        match self {
+            Phase::Existing => "Existing",
+            Phase::Demolished => "To demolish",
+            Phase::New => "New construction",
+        }
+    }
+}
+
+/// Which phases to show or export
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PhaseFilter {
+    /// Include existing construction
+    pub existing: bool,
+    /// Include construction to be demolished
+    pub demolished: bool,
+    /// Include new construction
+    pub new: bool,
+}
+
+impl Default for PhaseFilter {
+    fn default() -> Self {
+        Self {
+            existing: true,
+            demolished: true,
+            new: true,
+        }
+    }
+}
+
+impl PhaseFilter {
+    /// The proposed state: what remains once the work is done
+    #[must_use]
+    pub fn proposed() -> Self {
+        Self {
+            existing: true,
+            demolished: false,
+            new: true,
+        }
+    }
+
+    /// The survey state: what stands before the work starts
+    #[must_use]
+    pub fn survey() -> Self {
+        Self {
+            existing: true,
+            demolished: true,
+            new: false,
+        }
+    }
+
+    /// Check whether a phase passes the filter
+    #[must_use]
+    pub fn includes(&self, phase: Phase) -> bool {
+        match phase {
+            Phase::Existing => self.existing,
+            Phase::Demolished => self.demolished,
+            Phase::New => self.new,
+        }
+    }
+}
+
+/// Quantities summed over the solids of one phase
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct PhaseQuantities {
+    /// Number of solids
+    pub solid_count: usize,
+    /// Enclosed volume in cubic meters
+    pub volume: f32,
+    /// Surface area in square meters
+    pub surface_area: f32,
+}
+
+impl GeometryRegistry {
+    /// Set the phase of a solid
+    /// Returns false if the solid is missing
+    pub fn set_solid_phase(&mut self, solid_id: &Uuid, phase: Phase) -> bool {
+        let Some(solid) = self.solids.get_mut(solid_id) else {
+            return false;
+        };
+        solid.phase = phase;
+        true
+    }
+
+    /// IDs of the solids whose phase passes the filter
+    #[must_use]
+    pub fn solids_in_phases(&self, filter: &PhaseFilter) -> Vec<Uuid> {
+        self.solids
+            .solids
+            .values()
+            .filter(|solid| filter.includes(solid.phase))
+            .map(|solid| solid.id)
+            .collect()
+    }
+
+    /// Quantity takeoff for each phase that passes the filter
+    ///
+    /// Solids with missing geometry are counted but add no volume or area.
+    #[must_use]
+    pub fn phase_takeoff(&self, filter: &PhaseFilter) -> HashMap<Phase, PhaseQuantities> {
+        let mut takeoff: HashMap<Phase, PhaseQuantities> = Phase::ALL
+            .into_iter()
+            .filter(|phase| filter.includes(*phase))
+            .map(|phase| (phase, PhaseQuantities::default()))
+            .collect();
+        for solid in self.solids.solids.values() {
+            let Some(quantities) = takeoff.get_mut(&solid.phase) else {
+                continue;
+            };
+            quantities.solid_count += 1;
+            if let Some(loops) = self.solid_loops(&solid.id) {
+                quantities.volume += signed_volume(&loops).abs();
+                quantities.surface_area += loops.iter().map(|l| polygon_area(l)).sum::<f32>();
+            }
+        }
+        takeoff
+    }
+}
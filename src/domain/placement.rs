@@ -0,0 +1,196 @@
+/// Components placed on host faces
+///
+/// A hosted placement keeps a component instance on a floor or a wall. The
+/// instance's position is stored in the host's own frame rather than in
+/// the model's, so when the host moves or turns the instance is carried
+/// along with it.
+///
+/// A host's frame is laid out over the side of its solid the face lies
+/// on, all of the solid's faces in that plane taken together, so that a
+/// wall pierced by openings still gives one frame:
+///
+/// - On a wall, the frame starts at the bottom left corner of the side
+///   seen from outside, runs along the wall to the right and up, and its
+///   standoff points out from the wall.
+/// - On a floor, the frame runs along the floor's longest edge and across
+///   the floor from it, and its standoff points up.
+///
+/// A component's local X runs along the frame and its local Z points out
+/// from a wall, or across a floor, before its extra turn.
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::domain::{new_id, sorted_by_id, LinkTransform};
+
+/// What a host face is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HostKind {
+    /// A face looking up, such as a floor
+    Floor,
+    /// An upright face, such as the side of a wall
+    Wall,
+}
+
+impl HostKind {
+    /// Human-readable name of the kind
+    #[must_use]
+    pub fn label(self) -> &'static str {
+        match self {
+            HostKind::Floor => "Floor",
+            HostKind::Wall => "Wall",
+        }
+    }
+}
+
+/// The frame of a host face
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FaceFrame {
+    /// Whether the host is a floor or a wall
+    pub kind: HostKind,
+    /// The corner the frame starts at, as X, Y and Z
+    pub origin: [f32; 3],
+    /// The unit direction the frame runs along, as X and Z
+    pub direction: [f32; 2],
+    /// Extent of the host's side along the frame, and up a wall or across
+    /// a floor
+    pub size: [f32; 2],
+}
+
+impl FaceFrame {
+    /// The unit direction in plan a wall's standoff points along, or a
+    /// floor is crossed along
+    #[must_use]
+    pub fn across(&self) -> [f32; 2] {
+        [-self.direction[1], self.direction[0]]
+    }
+
+    /// Where a component sits with an offset in this frame
+    #[must_use]
+    pub fn place(&self, offset: &FaceOffset) -> LinkTransform {
+        let [dx, dz] = self.direction;
+        let [px, pz] = self.across();
+        let (plan, height) = match self.kind {
+            HostKind::Floor => (offset.across, offset.standoff),
+            HostKind::Wall => (offset.standoff, offset.across),
+        };
+        LinkTransform {
+            x: self.origin[0] + offset.along * dx + plan * px,
+            y: self.origin[1] + height,
+            z: self.origin[2] + offset.along * dz + plan * pz,
+            rotation: (-dz).atan2(dx) + offset.turn,
+        }
+    }
+}
+
+/// Where a component sits in its host's frame
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FaceOffset {
+    /// Distance along the frame from its start
+    pub along: f32,
+    /// Height up a wall from its bottom, or distance across a floor from
+    /// its longest edge
+    pub across: f32,
+    /// Distance off the face: out from a wall or up from a floor
+    pub standoff: f32,
+    /// Extra turn in radians, counter-clockwise seen from above
+    pub turn: f32,
+}
+
+/// Where a number of components go to be spread evenly along a length:
+/// each takes an equal share of it and sits in the middle of its share
+#[must_use]
+#[allow(clippy::cast_precision_loss)]
+pub fn equal_spacing(length: f32, count: usize) -> Vec<f32> {
+    let share = length / count.max(1) as f32;
+    (0..count)
+        .map(|index| (index as f32 + 0.5) * share)
+        .collect()
+}
+
+/// A component instance kept on a host face
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HostedPlacement {
+    /// Unique identifier of the placement
+    pub id: Uuid,
+    /// The instance's solid
+    pub solid: Uuid,
+    /// The host's solid
+    pub host: Uuid,
+    /// The host face the instance was placed on
+    pub face: Uuid,
+    /// The face's unit normal when last seen, to find the side again if
+    /// the host's faces are rebuilt
+    pub normal: [f32; 3],
+    /// Where the instance sits in the host's frame
+    pub offset: FaceOffset,
+    /// The placement the instance was last moved to
+    pub built_for: LinkTransform,
+}
+
+/// A registry of component instances kept on host faces
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HostedPlacementRegistry {
+    /// Unique identifier for the registry
+    pub id: Uuid,
+    /// The placements, by ID
+    pub placements: HashMap<Uuid, HostedPlacement>,
+}
+
+impl HostedPlacementRegistry {
+    /// Create a new, empty registry
+    #[must_use]
+    pub fn create_new() -> Self {
+        Self {
+            id: new_id(),
+            placements: HashMap::new(),
+        }
+    }
+
+    /// Store a placement and return its ID
+    pub fn store(&mut self, placement: HostedPlacement) -> Uuid {
+        let id = placement.id;
+        self.placements.insert(id, placement);
+        id
+    }
+
+    /// Remove a placement, leaving its instance where it is
+    pub fn remove(&mut self, id: &Uuid) {
+        self.placements.remove(id);
+    }
+
+    /// Get a placement by ID
+    #[must_use]
+    pub fn get(&self, id: &Uuid) -> Option<&HostedPlacement> {
+        self.placements.get(id)
+    }
+
+    /// Get a mutable reference to a placement by ID
+    pub fn get_mut(&mut self, id: &Uuid) -> Option<&mut HostedPlacement> {
+        self.placements.get_mut(id)
+    }
+
+    /// The placement keeping an instance on its host, if any
+    #[must_use]
+    pub fn of_instance(&self, solid: &Uuid) -> Option<&HostedPlacement> {
+        self.sorted()
+            .into_iter()
+            .find(|placement| placement.solid == *solid)
+    }
+
+    /// The placements on a host, in ID order
+    #[must_use]
+    pub fn on_host(&self, host: &Uuid) -> Vec<&HostedPlacement> {
+        self.sorted()
+            .into_iter()
+            .filter(|placement| placement.host == *host)
+            .collect()
+    }
+
+    /// The placements in ID order
+    #[must_use]
+    pub fn sorted(&self) -> Vec<&HostedPlacement> {
+        sorted_by_id(&self.placements)
+    }
+}
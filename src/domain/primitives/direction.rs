@@ -0,0 +1,105 @@
+/// Define the Direction type and its registry
+use crate::domain::{new_id, sorted_by_id, Vector};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// The world up direction used by plumb and level (the Y-axis)
+pub const WORLD_UP: Vector = Vector {
+    x: 0.0,
+    y: 1.0,
+    z: 0.0,
+};
+
+/// A direction variable in 3D space
+///
+/// Directions are unit vectors: every update is renormalized, so angle and
+/// alignment constraints can share one well-conditioned reference instead
+/// of each deriving it from vertex positions.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Direction {
+    /// The unique identifier of the direction
+    pub id: Uuid,
+    /// The unit vector of the direction
+    vector: Vector,
+}
+
+/// Create a new direction
+/// Returns None for a zero-length vector
+#[must_use]
+pub fn new_direction(vector: &Vector) -> Option<Direction> {
+    Some(Direction {
+        id: new_id(),
+        vector: vector.normalized()?,
+    })
+}
+
+impl Direction {
+    /// The unit vector of the direction
+    #[must_use]
+    pub fn vector(&self) -> &Vector {
+        &self.vector
+    }
+
+    /// Point the direction along `vector`, renormalizing it
+    ///
+    /// Returns false and leaves the direction unchanged for a zero-length vector.
+    pub fn set(&mut self, vector: &Vector) -> bool {
+        let Some(unit) = vector.normalized() else {
+            return false;
+        };
+        self.vector = unit;
+        true
+    }
+}
+
+/// A registry of directions
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DirectionRegistry {
+    /// Unique identifier for the registry
+    pub id: Uuid,
+    /// The directions in the registry
+    pub directions: HashMap<Uuid, Direction>,
+}
+
+impl DirectionRegistry {
+    /// Create a new direction registry
+    #[must_use]
+    pub fn create_new() -> Self {
+        Self {
+            id: new_id(),
+            directions: HashMap::new(),
+        }
+    }
+
+    /// Declare, store, and return the ID of a direction
+    /// Returns None for a zero-length vector
+    pub fn create_and_store(&mut self, vector: &Vector) -> Option<Uuid> {
+        let direction = new_direction(vector)?;
+        let id = direction.id;
+        self.directions.insert(id, direction);
+        Some(id)
+    }
+
+    /// Remove a direction from the registry
+    pub fn remove(&mut self, id: &Uuid) {
+        self.directions.remove(id);
+    }
+
+    /// Get a reference to a direction by ID
+    #[must_use]
+    pub fn get(&self, id: &Uuid) -> Option<&Direction> {
+        self.directions.get(id)
+    }
+
+    /// Get a mutable reference to a direction by ID
+    pub fn get_mut(&mut self, id: &Uuid) -> Option<&mut Direction> {
+        self.directions.get_mut(id)
+    }
+
+    /// The directions in ID order
+    #[must_use]
+    pub fn sorted(&self) -> Vec<&Direction> {
+        sorted_by_id(&self.directions)
+    }
+}
@@ -21,6 +21,10 @@ pub mod solid;
 /// A vector is a distance in 3D space
 pub mod vector;
 
+/// A direction is a unit vector variable
+pub mod direction;
+
+pub use direction::*;
 pub use point::*;
 pub use polygon::*;
 pub use segment::*;
@@ -4,6 +4,7 @@ use crate::domain::Vector;
 #[derive(Clone, Debug)]
 /// A position point in meters 3D space.
 /// Points can be moved in space.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Point {
     /// The east coordinate of the point in meters.
     /// Positive values are to the east.
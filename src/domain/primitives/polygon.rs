@@ -1,27 +1,139 @@
 /// Define the Polygon type and its registry
+use crate::domain::geometry::{fit_plane, Plane};
+use crate::domain::{new_id, sorted_by_id, Point, Segment, SegmentRegistry, Vector};
 use std::collections::HashMap;
 use uuid::Uuid;
 
 /// A polygon in 3D space
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Polygon {
     /// The unique identifier of the polygon
     pub id: Uuid,
     /// Reference to the segments of the polygon
     pub segments: Vec<Uuid>,
+    /// Reference plane of the polygon, its normal on the front (outward) side
+    /// None until computed from the vertex positions
+    pub plane: Option<Plane>,
 }
 
 /// Create a new polygon
 pub fn new_polygon(segment_ids: Vec<&Uuid>) -> Polygon {
     // Create polygon with owned UUIDs
     let new_polygon = Polygon {
-        id: Uuid::new_v4(),
+        id: new_id(),
         segments: segment_ids.iter().map(|id| **id).collect(),
+        plane: None,
     };
 
     new_polygon
 }
 
+impl Polygon {
+    /// Walk the polygon's segments and return its closed vertex loop
+    ///
+    /// The walk follows the stored segment order where consecutive segments
+    /// share a vertex, so a chained segment list defines the winding.
+    /// Returns None if the segments are missing or do not form a single loop.
+    #[must_use]
+    pub fn vertex_loop(&self, segments: &SegmentRegistry) -> Option<Vec<Uuid>> {
+        let polygon_segments: Vec<&Segment> = self
+            .segments
+            .iter()
+            .map(|id| segments.get(id))
+            .collect::<Option<Vec<_>>>()?;
+        if polygon_segments.len() < 3 {
+            return None;
+        }
+
+        // Start on the end of the first segment that is not shared with the second
+        let first = polygon_segments[0];
+        let start = if polygon_segments[1].contains_vertex(&first.vertices[0])
+            && !polygon_segments[1].contains_vertex(&first.vertices[1])
+        {
+            first.vertices[1]
+        } else {
+            first.vertices[0]
+        };
+
+        let mut used = vec![false; polygon_segments.len()];
+        used[0] = true;
+        let mut vertex_loop = vec![start];
+        let mut current = first.other_vertex(&start)?;
+
+        // Prefer the next segment in stored order so chained lists keep their winding
+        while current != start {
+            vertex_loop.push(current);
+            let next_index = (0..polygon_segments.len())
+                .find(|&index| !used[index] && polygon_segments[index].contains_vertex(&current))?;
+            used[next_index] = true;
+            current = polygon_segments[next_index].other_vertex(&current)?;
+        }
+
+        if used.iter().all(|&is_used| is_used) {
+            Some(vertex_loop)
+        } else {
+            None
+        }
+    }
+
+    /// The stored front normal, if the plane has been computed
+    #[must_use]
+    pub fn normal(&self) -> Option<&Vector> {
+        self.plane.as_ref().map(|plane| &plane.normal)
+    }
+
+    /// Recompute the reference plane from the polygon's vertex positions
+    ///
+    /// `points` must follow the polygon's winding; the normal is oriented to
+    /// agree with it. Returns false and clears the plane if the points are
+    /// degenerate.
+    pub fn update_plane(&mut self, points: &[Point]) -> bool {
+        self.plane = fit_plane(points).map(|(normal, point, _)| Plane { point, normal });
+        self.plane.is_some()
+    }
+
+    /// Reverse the polygon's winding and its stored normal together
+    ///
+    /// Returns false and leaves the polygon unchanged if its segments do
+    /// not form a single loop.
+    pub fn flip(&mut self, segments: &SegmentRegistry) -> bool {
+        let Some(mut vertex_loop) = self.vertex_loop(segments) else {
+            return false;
+        };
+        vertex_loop.reverse();
+        if !self.set_winding(&vertex_loop, segments) {
+            return false;
+        }
+        if let Some(plane) = &mut self.plane {
+            plane.normal = plane.normal.scaled(-1.0);
+        }
+        true
+    }
+
+    /// Reorder the segments so the polygon winds along `vertex_loop`
+    ///
+    /// Returns false and leaves the polygon unchanged if some consecutive
+    /// pair of vertices is not joined by one of the polygon's segments.
+    pub fn set_winding(&mut self, vertex_loop: &[Uuid], segments: &SegmentRegistry) -> bool {
+        let mut ordered = Vec::with_capacity(vertex_loop.len());
+        for (index, vertex_id) in vertex_loop.iter().enumerate() {
+            let next_id = &vertex_loop[(index + 1) % vertex_loop.len()];
+            let Some(segment_id) = self.segments.iter().find(|id| {
+                segments.get(id).is_some_and(|segment| {
+                    segment.contains_vertex(vertex_id) && segment.contains_vertex(next_id)
+                })
+            }) else {
+                return false;
+            };
+            ordered.push(*segment_id);
+        }
+        self.segments = ordered;
+        true
+    }
+}
+
 /// A registry of polygons
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PolygonRegistry {
     /// Unique identifier for the registry
     pub id: Uuid,
@@ -33,7 +145,7 @@ impl PolygonRegistry {
     /// Create a new polygon registry
     pub fn create_new() -> Self {
         Self {
-            id: Uuid::new_v4(),
+            id: new_id(),
             polygons: HashMap::new(),
         }
     }
@@ -68,4 +180,10 @@ impl PolygonRegistry {
     pub fn get_mut(&mut self, id: &Uuid) -> Option<&mut Polygon> {
         self.polygons.get_mut(id)
     }
+
+    /// The polygons in ID order
+    #[must_use]
+    pub fn sorted(&self) -> Vec<&Polygon> {
+        sorted_by_id(&self.polygons)
+    }
 }
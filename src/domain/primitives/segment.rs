@@ -1,4 +1,5 @@
 /// Define the Segment type and its registry
+use crate::domain::{new_id, sorted_by_id};
 use std::collections::HashMap;
 use uuid::Uuid;
 
@@ -6,6 +7,7 @@ use uuid::Uuid;
 ///
 /// Segments are unordered pairs of vertices - the order doesn't matter
 /// for geometric relationships, only for rendering/display purposes.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Segment {
     /// The unique identifier of the segment
     pub id: Uuid,
@@ -26,7 +28,7 @@ fn new_segment(vertex1: &Uuid, vertex2: &Uuid) -> Segment {
     };
 
     Segment {
-        id: Uuid::new_v4(),
+        id: new_id(),
         vertices,
     }
 }
@@ -55,9 +57,28 @@ impl Segment {
             None
         }
     }
+
+    /// Replace one of the segment's vertices, keeping the normalized order
+    /// Does nothing if `old_vertex` is not part of this segment
+    pub fn replace_vertex(&mut self, old_vertex: &Uuid, new_vertex: &Uuid) {
+        if let Some(other) = self.other_vertex(old_vertex) {
+            self.vertices = if *new_vertex < other {
+                [*new_vertex, other]
+            } else {
+                [other, *new_vertex]
+            };
+        }
+    }
+
+    /// Check if both ends of the segment are the same vertex
+    #[must_use]
+    pub fn is_degenerate(&self) -> bool {
+        self.vertices[0] == self.vertices[1]
+    }
 }
 
 /// A registry of segments
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SegmentRegistry {
     /// Unique identifier for the registry
     pub id: Uuid,
@@ -69,7 +90,7 @@ impl SegmentRegistry {
     /// Create a new segment registry
     pub fn create_new() -> Self {
         Self {
-            id: Uuid::new_v4(),
+            id: new_id(),
             segments: HashMap::new(),
         }
     }
@@ -104,4 +125,10 @@ impl SegmentRegistry {
     pub fn get_mut(&mut self, id: &Uuid) -> Option<&mut Segment> {
         self.segments.get_mut(id)
     }
+
+    /// The segments in ID order
+    #[must_use]
+    pub fn sorted(&self) -> Vec<&Segment> {
+        sorted_by_id(&self.segments)
+    }
 }
@@ -1,13 +1,17 @@
 /// Define the Solid type and its registry
+use crate::domain::{new_id, sorted_by_id, Phase};
 use std::collections::HashMap;
 use uuid::Uuid;
 
 /// A solid in 3D space
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Solid {
     /// The unique identifier of the solid
     pub id: Uuid,
     /// Reference to the polygons of the solid
     pub polygons: Vec<Uuid>,
+    /// The construction phase of the solid
+    pub phase: Phase,
 }
 
 /// Create a new solid
@@ -15,13 +19,15 @@ pub fn new_solid(polygon_ids: Vec<&Uuid>) -> Solid {
     // copy the polygon IDs to owned UUIDs
     let polygons: Vec<Uuid> = polygon_ids.iter().map(|p| **p).collect();
     let new_solid = Solid {
-        id: Uuid::new_v4(),
+        id: new_id(),
         polygons,
+        phase: Phase::default(),
     };
     new_solid
 }
 
 /// A registry of solids
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SolidRegistry {
     /// Unique identifier for the registry
     pub id: Uuid,
@@ -33,7 +39,7 @@ impl SolidRegistry {
     /// Create a new solid registry
     pub fn create_new() -> Self {
         Self {
-            id: Uuid::new_v4(),
+            id: new_id(),
             solids: HashMap::new(),
         }
     }
@@ -68,4 +74,10 @@ impl SolidRegistry {
     pub fn get_mut(&mut self, id: &Uuid) -> Option<&mut Solid> {
         self.solids.get_mut(id)
     }
+
+    /// The solids in ID order
+    #[must_use]
+    pub fn sorted(&self) -> Vec<&Solid> {
+        sorted_by_id(&self.solids)
+    }
 }
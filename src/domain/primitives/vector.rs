@@ -2,6 +2,7 @@ use crate::domain::Point;
 
 /// Define a vector in 3D space
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Vector {
     /// The east component of the distance in meters.
     /// Positive values are to the east.
@@ -14,6 +15,51 @@ pub struct Vector {
     pub z: f32,
 }
 
+impl Vector {
+    /// Dot product with another vector
+    #[must_use]
+    pub fn dot(&self, other: &Vector) -> f32 {
+        self.x * other.x + self.y * other.y + self.z * other.z
+    }
+
+    /// Cross product with another vector
+    #[must_use]
+    pub fn cross(&self, other: &Vector) -> Vector {
+        Vector {
+            x: self.y * other.z - self.z * other.y,
+            y: self.z * other.x - self.x * other.z,
+            z: self.x * other.y - self.y * other.x,
+        }
+    }
+
+    /// Length of the vector in meters
+    #[must_use]
+    pub fn length(&self) -> f32 {
+        self.dot(self).sqrt()
+    }
+
+    /// Unit vector in the same direction
+    /// Returns None for a zero-length vector
+    #[must_use]
+    pub fn normalized(&self) -> Option<Vector> {
+        let length = self.length();
+        if length <= f32::EPSILON {
+            return None;
+        }
+        Some(self.scaled(1.0 / length))
+    }
+
+    /// Vector multiplied by a scalar factor
+    #[must_use]
+    pub fn scaled(&self, factor: f32) -> Vector {
+        Vector {
+            x: self.x * factor,
+            y: self.y * factor,
+            z: self.z * factor,
+        }
+    }
+}
+
 /// Create a new distance
 pub fn measure_vector(start_point: &Point, end_point: &Point) -> Vector {
     Vector {
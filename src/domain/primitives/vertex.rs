@@ -1,9 +1,10 @@
 /// Define the Vertex type and its registry
-use crate::domain::Point;
+use crate::domain::{new_id, sorted_by_id, Point};
 use std::collections::HashMap;
 use uuid::Uuid;
 
 /// A vertex in 3D space
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Vertex {
     /// The unique identifier of the vertex
     pub id: Uuid,
@@ -14,13 +15,14 @@ pub struct Vertex {
 /// Create a new vertex
 pub fn new_vertex(position: Point) -> Vertex {
     let new_vertex = Vertex {
-        id: Uuid::new_v4(),
+        id: new_id(),
         position: position,
     };
     new_vertex
 }
 
 /// A registry of vertices
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct VertexRegistry {
     /// Unique identifier for the registry
     pub id: Uuid,
@@ -32,7 +34,7 @@ impl VertexRegistry {
     /// Create a new vertex registry
     pub fn create_new() -> Self {
         Self {
-            id: Uuid::new_v4(),
+            id: new_id(),
             vertices: HashMap::new(),
         }
     }
@@ -67,4 +69,10 @@ impl VertexRegistry {
     pub fn get_mut(&mut self, id: &Uuid) -> Option<&mut Vertex> {
         self.vertices.get_mut(id)
     }
+
+    /// The vertices in ID order
+    #[must_use]
+    pub fn sorted(&self) -> Vec<&Vertex> {
+        sorted_by_id(&self.vertices)
+    }
 }
@@ -0,0 +1,167 @@
+/// Space programs and area validation
+///
+/// A program lists the rooms a brief asks for, each with a target area
+/// and a count. Validation matches the model's spaces to program rooms by
+/// name, ignoring case and any trailing number, so "Bedroom 2" counts
+/// towards "Bedroom", and compares the areas found with the areas asked for.
+use crate::domain::Space;
+use uuid::Uuid;
+
+/// A room type the program asks for
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProgramRoom {
+    /// Name spaces are matched against
+    pub name: String,
+    /// Target floor area of one room
+    pub target_area: f32,
+    /// How many rooms of this type are needed
+    pub count: usize,
+}
+
+/// The rooms a design must provide
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpaceProgram {
+    /// Required rooms, in brief order
+    pub rooms: Vec<ProgramRoom>,
+    /// Fraction of the target area a room type may be off by and still pass
+    pub area_tolerance: f32,
+}
+
+impl Default for SpaceProgram {
+    fn default() -> Self {
+        Self {
+            rooms: Vec::new(),
+            area_tolerance: 0.05,
+        }
+    }
+}
+
+/// How a program room compares with the model
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProgramStatus {
+    /// The rooms and their area are within tolerance
+    Met,
+    /// Too few rooms or too little area
+    Shortfall,
+    /// More area than asked for
+    Overage,
+    /// No matching space
+    Missing,
+}
+
+impl ProgramStatus {
+    /// Human-readable name of the status
+    #[must_use]
+    pub fn label(&self) -> &'static str {
+        match self {
+            ProgramStatus::Met => "Met",
+            ProgramStatus::Shortfall => "Shortfall",
+            ProgramStatus::Overage => "Overage",
+            ProgramStatus::Missing => "Missing",
+        }
+    }
+}
+
+/// One program room and the spaces matched to it
+#[derive(Debug, Clone)]
+pub struct ProgramLine {
+    /// The program room's name
+    pub name: String,
+    /// Rooms asked for
+    pub required: usize,
+    /// Spaces matched
+    pub found: usize,
+    /// Total area asked for
+    pub target_area: f32,
+    /// Total floor area of the matched spaces
+    pub actual_area: f32,
+    /// The comparison result
+    pub status: ProgramStatus,
+    /// The matched space elements
+    pub spaces: Vec<Uuid>,
+}
+
+impl ProgramLine {
+    /// Actual minus target area, negative for a shortfall
+    #[must_use]
+    pub fn difference(&self) -> f32 {
+        self.actual_area - self.target_area
+    }
+}
+
+/// The result of validating a model against a program
+#[derive(Debug, Clone, Default)]
+pub struct ProgramReport {
+    /// One line per program room, in program order
+    pub lines: Vec<ProgramLine>,
+    /// Space elements that match no program room
+    pub unassigned: Vec<Uuid>,
+}
+
+impl ProgramReport {
+    /// Whether every program room is met
+    #[must_use]
+    pub fn is_met(&self) -> bool {
+        self.lines
+            .iter()
+            .all(|line| line.status == ProgramStatus::Met)
+    }
+}
+
+/// A space or room name reduced to the part used for matching
+fn match_key(name: &str) -> String {
+    name.trim()
+        .trim_end_matches(|c: char| c.is_ascii_digit())
+        .trim_end_matches([' ', '-', '_', '#'])
+        .to_lowercase()
+}
+
+/// Compare detected spaces with a program
+#[must_use]
+#[allow(clippy::cast_precision_loss)]
+pub fn validate_program(program: &SpaceProgram, spaces: &[Space]) -> ProgramReport {
+    let mut assigned = vec![false; spaces.len()];
+    let lines = program
+        .rooms
+        .iter()
+        .map(|room| {
+            let key = match_key(&room.name);
+            let mut matched = Vec::new();
+            let mut actual_area = 0.0;
+            for (index, space) in spaces.iter().enumerate() {
+                if !assigned[index] && match_key(&space.name) == key {
+                    assigned[index] = true;
+                    matched.push(space.element);
+                    actual_area += space.floor_area;
+                }
+            }
+            let target_area = room.target_area * room.count as f32;
+            let allowance = target_area * program.area_tolerance;
+            let status = if matched.is_empty() {
+                ProgramStatus::Missing
+            } else if matched.len() < room.count || actual_area < target_area - allowance {
+                ProgramStatus::Shortfall
+            } else if actual_area > target_area + allowance {
+                ProgramStatus::Overage
+            } else {
+                ProgramStatus::Met
+            };
+            ProgramLine {
+                name: room.name.clone(),
+                required: room.count,
+                found: matched.len(),
+                target_area,
+                actual_area,
+                status,
+                spaces: matched,
+            }
+        })
+        .collect();
+    let unassigned = spaces
+        .iter()
+        .zip(assigned)
+        .filter(|(_, assigned)| !assigned)
+        .map(|(space, _)| space.element)
+        .collect();
+    ProgramReport { lines, unassigned }
+}
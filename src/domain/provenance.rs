@@ -0,0 +1,316 @@
+/// The history of how generated geometry was made
+///
+/// Each operation that makes solids from other geometry, such as
+/// extruding a face or cutting a solid by a plane, is recorded with the
+/// primitives it read and the solids it made. The records form a graph:
+/// inputs lead to the operations reading them and operations to the
+/// solids they made, which may in turn be the inputs of later operations.
+///
+/// When an input changes, the graph gives every operation to run again,
+/// in order, and nothing else: operations whose inputs are untouched keep
+/// what they made. Each record keeps the points it was last run on, so a
+/// change is found by comparing them with the inputs as they are now.
+///
+/// The records are also kept in the order they were made, as a history
+/// tree. An operation may be moved up or down the tree as long as it stays
+/// after the operations making its inputs and before those reading what it
+/// made, and may be suppressed, leaving what it read untouched.
+use crate::domain::geometry::Plane;
+use crate::domain::solver::DependencyGraph;
+use crate::domain::{new_id, sorted_by_id, Point};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// An operation that makes solids from other geometry
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Operation {
+    /// A face swept along its normal into a solid
+    Extrude {
+        /// How far it is swept, negative to sweep it backward
+        depth: f32,
+    },
+    /// The part of a solid on one side of a plane
+    Cut {
+        /// The plane
+        plane: Plane,
+        /// Whether the part the plane's normal points into is kept,
+        /// rather than the part behind it
+        keep_front: bool,
+    },
+    /// A generator run by its own registry, such as a grid layout, whose
+    /// history is recorded here but which is run again by its owner
+    Generator {
+        /// What the generator is, for reports
+        name: String,
+    },
+}
+
+impl Operation {
+    /// The operation's name, for reports
+    #[must_use]
+    pub fn label(&self) -> &str {
+        match self {
+            Operation::Extrude { .. } => "Extrude",
+            Operation::Cut { .. } => "Cut",
+            Operation::Generator { name } => name,
+        }
+    }
+
+    /// The operation's name and parameters, for the history tree
+    #[must_use]
+    pub fn summary(&self) -> String {
+        match self {
+            Operation::Extrude { depth } => format!("Extrude {depth:.2} m"),
+            Operation::Cut { plane, keep_front } => format!(
+                "Cut at {:.2} m, keeping the {}",
+                plane.offset(),
+                if *keep_front { "front" } else { "back" }
+            ),
+            Operation::Generator { name } => name.clone(),
+        }
+    }
+
+    /// Whether another operation is of the same kind, so one can take the
+    /// other's place in the history
+    #[must_use]
+    pub fn same_kind(&self, other: &Operation) -> bool {
+        std::mem::discriminant(self) == std::mem::discriminant(other)
+    }
+}
+
+/// A change to a past operation in the history tree
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OperationChange {
+    /// Suppress it, or bring it back
+    Suppress(bool),
+    /// Replace its parameters with those of an operation of the same kind
+    Parameters(Operation),
+    /// Move it to a place in the history
+    Move(usize),
+    /// Delete it, with the operations built on what it made
+    Delete,
+}
+
+/// A recorded operation
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OperationRecord {
+    /// Unique identifier of the record
+    pub id: Uuid,
+    /// What was done
+    pub operation: Operation,
+    /// The polygons or solids it read, in order
+    pub inputs: Vec<Uuid>,
+    /// The solids it made, in order
+    pub outputs: Vec<Uuid>,
+    /// The points of its inputs when it was last run
+    pub built_from: Vec<Point>,
+    /// Whether it is suppressed, making nothing of its own: a suppressed
+    /// cut keeps the whole solid and a suppressed extrusion makes nothing
+    pub suppressed: bool,
+    /// Why it could not be run the last time it was, if it could not
+    pub error: Option<String>,
+}
+
+/// The recorded operations and what depends on what
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ProvenanceGraph {
+    /// Unique identifier for the graph
+    pub id: Uuid,
+    /// The records, by ID
+    pub records: HashMap<Uuid, OperationRecord>,
+    /// Edges from each input to the records reading it, and from each
+    /// record to the solids it made
+    pub dependencies: DependencyGraph,
+    /// The records' IDs in history order
+    pub order: Vec<Uuid>,
+}
+
+impl ProvenanceGraph {
+    /// Create a new, empty graph
+    #[must_use]
+    pub fn create_new() -> Self {
+        Self {
+            id: new_id(),
+            records: HashMap::new(),
+            dependencies: DependencyGraph::default(),
+            order: Vec::new(),
+        }
+    }
+
+    /// Record an operation and return the record's ID
+    pub fn record(
+        &mut self,
+        operation: Operation,
+        inputs: Vec<Uuid>,
+        outputs: Vec<Uuid>,
+        built_from: Vec<Point>,
+    ) -> Uuid {
+        let record = OperationRecord {
+            id: new_id(),
+            operation,
+            inputs,
+            outputs,
+            built_from,
+            suppressed: false,
+            error: None,
+        };
+        let id = record.id;
+        self.store(record);
+        id
+    }
+
+    /// Store a record, replacing the edges of any record with its ID and
+    /// adding it to the end of the history if it is new
+    pub fn store(&mut self, record: OperationRecord) {
+        if !self.order.contains(&record.id) {
+            self.order.push(record.id);
+        }
+        self.dependencies.remove(&record.id);
+        for input in &record.inputs {
+            self.dependencies.add(*input, record.id);
+        }
+        for output in &record.outputs {
+            self.dependencies.add(record.id, *output);
+        }
+        self.records.insert(record.id, record);
+    }
+
+    /// Forget a record, leaving what it made in place
+    pub fn remove(&mut self, id: &Uuid) {
+        self.records.remove(id);
+        self.dependencies.remove(id);
+        self.order.retain(|other| other != id);
+    }
+
+    /// Get a record by ID
+    #[must_use]
+    pub fn get(&self, id: &Uuid) -> Option<&OperationRecord> {
+        self.records.get(id)
+    }
+
+    /// The records in ID order
+    #[must_use]
+    pub fn sorted(&self) -> Vec<&OperationRecord> {
+        sorted_by_id(&self.records)
+    }
+
+    /// The records in history order
+    #[must_use]
+    pub fn history(&self) -> Vec<&OperationRecord> {
+        self.order
+            .iter()
+            .filter_map(|id| self.records.get(id))
+            .collect()
+    }
+
+    /// How many operations deep a record is: zero for one reading only
+    /// geometry no recorded operation made, and one more than the deepest
+    /// operation making its inputs otherwise
+    #[must_use]
+    pub fn depth_of(&self, id: &Uuid) -> usize {
+        let mut depth = 0;
+        let mut level = vec![*id];
+        let mut seen = vec![*id];
+        loop {
+            let mut next = Vec::new();
+            for record in level.iter().filter_map(|id| self.records.get(id)) {
+                for input in &record.inputs {
+                    if let Some(producer) = self.producer_of(input) {
+                        if !seen.contains(&producer.id) {
+                            seen.push(producer.id);
+                            next.push(producer.id);
+                        }
+                    }
+                }
+            }
+            if next.is_empty() {
+                return depth;
+            }
+            depth += 1;
+            level = next;
+        }
+    }
+
+    /// Move a record to a place in the history
+    ///
+    /// Returns false, leaving the history as it was, if the record is
+    /// missing or would come before an operation making its inputs or
+    /// after one reading what it made.
+    pub fn move_record(&mut self, id: &Uuid, place: usize) -> bool {
+        let Some(from) = self.order.iter().position(|other| other == id) else {
+            return false;
+        };
+        let mut order = self.order.clone();
+        order.remove(from);
+        order.insert(place.min(order.len()), *id);
+        let position = |id: &Uuid| order.iter().position(|other| other == id);
+        let keeps_dependencies = order.iter().enumerate().all(|(index, id)| {
+            self.records.get(id).is_none_or(|record| {
+                record.inputs.iter().all(|input| {
+                    self.producer_of(input)
+                        .and_then(|producer| position(&producer.id))
+                        .is_none_or(|producer| producer < index)
+                })
+            })
+        });
+        if keeps_dependencies {
+            self.order = order;
+        }
+        keeps_dependencies
+    }
+
+    /// The record of the operation that made a solid, if it was made by a
+    /// recorded operation
+    #[must_use]
+    pub fn producer_of(&self, solid: &Uuid) -> Option<&OperationRecord> {
+        self.sorted()
+            .into_iter()
+            .find(|record| record.outputs.contains(solid))
+    }
+
+    /// The records of the operations reading a primitive, in ID order
+    #[must_use]
+    pub fn consumers_of(&self, input: &Uuid) -> Vec<&OperationRecord> {
+        self.dependencies
+            .dependents_of(input)
+            .iter()
+            .filter_map(|id| self.records.get(id))
+            .collect()
+    }
+
+    /// The records of every operation a solid was made through, ending
+    /// with the one that made it
+    #[must_use]
+    pub fn history_of(&self, solid: &Uuid) -> Vec<&OperationRecord> {
+        let mut history = Vec::new();
+        let mut pending = vec![*solid];
+        while let Some(item) = pending.pop() {
+            if let Some(record) = self.producer_of(&item) {
+                if history
+                    .iter()
+                    .any(|seen: &&OperationRecord| seen.id == record.id)
+                {
+                    continue;
+                }
+                pending.extend(record.inputs.iter().copied());
+                history.push(record);
+            }
+        }
+        history.reverse();
+        history
+    }
+
+    /// The records of every operation to run again after the primitives
+    /// changed, each after the operations making its inputs
+    #[must_use]
+    pub fn invalidated(&self, changed: &[Uuid]) -> Vec<Uuid> {
+        self.dependencies
+            .affected(changed)
+            .into_iter()
+            .filter(|id| self.records.contains_key(id))
+            .collect()
+    }
+}
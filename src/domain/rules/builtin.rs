@@ -0,0 +1,186 @@
+/// Rules built into the application
+///
+/// These check model hygiene rather than any particular code: elements
+/// without materials, spaces without floors, and constraints that point
+/// at missing geometry or repeat one another.
+use crate::domain::rules::{Finding, Rule, RuleContext, RuleOutcome};
+use crate::domain::{ElementKind, GeometryRegistry};
+use uuid::Uuid;
+
+/// A single pass finding for a rule that found nothing wrong
+fn pass(rule: &str, message: String) -> Vec<Finding> {
+    vec![Finding {
+        rule: rule.to_string(),
+        outcome: RuleOutcome::Pass,
+        entities: Vec::new(),
+        message,
+    }]
+}
+
+/// Whether an ID names any geometry in the registry
+fn geometry_exists(geometry_registry: &GeometryRegistry, id: &Uuid) -> bool {
+    geometry_registry.vertices.vertices.contains_key(id)
+        || geometry_registry.segments.segments.contains_key(id)
+        || geometry_registry.polygons.polygons.contains_key(id)
+        || geometry_registry.solids.solids.contains_key(id)
+        || geometry_registry.directions.directions.contains_key(id)
+}
+
+/// Every physical element has a material
+///
+/// Spaces are voids and are not checked.
+pub struct MaterialAssigned;
+
+impl Rule for MaterialAssigned {
+    fn id(&self) -> &'static str {
+        "material-assigned"
+    }
+
+    fn description(&self) -> &'static str {
+        "Every physical element has a material"
+    }
+
+    fn check(&self, context: &RuleContext) -> Vec<Finding> {
+        let mut missing: Vec<_> = context
+            .element_registry
+            .elements
+            .values()
+            .filter(|element| element.kind != ElementKind::Space && element.material.is_none())
+            .collect();
+        if missing.is_empty() {
+            return pass(self.id(), "All elements have a material".to_string());
+        }
+        missing.sort_by(|a, b| a.name.cmp(&b.name).then(a.id.cmp(&b.id)));
+        missing
+            .into_iter()
+            .map(|element| Finding {
+                rule: self.id().to_string(),
+                outcome: RuleOutcome::Warn,
+                entities: vec![element.id, element.solid],
+                message: format!("{} {} has no material", element.kind.label(), element.name),
+            })
+            .collect()
+    }
+}
+
+/// Every space has a floor to stand on
+pub struct SpacesHaveFloors;
+
+impl Rule for SpacesHaveFloors {
+    fn id(&self) -> &'static str {
+        "space-has-floor"
+    }
+
+    fn description(&self) -> &'static str {
+        "Every space has a downward-facing floor"
+    }
+
+    fn check(&self, context: &RuleContext) -> Vec<Finding> {
+        let findings: Vec<Finding> = context
+            .spaces
+            .iter()
+            .filter(|space| space.floor_area <= 0.0)
+            .map(|space| Finding {
+                rule: self.id().to_string(),
+                outcome: RuleOutcome::Fail,
+                entities: vec![space.element, space.solid],
+                message: format!("Space {} has no floor", space.name),
+            })
+            .collect();
+        if findings.is_empty() {
+            return pass(
+                self.id(),
+                format!("{} space(s) have floors", context.spaces.len()),
+            );
+        }
+        findings
+    }
+}
+
+/// Every constraint target exists in the geometry registry
+pub struct ConstraintTargetsExist;
+
+impl Rule for ConstraintTargetsExist {
+    fn id(&self) -> &'static str {
+        "constraint-targets-exist"
+    }
+
+    fn description(&self) -> &'static str {
+        "Constraints only refer to geometry in the model"
+    }
+
+    fn check(&self, context: &RuleContext) -> Vec<Finding> {
+        let findings: Vec<Finding> = context
+            .constraints
+            .iter()
+            .filter_map(|constraint| {
+                let missing: Vec<Uuid> = constraint
+                    .targets
+                    .iter()
+                    .filter(|id| !geometry_exists(context.geometry_registry, id))
+                    .copied()
+                    .collect();
+                (!missing.is_empty()).then(|| Finding {
+                    rule: self.id().to_string(),
+                    outcome: RuleOutcome::Fail,
+                    message: format!(
+                        "{:?} constraint refers to {} missing target(s)",
+                        constraint.kind,
+                        missing.len()
+                    ),
+                    entities: missing,
+                })
+            })
+            .collect();
+        if findings.is_empty() {
+            return pass(
+                self.id(),
+                format!("{} constraint(s) checked", context.constraints.len()),
+            );
+        }
+        findings
+    }
+}
+
+/// No constraint repeats an earlier one
+pub struct NoDuplicateConstraints;
+
+impl Rule for NoDuplicateConstraints {
+    fn id(&self) -> &'static str {
+        "no-duplicate-constraints"
+    }
+
+    fn description(&self) -> &'static str {
+        "No constraint repeats another"
+    }
+
+    fn check(&self, context: &RuleContext) -> Vec<Finding> {
+        let constraints = context.constraints;
+        let findings: Vec<Finding> = constraints
+            .iter()
+            .enumerate()
+            .filter(|(index, constraint)| {
+                constraints[..*index]
+                    .iter()
+                    .any(|earlier| constraint.is_duplicate_of(earlier))
+            })
+            .map(|(index, constraint)| Finding {
+                rule: self.id().to_string(),
+                outcome: RuleOutcome::Warn,
+                entities: constraint.targets.clone(),
+                message: format!(
+                    "{:?} constraint {} repeats an earlier one",
+                    constraint.kind,
+                    index + 1
+                ),
+            })
+            .collect();
+        if findings.is_empty() {
+            return pass(
+                self.id(),
+                format!("{} constraint(s) are distinct", constraints.len()),
+            );
+        }
+        findings
+    }
+}
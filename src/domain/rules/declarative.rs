@@ -0,0 +1,175 @@
+/// Property limits on elements and spaces
+///
+/// A property rule measures one property of every element of a kind,
+/// optionally only those whose name contains some text, and reports each
+/// element outside the limits. These are the rules a rule file can
+/// express without writing Rust.
+use crate::domain::geometry::{newell_normal, polygon_area, signed_volume, Bounds};
+use crate::domain::rules::{Finding, Rule, RuleContext, RuleOutcome};
+use crate::domain::{Element, ElementKind};
+
+/// A measurable property of an element
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RuleProperty {
+    /// Total area of the faces pointing down, as for a space's floor
+    FloorArea,
+    /// Enclosed volume of the solid
+    Volume,
+    /// Vertical extent of the solid
+    Height,
+    /// Smaller plan dimension of the solid's bounding box
+    Width,
+    /// Larger plan dimension of the solid's bounding box
+    Length,
+}
+
+impl RuleProperty {
+    /// The name of the property in rule files
+    #[must_use]
+    pub fn key(self) -> &'static str {
+        match self {
+            RuleProperty::FloorArea => "floor_area",
+            RuleProperty::Volume => "volume",
+            RuleProperty::Height => "height",
+            RuleProperty::Width => "width",
+            RuleProperty::Length => "length",
+        }
+    }
+
+    /// Look up a property by its rule file name
+    #[must_use]
+    pub fn from_key(key: &str) -> Option<Self> {
+        [
+            RuleProperty::FloorArea,
+            RuleProperty::Volume,
+            RuleProperty::Height,
+            RuleProperty::Width,
+            RuleProperty::Length,
+        ]
+        .into_iter()
+        .find(|property| property.key() == key)
+    }
+
+    /// The unit the property is measured in
+    #[must_use]
+    pub fn unit(self) -> &'static str {
+        match self {
+            RuleProperty::FloorArea => "m²",
+            RuleProperty::Volume => "m³",
+            RuleProperty::Height | RuleProperty::Width | RuleProperty::Length => "m",
+        }
+    }
+}
+
+/// A limit on one property of the elements of a kind
+#[derive(Debug, Clone)]
+pub struct PropertyRule {
+    /// Short stable identifier
+    pub id: String,
+    /// What the rule requires, in words
+    pub description: String,
+    /// The kind of element checked
+    pub applies_to: ElementKind,
+    /// Only check elements whose name contains this, ignoring case
+    pub name_contains: Option<String>,
+    /// The property measured
+    pub property: RuleProperty,
+    /// Smallest allowed value
+    pub min: Option<f32>,
+    /// Largest allowed value
+    pub max: Option<f32>,
+    /// The outcome of an element outside the limits, warn or fail
+    pub severity: RuleOutcome,
+}
+
+impl PropertyRule {
+    /// Whether the rule checks an element
+    fn applies(&self, element: &Element) -> bool {
+        element.kind == self.applies_to
+            && self
+                .name_contains
+                .as_ref()
+                .is_none_or(|text| element.name.to_lowercase().contains(&text.to_lowercase()))
+    }
+}
+
+/// Measure a property of an element's solid
+fn measure(context: &RuleContext, element: &Element, property: RuleProperty) -> Option<f32> {
+    let loops = context.geometry_registry.solid_loops(&element.solid)?;
+    let bounds = Bounds::from_points(&loops.concat())?;
+    let (span_x, span_z) = (bounds.max.x - bounds.min.x, bounds.max.z - bounds.min.z);
+    Some(match property {
+        RuleProperty::FloorArea => loops
+            .iter()
+            .filter(|points| {
+                let normal = newell_normal(points);
+                normal.y < 0.0 && -normal.y >= normal.length() * std::f32::consts::FRAC_1_SQRT_2
+            })
+            .map(|points| polygon_area(points))
+            .sum(),
+        RuleProperty::Volume => signed_volume(&loops).abs(),
+        RuleProperty::Height => bounds.max.y - bounds.min.y,
+        RuleProperty::Width => span_x.min(span_z),
+        RuleProperty::Length => span_x.max(span_z),
+    })
+}
+
+impl Rule for PropertyRule {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn description(&self) -> &str {
+        &self.description
+    }
+
+    /// One finding per element outside the limits, or a single pass
+    fn check(&self, context: &RuleContext) -> Vec<Finding> {
+        let mut elements: Vec<&Element> = context
+            .element_registry
+            .elements
+            .values()
+            .filter(|element| self.applies(element))
+            .collect();
+        elements.sort_by(|a, b| a.name.cmp(&b.name).then(a.id.cmp(&b.id)));
+
+        let unit = self.property.unit();
+        let mut findings = Vec::new();
+        for element in &elements {
+            let Some(value) = measure(context, element, self.property) else {
+                continue;
+            };
+            let limit = match (self.min, self.max) {
+                (Some(min), _) if value < min => Some(format!("below the minimum {min:.2} {unit}")),
+                (_, Some(max)) if value > max => Some(format!("above the maximum {max:.2} {unit}")),
+                _ => None,
+            };
+            if let Some(limit) = limit {
+                findings.push(Finding {
+                    rule: self.id.clone(),
+                    outcome: self.severity,
+                    entities: vec![element.id, element.solid],
+                    message: format!(
+                        "{} {}: {} {value:.2} {unit} is {limit}",
+                        element.kind.label(),
+                        element.name,
+                        self.property.key()
+                    ),
+                });
+            }
+        }
+        if findings.is_empty() {
+            findings.push(Finding {
+                rule: self.id.clone(),
+                outcome: RuleOutcome::Pass,
+                entities: elements.iter().map(|element| element.id).collect(),
+                message: format!(
+                    "{} {} element(s) checked",
+                    elements.len(),
+                    self.applies_to.label()
+                ),
+            });
+        }
+        findings
+    }
+}
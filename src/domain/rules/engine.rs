@@ -0,0 +1,155 @@
+/// The rule trait, findings and the rule set runner
+use crate::domain::rules::{
+    ConstraintTargetsExist, MaterialAssigned, NoDuplicateConstraints, SpacesHaveFloors,
+};
+use crate::domain::solver::Constraint;
+use crate::domain::{detect_spaces, ElementRegistry, GeometryRegistry, Space};
+use uuid::Uuid;
+
+/// How a rule judged what it checked
+///
+/// Ordered from best to worst so reports can be sorted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum RuleOutcome {
+    /// The rule is satisfied
+    Pass,
+    /// Worth a look, but not a violation
+    Warn,
+    /// The rule is violated
+    Fail,
+}
+
+impl RuleOutcome {
+    /// Short label for display
+    #[must_use]
+    pub fn label(self) -> &'static str {
+        match self {
+            RuleOutcome::Pass => "Pass",
+            RuleOutcome::Warn => "Warn",
+            RuleOutcome::Fail => "Fail",
+        }
+    }
+}
+
+/// A single rule finding
+#[derive(Debug, Clone)]
+pub struct Finding {
+    /// The ID of the rule that made the finding
+    pub rule: String,
+    /// How the rule judged it
+    pub outcome: RuleOutcome,
+    /// The elements, geometry or constraint targets involved, most relevant first
+    pub entities: Vec<Uuid>,
+    /// Human-readable description
+    pub message: String,
+}
+
+/// What rules can see of the model
+pub struct RuleContext<'a> {
+    /// The geometry registry
+    pub geometry_registry: &'a GeometryRegistry,
+    /// The element registry
+    pub element_registry: &'a ElementRegistry,
+    /// The spaces detected in the model
+    pub spaces: Vec<Space>,
+    /// The explicit constraints to check
+    pub constraints: &'a [Constraint],
+}
+
+impl<'a> RuleContext<'a> {
+    /// A context over the registries, detecting spaces once for all rules
+    #[must_use]
+    pub fn new(
+        geometry_registry: &'a GeometryRegistry,
+        element_registry: &'a ElementRegistry,
+        constraints: &'a [Constraint],
+    ) -> Self {
+        Self {
+            geometry_registry,
+            element_registry,
+            spaces: detect_spaces(element_registry, geometry_registry),
+            constraints,
+        }
+    }
+}
+
+/// A code check over the model
+pub trait Rule: Send + Sync {
+    /// Short stable identifier, used in reports
+    fn id(&self) -> &str;
+
+    /// One-line description of what the rule requires
+    fn description(&self) -> &str;
+
+    /// Check the model and return the findings
+    fn check(&self, context: &RuleContext) -> Vec<Finding>;
+}
+
+/// The findings of a rule set run
+#[derive(Debug, Clone, Default)]
+pub struct RuleReport {
+    /// The findings, worst first
+    pub findings: Vec<Finding>,
+}
+
+impl RuleReport {
+    /// Count the findings with an outcome
+    #[must_use]
+    pub fn count(&self, outcome: RuleOutcome) -> usize {
+        self.findings
+            .iter()
+            .filter(|finding| finding.outcome == outcome)
+            .count()
+    }
+
+    /// Check if any finding is a failure
+    #[must_use]
+    pub fn has_failures(&self) -> bool {
+        self.count(RuleOutcome::Fail) > 0
+    }
+}
+
+/// An ordered collection of rules run together
+#[derive(Default)]
+pub struct RuleSet {
+    /// The rules, in the order they run
+    pub rules: Vec<Box<dyn Rule>>,
+}
+
+impl RuleSet {
+    /// A rule set with the built-in rules
+    #[must_use]
+    pub fn with_builtin_rules() -> Self {
+        let mut rules = Self::default();
+        rules.add(Box::new(MaterialAssigned));
+        rules.add(Box::new(SpacesHaveFloors));
+        rules.add(Box::new(ConstraintTargetsExist));
+        rules.add(Box::new(NoDuplicateConstraints));
+        rules
+    }
+
+    /// Add a rule, replacing any rule with the same ID
+    pub fn add(&mut self, rule: Box<dyn Rule>) {
+        self.rules.retain(|existing| existing.id() != rule.id());
+        self.rules.push(rule);
+    }
+
+    /// Run every rule and return the findings, worst first
+    #[must_use]
+    #[tracing::instrument(skip_all, fields(rules = self.rules.len()))]
+    pub fn run(&self, context: &RuleContext) -> RuleReport {
+        let mut findings: Vec<Finding> = self
+            .rules
+            .iter()
+            .flat_map(|rule| rule.check(context))
+            .collect();
+        findings.sort_by_key(|finding| std::cmp::Reverse(finding.outcome));
+        let report = RuleReport { findings };
+        tracing::info!(
+            failures = report.count(RuleOutcome::Fail),
+            warnings = report.count(RuleOutcome::Warn),
+            "ran rules"
+        );
+        report
+    }
+}
@@ -0,0 +1,21 @@
+//! Rule-based code checking
+//!
+//! Rules query the model's elements, spaces and constraints and report
+//! findings graded pass, warn or fail, each naming the entities it is
+//! about. Rules are either Rust types implementing `Rule` or declarative
+//! property limits, typically loaded from a rule file.
+//!
+//! Like validation checks, rules are pure: they never change the model.
+
+/// The rule trait, findings and the rule set runner
+pub mod engine;
+
+/// Property limits on elements and spaces
+pub mod declarative;
+
+/// Rules built into the application
+pub mod builtin;
+
+pub use builtin::*;
+pub use declarative::*;
+pub use engine::*;
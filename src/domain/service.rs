@@ -0,0 +1,608 @@
+/// Ducts, pipes and conduits routed along centerlines
+///
+/// A service run is a centerline path with a section, round or
+/// rectangular, swept along it. Where the path bends, the run turns
+/// through an elbow whose centerline is an arc, so the straight segments
+/// on either side stop short of the bend. Where another run branches off
+/// partway along a segment, a tee collar takes the place of that stretch
+/// of the segment and the branch stops at the collar's surface.
+///
+/// A rectangular section keeps its height upright on segments that are
+/// not vertical. Through each elbow its sides turn with the bend, so the
+/// section leaves an elbow the same way up as it entered.
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::domain::geometry::signed_volume;
+use crate::domain::{measure_vector, new_id, sorted_by_id, ElementKind, Point, Tolerance, Vector};
+
+/// Number of sides of the polygon standing in for a round section
+pub const ROUND_SIDES: usize = 12;
+/// Centerline radius of elbows, as a multiple of the section's size
+pub const BEND_RADIUS_FACTOR: f32 = 1.5;
+/// How far a tee collar reaches past its branch on either side
+pub const TEE_COLLAR: f32 = 0.05;
+/// Number of straight pieces an elbow's arc is divided into
+const ELBOW_SEGMENTS: usize = 4;
+
+/// What a service run carries
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ServiceKind {
+    /// Air, in ductwork
+    Duct,
+    /// Water, gas or drainage, in pipework
+    Pipe,
+    /// Cables, in conduit
+    Conduit,
+}
+
+impl ServiceKind {
+    /// Every kind of service
+    pub const ALL: [ServiceKind; 3] = [ServiceKind::Duct, ServiceKind::Pipe, ServiceKind::Conduit];
+
+    /// Human-readable name of the kind
+    #[must_use]
+    pub fn label(self) -> &'static str {
+        match self {
+            ServiceKind::Duct => "Duct",
+            ServiceKind::Pipe => "Pipe",
+            ServiceKind::Conduit => "Conduit",
+        }
+    }
+
+    /// The kind of the elements a run of this kind is made of
+    #[must_use]
+    pub fn element_kind(self) -> ElementKind {
+        match self {
+            ServiceKind::Duct => ElementKind::Duct,
+            ServiceKind::Pipe => ElementKind::Pipe,
+            ServiceKind::Conduit => ElementKind::Conduit,
+        }
+    }
+}
+
+/// The cross-section of a service run
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ServiceSection {
+    /// A round section of a diameter
+    Round {
+        /// Outside diameter in meters
+        diameter: f32,
+    },
+    /// A rectangular section, its width level and its height upright
+    Rectangular {
+        /// Outside width in meters
+        width: f32,
+        /// Outside height in meters
+        height: f32,
+    },
+}
+
+impl ServiceSection {
+    /// The section's largest dimension
+    #[must_use]
+    pub fn size(self) -> f32 {
+        match self {
+            ServiceSection::Round { diameter } => diameter,
+            ServiceSection::Rectangular { width, height } => width.max(height),
+        }
+    }
+
+    /// Human-readable dimensions of the section
+    #[must_use]
+    pub fn label(self) -> String {
+        match self {
+            ServiceSection::Round { diameter } => format!("{diameter} m round"),
+            ServiceSection::Rectangular { width, height } => format!("{width} x {height} m"),
+        }
+    }
+
+    /// Distance from the axis to the surface, in a direction across the
+    /// axis given by its unit components along the section's side and up
+    #[must_use]
+    pub fn reach(self, side: f32, up: f32) -> f32 {
+        match self {
+            ServiceSection::Round { diameter } => diameter / 2.0,
+            ServiceSection::Rectangular { width, height } => {
+                let across = |half: f32, part: f32| {
+                    if part.abs() <= f32::EPSILON {
+                        f32::MAX
+                    } else {
+                        half / part.abs()
+                    }
+                };
+                across(width / 2.0, side).min(across(height / 2.0, up))
+            }
+        }
+    }
+
+    /// The outline of the section around a point of the axis, in the plane
+    /// of a frame's side and up
+    #[allow(clippy::cast_precision_loss)]
+    fn profile(self, center: &Point, frame: &SectionFrame) -> Vec<Point> {
+        let offsets: Vec<(f32, f32)> = match self {
+            ServiceSection::Round { diameter } => (0..ROUND_SIDES)
+                .map(|index| {
+                    let angle = std::f32::consts::TAU * index as f32 / ROUND_SIDES as f32;
+                    let (sin, cos) = angle.sin_cos();
+                    (cos * diameter / 2.0, sin * diameter / 2.0)
+                })
+                .collect(),
+            ServiceSection::Rectangular { width, height } => {
+                let (half_width, half_height) = (width / 2.0, height / 2.0);
+                vec![
+                    (-half_width, -half_height),
+                    (half_width, -half_height),
+                    (half_width, half_height),
+                    (-half_width, half_height),
+                ]
+            }
+        };
+        offsets
+            .into_iter()
+            .map(|(side, up)| offset(&offset(center, &frame.side, side), &frame.up, up))
+            .collect()
+    }
+}
+
+/// The directions a section is set out in at a point of a run
+#[derive(Debug, Clone)]
+pub struct SectionFrame {
+    /// Unit direction of the run
+    pub axis: Vector,
+    /// Unit direction of the section's width, across the run
+    pub side: Vector,
+    /// Unit direction of the section's height, across the run
+    pub up: Vector,
+}
+
+impl SectionFrame {
+    /// The frame turned about an axis through an angle
+    fn rotated(&self, about: &Vector, angle: f32) -> SectionFrame {
+        SectionFrame {
+            axis: rotate(&self.axis, about, angle),
+            side: rotate(&self.side, about, angle),
+            up: rotate(&self.up, about, angle),
+        }
+    }
+}
+
+/// What a piece of a service run is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PieceKind {
+    /// A straight length
+    Segment,
+    /// A fitting turning the run at a bend
+    Elbow,
+    /// A fitting a branch joins
+    Tee,
+}
+
+impl PieceKind {
+    /// Human-readable name of the kind
+    #[must_use]
+    pub fn label(self) -> &'static str {
+        match self {
+            PieceKind::Segment => "Segment",
+            PieceKind::Elbow => "Elbow",
+            PieceKind::Tee => "Tee",
+        }
+    }
+}
+
+/// A piece of a service run, ready to become a solid
+#[derive(Debug, Clone)]
+pub struct ServicePiece {
+    /// What the piece is
+    pub kind: PieceKind,
+    /// Its faces, wound counter-clockwise seen from outside
+    pub loops: Vec<Vec<Point>>,
+}
+
+/// A branch joining a run partway along one of its segments
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Tee {
+    /// The branch run
+    pub branch: Uuid,
+    /// The segment the branch joins, segment `i` running from path point
+    /// `i` to the next
+    pub segment: usize,
+    /// Distance of the junction from the segment's start
+    pub along: f32,
+    /// Length of the tee collar along the segment
+    pub length: f32,
+}
+
+/// A duct, pipe or conduit routed along a centerline
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ServiceRun {
+    /// Unique identifier of the run
+    pub id: Uuid,
+    /// What it carries
+    pub kind: ServiceKind,
+    /// Its cross-section
+    pub section: ServiceSection,
+    /// The centerline, from start to end
+    pub path: Vec<Point>,
+    /// How far the run stops short of its start, where it branches off
+    /// another run
+    pub start_trim: f32,
+    /// How far the run stops short of its end
+    pub end_trim: f32,
+    /// The branches joining the run
+    pub tees: Vec<Tee>,
+    /// The elements the run is built of, segments and fittings
+    pub elements: Vec<Uuid>,
+}
+
+/// Where a branch meets a run partway along one of its segments
+#[derive(Debug, Clone)]
+pub struct Junction {
+    /// The segment the branch meets
+    pub segment: usize,
+    /// Distance of the junction from the segment's start
+    pub along: f32,
+    /// The junction, on the run's centerline
+    pub point: Point,
+    /// Distance from the junction to the run's surface along the branch
+    pub trim: f32,
+    /// How much of the branch's direction lies across the run, from zero
+    /// for a branch along the run to one for a square branch
+    pub across: f32,
+}
+
+impl ServiceRun {
+    /// Where a branch leaving a point in a direction would meet the run
+    ///
+    /// The point must lie within the snap radius of a segment's
+    /// centerline, clear of the segment's ends. Returns None if it does
+    /// not, or if the branch runs along the segment.
+    #[must_use]
+    pub fn junction(
+        &self,
+        point: &Point,
+        direction: &Vector,
+        tolerance: &Tolerance,
+    ) -> Option<Junction> {
+        let frames = section_frames(&self.path, tolerance).ok()?;
+        frames.iter().enumerate().find_map(|(segment, frame)| {
+            let start = &self.path[segment];
+            let length = measure_vector(start, &self.path[segment + 1]).length();
+            let along = frame.axis.dot(&measure_vector(start, point));
+            if along <= tolerance.linear || along >= length - tolerance.linear {
+                return None;
+            }
+            let center = offset(start, &frame.axis, along);
+            if measure_vector(&center, point).length() > tolerance.snap {
+                return None;
+            }
+            let square = across(direction, &frame.axis)?;
+            let fraction = (1.0 - frame.axis.dot(direction).powi(2)).max(0.0).sqrt();
+            if fraction < tolerance.angular.sin() {
+                return None;
+            }
+            let reach = self
+                .section
+                .reach(square.dot(&frame.side), square.dot(&frame.up));
+            Some(Junction {
+                segment,
+                along,
+                point: center,
+                trim: reach / fraction,
+                across: fraction,
+            })
+        })
+    }
+}
+
+/// Create a new service run along a path, not yet built
+#[must_use]
+pub fn new_service_run(kind: ServiceKind, section: ServiceSection, path: Vec<Point>) -> ServiceRun {
+    ServiceRun {
+        id: new_id(),
+        kind,
+        section,
+        path,
+        start_trim: 0.0,
+        end_trim: 0.0,
+        tees: Vec::new(),
+        elements: Vec::new(),
+    }
+}
+
+/// A point moved along a direction
+fn offset(point: &Point, direction: &Vector, distance: f32) -> Point {
+    Point {
+        x: point.x + direction.x * distance,
+        y: point.y + direction.y * distance,
+        z: point.z + direction.z * distance,
+    }
+}
+
+/// A vector turned about a unit axis through an angle
+fn rotate(vector: &Vector, about: &Vector, angle: f32) -> Vector {
+    let (sin, cos) = angle.sin_cos();
+    let cross = about.cross(vector);
+    let along = about.dot(vector) * (1.0 - cos);
+    Vector {
+        x: vector.x * cos + cross.x * sin + about.x * along,
+        y: vector.y * cos + cross.y * sin + about.y * along,
+        z: vector.z * cos + cross.z * sin + about.z * along,
+    }
+}
+
+/// The part of a vector across a unit axis, scaled to unit length
+fn across(vector: &Vector, axis: &Vector) -> Option<Vector> {
+    let along = axis.dot(vector);
+    Vector {
+        x: vector.x - axis.x * along,
+        y: vector.y - axis.y * along,
+        z: vector.z - axis.z * along,
+    }
+    .normalized()
+}
+
+/// The section frame of every segment of a path
+///
+/// The first segment's section stands upright, or on a vertical segment
+/// faces along X; each later frame is the one before turned through the
+/// bend between them.
+///
+/// # Errors
+/// Returns a message if the path has fewer than two points, a segment
+/// shorter than the tolerance or a bend doubling back on itself
+pub fn section_frames(path: &[Point], tolerance: &Tolerance) -> Result<Vec<SectionFrame>, String> {
+    if path.len() < 2 {
+        return Err("a run needs at least two points".to_string());
+    }
+    let axes = path
+        .windows(2)
+        .map(|pair| {
+            let vector = measure_vector(&pair[0], &pair[1]);
+            (vector.length() > tolerance.linear)
+                .then(|| vector.normalized())
+                .flatten()
+                .ok_or_else(|| "a segment is shorter than the tolerance".to_string())
+        })
+        .collect::<Result<Vec<Vector>, String>>()?;
+    let vertical = Vector {
+        x: 0.0,
+        y: 1.0,
+        z: 0.0,
+    };
+    let level = Vector {
+        x: 1.0,
+        y: 0.0,
+        z: 0.0,
+    };
+    let up = match across(&vertical, &axes[0]) {
+        Some(up) if axes[0].dot(&vertical).abs() < tolerance.angular.cos() => up,
+        _ => across(&level, &axes[0]).unwrap_or(level),
+    };
+    let mut frames = vec![SectionFrame {
+        side: up.cross(&axes[0]),
+        axis: axes[0].clone(),
+        up,
+    }];
+    for axis in &axes[1..] {
+        let previous = &frames[frames.len() - 1];
+        let angle = previous.axis.dot(axis).clamp(-1.0, 1.0).acos();
+        if angle >= std::f32::consts::PI - tolerance.angular {
+            return Err("the path doubles back on itself".to_string());
+        }
+        let frame = match previous.axis.cross(axis).normalized() {
+            Some(about) if angle > tolerance.angular => previous.rotated(&about, angle),
+            _ => previous.clone(),
+        };
+        frames.push(frame);
+    }
+    Ok(frames)
+}
+
+/// The faces of a section swept through rings of points: a cap at each
+/// end and a side face between each pair of neighbouring points of
+/// neighbouring rings, wound to face outward
+fn swept_loops(rings: &[Vec<Point>]) -> Vec<Vec<Point>> {
+    let mut loops = vec![
+        rings[0].iter().rev().cloned().collect(),
+        rings[rings.len() - 1].clone(),
+    ];
+    for pair in rings.windows(2) {
+        let count = pair[0].len();
+        for index in 0..count {
+            let next = (index + 1) % count;
+            loops.push(vec![
+                pair[0][index].clone(),
+                pair[0][next].clone(),
+                pair[1][next].clone(),
+                pair[1][index].clone(),
+            ]);
+        }
+    }
+    if signed_volume(&loops) < 0.0 {
+        for face in &mut loops {
+            face.reverse();
+        }
+    }
+    loops
+}
+
+/// The rings of an elbow turning from one frame to the next at a path
+/// point, where the straight segments either side stop `trim` short
+fn elbow_rings(
+    section: ServiceSection,
+    corner: &Point,
+    incoming: &SectionFrame,
+    outgoing: &SectionFrame,
+    trim: f32,
+) -> Vec<Vec<Point>> {
+    let angle = incoming.axis.dot(&outgoing.axis).clamp(-1.0, 1.0).acos();
+    let radius = BEND_RADIUS_FACTOR * section.size();
+    let Some(inward) = across(&outgoing.axis, &incoming.axis) else {
+        return Vec::new();
+    };
+    let about = incoming.axis.cross(&inward);
+    let center = offset(&offset(corner, &incoming.axis, -trim), &inward, radius);
+    let outward = inward.scaled(-1.0);
+    #[allow(clippy::cast_precision_loss)]
+    (0..=ELBOW_SEGMENTS)
+        .map(|step| {
+            let turn = angle * step as f32 / ELBOW_SEGMENTS as f32;
+            let point = offset(&center, &rotate(&outward, &about, turn), radius);
+            section.profile(&point, &incoming.rotated(&about, turn))
+        })
+        .collect()
+}
+
+/// The pieces of a run: its straight segments, an elbow at every bend and
+/// a tee collar at every branch
+///
+/// # Errors
+/// Returns a message if the path is not a valid run, a bend is too tight
+/// for its elbow or a tee does not fit on its segment
+pub fn service_pieces(
+    run: &ServiceRun,
+    tolerance: &Tolerance,
+) -> Result<Vec<ServicePiece>, String> {
+    let frames = section_frames(&run.path, tolerance)?;
+    let radius = BEND_RADIUS_FACTOR * run.section.size();
+    let count = frames.len();
+    // How far each path point's elbow reaches along the segments either side
+    let mut trims = vec![0.0; count + 1];
+    trims[0] = run.start_trim;
+    trims[count] = run.end_trim;
+    for index in 1..count {
+        let angle = frames[index - 1]
+            .axis
+            .dot(&frames[index].axis)
+            .clamp(-1.0, 1.0)
+            .acos();
+        if angle > tolerance.angular {
+            trims[index] = radius * (angle / 2.0).tan();
+        }
+    }
+    let mut pieces = Vec::new();
+    for (index, frame) in frames.iter().enumerate() {
+        let start = &run.path[index];
+        let length = measure_vector(start, &run.path[index + 1]).length();
+        let (begin, end) = (trims[index], length - trims[index + 1]);
+        if end < begin - tolerance.linear {
+            return Err(format!(
+                "segment {} is too short for its fittings",
+                index + 1
+            ));
+        }
+        let mut tees: Vec<&Tee> = run.tees.iter().filter(|tee| tee.segment == index).collect();
+        tees.sort_by(|a, b| a.along.total_cmp(&b.along));
+        let mut stretches = Vec::new();
+        let mut reached = begin;
+        for tee in tees {
+            let (from, to) = (tee.along - tee.length / 2.0, tee.along + tee.length / 2.0);
+            if from < reached - tolerance.linear || to > end + tolerance.linear {
+                return Err(format!("a tee does not fit on segment {}", index + 1));
+            }
+            stretches.push((PieceKind::Segment, reached, from));
+            stretches.push((PieceKind::Tee, from, to));
+            reached = to;
+        }
+        stretches.push((PieceKind::Segment, reached, end));
+        for (kind, from, to) in stretches {
+            if to - from <= tolerance.linear {
+                continue;
+            }
+            let rings =
+                [from, to].map(|at| run.section.profile(&offset(start, &frame.axis, at), frame));
+            pieces.push(ServicePiece {
+                kind,
+                loops: swept_loops(&rings),
+            });
+        }
+        if index + 1 < count && trims[index + 1] > 0.0 {
+            let rings = elbow_rings(
+                run.section,
+                &run.path[index + 1],
+                frame,
+                &frames[index + 1],
+                trims[index + 1],
+            );
+            if !rings.is_empty() {
+                pieces.push(ServicePiece {
+                    kind: PieceKind::Elbow,
+                    loops: swept_loops(&rings),
+                });
+            }
+        }
+    }
+    Ok(pieces)
+}
+
+/// A registry of service runs
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ServiceRegistry {
+    /// Unique identifier for the registry
+    pub id: Uuid,
+    /// The runs, by ID
+    pub runs: HashMap<Uuid, ServiceRun>,
+}
+
+impl ServiceRegistry {
+    /// Create a new, empty registry
+    #[must_use]
+    pub fn create_new() -> Self {
+        Self {
+            id: new_id(),
+            runs: HashMap::new(),
+        }
+    }
+
+    /// Store a run and return its ID
+    pub fn store(&mut self, run: ServiceRun) -> Uuid {
+        let id = run.id;
+        self.runs.insert(id, run);
+        id
+    }
+
+    /// Remove a run
+    pub fn remove(&mut self, id: &Uuid) {
+        self.runs.remove(id);
+    }
+
+    /// Get a run by ID
+    #[must_use]
+    pub fn get(&self, id: &Uuid) -> Option<&ServiceRun> {
+        self.runs.get(id)
+    }
+
+    /// Get a mutable reference to a run by ID
+    pub fn get_mut(&mut self, id: &Uuid) -> Option<&mut ServiceRun> {
+        self.runs.get_mut(id)
+    }
+
+    /// The run an element belongs to, if any
+    #[must_use]
+    pub fn run_of_element(&self, element: &Uuid) -> Option<&ServiceRun> {
+        self.sorted()
+            .into_iter()
+            .find(|run| run.elements.contains(element))
+    }
+
+    /// Whether two runs are the same or one branches off the other
+    #[must_use]
+    pub fn connected(&self, first: &Uuid, second: &Uuid) -> bool {
+        let branches = |main: &Uuid, branch: &Uuid| {
+            self.get(main)
+                .is_some_and(|run| run.tees.iter().any(|tee| tee.branch == *branch))
+        };
+        first == second || branches(first, second) || branches(second, first)
+    }
+
+    /// The runs in ID order
+    #[must_use]
+    pub fn sorted(&self) -> Vec<&ServiceRun> {
+        sorted_by_id(&self.runs)
+    }
+}
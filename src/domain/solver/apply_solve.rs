@@ -45,9 +45,10 @@ impl ConstraintResult {
 /// 4. Boundary
 /// 5. Equilateral
 /// 6. Equiangular
-/// 7. Plumb (Opt-out)
-/// 8. Level (Opt-out)
-/// 9. Orthogonal (Opt-out)
+/// 7. Parallel
+/// 8. Plumb (Opt-out)
+/// 9. Level (Opt-out)
+/// 10. Orthogonal (Opt-out)
 ///
 /// # Arguments
 /// * `geometry_registry` - Registry containing all geometry
@@ -56,10 +57,11 @@ impl ConstraintResult {
 ///
 /// # Returns
 /// Constraint result with deltas or errors
+#[tracing::instrument(skip_all, fields(geometry = tier_geometry_ids.len()))]
 pub fn apply_constraints(
     _geometry_registry: &GeometryRegistry,
     _context: &context::TierContext,
-    _tier_geometry_ids: &[uuid::Uuid],
+    tier_geometry_ids: &[uuid::Uuid],
 ) -> ConstraintResult {
     // TODO: Implement constraint application
     // 1. Check boundary (if parent exists)
@@ -74,26 +76,49 @@ pub fn apply_constraints(
 /// Applies deltas and re-evaluates constraints until convergence
 /// or maximum iterations reached.
 ///
+/// The loop runs with the settings of the context's solver for the
+/// geometry the deltas move, so a cluster with its own settings, such as
+/// furniture in a building tier, converges to its own tolerance within
+/// its own iteration budget.
+///
 /// # Arguments
 /// * `geometry_registry` - Registry containing all geometry (mutable)
-/// * `context` - Tier context
+/// * `context` - Tier context, with the solver settings
 /// * `initial_deltas` - Initial deltas to propagate
-/// * `max_iterations` - Maximum delta loop iterations
 ///
 /// # Returns
 /// Constraint result after propagation
+#[tracing::instrument(
+    skip_all,
+    fields(max_iterations = tracing::field::Empty, iterations = tracing::field::Empty)
+)]
 pub fn propagate_deltas(
     _geometry_registry: &mut GeometryRegistry,
-    _context: &context::TierContext,
-    _initial_deltas: delta::DeltaSet,
-    _max_iterations: usize,
+    context: &context::TierContext,
+    initial_deltas: &delta::DeltaSet,
 ) -> Result<ConstraintResult, error::ConstraintError> {
+    let moved: Vec<uuid::Uuid> = initial_deltas
+        .deltas
+        .iter()
+        .map(|delta| delta.vertex_id)
+        .collect();
+    let solver = context.solver_for(&moved);
+    tracing::Span::current().record("max_iterations", solver.max_iterations);
     // TODO: Implement delta propagation loop
-    // 1. Apply initial deltas
+    // 1. Apply initial deltas (DeltaSet::apply writes them atomically), scaled by
+    //    DeltaSet::damped(solver.damping)
     // 2. Find affected geometry
     // 3. Re-apply constraints
     // 4. Check for new deltas
-    // 5. Repeat until no deltas or max iterations
+    // 5. Repeat until DeltaSet::is_converged against solver.tolerance or for
+    //    solver.max_iterations, logging each pass with
+    //    tracing::debug!(iteration, moved = deltas.len())
     // 6. Detect cycles
+    // 7. Record the passes taken on the span, which the profiling overlay reads
+    tracing::debug!(
+        max_iterations = solver.max_iterations,
+        "delta loop not yet implemented"
+    );
+    tracing::Span::current().record("iterations", 0);
     Ok(ConstraintResult::success())
 }
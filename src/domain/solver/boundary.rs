@@ -6,7 +6,7 @@
 /// This is pure validation logic - it checks boundaries but does not
 /// mutate geometry.
 
-use crate::domain::GeometryRegistry;
+use crate::domain::{GeometryRegistry, Tolerance};
 use uuid::Uuid;
 
 /// Check if geometry is within parent tier boundary
@@ -18,7 +18,7 @@ use uuid::Uuid;
 /// * `geometry_registry` - Registry containing all geometry
 /// * `parent_boundary_geometry` - Parent tier's geometry IDs (defines boundary)
 /// * `child_geometry` - Child tier's geometry IDs (must be within boundary)
-/// * `tolerance` - Geometric tolerance for boundary checks
+/// * `tolerance` - The child tier's tolerance for boundary checks
 /// 
 /// # Returns
 /// `true` if all child geometry is within boundary, `false` otherwise
@@ -26,7 +26,7 @@ pub fn is_within_boundary(
     _geometry_registry: &GeometryRegistry,
     _parent_boundary_geometry: &[Uuid],
     _child_geometry: &[Uuid],
-    _tolerance: f32,
+    _tolerance: &Tolerance,
 ) -> bool {
     // TODO: Implement boundary checking logic
     // - Extract boundary from parent geometry (polygons/segments)
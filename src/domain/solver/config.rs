@@ -0,0 +1,98 @@
+/// Solver configuration
+///
+/// How hard the delta loop works and when it stops: the most passes it
+/// makes, the tolerance it converges to and how far each pass moves the
+/// geometry toward satisfying its constraints. Site-scale and
+/// furniture-scale geometry need very different settings, so a tier may
+/// override the configuration it inherits from its parent, and a cluster
+/// of geometry within a tier may override the tier's.
+use crate::domain::Tolerance;
+use uuid::Uuid;
+
+/// Most delta loop passes when nothing overrides it
+pub const DEFAULT_MAX_ITERATIONS: usize = 100;
+
+/// Share of each delta applied per pass when nothing overrides it
+pub const DEFAULT_DAMPING: f32 = 1.0;
+
+/// The settings the delta loop runs with
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SolverConfig {
+    /// Most passes before the loop gives up without converging
+    pub max_iterations: usize,
+    /// The thresholds the loop converges to
+    pub tolerance: Tolerance,
+    /// Share of each delta applied per pass, from just above 0 to 1;
+    /// below 1 the loop takes smaller steps and more passes
+    pub damping: f32,
+}
+
+impl SolverConfig {
+    /// The default configuration at a tolerance
+    #[must_use]
+    pub fn for_tolerance(tolerance: Tolerance) -> Self {
+        Self {
+            max_iterations: DEFAULT_MAX_ITERATIONS,
+            tolerance,
+            damping: DEFAULT_DAMPING,
+        }
+    }
+
+    /// This configuration with the settings an override sets replaced
+    ///
+    /// An overriding tolerance can only tighten this one, as a child
+    /// tier's can only tighten its parent's. Damping is clamped to the
+    /// range it is meaningful in.
+    #[must_use]
+    pub fn overridden(&self, overrides: &SolverOverride) -> SolverConfig {
+        SolverConfig {
+            max_iterations: overrides.max_iterations.unwrap_or(self.max_iterations),
+            tolerance: overrides.tolerance.map_or(self.tolerance, |requested| {
+                self.tolerance.tightened(&requested)
+            }),
+            damping: overrides
+                .damping
+                .map_or(self.damping, |damping| damping.clamp(f32::EPSILON, 1.0)),
+        }
+    }
+}
+
+impl Default for SolverConfig {
+    fn default() -> Self {
+        Self::for_tolerance(Tolerance::default())
+    }
+}
+
+/// Settings that replace those inherited, each None to inherit it
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SolverOverride {
+    /// Most passes
+    pub max_iterations: Option<usize>,
+    /// The thresholds to converge to, tightening those inherited
+    pub tolerance: Option<Tolerance>,
+    /// Share of each delta applied per pass
+    pub damping: Option<f32>,
+}
+
+impl SolverOverride {
+    /// Whether the override sets nothing
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        *self == SolverOverride::default()
+    }
+}
+
+/// A cluster of geometry solved with its own settings
+///
+/// Constraints on any of the cluster's geometry are solved with the
+/// settings of the tier overridden by the cluster's.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SolverCluster {
+    /// The geometry in the cluster
+    pub geometry: Vec<Uuid>,
+    /// The settings it overrides
+    pub overrides: SolverOverride,
+}
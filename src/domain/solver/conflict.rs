@@ -0,0 +1,142 @@
+/// Constraint conflicts
+///
+/// Explicit constraints conflict when solving them together leaves some
+/// unsatisfied, as when a segment is held both plumb and parallel to a
+/// horizontal direction and each pass undoes the other's deltas. The
+/// smallest conflicting set is found by removal: each constraint is left
+/// out in turn and stays out if the rest still conflict, so every
+/// constraint left is needed for the conflict and relaxing any one of
+/// them resolves it. Each trial solve is made on the model and undone.
+use crate::domain::geometry::distance;
+use crate::domain::solver::{constraint_vertices, context, error, solve_incrementally, Constraint};
+use crate::domain::{GeometryRegistry, Point};
+use std::collections::{BTreeMap, BTreeSet};
+use uuid::Uuid;
+
+/// One way out of a conflict: relaxing one of its constraints
+#[derive(Debug, Clone)]
+pub struct Relaxation {
+    /// The constraint's place among the context's explicit constraints
+    pub index: usize,
+    /// The constraint relaxed
+    pub constraint: Constraint,
+    /// Whether the other explicit constraints are all satisfied without it
+    pub resolves: bool,
+    /// Where solving without it puts each vertex it moves
+    pub moves: BTreeMap<Uuid, Point>,
+    /// The furthest any vertex moves, in meters
+    pub largest_move: f32,
+}
+
+/// The smallest set of explicit constraints that cannot be satisfied
+/// together, with the effect of relaxing each
+#[derive(Debug, Clone)]
+pub struct ConstraintConflict {
+    /// One relaxation for each constraint in the set, in the order the
+    /// constraints are listed in the context
+    pub relaxations: Vec<Relaxation>,
+}
+
+impl ConstraintConflict {
+    /// The conflict as a constraint error
+    #[must_use]
+    pub fn error(&self) -> error::ConstraintError {
+        error::ConstraintError::ConstraintConflict {
+            conflicting_constraints: self
+                .relaxations
+                .iter()
+                .map(|relaxation| relaxation.constraint.summary())
+                .collect(),
+            message: format!(
+                "{} constraints cannot be satisfied together",
+                self.relaxations.len()
+            ),
+        }
+    }
+}
+
+/// Solve some of the context's explicit constraints on all their geometry,
+/// then undo the solve
+///
+/// Returns whether they were all satisfied and where the solve put each
+/// vertex it moved.
+fn trial(
+    geometry_registry: &mut GeometryRegistry,
+    context: &context::TierContext,
+    indices: &[usize],
+) -> (bool, BTreeMap<Uuid, Point>) {
+    let mut trial = context.clone();
+    trial.constraints.explicit = indices
+        .iter()
+        .filter_map(|index| context.constraints.explicit.get(*index))
+        .cloned()
+        .collect();
+    let vertices: BTreeSet<Uuid> = trial
+        .constraints
+        .explicit
+        .iter()
+        .flat_map(|constraint| constraint_vertices(geometry_registry, constraint))
+        .collect();
+    let vertices: Vec<Uuid> = vertices.into_iter().collect();
+    let solve = solve_incrementally(geometry_registry, &trial, &vertices, None);
+    let mut solved = BTreeMap::new();
+    for (vertex_id, before) in solve.moved {
+        if let Some(vertex) = geometry_registry.vertices.get_mut(&vertex_id) {
+            solved.insert(vertex_id, std::mem::replace(&mut vertex.position, before));
+        }
+    }
+    (solve.violated.is_empty(), solved)
+}
+
+/// Find the smallest set of the context's explicit constraints that cannot
+/// be satisfied together, or None if they all can
+///
+/// The geometry is left as it was. Solving runs every pass the solver
+/// settings allow, so settings too strict to converge read as a conflict.
+#[tracing::instrument(skip_all, fields(constraints = context.constraints.explicit.len()))]
+pub fn find_conflict(
+    geometry_registry: &mut GeometryRegistry,
+    context: &context::TierContext,
+) -> Option<ConstraintConflict> {
+    let all: Vec<usize> = (0..context.constraints.explicit.len()).collect();
+    if trial(geometry_registry, context, &all).0 {
+        return None;
+    }
+    let mut conflicting = all.clone();
+    let mut place = 0;
+    while place < conflicting.len() {
+        let mut without = conflicting.clone();
+        without.remove(place);
+        if trial(geometry_registry, context, &without).0 {
+            place += 1;
+        } else {
+            conflicting = without;
+        }
+    }
+    let relaxations = conflicting
+        .into_iter()
+        .filter_map(|index| {
+            let rest: Vec<usize> = all
+                .iter()
+                .copied()
+                .filter(|other| *other != index)
+                .collect();
+            let (resolves, moves) = trial(geometry_registry, context, &rest);
+            let largest_move = moves
+                .iter()
+                .filter_map(|(vertex_id, position)| {
+                    let vertex = geometry_registry.vertices.get(vertex_id)?;
+                    Some(distance(&vertex.position, position))
+                })
+                .fold(0.0, f32::max);
+            Some(Relaxation {
+                index,
+                constraint: context.constraints.explicit.get(index)?.clone(),
+                resolves,
+                moves,
+                largest_move,
+            })
+        })
+        .collect();
+    Some(ConstraintConflict { relaxations })
+}
@@ -27,7 +27,7 @@ pub fn apply_boundary(
             geometry_registry,
             parent_boundary,
             tier_geometry,
-            context.tolerance,
+            &context.tolerance,
         );
 
         if !is_valid {
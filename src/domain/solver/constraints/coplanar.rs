@@ -2,13 +2,16 @@
 /// 
 /// Points/segments/polygons must lie on the same plane (projection constraint).
 
+use crate::domain::geometry::{fit_plane, Plane};
 use crate::domain::solver::{delta, error, context};
 use crate::domain::GeometryRegistry;
 use uuid::Uuid;
 
 /// Apply coplanar constraint
 /// 
-/// Ensures that specified geometry is coplanar.
+/// Ensures that specified geometry is coplanar. Every vertex of the targets
+/// further than the tier tolerance from their best-fit plane is projected
+/// onto it.
 /// 
 /// # Arguments
 /// * `geometry_registry` - Registry containing geometry
@@ -17,16 +20,71 @@ use uuid::Uuid;
 /// 
 /// # Returns
 /// Deltas to make geometry coplanar, or error
+///
+/// # Errors
+/// Returns `GeometryNotFound` if a target or one of its vertices is missing.
 pub fn apply_coplanar(
-    _geometry_registry: &GeometryRegistry,
-    _context: &context::TierContext,
-    _targets: &[Uuid],
+    geometry_registry: &GeometryRegistry,
+    context: &context::TierContext,
+    targets: &[Uuid],
 ) -> Result<delta::DeltaSet, error::ConstraintError> {
-    // TODO: Implement coplanar constraint
-    // - Use first 3 points to define plane
-    // - Project other points onto that plane
-    // - Create deltas for projected positions
-    // - Can leverage existing validate_coplanar_vertices function
-    Ok(delta::DeltaSet::new())
+    // Gather every vertex the targets touch, keeping first-seen order
+    let mut vertex_ids: Vec<Uuid> = Vec::new();
+    for target in targets {
+        for vertex_id in target_vertices(geometry_registry, target)? {
+            if !vertex_ids.contains(&vertex_id) {
+                vertex_ids.push(vertex_id);
+            }
+        }
+    }
+
+    let positions = vertex_ids
+        .iter()
+        .map(|id| {
+            geometry_registry
+                .vertices
+                .get(id)
+                .map(|vertex| vertex.position.clone())
+                .ok_or(error::ConstraintError::GeometryNotFound { geometry_id: *id })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // The best-fit plane of all the vertices is the reference; fewer than
+    // three or collinear vertices are trivially coplanar
+    let mut deltas = delta::DeltaSet::new();
+    let Some((normal, point, _)) = fit_plane(&positions) else {
+        return Ok(deltas);
+    };
+    let plane = Plane { point, normal };
+
+    for (vertex_id, position) in vertex_ids.iter().zip(&positions) {
+        if plane.signed_distance(position).abs() > context.tolerance.linear {
+            deltas.add(delta::Delta {
+                vertex_id: *vertex_id,
+                old_position: position.clone(),
+                new_position: plane.project(position),
+            });
+        }
+    }
+    Ok(deltas)
 }
 
+/// Vertices referenced by a vertex, segment or polygon ID
+fn target_vertices(
+    geometry_registry: &GeometryRegistry,
+    target: &Uuid,
+) -> Result<Vec<Uuid>, error::ConstraintError> {
+    if geometry_registry.vertices.get(target).is_some() {
+        return Ok(vec![*target]);
+    }
+    if let Some(segment) = geometry_registry.segments.get(target) {
+        return Ok(segment.vertices.to_vec());
+    }
+    geometry_registry
+        .polygons
+        .get(target)
+        .and_then(|polygon| polygon.vertex_loop(&geometry_registry.segments))
+        .ok_or(error::ConstraintError::GeometryNotFound {
+            geometry_id: *target,
+        })
+}
@@ -0,0 +1,119 @@
+/// Direction alignment helpers
+///
+/// Shared by the direction-based constraints (parallel, plumb, level).
+/// Segments are turned about their midpoint onto a unit direction, keeping
+/// their length, so both endpoints move by the same amount.
+use crate::domain::solver::{delta, error};
+use crate::domain::{measure_vector, GeometryRegistry, Point, Tolerance, Vector};
+use uuid::Uuid;
+
+/// Resolve a direction variable from the registry
+///
+/// # Errors
+/// Returns `GeometryNotFound` if the direction is not in the registry.
+pub fn direction_vector(
+    geometry_registry: &GeometryRegistry,
+    direction_id: &Uuid,
+) -> Result<Vector, error::ConstraintError> {
+    geometry_registry
+        .directions
+        .get(direction_id)
+        .map(|direction| direction.vector().clone())
+        .ok_or(error::ConstraintError::GeometryNotFound {
+            geometry_id: *direction_id,
+        })
+}
+
+/// Unit direction of a segment, from its first stored vertex to its second
+///
+/// # Errors
+/// Returns `GeometryNotFound` if the segment or a vertex is missing.
+pub fn segment_direction(
+    geometry_registry: &GeometryRegistry,
+    segment_id: &Uuid,
+) -> Result<Option<Vector>, error::ConstraintError> {
+    let (_, start, _, end) = segment_endpoints(geometry_registry, segment_id)?;
+    Ok(measure_vector(&start, &end).normalized())
+}
+
+/// Deltas turning a segment about its midpoint onto `direction`
+///
+/// The segment keeps its length and the sense closest to its current one.
+/// No deltas are produced when the endpoints would move no more than the
+/// linear tolerance, or when the segment has zero length.
+///
+/// # Errors
+/// Returns `GeometryNotFound` if the segment or a vertex is missing.
+pub fn align_segment(
+    geometry_registry: &GeometryRegistry,
+    segment_id: &Uuid,
+    direction: &Vector,
+    tolerance: &Tolerance,
+) -> Result<Vec<delta::Delta>, error::ConstraintError> {
+    let (start_id, start, end_id, end) = segment_endpoints(geometry_registry, segment_id)?;
+    let current = measure_vector(&start, &end);
+    let Some(unit) = direction.normalized() else {
+        return Ok(Vec::new());
+    };
+    let length = current.length();
+    if length <= f32::EPSILON {
+        return Ok(Vec::new());
+    }
+
+    let sense = if current.dot(&unit) < 0.0 { -1.0 } else { 1.0 };
+    let half = unit.scaled(sense * length / 2.0);
+    let middle = Point {
+        x: f32::midpoint(start.x, end.x),
+        y: f32::midpoint(start.y, end.y),
+        z: f32::midpoint(start.z, end.z),
+    };
+    let new_start = Point {
+        x: middle.x - half.x,
+        y: middle.y - half.y,
+        z: middle.z - half.z,
+    };
+    let new_end = Point {
+        x: middle.x + half.x,
+        y: middle.y + half.y,
+        z: middle.z + half.z,
+    };
+
+    if measure_vector(&start, &new_start).length() <= tolerance.linear {
+        return Ok(Vec::new());
+    }
+    Ok(vec![
+        delta::Delta {
+            vertex_id: start_id,
+            old_position: start,
+            new_position: new_start,
+        },
+        delta::Delta {
+            vertex_id: end_id,
+            old_position: end,
+            new_position: new_end,
+        },
+    ])
+}
+
+/// The segment's vertex IDs and positions, in stored order
+fn segment_endpoints(
+    geometry_registry: &GeometryRegistry,
+    segment_id: &Uuid,
+) -> Result<(Uuid, Point, Uuid, Point), error::ConstraintError> {
+    let segment = geometry_registry.segments.get(segment_id).ok_or(
+        error::ConstraintError::GeometryNotFound {
+            geometry_id: *segment_id,
+        },
+    )?;
+    let position = |vertex_id: &Uuid| {
+        geometry_registry
+            .vertices
+            .get(vertex_id)
+            .map(|vertex| vertex.position.clone())
+            .ok_or(error::ConstraintError::GeometryNotFound {
+                geometry_id: *vertex_id,
+            })
+    };
+    let [start_id, end_id] = segment.vertices;
+    Ok((start_id, position(&start_id)?, end_id, position(&end_id)?))
+}
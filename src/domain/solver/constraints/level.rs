@@ -3,8 +3,9 @@
 /// Horizontal alignment relative to world XZ-plane (gravity).
 /// Applied by default unless explicitly disabled.
 
+use crate::domain::solver::constraints::direction::{align_segment, segment_direction};
 use crate::domain::solver::{delta, error, context};
-use crate::domain::GeometryRegistry;
+use crate::domain::{GeometryRegistry, Vector, WORLD_UP};
 use uuid::Uuid;
 
 /// Apply level constraint
@@ -18,20 +19,42 @@ use uuid::Uuid;
 /// 
 /// # Returns
 /// Deltas to make geometry level, or error
+///
+/// # Errors
+/// Returns `GeometryNotFound` if a target segment's vertices are missing.
 pub fn apply_level(
-    _geometry_registry: &GeometryRegistry,
-    _context: &context::TierContext,
-    _targets: &[Uuid],
+    geometry_registry: &GeometryRegistry,
+    context: &context::TierContext,
+    targets: &[Uuid],
 ) -> Result<delta::DeltaSet, error::ConstraintError> {
     // Check if level is enabled (opt-out constraint)
-    if !_context.constraints.opt_out.level_enabled {
+    if !context.constraints.opt_out.level_enabled {
         return Ok(delta::DeltaSet::new());
     }
 
-    // TODO: Implement level constraint
-    // - For segments: ensure they are horizontal (parallel to XZ-plane)
-    // - For vertices: align Y coordinates to reference
-    // - Create deltas for adjusted positions
-    Ok(delta::DeltaSet::new())
+    // Segments are turned about their midpoint onto their own horizontal
+    // heading; vertical segments have none and are left alone
+    let mut deltas = delta::DeltaSet::new();
+    let segments = targets
+        .iter()
+        .filter(|id| geometry_registry.segments.get(id).is_some());
+    for segment_id in segments {
+        let Some(current) = segment_direction(geometry_registry, segment_id)? else {
+            continue;
+        };
+        let vertical = WORLD_UP.scaled(current.dot(&WORLD_UP));
+        let horizontal = Vector {
+            x: current.x - vertical.x,
+            y: current.y - vertical.y,
+            z: current.z - vertical.z,
+        };
+        let Some(heading) = horizontal.normalized() else {
+            continue;
+        };
+        for change in align_segment(geometry_registry, segment_id, &heading, &context.tolerance)? {
+            deltas.add(change);
+        }
+    }
+    // TODO: For vertices: align Y coordinates to reference
+    Ok(deltas)
 }
-
@@ -23,6 +23,12 @@ pub mod equilateral;
 /// Equiangular constraint
 pub mod equiangular;
 
+/// Direction alignment shared by parallel, plumb and level
+pub mod direction;
+
+/// Parallel constraint
+pub mod parallel;
+
 /// Plumb constraint (Opt-out)
 pub mod plumb;
 
@@ -38,6 +44,7 @@ pub use coplanar::*;
 pub use boundary_constraint::*;
 pub use equilateral::*;
 pub use equiangular::*;
+pub use parallel::*;
 pub use plumb::*;
 pub use level::*;
 pub use orthogonal::*;
@@ -0,0 +1,63 @@
+/// Parallel constraint
+///
+/// Segments must point along a common direction.
+use crate::domain::solver::constraints::direction::{
+    align_segment, direction_vector, segment_direction,
+};
+use crate::domain::solver::types::ConstraintReference;
+use crate::domain::solver::{context, delta, error};
+use crate::domain::GeometryRegistry;
+use uuid::Uuid;
+
+/// Apply parallel constraint
+///
+/// Ensures that specified segments are parallel. With a direction
+/// reference every segment is turned onto that direction; otherwise the
+/// first segment defines it and the rest are turned to match.
+///
+/// # Arguments
+/// * `geometry_registry` - Registry containing geometry
+/// * `context` - Constraint context
+/// * `target_segments` - Segments that must be parallel
+/// * `reference` - Optional direction the segments must follow
+///
+/// # Returns
+/// Deltas to make segments parallel, or error
+///
+/// # Errors
+/// Returns `GeometryNotFound` if a segment, vertex or direction is missing.
+pub fn apply_parallel(
+    geometry_registry: &GeometryRegistry,
+    context: &context::TierContext,
+    target_segments: &[Uuid],
+    reference: Option<&ConstraintReference>,
+) -> Result<delta::DeltaSet, error::ConstraintError> {
+    let mut deltas = delta::DeltaSet::new();
+    let (direction, segments) = match reference {
+        Some(ConstraintReference::Direction(direction_id)) => (
+            Some(direction_vector(geometry_registry, direction_id)?),
+            target_segments,
+        ),
+        Some(ConstraintReference::SelfDefined) | None => {
+            let Some((first, rest)) = target_segments.split_first() else {
+                return Ok(deltas);
+            };
+            (segment_direction(geometry_registry, first)?, rest)
+        }
+    };
+    let Some(direction) = direction else {
+        return Ok(deltas);
+    };
+
+    for segment_id in segments {
+        for change in align_segment(
+            geometry_registry,
+            segment_id,
+            &direction,
+            &context.tolerance,
+        )? {
+            deltas.add(change);
+        }
+    }
+    Ok(deltas)
+}
@@ -3,8 +3,9 @@
 /// Vertical alignment relative to world Y-axis (gravity).
 /// Applied by default unless explicitly disabled.
 
+use crate::domain::solver::constraints::direction::align_segment;
 use crate::domain::solver::{delta, error, context};
-use crate::domain::GeometryRegistry;
+use crate::domain::{GeometryRegistry, WORLD_UP};
 use uuid::Uuid;
 
 /// Apply plumb constraint
@@ -18,20 +19,29 @@ use uuid::Uuid;
 /// 
 /// # Returns
 /// Deltas to make geometry plumb, or error
+///
+/// # Errors
+/// Returns `GeometryNotFound` if a target segment's vertices are missing.
 pub fn apply_plumb(
-    _geometry_registry: &GeometryRegistry,
-    _context: &context::TierContext,
-    _targets: &[Uuid],
+    geometry_registry: &GeometryRegistry,
+    context: &context::TierContext,
+    targets: &[Uuid],
 ) -> Result<delta::DeltaSet, error::ConstraintError> {
     // Check if plumb is enabled (opt-out constraint)
-    if !_context.constraints.opt_out.plumb_enabled {
+    if !context.constraints.opt_out.plumb_enabled {
         return Ok(delta::DeltaSet::new());
     }
 
-    // TODO: Implement plumb constraint
-    // - For segments: ensure they are vertical (parallel to Y-axis)
-    // - For vertices: align X and Z coordinates to reference
-    // - Create deltas for adjusted positions
-    Ok(delta::DeltaSet::new())
+    // Segments are turned about their midpoint onto the world up direction
+    let mut deltas = delta::DeltaSet::new();
+    let segments = targets
+        .iter()
+        .filter(|id| geometry_registry.segments.get(id).is_some());
+    for segment_id in segments {
+        for change in align_segment(geometry_registry, segment_id, &WORLD_UP, &context.tolerance)? {
+            deltas.add(change);
+        }
+    }
+    // TODO: For vertices: align X and Z coordinates to reference
+    Ok(deltas)
 }
-
@@ -2,12 +2,12 @@
 ///
 /// Provides tier-aware settings and merged constraint configuration
 /// for constraint solving. This is a pure domain type with no side effects.
-use crate::domain::solver::types::ConstraintSet;
+use crate::domain::solver::config::{SolverCluster, SolverConfig, SolverOverride};
+use crate::domain::solver::expressions::ExpressionConstraint;
+use crate::domain::solver::types::{Constraint, ConstraintSet, OptOutConstraints};
+use crate::domain::Tolerance;
 use uuid::Uuid;
 
-/// Tolerance for geometric precision (in meters)
-pub type Tolerance = f32;
-
 /// Context for applying constraints to a tier
 ///
 /// Merges parent tier constraints with child tier constraints,
@@ -16,21 +16,26 @@ pub type Tolerance = f32;
 pub struct TierContext {
     /// The tier's own constraint set
     pub constraints: ConstraintSet,
-    /// Tolerance for this tier
+    /// Tolerance for this tier, never looser than the parent's
     pub tolerance: Tolerance,
     /// Parent tier's geometry IDs (for boundary enforcement)
     /// None if this is the root tier
     pub parent_boundary_geometry: Option<Vec<Uuid>>,
     /// Parent tier's tolerance (for inheritance)
     pub parent_tolerance: Option<Tolerance>,
+    /// The settings the tier's delta loop runs with, at its tolerance
+    pub solver: SolverConfig,
 }
 
 impl TierContext {
     /// Create a new tier context
     ///
+    /// The tier's tolerance is tightened against the parent's, so a child
+    /// tier inherits any threshold it does not make stricter.
+    ///
     /// # Arguments
     /// * `constraints` - This tier's constraint set
-    /// * `tolerance` - This tier's tolerance
+    /// * `tolerance` - This tier's requested tolerance
     /// * `parent_boundary_geometry` - Parent tier's geometry (for boundary)
     /// * `parent_tolerance` - Parent tier's tolerance
     pub fn new(
@@ -39,29 +44,219 @@ impl TierContext {
         parent_boundary_geometry: Option<Vec<Uuid>>,
         parent_tolerance: Option<Tolerance>,
     ) -> Self {
+        let tolerance = parent_tolerance.map_or(tolerance, |parent| parent.tightened(&tolerance));
         Self {
             constraints,
             tolerance,
             parent_boundary_geometry,
             parent_tolerance,
+            solver: SolverConfig::for_tolerance(tolerance),
         }
     }
 
+    /// This context with its solver settings overridden, as by each tier
+    /// from the root down to this one
+    ///
+    /// The tolerance stays the tier's: tiers set theirs by requesting it.
+    #[must_use]
+    pub fn with_solver(mut self, overrides: &SolverOverride) -> Self {
+        self.solver = self.solver.overridden(&SolverOverride {
+            tolerance: None,
+            ..*overrides
+        });
+        self
+    }
+
+    /// The settings to solve constraints on some geometry with
+    ///
+    /// Each of the tier's clusters holding any of the geometry overrides
+    /// the tier's settings in turn, so the tightest tolerance wins and
+    /// the last cluster sets the iterations and damping.
+    #[must_use]
+    pub fn solver_for(&self, geometry: &[Uuid]) -> SolverConfig {
+        self.constraints
+            .clusters
+            .iter()
+            .filter(|cluster| geometry.iter().any(|id| cluster.geometry.contains(id)))
+            .fold(self.solver, |config, cluster| {
+                config.overridden(&cluster.overrides)
+            })
+    }
+
     /// Merge parent constraints with child constraints
     ///
-    /// Parent constraints are inherited, with child constraints
-    /// potentially overriding opt-out flags.
+    /// An opt-out constraint stays enabled only if both tiers enable it:
+    /// the child inherits the parent's flags and may disable more, but
+    /// cannot re-enable one the parent turned off. Explicit constraints are
+    /// concatenated parent first, dropping any child constraint that repeats
+    /// one already present with the same kind, targets and reference.
+    /// Expression constraints are concatenated the same way, except that a
+    /// child's setting the same property as the parent's replaces it.
+    /// Solver clusters are concatenated parent first, dropping any the
+    /// child repeats, so a child's cluster overrides the parent's for
+    /// geometry in both.
     ///
     /// # Arguments
     /// * `parent_constraints` - Parent tier's constraint set
     ///
     /// # Returns
     /// Merged constraint set
-    pub fn merge_parent_constraints(&self, _parent_constraints: &ConstraintSet) -> ConstraintSet {
-        // TODO: Implement constraint merging logic
-        // - Inherit parent's opt-out constraints
-        // - Child can disable opt-out constraints
-        // - Combine explicit constraints
-        ConstraintSet::default()
+    #[must_use]
+    pub fn merge_parent_constraints(&self, parent_constraints: &ConstraintSet) -> ConstraintSet {
+        let parent = &parent_constraints.opt_out;
+        let child = &self.constraints.opt_out;
+        let opt_out = OptOutConstraints {
+            plumb_enabled: parent.plumb_enabled && child.plumb_enabled,
+            level_enabled: parent.level_enabled && child.level_enabled,
+            orthogonal_enabled: parent.orthogonal_enabled && child.orthogonal_enabled,
+        };
+
+        let mut explicit: Vec<Constraint> = Vec::new();
+        for constraint in parent_constraints
+            .explicit
+            .iter()
+            .chain(&self.constraints.explicit)
+        {
+            if !explicit.iter().any(|kept| kept.is_duplicate_of(constraint)) {
+                explicit.push(constraint.clone());
+            }
+        }
+        let mut expressions: Vec<ExpressionConstraint> = Vec::new();
+        for constraint in parent_constraints
+            .expressions
+            .iter()
+            .chain(&self.constraints.expressions)
+        {
+            match expressions
+                .iter_mut()
+                .find(|kept| kept.target == constraint.target)
+            {
+                Some(kept) => *kept = constraint.clone(),
+                None => expressions.push(constraint.clone()),
+            }
+        }
+        let mut clusters: Vec<SolverCluster> = Vec::new();
+        for cluster in parent_constraints
+            .clusters
+            .iter()
+            .chain(&self.constraints.clusters)
+        {
+            if !clusters.contains(cluster) {
+                clusters.push(cluster.clone());
+            }
+        }
+        ConstraintSet {
+            opt_out,
+            explicit,
+            expressions,
+            clusters,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::new_id;
+    use crate::domain::solver::ConstraintKind;
+
+    fn tier(constraints: ConstraintSet) -> TierContext {
+        TierContext::new(constraints, Tolerance::default(), None, None)
+    }
+
+    fn equilateral(targets: &[Uuid]) -> Constraint {
+        Constraint {
+            kind: ConstraintKind::Equilateral,
+            targets: targets.to_vec(),
+            reference: None,
+        }
+    }
+
+    fn cluster(geometry: Uuid, damping: f32) -> SolverCluster {
+        SolverCluster {
+            geometry: vec![geometry],
+            overrides: SolverOverride {
+                damping: Some(damping),
+                ..SolverOverride::default()
+            },
+        }
+    }
+
+    #[test]
+    fn a_child_cannot_enable_what_its_parent_disabled() {
+        let mut parent = ConstraintSet::default();
+        parent.opt_out.plumb_enabled = false;
+        let mut child = ConstraintSet::default();
+        child.opt_out.level_enabled = false;
+
+        let merged = tier(child).merge_parent_constraints(&parent);
+        assert!(!merged.opt_out.plumb_enabled);
+        assert!(!merged.opt_out.level_enabled);
+        assert!(merged.opt_out.orthogonal_enabled);
+    }
+
+    #[test]
+    fn a_child_expression_overrides_the_parents() {
+        let parent = ConstraintSet {
+            expressions: vec![
+                ExpressionConstraint::parse("wall.height = 3").unwrap(),
+                ExpressionConstraint::parse("wall.thickness = 0.2").unwrap(),
+            ],
+            ..ConstraintSet::default()
+        };
+        let child = ConstraintSet {
+            expressions: vec![ExpressionConstraint::parse("wall.height = 4").unwrap()],
+            ..ConstraintSet::default()
+        };
+
+        let merged = tier(child).merge_parent_constraints(&parent);
+        let expressions: Vec<&str> = merged
+            .expressions
+            .iter()
+            .map(|constraint| constraint.expression.as_str())
+            .collect();
+        assert_eq!(expressions, ["4", "0.2"]);
+    }
+
+    #[test]
+    fn merging_a_parent_twice_repeats_nothing() {
+        let (a, b) = (new_id(), new_id());
+        let parent = ConstraintSet {
+            explicit: vec![equilateral(&[a, b])],
+            expressions: vec![ExpressionConstraint::parse("wall.height = 3").unwrap()],
+            clusters: vec![cluster(a, 0.5)],
+            ..ConstraintSet::default()
+        };
+        let child = ConstraintSet {
+            clusters: vec![cluster(a, 0.8)],
+            ..ConstraintSet::default()
+        };
+
+        let once = tier(child).merge_parent_constraints(&parent);
+        let twice = tier(once.clone()).merge_parent_constraints(&parent);
+        assert_eq!(twice.explicit.len(), 1);
+        assert_eq!(twice.expressions, once.expressions);
+        assert_eq!(twice.clusters, [cluster(a, 0.5), cluster(a, 0.8)]);
+    }
+
+    #[test]
+    fn repeated_targets_make_different_constraints() {
+        let (a, b) = (new_id(), new_id());
+        let parent = ConstraintSet {
+            explicit: vec![equilateral(&[a, a, b])],
+            ..ConstraintSet::default()
+        };
+        let child = ConstraintSet {
+            explicit: vec![equilateral(&[a, b, b]), equilateral(&[b, a, a])],
+            ..ConstraintSet::default()
+        };
+
+        let merged = tier(child).merge_parent_constraints(&parent);
+        let targets: Vec<&[Uuid]> = merged
+            .explicit
+            .iter()
+            .map(|constraint| constraint.targets.as_slice())
+            .collect();
+        assert_eq!(targets, [[a, a, b], [a, b, b]]);
     }
 }
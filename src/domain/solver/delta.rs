@@ -3,7 +3,10 @@
 /// Tracks changes to geometry that result from constraint application.
 /// Deltas cascade through the constraint system until convergence.
 
-use crate::domain::Point;
+use crate::domain::geometry::distance;
+use crate::domain::solver::error::ConstraintError;
+use crate::domain::{Point, Tolerance, VertexRegistry};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use uuid::Uuid;
 
 /// A single change to geometry
@@ -49,14 +52,164 @@ impl DeltaSet {
     pub fn len(&self) -> usize {
         self.deltas.len()
     }
+
+    /// Check whether every delta moves its vertex by no more than the
+    /// convergence tolerance, so another solver pass would change nothing
+    #[must_use]
+    pub fn is_converged(&self, tolerance: &Tolerance) -> bool {
+        self.deltas.iter().all(|delta| {
+            distance(&delta.old_position, &delta.new_position) <= tolerance.convergence
+        })
+    }
+
+    /// The deltas scaled by a damping share, each moving its vertex that
+    /// share of the way to its new position
+    #[must_use]
+    pub fn damped(&self, damping: f32) -> DeltaSet {
+        let share = damping.clamp(0.0, 1.0);
+        let deltas = self
+            .deltas
+            .iter()
+            .map(|delta| {
+                let (old, new) = (&delta.old_position, &delta.new_position);
+                Delta {
+                    vertex_id: delta.vertex_id,
+                    old_position: old.clone(),
+                    new_position: Point {
+                        x: old.x + (new.x - old.x) * share,
+                        y: old.y + (new.y - old.y) * share,
+                        z: old.z + (new.z - old.z) * share,
+                    },
+                }
+            })
+            .collect();
+        DeltaSet { deltas }
+    }
+
+    /// Write the new positions back to the vertex registry
+    ///
+    /// The write is atomic: every vertex is checked before any is moved,
+    /// so a missing vertex leaves the registry untouched. When a vertex
+    /// appears more than once, the last delta wins.
+    ///
+    /// # Errors
+    /// Returns `GeometryNotFound` for the first vertex missing from the registry.
+    pub fn apply(&self, vertex_registry: &mut VertexRegistry) -> Result<(), ConstraintError> {
+        if let Some(missing) = self
+            .deltas
+            .iter()
+            .find(|delta| vertex_registry.get(&delta.vertex_id).is_none())
+        {
+            return Err(ConstraintError::GeometryNotFound {
+                geometry_id: missing.vertex_id,
+            });
+        }
+        for delta in &self.deltas {
+            if let Some(vertex) = vertex_registry.get_mut(&delta.vertex_id) {
+                vertex.position.move_to_position(&delta.new_position);
+            }
+        }
+        Ok(())
+    }
 }
 
-/// Tracks which geometry entities are affected by changes
-/// 
-/// Used to determine what needs to be re-evaluated when deltas are applied.
+/// Tracks which items are affected by changes to others
+///
+/// Items are anything with an ID: datums, generators, geometry. Each edge
+/// says one item is derived from another, so when the source changes the
+/// dependent has to be re-evaluated, and in turn everything derived from
+/// it.
 #[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DependencyGraph {
-    // TODO: Implement dependency tracking
-    // Maps vertex_id -> affected segment_ids -> affected polygon_ids
+    /// The items derived directly from each item, by the item's ID
+    dependents: HashMap<Uuid, BTreeSet<Uuid>>,
+}
+
+impl DependencyGraph {
+    /// Record that `dependent` is derived from `source`
+    pub fn add(&mut self, source: Uuid, dependent: Uuid) {
+        self.dependents.entry(source).or_default().insert(dependent);
+    }
+
+    /// Forget an item, both as a source and as a dependent
+    pub fn remove(&mut self, id: &Uuid) {
+        self.dependents.remove(id);
+        for dependents in self.dependents.values_mut() {
+            dependents.remove(id);
+        }
+        self.dependents.retain(|_, dependents| !dependents.is_empty());
+    }
+
+    /// The items derived directly from an item, sorted by ID
+    #[must_use]
+    pub fn dependents_of(&self, id: &Uuid) -> Vec<Uuid> {
+        self.dependents
+            .get(id)
+            .map(|dependents| dependents.iter().copied().collect())
+            .unwrap_or_default()
+    }
+
+    /// Every item derived directly or indirectly from the changed items,
+    /// in an order to re-evaluate them in
+    ///
+    /// Each item comes after the affected items it is derived from; ties
+    /// are broken by ID. Items on a cycle come last, in ID order, since no
+    /// order can satisfy them.
+    #[must_use]
+    pub fn affected(&self, changed: &[Uuid]) -> Vec<Uuid> {
+        let mut reached = BTreeSet::new();
+        let mut pending: Vec<Uuid> = changed.to_vec();
+        while let Some(id) = pending.pop() {
+            for dependent in self.dependents_of(&id) {
+                if reached.insert(dependent) {
+                    pending.push(dependent);
+                }
+            }
+        }
+        self.ordered(&reached)
+    }
+
+    /// The items on a cycle, each derived indirectly from itself, sorted
+    /// by ID
+    #[must_use]
+    pub fn cycles(&self) -> Vec<Uuid> {
+        let items: BTreeSet<Uuid> = self.dependents.keys().copied().collect();
+        items
+            .into_iter()
+            .filter(|id| self.affected(&[*id]).contains(id))
+            .collect()
+    }
+
+    /// Items in an order to evaluate them in, each after the items among
+    /// them it is derived from; ties are broken by ID, and items on a
+    /// cycle or derived from one come last
+    #[must_use]
+    pub fn ordered(&self, items: &BTreeSet<Uuid>) -> Vec<Uuid> {
+        let mut waiting: BTreeMap<Uuid, usize> = items.iter().map(|id| (*id, 0)).collect();
+        for id in items {
+            for dependent in self.dependents_of(id) {
+                if let Some(count) = waiting.get_mut(&dependent) {
+                    *count += 1;
+                }
+            }
+        }
+        let mut order = Vec::with_capacity(items.len());
+        while let Some(next) = waiting
+            .iter()
+            .find(|(_, count)| **count == 0)
+            .map(|(id, _)| *id)
+        {
+            waiting.remove(&next);
+            for dependent in self.dependents_of(&next) {
+                if let Some(count) = waiting.get_mut(&dependent) {
+                    *count -= 1;
+                }
+            }
+            order.push(next);
+        }
+        order.extend(waiting.into_keys());
+        order
+    }
 }
 
@@ -0,0 +1,111 @@
+/// Expression constraints
+///
+/// An expression constraint sets one property of a named object to the
+/// value of an expression over other properties, such as
+/// `window_frame.sill = level_1.elevation + 0.9`. The constraints are
+/// evaluated in dependency order, so a constraint reading a property set
+/// by another is evaluated after it; constraints that depend on each
+/// other in a loop have no order and are reported as a cycle.
+use crate::domain::solver::delta::DependencyGraph;
+use crate::domain::{new_id, Expression, ExpressionError, PropertyRef};
+use std::collections::BTreeSet;
+use uuid::Uuid;
+
+/// A property kept equal to an expression
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ExpressionConstraint {
+    /// Unique identifier of the constraint
+    pub id: Uuid,
+    /// The property set
+    pub target: PropertyRef,
+    /// The expression it is set to, as written
+    pub expression: String,
+}
+
+impl ExpressionConstraint {
+    /// Read a constraint written as `object.property = expression`
+    ///
+    /// # Errors
+    /// Returns a syntax error if the left side is not a single property
+    /// or the right side is not an expression
+    pub fn parse(text: &str) -> Result<Self, ExpressionError> {
+        let Some((written, expression)) = text.split_once('=') else {
+            return Err(ExpressionError::Syntax {
+                position: text.chars().count(),
+                message: "an equals sign".to_string(),
+            });
+        };
+        let Expression::Property(target) = Expression::parse(written)? else {
+            return Err(ExpressionError::Syntax {
+                position: 0,
+                message: "a property to set".to_string(),
+            });
+        };
+        Expression::parse(expression).map_err(|error| match error {
+            ExpressionError::Syntax { position, message } => ExpressionError::Syntax {
+                position: position + written.chars().count() + 1,
+                message,
+            },
+            other => other,
+        })?;
+        Ok(Self {
+            id: new_id(),
+            target,
+            expression: expression.trim().to_string(),
+        })
+    }
+
+    /// The parsed expression
+    ///
+    /// # Errors
+    /// Returns a syntax error if the expression no longer parses
+    pub fn expression(&self) -> Result<Expression, ExpressionError> {
+        Expression::parse(&self.expression)
+    }
+}
+
+/// Order expression constraints so that each comes after the constraints
+/// setting the properties it reads
+///
+/// # Errors
+/// Returns an error if an expression does not parse, two constraints set
+/// the same property, or constraints depend on each other in a loop,
+/// naming the properties on it
+pub fn order_expression_constraints(
+    constraints: &[ExpressionConstraint],
+) -> Result<Vec<&ExpressionConstraint>, ExpressionError> {
+    let mut graph = DependencyGraph::default();
+    for constraint in constraints {
+        if constraints
+            .iter()
+            .any(|other| other.id < constraint.id && other.target == constraint.target)
+        {
+            return Err(ExpressionError::Overconstrained(constraint.target.clone()));
+        }
+        for reference in constraint.expression()?.references() {
+            for setter in constraints
+                .iter()
+                .filter(|other| other.target == *reference)
+            {
+                graph.add(setter.id, constraint.id);
+            }
+        }
+    }
+    let cycle = graph.cycles();
+    if !cycle.is_empty() {
+        return Err(ExpressionError::Cycle(
+            constraints
+                .iter()
+                .filter(|constraint| cycle.contains(&constraint.id))
+                .map(|constraint| constraint.target.clone())
+                .collect(),
+        ));
+    }
+    let ids: BTreeSet<Uuid> = constraints.iter().map(|constraint| constraint.id).collect();
+    Ok(graph
+        .ordered(&ids)
+        .into_iter()
+        .filter_map(|id| constraints.iter().find(|constraint| constraint.id == id))
+        .collect())
+}
@@ -0,0 +1,189 @@
+/// Incremental solving
+///
+/// Solving on edit applies only the explicit constraints on the geometry
+/// an edit moved, rather than every constraint in the tier, and stops at
+/// a deadline so that it fits in a frame. Each pass applies the
+/// constraints in ORDER.md order, each writing its deltas before the next
+/// is applied, and the constraints on the vertices a pass moved are taken
+/// in on the next. The constraints still unsatisfied when the passes stop
+/// are reported as violated.
+use crate::domain::solver::constraints::{
+    boundary_constraint, coincident, collinear, coplanar, equiangular, equilateral, level,
+    orthogonal, parallel, plumb,
+};
+use crate::domain::solver::{context, delta, error, Constraint, ConstraintKind};
+use crate::domain::{GeometryRegistry, Point};
+use std::collections::{BTreeMap, BTreeSet};
+use std::time::Instant;
+use uuid::Uuid;
+
+/// What an incremental solve did
+#[derive(Debug, Clone, Default)]
+pub struct IncrementalSolve {
+    /// Passes made over the constraints
+    pub passes: usize,
+    /// Whether the last pass moved nothing further than the convergence
+    /// tolerance
+    pub converged: bool,
+    /// The vertices the solve moved and where each was before it, so
+    /// that the solve can be undone
+    pub moved: BTreeMap<Uuid, Point>,
+    /// The constraints on the edited geometry still unsatisfied, or that
+    /// could not be applied, in the order they are applied
+    pub violated: Vec<Constraint>,
+}
+
+/// The deltas that would satisfy a constraint
+///
+/// # Errors
+/// Returns the constraint's error, such as a boundary violation or
+/// missing geometry
+pub fn constraint_deltas(
+    geometry_registry: &GeometryRegistry,
+    context: &context::TierContext,
+    constraint: &Constraint,
+) -> Result<delta::DeltaSet, error::ConstraintError> {
+    let targets = constraint.targets.as_slice();
+    match constraint.kind {
+        ConstraintKind::Coincident => {
+            coincident::apply_coincident(geometry_registry, context, targets)
+        }
+        ConstraintKind::Collinear => {
+            collinear::apply_collinear(geometry_registry, context, targets)
+        }
+        ConstraintKind::Coplanar => coplanar::apply_coplanar(geometry_registry, context, targets),
+        ConstraintKind::Boundary => {
+            boundary_constraint::apply_boundary(geometry_registry, context, targets)
+        }
+        ConstraintKind::Equilateral => {
+            equilateral::apply_equilateral(geometry_registry, context, targets)
+        }
+        ConstraintKind::Equiangular => {
+            equiangular::apply_equiangular(geometry_registry, context, targets)
+        }
+        ConstraintKind::Parallel => parallel::apply_parallel(
+            geometry_registry,
+            context,
+            targets,
+            constraint.reference.as_ref(),
+        ),
+        ConstraintKind::Plumb => plumb::apply_plumb(geometry_registry, context, targets),
+        ConstraintKind::Level => level::apply_level(geometry_registry, context, targets),
+        ConstraintKind::Orthogonal => {
+            orthogonal::apply_orthogonal(geometry_registry, context, targets)
+        }
+    }
+}
+
+/// The vertices a constraint's targets are made of
+///
+/// Targets missing from the registry have none.
+#[must_use]
+pub fn constraint_vertices(
+    geometry_registry: &GeometryRegistry,
+    constraint: &Constraint,
+) -> BTreeSet<Uuid> {
+    let mut vertices = BTreeSet::new();
+    for target in &constraint.targets {
+        if geometry_registry.vertices.get(target).is_some() {
+            vertices.insert(*target);
+        } else if let Some(segment) = geometry_registry.segments.get(target) {
+            vertices.extend(segment.vertices);
+        } else if let Some(polygon) = geometry_registry.polygons.get(target) {
+            vertices.extend(
+                polygon
+                    .vertex_loop(&geometry_registry.segments)
+                    .unwrap_or_default(),
+            );
+        }
+    }
+    vertices
+}
+
+/// The explicit constraints on any of some vertices, in the order they
+/// are applied
+fn constraints_on<'a>(
+    geometry_registry: &GeometryRegistry,
+    context: &'a context::TierContext,
+    vertices: &BTreeSet<Uuid>,
+) -> Vec<&'a Constraint> {
+    let mut constraints: Vec<&Constraint> = context
+        .constraints
+        .explicit
+        .iter()
+        .filter(|constraint| {
+            !constraint_vertices(geometry_registry, constraint).is_disjoint(vertices)
+        })
+        .collect();
+    constraints.sort_by_key(|constraint| {
+        ConstraintKind::ALL
+            .iter()
+            .position(|kind| *kind == constraint.kind)
+    });
+    constraints
+}
+
+/// Solve the constraints on edited vertices until they converge, the
+/// solver's passes run out or the deadline, if any, passes
+///
+/// The solver settings are those of the context for the edited vertices,
+/// so a cluster with its own settings is solved with them. At least one
+/// pass is made however late it is, so every edit is solved a little.
+/// The geometry is solved in place, and the positions the solve moved
+/// vertices from are returned so that it can be undone.
+#[tracing::instrument(skip_all, fields(edited = edited.len(), iterations = tracing::field::Empty))]
+pub fn solve_incrementally(
+    geometry_registry: &mut GeometryRegistry,
+    context: &context::TierContext,
+    edited: &[Uuid],
+    deadline: Option<Instant>,
+) -> IncrementalSolve {
+    let solver = context.solver_for(edited);
+    let mut touched: BTreeSet<Uuid> = edited.iter().copied().collect();
+    let mut solve = IncrementalSolve::default();
+    while solve.passes < solver.max_iterations
+        && (solve.passes == 0 || deadline.is_none_or(|deadline| Instant::now() < deadline))
+    {
+        solve.passes += 1;
+        let mut converged = true;
+        let mut pass_moved = Vec::new();
+        for constraint in constraints_on(geometry_registry, context, &touched) {
+            let Ok(deltas) = constraint_deltas(geometry_registry, context, constraint) else {
+                continue;
+            };
+            if deltas.is_converged(&solver.tolerance) {
+                continue;
+            }
+            converged = false;
+            if deltas
+                .damped(solver.damping)
+                .apply(&mut geometry_registry.vertices)
+                .is_ok()
+            {
+                for delta in &deltas.deltas {
+                    pass_moved.push(delta.vertex_id);
+                    solve
+                        .moved
+                        .entry(delta.vertex_id)
+                        .or_insert_with(|| delta.old_position.clone());
+                }
+            }
+        }
+        tracing::debug!(iteration = solve.passes, moved = pass_moved.len());
+        if converged {
+            solve.converged = true;
+            break;
+        }
+        touched.extend(pass_moved);
+    }
+    tracing::Span::current().record("iterations", solve.passes);
+    solve.violated = constraints_on(geometry_registry, context, &touched)
+        .into_iter()
+        .filter(|constraint| {
+            constraint_deltas(geometry_registry, context, constraint)
+                .map_or(true, |deltas| !deltas.is_converged(&solver.tolerance))
+        })
+        .cloned()
+        .collect();
+    solve
+}
@@ -14,9 +14,18 @@ pub mod types;
 /// Constraint application context (tier-aware settings)
 pub mod context;
 
+/// Solver settings and their per-tier and per-cluster overrides
+pub mod config;
+
 /// Main constraint solver (orchestrates constraint application)
 pub mod apply_solve;
 
+/// Solving only the constraints on edited geometry, within a deadline
+pub mod incremental;
+
+/// Finding the smallest set of conflicting constraints and ways out of it
+pub mod conflict;
+
 /// Delta tracking and propagation
 pub mod delta;
 
@@ -29,11 +38,18 @@ pub mod error;
 /// Individual constraint implementations
 pub mod constraints;
 
+/// Properties kept equal to expressions over other properties
+pub mod expressions;
+
 pub use types::*;
 pub use context::*;
+pub use config::*;
 pub use apply_solve::*;
+pub use incremental::*;
+pub use conflict::*;
 pub use delta::*;
 pub use boundary::*;
 pub use error::*;
 pub use constraints::*;
+pub use expressions::*;
 
@@ -2,12 +2,14 @@
 ///
 /// Defines the fundamental constraint types, kinds, and structures
 /// that the constraint solver operates on.
+use crate::domain::solver::{ExpressionConstraint, SolverCluster};
 use uuid::Uuid;
 
 /// The kind of constraint being applied
 ///
 /// Matches the ordering defined in ORDER.md
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ConstraintKind {
     /// Coincident (Rare) - Points must be at the same location
     Coincident,
@@ -21,6 +23,8 @@ pub enum ConstraintKind {
     Equilateral,
     /// Equiangular - Angles must be equal
     Equiangular,
+    /// Parallel - Segments must share a direction
+    Parallel,
     /// Plumb (Opt-out) - Vertical alignment (default enabled)
     Plumb,
     /// Level (Opt-out) - Horizontal alignment (default enabled)
@@ -29,25 +33,62 @@ pub enum ConstraintKind {
     Orthogonal,
 }
 
+impl ConstraintKind {
+    /// Every kind, in the order they are applied
+    pub const ALL: [ConstraintKind; 10] = [
+        ConstraintKind::Coincident,
+        ConstraintKind::Collinear,
+        ConstraintKind::Coplanar,
+        ConstraintKind::Boundary,
+        ConstraintKind::Equilateral,
+        ConstraintKind::Equiangular,
+        ConstraintKind::Parallel,
+        ConstraintKind::Plumb,
+        ConstraintKind::Level,
+        ConstraintKind::Orthogonal,
+    ];
+
+    /// Human-readable name of the kind
+    #[must_use]
+    pub fn label(&self) -> &'static str {
+        match self {
+            ConstraintKind::Coincident => "Coincident",
+            ConstraintKind::Collinear => "Collinear",
+            ConstraintKind::Coplanar => "Coplanar",
+            ConstraintKind::Boundary => "Boundary",
+            ConstraintKind::Equilateral => "Equilateral",
+            ConstraintKind::Equiangular => "Equiangular",
+            ConstraintKind::Parallel => "Parallel",
+            ConstraintKind::Plumb => "Plumb",
+            ConstraintKind::Level => "Level",
+            ConstraintKind::Orthogonal => "Orthogonal",
+        }
+    }
+}
+
 /// Reference for relational constraints
 ///
 /// Some constraints (coplanar, orthogonal) need to reference other geometry
 /// or define a reference frame. This enum captures those references.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ConstraintReference {
     /// Self-referential: the constraint set defines its own reference
-    /// (e.g., first 3 vertices define plane for coplanar)
+    /// (e.g., the best-fit plane of all vertices for coplanar)
     SelfDefined,
+    /// A direction variable from the direction registry
+    /// (e.g., the shared heading of parallel segments)
+    Direction(Uuid),
     // TODO: Add explicit reference types as needed
     // Plane(PlaneRef),
     // Axis(AxisRef),
-    // Direction(Vector),
 }
 
 /// A single constraint assignment
 ///
 /// Defines a constraint applied to specific geometry entities.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Constraint {
     /// The kind of constraint
     pub kind: ConstraintKind,
@@ -57,11 +98,43 @@ pub struct Constraint {
     pub reference: Option<ConstraintReference>,
 }
 
+impl Constraint {
+    /// The constraint's kind and what it holds, for lists of constraints
+    #[must_use]
+    pub fn summary(&self) -> String {
+        let reference = match &self.reference {
+            Some(ConstraintReference::Direction(_)) => " to a direction",
+            Some(ConstraintReference::SelfDefined) | None => "",
+        };
+        match self.targets.len() {
+            1 => format!("{}, 1 target{reference}", self.kind.label()),
+            targets => format!("{}, {targets} targets{reference}", self.kind.label()),
+        }
+    }
+
+    /// Check whether two constraints express the same intent
+    ///
+    /// Targets are compared sorted, so listing them in another order does
+    /// not make a new constraint, but listing one more times does.
+    #[must_use]
+    pub fn is_duplicate_of(&self, other: &Constraint) -> bool {
+        let sorted = |targets: &[Uuid]| {
+            let mut targets = targets.to_vec();
+            targets.sort_unstable();
+            targets
+        };
+        self.kind == other.kind
+            && self.reference == other.reference
+            && sorted(&self.targets) == sorted(&other.targets)
+    }
+}
+
 /// Configuration for opt-out constraints
 ///
 /// Architectural constraints (plumb, level, orthogonal) are opt-out.
 /// This struct tracks which ones are enabled (default true).
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct OptOutConstraints {
     /// Plumb constraint enabled (default: true)
     pub plumb_enabled: bool,
@@ -85,9 +158,16 @@ impl Default for OptOutConstraints {
 ///
 /// Combines opt-out constraint flags with explicit constraint assignments.
 #[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ConstraintSet {
     /// Opt-out constraint configuration
     pub opt_out: OptOutConstraints,
     /// Explicit constraint assignments
     pub explicit: Vec<Constraint>,
+    /// Properties kept equal to expressions
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub expressions: Vec<ExpressionConstraint>,
+    /// Clusters of geometry solved with their own settings
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub clusters: Vec<SolverCluster>,
 }
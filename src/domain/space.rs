@@ -0,0 +1,62 @@
+/// Rooms found in the model
+///
+/// A space is an element of kind `Space` whose solid is the room volume.
+/// Its floor is every face pointing down, so a room's floor area is what
+/// a tenant would measure, whatever shape the room has.
+use crate::domain::geometry::{newell_normal, polygon_area, signed_volume};
+use crate::domain::{ElementKind, ElementRegistry, GeometryRegistry, Point};
+use uuid::Uuid;
+
+/// A room volume with its measured areas
+#[derive(Debug, Clone)]
+pub struct Space {
+    /// The space element
+    pub element: Uuid,
+    /// The solid bounding the room
+    pub solid: Uuid,
+    /// The room's name
+    pub name: String,
+    /// Total area of the downward-facing faces
+    pub floor_area: f32,
+    /// Enclosed volume
+    pub volume: f32,
+    /// The downward-facing faces as point loops
+    pub floors: Vec<Vec<Point>>,
+}
+
+/// Find the spaces whose solids are in the registry, sorted by name
+///
+/// Faces count as floor when their normal is within 45 degrees of
+/// straight down.
+#[must_use]
+pub fn detect_spaces(
+    element_registry: &ElementRegistry,
+    geometry_registry: &GeometryRegistry,
+) -> Vec<Space> {
+    let mut spaces: Vec<Space> = element_registry
+        .elements
+        .values()
+        .filter(|element| element.kind == ElementKind::Space)
+        .filter_map(|element| {
+            let loops = geometry_registry.solid_loops(&element.solid)?;
+            let floors: Vec<Vec<Point>> = loops
+                .iter()
+                .filter(|points| {
+                    let normal = newell_normal(points);
+                    normal.y < 0.0 && -normal.y >= normal.length() * std::f32::consts::FRAC_1_SQRT_2
+                })
+                .cloned()
+                .collect();
+            Some(Space {
+                element: element.id,
+                solid: element.solid,
+                name: element.name.clone(),
+                floor_area: floors.iter().map(|points| polygon_area(points)).sum(),
+                volume: signed_volume(&loops).abs(),
+                floors,
+            })
+        })
+        .collect();
+    spaces.sort_by(|a, b| a.name.cmp(&b.name).then(a.element.cmp(&b.element)));
+    spaces
+}
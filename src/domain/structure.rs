@@ -0,0 +1,475 @@
+/// Analytical structural models
+///
+/// Columns and beams become line members along their axes and slabs become
+/// shells at their mid-plane, the idealization structural analysis tools
+/// work on. Member ends are joined by geometric adjacency: a column end
+/// touching a slab moves to the slab's mid-plane, and a beam end resting in
+/// a column or on another beam moves onto that member's axis. Members are
+/// split wherever another member joins them part way, so every connection
+/// is a node shared by the members meeting there.
+use crate::domain::geometry::{newell_normal, point_in_loop, polygon_area, Bounds, PointIndex};
+use crate::domain::{measure_vector, Element, ElementKind, ElementRegistry, GeometryRegistry};
+use crate::domain::{Point, Vector};
+use uuid::Uuid;
+
+/// Column axes are vertical
+const UP: Vector = Vector {
+    x: 0.0,
+    y: 1.0,
+    z: 0.0,
+};
+
+/// Settings for deriving an analytical model
+#[derive(Debug, Clone)]
+pub struct StructuralSettings {
+    /// How far apart member ends and the elements they rest on may be and
+    /// still connect
+    pub tolerance: f32,
+}
+
+impl Default for StructuralSettings {
+    fn default() -> Self {
+        Self { tolerance: 0.05 }
+    }
+}
+
+/// What a line member is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemberKind {
+    /// A vertical member
+    Column,
+    /// A horizontal or sloping member
+    Beam,
+}
+
+impl MemberKind {
+    /// Human-readable name of the kind
+    #[must_use]
+    pub fn label(self) -> &'static str {
+        match self {
+            MemberKind::Column => "Column",
+            MemberKind::Beam => "Beam",
+        }
+    }
+}
+
+/// A point where members and shells connect
+#[derive(Debug, Clone)]
+pub struct StructuralNode {
+    /// Position of the node
+    pub position: Point,
+    /// Whether the node is held in place, at the foot of a column with
+    /// nothing below it
+    pub support: bool,
+}
+
+/// A rectangular member cross-section
+#[derive(Debug, Clone, PartialEq)]
+pub struct Section {
+    /// Size across the member, horizontally
+    pub width: f32,
+    /// Size across the member, perpendicular to the width
+    pub depth: f32,
+    /// The material of the members with this section
+    pub material: Option<String>,
+}
+
+impl Section {
+    /// Cross-sectional area
+    #[must_use]
+    pub fn area(&self) -> f32 {
+        self.width * self.depth
+    }
+}
+
+/// A line member between two nodes
+#[derive(Debug, Clone)]
+pub struct Member {
+    /// The column or beam element the member comes from
+    pub element: Uuid,
+    /// Whether the member is a column or a beam
+    pub kind: MemberKind,
+    /// Index of the start node, the lower end of a column
+    pub start: usize,
+    /// Index of the end node
+    pub end: usize,
+    /// Index of the member's section
+    pub section: usize,
+}
+
+/// A slab as a shell at its mid-plane
+#[derive(Debug, Clone)]
+pub struct Shell {
+    /// The slab element
+    pub element: Uuid,
+    /// Indices of the outline nodes, in order
+    pub nodes: Vec<usize>,
+    /// Member nodes inside the outline, where columns meet the shell
+    pub connected: Vec<usize>,
+    /// Slab thickness
+    pub thickness: f32,
+    /// The slab material
+    pub material: Option<String>,
+}
+
+/// Nodes, sections, members and shells of an analytical model
+#[derive(Debug, Clone, Default)]
+pub struct StructuralModel {
+    /// The connection points
+    pub nodes: Vec<StructuralNode>,
+    /// The distinct member sections
+    pub sections: Vec<Section>,
+    /// The line members, split at every connection
+    pub members: Vec<Member>,
+    /// The slab shells
+    pub shells: Vec<Shell>,
+}
+
+/// The axis and section of a column or beam
+struct Axis<'a> {
+    element: &'a Element,
+    kind: MemberKind,
+    start: Point,
+    end: Point,
+    width: f32,
+    depth: f32,
+    bounds: Bounds,
+}
+
+/// A point moved along a direction
+fn offset(point: &Point, direction: &Vector, distance: f32) -> Point {
+    Point {
+        x: point.x + direction.x * distance,
+        y: point.y + direction.y * distance,
+        z: point.z + direction.z * distance,
+    }
+}
+
+/// Whether a box widened by a margin holds a point
+fn reaches(bounds: &Bounds, point: &Point, margin: f32) -> bool {
+    (bounds.min.x - margin..=bounds.max.x + margin).contains(&point.x)
+        && (bounds.min.y - margin..=bounds.max.y + margin).contains(&point.y)
+        && (bounds.min.z - margin..=bounds.max.z + margin).contains(&point.z)
+}
+
+/// Parameter of the closest point on segment `a`-`b`, clamped to it
+fn segment_parameter(a: &Point, b: &Point, point: &Point) -> f32 {
+    let along = measure_vector(a, b);
+    let length_squared = along.dot(&along);
+    if length_squared <= f32::EPSILON {
+        return 0.0;
+    }
+    (measure_vector(a, point).dot(&along) / length_squared).clamp(0.0, 1.0)
+}
+
+/// The direction of the longest edge, or of the longest horizontal edge
+fn longest_edge(loops: &[Vec<Point>], horizontal: bool) -> Option<Vector> {
+    loops
+        .iter()
+        .flat_map(|points| {
+            points
+                .iter()
+                .zip(points.iter().cycle().skip(1))
+                .map(|(a, b)| measure_vector(a, b))
+        })
+        .filter(|edge| !horizontal || edge.y.abs() <= edge.length() * 0.1)
+        .max_by(|a, b| a.length().total_cmp(&b.length()))
+        .and_then(|edge| edge.normalized())
+}
+
+/// Find the axis and section of a column or beam solid
+#[allow(clippy::cast_precision_loss)]
+fn member_axis<'a>(
+    element: &'a Element,
+    kind: MemberKind,
+    loops: &[Vec<Point>],
+) -> Option<Axis<'a>> {
+    let points = loops.concat();
+    let bounds = Bounds::from_points(&points)?;
+    let count = points.len() as f32;
+    let center = Point {
+        x: points.iter().map(|point| point.x).sum::<f32>() / count,
+        y: points.iter().map(|point| point.y).sum::<f32>() / count,
+        z: points.iter().map(|point| point.z).sum::<f32>() / count,
+    };
+    let (direction, across) = match kind {
+        MemberKind::Column => {
+            let across = longest_edge(loops, true).unwrap_or(Vector {
+                x: 1.0,
+                y: 0.0,
+                z: 0.0,
+            });
+            (UP, across)
+        }
+        MemberKind::Beam => {
+            let mut direction = longest_edge(loops, false)?;
+            // Beams run towards +X, or towards +Z when they run north-south
+            if direction.x < -f32::EPSILON
+                || (direction.x.abs() <= f32::EPSILON && direction.z < 0.0)
+            {
+                direction = direction.scaled(-1.0);
+            }
+            let across = direction.cross(&UP).normalized().unwrap_or(Vector {
+                x: 1.0,
+                y: 0.0,
+                z: 0.0,
+            });
+            (direction, across)
+        }
+    };
+    let third = direction.cross(&across);
+    let extent = |axis: &Vector| {
+        let values = points
+            .iter()
+            .map(|point| measure_vector(&center, point).dot(axis));
+        (
+            values.clone().fold(f32::INFINITY, f32::min),
+            values.fold(f32::NEG_INFINITY, f32::max),
+        )
+    };
+    let (first, last) = extent(&direction);
+    let (across_min, across_max) = extent(&across);
+    let (third_min, third_max) = extent(&third);
+    Some(Axis {
+        element,
+        kind,
+        start: offset(&center, &direction, first),
+        end: offset(&center, &direction, last),
+        width: across_max - across_min,
+        depth: third_max - third_min,
+        bounds,
+    })
+}
+
+/// A slab's outline at its mid-plane, its thickness and bounds
+struct SlabOutline<'a> {
+    element: &'a Element,
+    outline: Vec<Point>,
+    thickness: f32,
+    bounds: Bounds,
+}
+
+/// The mid-plane outline of a slab from its largest upward face
+fn slab_outline<'a>(element: &'a Element, loops: &[Vec<Point>]) -> Option<SlabOutline<'a>> {
+    let bounds = Bounds::from_points(&loops.concat())?;
+    let thickness = bounds.max.y - bounds.min.y;
+    let top = loops
+        .iter()
+        .filter(|points| newell_normal(points).y > 0.0)
+        .max_by(|a, b| polygon_area(a).total_cmp(&polygon_area(b)))?;
+    Some(SlabOutline {
+        element,
+        outline: top
+            .iter()
+            .map(|point| offset(point, &UP, -thickness / 2.0))
+            .collect(),
+        thickness,
+        bounds,
+    })
+}
+
+/// Move column ends onto the slabs they touch and beam ends onto the
+/// columns and beams they rest on
+fn connect_ends(axes: &mut [Axis], slabs: &[SlabOutline], tolerance: f32) {
+    for axis in axes.iter_mut() {
+        if axis.kind != MemberKind::Column {
+            continue;
+        }
+        for end in [&mut axis.start, &mut axis.end] {
+            if let Some(slab) = slabs
+                .iter()
+                .find(|slab| reaches(&slab.bounds, end, tolerance))
+            {
+                end.y = slab.bounds.min.y.midpoint(slab.bounds.max.y);
+            }
+        }
+    }
+    let supports: Vec<(MemberKind, Point, Point, Bounds)> = axes
+        .iter()
+        .map(|axis| {
+            (
+                axis.kind,
+                axis.start.clone(),
+                axis.end.clone(),
+                axis.bounds.clone(),
+            )
+        })
+        .collect();
+    for (index, axis) in axes.iter_mut().enumerate() {
+        if axis.kind != MemberKind::Beam {
+            continue;
+        }
+        for end in [&mut axis.start, &mut axis.end] {
+            // Columns take priority over beams when an end touches both
+            let support = supports
+                .iter()
+                .enumerate()
+                .filter(|(other, (_, _, _, bounds))| {
+                    *other != index && reaches(bounds, end, tolerance)
+                })
+                .min_by_key(|(_, (kind, ..))| *kind != MemberKind::Column);
+            match support {
+                Some((_, (MemberKind::Column, start, _, _))) => {
+                    end.x = start.x;
+                    end.z = start.z;
+                }
+                Some((_, (MemberKind::Beam, start, stop, _))) => {
+                    let t = segment_parameter(start, stop, end);
+                    *end = offset(start, &measure_vector(start, stop), t);
+                }
+                None => {}
+            }
+        }
+    }
+}
+
+/// Add a member's section and its parts between the nodes along it
+fn add_member(
+    model: &mut StructuralModel,
+    axis: &Axis,
+    (start, end): (usize, usize),
+    index: &PointIndex,
+    tolerance: f32,
+) {
+    let section = Section {
+        width: (axis.width * 1000.0).round() / 1000.0,
+        depth: (axis.depth * 1000.0).round() / 1000.0,
+        material: axis.element.material.clone(),
+    };
+    let section = model
+        .sections
+        .iter()
+        .position(|existing| *existing == section)
+        .unwrap_or_else(|| {
+            model.sections.push(section);
+            model.sections.len() - 1
+        });
+    // Split the member at every node lying part way along it
+    let mut stations: Vec<(f32, usize)> = index
+        .points
+        .iter()
+        .enumerate()
+        .filter(|&(node, _)| node != start && node != end)
+        .filter_map(|(node, point)| {
+            let t = segment_parameter(&axis.start, &axis.end, point);
+            let closest = offset(&axis.start, &measure_vector(&axis.start, &axis.end), t);
+            (t > 0.0 && t < 1.0 && measure_vector(&closest, point).length() <= tolerance)
+                .then_some((t, node))
+        })
+        .collect();
+    stations.sort_by(|a, b| a.0.total_cmp(&b.0));
+    let chain: Vec<usize> = std::iter::once(start)
+        .chain(stations.into_iter().map(|(_, node)| node))
+        .chain(std::iter::once(end))
+        .collect();
+    for pair in chain.windows(2) {
+        model.members.push(Member {
+            element: axis.element.id,
+            kind: axis.kind,
+            start: pair[0],
+            end: pair[1],
+            section,
+        });
+    }
+}
+
+/// Derive an analytical model from the columns, beams and slabs
+#[must_use]
+#[tracing::instrument(skip_all)]
+pub fn derive_structural_model(
+    element_registry: &ElementRegistry,
+    geometry_registry: &GeometryRegistry,
+    settings: &StructuralSettings,
+) -> StructuralModel {
+    let tolerance = settings.tolerance;
+    let mut elements: Vec<&Element> = element_registry.elements.values().collect();
+    elements.sort_by_key(|element| element.id);
+
+    let mut axes = Vec::new();
+    let mut slabs = Vec::new();
+    for element in elements {
+        let Some(loops) = geometry_registry.solid_loops(&element.solid) else {
+            continue;
+        };
+        match element.kind {
+            ElementKind::Column => axes.extend(member_axis(element, MemberKind::Column, &loops)),
+            ElementKind::Beam => axes.extend(member_axis(element, MemberKind::Beam, &loops)),
+            ElementKind::Slab => slabs.extend(slab_outline(element, &loops)),
+            _ => {}
+        }
+    }
+    connect_ends(&mut axes, &slabs, tolerance);
+
+    let mut index = PointIndex::new(tolerance);
+    let ends: Vec<(usize, usize)> = axes
+        .iter()
+        .map(|axis| (index.insert(&axis.start), index.insert(&axis.end)))
+        .collect();
+    let outlines: Vec<Vec<usize>> = slabs
+        .iter()
+        .map(|slab| index.insert_loop(&slab.outline))
+        .collect();
+
+    let mut model = StructuralModel::default();
+    for (axis, &(start, end)) in axes.iter().zip(&ends) {
+        add_member(&mut model, axis, (start, end), &index, tolerance);
+    }
+
+    // A column foot is a support unless another column stands beneath it
+    let column_tops: Vec<usize> = axes
+        .iter()
+        .zip(&ends)
+        .filter(|(axis, _)| axis.kind == MemberKind::Column)
+        .map(|(_, &(_, end))| end)
+        .collect();
+    let supports: Vec<usize> = axes
+        .iter()
+        .zip(&ends)
+        .filter(|(axis, _)| axis.kind == MemberKind::Column)
+        .map(|(_, &(start, _))| start)
+        .filter(|start| !column_tops.contains(start))
+        .collect();
+    model.nodes = index
+        .points
+        .iter()
+        .enumerate()
+        .map(|(node, point)| StructuralNode {
+            position: point.clone(),
+            support: supports.contains(&node),
+        })
+        .collect();
+
+    for (slab, nodes) in slabs.iter().zip(outlines) {
+        let normal = newell_normal(&slab.outline);
+        let mid = slab.bounds.min.y.midpoint(slab.bounds.max.y);
+        let connected = model
+            .members
+            .iter()
+            .flat_map(|member| [member.start, member.end])
+            .filter(|node| !nodes.contains(node))
+            .filter(|&node| {
+                let point = &index.points[node];
+                (point.y - mid).abs() <= tolerance && point_in_loop(point, &slab.outline, &normal)
+            })
+            .fold(Vec::new(), |mut connected, node| {
+                if !connected.contains(&node) {
+                    connected.push(node);
+                }
+                connected
+            });
+        model.shells.push(Shell {
+            element: slab.element.id,
+            nodes,
+            connected,
+            thickness: slab.thickness,
+            material: slab.element.material.clone(),
+        });
+    }
+    tracing::info!(
+        nodes = model.nodes.len(),
+        members = model.members.len(),
+        shells = model.shells.len(),
+        "derived structural model"
+    );
+    model
+}
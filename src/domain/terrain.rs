@@ -0,0 +1,311 @@
+/// Terrain modeling
+///
+/// A terrain is a triangulated irregular network (TIN): surveyed points
+/// joined into triangles in plan (X-Z), each point keeping its elevation
+/// (Y). TINs are built from contour polylines or scattered points by
+/// Delaunay triangulation, or directly from regular height grids, and can
+/// be compared for cut and fill.
+use crate::domain::geometry::PointIndex;
+use crate::domain::{GeometryRegistry, Point, Tolerance};
+use uuid::Uuid;
+
+/// A triangulated terrain surface
+#[derive(Debug, Clone, Default)]
+pub struct Tin {
+    /// The surface points
+    pub points: Vec<Point>,
+    /// Triangles as point indices, counter-clockwise seen from above
+    pub triangles: Vec<[usize; 3]>,
+}
+
+/// Earthwork volumes between two terrains
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct CutFill {
+    /// Volume to remove where the proposed surface is lower, in cubic meters
+    pub cut: f32,
+    /// Volume to add where the proposed surface is higher, in cubic meters
+    pub fill: f32,
+}
+
+impl CutFill {
+    /// Fill minus cut: positive when material must be brought in
+    #[must_use]
+    pub fn net(&self) -> f32 {
+        self.fill - self.cut
+    }
+}
+
+impl Tin {
+    /// Delaunay triangulation of scattered points
+    ///
+    /// Points closer in plan than the linear tolerance are merged, keeping
+    /// the first. Returns an empty TIN for fewer than three points.
+    #[must_use]
+    pub fn from_points(points: &[Point], tolerance: &Tolerance) -> Self {
+        let mut index = PointIndex::new(tolerance.linear);
+        let mut unique = Vec::new();
+        for point in points {
+            let plan = Point {
+                x: point.x,
+                y: 0.0,
+                z: point.z,
+            };
+            if index.insert(&plan) == unique.len() {
+                unique.push(point.clone());
+            }
+        }
+        let triangles = delaunay(&unique);
+        Self {
+            points: unique,
+            triangles,
+        }
+    }
+
+    /// Delaunay triangulation of contour polylines
+    ///
+    /// Contours are densified so no edge is longer than `spacing`, which
+    /// keeps triangles from bridging across a contour.
+    #[must_use]
+    pub fn from_contours(contours: &[Vec<Point>], spacing: f32, tolerance: &Tolerance) -> Self {
+        let mut points = Vec::new();
+        for contour in contours {
+            for pair in contour.windows(2) {
+                let (a, b) = (&pair[0], &pair[1]);
+                let length = ((b.x - a.x).powi(2) + (b.z - a.z).powi(2)).sqrt();
+                let steps = step_count(length, spacing);
+                for step in 0..steps {
+                    #[allow(clippy::cast_precision_loss)]
+                    let t = step as f32 / steps as f32;
+                    points.push(Point {
+                        x: a.x + (b.x - a.x) * t,
+                        y: a.y + (b.y - a.y) * t,
+                        z: a.z + (b.z - a.z) * t,
+                    });
+                }
+            }
+            points.extend(contour.last().cloned());
+        }
+        Self::from_points(&points, tolerance)
+    }
+
+    /// Triangulation of a regular height grid
+    ///
+    /// `heights[row][column]` is the elevation above `origin` of the point
+    /// `column * spacing` along X and `row * spacing` along Z. Rows must be
+    /// the same length.
+    #[must_use]
+    pub fn from_grid(origin: &Point, spacing: f32, heights: &[Vec<f32>]) -> Self {
+        let columns = heights.first().map_or(0, Vec::len);
+        let mut points = Vec::with_capacity(columns * heights.len());
+        for (row, row_heights) in heights.iter().enumerate() {
+            for (column, height) in row_heights.iter().enumerate().take(columns) {
+                #[allow(clippy::cast_precision_loss)]
+                points.push(Point {
+                    x: origin.x + column as f32 * spacing,
+                    y: origin.y + height,
+                    z: origin.z + row as f32 * spacing,
+                });
+            }
+        }
+        let mut triangles = Vec::new();
+        for row in 1..heights.len() {
+            for column in 1..columns {
+                let (a, b) = (
+                    (row - 1) * columns + column - 1,
+                    (row - 1) * columns + column,
+                );
+                let (c, d) = (row * columns + column, row * columns + column - 1);
+                triangles.push([a, d, c]);
+                triangles.push([a, c, b]);
+            }
+        }
+        Self { points, triangles }
+    }
+
+    /// Elevation of the surface above a plan position
+    /// Returns None outside the terrain
+    #[must_use]
+    pub fn elevation_at(&self, x: f32, z: f32) -> Option<f32> {
+        let here = Point { x, y: 0.0, z };
+        self.triangles.iter().find_map(|triangle| {
+            let [first, second, third] = triangle.map(|index| &self.points[index]);
+            let area = plan_cross(first, second, third);
+            if area.abs() <= f32::EPSILON {
+                return None;
+            }
+            let weights = [
+                plan_cross(&here, second, third) / area,
+                plan_cross(first, &here, third) / area,
+                plan_cross(first, second, &here) / area,
+            ];
+            let inside = weights.iter().all(|weight| *weight >= -1e-6);
+            inside.then_some(weights[0] * first.y + weights[1] * second.y + weights[2] * third.y)
+        })
+    }
+
+    /// Plan extent as (min x, min z, max x, max z)
+    /// Returns None for an empty terrain
+    #[must_use]
+    pub fn plan_bounds(&self) -> Option<(f32, f32, f32, f32)> {
+        self.points.iter().fold(None, |bounds, point| {
+            let (x0, z0, x1, z1) = bounds.unwrap_or((point.x, point.z, point.x, point.z));
+            Some((
+                x0.min(point.x),
+                z0.min(point.z),
+                x1.max(point.x),
+                z1.max(point.z),
+            ))
+        })
+    }
+
+    /// Cut and fill from this existing terrain to a proposed grading surface
+    ///
+    /// Both surfaces are sampled at the centers of a plan grid of the given
+    /// spacing over their overlap; cells outside either surface are left out.
+    #[must_use]
+    pub fn cut_fill(&self, proposed: &Tin, spacing: f32) -> CutFill {
+        let mut result = CutFill::default();
+        let (Some(existing), Some(graded)) = (self.plan_bounds(), proposed.plan_bounds()) else {
+            return result;
+        };
+        let (x0, z0) = (existing.0.max(graded.0), existing.1.max(graded.1));
+        let (x1, z1) = (existing.2.min(graded.2), existing.3.min(graded.3));
+        if spacing <= 0.0 || x1 <= x0 || z1 <= z0 {
+            return result;
+        }
+        let cell_area = spacing * spacing;
+        for row in 0..step_count(z1 - z0, spacing) {
+            for column in 0..step_count(x1 - x0, spacing) {
+                #[allow(clippy::cast_precision_loss)]
+                let (x, z) = (
+                    x0 + (column as f32 + 0.5) * spacing,
+                    z0 + (row as f32 + 0.5) * spacing,
+                );
+                let (Some(before), Some(after)) =
+                    (self.elevation_at(x, z), proposed.elevation_at(x, z))
+                else {
+                    continue;
+                };
+                if after > before {
+                    result.fill += (after - before) * cell_area;
+                } else {
+                    result.cut += (before - after) * cell_area;
+                }
+            }
+        }
+        result
+    }
+}
+
+impl GeometryRegistry {
+    /// Store a terrain as an open surface solid and return its ID
+    ///
+    /// Each triangle becomes an upward-facing polygon; shared edges and
+    /// points are welded within the tolerance.
+    #[tracing::instrument(skip_all, fields(triangles = tin.triangles.len()))]
+    pub fn create_terrain(&mut self, tin: &Tin, tolerance: &Tolerance) -> Uuid {
+        let loops: Vec<Vec<Point>> = tin
+            .triangles
+            .iter()
+            .map(|triangle| triangle.iter().map(|&i| tin.points[i].clone()).collect())
+            .collect();
+        self.create_solid_from_loops(&loops, tolerance)
+    }
+}
+
+/// Number of whole steps of at most `spacing` covering `length`, at least one
+fn step_count(length: f32, spacing: f32) -> usize {
+    if spacing <= 0.0 {
+        return 1;
+    }
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    let steps = (length / spacing).ceil() as usize;
+    steps.max(1)
+}
+
+/// Twice the signed plan area of a triangle, positive when it turns
+/// counter-clockwise seen from above (+Y)
+fn plan_cross(a: &Point, b: &Point, c: &Point) -> f32 {
+    (b.z - a.z) * (c.x - a.x) - (b.x - a.x) * (c.z - a.z)
+}
+
+/// Bowyer-Watson Delaunay triangulation in plan
+///
+/// Triangles are returned counter-clockwise seen from above.
+fn delaunay(points: &[Point]) -> Vec<[usize; 3]> {
+    if points.len() < 3 {
+        return Vec::new();
+    }
+    let (mut x0, mut z0, mut x1, mut z1) = (f32::MAX, f32::MAX, f32::MIN, f32::MIN);
+    for point in points {
+        (x0, z0) = (x0.min(point.x), z0.min(point.z));
+        (x1, z1) = (x1.max(point.x), z1.max(point.z));
+    }
+    // A super triangle well outside the bounds, removed at the end
+    let size = (x1 - x0).max(z1 - z0).max(1.0) * 20.0;
+    let (cx, cz) = (f32::midpoint(x0, x1), f32::midpoint(z0, z1));
+    let mut vertices = points.to_vec();
+    let first_super = vertices.len();
+    for (x, z) in [
+        (cx - size, cz - size),
+        (cx + size, cz - size),
+        (cx, cz + size),
+    ] {
+        vertices.push(Point { x, y: 0.0, z });
+    }
+    let mut triangles = vec![counter_clockwise(
+        &vertices,
+        [first_super, first_super + 1, first_super + 2],
+    )];
+
+    for index in 0..points.len() {
+        let point = &vertices[index];
+        let (bad, kept): (Vec<[usize; 3]>, Vec<[usize; 3]>) = triangles
+            .into_iter()
+            .partition(|triangle| in_circumcircle(&vertices, *triangle, point));
+        // The cavity boundary: edges of bad triangles not shared by another
+        let mut boundary: Vec<[usize; 2]> = Vec::new();
+        for [a, b, c] in &bad {
+            for edge in [[*a, *b], [*b, *c], [*c, *a]] {
+                if let Some(shared) = boundary
+                    .iter()
+                    .position(|other| *other == [edge[1], edge[0]] || *other == edge)
+                {
+                    boundary.swap_remove(shared);
+                } else {
+                    boundary.push(edge);
+                }
+            }
+        }
+        triangles = kept;
+        for [a, b] in boundary {
+            triangles.push(counter_clockwise(&vertices, [a, b, index]));
+        }
+    }
+
+    triangles.retain(|triangle| triangle.iter().all(|&i| i < first_super));
+    triangles
+}
+
+/// Reorder a triangle to turn counter-clockwise seen from above
+fn counter_clockwise(points: &[Point], [a, b, c]: [usize; 3]) -> [usize; 3] {
+    if plan_cross(&points[a], &points[b], &points[c]) < 0.0 {
+        [a, c, b]
+    } else {
+        [a, b, c]
+    }
+}
+
+/// Check whether a point lies inside a triangle's circumcircle in plan
+fn in_circumcircle(points: &[Point], [a, b, c]: [usize; 3], point: &Point) -> bool {
+    let (a, b, c) = (&points[a], &points[b], &points[c]);
+    let d = 2.0 * (a.x * (b.z - c.z) + b.x * (c.z - a.z) + c.x * (a.z - b.z));
+    if d.abs() <= f32::EPSILON {
+        return false;
+    }
+    let square = |p: &Point| p.x * p.x + p.z * p.z;
+    let ux = (square(a) * (b.z - c.z) + square(b) * (c.z - a.z) + square(c) * (a.z - b.z)) / d;
+    let uz = (square(a) * (c.x - b.x) + square(b) * (a.x - c.x) + square(c) * (b.x - a.x)) / d;
+    let radius = (a.x - ux).powi(2) + (a.z - uz).powi(2);
+    (point.x - ux).powi(2) + (point.z - uz).powi(2) < radius
+}
@@ -0,0 +1,375 @@
+/// Define the Tier type and its registry
+///
+/// Tiers form a hierarchy. Each tier may request its own tolerance, which
+/// is tightened against its parent's, so tolerances cascade from the root
+/// down and can only get stricter.
+use crate::domain::solver::{
+    apply_boundary, ConstraintError, ConstraintSet, SolverOverride, TierContext,
+};
+use crate::domain::{new_id, sorted_by_id, GeometryRegistry, Tolerance};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// A tier is a geometry scope
+/// This is the basis of the hierarchical geometry system
+/// Each tier is propagated to the next tier in a one-way relationship
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Tier {
+    /// The unique identifier of the tier
+    pub id: Uuid,
+    /// The name of the tier
+    pub name: String,
+    /// The parent tier, None for a root tier
+    pub parent: Option<Uuid>,
+    /// The geometry associated with the tier
+    pub geometry: Vec<Uuid>,
+    /// The tolerance the tier requests, None to inherit its parent's
+    pub tolerance: Option<Tolerance>,
+    /// The delta loop settings the tier overrides for itself and the
+    /// tiers below it; its tolerance is the one requested above, so a
+    /// tolerance set here is ignored
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub solver: SolverOverride,
+}
+
+/// Create a new tier
+#[must_use]
+pub fn new_tier(name: &str, parent: Option<Uuid>, tolerance: Option<Tolerance>) -> Tier {
+    Tier {
+        id: new_id(),
+        name: name.to_string(),
+        parent,
+        geometry: Vec::new(),
+        tolerance,
+        solver: SolverOverride::default(),
+    }
+}
+
+/// A move of geometry from one tier to another
+///
+/// Holds everything needed to replay or undo the move: only the geometry
+/// that moved, so undoing it never takes from the destination what it
+/// held before.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TierMove {
+    /// The geometry that moved
+    pub geometry: Vec<Uuid>,
+    /// The tier the geometry left
+    pub from: Uuid,
+    /// The tier the geometry joined
+    pub to: Uuid,
+}
+
+impl TierMove {
+    /// The move that undoes this one
+    #[must_use]
+    pub fn inverse(&self) -> TierMove {
+        TierMove {
+            geometry: self.geometry.clone(),
+            from: self.to,
+            to: self.from,
+        }
+    }
+}
+
+/// A registry of tiers
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TierRegistry {
+    /// Unique identifier for the registry
+    pub id: Uuid,
+    /// The tiers in the registry
+    pub tiers: HashMap<Uuid, Tier>,
+    /// Tolerance of root tiers that do not request their own
+    pub default_tolerance: Tolerance,
+}
+
+impl TierRegistry {
+    /// Create a new tier registry
+    #[must_use]
+    pub fn create_new() -> Self {
+        Self {
+            id: new_id(),
+            tiers: HashMap::new(),
+            default_tolerance: Tolerance::default(),
+        }
+    }
+
+    /// Declare, store, and return the ID of a tier
+    pub fn create_and_store(
+        &mut self,
+        name: &str,
+        parent: Option<Uuid>,
+        tolerance: Option<Tolerance>,
+    ) -> Uuid {
+        let tier = new_tier(name, parent, tolerance);
+        let id = tier.id;
+        self.tiers.insert(id, tier);
+        id
+    }
+
+    /// Remove a tier from the registry
+    pub fn remove(&mut self, id: &Uuid) {
+        self.tiers.remove(id);
+    }
+
+    /// Get a reference to a tier by ID
+    #[must_use]
+    pub fn get(&self, id: &Uuid) -> Option<&Tier> {
+        self.tiers.get(id)
+    }
+
+    /// Get a mutable reference to a tier by ID
+    pub fn get_mut(&mut self, id: &Uuid) -> Option<&mut Tier> {
+        self.tiers.get_mut(id)
+    }
+
+    /// The tiers in ID order
+    #[must_use]
+    pub fn sorted(&self) -> Vec<&Tier> {
+        sorted_by_id(&self.tiers)
+    }
+
+    /// The tier and its ancestors, nearest first
+    ///
+    /// Stops at a missing parent or a cycle.
+    #[must_use]
+    pub fn lineage(&self, id: &Uuid) -> Vec<Uuid> {
+        let mut lineage = Vec::new();
+        let mut current = self.tiers.get(id);
+        while let Some(tier) = current {
+            if lineage.contains(&tier.id) {
+                break;
+            }
+            lineage.push(tier.id);
+            current = tier.parent.and_then(|parent| self.tiers.get(&parent));
+        }
+        lineage
+    }
+
+    /// The tolerance a tier works to
+    ///
+    /// The outermost tier that requests a tolerance sets it; every tier
+    /// below can only tighten it. A tier with no requests in its lineage,
+    /// or a missing tier, gets the default tolerance.
+    #[must_use]
+    pub fn tolerance(&self, id: &Uuid) -> Tolerance {
+        self.lineage(id)
+            .iter()
+            .rev()
+            .filter_map(|tier_id| self.tiers.get(tier_id)?.tolerance)
+            .reduce(|resolved, requested| resolved.tightened(&requested))
+            .unwrap_or(self.default_tolerance)
+    }
+
+    /// The deepest tier holding a piece of geometry, the first by ID
+    /// among equally deep tiers
+    #[must_use]
+    pub fn tier_of(&self, geometry_id: &Uuid) -> Option<Uuid> {
+        self.tiers
+            .values()
+            .filter(|tier| tier.geometry.contains(geometry_id))
+            .max_by_key(|tier| (self.lineage(&tier.id).len(), std::cmp::Reverse(tier.id)))
+            .map(|tier| tier.id)
+    }
+
+    /// The tolerance that applies to a piece of geometry
+    ///
+    /// Geometry outside every tier gets the default tolerance.
+    #[must_use]
+    pub fn tolerance_for(&self, geometry_id: &Uuid) -> Tolerance {
+        self.tier_of(geometry_id)
+            .map_or(self.default_tolerance, |tier_id| self.tolerance(&tier_id))
+    }
+
+    /// Build the constraint context for solving a tier
+    ///
+    /// The parent's geometry becomes the boundary and its resolved
+    /// tolerance is passed down. The solver settings are overridden by
+    /// each tier from the root down. Returns None if the tier is missing.
+    #[must_use]
+    pub fn tier_context(&self, id: &Uuid, constraints: ConstraintSet) -> Option<TierContext> {
+        let tier = self.tiers.get(id)?;
+        let parent = tier.parent.and_then(|parent| self.tiers.get(&parent));
+        let context = TierContext::new(
+            constraints,
+            self.tolerance(id),
+            parent.map(|parent| parent.geometry.clone()),
+            parent.map(|parent| self.tolerance(&parent.id)),
+        );
+        Some(
+            self.lineage(id)
+                .iter()
+                .rev()
+                .filter_map(|tier_id| self.tiers.get(tier_id))
+                .fold(context, |context, tier| context.with_solver(&tier.solver)),
+        )
+    }
+}
+
+impl TierRegistry {
+    /// Promote geometry from a tier to its parent
+    ///
+    /// # Errors
+    /// Returns an error if the tier is missing or a root, or if the move
+    /// fails as described in [`TierRegistry::move_geometry`].
+    pub fn promote(
+        &mut self,
+        geometry_registry: &GeometryRegistry,
+        geometry: &[Uuid],
+        from: &Uuid,
+    ) -> Result<TierMove, ConstraintError> {
+        let parent = self
+            .tiers
+            .get(from)
+            .and_then(|tier| tier.parent)
+            .ok_or_else(|| ConstraintError::InvalidConfiguration {
+                message: format!("Tier {from} has no parent to promote into"),
+            })?;
+        self.move_geometry(geometry_registry, geometry, from, &parent)
+    }
+
+    /// Demote geometry from a tier to one of its descendants
+    ///
+    /// # Errors
+    /// Returns an error if `to` does not descend from `from`, or if the
+    /// move fails as described in [`TierRegistry::move_geometry`].
+    pub fn demote(
+        &mut self,
+        geometry_registry: &GeometryRegistry,
+        geometry: &[Uuid],
+        from: &Uuid,
+        to: &Uuid,
+    ) -> Result<TierMove, ConstraintError> {
+        if from == to || !self.lineage(to).contains(from) {
+            return Err(ConstraintError::InvalidConfiguration {
+                message: format!("Tier {to} does not descend from tier {from}"),
+            });
+        }
+        self.move_geometry(geometry_registry, geometry, from, to)
+    }
+
+    /// Move geometry between tiers and re-check boundaries
+    ///
+    /// Geometry the destination already holds is left where it is. The
+    /// rest must lie within the destination's parent boundary, and the
+    /// source tier's children must still lie within what the source keeps.
+    /// On failure the membership is restored. The returned move can be kept
+    /// in the undo history and undone with [`TierRegistry::undo_move`].
+    ///
+    /// # Errors
+    /// Returns `InvalidConfiguration` if either tier is missing,
+    /// `GeometryNotFound` if a piece of geometry is not in the source tier,
+    /// and `BoundaryViolation` if the move breaks a tier boundary.
+    pub fn move_geometry(
+        &mut self,
+        geometry_registry: &GeometryRegistry,
+        geometry: &[Uuid],
+        from: &Uuid,
+        to: &Uuid,
+    ) -> Result<TierMove, ConstraintError> {
+        let source = self.tiers.get(from).ok_or_else(|| missing_tier(from))?;
+        let destination = self.tiers.get(to).ok_or_else(|| missing_tier(to))?;
+        if let Some(stray) = geometry.iter().find(|id| !source.geometry.contains(id)) {
+            return Err(ConstraintError::GeometryNotFound {
+                geometry_id: *stray,
+            });
+        }
+
+        let record = TierMove {
+            geometry: geometry
+                .iter()
+                .filter(|id| !destination.geometry.contains(id))
+                .copied()
+                .collect(),
+            from: *from,
+            to: *to,
+        };
+        self.apply_move(&record);
+        if let Err(error) = self.check_move(geometry_registry, &record) {
+            self.undo_move(&record);
+            return Err(error);
+        }
+        Ok(record)
+    }
+
+    /// Rewrite tier membership for a move without checking boundaries
+    pub fn apply_move(&mut self, record: &TierMove) {
+        tracing::debug!(
+            from = %record.from,
+            to = %record.to,
+            geometry = record.geometry.len(),
+            "moving geometry between tiers"
+        );
+        if let Some(source) = self.tiers.get_mut(&record.from) {
+            source.geometry.retain(|id| !record.geometry.contains(id));
+        }
+        if let Some(destination) = self.tiers.get_mut(&record.to) {
+            for id in &record.geometry {
+                if !destination.geometry.contains(id) {
+                    destination.geometry.push(*id);
+                }
+            }
+        }
+    }
+
+    /// Undo a recorded move
+    pub fn undo_move(&mut self, record: &TierMove) {
+        self.apply_move(&record.inverse());
+    }
+
+    /// Re-evaluate the boundary constraints a move affects
+    fn check_move(
+        &self,
+        geometry_registry: &GeometryRegistry,
+        record: &TierMove,
+    ) -> Result<(), ConstraintError> {
+        let mut affected = vec![(record.to, record.geometry.clone())];
+        affected.extend(
+            self.sorted()
+                .into_iter()
+                .filter(|tier| tier.parent == Some(record.from) && !tier.geometry.is_empty())
+                .map(|tier| (tier.id, tier.geometry.clone())),
+        );
+        for (tier_id, geometry) in affected {
+            if let Some(context) = self.tier_context(&tier_id, ConstraintSet::default()) {
+                apply_boundary(geometry_registry, &context, &geometry)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Error for a tier missing from the registry
+fn missing_tier(id: &Uuid) -> ConstraintError {
+    ConstraintError::InvalidConfiguration {
+        message: format!("Tier not found: {id}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn undoing_a_move_keeps_what_the_destination_held() {
+        let geometry = GeometryRegistry::create_new();
+        let mut tiers = TierRegistry::create_new();
+        let from = tiers.create_and_store("Sketch", None, None);
+        let to = tiers.create_and_store("Building", None, None);
+        let (shared, moved) = (new_id(), new_id());
+        tiers.get_mut(&from).unwrap().geometry = vec![shared, moved];
+        tiers.get_mut(&to).unwrap().geometry = vec![shared];
+
+        let record = tiers
+            .move_geometry(&geometry, &[shared, moved], &from, &to)
+            .unwrap();
+        assert_eq!(record.geometry, vec![moved]);
+        assert_eq!(tiers.get(&from).unwrap().geometry, vec![shared]);
+        assert_eq!(tiers.get(&to).unwrap().geometry, vec![shared, moved]);
+
+        tiers.undo_move(&record);
+        assert_eq!(tiers.get(&from).unwrap().geometry, vec![shared, moved]);
+        assert_eq!(tiers.get(&to).unwrap().geometry, vec![shared]);
+    }
+}
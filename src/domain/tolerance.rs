@@ -0,0 +1,90 @@
+//! Geometric tolerances
+//!
+//! A tier's tolerance bundles every threshold that geometry code compares
+//! against: coincidence, parallelism, snapping and solver convergence, plus
+//! the minimum feature sizes derived from them. Code that needs a threshold
+//! takes a resolved `Tolerance` rather than a loose f32.
+
+/// Linear tolerance in meters used when nothing else is specified
+pub const DEFAULT_LINEAR_TOLERANCE: f32 = 0.001;
+
+/// Ratio between a tier's linear tolerance and its minimum feature size
+pub const MIN_FEATURE_FACTOR: f32 = 10.0;
+
+/// Ratio between a tier's linear tolerance and its solver convergence threshold
+pub const CONVERGENCE_FACTOR: f32 = 0.1;
+
+/// The thresholds a tier works to
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Tolerance {
+    /// Distance in meters within which points coincide and lie on a plane
+    pub linear: f32,
+    /// Angle in radians within which directions are parallel
+    pub angular: f32,
+    /// Radius in meters within which a point snaps to an existing vertex
+    pub snap: f32,
+    /// Largest vertex move in meters at which the solver has converged
+    pub convergence: f32,
+}
+
+impl Default for Tolerance {
+    fn default() -> Self {
+        Self::from_linear(DEFAULT_LINEAR_TOLERANCE)
+    }
+}
+
+impl Tolerance {
+    /// Derive a full tolerance from a linear tolerance
+    ///
+    /// Snapping reaches one minimum feature, convergence is a tenth of the
+    /// linear tolerance, and the angular tolerance is the angle subtended
+    /// by the linear tolerance over one meter.
+    #[must_use]
+    pub fn from_linear(linear: f32) -> Self {
+        Self {
+            linear,
+            angular: linear.atan(),
+            snap: linear * MIN_FEATURE_FACTOR,
+            convergence: linear * CONVERGENCE_FACTOR,
+        }
+    }
+
+    /// This tolerance tightened by a child's request
+    ///
+    /// Each threshold is the smaller of the two, so a child can never be
+    /// looser than its parent.
+    #[must_use]
+    pub fn tightened(&self, requested: &Tolerance) -> Tolerance {
+        Tolerance {
+            linear: self.linear.min(requested.linear),
+            angular: self.angular.min(requested.angular),
+            snap: self.snap.min(requested.snap),
+            convergence: self.convergence.min(requested.convergence),
+        }
+    }
+
+    /// Smallest feature in meters the tier can represent reliably
+    #[must_use]
+    pub fn min_feature(&self) -> f32 {
+        self.linear * MIN_FEATURE_FACTOR
+    }
+
+    /// Shortest segment in meters before it counts as degenerate
+    #[must_use]
+    pub fn min_segment_length(&self) -> f32 {
+        self.min_feature()
+    }
+
+    /// Thinnest polygon in meters before it counts as a sliver
+    #[must_use]
+    pub fn sliver_thickness(&self) -> f32 {
+        self.min_feature() / 2.0
+    }
+
+    /// Smallest triangle area in square meters before it counts as degenerate
+    #[must_use]
+    pub fn min_triangle_area(&self) -> f32 {
+        self.min_feature() * self.min_feature() / 10.0
+    }
+}
@@ -0,0 +1,242 @@
+/// Euler operators
+///
+/// Local topology edits that keep V - E + F unchanged. Each operator edits
+/// the half-edge mesh and the registries together, so the two stay in sync
+/// without a rebuild: new vertices, segments and polygons are created in
+/// the registries and referenced by ID from the mesh.
+use crate::domain::topology::half_edge::{HalfEdge, HalfEdgeMesh};
+use crate::domain::{new_id, GeometryRegistry, Point};
+use uuid::Uuid;
+
+impl HalfEdgeMesh {
+    /// Split an edge by inserting a vertex at `position` (make edge, vertex)
+    ///
+    /// The edge's segment is replaced by two segments through the new
+    /// vertex, on both faces that share it. Returns the new vertex ID, or
+    /// None if the half-edge is not in the mesh.
+    pub fn split_edge(
+        &mut self,
+        geometry_registry: &mut GeometryRegistry,
+        half_edge_id: &Uuid,
+        position: Point,
+    ) -> Option<Uuid> {
+        let half_edge = self.half_edges.get(half_edge_id)?.clone();
+        let target = self.target(half_edge_id)?;
+        let old_segment = half_edge.segment;
+
+        let middle = geometry_registry.vertices.create_and_store(position);
+        let first_segment = geometry_registry
+            .segments
+            .create_and_store(&half_edge.origin, &middle);
+        let second_segment = geometry_registry
+            .segments
+            .create_and_store(&middle, &target);
+
+        // origin -> middle keeps the half-edge; middle -> target is new
+        let second_id = new_id();
+        self.half_edges.insert(
+            second_id,
+            HalfEdge {
+                id: second_id,
+                origin: middle,
+                twin: half_edge.twin,
+                next: half_edge.next,
+                prev: half_edge.id,
+                face: half_edge.face,
+                segment: second_segment,
+            },
+        );
+        self.relink(&half_edge.next, |next| next.prev = second_id);
+        self.relink(half_edge_id, |first| {
+            first.next = second_id;
+            first.segment = first_segment;
+        });
+        self.vertex_half_edges.insert(middle, second_id);
+
+        // The twin is split the same way: target -> middle, middle -> origin
+        let mut faces = vec![half_edge.face];
+        if let Some(twin_id) = half_edge.twin {
+            let twin = self.half_edges.get(&twin_id)?.clone();
+            let twin_second_id = new_id();
+            self.half_edges.insert(
+                twin_second_id,
+                HalfEdge {
+                    id: twin_second_id,
+                    origin: middle,
+                    twin: Some(half_edge.id),
+                    next: twin.next,
+                    prev: twin.id,
+                    face: twin.face,
+                    segment: first_segment,
+                },
+            );
+            self.relink(&twin.next, |next| next.prev = twin_second_id);
+            self.relink(&twin_id, |first| {
+                first.next = twin_second_id;
+                first.twin = Some(second_id);
+                first.segment = second_segment;
+            });
+            self.relink(half_edge_id, |first| first.twin = Some(twin_second_id));
+            faces.push(twin.face);
+        }
+
+        geometry_registry.segments.remove(&old_segment);
+        for face in &faces {
+            self.sync_face(geometry_registry, face);
+        }
+        Some(middle)
+    }
+
+    /// Split a face along a new edge between two of its vertices (make edge, face)
+    ///
+    /// The part of the face from `from` round to `to` keeps the original
+    /// polygon; the rest becomes a new polygon with the same plane, added
+    /// to every solid that holds the original. Returns the new polygon ID,
+    /// or None if either vertex is not on the face or they are adjacent.
+    pub fn split_face(
+        &mut self,
+        geometry_registry: &mut GeometryRegistry,
+        face_id: &Uuid,
+        from: &Uuid,
+        to: &Uuid,
+    ) -> Option<Uuid> {
+        let face_loop = self.face_half_edge_loop(face_id);
+        let find = |vertex: &Uuid| {
+            face_loop
+                .iter()
+                .copied()
+                .find(|id| self.half_edges.get(id).is_some_and(|h| h.origin == *vertex))
+        };
+        let (leaving_from, leaving_to) = (find(from)?, find(to)?);
+        let from_prev = self.half_edges.get(&leaving_from)?.prev;
+        let to_prev = self.half_edges.get(&leaving_to)?.prev;
+        if from == to || from_prev == leaving_to || to_prev == leaving_from {
+            return None;
+        }
+
+        // The new face runs from `to` round to `from`, closed by the new edge
+        let segment = geometry_registry.segments.create_and_store(from, to);
+        let mut new_segments = Vec::new();
+        let mut current = leaving_to;
+        while current != leaving_from {
+            let half_edge = self.half_edges.get(&current)?;
+            new_segments.push(half_edge.segment);
+            current = half_edge.next;
+        }
+        new_segments.push(segment);
+        let new_face = geometry_registry
+            .polygons
+            .create_and_store(new_segments.iter().collect());
+        let plane = geometry_registry
+            .polygons
+            .get(face_id)
+            .and_then(|original| original.plane.clone());
+        if let Some(polygon) = geometry_registry.polygons.get_mut(&new_face) {
+            polygon.plane = plane;
+        }
+        for solid in geometry_registry.solids.solids.values_mut() {
+            if solid.polygons.contains(face_id) {
+                solid.polygons.push(new_face);
+            }
+        }
+
+        let (closing_id, opening_id) = (new_id(), new_id());
+        // to -> from closes the original face's part, from -> to the new face's
+        self.half_edges.insert(
+            closing_id,
+            HalfEdge {
+                id: closing_id,
+                origin: *to,
+                twin: Some(opening_id),
+                next: leaving_from,
+                prev: to_prev,
+                face: *face_id,
+                segment,
+            },
+        );
+        self.half_edges.insert(
+            opening_id,
+            HalfEdge {
+                id: opening_id,
+                origin: *from,
+                twin: Some(closing_id),
+                next: leaving_to,
+                prev: from_prev,
+                face: new_face,
+                segment,
+            },
+        );
+        self.relink(&to_prev, |h| h.next = closing_id);
+        self.relink(&leaving_from, |h| h.prev = closing_id);
+        self.relink(&from_prev, |h| h.next = opening_id);
+        self.relink(&leaving_to, |h| h.prev = opening_id);
+        self.face_half_edges.insert(*face_id, leaving_from);
+        self.face_half_edges.insert(new_face, opening_id);
+        for id in self.face_half_edge_loop(&new_face) {
+            self.relink(&id, |h| h.face = new_face);
+        }
+
+        self.sync_face(geometry_registry, face_id);
+        Some(new_face)
+    }
+
+    /// Remove an edge between two faces and merge them (kill edge, face)
+    ///
+    /// The twin's face is absorbed into the half-edge's face and removed
+    /// from the registry and from every solid. Returns false if the edge is
+    /// on a boundary, both sides belong to the same face, or an endpoint
+    /// would be left dangling.
+    pub fn join_faces(
+        &mut self,
+        geometry_registry: &mut GeometryRegistry,
+        half_edge_id: &Uuid,
+    ) -> bool {
+        let Some(half_edge) = self.half_edges.get(half_edge_id).cloned() else {
+            return false;
+        };
+        let Some(twin) = half_edge
+            .twin
+            .and_then(|id| self.half_edges.get(&id))
+            .cloned()
+        else {
+            return false;
+        };
+        if half_edge.face == twin.face || half_edge.next == twin.id || twin.next == half_edge.id {
+            return false;
+        }
+
+        // Stitch the two loops together around the removed edge
+        for id in self.face_half_edge_loop(&twin.face) {
+            self.relink(&id, |h| h.face = half_edge.face);
+        }
+        self.relink(&half_edge.prev, |h| h.next = twin.next);
+        self.relink(&twin.next, |h| h.prev = half_edge.prev);
+        self.relink(&twin.prev, |h| h.next = half_edge.next);
+        self.relink(&half_edge.next, |h| h.prev = twin.prev);
+        self.half_edges.remove(&half_edge.id);
+        self.half_edges.remove(&twin.id);
+        if self.vertex_half_edges.get(&half_edge.origin) == Some(&half_edge.id) {
+            self.vertex_half_edges.insert(half_edge.origin, twin.next);
+        }
+        if self.vertex_half_edges.get(&twin.origin) == Some(&twin.id) {
+            self.vertex_half_edges.insert(twin.origin, half_edge.next);
+        }
+        self.face_half_edges.insert(half_edge.face, half_edge.next);
+        self.face_half_edges.remove(&twin.face);
+
+        geometry_registry.segments.remove(&half_edge.segment);
+        geometry_registry.polygons.remove(&twin.face);
+        for solid in geometry_registry.solids.solids.values_mut() {
+            solid.polygons.retain(|id| *id != twin.face);
+        }
+        self.sync_face(geometry_registry, &half_edge.face);
+        true
+    }
+
+    /// Apply an edit to a half-edge if it exists
+    fn relink(&mut self, id: &Uuid, edit: impl FnOnce(&mut HalfEdge)) {
+        if let Some(half_edge) = self.half_edges.get_mut(id) {
+            edit(half_edge);
+        }
+    }
+}
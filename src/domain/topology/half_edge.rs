@@ -0,0 +1,379 @@
+/// Half-edge mesh
+///
+/// A topological index over a set of polygons. Every polygon's vertex loop
+/// becomes a cycle of half-edges; half-edges walking the same segment in
+/// opposite directions are twins. Geometry stays in the registries: the
+/// mesh only stores IDs, so it is rebuilt or updated alongside them.
+use crate::domain::{new_id, GeometryRegistry};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// Errors that prevent building a half-edge mesh
+#[derive(Debug, Clone, PartialEq)]
+pub enum TopologyError {
+    /// The solid is not in the registry
+    SolidNotFound {
+        /// The missing solid ID
+        solid_id: Uuid,
+    },
+    /// A polygon is missing or its segments do not form a single closed loop
+    BrokenPolygon {
+        /// The polygon that could not be walked
+        polygon_id: Uuid,
+    },
+    /// An edge is shared by more than two polygons
+    NonManifoldEdge {
+        /// The over-shared segment
+        segment_id: Uuid,
+    },
+    /// Two polygons walk a shared edge in the same direction
+    InconsistentOrientation {
+        /// The segment walked twice in one direction
+        segment_id: Uuid,
+    },
+}
+
+impl std::fmt::Display for TopologyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TopologyError::SolidNotFound { solid_id } => {
+                write!(f, "Solid not found: {solid_id}")
+            }
+            TopologyError::BrokenPolygon { polygon_id } => {
+                write!(f, "Polygon {polygon_id} does not form a closed loop")
+            }
+            TopologyError::NonManifoldEdge { segment_id } => {
+                write!(f, "Segment {segment_id} is shared by more than two faces")
+            }
+            TopologyError::InconsistentOrientation { segment_id } => {
+                write!(
+                    f,
+                    "Faces meeting at segment {segment_id} walk it in the same direction"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for TopologyError {}
+
+/// One directed side of an edge, belonging to a single face
+#[derive(Debug, Clone)]
+pub struct HalfEdge {
+    /// The unique identifier of the half-edge
+    pub id: Uuid,
+    /// The vertex the half-edge starts at
+    pub origin: Uuid,
+    /// The opposite half-edge on the neighbouring face, None on a boundary
+    pub twin: Option<Uuid>,
+    /// The next half-edge around the face
+    pub next: Uuid,
+    /// The previous half-edge around the face
+    pub prev: Uuid,
+    /// The polygon the half-edge bounds
+    pub face: Uuid,
+    /// The segment the half-edge runs along
+    pub segment: Uuid,
+}
+
+/// Half-edge connectivity of a set of polygons
+///
+/// Faces keep the winding of their polygons, so a mesh built from an
+/// oriented solid stays consistently oriented.
+#[derive(Debug, Clone, Default)]
+pub struct HalfEdgeMesh {
+    /// The half-edges by ID
+    pub half_edges: HashMap<Uuid, HalfEdge>,
+    /// One outgoing half-edge for each vertex
+    pub vertex_half_edges: HashMap<Uuid, Uuid>,
+    /// One half-edge on each face, keyed by polygon ID
+    pub face_half_edges: HashMap<Uuid, Uuid>,
+}
+
+impl HalfEdgeMesh {
+    /// Build the mesh of every polygon in a solid
+    ///
+    /// # Errors
+    /// Returns an error if the solid is missing or its polygons do not form
+    /// a consistently wound manifold. Run the orientation repair first on
+    /// imported solids.
+    pub fn from_solid(
+        geometry_registry: &GeometryRegistry,
+        solid_id: &Uuid,
+    ) -> Result<Self, TopologyError> {
+        let solid = geometry_registry
+            .solids
+            .get(solid_id)
+            .ok_or(TopologyError::SolidNotFound {
+                solid_id: *solid_id,
+            })?;
+        Self::from_polygons(geometry_registry, &solid.polygons)
+    }
+
+    /// Build the mesh of a set of polygons, following their stored winding
+    ///
+    /// # Errors
+    /// Returns an error if a polygon is broken, an edge is shared by more
+    /// than two polygons, or two polygons walk an edge the same way.
+    pub fn from_polygons(
+        geometry_registry: &GeometryRegistry,
+        polygon_ids: &[Uuid],
+    ) -> Result<Self, TopologyError> {
+        let mut mesh = Self::default();
+        // Directed edge (from, to) -> half-edge, for twin matching
+        let mut directed: HashMap<(Uuid, Uuid), Uuid> = HashMap::new();
+        let mut edge_uses: HashMap<Uuid, usize> = HashMap::new();
+
+        for polygon_id in polygon_ids {
+            let broken = TopologyError::BrokenPolygon {
+                polygon_id: *polygon_id,
+            };
+            let vertex_loop = geometry_registry
+                .polygons
+                .get(polygon_id)
+                .and_then(|polygon| polygon.vertex_loop(&geometry_registry.segments))
+                .ok_or(broken.clone())?;
+            let segment_ids =
+                loop_segments(geometry_registry, polygon_id, &vertex_loop).ok_or(broken)?;
+
+            let ids: Vec<Uuid> = vertex_loop.iter().map(|_| new_id()).collect();
+            let count = ids.len();
+            for (index, origin) in vertex_loop.iter().enumerate() {
+                let target = vertex_loop[(index + 1) % count];
+                let segment_id = segment_ids[index];
+
+                let uses = edge_uses.entry(segment_id).or_default();
+                *uses += 1;
+                if *uses > 2 {
+                    return Err(TopologyError::NonManifoldEdge { segment_id });
+                }
+                if directed.insert((*origin, target), ids[index]).is_some() {
+                    return Err(TopologyError::InconsistentOrientation { segment_id });
+                }
+
+                mesh.half_edges.insert(
+                    ids[index],
+                    HalfEdge {
+                        id: ids[index],
+                        origin: *origin,
+                        twin: None,
+                        next: ids[(index + 1) % count],
+                        prev: ids[(index + count - 1) % count],
+                        face: *polygon_id,
+                        segment: segment_id,
+                    },
+                );
+                mesh.vertex_half_edges.entry(*origin).or_insert(ids[index]);
+            }
+            mesh.face_half_edges.insert(*polygon_id, ids[0]);
+        }
+
+        // Pair each half-edge with the one walking its edge the other way
+        for ((from, to), id) in &directed {
+            if let Some(twin) = directed.get(&(*to, *from)) {
+                if let Some(half_edge) = mesh.half_edges.get_mut(id) {
+                    half_edge.twin = Some(*twin);
+                }
+            }
+        }
+        Ok(mesh)
+    }
+
+    /// Get a half-edge by ID
+    #[must_use]
+    pub fn get(&self, id: &Uuid) -> Option<&HalfEdge> {
+        self.half_edges.get(id)
+    }
+
+    /// The vertex a half-edge ends at
+    #[must_use]
+    pub fn target(&self, id: &Uuid) -> Option<Uuid> {
+        let half_edge = self.half_edges.get(id)?;
+        Some(self.half_edges.get(&half_edge.next)?.origin)
+    }
+
+    /// The half-edges around a face, in winding order
+    #[must_use]
+    pub fn face_half_edge_loop(&self, face_id: &Uuid) -> Vec<Uuid> {
+        let Some(&start) = self.face_half_edges.get(face_id) else {
+            return Vec::new();
+        };
+        let mut loop_ids = Vec::new();
+        let mut current = start;
+        loop {
+            loop_ids.push(current);
+            let Some(half_edge) = self.half_edges.get(&current) else {
+                break;
+            };
+            current = half_edge.next;
+            if current == start || loop_ids.len() > self.half_edges.len() {
+                break;
+            }
+        }
+        loop_ids
+    }
+
+    /// The vertices around a face, in winding order
+    #[must_use]
+    pub fn face_vertices(&self, face_id: &Uuid) -> Vec<Uuid> {
+        self.face_half_edge_loop(face_id)
+            .iter()
+            .filter_map(|id| self.half_edges.get(id))
+            .map(|half_edge| half_edge.origin)
+            .collect()
+    }
+
+    /// The faces sharing an edge with a face
+    #[must_use]
+    pub fn face_neighbours(&self, face_id: &Uuid) -> Vec<Uuid> {
+        self.face_half_edge_loop(face_id)
+            .iter()
+            .filter_map(|id| self.half_edges.get(id)?.twin)
+            .filter_map(|twin| self.half_edges.get(&twin))
+            .map(|half_edge| half_edge.face)
+            .collect()
+    }
+
+    /// The half-edges leaving a vertex, rotating across twins
+    ///
+    /// On a boundary vertex the rotation is completed from the other side,
+    /// so every outgoing half-edge is listed once.
+    #[must_use]
+    pub fn outgoing(&self, vertex_id: &Uuid) -> Vec<Uuid> {
+        let Some(&start) = self.vertex_half_edges.get(vertex_id) else {
+            return Vec::new();
+        };
+        let mut outgoing = vec![start];
+        // Rotate one way: the twin of the incoming half-edge leaves the vertex
+        let mut current = start;
+        while let Some(next) = self
+            .half_edges
+            .get(&current)
+            .and_then(|half_edge| self.half_edges.get(&half_edge.prev)?.twin)
+        {
+            if next == start || outgoing.len() > self.half_edges.len() {
+                return outgoing;
+            }
+            outgoing.push(next);
+            current = next;
+        }
+        // Hit a boundary: rotate the other way from the start
+        current = start;
+        while let Some(next) = self
+            .half_edges
+            .get(&current)
+            .and_then(|half_edge| half_edge.twin)
+            .and_then(|twin| self.half_edges.get(&twin))
+            .map(|twin| twin.next)
+        {
+            if outgoing.contains(&next) {
+                break;
+            }
+            outgoing.push(next);
+            current = next;
+        }
+        outgoing
+    }
+
+    /// The vertices joined to a vertex by an edge
+    #[must_use]
+    pub fn vertex_neighbours(&self, vertex_id: &Uuid) -> Vec<Uuid> {
+        let mut neighbours: Vec<Uuid> = Vec::new();
+        for id in self.outgoing(vertex_id) {
+            // The previous half-edge's origin covers the last edge on a boundary
+            let previous = self
+                .half_edges
+                .get(&id)
+                .and_then(|half_edge| self.half_edges.get(&half_edge.prev))
+                .map(|half_edge| half_edge.origin);
+            for neighbour in [self.target(&id), previous].into_iter().flatten() {
+                if !neighbours.contains(&neighbour) {
+                    neighbours.push(neighbour);
+                }
+            }
+        }
+        neighbours
+    }
+
+    /// The faces around a vertex
+    #[must_use]
+    pub fn vertex_faces(&self, vertex_id: &Uuid) -> Vec<Uuid> {
+        self.outgoing(vertex_id)
+            .iter()
+            .filter_map(|id| self.half_edges.get(id))
+            .map(|half_edge| half_edge.face)
+            .collect()
+    }
+
+    /// Half-edges with no twin, lying on an open boundary
+    #[must_use]
+    pub fn boundary_half_edges(&self) -> Vec<Uuid> {
+        self.half_edges
+            .values()
+            .filter(|half_edge| half_edge.twin.is_none())
+            .map(|half_edge| half_edge.id)
+            .collect()
+    }
+
+    /// Check whether every edge is shared by exactly two faces
+    #[must_use]
+    pub fn is_closed(&self) -> bool {
+        self.half_edges
+            .values()
+            .all(|half_edge| half_edge.twin.is_some())
+    }
+
+    /// Euler characteristic V - E + F of the mesh
+    ///
+    /// A closed mesh of genus g has characteristic 2 - 2g, so a closed
+    /// mesh with characteristic 2 is topologically a sphere.
+    #[must_use]
+    pub fn euler_characteristic(&self) -> i64 {
+        let vertices = self.vertex_half_edges.len();
+        let edges = self
+            .half_edges
+            .values()
+            .filter(|half_edge| half_edge.twin.is_none_or(|twin| half_edge.id < twin))
+            .count();
+        let faces = self.face_half_edges.len();
+        i64::try_from(vertices + faces).unwrap_or(i64::MAX)
+            - i64::try_from(edges).unwrap_or(i64::MAX)
+    }
+
+    /// Rewrite a face's polygon segments in the mesh's winding order
+    ///
+    /// Keeps the registry's stored winding in step with the mesh after an
+    /// operator has changed the face's boundary.
+    pub fn sync_face(&self, geometry_registry: &mut GeometryRegistry, face_id: &Uuid) {
+        let segments: Vec<Uuid> = self
+            .face_half_edge_loop(face_id)
+            .iter()
+            .filter_map(|id| self.half_edges.get(id))
+            .map(|half_edge| half_edge.segment)
+            .collect();
+        if let Some(polygon) = geometry_registry.polygons.get_mut(face_id) {
+            polygon.segments = segments;
+        }
+    }
+}
+
+/// The segment joining each consecutive pair in a polygon's vertex loop
+fn loop_segments(
+    geometry_registry: &GeometryRegistry,
+    polygon_id: &Uuid,
+    vertex_loop: &[Uuid],
+) -> Option<Vec<Uuid>> {
+    let polygon = geometry_registry.polygons.get(polygon_id)?;
+    vertex_loop
+        .iter()
+        .enumerate()
+        .map(|(index, vertex_id)| {
+            let next_id = &vertex_loop[(index + 1) % vertex_loop.len()];
+            polygon.segments.iter().copied().find(|id| {
+                geometry_registry.segments.get(id).is_some_and(|segment| {
+                    segment.contains_vertex(vertex_id) && segment.contains_vertex(next_id)
+                })
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,16 @@
+//! Half-edge topology
+//!
+//! An optional half-edge index derived from the registries, giving
+//! constant-time adjacency queries and the Euler operators that booleans,
+//! fillets and push/pull are built from.
+//!
+//! The registries remain the source of truth. A mesh is built from a solid
+//! or a set of polygons, and the operators keep both in sync as they edit.
+
+/// Half-edge mesh and adjacency queries
+pub mod half_edge;
+
+/// Euler operators over the mesh and registries
+pub mod euler;
+
+pub use half_edge::*;
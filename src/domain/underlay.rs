@@ -0,0 +1,156 @@
+/// Define the Underlay type and its registry
+///
+/// An underlay is a scanned drawing laid flat on a horizontal work plane
+/// for tracing. Its top-left pixel sits at the origin; pixel columns run
+/// along the underlay's X axis and rows along its Z axis, both scaled by
+/// meters per pixel and turned about the vertical by the rotation.
+use std::collections::HashMap;
+use std::path::PathBuf;
+use uuid::Uuid;
+
+use crate::domain::{new_id, Point};
+
+/// Where an underlay's picture comes from
+#[derive(Debug, Clone, PartialEq)]
+pub enum UnderlaySource {
+    /// A raster image file
+    Image(PathBuf),
+    /// One page of a PDF file, numbered from 1
+    PdfPage {
+        /// The PDF file
+        path: PathBuf,
+        /// The page shown
+        page: u32,
+    },
+}
+
+/// A scaled drawing on a work plane
+#[derive(Debug, Clone)]
+pub struct Underlay {
+    /// The unique identifier of the underlay
+    pub id: Uuid,
+    /// The picture shown
+    pub source: UnderlaySource,
+    /// Width and height of the picture in pixels
+    pub pixel_size: (u32, u32),
+    /// Model position of the top-left pixel; its Y is the work plane
+    pub origin: Point,
+    /// Model length of one pixel in meters
+    pub meters_per_pixel: f32,
+    /// Turn about the vertical in radians, counter-clockwise seen from above
+    pub rotation: f32,
+    /// Opacity from 0 (invisible) to 1 (opaque)
+    pub opacity: f32,
+}
+
+/// Create a new underlay with its top-left pixel at `origin`
+#[must_use]
+pub fn new_underlay(
+    source: UnderlaySource,
+    pixel_size: (u32, u32),
+    origin: Point,
+    meters_per_pixel: f32,
+) -> Underlay {
+    Underlay {
+        id: new_id(),
+        source,
+        pixel_size,
+        origin,
+        meters_per_pixel,
+        rotation: 0.0,
+        opacity: 0.5,
+    }
+}
+
+impl Underlay {
+    /// Model position of a pixel
+    #[must_use]
+    pub fn pixel_to_model(&self, column: f32, row: f32) -> Point {
+        let (along, across) = (column * self.meters_per_pixel, row * self.meters_per_pixel);
+        let (sin, cos) = self.rotation.sin_cos();
+        Point {
+            x: self.origin.x + along * cos + across * sin,
+            y: self.origin.y,
+            z: self.origin.z - along * sin + across * cos,
+        }
+    }
+
+    /// Model width and depth of the whole picture
+    #[must_use]
+    pub fn model_size(&self) -> (f32, f32) {
+        #[allow(clippy::cast_precision_loss)]
+        let (width, height) = (self.pixel_size.0 as f32, self.pixel_size.1 as f32);
+        (
+            width * self.meters_per_pixel,
+            height * self.meters_per_pixel,
+        )
+    }
+
+    /// Model position of the picture's center
+    #[must_use]
+    pub fn center(&self) -> Point {
+        #[allow(clippy::cast_precision_loss)]
+        let (width, height) = (self.pixel_size.0 as f32, self.pixel_size.1 as f32);
+        self.pixel_to_model(width / 2.0, height / 2.0)
+    }
+
+    /// Rescale so two picked points lie `distance` meters apart
+    ///
+    /// The points are picked on the work plane; only their plan distance
+    /// counts. The underlay is scaled about the first point, which stays
+    /// where it is. Returns false and leaves the underlay unchanged if the
+    /// points coincide or the distance is not positive.
+    pub fn calibrate(&mut self, first: &Point, second: &Point, distance: f32) -> bool {
+        let measured = (second.x - first.x).hypot(second.z - first.z);
+        if measured <= f32::EPSILON || distance <= 0.0 {
+            return false;
+        }
+        let factor = distance / measured;
+        self.meters_per_pixel *= factor;
+        self.origin.x = first.x + (self.origin.x - first.x) * factor;
+        self.origin.z = first.z + (self.origin.z - first.z) * factor;
+        true
+    }
+}
+
+/// A registry of underlays
+pub struct UnderlayRegistry {
+    /// Unique identifier for the registry
+    pub id: Uuid,
+    /// The underlays in the registry
+    pub underlays: HashMap<Uuid, Underlay>,
+}
+
+impl UnderlayRegistry {
+    /// Create a new underlay registry
+    #[must_use]
+    pub fn create_new() -> Self {
+        Self {
+            id: new_id(),
+            underlays: HashMap::new(),
+        }
+    }
+
+    /// Store an underlay and return its ID
+    pub fn store(&mut self, underlay: Underlay) -> Uuid {
+        let id = underlay.id;
+        self.underlays.insert(id, underlay);
+        id
+    }
+
+    /// Remove an underlay from the registry
+    pub fn remove(&mut self, id: &Uuid) {
+        self.underlays.remove(id);
+    }
+
+    /// Get a reference to an underlay by ID
+    #[must_use]
+    pub fn get(&self, id: &Uuid) -> Option<&Underlay> {
+        self.underlays.get(id)
+    }
+
+    /// Get a mutable reference to an underlay by ID
+    pub fn get_mut(&mut self, id: &Uuid) -> Option<&mut Underlay> {
+        self.underlays.get_mut(id)
+    }
+}
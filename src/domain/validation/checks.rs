@@ -0,0 +1,308 @@
+/// Individual geometry checks
+///
+/// Each check is a pure function that inspects the registry and returns
+/// the issues it found at the requested severity.
+use crate::domain::geometry::{
+    fan_triangles, fit_plane, newell_normal, polygon_area, polygon_perimeter, triangle_area,
+};
+use crate::domain::validation::report::{IssueKind, Severity, ValidationIssue};
+use crate::domain::{measure_vector, GeometryRegistry, Point};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// Check whether a set of points lies on a common plane
+///
+/// Fits a least-squares plane to all the points; every point must lie
+/// within `tolerance` of it. Collinear point sets and fewer than four
+/// points are always coplanar.
+#[must_use]
+pub fn validate_coplanar_vertices(points: &[Point], tolerance: f32) -> bool {
+    plane_deviation(points) <= tolerance
+}
+
+/// Largest distance of any point from the best-fit plane of all the points
+fn plane_deviation(points: &[Point]) -> f32 {
+    let Some((normal, center, _)) = fit_plane(points) else {
+        return 0.0;
+    };
+    points
+        .iter()
+        .map(|point| measure_vector(&center, point).dot(&normal).abs())
+        .fold(0.0, f32::max)
+}
+
+/// Flag polygons whose vertices deviate from their plane by more than `tolerance`
+#[must_use]
+pub fn check_non_planar_faces(
+    geometry_registry: &GeometryRegistry,
+    severity: Severity,
+    tolerance: f32,
+) -> Vec<ValidationIssue> {
+    let mut issues = Vec::new();
+    for polygon_id in geometry_registry.polygons.polygons.keys() {
+        let Some(points) = geometry_registry.polygon_points(polygon_id) else {
+            continue;
+        };
+        let deviation = plane_deviation(&points);
+        if deviation > tolerance {
+            issues.push(ValidationIssue {
+                kind: IssueKind::NonPlanarFace,
+                severity,
+                geometry_ids: vec![*polygon_id],
+                message: format!("Polygon {polygon_id} deviates {deviation:.4} m from its plane"),
+            });
+        }
+    }
+    issues
+}
+
+/// Flag polygons whose stored normal points against their current winding
+///
+/// Polygons without a stored plane are skipped.
+#[must_use]
+pub fn check_normal_mismatches(
+    geometry_registry: &GeometryRegistry,
+    severity: Severity,
+) -> Vec<ValidationIssue> {
+    let mut issues = Vec::new();
+    for (polygon_id, polygon) in &geometry_registry.polygons.polygons {
+        let (Some(normal), Some(points)) = (
+            polygon.normal(),
+            geometry_registry.polygon_points(polygon_id),
+        ) else {
+            continue;
+        };
+        if newell_normal(&points).dot(normal) < 0.0 {
+            issues.push(ValidationIssue {
+                kind: IssueKind::NormalMismatch,
+                severity,
+                geometry_ids: vec![*polygon_id],
+                message: format!("Polygon {polygon_id} stored normal opposes its winding"),
+            });
+        }
+    }
+    issues
+}
+
+/// Flag segments shorter than `min_length`
+#[must_use]
+pub fn check_short_segments(
+    geometry_registry: &GeometryRegistry,
+    severity: Severity,
+    min_length: f32,
+) -> Vec<ValidationIssue> {
+    let vertex_registry = &geometry_registry.vertices;
+    let mut issues = Vec::new();
+    for segment in geometry_registry.segments.segments.values() {
+        let (Some(start), Some(end)) = (
+            vertex_registry.get(&segment.vertices[0]),
+            vertex_registry.get(&segment.vertices[1]),
+        ) else {
+            continue;
+        };
+        let length = measure_vector(&start.position, &end.position).length();
+        if length < min_length {
+            issues.push(ValidationIssue {
+                kind: IssueKind::ShortSegment,
+                severity,
+                geometry_ids: vec![segment.id, segment.vertices[0], segment.vertices[1]],
+                message: format!(
+                    "Segment {} is {length:.4} m long (minimum {min_length:.4} m)",
+                    segment.id
+                ),
+            });
+        }
+    }
+    issues
+}
+
+/// Flag polygons thinner than `sliver_thickness`
+///
+/// Thickness is estimated as twice the area over the perimeter, which is
+/// close to the short side of a thin rectangle and zero for a needle.
+#[must_use]
+pub fn check_sliver_polygons(
+    geometry_registry: &GeometryRegistry,
+    severity: Severity,
+    sliver_thickness: f32,
+) -> Vec<ValidationIssue> {
+    let mut issues = Vec::new();
+    for polygon_id in geometry_registry.polygons.polygons.keys() {
+        let Some(points) = geometry_registry.polygon_points(polygon_id) else {
+            continue;
+        };
+        let thickness = polygon_thickness(&points);
+        if thickness < sliver_thickness {
+            issues.push(ValidationIssue {
+                kind: IssueKind::SliverPolygon,
+                severity,
+                geometry_ids: vec![*polygon_id],
+                message: format!(
+                    "Polygon {polygon_id} is {thickness:.4} m thick (sliver below {sliver_thickness:.4} m)"
+                ),
+            });
+        }
+    }
+    issues
+}
+
+/// Estimated thickness of a polygon: twice its area over its perimeter
+#[must_use]
+pub fn polygon_thickness(points: &[Point]) -> f32 {
+    let perimeter = polygon_perimeter(points);
+    if perimeter <= f32::EPSILON {
+        return 0.0;
+    }
+    2.0 * polygon_area(points) / perimeter
+}
+
+/// Flag polygons whose fan triangulation produces triangles below `min_area`
+#[must_use]
+pub fn check_degenerate_triangles(
+    geometry_registry: &GeometryRegistry,
+    severity: Severity,
+    min_area: f32,
+) -> Vec<ValidationIssue> {
+    let mut issues = Vec::new();
+    for polygon_id in geometry_registry.polygons.polygons.keys() {
+        let Some(points) = geometry_registry.polygon_points(polygon_id) else {
+            continue;
+        };
+        let degenerate = fan_triangles(points.len())
+            .into_iter()
+            .filter(|[a, b, c]| triangle_area(&points[*a], &points[*b], &points[*c]) < min_area)
+            .count();
+        if degenerate > 0 {
+            issues.push(ValidationIssue {
+                kind: IssueKind::DegenerateTriangle,
+                severity,
+                geometry_ids: vec![*polygon_id],
+                message: format!(
+                    "Polygon {polygon_id} triangulates into {degenerate} triangle(s) below {min_area:.6} m²"
+                ),
+            });
+        }
+    }
+    issues
+}
+
+/// Flag pairs of vertices closer together than `tolerance`
+///
+/// Vertices are swept in order of their east coordinate so only nearby
+/// candidates are compared.
+#[must_use]
+pub fn check_duplicate_vertices(
+    geometry_registry: &GeometryRegistry,
+    severity: Severity,
+    tolerance: f32,
+) -> Vec<ValidationIssue> {
+    let mut vertices: Vec<_> = geometry_registry.vertices.vertices.values().collect();
+    vertices.sort_by(|a, b| a.position.x.total_cmp(&b.position.x));
+
+    let mut issues = Vec::new();
+    for (index, vertex) in vertices.iter().enumerate() {
+        for other in &vertices[index + 1..] {
+            if other.position.x - vertex.position.x > tolerance {
+                break;
+            }
+            if measure_vector(&vertex.position, &other.position).length() <= tolerance {
+                issues.push(ValidationIssue {
+                    kind: IssueKind::DuplicateVertex,
+                    severity,
+                    geometry_ids: vec![vertex.id, other.id],
+                    message: format!("Vertices {} and {} coincide", vertex.id, other.id),
+                });
+            }
+        }
+    }
+    issues
+}
+
+/// Flag solids with edges not shared by exactly two of their polygons
+#[must_use]
+pub fn check_open_solids(
+    geometry_registry: &GeometryRegistry,
+    severity: Severity,
+) -> Vec<ValidationIssue> {
+    let mut issues = Vec::new();
+    for solid in geometry_registry.solids.solids.values() {
+        let mut edge_use: HashMap<Uuid, usize> = HashMap::new();
+        for polygon_id in &solid.polygons {
+            let Some(polygon) = geometry_registry.polygons.get(polygon_id) else {
+                continue;
+            };
+            for segment_id in &polygon.segments {
+                *edge_use.entry(*segment_id).or_default() += 1;
+            }
+        }
+
+        let mut open_edges: Vec<Uuid> = edge_use
+            .into_iter()
+            .filter(|(_, count)| *count != 2)
+            .map(|(segment_id, _)| segment_id)
+            .collect();
+        if open_edges.is_empty() && !solid.polygons.is_empty() {
+            continue;
+        }
+        open_edges.sort();
+
+        let message = if solid.polygons.is_empty() {
+            format!("Solid {} has no polygons", solid.id)
+        } else {
+            format!(
+                "Solid {} has {} edge(s) not shared by exactly two faces",
+                solid.id,
+                open_edges.len()
+            )
+        };
+        let mut geometry_ids = vec![solid.id];
+        geometry_ids.extend(open_edges);
+        issues.push(ValidationIssue {
+            kind: IssueKind::OpenSolid,
+            severity,
+            geometry_ids,
+            message,
+        });
+    }
+    issues
+}
+
+/// Flag geometry that references IDs missing from the registry
+#[must_use]
+pub fn check_missing_references(
+    geometry_registry: &GeometryRegistry,
+    severity: Severity,
+) -> Vec<ValidationIssue> {
+    let mut issues = Vec::new();
+    let mut report = |owner: Uuid, missing: Uuid, what: &str| {
+        issues.push(ValidationIssue {
+            kind: IssueKind::MissingReference,
+            severity,
+            geometry_ids: vec![owner, missing],
+            message: format!("{owner} references missing {what} {missing}"),
+        });
+    };
+
+    for segment in geometry_registry.segments.segments.values() {
+        for vertex_id in &segment.vertices {
+            if geometry_registry.vertices.get(vertex_id).is_none() {
+                report(segment.id, *vertex_id, "vertex");
+            }
+        }
+    }
+    for polygon in geometry_registry.polygons.polygons.values() {
+        for segment_id in &polygon.segments {
+            if geometry_registry.segments.get(segment_id).is_none() {
+                report(polygon.id, *segment_id, "segment");
+            }
+        }
+    }
+    for solid in geometry_registry.solids.solids.values() {
+        for polygon_id in &solid.polygons {
+            if geometry_registry.polygons.get(polygon_id).is_none() {
+                report(solid.id, *polygon_id, "polygon");
+            }
+        }
+    }
+    issues
+}
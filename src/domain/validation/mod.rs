@@ -0,0 +1,28 @@
+//! Geometry validation pipeline
+//!
+//! Runs configurable geometry checks over a registry and collects the
+//! findings into a structured report graded by severity (error, warning, info).
+//!
+//! Checks are pure: they read the registry and never mutate geometry.
+//! Repairs are opt-in and live in their own module.
+
+/// Issue, severity and report types
+pub mod report;
+
+/// Individual geometry checks
+pub mod checks;
+
+/// Pipeline configuration and runner
+pub mod pipeline;
+
+/// Welding repairs for degenerate features
+pub mod repair;
+
+/// Outward normal orientation repair
+pub mod orientation;
+
+pub use checks::*;
+pub use orientation::*;
+pub use pipeline::*;
+pub use repair::*;
+pub use report::*;
@@ -0,0 +1,248 @@
+/// Normal orientation repair
+///
+/// Re-orients the polygons of a solid so they wind consistently with
+/// outward-facing normals. Winding is propagated across shared edges by
+/// flood fill, then each connected shell is flipped if its signed volume
+/// is negative.
+use crate::domain::geometry::signed_volume;
+use crate::domain::{GeometryRegistry, Point};
+use std::collections::{HashMap, VecDeque};
+use uuid::Uuid;
+
+/// Errors that prevent orienting a solid
+#[derive(Debug, Clone, PartialEq)]
+pub enum OrientationError {
+    /// The solid is not in the registry
+    SolidNotFound {
+        /// The missing solid ID
+        solid_id: Uuid,
+    },
+    /// A polygon's segments do not form a single closed loop
+    BrokenPolygon {
+        /// The polygon that could not be walked
+        polygon_id: Uuid,
+    },
+    /// An edge is shared by more than two polygons
+    NonManifoldEdge {
+        /// The over-shared segment
+        segment_id: Uuid,
+    },
+    /// Neighbouring faces cannot be wound consistently (e.g. a Möbius strip)
+    NonOrientable {
+        /// The segment where the propagated winding conflicts
+        segment_id: Uuid,
+    },
+}
+
+impl std::fmt::Display for OrientationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OrientationError::SolidNotFound { solid_id } => {
+                write!(f, "Solid not found: {solid_id}")
+            }
+            OrientationError::BrokenPolygon { polygon_id } => {
+                write!(f, "Polygon {polygon_id} does not form a closed loop")
+            }
+            OrientationError::NonManifoldEdge { segment_id } => {
+                write!(f, "Segment {segment_id} is shared by more than two faces")
+            }
+            OrientationError::NonOrientable { segment_id } => {
+                write!(
+                    f,
+                    "Faces meeting at segment {segment_id} cannot be wound consistently"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for OrientationError {}
+
+/// What an orientation repair changed
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OrientationSummary {
+    /// Number of polygons whose winding was reversed
+    pub flipped_polygons: usize,
+    /// Number of edge-connected shells in the solid
+    pub shells: usize,
+}
+
+/// Re-orient every polygon of a solid so its normal points outward
+///
+/// Every polygon's segments are rewritten in loop order and its plane is
+/// refitted, so the winding and stored normal are explicit afterwards even
+/// for polygons that did not need flipping.
+///
+/// # Errors
+/// Returns an error if the solid is missing, a polygon is not a closed
+/// loop, or the faces are non-manifold or non-orientable. The registry is
+/// left untouched when an error is returned.
+pub fn orient_solid_outward(
+    geometry_registry: &mut GeometryRegistry,
+    solid_id: &Uuid,
+) -> Result<OrientationSummary, OrientationError> {
+    let solid = geometry_registry
+        .solids
+        .get(solid_id)
+        .ok_or(OrientationError::SolidNotFound {
+            solid_id: *solid_id,
+        })?;
+    let polygon_ids = solid.polygons.clone();
+
+    // Current vertex loop of every polygon
+    let mut loops = Vec::with_capacity(polygon_ids.len());
+    for polygon_id in &polygon_ids {
+        let vertex_loop = geometry_registry
+            .polygons
+            .get(polygon_id)
+            .and_then(|polygon| polygon.vertex_loop(&geometry_registry.segments))
+            .ok_or(OrientationError::BrokenPolygon {
+                polygon_id: *polygon_id,
+            })?;
+        loops.push(vertex_loop);
+    }
+
+    let edge_uses = collect_edge_uses(geometry_registry, &polygon_ids, &loops)?;
+    let (flip, shells) = propagate_winding(loops.len(), &edge_uses)?;
+
+    // Flip whole shells that enclose a negative volume
+    let mut final_loops: Vec<Vec<Uuid>> = loops
+        .into_iter()
+        .zip(&flip)
+        .map(|(mut vertex_loop, &flipped)| {
+            if flipped {
+                vertex_loop.reverse();
+            }
+            vertex_loop
+        })
+        .collect();
+    let mut flipped = flip;
+    for shell in &shells {
+        let shell_points: Vec<Vec<Point>> = shell
+            .iter()
+            .map(|&index| loop_points(geometry_registry, &final_loops[index]))
+            .collect();
+        if signed_volume(&shell_points) < 0.0 {
+            for &index in shell {
+                final_loops[index].reverse();
+                flipped[index] = !flipped[index];
+            }
+        }
+    }
+
+    // Write the explicit winding and the matching outward plane back to the polygons
+    for (polygon_id, vertex_loop) in polygon_ids.iter().zip(&final_loops) {
+        let points = loop_points(geometry_registry, vertex_loop);
+        if let Some(polygon) = geometry_registry.polygons.get_mut(polygon_id) {
+            polygon.set_winding(vertex_loop, &geometry_registry.segments);
+            polygon.update_plane(&points);
+        }
+    }
+
+    Ok(OrientationSummary {
+        flipped_polygons: flipped.iter().filter(|&&f| f).count(),
+        shells: shells.len(),
+    })
+}
+
+/// For every edge, the polygons using it and whether they walk it forwards
+///
+/// # Errors
+/// Returns `NonManifoldEdge` if an edge is used by more than two polygons.
+fn collect_edge_uses(
+    geometry_registry: &GeometryRegistry,
+    polygon_ids: &[Uuid],
+    loops: &[Vec<Uuid>],
+) -> Result<HashMap<Uuid, Vec<(usize, bool)>>, OrientationError> {
+    let mut edge_uses: HashMap<Uuid, Vec<(usize, bool)>> = HashMap::new();
+    for (polygon_index, vertex_loop) in loops.iter().enumerate() {
+        for (index, vertex_id) in vertex_loop.iter().enumerate() {
+            let next_id = vertex_loop[(index + 1) % vertex_loop.len()];
+            let Some(segment) = geometry_registry
+                .polygons
+                .get(&polygon_ids[polygon_index])
+                .into_iter()
+                .flat_map(|polygon| &polygon.segments)
+                .filter_map(|id| geometry_registry.segments.get(id))
+                .find(|segment| {
+                    segment.contains_vertex(vertex_id) && segment.contains_vertex(&next_id)
+                })
+            else {
+                continue;
+            };
+            let forward = segment.vertices[0] == *vertex_id;
+            edge_uses
+                .entry(segment.id)
+                .or_default()
+                .push((polygon_index, forward));
+        }
+    }
+    if let Some((segment_id, _)) = edge_uses.iter().find(|(_, uses)| uses.len() > 2) {
+        return Err(OrientationError::NonManifoldEdge {
+            segment_id: *segment_id,
+        });
+    }
+    Ok(edge_uses)
+}
+
+/// Flood fill winding flips across shared edges
+///
+/// Faces sharing an edge must walk it in opposite directions. Returns which
+/// polygons must be reversed and the polygon indices of each shell.
+///
+/// # Errors
+/// Returns `NonOrientable` if the propagated winding conflicts.
+fn propagate_winding(
+    loop_count: usize,
+    edge_uses: &HashMap<Uuid, Vec<(usize, bool)>>,
+) -> Result<(Vec<bool>, Vec<Vec<usize>>), OrientationError> {
+    let mut neighbours: Vec<Vec<(usize, bool, Uuid)>> = vec![Vec::new(); loop_count];
+    for (segment_id, uses) in edge_uses {
+        if let [(a, forward_a), (b, forward_b)] = uses[..] {
+            let same_direction = forward_a == forward_b;
+            neighbours[a].push((b, same_direction, *segment_id));
+            neighbours[b].push((a, same_direction, *segment_id));
+        }
+    }
+    let mut flip: Vec<Option<bool>> = vec![None; loop_count];
+    let mut shells: Vec<Vec<usize>> = Vec::new();
+    for seed in 0..loop_count {
+        if flip[seed].is_some() {
+            continue;
+        }
+        flip[seed] = Some(false);
+        let mut shell = vec![seed];
+        let mut queue = VecDeque::from([seed]);
+        while let Some(current) = queue.pop_front() {
+            let current_flip = flip[current].unwrap_or(false);
+            for &(neighbour, same_direction, segment_id) in &neighbours[current] {
+                let required = current_flip ^ same_direction;
+                match flip[neighbour] {
+                    None => {
+                        flip[neighbour] = Some(required);
+                        shell.push(neighbour);
+                        queue.push_back(neighbour);
+                    }
+                    Some(existing) if existing != required => {
+                        return Err(OrientationError::NonOrientable { segment_id });
+                    }
+                    Some(_) => {}
+                }
+            }
+        }
+        shells.push(shell);
+    }
+    Ok((
+        flip.into_iter().map(|f| f.unwrap_or(false)).collect(),
+        shells,
+    ))
+}
+
+/// Positions of a vertex loop, skipping missing vertices
+fn loop_points(geometry_registry: &GeometryRegistry, vertex_loop: &[Uuid]) -> Vec<Point> {
+    vertex_loop
+        .iter()
+        .filter_map(|id| geometry_registry.vertices.get(id))
+        .map(|vertex| vertex.position.clone())
+        .collect()
+}
@@ -0,0 +1,172 @@
+/// Validation pipeline
+///
+/// Combines the individual checks into a single configurable pass.
+use crate::domain::validation::checks;
+use crate::domain::validation::report::{Severity, ValidationReport};
+use crate::domain::{GeometryRegistry, Tolerance};
+
+/// Whether a check runs and at which severity it reports
+#[derive(Debug, Clone, Copy)]
+pub struct CheckSetting {
+    /// Whether the check runs
+    pub enabled: bool,
+    /// The severity assigned to issues the check finds
+    pub severity: Severity,
+}
+
+impl CheckSetting {
+    /// An enabled check reporting at the given severity
+    #[must_use]
+    pub fn enabled(severity: Severity) -> Self {
+        Self {
+            enabled: true,
+            severity,
+        }
+    }
+
+    /// A disabled check
+    #[must_use]
+    pub fn disabled(severity: Severity) -> Self {
+        Self {
+            enabled: false,
+            severity,
+        }
+    }
+}
+
+/// Configuration for the validation pipeline
+#[derive(Debug, Clone)]
+pub struct ValidationConfig {
+    /// Polygons whose vertices are not coplanar (default: error)
+    pub non_planar_face: CheckSetting,
+    /// Polygons whose stored normal opposes their winding (default: warning)
+    pub normal_mismatch: CheckSetting,
+    /// Segments shorter than the minimum segment length (default: warning)
+    pub short_segment: CheckSetting,
+    /// Polygons thinner than the sliver thickness (default: warning)
+    pub sliver_polygon: CheckSetting,
+    /// Triangles smaller than the minimum triangle area (default: info)
+    pub degenerate_triangle: CheckSetting,
+    /// Vertices closer together than the linear tolerance (default: warning)
+    pub duplicate_vertex: CheckSetting,
+    /// Solids that are not watertight (default: error)
+    pub open_solid: CheckSetting,
+    /// References to geometry missing from the registry (default: error)
+    pub missing_reference: CheckSetting,
+    /// Tolerance the checks work to; feature thresholds derive from it
+    pub tolerance: Tolerance,
+    /// Whether the pipeline should run automatically after edits
+    pub run_after_edits: bool,
+}
+
+impl Default for ValidationConfig {
+    fn default() -> Self {
+        Self {
+            non_planar_face: CheckSetting::enabled(Severity::Error),
+            normal_mismatch: CheckSetting::enabled(Severity::Warning),
+            short_segment: CheckSetting::enabled(Severity::Warning),
+            sliver_polygon: CheckSetting::enabled(Severity::Warning),
+            degenerate_triangle: CheckSetting::enabled(Severity::Info),
+            duplicate_vertex: CheckSetting::enabled(Severity::Warning),
+            open_solid: CheckSetting::enabled(Severity::Error),
+            missing_reference: CheckSetting::enabled(Severity::Error),
+            tolerance: Tolerance::default(),
+            run_after_edits: true,
+        }
+    }
+}
+
+impl ValidationConfig {
+    /// Default checks working to a tier's tolerance
+    ///
+    /// Coincidence uses the linear tolerance directly; segments must be at
+    /// least one minimum feature long, slivers half that thick, and
+    /// triangles must cover a tenth of one minimum feature squared.
+    #[must_use]
+    pub fn for_tolerance(tolerance: Tolerance) -> Self {
+        Self {
+            tolerance,
+            ..Self::default()
+        }
+    }
+}
+
+/// Runs the configured checks over a geometry registry
+#[derive(Debug, Clone, Default)]
+pub struct ValidationPipeline {
+    /// The pipeline configuration
+    pub config: ValidationConfig,
+}
+
+impl ValidationPipeline {
+    /// Create a new pipeline with the given configuration
+    #[must_use]
+    pub fn new(config: ValidationConfig) -> Self {
+        Self { config }
+    }
+
+    /// Run all enabled checks and return the report, most severe issues first
+    #[must_use]
+    #[tracing::instrument(skip_all)]
+    pub fn run(&self, geometry_registry: &GeometryRegistry) -> ValidationReport {
+        let config = &self.config;
+        let mut report = ValidationReport::new();
+
+        if config.missing_reference.enabled {
+            report.issues.extend(checks::check_missing_references(
+                geometry_registry,
+                config.missing_reference.severity,
+            ));
+        }
+        if config.non_planar_face.enabled {
+            report.issues.extend(checks::check_non_planar_faces(
+                geometry_registry,
+                config.non_planar_face.severity,
+                config.tolerance.linear,
+            ));
+        }
+        if config.normal_mismatch.enabled {
+            report.issues.extend(checks::check_normal_mismatches(
+                geometry_registry,
+                config.normal_mismatch.severity,
+            ));
+        }
+        if config.short_segment.enabled {
+            report.issues.extend(checks::check_short_segments(
+                geometry_registry,
+                config.short_segment.severity,
+                config.tolerance.min_segment_length(),
+            ));
+        }
+        if config.sliver_polygon.enabled {
+            report.issues.extend(checks::check_sliver_polygons(
+                geometry_registry,
+                config.sliver_polygon.severity,
+                config.tolerance.sliver_thickness(),
+            ));
+        }
+        if config.degenerate_triangle.enabled {
+            report.issues.extend(checks::check_degenerate_triangles(
+                geometry_registry,
+                config.degenerate_triangle.severity,
+                config.tolerance.min_triangle_area(),
+            ));
+        }
+        if config.duplicate_vertex.enabled {
+            report.issues.extend(checks::check_duplicate_vertices(
+                geometry_registry,
+                config.duplicate_vertex.severity,
+                config.tolerance.linear,
+            ));
+        }
+        if config.open_solid.enabled {
+            report.issues.extend(checks::check_open_solids(
+                geometry_registry,
+                config.open_solid.severity,
+            ));
+        }
+
+        report.sort_by_severity();
+        report
+    }
+}
@@ -0,0 +1,233 @@
+/// Degenerate feature repair
+///
+/// Collapses short segments and sliver polygons by welding vertices.
+/// Unlike the checks, repairs mutate the registry.
+use crate::domain::geometry::distance;
+use crate::domain::validation::checks::polygon_thickness;
+use crate::domain::{GeometryRegistry, Point, Tolerance};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// What a repair pass changed
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RepairSummary {
+    /// Number of vertices welded away
+    pub welded_vertices: usize,
+    /// Number of segments removed (collapsed or merged into duplicates)
+    pub removed_segments: usize,
+    /// Number of polygons removed (collapsed below three edges)
+    pub removed_polygons: usize,
+}
+
+impl RepairSummary {
+    /// Check if the repair changed anything
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    fn absorb(&mut self, other: &RepairSummary) {
+        self.welded_vertices += other.welded_vertices;
+        self.removed_segments += other.removed_segments;
+        self.removed_polygons += other.removed_polygons;
+    }
+}
+
+/// Weld two vertices into one at their midpoint
+///
+/// Segments using `remove` are rewired to `keep`. Segments that collapse to
+/// a point are deleted, segments that become duplicates are merged, and
+/// polygons left with fewer than three edges are removed from the registry
+/// and from their solids.
+#[tracing::instrument(level = "debug", skip(geometry_registry))]
+pub fn weld_vertices(
+    geometry_registry: &mut GeometryRegistry,
+    keep: &Uuid,
+    remove: &Uuid,
+) -> RepairSummary {
+    let mut summary = RepairSummary::default();
+
+    if keep != remove {
+        let Some(removed_position) = geometry_registry
+            .vertices
+            .get(remove)
+            .map(|v| v.position.clone())
+        else {
+            return summary;
+        };
+        let Some(kept) = geometry_registry.vertices.get_mut(keep) else {
+            return summary;
+        };
+        kept.position = Point {
+            x: f32::midpoint(kept.position.x, removed_position.x),
+            y: f32::midpoint(kept.position.y, removed_position.y),
+            z: f32::midpoint(kept.position.z, removed_position.z),
+        };
+
+        for segment in geometry_registry.segments.segments.values_mut() {
+            segment.replace_vertex(remove, keep);
+        }
+        geometry_registry.vertices.remove(remove);
+        summary.welded_vertices += 1;
+    }
+
+    summary.absorb(&collapse_segments_at(geometry_registry, keep));
+    summary
+}
+
+/// Delete collapsed segments at a vertex, merge duplicates and drop
+/// polygons that no longer enclose an area
+fn collapse_segments_at(
+    geometry_registry: &mut GeometryRegistry,
+    vertex_id: &Uuid,
+) -> RepairSummary {
+    let mut summary = RepairSummary::default();
+
+    let mut candidates: Vec<Uuid> = geometry_registry
+        .segments
+        .segments
+        .values()
+        .filter(|segment| segment.contains_vertex(vertex_id))
+        .map(|segment| segment.id)
+        .collect();
+    candidates.sort();
+
+    // None marks a deleted segment, Some marks a merge into a surviving duplicate
+    let mut replacements: HashMap<Uuid, Option<Uuid>> = HashMap::new();
+    let mut by_pair: HashMap<[Uuid; 2], Uuid> = HashMap::new();
+    for segment_id in candidates {
+        let Some(segment) = geometry_registry.segments.get(&segment_id) else {
+            continue;
+        };
+        if segment.is_degenerate() {
+            replacements.insert(segment_id, None);
+        } else if let Some(existing) = by_pair.get(&segment.vertices) {
+            replacements.insert(segment_id, Some(*existing));
+        } else {
+            by_pair.insert(segment.vertices, segment_id);
+        }
+    }
+    if replacements.is_empty() {
+        return summary;
+    }
+
+    let mut removed_polygons = Vec::new();
+    for polygon in geometry_registry.polygons.polygons.values_mut() {
+        if !polygon
+            .segments
+            .iter()
+            .any(|id| replacements.contains_key(id))
+        {
+            continue;
+        }
+        let remapped: Vec<Uuid> = polygon
+            .segments
+            .iter()
+            .filter_map(|id| replacements.get(id).copied().unwrap_or(Some(*id)))
+            .collect();
+        // An edge used twice in one loop is a folded spike left by the collapse
+        polygon.segments = remapped
+            .iter()
+            .filter(|id| remapped.iter().filter(|other| other == id).count() == 1)
+            .copied()
+            .collect();
+        if polygon.segments.len() < 3 {
+            removed_polygons.push(polygon.id);
+        }
+    }
+
+    for segment_id in replacements.keys() {
+        geometry_registry.segments.remove(segment_id);
+        summary.removed_segments += 1;
+    }
+    for polygon_id in &removed_polygons {
+        geometry_registry.polygons.remove(polygon_id);
+        summary.removed_polygons += 1;
+    }
+    for solid in geometry_registry.solids.solids.values_mut() {
+        solid.polygons.retain(|id| !removed_polygons.contains(id));
+    }
+    summary
+}
+
+/// Collapse every segment shorter than the tolerance's minimum segment length
+/// and every sliver polygon in the registry
+///
+/// The shortest offending segment is welded first; slivers are collapsed by
+/// welding their shortest edge. Repeats until no offending feature remains.
+#[tracing::instrument(skip_all)]
+pub fn repair_degenerate_features(
+    geometry_registry: &mut GeometryRegistry,
+    tolerance: &Tolerance,
+) -> RepairSummary {
+    let mut summary = RepairSummary::default();
+    let max_passes =
+        geometry_registry.vertices.vertices.len() + geometry_registry.segments.segments.len();
+
+    for _ in 0..max_passes {
+        let target = shortest_segment_below(geometry_registry, tolerance.min_segment_length())
+            .or_else(|| sliver_edge(geometry_registry, tolerance.sliver_thickness()));
+        let Some([keep, remove]) = target else {
+            break;
+        };
+        summary.absorb(&weld_vertices(geometry_registry, &keep, &remove));
+    }
+    tracing::info!(
+        welded = summary.welded_vertices,
+        segments = summary.removed_segments,
+        polygons = summary.removed_polygons,
+        "repaired degenerate features"
+    );
+    summary
+}
+
+/// Length of a segment, or None if a vertex is missing
+fn segment_length(geometry_registry: &GeometryRegistry, vertices: &[Uuid; 2]) -> Option<f32> {
+    let start = geometry_registry.vertices.get(&vertices[0])?;
+    let end = geometry_registry.vertices.get(&vertices[1])?;
+    Some(distance(&start.position, &end.position))
+}
+
+/// Vertex pair of the shortest segment below `min_length`
+fn shortest_segment_below(
+    geometry_registry: &GeometryRegistry,
+    min_length: f32,
+) -> Option<[Uuid; 2]> {
+    geometry_registry
+        .segments
+        .segments
+        .values()
+        .filter_map(|segment| {
+            segment_length(geometry_registry, &segment.vertices)
+                .filter(|length| *length < min_length)
+                .map(|length| (length, segment.vertices))
+        })
+        .min_by(|a, b| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)))
+        .map(|(_, vertices)| vertices)
+}
+
+/// Vertex pair of the shortest edge of the thinnest sliver polygon
+fn sliver_edge(geometry_registry: &GeometryRegistry, sliver_thickness: f32) -> Option<[Uuid; 2]> {
+    let (_, polygon_id) = geometry_registry
+        .polygons
+        .polygons
+        .keys()
+        .filter_map(|polygon_id| {
+            let points = geometry_registry.polygon_points(polygon_id)?;
+            let thickness = polygon_thickness(&points);
+            (thickness < sliver_thickness).then_some((thickness, *polygon_id))
+        })
+        .min_by(|a, b| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)))?;
+
+    let polygon = geometry_registry.polygons.get(&polygon_id)?;
+    polygon
+        .segments
+        .iter()
+        .filter_map(|segment_id| geometry_registry.segments.get(segment_id))
+        .filter_map(|segment| {
+            segment_length(geometry_registry, &segment.vertices)
+                .map(|length| (length, segment.vertices))
+        })
+        .min_by(|a, b| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)))
+        .map(|(_, vertices)| vertices)
+}
@@ -0,0 +1,140 @@
+/// Validation report types
+///
+/// Issues found by the validation checks, graded by severity.
+use uuid::Uuid;
+
+/// How serious a validation issue is
+///
+/// Ordered from least to most severe so reports can be sorted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    /// Informational finding, no action required
+    Info,
+    /// Suspicious geometry that may cause problems downstream
+    Warning,
+    /// Invalid geometry that must be fixed
+    Error,
+}
+
+impl Severity {
+    /// Short label for display
+    #[must_use]
+    pub fn label(self) -> &'static str {
+        match self {
+            Severity::Info => "Info",
+            Severity::Warning => "Warning",
+            Severity::Error => "Error",
+        }
+    }
+}
+
+/// The kind of problem a validation check found
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum IssueKind {
+    /// Polygon vertices do not lie on a common plane
+    NonPlanarFace,
+    /// Polygon's stored normal disagrees with its winding
+    NormalMismatch,
+    /// Segment is shorter than the minimum segment length
+    ShortSegment,
+    /// Polygon is thinner than the sliver tolerance
+    SliverPolygon,
+    /// Triangle from triangulation has near-zero area
+    DegenerateTriangle,
+    /// Two vertices occupy the same position
+    DuplicateVertex,
+    /// Solid is not watertight (an edge is not shared by exactly two faces)
+    OpenSolid,
+    /// Geometry references an ID that is not in the registry
+    MissingReference,
+}
+
+impl IssueKind {
+    /// Short label for display
+    #[must_use]
+    pub fn label(self) -> &'static str {
+        match self {
+            IssueKind::NonPlanarFace => "Non-planar face",
+            IssueKind::NormalMismatch => "Normal mismatch",
+            IssueKind::ShortSegment => "Short segment",
+            IssueKind::SliverPolygon => "Sliver polygon",
+            IssueKind::DegenerateTriangle => "Degenerate triangle",
+            IssueKind::DuplicateVertex => "Duplicate vertex",
+            IssueKind::OpenSolid => "Open solid",
+            IssueKind::MissingReference => "Missing reference",
+        }
+    }
+}
+
+/// A single validation finding
+#[derive(Debug, Clone)]
+pub struct ValidationIssue {
+    /// The kind of problem
+    pub kind: IssueKind,
+    /// How serious the problem is
+    pub severity: Severity,
+    /// The geometry entities involved, most relevant first
+    pub geometry_ids: Vec<Uuid>,
+    /// Human-readable description
+    pub message: String,
+}
+
+/// Structured result of running the validation pipeline
+#[derive(Debug, Clone, Default)]
+pub struct ValidationReport {
+    /// The issues found, most severe first once sorted
+    pub issues: Vec<ValidationIssue>,
+}
+
+impl ValidationReport {
+    /// Create a new empty report
+    #[must_use]
+    pub fn new() -> Self {
+        Self { issues: Vec::new() }
+    }
+
+    /// Add an issue to the report
+    pub fn add(&mut self, issue: ValidationIssue) {
+        self.issues.push(issue);
+    }
+
+    /// Check if there are any issues
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    /// Get the number of issues
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.issues.len()
+    }
+
+    /// Count the issues of a given severity
+    #[must_use]
+    pub fn count(&self, severity: Severity) -> usize {
+        self.issues
+            .iter()
+            .filter(|issue| issue.severity == severity)
+            .count()
+    }
+
+    /// Check if any issue is an error
+    #[must_use]
+    pub fn has_errors(&self) -> bool {
+        self.count(Severity::Error) > 0
+    }
+
+    /// Iterate over the issues of a given severity
+    pub fn with_severity(&self, severity: Severity) -> impl Iterator<Item = &ValidationIssue> {
+        self.issues
+            .iter()
+            .filter(move |issue| issue.severity == severity)
+    }
+
+    /// Sort issues so the most severe come first
+    pub fn sort_by_severity(&mut self) {
+        self.issues
+            .sort_by_key(|issue| std::cmp::Reverse(issue.severity));
+    }
+}
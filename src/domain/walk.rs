@@ -0,0 +1,199 @@
+/// Walking through a model on foot
+///
+/// A walker is a vertical capsule. It stands on the highest upward face
+/// under its feet that is no more than a step above them, so it climbs
+/// stairs and slab edges, and it is stopped by any face its body would
+/// overlap above that step, so it cannot pass through walls. Faces are
+/// given in world space; callers pass only those near the walker, found
+/// from the solids' bounds.
+use crate::domain::geometry::{distance, point_in_loop, Bounds, Plane};
+use crate::domain::{measure_vector, Point};
+
+/// Upward faces are those within 45 degrees of level
+const LEVEL_COSINE: f32 = std::f32::consts::FRAC_1_SQRT_2;
+
+/// A face the walker can stand on or bump into
+#[derive(Debug, Clone)]
+pub struct WalkFace {
+    /// The face's loop
+    pub points: Vec<Point>,
+    /// The face's plane, with the normal following the loop's winding
+    pub plane: Plane,
+    /// The box around the loop
+    pub bounds: Bounds,
+}
+
+impl WalkFace {
+    /// A face from a closed point loop
+    /// Returns None for degenerate loops with no area
+    #[must_use]
+    pub fn new(points: Vec<Point>) -> Option<Self> {
+        Some(Self {
+            plane: Plane::from_loop(&points)?,
+            bounds: Bounds::from_points(&points)?,
+            points,
+        })
+    }
+
+    /// The height of the face straight above or below a plan position,
+    /// if the face is upward and covers it
+    fn height_at(&self, x: f32, z: f32) -> Option<f32> {
+        let (normal, origin) = (&self.plane.normal, &self.plane.point);
+        if normal.y < LEVEL_COSINE
+            || x < self.bounds.min.x
+            || x > self.bounds.max.x
+            || z < self.bounds.min.z
+            || z > self.bounds.max.z
+        {
+            return None;
+        }
+        let y = origin.y - ((x - origin.x) * normal.x + (z - origin.z) * normal.z) / normal.y;
+        point_in_loop(&Point { x, y, z }, &self.points, normal).then_some(y)
+    }
+
+    /// The point on the face nearest another point
+    fn closest_point(&self, point: &Point) -> Point {
+        let projected = self.plane.project(point);
+        if point_in_loop(&projected, &self.points, &self.plane.normal) {
+            return projected;
+        }
+        (0..self.points.len())
+            .map(|index| {
+                let start = &self.points[index];
+                let end = &self.points[(index + 1) % self.points.len()];
+                closest_on_segment(point, start, end)
+            })
+            .min_by(|a, b| distance(point, a).total_cmp(&distance(point, b)))
+            .unwrap_or(projected)
+    }
+}
+
+/// The point on a segment nearest another point
+fn closest_on_segment(point: &Point, start: &Point, end: &Point) -> Point {
+    let along = measure_vector(start, end);
+    let length_squared = along.dot(&along);
+    let t = if length_squared > 0.0 {
+        (measure_vector(start, point).dot(&along) / length_squared).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    Point {
+        x: start.x + along.x * t,
+        y: start.y + along.y * t,
+        z: start.z + along.z * t,
+    }
+}
+
+/// The body walking through the model
+#[derive(Debug, Clone)]
+pub struct Walker {
+    /// Radius of the body in meters
+    pub radius: f32,
+    /// Height of the body from its feet in meters
+    pub height: f32,
+    /// Tallest rise the walker steps up without being stopped
+    pub step_height: f32,
+    /// Height of open ground to stand on where there is no floor, None to
+    /// fall without end
+    pub grade: Option<f32>,
+}
+
+impl Default for Walker {
+    fn default() -> Self {
+        Self {
+            radius: 0.25,
+            height: 1.8,
+            step_height: 0.35,
+            grade: Some(0.0),
+        }
+    }
+}
+
+impl Walker {
+    /// The height of the highest floor under the feet that is no more than
+    /// a step above them, or the grade below the feet
+    #[must_use]
+    pub fn ground(&self, feet: &Point, faces: &[WalkFace]) -> Option<f32> {
+        let reach = feet.y + self.step_height;
+        faces
+            .iter()
+            .filter_map(|face| face.height_at(feet.x, feet.z))
+            .chain(self.grade)
+            .filter(|height| *height <= reach)
+            .max_by(f32::total_cmp)
+    }
+
+    /// Whether the body, above its step height, overlaps any of the faces
+    #[must_use]
+    pub fn blocked(&self, feet: &Point, faces: &[WalkFace]) -> bool {
+        let bottom = feet.y + self.step_height + self.radius;
+        let top = (feet.y + self.height - self.radius).max(bottom);
+        // Spheres along the body's axis, no further apart than their radius
+        #[allow(
+            clippy::cast_possible_truncation,
+            clippy::cast_sign_loss,
+            clippy::cast_precision_loss
+        )]
+        let samples = ((top - bottom) / self.radius).ceil().max(1.0) as usize;
+        faces
+            .iter()
+            .filter(|face| {
+                face.bounds.min.x < feet.x + self.radius
+                    && face.bounds.max.x > feet.x - self.radius
+                    && face.bounds.min.z < feet.z + self.radius
+                    && face.bounds.max.z > feet.z - self.radius
+                    && face.bounds.min.y < top + self.radius
+                    && face.bounds.max.y > bottom - self.radius
+            })
+            .any(|face| {
+                #[allow(clippy::cast_precision_loss)]
+                (0..=samples).any(|sample| {
+                    let center = Point {
+                        x: feet.x,
+                        y: bottom + (top - bottom) * sample as f32 / samples as f32,
+                        z: feet.z,
+                    };
+                    distance(&center, &face.closest_point(&center)) < self.radius
+                })
+            })
+    }
+
+    /// Move the feet across the plan, sliding along whatever blocks the
+    /// way and staying put if every direction is blocked
+    #[must_use]
+    pub fn slide(&self, feet: &Point, dx: f32, dz: f32, faces: &[WalkFace]) -> Point {
+        [(dx, dz), (dx, 0.0), (0.0, dz)]
+            .into_iter()
+            .filter(|(x, z)| *x != 0.0 || *z != 0.0)
+            .map(|(x, z)| Point {
+                x: feet.x + x,
+                y: feet.y,
+                z: feet.z + z,
+            })
+            .find(|moved| !self.blocked(moved, faces))
+            .unwrap_or_else(|| feet.clone())
+    }
+
+    /// Let the walker fall under gravity for a time step, landing on the
+    /// ground; returns the new feet position and vertical speed
+    #[must_use]
+    pub fn fall(
+        &self,
+        feet: &Point,
+        vertical_speed: f32,
+        gravity: f32,
+        time_step: f32,
+        faces: &[WalkFace],
+    ) -> (Point, f32) {
+        let speed = vertical_speed - gravity * time_step;
+        let mut moved = feet.clone();
+        moved.y += speed * time_step;
+        match self.ground(feet, faces) {
+            Some(ground) if moved.y <= ground => {
+                moved.y = ground;
+                (moved, 0.0)
+            }
+            _ => (moved, speed),
+        }
+    }
+}
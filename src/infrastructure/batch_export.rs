@@ -0,0 +1,247 @@
+/// Batch export
+///
+/// A batch writes a configurable set of outputs in one action: any of
+/// STL, OBJ, glTF, DXF plans, a CSV quantity takeoff, IFC, COLLADA and a
+/// gbXML energy model, each with its own exporter's settings and export
+/// filter, into one folder under a shared file stem. Outputs are written one at a time so callers can report
+/// progress, and one that fails does not stop the rest. A JSON manifest
+/// beside them lists each file produced with its format and size, and
+/// each output that failed with why.
+use crate::domain::{ElementRegistry, ExportFilter, GeometryRegistry, LevelRegistry};
+use crate::infrastructure::collada::{write_collada, ColladaExportSettings};
+use crate::infrastructure::current_timestamp;
+use crate::infrastructure::dxf_plans::{write_dxf_plans, DxfPlanSettings};
+use crate::infrastructure::gbxml::{write_gbxml, GbxmlExportSettings};
+use crate::infrastructure::gltf::{write_gltf, GltfExportSettings};
+use crate::infrastructure::ifc::{write_ifc, IfcExportSettings};
+use crate::infrastructure::obj::write_obj;
+use crate::infrastructure::stl::write_stl;
+use crate::infrastructure::takeoff::{write_takeoff, TakeoffSettings};
+use serde_json::json;
+use std::path::{Path, PathBuf};
+
+/// One output of a batch and its settings
+#[derive(Debug, Clone)]
+pub enum BatchOutput {
+    /// An ASCII STL mesh of the solids the filter includes
+    Stl(ExportFilter),
+    /// An OBJ mesh of the solids the filter includes
+    Obj(ExportFilter),
+    /// A glTF asset
+    Gltf(GltfExportSettings),
+    /// DXF plans, one file per level when split by level
+    DxfPlans(DxfPlanSettings),
+    /// A CSV quantity takeoff
+    Takeoff(TakeoffSettings),
+    /// An IFC model
+    Ifc(IfcExportSettings),
+    /// A COLLADA document
+    Collada(ColladaExportSettings),
+    /// A gbXML energy model of the spaces
+    Gbxml(GbxmlExportSettings),
+}
+
+impl BatchOutput {
+    /// The name shown for the output's format
+    #[must_use]
+    pub fn label(&self) -> &'static str {
+        match self {
+            BatchOutput::Stl(_) => "STL",
+            BatchOutput::Obj(_) => "OBJ",
+            BatchOutput::Gltf(_) => "glTF",
+            BatchOutput::DxfPlans(_) => "DXF plans",
+            BatchOutput::Takeoff(_) => "CSV takeoff",
+            BatchOutput::Ifc(_) => "IFC",
+            BatchOutput::Collada(_) => "COLLADA",
+            BatchOutput::Gbxml(_) => "gbXML",
+        }
+    }
+
+    /// The extension of the output's files
+    #[must_use]
+    pub fn extension(&self) -> &'static str {
+        match self {
+            BatchOutput::Stl(_) => "stl",
+            BatchOutput::Obj(_) => "obj",
+            BatchOutput::Gltf(_) => "gltf",
+            BatchOutput::DxfPlans(_) => "dxf",
+            BatchOutput::Takeoff(_) => "csv",
+            BatchOutput::Ifc(_) => "ifc",
+            BatchOutput::Collada(_) => "dae",
+            BatchOutput::Gbxml(_) => "xml",
+        }
+    }
+}
+
+/// The registries a batch exports from
+#[derive(Clone, Copy)]
+pub struct BatchModel<'a> {
+    /// The model's geometry
+    pub geometry: &'a GeometryRegistry,
+    /// The model's elements
+    pub elements: &'a ElementRegistry,
+    /// The model's levels, for plans split by level
+    pub levels: &'a LevelRegistry,
+}
+
+/// A file a batch produced
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BatchFile {
+    /// The format of the output that produced it
+    pub format: String,
+    /// Where it was written
+    pub path: PathBuf,
+    /// Its size in bytes
+    pub bytes: u64,
+}
+
+/// What a batch produced so far
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BatchReport {
+    /// The files written
+    pub files: Vec<BatchFile>,
+    /// The format of each output that failed, with why
+    pub failures: Vec<(String, String)>,
+}
+
+/// How far a batch has got, reported after each output
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BatchProgress {
+    /// Outputs finished, including this one
+    pub done: usize,
+    /// Outputs in the batch
+    pub total: usize,
+    /// The format of the output just finished
+    pub format: &'static str,
+}
+
+/// A set of outputs written together into one folder
+#[derive(Debug, Clone)]
+pub struct BatchExport {
+    /// The folder the files are written to
+    pub folder: PathBuf,
+    /// The start of every file name, such as `model` for `model.stl`
+    pub stem: String,
+    /// The outputs, in the order they are written
+    pub outputs: Vec<BatchOutput>,
+}
+
+impl BatchExport {
+    /// Where the manifest is written
+    #[must_use]
+    pub fn manifest_path(&self) -> PathBuf {
+        self.folder.join(format!("{}-manifest.json", self.stem))
+    }
+
+    /// Write one output, adding its files or its failure to the report
+    /// Returns None if there is no output at the index
+    pub fn write_output(
+        &self,
+        index: usize,
+        model: BatchModel,
+        report: &mut BatchReport,
+    ) -> Option<()> {
+        let output = self.outputs.get(index)?;
+        let (geometry, elements) = (model.geometry, model.elements);
+        let path = self
+            .folder
+            .join(format!("{}.{}", self.stem, output.extension()));
+        let single = |written: std::io::Result<()>| written.map(|()| vec![path.clone()]);
+        let written = match output {
+            BatchOutput::Stl(filter) => single(write_stl(&path, geometry, elements, filter)),
+            BatchOutput::Obj(filter) => single(write_obj(&path, geometry, elements, filter)),
+            BatchOutput::Gltf(settings) => single(write_gltf(&path, geometry, elements, settings)),
+            BatchOutput::DxfPlans(settings) => write_dxf_plans(
+                &self.folder,
+                &self.stem,
+                geometry,
+                elements,
+                model.levels,
+                settings,
+            ),
+            BatchOutput::Takeoff(settings) => {
+                single(write_takeoff(&path, geometry, elements, settings))
+            }
+            BatchOutput::Ifc(settings) => single(write_ifc(&path, geometry, elements, settings)),
+            BatchOutput::Collada(settings) => {
+                single(write_collada(&path, geometry, elements, settings))
+            }
+            BatchOutput::Gbxml(settings) => {
+                single(write_gbxml(&path, geometry, elements, settings))
+            }
+        };
+        let format = output.label().to_string();
+        match written {
+            Ok(paths) => report.files.extend(paths.into_iter().map(|path| BatchFile {
+                format: format.clone(),
+                bytes: std::fs::metadata(&path).map_or(0, |metadata| metadata.len()),
+                path,
+            })),
+            Err(error) => report.failures.push((format, error.to_string())),
+        }
+        Some(())
+    }
+
+    /// Write the manifest of a finished batch
+    /// Returns its path
+    ///
+    /// # Errors
+    /// Returns an error if the manifest cannot be written.
+    pub fn write_manifest(&self, report: &BatchReport) -> std::io::Result<PathBuf> {
+        let relative = |path: &Path| {
+            path.strip_prefix(&self.folder)
+                .unwrap_or(path)
+                .display()
+                .to_string()
+        };
+        let manifest = json!({
+            "created": current_timestamp(),
+            "files": report.files.iter().map(|file| json!({
+                "format": file.format,
+                "path": relative(&file.path),
+                "bytes": file.bytes,
+            })).collect::<Vec<_>>(),
+            "failed": report.failures.iter().map(|(format, error)| json!({
+                "format": format,
+                "error": error,
+            })).collect::<Vec<_>>(),
+        });
+        let path = self.manifest_path();
+        std::fs::write(
+            &path,
+            serde_json::to_string_pretty(&manifest).unwrap_or_default(),
+        )?;
+        Ok(path)
+    }
+
+    /// Write every output and then the manifest, reporting progress after
+    /// each output
+    ///
+    /// # Errors
+    /// Returns an error if the folder or the manifest cannot be written;
+    /// outputs that fail are listed in the report instead.
+    #[tracing::instrument(skip_all, fields(folder = %self.folder.display()))]
+    pub fn run(
+        &self,
+        model: BatchModel,
+        mut progress: impl FnMut(BatchProgress),
+    ) -> std::io::Result<BatchReport> {
+        std::fs::create_dir_all(&self.folder)?;
+        let mut report = BatchReport::default();
+        for (index, output) in self.outputs.iter().enumerate() {
+            self.write_output(index, model, &mut report);
+            progress(BatchProgress {
+                done: index + 1,
+                total: self.outputs.len(),
+                format: output.label(),
+            });
+        }
+        self.write_manifest(&report)?;
+        tracing::info!(
+            files = report.files.len(),
+            failures = report.failures.len(),
+            "wrote batch export"
+        );
+        Ok(report)
+    }
+}
@@ -0,0 +1,621 @@
+/// BCF import and export
+///
+/// Writes coordination comments as a BIM Collaboration Format 2.1 archive
+/// (`.bcfzip`) for other BIM tools' issue trackers. Each comment becomes a
+/// topic holding the comment itself and one viewpoint: the camera it was
+/// raised from and, when it is anchored to an element, that element
+/// selected by the IFC GUID the IFC export gives it. Viewpoints are
+/// converted to IFC's Z-up axes, as the IFC export converts the model.
+///
+/// Reading takes BCF 2.1 and 3.0 archives. Each topic becomes a comment
+/// keeping the topic's GUID, so importing an archive again updates its
+/// comments rather than repeating them. The topic's title, description and
+/// comments make up the comment's text; its first viewpoint gives the
+/// camera, the selected element's GUID and the snapshot.
+use crate::domain::{
+    Comment, CommentRegistry, CommentStatus, Point, Vector, ViewProjection, Viewpoint,
+};
+use crate::infrastructure::ifc::{ifc_guid, uuid_from_ifc_guid};
+use crate::infrastructure::zip::{read_zip_archive, zip_archive};
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::path::Path;
+use uuid::Uuid;
+
+/// The BCF version written
+const BCF_VERSION: &str = "2.1";
+/// Name of each topic's viewpoint file
+const VIEWPOINT_FILE: &str = "viewpoint.bcfv";
+/// Name of each topic's snapshot
+const SNAPSHOT_FILE: &str = "snapshot.png";
+/// Field of view of imported perspective cameras that give none
+const DEFAULT_FIELD_OF_VIEW: f32 = 60.0;
+
+/// Errors raised while reading a BCF archive
+#[derive(Debug)]
+pub enum BcfError {
+    /// The file could not be read, or a snapshot could not be written
+    Io(std::io::Error),
+    /// The archive is not valid BCF
+    Parse(String),
+}
+
+impl std::fmt::Display for BcfError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BcfError::Io(error) => write!(f, "Could not read BCF archive: {error}"),
+            BcfError::Parse(message) => write!(f, "Invalid BCF archive: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for BcfError {}
+
+impl From<std::io::Error> for BcfError {
+    fn from(error: std::io::Error) -> Self {
+        BcfError::Io(error)
+    }
+}
+
+/// A topic read from a BCF archive
+#[derive(Debug, Clone)]
+pub struct BcfTopic {
+    /// The topic as a comment; its element is the UUID the selected
+    /// element's IFC GUID stands for, which may not be in the model
+    pub comment: Comment,
+    /// The PNG snapshot of the topic's viewpoint, if it has one
+    pub snapshot: Option<Vec<u8>>,
+}
+
+/// Write comments as a BCF archive
+///
+/// # Errors
+/// Returns an error if the file cannot be written.
+#[tracing::instrument(skip_all, fields(path = %path.display()))]
+pub fn write_bcf(path: &Path, comments: &CommentRegistry) -> std::io::Result<()> {
+    std::fs::write(path, export_bcf(comments))?;
+    tracing::info!(topics = comments.comments.len(), "wrote BCF archive");
+    Ok(())
+}
+
+/// Read the topics of a BCF archive as comments
+///
+/// Snapshots are written into a folder beside the archive, named after it
+/// with a `.snapshots` extension, and each comment keeps the path of its
+/// snapshot.
+///
+/// # Errors
+/// Returns an error if the archive cannot be read or is not valid BCF, or
+/// if a snapshot cannot be written.
+#[tracing::instrument(skip_all, fields(path = %path.display()))]
+pub fn read_bcf(path: &Path) -> Result<Vec<Comment>, BcfError> {
+    let topics = import_bcf(&std::fs::read(path)?)?;
+    let folder = path.with_extension("snapshots");
+    let mut comments = Vec::new();
+    for topic in topics {
+        let mut comment = topic.comment;
+        if let Some(snapshot) = topic.snapshot {
+            std::fs::create_dir_all(&folder)?;
+            let file = folder.join(format!("{}.png", comment.id));
+            std::fs::write(&file, snapshot)?;
+            comment.snapshot = Some(file);
+        }
+        comments.push(comment);
+    }
+    tracing::info!(topics = comments.len(), "read BCF archive");
+    Ok(comments)
+}
+
+/// Read the topics of a BCF archive's bytes
+///
+/// # Errors
+/// Returns an error if the bytes are not a zip archive or a topic's markup
+/// or viewpoint is not valid.
+pub fn import_bcf(bytes: &[u8]) -> Result<Vec<BcfTopic>, BcfError> {
+    let files: HashMap<String, Vec<u8>> = read_zip_archive(bytes)?.into_iter().collect();
+    let document = |name: &str| -> Result<Option<XmlElement>, BcfError> {
+        files
+            .get(name)
+            .map(|data| parse_xml(&String::from_utf8_lossy(data)))
+            .transpose()
+    };
+    let mut folders: Vec<&str> = files
+        .keys()
+        .filter_map(|name| name.strip_suffix("/markup.bcf"))
+        .collect();
+    folders.sort_unstable();
+    let mut topics = Vec::new();
+    for folder in folders {
+        let Some(markup) = document(&format!("{folder}/markup.bcf"))? else {
+            continue;
+        };
+        let topic = markup
+            .find("Topic")
+            .ok_or_else(|| BcfError::Parse(format!("{folder} has no topic")))?;
+        let id = topic
+            .attribute("Guid")
+            .and_then(|guid| Uuid::parse_str(guid).ok())
+            .ok_or_else(|| BcfError::Parse(format!("topic {folder} has no valid GUID")))?;
+
+        // The first viewpoint, listed as ViewPoint in BCF 3.0 and
+        // Viewpoints in BCF 2.1
+        let listed = markup
+            .find("ViewPoint")
+            .or_else(|| markup.find("Viewpoints"));
+        let file_in_folder = |key: &str, default: &str| {
+            let name = listed
+                .and_then(|listed| listed.child(key))
+                .map_or(default, |file| file.text.trim());
+            format!("{folder}/{name}")
+        };
+        let visualization = document(&file_in_folder("Viewpoint", VIEWPOINT_FILE))?;
+        let snapshot = files
+            .get(&file_in_folder("Snapshot", SNAPSHOT_FILE))
+            .cloned();
+        let element = visualization
+            .as_ref()
+            .and_then(|info| info.find("Selection"))
+            .and_then(|selection| selection.child("Component"))
+            .and_then(|component| component.attribute("IfcGuid"))
+            .and_then(uuid_from_ifc_guid);
+
+        topics.push(BcfTopic {
+            comment: Comment {
+                id,
+                text: topic_text(&markup, topic),
+                author: topic.child_text("CreationAuthor").to_string(),
+                timestamp: topic.child_text("CreationDate").to_string(),
+                status: topic_status(topic.attribute("TopicStatus").unwrap_or_default()),
+                element,
+                viewpoint: visualization
+                    .as_ref()
+                    .and_then(read_viewpoint)
+                    .unwrap_or_else(default_viewpoint),
+                snapshot: None,
+            },
+            snapshot,
+        });
+    }
+    Ok(topics)
+}
+
+/// A topic's title, then its description and each of its comments on
+/// lines of their own
+///
+/// A comment repeating the title and description, as exported comments
+/// do, is left out.
+fn topic_text(markup: &XmlElement, topic: &XmlElement) -> String {
+    let mut lines = vec![topic.child_text("Title").to_string()];
+    let description = topic.child_text("Description");
+    if !description.is_empty() {
+        lines.push(description.to_string());
+    }
+    let heading = lines.join("\n");
+    // Comments sit beside the topic in BCF 2.1 and inside it in BCF 3.0
+    let comments = markup.children.iter().chain(
+        topic
+            .find("Comments")
+            .map_or(&[][..], |list| &list.children),
+    );
+    for comment in comments.filter(|element| element.name == "Comment") {
+        let text = comment.child_text("Comment");
+        if text.is_empty() || text == heading {
+            continue;
+        }
+        match comment.child_text("Author") {
+            "" => lines.push(text.to_string()),
+            author => lines.push(format!("{author}: {text}")),
+        }
+    }
+    lines.join("\n")
+}
+
+/// The comment status matching a topic status
+///
+/// Statuses are free text in BCF; those that are not one of ours, such as
+/// `Active`, are taken as open.
+fn topic_status(label: &str) -> CommentStatus {
+    CommentStatus::ALL
+        .into_iter()
+        .find(|status| {
+            status
+                .label()
+                .replace(' ', "")
+                .eq_ignore_ascii_case(&label.replace(' ', ""))
+        })
+        .unwrap_or_default()
+}
+
+/// The camera of a viewpoint file, in Y-up model axes
+fn read_viewpoint(info: &XmlElement) -> Option<Viewpoint> {
+    let (camera, projection) = if let Some(camera) = info.child("PerspectiveCamera") {
+        let field_of_view = camera
+            .child_text("FieldOfView")
+            .parse()
+            .unwrap_or(DEFAULT_FIELD_OF_VIEW);
+        (camera, ViewProjection::Perspective { field_of_view })
+    } else {
+        let camera = info.child("OrthogonalCamera")?;
+        let view_height = camera.child_text("ViewToWorldScale").parse().ok()?;
+        (camera, ViewProjection::Orthographic { view_height })
+    };
+    let triple = |name: &str| -> Option<[f32; 3]> {
+        let element = camera.child(name)?;
+        let axis = |axis: &str| element.child_text(axis).parse::<f32>().ok();
+        // IFC's Z-up axes back to the model's Y-up ones
+        Some([axis("X")?, axis("Z")?, -axis("Y")?])
+    };
+    let [x, y, z] = triple("CameraViewPoint")?;
+    let [dx, dy, dz] = triple("CameraDirection")?;
+    let [ux, uy, uz] = triple("CameraUpVector")?;
+    Some(Viewpoint {
+        position: Point { x, y, z },
+        direction: Vector {
+            x: dx,
+            y: dy,
+            z: dz,
+        },
+        up: Vector {
+            x: ux,
+            y: uy,
+            z: uz,
+        },
+        projection,
+    })
+}
+
+/// The view of topics without a camera: the whole model from the front
+fn default_viewpoint() -> Viewpoint {
+    Viewpoint {
+        position: Point {
+            x: 0.0,
+            y: 0.0,
+            z: 8.0,
+        },
+        direction: Vector {
+            x: 0.0,
+            y: 0.0,
+            z: -1.0,
+        },
+        up: Vector {
+            x: 0.0,
+            y: 1.0,
+            z: 0.0,
+        },
+        projection: ViewProjection::Perspective {
+            field_of_view: DEFAULT_FIELD_OF_VIEW,
+        },
+    }
+}
+
+/// Build the bytes of a BCF archive of the comments
+///
+/// Snapshots that can no longer be read are left out.
+#[must_use]
+pub fn export_bcf(comments: &CommentRegistry) -> Vec<u8> {
+    let mut entries = vec![(
+        "bcf.version".to_string(),
+        format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <Version VersionId=\"{BCF_VERSION}\">\n  \
+             <DetailedVersion>{BCF_VERSION}</DetailedVersion>\n</Version>\n"
+        )
+        .into_bytes(),
+    )];
+    for comment in comments.by_time() {
+        // The viewpoint needs an ID of its own; deriving it from the
+        // comment's keeps repeated exports identical
+        let viewpoint = Uuid::from_u128(comment.id.as_u128() ^ 1);
+        let snapshot = comment
+            .snapshot
+            .as_ref()
+            .and_then(|path| std::fs::read(path).ok());
+        entries.push((
+            format!("{}/markup.bcf", comment.id),
+            markup(comment, &viewpoint, snapshot.is_some()).into_bytes(),
+        ));
+        entries.push((
+            format!("{}/{VIEWPOINT_FILE}", comment.id),
+            visualization_info(comment, &viewpoint).into_bytes(),
+        ));
+        if let Some(snapshot) = snapshot {
+            entries.push((format!("{}/{SNAPSHOT_FILE}", comment.id), snapshot));
+        }
+    }
+    zip_archive(&entries)
+}
+
+/// The markup file of a comment's topic
+fn markup(comment: &Comment, viewpoint: &Uuid, snapshot: bool) -> String {
+    let mut text = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<Markup>\n");
+    let _ = write!(
+        text,
+        "  <Topic Guid=\"{}\" TopicType=\"Issue\" TopicStatus=\"{}\">\n    \
+         <Title>{}</Title>\n    \
+         <CreationDate>{}</CreationDate>\n    \
+         <CreationAuthor>{}</CreationAuthor>\n    \
+         <Description>{}</Description>\n  \
+         </Topic>\n",
+        comment.id,
+        comment.status.label(),
+        xml_escape(comment.title()),
+        xml_escape(&comment.timestamp),
+        xml_escape(&comment.author),
+        xml_escape(comment.text.split_once('\n').map_or("", |(_, rest)| rest)),
+    );
+    let _ = write!(
+        text,
+        "  <Comment Guid=\"{}\">\n    \
+         <Date>{}</Date>\n    \
+         <Author>{}</Author>\n    \
+         <Comment>{}</Comment>\n    \
+         <Viewpoint Guid=\"{viewpoint}\"/>\n  \
+         </Comment>\n",
+        Uuid::from_u128(comment.id.as_u128() ^ 2),
+        xml_escape(&comment.timestamp),
+        xml_escape(&comment.author),
+        xml_escape(&comment.text),
+    );
+    let _ = write!(
+        text,
+        "  <Viewpoints Guid=\"{viewpoint}\">\n    <Viewpoint>{VIEWPOINT_FILE}</Viewpoint>\n"
+    );
+    if snapshot {
+        let _ = writeln!(text, "    <Snapshot>{SNAPSHOT_FILE}</Snapshot>");
+    }
+    text.push_str("  </Viewpoints>\n</Markup>\n");
+    text
+}
+
+/// The viewpoint file of a comment's topic
+fn visualization_info(comment: &Comment, viewpoint: &Uuid) -> String {
+    let mut text = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <VisualizationInfo Guid=\"{viewpoint}\">\n"
+    );
+    if let Some(element) = comment.element {
+        let _ = write!(
+            text,
+            "  <Components>\n    <Selection>\n      \
+             <Component IfcGuid=\"{}\"/>\n    \
+             </Selection>\n  </Components>\n",
+            ifc_guid(&element)
+        );
+    }
+    let view = &comment.viewpoint;
+    let (camera, scale) = match view.projection {
+        ViewProjection::Perspective { field_of_view } => (
+            "PerspectiveCamera",
+            format!("<FieldOfView>{field_of_view}</FieldOfView>"),
+        ),
+        ViewProjection::Orthographic { view_height } => (
+            "OrthogonalCamera",
+            format!("<ViewToWorldScale>{view_height}</ViewToWorldScale>"),
+        ),
+    };
+    let _ = write!(
+        text,
+        "  <{camera}>\n{}{}{}    {scale}\n  </{camera}>\n</VisualizationInfo>\n",
+        xyz("CameraViewPoint", point_axes(&view.position)),
+        xyz("CameraDirection", vector_axes(&view.direction)),
+        xyz("CameraUpVector", vector_axes(&view.up)),
+    );
+    text
+}
+
+/// An element with X, Y and Z children in IFC's Z-up axes, from Y-up
+/// model coordinates
+fn xyz(name: &str, [x, y, z]: [f32; 3]) -> String {
+    format!(
+        "    <{name}>\n      <X>{x}</X>\n      <Y>{}</Y>\n      <Z>{y}</Z>\n    </{name}>\n",
+        -z
+    )
+}
+
+/// A point's coordinates
+fn point_axes(point: &Point) -> [f32; 3] {
+    [point.x, point.y, point.z]
+}
+
+/// A vector's components
+fn vector_axes(vector: &Vector) -> [f32; 3] {
+    [vector.x, vector.y, vector.z]
+}
+
+/// Escape text for XML content and attributes
+fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// An element of an XML document
+#[derive(Debug, Default)]
+struct XmlElement {
+    /// The element's name without its namespace prefix
+    name: String,
+    attributes: Vec<(String, String)>,
+    children: Vec<XmlElement>,
+    /// The text directly inside the element
+    text: String,
+}
+
+impl XmlElement {
+    /// The value of an attribute
+    fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// The first child element with a name
+    fn child(&self, name: &str) -> Option<&XmlElement> {
+        self.children.iter().find(|child| child.name == name)
+    }
+
+    /// The trimmed text of the first child element with a name, empty if
+    /// there is none
+    fn child_text(&self, name: &str) -> &str {
+        self.child(name).map_or("", |child| child.text.trim())
+    }
+
+    /// The first element with a name among this one's descendants, depth
+    /// first
+    fn find(&self, name: &str) -> Option<&XmlElement> {
+        self.children.iter().find_map(|child| {
+            (child.name == name)
+                .then_some(child)
+                .or_else(|| child.find(name))
+        })
+    }
+}
+
+/// Parse an XML document into its root element
+///
+/// Enough of XML for BCF: elements, attributes, text, character data,
+/// comments and processing instructions. Document types are skipped.
+fn parse_xml(text: &str) -> Result<XmlElement, BcfError> {
+    let invalid = |message: &str| BcfError::Parse(message.to_string());
+    let mut stack = vec![XmlElement::default()];
+    let mut rest = text;
+    while let Some(start) = rest.find('<') {
+        if let Some(open) = stack.last_mut() {
+            open.text.push_str(&xml_unescape(&rest[..start]));
+        }
+        rest = &rest[start..];
+        let skip_past = |rest: &str, end: &str| {
+            rest.find(end)
+                .map(|index| index + end.len())
+                .ok_or_else(|| invalid("unterminated markup"))
+        };
+        if let Some(data) = rest.strip_prefix("<![CDATA[") {
+            let end = data
+                .find("]]>")
+                .ok_or_else(|| invalid("unterminated CDATA"))?;
+            if let Some(open) = stack.last_mut() {
+                open.text.push_str(&data[..end]);
+            }
+            rest = &data[end + 3..];
+        } else if rest.starts_with("<?") {
+            rest = &rest[skip_past(rest, "?>")?..];
+        } else if rest.starts_with("<!--") {
+            rest = &rest[skip_past(rest, "-->")?..];
+        } else if rest.starts_with("<!") {
+            rest = &rest[skip_past(rest, ">")?..];
+        } else if rest.starts_with("</") {
+            rest = &rest[skip_past(rest, ">")?..];
+            let element = stack.pop().filter(|_| !stack.is_empty());
+            let (Some(element), Some(parent)) = (element, stack.last_mut()) else {
+                return Err(invalid("unbalanced closing tag"));
+            };
+            parent.children.push(element);
+        } else {
+            let end = tag_end(rest).ok_or_else(|| invalid("unterminated tag"))?;
+            let tag = &rest[1..end];
+            rest = &rest[end + 1..];
+            let (tag, closed) = match tag.strip_suffix('/') {
+                Some(tag) => (tag, true),
+                None => (tag, false),
+            };
+            let element = parse_tag(tag)?;
+            if closed {
+                if let Some(parent) = stack.last_mut() {
+                    parent.children.push(element);
+                }
+            } else {
+                stack.push(element);
+            }
+        }
+    }
+    let document = stack.pop().filter(|_| stack.is_empty());
+    document
+        .and_then(|document| document.children.into_iter().next())
+        .ok_or_else(|| invalid("no root element, or an element left open"))
+}
+
+/// The index of the `>` closing the tag at the start of the text, skipping
+/// any inside quoted attribute values
+fn tag_end(text: &str) -> Option<usize> {
+    let mut quote = None;
+    for (index, character) in text.char_indices() {
+        match (quote, character) {
+            (None, '"' | '\'') => quote = Some(character),
+            (Some(open), _) if open == character => quote = None,
+            (None, '>') => return Some(index),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// An element from the inside of its opening tag
+fn parse_tag(tag: &str) -> Result<XmlElement, BcfError> {
+    let local = |name: &str| name.rsplit(':').next().unwrap_or(name).to_string();
+    let tag = tag.trim();
+    let name_end = tag.find(char::is_whitespace).unwrap_or(tag.len());
+    let mut element = XmlElement {
+        name: local(&tag[..name_end]),
+        ..XmlElement::default()
+    };
+    let mut rest = tag[name_end..].trim_start();
+    while !rest.is_empty() {
+        let (key, value) = rest
+            .split_once('=')
+            .ok_or_else(|| BcfError::Parse(format!("malformed attribute in <{tag}>")))?;
+        let value = value.trim_start();
+        let quote = value
+            .chars()
+            .next()
+            .filter(|quote| matches!(quote, '"' | '\''))
+            .ok_or_else(|| BcfError::Parse(format!("unquoted attribute in <{tag}>")))?;
+        let end = value[1..]
+            .find(quote)
+            .ok_or_else(|| BcfError::Parse(format!("unterminated attribute in <{tag}>")))?;
+        element
+            .attributes
+            .push((local(key.trim()), xml_unescape(&value[1..=end])));
+        rest = value[end + 2..].trim_start();
+    }
+    Ok(element)
+}
+
+/// Replace XML entity and character references with the characters they
+/// stand for
+fn xml_unescape(text: &str) -> String {
+    let mut unescaped = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        unescaped.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let Some(end) = rest.find(';') else {
+            break;
+        };
+        let reference = &rest[1..end];
+        let character = match reference {
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "amp" => Some('&'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => reference
+                .strip_prefix("#x")
+                .map(|hex| u32::from_str_radix(hex, 16))
+                .or_else(|| reference.strip_prefix('#').map(str::parse))
+                .and_then(Result::ok)
+                .and_then(char::from_u32),
+        };
+        if let Some(character) = character {
+            unescaped.push(character);
+            rest = &rest[end + 1..];
+        } else {
+            // A stray ampersand is kept as it is
+            unescaped.push('&');
+            rest = &rest[1..];
+        }
+    }
+    unescaped.push_str(rest);
+    unescaped
+}
@@ -0,0 +1,132 @@
+/// Material carbon import and carbon report export
+///
+/// Material definitions are CSV rows of `material,unit,factor`, where the
+/// unit is `m2`, `m3` or `item` and the factor is in kilograms of CO2
+/// equivalent per unit. Reports are written back as CSV with one row per
+/// rated element, followed by subtotals per element kind and the total.
+use crate::domain::{CarbonFactors, CarbonReport, MaterialCarbon, RateUnit};
+use crate::infrastructure::csv::{csv_field, csv_rows};
+use std::path::Path;
+
+/// Errors raised while reading material carbon definitions
+#[derive(Debug)]
+pub enum CarbonError {
+    /// The file could not be read
+    Io(std::io::Error),
+    /// A row could not be parsed
+    Parse {
+        /// The 1-based line number
+        line: usize,
+        /// What was wrong with it
+        message: String,
+    },
+}
+
+impl std::fmt::Display for CarbonError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CarbonError::Io(error) => write!(f, "Could not read carbon file: {error}"),
+            CarbonError::Parse { line, message } => write!(f, "Carbon line {line}: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for CarbonError {}
+
+impl From<std::io::Error> for CarbonError {
+    fn from(error: std::io::Error) -> Self {
+        CarbonError::Io(error)
+    }
+}
+
+/// Read material carbon definitions from a CSV file
+///
+/// # Errors
+/// Returns an error if the file cannot be read or parsed.
+#[tracing::instrument(skip_all, fields(path = %path.display()))]
+pub fn read_carbon_factors(path: &Path) -> Result<CarbonFactors, CarbonError> {
+    let factors = parse_carbon_csv(&std::fs::read_to_string(path)?)?;
+    tracing::info!(materials = factors.materials.len(), "read material carbon");
+    Ok(factors)
+}
+
+/// Parse CSV definition rows of `material,unit,factor`
+///
+/// Blank lines and lines starting with `#` are skipped, as is a first row
+/// whose factor is not a number (a header). A material defined twice
+/// keeps its last definition.
+///
+/// # Errors
+/// Returns an error naming the first row that cannot be parsed.
+pub fn parse_carbon_csv(text: &str) -> Result<CarbonFactors, CarbonError> {
+    let mut factors = CarbonFactors::default();
+    for row in csv_rows(text) {
+        let error = |message: String| CarbonError::Parse {
+            line: row.line,
+            message,
+        };
+        let [material, unit, factor] = &row.fields[..] else {
+            return Err(error(format!(
+                "expected material,unit,factor, found \"{}\"",
+                row.text
+            )));
+        };
+        let Ok(factor) = factor.parse::<f64>() else {
+            if row.first {
+                continue;
+            }
+            return Err(error(format!("factor \"{factor}\" is not a number")));
+        };
+        if material.is_empty() {
+            return Err(error("material is empty".to_string()));
+        }
+        let unit = RateUnit::from_label(unit)
+            .ok_or_else(|| error(format!("unknown unit \"{unit}\", expected m2, m3 or item")))?;
+        factors.add(MaterialCarbon {
+            material: material.clone(),
+            unit,
+            factor,
+        });
+    }
+    Ok(factors)
+}
+
+/// Build the CSV text of a carbon report, emissions in kgCO2e
+#[must_use]
+pub fn export_carbon_report(report: &CarbonReport) -> String {
+    let mut rows = vec!["element,name,kind,material,quantity,unit,factor,kgco2e".to_string()];
+    for line in &report.lines {
+        rows.push(format!(
+            "{},{},{},{},{:.3},{},{:.2},{:.2}",
+            line.element,
+            csv_field(&line.name),
+            line.kind.label(),
+            csv_field(&line.material),
+            line.quantity,
+            line.unit.label(),
+            line.factor,
+            line.emissions()
+        ));
+    }
+    for (kind, emissions) in report.by_kind() {
+        rows.push(format!(",Subtotal,{},,,,,{emissions:.2}", kind.label()));
+    }
+    rows.push(format!(",Total,,,,,,{:.2}", report.total()));
+    rows.push(String::new());
+    rows.join("\n")
+}
+
+/// Write a carbon report as a CSV file
+///
+/// # Errors
+/// Returns an error if the file cannot be written.
+#[tracing::instrument(skip_all, fields(path = %path.display()))]
+pub fn write_carbon_report(path: &Path, report: &CarbonReport) -> std::io::Result<()> {
+    std::fs::write(path, export_carbon_report(report))?;
+    tracing::info!(
+        lines = report.lines.len(),
+        total = report.total(),
+        "wrote carbon report"
+    );
+    Ok(())
+}
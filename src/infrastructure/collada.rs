@@ -0,0 +1,283 @@
+/// COLLADA export
+///
+/// Writes solids as a COLLADA 1.4.1 document for `SketchUp` and older
+/// visualization pipelines. Each solid becomes its own geometry and scene
+/// node, named after its element, and each element material becomes a
+/// COLLADA material bound to the node. Nodes are put on the office
+/// standards layer of their element kind, and elements without a material
+/// take their kind's standard color if it has one. The document is Y-up in
+/// meters, matching the model, so no axis conversion is needed.
+use crate::domain::geometry::{fan_triangles, newell_normal};
+use crate::domain::{
+    collect_export_solids, ElementRegistry, ExportFilter, ExportSolid, GeometryRegistry, Point,
+    Solid,
+};
+use crate::infrastructure::current_timestamp;
+use crate::infrastructure::standards::OfficeStandards;
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::path::Path;
+
+/// Material used for solids without an element material
+const DEFAULT_MATERIAL: &str = "Default";
+
+/// Settings for a COLLADA export
+#[derive(Debug, Clone)]
+pub struct ColladaExportSettings {
+    /// Author written to the asset header
+    pub author: String,
+    /// ISO 8601 time stamp written to the asset header
+    pub timestamp: String,
+    /// Which solids to export
+    pub filter: ExportFilter,
+    /// Layer names and colors for element kinds
+    pub standards: OfficeStandards,
+}
+
+impl Default for ColladaExportSettings {
+    fn default() -> Self {
+        Self {
+            author: String::new(),
+            timestamp: current_timestamp(),
+            filter: ExportFilter::default(),
+            standards: OfficeStandards::default(),
+        }
+    }
+}
+
+/// Write a COLLADA file of the solids in the registry
+///
+/// # Errors
+/// Returns an error if the file cannot be written.
+#[tracing::instrument(skip_all, fields(path = %path.display()))]
+pub fn write_collada(
+    path: &Path,
+    geometry_registry: &GeometryRegistry,
+    element_registry: &ElementRegistry,
+    settings: &ColladaExportSettings,
+) -> std::io::Result<()> {
+    std::fs::write(
+        path,
+        export_collada(geometry_registry, element_registry, settings),
+    )?;
+    tracing::info!(
+        solids = geometry_registry.solids.solids.len(),
+        "wrote COLLADA file"
+    );
+    Ok(())
+}
+
+/// Build the text of a COLLADA document
+#[must_use]
+pub fn export_collada(
+    geometry_registry: &GeometryRegistry,
+    element_registry: &ElementRegistry,
+    settings: &ColladaExportSettings,
+) -> String {
+    let solids = collect_export_solids(geometry_registry, element_registry, &settings.filter);
+    let (described, colors) = describe_solids(solids, &settings.standards);
+    let mut materials: Vec<&str> = described.iter().map(|(.., m)| m.as_str()).collect();
+    materials.sort_unstable();
+    materials.dedup();
+    let material_id = |name: &str| {
+        let index = materials.iter().position(|m| *m == name).unwrap_or(0);
+        format!("material-{index}")
+    };
+
+    let mut text = String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+    text.push_str(
+        "<COLLADA xmlns=\"http://www.collada.org/2005/11/COLLADASchema\" version=\"1.4.1\">\n",
+    );
+    let _ = write!(
+        text,
+        "  <asset>\n    <contributor>\n      <author>{}</author>\n      \
+         <authoring_tool>HarmonyArch</authoring_tool>\n    </contributor>\n    \
+         <created>{timestamp}</created>\n    <modified>{timestamp}</modified>\n    \
+         <unit name=\"meter\" meter=\"1\"/>\n    <up_axis>Y_UP</up_axis>\n  </asset>\n",
+        xml_escape(&settings.author),
+        timestamp = xml_escape(&settings.timestamp),
+    );
+
+    text.push_str("  <library_effects>\n");
+    for (index, name) in materials.iter().enumerate() {
+        let [r, g, b] = colors
+            .get(*name)
+            .copied()
+            .unwrap_or_else(|| material_color(name));
+        let _ = write!(
+            text,
+            "    <effect id=\"material-{index}-effect\">\n      <profile_COMMON>\n        \
+             <technique sid=\"common\">\n          <lambert>\n            <diffuse>\
+             <color>{r:.3} {g:.3} {b:.3} 1</color></diffuse>\n          </lambert>\n        \
+             </technique>\n      </profile_COMMON>\n    </effect>\n"
+        );
+    }
+    text.push_str("  </library_effects>\n  <library_materials>\n");
+    for (index, name) in materials.iter().enumerate() {
+        let _ = writeln!(
+            text,
+            "    <material id=\"material-{index}\" name=\"{}\">\
+             <instance_effect url=\"#material-{index}-effect\"/></material>",
+            xml_escape(name)
+        );
+    }
+    text.push_str("  </library_materials>\n  <library_geometries>\n");
+    for (solid, name, _, material) in &described {
+        write_geometry(
+            &mut text,
+            geometry_registry,
+            solid,
+            name,
+            &material_id(material),
+        );
+    }
+    text.push_str("  </library_geometries>\n  <library_visual_scenes>\n");
+    text.push_str("    <visual_scene id=\"scene\" name=\"Model\">\n");
+    for (solid, name, layer, material) in &described {
+        let material = material_id(material);
+        let _ = writeln!(
+            text,
+            "      <node id=\"node-{id}\" name=\"{name}\" layer=\"{layer}\">\n        \
+             <instance_geometry url=\"#solid-{id}\">\n          <bind_material>\
+             <technique_common><instance_material symbol=\"{material}\" \
+             target=\"#{material}\"/></technique_common></bind_material>\n        \
+             </instance_geometry>\n      </node>",
+            id = solid.id,
+            name = xml_escape(name),
+            layer = xml_escape(layer),
+        );
+    }
+    text.push_str("    </visual_scene>\n  </library_visual_scenes>\n");
+    text.push_str("  <scene>\n    <instance_visual_scene url=\"#scene\"/>\n  </scene>\n");
+    text.push_str("</COLLADA>\n");
+    text
+}
+
+/// A solid with its name, layer and material
+pub(crate) type Described<'a> = (&'a Solid, String, String, String);
+
+/// Name, layer and material of each solid, from its element when it has
+/// one, and the colors of materials standing for an element kind
+pub(crate) fn describe_solids<'a>(
+    solids: Vec<ExportSolid<'a>>,
+    standards: &OfficeStandards,
+) -> (Vec<Described<'a>>, HashMap<String, [f32; 3]>) {
+    let mut colors = HashMap::new();
+    let described = solids
+        .into_iter()
+        .map(|export| {
+            let element = export.element;
+            let name = export.name().to_string();
+            let layer = standards.layer_for(export.kind());
+            let material = element
+                .and_then(|element| element.material.clone())
+                .or_else(|| {
+                    let color = standards.color_for(element?.kind)?;
+                    colors.insert(layer.clone(), color);
+                    Some(layer.clone())
+                })
+                .unwrap_or_else(|| DEFAULT_MATERIAL.to_string());
+            (export.solid, name, layer, material)
+        })
+        .collect();
+    (described, colors)
+}
+
+/// Write one solid as a triangle mesh geometry with flat face normals
+///
+/// Polygons with missing or degenerate geometry are skipped.
+fn write_geometry(
+    text: &mut String,
+    geometry_registry: &GeometryRegistry,
+    solid: &Solid,
+    name: &str,
+    material: &str,
+) {
+    let mut positions: Vec<f32> = Vec::new();
+    let mut normals: Vec<f32> = Vec::new();
+    let mut indices: Vec<usize> = Vec::new();
+    let faces = solid.polygons.iter().filter_map(|polygon_id| {
+        let points = geometry_registry.polygon_points(polygon_id)?;
+        // Prefer the stored plane normal, which follows the face's winding
+        let normal = geometry_registry
+            .polygons
+            .get(polygon_id)?
+            .normal()
+            .cloned()
+            .or_else(|| newell_normal(&points).normalized())?;
+        Some((points, normal))
+    });
+    for (face, (points, normal)) in faces.enumerate() {
+        let base = positions.len() / 3;
+        positions.extend(points.iter().flat_map(|p: &Point| [p.x, p.y, p.z]));
+        normals.extend([normal.x, normal.y, normal.z]);
+        for triangle in fan_triangles(points.len()) {
+            for corner in triangle {
+                indices.extend([base + corner, face]);
+            }
+        }
+    }
+
+    let id = format!("solid-{}", solid.id);
+    let _ = write!(
+        text,
+        "    <geometry id=\"{id}\" name=\"{}\">\n      <mesh>\n",
+        xml_escape(name)
+    );
+    write_source(text, &format!("{id}-positions"), &positions);
+    write_source(text, &format!("{id}-normals"), &normals);
+    let _ = write!(
+        text,
+        "        <vertices id=\"{id}-vertices\">\
+         <input semantic=\"POSITION\" source=\"#{id}-positions\"/></vertices>\n        \
+         <triangles material=\"{material}\" count=\"{}\">\n          \
+         <input semantic=\"VERTEX\" source=\"#{id}-vertices\" offset=\"0\"/>\n          \
+         <input semantic=\"NORMAL\" source=\"#{id}-normals\" offset=\"1\"/>\n          \
+         <p>{}</p>\n        </triangles>\n      </mesh>\n    </geometry>\n",
+        indices.len() / 6,
+        join(&indices)
+    );
+}
+
+/// Write an XYZ float source
+fn write_source(text: &mut String, id: &str, values: &[f32]) {
+    let _ = write!(
+        text,
+        "        <source id=\"{id}\">\n          \
+         <float_array id=\"{id}-array\" count=\"{count}\">{}</float_array>\n          \
+         <technique_common><accessor source=\"#{id}-array\" count=\"{}\" stride=\"3\">\
+         <param name=\"X\" type=\"float\"/><param name=\"Y\" type=\"float\"/>\
+         <param name=\"Z\" type=\"float\"/></accessor></technique_common>\n        </source>\n",
+        join(values),
+        values.len() / 3,
+        count = values.len(),
+    );
+}
+
+/// Space-separated values
+fn join<T: std::fmt::Display>(values: &[T]) -> String {
+    let items: Vec<String> = values.iter().map(ToString::to_string).collect();
+    items.join(" ")
+}
+
+/// A stable light colour for a material, derived from its name
+pub(crate) fn material_color(name: &str) -> [f32; 3] {
+    if name == DEFAULT_MATERIAL {
+        return [0.8, 0.8, 0.8];
+    }
+    let hash = name.bytes().fold(2_166_136_261_u32, |hash, byte| {
+        (hash ^ u32::from(byte)).wrapping_mul(16_777_619)
+    });
+    let channel =
+        |shift: u32| 0.4 + f32::from(u8::try_from((hash >> shift) & 0xff).unwrap_or(0)) / 425.0;
+    [channel(0), channel(8), channel(16)]
+}
+
+/// Escape text for an XML attribute or element
+fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,158 @@
+/// Cost rates import and cost estimate export
+///
+/// Rates tables are CSV rows of `kind,material,unit,rate`, where the unit
+/// is `m2`, `m3` or `item` and a blank kind or material matches any.
+/// Estimates are written back as CSV with one row per priced element,
+/// followed by subtotals per phase and per tier and the grand total.
+use crate::domain::{CostEstimate, CostRate, ElementKind, RateUnit, RatesTable, TierRegistry};
+use crate::infrastructure::csv::{csv_field, csv_rows};
+use std::path::Path;
+
+/// Errors raised while reading a rates table
+#[derive(Debug)]
+pub enum CostError {
+    /// The file could not be read
+    Io(std::io::Error),
+    /// A row could not be parsed
+    Parse {
+        /// The 1-based line number
+        line: usize,
+        /// What was wrong with it
+        message: String,
+    },
+}
+
+impl std::fmt::Display for CostError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CostError::Io(error) => write!(f, "Could not read rates file: {error}"),
+            CostError::Parse { line, message } => write!(f, "Rates line {line}: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for CostError {}
+
+impl From<std::io::Error> for CostError {
+    fn from(error: std::io::Error) -> Self {
+        CostError::Io(error)
+    }
+}
+
+/// Read a rates table from a CSV file
+///
+/// # Errors
+/// Returns an error if the file cannot be read or parsed.
+#[tracing::instrument(skip_all, fields(path = %path.display()))]
+pub fn read_rates(path: &Path) -> Result<RatesTable, CostError> {
+    let table = parse_rates_csv(&std::fs::read_to_string(path)?)?;
+    tracing::info!(rates = table.rates.len(), "read rates table");
+    Ok(table)
+}
+
+/// Parse CSV rate rows of `kind,material,unit,rate`
+///
+/// Blank lines and lines starting with `#` are skipped, as is a first row
+/// whose rate is not a number (a header).
+///
+/// # Errors
+/// Returns an error naming the first row that cannot be parsed.
+pub fn parse_rates_csv(text: &str) -> Result<RatesTable, CostError> {
+    let mut table = RatesTable::default();
+    for row in csv_rows(text) {
+        let error = |message: String| CostError::Parse {
+            line: row.line,
+            message,
+        };
+        let [kind, material, unit, rate] = &row.fields[..] else {
+            return Err(error(format!(
+                "expected kind,material,unit,rate, found \"{}\"",
+                row.text
+            )));
+        };
+        let Ok(rate) = rate.parse::<f64>() else {
+            if row.first {
+                continue;
+            }
+            return Err(error(format!("rate \"{rate}\" is not a number")));
+        };
+        let kind = match kind.as_str() {
+            "" => None,
+            name => Some(
+                ElementKind::from_label(name)
+                    .ok_or_else(|| error(format!("unknown element kind \"{name}\"")))?,
+            ),
+        };
+        let unit = RateUnit::from_label(unit)
+            .ok_or_else(|| error(format!("unknown unit \"{unit}\", expected m2, m3 or item")))?;
+        if rate < 0.0 {
+            return Err(error(format!("rate {rate} is negative")));
+        }
+        table.rates.push(CostRate {
+            kind,
+            material: (!material.is_empty()).then(|| material.clone()),
+            unit,
+            rate,
+        });
+    }
+    Ok(table)
+}
+
+/// Build the CSV text of a cost estimate
+///
+/// Tiers are looked up to name the tier subtotals.
+#[must_use]
+pub fn export_cost_estimate(estimate: &CostEstimate, tier_registry: &TierRegistry) -> String {
+    let tier_name = |tier: Option<uuid::Uuid>| {
+        tier.and_then(|id| tier_registry.get(&id))
+            .map_or_else(String::new, |tier| csv_field(&tier.name))
+    };
+    let mut rows =
+        vec!["element,name,kind,material,phase,tier,quantity,unit,rate,cost".to_string()];
+    for line in &estimate.lines {
+        rows.push(format!(
+            "{},{},{},{},{},{},{:.3},{},{:.2},{:.2}",
+            line.element,
+            csv_field(&line.name),
+            line.kind.label(),
+            csv_field(line.material.as_deref().unwrap_or_default()),
+            line.phase.label(),
+            tier_name(line.tier),
+            line.quantity,
+            line.unit.label(),
+            line.rate,
+            line.cost()
+        ));
+    }
+    for (phase, cost) in estimate.by_phase() {
+        rows.push(format!(",Phase subtotal,,,{},,,,,{cost:.2}", phase.label()));
+    }
+    for (tier, cost) in estimate.by_tier() {
+        rows.push(format!(
+            ",Tier subtotal,,,,{},,,,{cost:.2}",
+            tier_name(tier)
+        ));
+    }
+    rows.push(format!(",Total,,,,,,,,{:.2}", estimate.total()));
+    rows.push(String::new());
+    rows.join("\n")
+}
+
+/// Write a cost estimate as a CSV file
+///
+/// # Errors
+/// Returns an error if the file cannot be written.
+#[tracing::instrument(skip_all, fields(path = %path.display()))]
+pub fn write_cost_estimate(
+    path: &Path,
+    estimate: &CostEstimate,
+    tier_registry: &TierRegistry,
+) -> std::io::Result<()> {
+    std::fs::write(path, export_cost_estimate(estimate, tier_registry))?;
+    tracing::info!(
+        lines = estimate.lines.len(),
+        total = estimate.total(),
+        "wrote cost estimate"
+    );
+    Ok(())
+}
@@ -0,0 +1,166 @@
+//! CSV fields and rows shared by the table imports and the reports
+//!
+//! Reports quote a field holding a separator, quote or line break, doubling
+//! the quotes inside it. Imports read the same quoting back, so a name
+//! written to a report survives being read again, and a quoted field may
+//! run over several lines. Fields are trimmed outside their quotes.
+//!
+//! Blank lines and lines starting with `#` are skipped. Tables may start
+//! with a header row, which each import recognises by a field that should
+//! be a number and is not, and is allowed only as the first row.
+
+/// One row of a CSV table
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct CsvRow<'a> {
+    /// The 1-based line number the row starts on
+    pub line: usize,
+    /// The row as written, for error messages
+    pub text: &'a str,
+    /// The row's fields, unquoted and trimmed
+    pub fields: Vec<String>,
+    /// Whether it is the table's first row, which may be a header
+    pub first: bool,
+}
+
+impl CsvRow<'_> {
+    /// A field by position, empty if the row is shorter
+    pub fn field(&self, index: usize) -> &str {
+        self.fields.get(index).map_or("", String::as_str)
+    }
+}
+
+/// Quote a CSV field if it holds a separator, quote or line break, or
+/// white space at either end that reading it back would trim
+pub(crate) fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) || value.trim() != value {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// The rows of a CSV table, skipping blank lines and `#` comments
+pub(crate) fn csv_rows(text: &str) -> Vec<CsvRow<'_>> {
+    let mut rows = Vec::new();
+    let mut line = 1;
+    let mut rest = text;
+    while !rest.is_empty() {
+        let (length, breaks) = record_length(rest);
+        let record = &rest[..length];
+        let trimmed = record.trim();
+        if !trimmed.is_empty() && !trimmed.starts_with('#') {
+            rows.push(CsvRow {
+                line,
+                text: trimmed,
+                fields: split_record(trimmed),
+                first: rows.is_empty(),
+            });
+        }
+        line += breaks;
+        rest = &rest[length..];
+        rest = rest.strip_prefix('\n').unwrap_or(rest);
+    }
+    rows
+}
+
+/// The length of the record at the start of some text, up to the first
+/// line break outside quotes, and the lines it spans
+fn record_length(text: &str) -> (usize, usize) {
+    let mut quoted = false;
+    let mut field_start = true;
+    let mut lines = 1;
+    for (index, character) in text.char_indices() {
+        match character {
+            '"' if quoted || field_start => quoted = !quoted,
+            '\n' if quoted => lines += 1,
+            '\n' => return (index, lines),
+            ',' if !quoted => field_start = true,
+            character if !character.is_whitespace() => field_start = false,
+            _ => {}
+        }
+    }
+    (text.len(), lines)
+}
+
+/// Split a record into its fields, unquoting quoted ones
+///
+/// A quote opens a quoted field only at the start of the field; anywhere
+/// else it is part of the text, as in `5" pipe`.
+fn split_record(record: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    // Whether the field is quoted, and whether its closing quote was read
+    let mut quoted = false;
+    let mut closed = false;
+    let mut characters = record.chars().peekable();
+    while let Some(character) = characters.next() {
+        match character {
+            '"' if quoted && !closed && characters.peek() == Some(&'"') => {
+                characters.next();
+                field.push('"');
+            }
+            '"' if quoted && !closed => closed = true,
+            '"' if !quoted && field.trim().is_empty() => {
+                field.clear();
+                quoted = true;
+            }
+            ',' if !quoted || closed => {
+                fields.push(finish_field(std::mem::take(&mut field), quoted));
+                quoted = false;
+                closed = false;
+            }
+            // White space between a closing quote and the separator
+            _ if closed => {}
+            character => field.push(character),
+        }
+    }
+    fields.push(finish_field(field, quoted));
+    fields
+}
+
+/// A field as read: quoted fields keep their text as quoted, others lose
+/// the white space around them
+fn finish_field(field: String, quoted: bool) -> String {
+    if quoted {
+        field
+    } else {
+        field.trim().to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quoted_fields_round_trip() {
+        let names = [
+            "Office, north",
+            "The \"big\" room",
+            "Line\nbreak",
+            "5\" pipe",
+            " Padded ",
+        ];
+        let text = names
+            .iter()
+            .map(|name| format!("{},1", csv_field(name)))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let rows = csv_rows(&text);
+        let read: Vec<&str> = rows.iter().map(|row| row.field(0)).collect();
+        assert_eq!(read, names);
+        assert_eq!(rows[3].line, 5);
+        let inch = csv_rows("5\" pipe,1");
+        assert_eq!(inch[0].fields, ["5\" pipe", "1"]);
+    }
+
+    #[test]
+    fn blank_lines_and_comments_are_skipped() {
+        let rows = csv_rows("# rates\n\nname , area\r\nLobby,40\n");
+        assert_eq!(rows.len(), 2);
+        assert!(rows[0].first);
+        assert_eq!(rows[0].fields, ["name", "area"]);
+        assert_eq!(rows[1].line, 4);
+        assert!(!rows[1].first);
+    }
+}
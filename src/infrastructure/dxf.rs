@@ -0,0 +1,701 @@
+/// DXF line work import
+///
+/// Reads LINE, LWPOLYLINE, ARC and CIRCLE entities from the ENTITIES
+/// section of an ASCII DXF file as 2D paths, then stores them as sketch
+/// segments on a horizontal work plane. Drawing X runs along the plane's
+/// X axis and drawing Y points to model north (-Z). Arcs, circles and
+/// polyline bulges are split into straight segments. Each DXF layer goes
+/// into a tier, named after the layer unless mapped otherwise.
+use crate::domain::geometry::PointIndex;
+use crate::domain::{
+    same_points, ExternalId, ExternalIdMap, ExternalSource, GeometryRegistry, Point, TierRegistry,
+};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use uuid::Uuid;
+
+/// Largest angle in degrees swept by one segment of a split arc
+const MAX_ARC_STEP_DEGREES: f64 = 10.0;
+
+/// A path of drawing coordinates from one DXF entity
+#[derive(Debug, Clone, PartialEq)]
+pub struct DxfPath {
+    /// The DXF layer of the entity
+    pub layer: String,
+    /// The entity's handle, unique within the drawing, if it has one
+    pub handle: Option<String>,
+    /// Drawing X and Y of each point
+    pub points: Vec<(f64, f64)>,
+    /// Whether the last point joins back to the first
+    pub closed: bool,
+}
+
+/// The line work read from a DXF file
+#[derive(Debug, Clone, Default)]
+pub struct DxfDrawing {
+    /// Meters per drawing unit from the `$INSUNITS` header, if recognized
+    pub units: Option<f64>,
+    /// The paths, in file order
+    pub paths: Vec<DxfPath>,
+    /// Entities of other types that were skipped
+    pub skipped: usize,
+}
+
+/// Errors raised while reading a DXF file
+#[derive(Debug)]
+pub enum DxfError {
+    /// The file could not be read
+    Io(std::io::Error),
+    /// A group code or value could not be parsed
+    Parse {
+        /// The 1-based line number
+        line: usize,
+        /// What was wrong with it
+        message: String,
+    },
+}
+
+impl std::fmt::Display for DxfError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DxfError::Io(error) => write!(f, "Could not read DXF file: {error}"),
+            DxfError::Parse { line, message } => write!(f, "DXF line {line}: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for DxfError {}
+
+impl From<std::io::Error> for DxfError {
+    fn from(error: std::io::Error) -> Self {
+        DxfError::Io(error)
+    }
+}
+
+/// Settings for a DXF import
+#[derive(Debug, Clone)]
+pub struct DxfImportSettings {
+    /// Model position of the drawing origin; its Y is the work plane
+    pub origin: Point,
+    /// Turn of the drawing about the vertical in radians, counter-clockwise
+    /// seen from above
+    pub rotation: f32,
+    /// Meters per drawing unit, overriding the file's `$INSUNITS`; meters
+    /// are assumed if neither is given
+    pub scale: Option<f64>,
+    /// Tier name for each DXF layer; `None` skips the layer, and unmapped
+    /// layers go into a tier named after the layer
+    pub layers: HashMap<String, Option<String>>,
+    /// File the entity handles are recorded under in the external ID
+    /// table, usually the drawing's path; imports under the same file
+    /// merge into each other
+    pub file: String,
+}
+
+impl Default for DxfImportSettings {
+    fn default() -> Self {
+        Self {
+            origin: Point {
+                x: 0.0,
+                y: 0.0,
+                z: 0.0,
+            },
+            rotation: 0.0,
+            scale: None,
+            layers: HashMap::new(),
+            file: String::new(),
+        }
+    }
+}
+
+/// What a DXF import changed
+#[derive(Debug, Clone, Default)]
+pub struct DxfImport {
+    /// The tier each imported DXF layer went into
+    pub tiers: HashMap<String, Uuid>,
+    /// The vertices added, in file order
+    pub vertices: Vec<Uuid>,
+    /// The segments added, in file order
+    pub segments: Vec<Uuid>,
+    /// The segments of entities imported before, given their new ends
+    pub updated: Vec<Uuid>,
+    /// The segments of entities no longer in the drawing, removed with the
+    /// vertices only they used
+    pub removed: Vec<Uuid>,
+    /// Segments edited here that the drawing changed or removed too; they
+    /// keep their own ends
+    pub conflicts: Vec<Uuid>,
+}
+
+/// Read the line work of a DXF file
+///
+/// # Errors
+/// Returns an error if the file cannot be read or parsed.
+#[tracing::instrument(skip_all, fields(path = %path.display()))]
+pub fn read_dxf(path: &Path) -> Result<DxfDrawing, DxfError> {
+    let bytes = std::fs::read(path)?;
+    let drawing = parse_dxf(&String::from_utf8_lossy(&bytes))?;
+    tracing::info!(
+        paths = drawing.paths.len(),
+        skipped = drawing.skipped,
+        "read DXF file"
+    );
+    Ok(drawing)
+}
+
+/// Parse the text of an ASCII DXF file
+///
+/// # Errors
+/// Returns an error naming the first group code or number that cannot be
+/// parsed.
+pub fn parse_dxf(text: &str) -> Result<DxfDrawing, DxfError> {
+    let records = records(text)?;
+    let mut drawing = DxfDrawing::default();
+    let mut section = "";
+    for record in &records {
+        match record.kind {
+            "SECTION" => {
+                section = record.text(2).unwrap_or_default();
+                if section == "HEADER" {
+                    drawing.units = header_units(record)?;
+                }
+            }
+            "ENDSEC" => section = "",
+            _ if section == "ENTITIES" => match entity_path(record)? {
+                Some(path) => drawing.paths.push(path),
+                None => drawing.skipped += 1,
+            },
+            _ => {}
+        }
+    }
+    Ok(drawing)
+}
+
+/// Store the drawing's paths as sketch segments on the work plane
+///
+/// Points within a tier's linear tolerance of each other become one
+/// vertex, so touching entities share their end points.
+///
+/// Each segment is recorded in the external ID table under its entity's
+/// handle, with where its ends were imported. Importing the same file
+/// again merges the changes into the model: segments of entities the
+/// drawing moved get their new ends, keeping their IDs and the vertices
+/// that did not move, and those of entities gone from the drawing are
+/// removed. A segment edited here since it was imported is left as it is
+/// and reported as a conflict if the drawing changed it too.
+#[tracing::instrument(skip_all, fields(paths = drawing.paths.len()))]
+pub fn import_dxf(
+    geometry_registry: &mut GeometryRegistry,
+    tier_registry: &mut TierRegistry,
+    drawing: &DxfDrawing,
+    settings: &DxfImportSettings,
+    external_ids: &mut ExternalIdMap,
+) -> DxfImport {
+    let scale = settings.scale.or(drawing.units).unwrap_or(1.0);
+    let (sin, cos) = settings.rotation.sin_cos();
+    #[allow(clippy::cast_possible_truncation)]
+    let to_model = |(x, y): (f64, f64)| {
+        let (along, across) = ((x * scale) as f32, (-y * scale) as f32);
+        Point {
+            x: settings.origin.x + along * cos + across * sin,
+            y: settings.origin.y,
+            z: settings.origin.z - along * sin + across * cos,
+        }
+    };
+
+    // Segments of the last import of this file, by external ID
+    let mut previous = external_ids.from_file(ExternalSource::Dxf, &settings.file);
+    previous.retain(|_, segment| geometry_registry.segments.get(segment).is_some());
+    let mut import = DxfImport::default();
+    let mut welds: HashMap<Uuid, Weld> = HashMap::new();
+    // Vertices the merge may have left unused
+    let mut released = Vec::new();
+    for path in &drawing.paths {
+        let Some(tier) = layer_tier(tier_registry, settings, &path.layer) else {
+            continue;
+        };
+        import.tiers.insert(path.layer.clone(), tier);
+        let weld = welds.entry(tier).or_insert_with(|| {
+            Weld::seeded(
+                geometry_registry,
+                tier_registry.tolerance(&tier).linear,
+                &previous,
+            )
+        });
+
+        let mut points: Vec<Point> = path.points.iter().map(|point| to_model(*point)).collect();
+        if path.closed {
+            points.extend(points.first().cloned());
+        }
+        let slots: Vec<usize> = points.iter().map(|point| weld.slot(point)).collect();
+        let mut added = Vec::new();
+        let spans = slots.windows(2).filter(|pair| pair[0] != pair[1]);
+        for (part, pair) in spans.enumerate() {
+            let external = path.handle.as_ref().map(|handle| ExternalId {
+                source: ExternalSource::Dxf,
+                file: settings.file.clone(),
+                key: handle.clone(),
+                part,
+            });
+            let reused = external
+                .as_ref()
+                .and_then(|external| previous.remove(external));
+            let (segment, merged) = if let Some(segment) = reused {
+                let merged = merge_segment(
+                    geometry_registry,
+                    external_ids,
+                    weld,
+                    segment,
+                    [pair[0], pair[1]],
+                    &mut import,
+                    &mut released,
+                );
+                (segment, merged)
+            } else {
+                let start = weld.vertex(geometry_registry, pair[0], &mut import);
+                let end = weld.vertex(geometry_registry, pair[1], &mut import);
+                let segment = geometry_registry.segments.create_and_store(&start, &end);
+                added.push(segment);
+                (segment, true)
+            };
+            if let Some(external) = external {
+                external_ids.assign(segment, external);
+                if merged {
+                    let ends = vec![
+                        weld.index.points[pair[0]].clone(),
+                        weld.index.points[pair[1]].clone(),
+                    ];
+                    external_ids.imported.insert(segment, ends);
+                }
+            }
+        }
+        if let Some(tier) = tier_registry.get_mut(&tier) {
+            tier.geometry.extend(&added);
+        }
+        import.segments.extend(added);
+    }
+
+    for (tier, weld) in &welds {
+        if let Some(tier) = tier_registry.get_mut(tier) {
+            tier.geometry
+                .extend(weld.vertices[weld.reused..].iter().flatten());
+        }
+    }
+    let mut gone: Vec<Uuid> = previous.into_values().collect();
+    gone.sort();
+    remove_stale(
+        geometry_registry,
+        tier_registry,
+        external_ids,
+        &gone,
+        released,
+        &mut import,
+    );
+    import
+}
+
+/// The tier a layer goes into, created if needed; None if the layer is
+/// skipped
+fn layer_tier(
+    tier_registry: &mut TierRegistry,
+    settings: &DxfImportSettings,
+    layer: &str,
+) -> Option<Uuid> {
+    let tier_name = match settings.layers.get(layer) {
+        Some(Some(name)) => name.clone(),
+        Some(None) => return None,
+        None => layer.to_string(),
+    };
+    let existing = tier_registry
+        .sorted()
+        .into_iter()
+        .find(|tier| tier.name == tier_name)
+        .map(|tier| tier.id);
+    Some(existing.unwrap_or_else(|| tier_registry.create_and_store(&tier_name, None, None)))
+}
+
+/// A tier's welding index and the vertex at each of its points, created
+/// only once a segment needs it
+struct Weld {
+    /// The distinct points of the tier
+    index: PointIndex,
+    /// The vertex at each point, if one exists yet
+    vertices: Vec<Option<Uuid>>,
+    /// How many vertices were taken from the last import; those are held
+    /// by their tiers already
+    reused: usize,
+    /// Distance within which points are one
+    tolerance: f32,
+}
+
+impl Weld {
+    /// A welding index seeded with the vertices of the last import, so
+    /// points that did not move keep their vertex
+    fn seeded(
+        geometry_registry: &GeometryRegistry,
+        tolerance: f32,
+        previous: &HashMap<ExternalId, Uuid>,
+    ) -> Self {
+        let mut weld = Weld {
+            index: PointIndex::new(tolerance),
+            vertices: Vec::new(),
+            reused: 0,
+            tolerance,
+        };
+        let mut segments: Vec<_> = previous
+            .values()
+            .filter_map(|segment| geometry_registry.segments.get(segment))
+            .collect();
+        segments.sort_by_key(|segment| segment.id);
+        for id in segments.iter().flat_map(|segment| segment.vertices) {
+            if let Some(vertex) = geometry_registry.vertices.get(&id) {
+                let slot = weld.slot(&vertex.position);
+                weld.vertices[slot].get_or_insert(id);
+            }
+        }
+        weld.reused = weld.vertices.len();
+        weld
+    }
+
+    /// The index of a point, adding it if no point lies within tolerance
+    fn slot(&mut self, point: &Point) -> usize {
+        let slot = self.index.insert(point);
+        if slot == self.vertices.len() {
+            self.vertices.push(None);
+        }
+        slot
+    }
+
+    /// The vertex at a point, created if it does not exist yet
+    fn vertex(
+        &mut self,
+        geometry_registry: &mut GeometryRegistry,
+        slot: usize,
+        import: &mut DxfImport,
+    ) -> Uuid {
+        if let Some(vertex) = self.vertices[slot] {
+            return vertex;
+        }
+        let position = self.index.points[slot].clone();
+        let vertex = geometry_registry.vertices.create_and_store(position);
+        self.vertices[slot] = Some(vertex);
+        import.vertices.push(vertex);
+        vertex
+    }
+}
+
+/// Bring a segment imported before up to date with its entity
+///
+/// A segment the drawing moved gets its new ends, unless it was edited
+/// here since it was imported: then it keeps its own ends and is reported
+/// as a conflict. Returns false on a conflict.
+fn merge_segment(
+    geometry_registry: &mut GeometryRegistry,
+    external_ids: &ExternalIdMap,
+    weld: &mut Weld,
+    segment: Uuid,
+    slots: [usize; 2],
+    import: &mut DxfImport,
+    released: &mut Vec<Uuid>,
+) -> bool {
+    let ends = [
+        weld.index.points[slots[0]].clone(),
+        weld.index.points[slots[1]].clone(),
+    ];
+    let current = segment_ends(geometry_registry, &segment);
+    let imported = external_ids
+        .imported
+        .get(&segment)
+        .map_or(current.as_slice(), Vec::as_slice);
+    if same_points(imported, &ends, weld.tolerance) {
+        return true;
+    }
+    if external_ids.edited_locally(&segment, &current, weld.tolerance) {
+        import.conflicts.push(segment);
+        return false;
+    }
+    let start = weld.vertex(geometry_registry, slots[0], import);
+    let end = weld.vertex(geometry_registry, slots[1], import);
+    if let Some(segment) = geometry_registry.segments.get_mut(&segment) {
+        released.extend(segment.vertices);
+        // Stored smaller ID first, as new segments are
+        segment.vertices = [start.min(end), start.max(end)];
+    }
+    import.updated.push(segment);
+    true
+}
+
+/// Where a segment's ends are
+fn segment_ends(geometry_registry: &GeometryRegistry, segment: &Uuid) -> Vec<Point> {
+    geometry_registry
+        .segments
+        .get(segment)
+        .map(|segment| {
+            segment
+                .vertices
+                .iter()
+                .filter_map(|vertex| geometry_registry.vertices.get(vertex))
+                .map(|vertex| vertex.position.clone())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Remove the segments of entities gone from the drawing, then the
+/// released vertices no segment uses any more
+///
+/// Gone segments edited here since they were imported are kept and
+/// reported as conflicts.
+fn remove_stale(
+    geometry_registry: &mut GeometryRegistry,
+    tier_registry: &mut TierRegistry,
+    external_ids: &mut ExternalIdMap,
+    gone: &[Uuid],
+    mut released: Vec<Uuid>,
+    import: &mut DxfImport,
+) {
+    for segment in gone {
+        let ends = segment_ends(geometry_registry, segment);
+        let tolerance = tier_registry.tolerance_for(segment).linear;
+        if external_ids.edited_locally(segment, &ends, tolerance) {
+            import.conflicts.push(*segment);
+            continue;
+        }
+        if let Some(segment) = geometry_registry.segments.get(segment) {
+            released.extend(segment.vertices);
+        }
+        geometry_registry.segments.remove(segment);
+        external_ids.remove(segment);
+        import.removed.push(*segment);
+    }
+    let used: HashSet<Uuid> = geometry_registry
+        .segments
+        .segments
+        .values()
+        .flat_map(|segment| segment.vertices)
+        .collect();
+    let mut removed: HashSet<Uuid> = import.removed.iter().copied().collect();
+    for vertex in released {
+        if !used.contains(&vertex) && removed.insert(vertex) {
+            geometry_registry.vertices.remove(&vertex);
+        }
+    }
+    if removed.is_empty() {
+        return;
+    }
+    for tier in tier_registry.tiers.values_mut() {
+        tier.geometry.retain(|id| !removed.contains(id));
+    }
+}
+
+/// A DXF record: an entity or section marker and its group codes
+struct Record<'a> {
+    /// The value of the record's group code 0
+    kind: &'a str,
+    /// The following group codes and values with their line numbers
+    fields: Vec<(i32, &'a str, usize)>,
+}
+
+impl<'a> Record<'a> {
+    /// The first value of a group code
+    fn text(&self, code: i32) -> Option<&'a str> {
+        self.fields
+            .iter()
+            .find(|(field_code, _, _)| *field_code == code)
+            .map(|(_, value, _)| *value)
+    }
+
+    /// The first value of a group code as a number, zero if absent
+    fn number(&self, code: i32) -> Result<f64, DxfError> {
+        self.fields
+            .iter()
+            .find(|(field_code, _, _)| *field_code == code)
+            .map_or(Ok(0.0), |(_, value, line)| parse_number(value, *line))
+    }
+}
+
+/// Split the group code and value line pairs into records at each code 0
+fn records(text: &str) -> Result<Vec<Record<'_>>, DxfError> {
+    let lines: Vec<&str> = text.lines().map(str::trim).collect();
+    let mut records: Vec<Record> = Vec::new();
+    for (pair, chunk) in lines.chunks(2).enumerate() {
+        let &[code, value] = chunk else {
+            break;
+        };
+        let line = pair * 2 + 1;
+        let code: i32 = code.parse().map_err(|_| DxfError::Parse {
+            line,
+            message: format!("expected a group code, found \"{code}\""),
+        })?;
+        if code == 0 {
+            records.push(Record {
+                kind: value,
+                fields: Vec::new(),
+            });
+        } else if let Some(record) = records.last_mut() {
+            record.fields.push((code, value, line + 1));
+        }
+    }
+    Ok(records)
+}
+
+/// Parse a group value as a number
+fn parse_number(value: &str, line: usize) -> Result<f64, DxfError> {
+    value.parse().map_err(|_| DxfError::Parse {
+        line,
+        message: format!("expected a number, found \"{value}\""),
+    })
+}
+
+/// Meters per drawing unit from the header's `$INSUNITS` variable
+fn header_units(header: &Record) -> Result<Option<f64>, DxfError> {
+    let Some(position) = header
+        .fields
+        .iter()
+        .position(|(code, value, _)| *code == 9 && *value == "$INSUNITS")
+    else {
+        return Ok(None);
+    };
+    let Some((_, value, line)) = header.fields.get(position + 1) else {
+        return Ok(None);
+    };
+    #[allow(clippy::cast_possible_truncation)]
+    let code = parse_number(value, *line)? as i64;
+    Ok(match code {
+        1 => Some(0.0254),
+        2 => Some(0.3048),
+        4 => Some(0.001),
+        5 => Some(0.01),
+        6 => Some(1.0),
+        _ => None,
+    })
+}
+
+/// The path of a supported entity, or None for other entity types
+fn entity_path(record: &Record) -> Result<Option<DxfPath>, DxfError> {
+    let layer = record.text(8).unwrap_or("0").to_string();
+    let handle = record.text(5).map(str::to_string);
+    let (points, closed) = match record.kind {
+        "LINE" => (
+            vec![
+                (record.number(10)?, record.number(20)?),
+                (record.number(11)?, record.number(21)?),
+            ],
+            false,
+        ),
+        "LWPOLYLINE" => lightweight_polyline(record)?,
+        "ARC" => {
+            let (start, mut end) = (record.number(50)?, record.number(51)?);
+            if end <= start {
+                end += 360.0;
+            }
+            let center = (record.number(10)?, record.number(20)?);
+            let points = arc_points(center, record.number(40)?, start, end);
+            (points, false)
+        }
+        "CIRCLE" => {
+            let center = (record.number(10)?, record.number(20)?);
+            let mut points = arc_points(center, record.number(40)?, 0.0, 360.0);
+            points.pop();
+            (points, true)
+        }
+        _ => return Ok(None),
+    };
+    Ok(Some(DxfPath {
+        layer,
+        handle,
+        points,
+        closed,
+    }))
+}
+
+/// Points of a lightweight polyline, with bulged spans split into arcs
+fn lightweight_polyline(record: &Record) -> Result<(Vec<(f64, f64)>, bool), DxfError> {
+    #[allow(clippy::cast_possible_truncation)]
+    let closed = record.number(70)? as i64 & 1 == 1;
+    // Vertices as (x, y, bulge); a bulge belongs to the span after its vertex
+    let mut vertices: Vec<(f64, f64, f64)> = Vec::new();
+    for (code, value, line) in &record.fields {
+        let number = || parse_number(value, *line);
+        match (code, vertices.last_mut()) {
+            (10, _) => vertices.push((number()?, 0.0, 0.0)),
+            (20, Some(vertex)) => vertex.1 = number()?,
+            (42, Some(vertex)) => vertex.2 = number()?,
+            _ => {}
+        }
+    }
+
+    let mut points = Vec::new();
+    let spans = if closed {
+        vertices.len()
+    } else {
+        vertices.len().saturating_sub(1)
+    };
+    for span in 0..spans {
+        let (x, y, bulge) = vertices[span];
+        let (next_x, next_y, _) = vertices[(span + 1) % vertices.len()];
+        points.push((x, y));
+        if bulge.abs() > 1e-9 {
+            let mut arc = bulge_points((x, y), (next_x, next_y), bulge);
+            arc.pop();
+            points.extend(arc.into_iter().skip(1));
+        }
+    }
+    if !closed {
+        points.extend(vertices.last().map(|(x, y, _)| (*x, *y)));
+    }
+    Ok((points, closed))
+}
+
+/// Points along a counter-clockwise arc between two angles in degrees,
+/// including both ends
+fn arc_points(center: (f64, f64), radius: f64, start: f64, end: f64) -> Vec<(f64, f64)> {
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    let steps = ((end - start) / MAX_ARC_STEP_DEGREES).ceil().max(1.0) as usize;
+    (0..=steps)
+        .map(|step| {
+            #[allow(clippy::cast_precision_loss)]
+            let angle = (start + (end - start) * step as f64 / steps as f64).to_radians();
+            (
+                center.0 + radius * angle.cos(),
+                center.1 + radius * angle.sin(),
+            )
+        })
+        .collect()
+}
+
+/// Points along a bulged polyline span, including both ends
+///
+/// The bulge is the tangent of a quarter of the included angle, positive
+/// for a counter-clockwise arc.
+fn bulge_points(start: (f64, f64), end: (f64, f64), bulge: f64) -> Vec<(f64, f64)> {
+    let included = 4.0 * bulge.atan();
+    let (dx, dy) = (end.0 - start.0, end.1 - start.1);
+    let chord = dx.hypot(dy);
+    if chord <= f64::EPSILON {
+        return vec![start, end];
+    }
+    // The center lies off the chord's midpoint, left of it for a positive bulge
+    let offset = chord / (2.0 * (included / 2.0).tan());
+    let center = (
+        f64::midpoint(start.0, end.0) - dy / chord * offset,
+        f64::midpoint(start.1, end.1) + dx / chord * offset,
+    );
+    let radius = (start.0 - center.0).hypot(start.1 - center.1);
+    let first = (start.1 - center.1).atan2(start.0 - center.0).to_degrees();
+    let mut points = if included > 0.0 {
+        arc_points(center, radius, first, first + included.to_degrees())
+    } else {
+        let mut points = arc_points(center, radius, first + included.to_degrees(), first);
+        points.reverse();
+        points
+    };
+    // Land exactly on the span's end points
+    if let Some(point) = points.first_mut() {
+        *point = start;
+    }
+    if let Some(point) = points.last_mut() {
+        *point = end;
+    }
+    points
+}
@@ -0,0 +1,200 @@
+/// DXF plan export
+///
+/// Writes plans as ASCII DXF line work: the edges of the solids seen from
+/// above, as LINE entities on the office standards layer of their element
+/// kind. Drawing X is model X and drawing Y points to model north (-Z),
+/// matching the DXF import, in meters. Edges seen end-on, such as the
+/// vertical edges of walls, vanish, and edges that coincide in plan are
+/// drawn once. Plans can be split by story, a solid belonging to the story
+/// its base is in.
+use crate::domain::{
+    collect_export_solids, ElementKind, ElementRegistry, ExportFilter, GeometryRegistry,
+    LevelRegistry,
+};
+use crate::infrastructure::standards::OfficeStandards;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+/// Shortest edge drawn in plan, in meters
+const MIN_PLAN_LENGTH: f32 = 1e-4;
+/// How far below a level a solid's base may be and still start on it
+const LEVEL_TOLERANCE: f32 = 1e-3;
+
+/// Settings for a DXF plan export
+#[derive(Debug, Clone, Default)]
+pub struct DxfPlanSettings {
+    /// Which solids to export
+    pub filter: ExportFilter,
+    /// Layer names for element kinds
+    pub standards: OfficeStandards,
+    /// Whether each level gets its own plan, rather than one plan of the
+    /// whole model
+    pub per_level: bool,
+}
+
+/// One plan drawing
+#[derive(Debug, Clone, PartialEq)]
+pub struct DxfPlan {
+    /// The level the plan shows, or None for the whole model
+    pub level: Option<String>,
+    /// The DXF text
+    pub text: String,
+}
+
+impl DxfPlan {
+    /// The plan's file name from a stem, such as `model-level-1.dxf`
+    #[must_use]
+    pub fn file_name(&self, stem: &str) -> String {
+        match &self.level {
+            None => format!("{stem}.dxf"),
+            Some(level) => {
+                let level: String = level
+                    .to_lowercase()
+                    .chars()
+                    .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+                    .collect();
+                format!("{stem}-{level}.dxf")
+            }
+        }
+    }
+}
+
+/// Write the plans beside each other in a folder, named from a stem
+/// Returns the paths written
+///
+/// # Errors
+/// Returns an error if a file cannot be written.
+#[tracing::instrument(skip_all, fields(folder = %folder.display()))]
+pub fn write_dxf_plans(
+    folder: &Path,
+    stem: &str,
+    geometry_registry: &GeometryRegistry,
+    element_registry: &ElementRegistry,
+    level_registry: &LevelRegistry,
+    settings: &DxfPlanSettings,
+) -> std::io::Result<Vec<PathBuf>> {
+    let plans = export_dxf_plans(
+        geometry_registry,
+        element_registry,
+        level_registry,
+        settings,
+    );
+    let mut paths = Vec::new();
+    for plan in plans {
+        let path = folder.join(plan.file_name(stem));
+        std::fs::write(&path, plan.text)?;
+        paths.push(path);
+    }
+    tracing::info!(plans = paths.len(), "wrote DXF plans");
+    Ok(paths)
+}
+
+/// Build the plans, one per level when asked and the model has levels
+#[must_use]
+pub fn export_dxf_plans(
+    geometry_registry: &GeometryRegistry,
+    element_registry: &ElementRegistry,
+    level_registry: &LevelRegistry,
+    settings: &DxfPlanSettings,
+) -> Vec<DxfPlan> {
+    let levels = level_registry.sorted();
+    // Solids by the index of the level they start on, or 0 for one plan
+    let mut stories: BTreeMap<usize, Vec<Uuid>> = BTreeMap::new();
+    for export in collect_export_solids(geometry_registry, element_registry, &settings.filter) {
+        let solid = export.solid;
+        let story = if settings.per_level {
+            geometry_registry
+                .solid_loops(&solid.id)
+                .map(|loops| loops.iter().flatten().map(|p| p.y).fold(f32::MAX, f32::min))
+                .and_then(|base| level_registry.story_at(base, LEVEL_TOLERANCE))
+                .and_then(|level| levels.iter().position(|l| l.id == level.id))
+                .unwrap_or(0)
+        } else {
+            0
+        };
+        stories.entry(story).or_default().push(solid.id);
+    }
+    if !settings.per_level || levels.is_empty() {
+        let solids = stories.into_values().flatten().collect::<Vec<_>>();
+        let text = plan_text(geometry_registry, element_registry, &solids, settings);
+        return vec![DxfPlan { level: None, text }];
+    }
+    levels
+        .iter()
+        .enumerate()
+        .map(|(index, level)| DxfPlan {
+            level: Some(level.name.clone()),
+            text: plan_text(
+                geometry_registry,
+                element_registry,
+                stories.get(&index).map_or(&[], Vec::as_slice),
+                settings,
+            ),
+        })
+        .collect()
+}
+
+/// A plan point rounded to a tenth of a millimeter, for finding edges
+/// that coincide in plan
+fn plan_key(x: f32, y: f32) -> (i64, i64) {
+    #[allow(clippy::cast_possible_truncation)]
+    let round = |value: f32| (f64::from(value) * 10_000.0).round() as i64;
+    (round(x), round(y))
+}
+
+/// The DXF text of a plan of some solids
+fn plan_text(
+    geometry_registry: &GeometryRegistry,
+    element_registry: &ElementRegistry,
+    solids: &[Uuid],
+    settings: &DxfPlanSettings,
+) -> String {
+    let mut lines: BTreeMap<String, Vec<[f32; 4]>> = BTreeMap::new();
+    let mut drawn = BTreeSet::new();
+    for solid_id in solids {
+        let kind = element_registry
+            .element_of_solid(solid_id)
+            .map_or(ElementKind::Generic, |element| element.kind);
+        let layer = settings.standards.layer_for(kind);
+        for points in geometry_registry.solid_loops(solid_id).unwrap_or_default() {
+            for (index, start) in points.iter().enumerate() {
+                let end = &points[(index + 1) % points.len()];
+                let (x1, y1, x2, y2) = (start.x, -start.z, end.x, -end.z);
+                if (x2 - x1).hypot(y2 - y1) < MIN_PLAN_LENGTH {
+                    continue;
+                }
+                let (a, b) = (plan_key(x1, y1), plan_key(x2, y2));
+                if drawn.insert((layer.clone(), a.min(b), a.max(b))) {
+                    lines
+                        .entry(layer.clone())
+                        .or_default()
+                        .push([x1, y1, x2, y2]);
+                }
+            }
+        }
+    }
+
+    let mut text = String::from("0\nSECTION\n2\nHEADER\n9\n$ACADVER\n1\nAC1009\n");
+    text.push_str("9\n$INSUNITS\n70\n6\n0\nENDSEC\n");
+    let _ = write!(
+        text,
+        "0\nSECTION\n2\nTABLES\n0\nTABLE\n2\nLAYER\n70\n{}\n",
+        lines.len()
+    );
+    for layer in lines.keys() {
+        let _ = write!(text, "0\nLAYER\n2\n{layer}\n70\n0\n62\n7\n6\nCONTINUOUS\n");
+    }
+    text.push_str("0\nENDTAB\n0\nENDSEC\n0\nSECTION\n2\nENTITIES\n");
+    for (layer, segments) in &lines {
+        for [x1, y1, x2, y2] in segments {
+            let _ = write!(
+                text,
+                "0\nLINE\n8\n{layer}\n10\n{x1}\n20\n{y1}\n30\n0\n11\n{x2}\n21\n{y2}\n31\n0\n"
+            );
+        }
+    }
+    text.push_str("0\nENDSEC\n0\nEOF\n");
+    text
+}
@@ -0,0 +1,239 @@
+/// gbXML export
+///
+/// Writes the energy model as a gbXML 6.01 document for energy simulation
+/// tools. Each space becomes a `Space` with its closed shell and a `Zone`
+/// of its own; each thermal surface becomes a `Surface` naming the spaces
+/// on either side, with its windows and doors as openings. gbXML is Z-up,
+/// so points are rotated like the IFC export.
+use crate::domain::{
+    derive_energy_model, ElementKind, ElementRegistry, GeometryRegistry, Georeference, Point,
+    SurfaceKind, ThermalSurface, ThermalZone,
+};
+use crate::infrastructure::current_timestamp;
+use std::fmt::Write as _;
+use std::path::Path;
+
+/// Settings for a gbXML export
+#[derive(Debug, Clone)]
+pub struct GbxmlExportSettings {
+    /// Name written to the building
+    pub building_name: String,
+    /// ISO 8601 time stamp written to the document history
+    pub timestamp: String,
+    /// Map placement written as the campus location, if set
+    pub georeference: Option<Georeference>,
+}
+
+impl Default for GbxmlExportSettings {
+    fn default() -> Self {
+        Self {
+            building_name: "HarmonyArch project".to_string(),
+            timestamp: current_timestamp(),
+            georeference: None,
+        }
+    }
+}
+
+/// Write a gbXML file of the spaces and the elements around them
+///
+/// # Errors
+/// Returns an error if the file cannot be written.
+#[tracing::instrument(skip_all, fields(path = %path.display()))]
+pub fn write_gbxml(
+    path: &Path,
+    geometry_registry: &GeometryRegistry,
+    element_registry: &ElementRegistry,
+    settings: &GbxmlExportSettings,
+) -> std::io::Result<()> {
+    std::fs::write(
+        path,
+        export_gbxml(geometry_registry, element_registry, settings),
+    )?;
+    tracing::info!("wrote gbXML file");
+    Ok(())
+}
+
+/// Build the text of a gbXML document
+#[must_use]
+pub fn export_gbxml(
+    geometry_registry: &GeometryRegistry,
+    element_registry: &ElementRegistry,
+    settings: &GbxmlExportSettings,
+) -> String {
+    let model = derive_energy_model(element_registry, geometry_registry);
+
+    let mut text = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    text.push_str(
+        "<gbXML xmlns=\"http://www.gbxml.org/schema\" version=\"6.01\" temperatureUnit=\"C\" \
+         lengthUnit=\"Meters\" areaUnit=\"SquareMeters\" volumeUnit=\"CubicMeters\" \
+         useSIUnitsForResults=\"true\">\n  <Campus id=\"campus\">\n",
+    );
+    let _ = writeln!(
+        text,
+        "    <Name>{}</Name>",
+        xml_escape(&settings.building_name)
+    );
+    if let Some(georeference) = &settings.georeference {
+        text.push_str("    <Location>\n");
+        if let (Some(latitude), Some(longitude)) = (georeference.latitude, georeference.longitude) {
+            let _ = writeln!(text, "      <Latitude>{latitude:.6}</Latitude>");
+            let _ = writeln!(text, "      <Longitude>{longitude:.6}</Longitude>");
+        }
+        let _ = writeln!(
+            text,
+            "      <Elevation>{:.3}</Elevation>",
+            georeference.elevation
+        );
+        text.push_str("    </Location>\n");
+    }
+
+    let floor_area: f32 = model.zones.iter().map(|zone| zone.space.floor_area).sum();
+    let _ = write!(
+        text,
+        "    <Building id=\"building\" buildingType=\"Unknown\">\n      \
+         <Name>{}</Name>\n      <Area>{floor_area:.3}</Area>\n",
+        xml_escape(&settings.building_name)
+    );
+    for zone in &model.zones {
+        write_space(&mut text, zone);
+    }
+    text.push_str("    </Building>\n");
+
+    let mut opening_count = 0;
+    for (index, surface) in model.surfaces.iter().enumerate() {
+        write_surface(&mut text, index + 1, surface, &mut opening_count);
+    }
+    text.push_str("  </Campus>\n");
+
+    for zone in &model.zones {
+        let _ = writeln!(
+            text,
+            "  <Zone id=\"zone-{}\">\n    <Name>{}</Name>\n  </Zone>",
+            zone.space.element,
+            xml_escape(&zone.space.name)
+        );
+    }
+    let _ = write!(
+        text,
+        "  <DocumentHistory>\n    <ProgramInfo id=\"harmonyarch\">\n      \
+         <ProductName>HarmonyArch</ProductName>\n    </ProgramInfo>\n    \
+         <CreatedBy programId=\"harmonyarch\" date=\"{}\"/>\n  </DocumentHistory>\n</gbXML>\n",
+        xml_escape(&settings.timestamp)
+    );
+    text
+}
+
+/// Write a space with its closed shell
+fn write_space(text: &mut String, zone: &ThermalZone) {
+    let space = &zone.space;
+    let _ = write!(
+        text,
+        "      <Space id=\"space-{id}\" zoneIdRef=\"zone-{id}\">\n        \
+         <Name>{}</Name>\n        <Area>{:.3}</Area>\n        <Volume>{:.3}</Volume>\n        \
+         <ShellGeometry id=\"shell-{id}\">\n          <ClosedShell>\n",
+        xml_escape(&space.name),
+        space.floor_area,
+        space.volume,
+        id = space.element
+    );
+    for points in &zone.shell {
+        poly_loop(text, points, 12);
+    }
+    let _ = write!(
+        text,
+        "          </ClosedShell>\n        </ShellGeometry>\n        \
+         <CADObjectId>{}</CADObjectId>\n      </Space>\n",
+        space.element
+    );
+}
+
+/// Write a surface and its openings, numbering openings from `opening_count`
+fn write_surface(
+    text: &mut String,
+    number: usize,
+    surface: &ThermalSurface,
+    opening_count: &mut usize,
+) {
+    let exposed = matches!(
+        surface.kind,
+        SurfaceKind::ExteriorWall | SurfaceKind::Roof | SurfaceKind::RaisedFloor
+    );
+    let _ = writeln!(
+        text,
+        "    <Surface id=\"surface-{number}\" surfaceType=\"{}\" exposedToSun=\"{exposed}\">",
+        surface_type(surface.kind)
+    );
+    let _ = writeln!(text, "      <Name>{} {number}</Name>", surface.kind.label());
+    for space in &surface.spaces {
+        let _ = writeln!(
+            text,
+            "      <AdjacentSpaceId spaceIdRef=\"space-{space}\"/>"
+        );
+    }
+    text.push_str("      <PlanarGeometry>\n");
+    poly_loop(text, &surface.points, 8);
+    text.push_str("      </PlanarGeometry>\n");
+    for opening in &surface.openings {
+        *opening_count += 1;
+        let opening_type = if opening.kind == ElementKind::Door {
+            "NonSlidingDoor"
+        } else {
+            "FixedWindow"
+        };
+        let number = *opening_count;
+        let _ = writeln!(
+            text,
+            "      <Opening id=\"opening-{number}\" openingType=\"{opening_type}\">\n        \
+             <PlanarGeometry>"
+        );
+        poly_loop(text, &opening.points, 10);
+        let _ = writeln!(
+            text,
+            "        </PlanarGeometry>\n        <CADObjectId>{}</CADObjectId>\n      </Opening>",
+            opening.element
+        );
+    }
+    if let Some(element) = surface.element {
+        let _ = writeln!(text, "      <CADObjectId>{element}</CADObjectId>");
+    }
+    text.push_str("    </Surface>\n");
+}
+
+/// The gbXML surface type of a surface kind
+fn surface_type(kind: SurfaceKind) -> &'static str {
+    match kind {
+        SurfaceKind::ExteriorWall => "ExteriorWall",
+        SurfaceKind::InteriorWall => "InteriorWall",
+        SurfaceKind::Roof => "Roof",
+        SurfaceKind::InteriorFloor => "InteriorFloor",
+        SurfaceKind::SlabOnGrade => "SlabOnGrade",
+        SurfaceKind::RaisedFloor => "RaisedFloor",
+        SurfaceKind::Air => "Air",
+    }
+}
+
+/// Write a polygon loop of Z-up points at an indent
+fn poly_loop(text: &mut String, points: &[Point], indent: usize) {
+    let pad = " ".repeat(indent);
+    let _ = writeln!(text, "{pad}<PolyLoop>");
+    for point in points {
+        // Y-up model to Z-up, a rotation about X so windings keep their
+        // orientation
+        let _ = writeln!(
+            text,
+            "{pad}  <CartesianPoint><Coordinate>{:.4}</Coordinate><Coordinate>{:.4}</Coordinate>\
+             <Coordinate>{:.4}</Coordinate></CartesianPoint>",
+            point.x, -point.z, point.y
+        );
+    }
+    let _ = writeln!(text, "{pad}</PolyLoop>");
+}
+
+/// Escape text for an XML attribute or element
+fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,230 @@
+/// glTF export
+///
+/// Writes solids as a glTF 2.0 asset in a single `.gltf` file, its binary
+/// buffer embedded as a base64 data URI so nothing can go missing beside
+/// it. Each solid becomes a mesh and a node named after its element, with
+/// flat face normals, and each element material a PBR material colored
+/// like the COLLADA export. glTF is Y-up in meters like the model, so no
+/// axis conversion is needed.
+use crate::domain::geometry::{fan_triangles, newell_normal};
+use crate::domain::{
+    collect_export_solids, ElementRegistry, ExportFilter, GeometryRegistry, Solid,
+};
+use crate::infrastructure::collada::{describe_solids, material_color};
+use crate::infrastructure::standards::OfficeStandards;
+use serde_json::{json, Value};
+use std::path::Path;
+
+/// glTF component type of 32-bit floats
+const FLOAT: u32 = 5126;
+/// glTF component type of 32-bit unsigned integers
+const UNSIGNED_INT: u32 = 5125;
+/// glTF buffer view target of vertex attributes
+const ARRAY_BUFFER: u32 = 34962;
+/// glTF buffer view target of indices
+const ELEMENT_ARRAY_BUFFER: u32 = 34963;
+
+/// Settings for a glTF export
+#[derive(Debug, Clone, Default)]
+pub struct GltfExportSettings {
+    /// Which solids to export
+    pub filter: ExportFilter,
+    /// Colors for element kinds without a material
+    pub standards: OfficeStandards,
+}
+
+/// Write a glTF file of the solids in the registry
+///
+/// # Errors
+/// Returns an error if the file cannot be written.
+#[tracing::instrument(skip_all, fields(path = %path.display()))]
+pub fn write_gltf(
+    path: &Path,
+    geometry_registry: &GeometryRegistry,
+    element_registry: &ElementRegistry,
+    settings: &GltfExportSettings,
+) -> std::io::Result<()> {
+    std::fs::write(
+        path,
+        export_gltf(geometry_registry, element_registry, settings),
+    )?;
+    tracing::info!(
+        solids = geometry_registry.solids.solids.len(),
+        "wrote glTF file"
+    );
+    Ok(())
+}
+
+/// The binary buffer under construction and the views into it
+#[derive(Default)]
+struct Buffer {
+    bytes: Vec<u8>,
+    views: Vec<Value>,
+    accessors: Vec<Value>,
+}
+
+impl Buffer {
+    /// Append values as a buffer view with one accessor over it
+    /// Returns the accessor's index
+    fn push(&mut self, values: &[[u8; 4]], accessor: Value, target: u32) -> usize {
+        self.views.push(json!({
+            "buffer": 0,
+            "byteOffset": self.bytes.len(),
+            "byteLength": values.len() * 4,
+            "target": target,
+        }));
+        self.bytes.extend(values.iter().flatten());
+        let mut accessor = accessor;
+        accessor["bufferView"] = json!(self.views.len() - 1);
+        self.accessors.push(accessor);
+        self.accessors.len() - 1
+    }
+
+    /// Append a solid's triangles with flat face normals
+    /// Returns the mesh primitive's attributes and indices, or None if no
+    /// face has usable geometry
+    fn solid(&mut self, geometry_registry: &GeometryRegistry, solid: &Solid) -> Option<Value> {
+        let mut positions: Vec<[f32; 3]> = Vec::new();
+        let mut normals: Vec<[f32; 3]> = Vec::new();
+        let mut indices: Vec<u32> = Vec::new();
+        for polygon_id in &solid.polygons {
+            let Some(points) = geometry_registry.polygon_points(polygon_id) else {
+                continue;
+            };
+            // Prefer the stored plane normal, which follows the face's winding
+            let Some(normal) = geometry_registry
+                .polygons
+                .get(polygon_id)
+                .and_then(|polygon| polygon.normal().cloned())
+                .or_else(|| newell_normal(&points).normalized())
+            else {
+                continue;
+            };
+            let base = u32::try_from(positions.len()).ok()?;
+            positions.extend(points.iter().map(|point| [point.x, point.y, point.z]));
+            normals.extend(std::iter::repeat_n(
+                [normal.x, normal.y, normal.z],
+                points.len(),
+            ));
+            for triangle in fan_triangles(points.len()) {
+                for corner in triangle {
+                    indices.push(base + u32::try_from(corner).ok()?);
+                }
+            }
+        }
+        if indices.is_empty() {
+            return None;
+        }
+        let bound = |pick: fn(f32, f32) -> f32, start: f32| {
+            (0..3)
+                .map(|axis| positions.iter().map(|p| p[axis]).fold(start, pick))
+                .collect::<Vec<f32>>()
+        };
+        let (min, max) = (bound(f32::min, f32::MAX), bound(f32::max, f32::MIN));
+        let floats = |values: &[[f32; 3]]| -> Vec<[u8; 4]> {
+            values.iter().flatten().map(|v| v.to_le_bytes()).collect()
+        };
+        let position = self.push(
+            &floats(&positions),
+            json!({ "componentType": FLOAT, "count": positions.len(), "type": "VEC3",
+                    "min": min, "max": max }),
+            ARRAY_BUFFER,
+        );
+        let normal = self.push(
+            &floats(&normals),
+            json!({ "componentType": FLOAT, "count": normals.len(), "type": "VEC3" }),
+            ARRAY_BUFFER,
+        );
+        let index_bytes: Vec<[u8; 4]> = indices.iter().map(|i| i.to_le_bytes()).collect();
+        let index = self.push(
+            &index_bytes,
+            json!({ "componentType": UNSIGNED_INT, "count": indices.len(), "type": "SCALAR" }),
+            ELEMENT_ARRAY_BUFFER,
+        );
+        Some(json!({
+            "attributes": { "POSITION": position, "NORMAL": normal },
+            "indices": index,
+        }))
+    }
+}
+
+/// Build the text of a glTF asset
+#[must_use]
+pub fn export_gltf(
+    geometry_registry: &GeometryRegistry,
+    element_registry: &ElementRegistry,
+    settings: &GltfExportSettings,
+) -> String {
+    let solids = collect_export_solids(geometry_registry, element_registry, &settings.filter);
+    let (described, colors) = describe_solids(solids, &settings.standards);
+    let mut materials: Vec<&str> = described.iter().map(|(.., m)| m.as_str()).collect();
+    materials.sort_unstable();
+    materials.dedup();
+
+    let mut buffer = Buffer::default();
+    let (mut meshes, mut nodes) = (Vec::new(), Vec::new());
+    for (solid, name, _, material) in &described {
+        let Some(mut primitive) = buffer.solid(geometry_registry, solid) else {
+            continue;
+        };
+        primitive["material"] = json!(materials.iter().position(|m| m == material));
+        nodes.push(json!({ "name": name, "mesh": meshes.len() }));
+        meshes.push(json!({ "name": name, "primitives": [primitive] }));
+    }
+    let materials: Vec<Value> = materials
+        .iter()
+        .map(|name| {
+            let [r, g, b] = colors
+                .get(*name)
+                .copied()
+                .unwrap_or_else(|| material_color(name));
+            json!({
+                "name": name,
+                "pbrMetallicRoughness": {
+                    "baseColorFactor": [r, g, b, 1.0],
+                    "metallicFactor": 0.0,
+                    "roughnessFactor": 0.8,
+                },
+            })
+        })
+        .collect();
+
+    let mut document = json!({
+        "asset": { "version": "2.0", "generator": "HarmonyArch" },
+        "scene": 0,
+        "scenes": [{ "name": "Model", "nodes": (0..nodes.len()).collect::<Vec<_>>() }],
+        "nodes": nodes,
+        "meshes": meshes,
+        "materials": materials,
+    });
+    if !buffer.bytes.is_empty() {
+        document["buffers"] = json!([{
+            "byteLength": buffer.bytes.len(),
+            "uri": format!("data:application/octet-stream;base64,{}", base64(&buffer.bytes)),
+        }]);
+        document["bufferViews"] = json!(buffer.views);
+        document["accessors"] = json!(buffer.accessors);
+    }
+    serde_json::to_string_pretty(&document).unwrap_or_default()
+}
+
+/// Standard base64 with padding
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut text = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let word = chunk.iter().enumerate().fold(0_u32, |word, (index, byte)| {
+            word | u32::from(*byte) << (16 - 8 * index)
+        });
+        for index in 0..4 {
+            if index <= chunk.len() {
+                text.push(char::from(
+                    ALPHABET[(word >> (18 - 6 * index)) as usize & 63],
+                ));
+            } else {
+                text.push('=');
+            }
+        }
+    }
+    text
+}
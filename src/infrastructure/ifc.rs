@@ -0,0 +1,600 @@
+/// IFC export
+///
+/// Writes elements and their solids as an IFC4 STEP file (ISO 10303-21).
+/// Each element becomes the matching IFC entity with a faceted B-rep body,
+/// base quantities from its bounds, its material and a common property set
+/// carrying its phase. The spatial structure is a single site, building
+/// and storey. A georeferenced project also gets an `IfcMapConversion` to
+/// its projected coordinate system and the site's latitude and longitude.
+///
+/// Walls are written as `IfcWall` rather than `IfcWallStandardCase`: IFC4
+/// reserves the standard case for walls with a material layer set and an
+/// extruded body, and our bodies are B-reps.
+///
+/// Bodies are assigned to presentation layers named by the office
+/// standards, and bodies of element kinds the standards give a color are
+/// styled with it.
+use crate::domain::geometry::{polygon_area, signed_volume};
+use crate::domain::{
+    collect_export_solids, new_detached_id, Element, ElementKind, ElementRegistry, ExportFilter,
+    GeometryRegistry, Georeference, Phase,
+};
+use crate::infrastructure::current_timestamp;
+use crate::infrastructure::standards::OfficeStandards;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write as _;
+use std::path::Path;
+use uuid::Uuid;
+
+/// Settings for an IFC export
+#[derive(Debug, Clone)]
+pub struct IfcExportSettings {
+    /// Name written to the project and the file header
+    pub project_name: String,
+    /// Author written to the file header
+    pub author: String,
+    /// ISO 8601 time stamp written to the file header
+    pub timestamp: String,
+    /// Which solids to export
+    pub filter: ExportFilter,
+    /// Map placement written as a map conversion and site reference, if set
+    pub georeference: Option<Georeference>,
+    /// `GlobalId` to keep for each element imported from another IFC
+    /// model, so its author's tools recognize the element coming back
+    pub global_ids: HashMap<Uuid, String>,
+    /// Layer names and colors for element kinds
+    pub standards: OfficeStandards,
+}
+
+impl Default for IfcExportSettings {
+    fn default() -> Self {
+        Self {
+            project_name: "HarmonyArch project".to_string(),
+            author: String::new(),
+            timestamp: current_timestamp(),
+            filter: ExportFilter::default(),
+            georeference: None,
+            global_ids: HashMap::new(),
+            standards: OfficeStandards::default(),
+        }
+    }
+}
+
+/// Write an IFC file of the elements and solids in the registries
+///
+/// # Errors
+/// Returns an error if the file cannot be written.
+#[tracing::instrument(skip_all, fields(path = %path.display()))]
+pub fn write_ifc(
+    path: &Path,
+    geometry_registry: &GeometryRegistry,
+    element_registry: &ElementRegistry,
+    settings: &IfcExportSettings,
+) -> std::io::Result<()> {
+    std::fs::write(
+        path,
+        export_ifc(geometry_registry, element_registry, settings),
+    )?;
+    tracing::info!(elements = element_registry.elements.len(), "wrote IFC file");
+    Ok(())
+}
+
+/// Build the text of an IFC file
+///
+/// Solids not backed by an element are exported as building element
+/// proxies, so nothing in the model is dropped.
+#[must_use]
+pub fn export_ifc(
+    geometry_registry: &GeometryRegistry,
+    element_registry: &ElementRegistry,
+    settings: &IfcExportSettings,
+) -> String {
+    let mut file = StepFile::default();
+    let storey = file.spatial_structure(settings);
+
+    let mut contained = Vec::new();
+    let mut spaces = Vec::new();
+    let mut by_material: HashMap<&str, Vec<usize>> = HashMap::new();
+    for export in collect_export_solids(geometry_registry, element_registry, &settings.filter) {
+        let (solid, element) = (export.solid, export.element);
+        let id = element.map_or(&solid.id, |element| &element.id);
+        let guid = settings
+            .global_ids
+            .get(id)
+            .cloned()
+            .unwrap_or_else(|| ifc_guid(id));
+        let Some(product) = file.product(geometry_registry, &solid.id, solid.phase, element, &guid)
+        else {
+            continue;
+        };
+        if element.is_some_and(|element| element.kind == ElementKind::Space) {
+            spaces.push(product);
+        } else {
+            contained.push(product);
+        }
+        if let Some(material) = element.and_then(|element| element.material.as_deref()) {
+            by_material.entry(material).or_default().push(product);
+        }
+    }
+
+    if !contained.is_empty() {
+        file.add(format!(
+            "IFCRELCONTAINEDINSPATIALSTRUCTURE({},$,$,$,{},#{storey})",
+            new_guid(),
+            refs(&contained)
+        ));
+    }
+    // Spaces decompose the storey rather than being contained in it
+    if !spaces.is_empty() {
+        file.add(format!(
+            "IFCRELAGGREGATES({},$,$,$,#{storey},{})",
+            new_guid(),
+            refs(&spaces)
+        ));
+    }
+    let mut materials: Vec<_> = by_material.into_iter().collect();
+    materials.sort_unstable();
+    for (name, products) in materials {
+        let material = file.add(format!("IFCMATERIAL({},$,$)", step_string(name)));
+        file.add(format!(
+            "IFCRELASSOCIATESMATERIAL({},$,$,$,{},#{material})",
+            new_guid(),
+            refs(&products)
+        ));
+    }
+    file.presentation(&settings.standards);
+
+    file.finish(settings)
+}
+
+/// Entities of a STEP file under construction
+#[derive(Default)]
+struct StepFile {
+    /// Entity instances, numbered from 1 in order
+    entities: Vec<String>,
+    /// The model body representation context
+    body_context: usize,
+    /// The shared origin placement
+    origin: usize,
+    /// The storey placement products are placed relative to
+    storey_placement: usize,
+    /// Each product's element kind, shape representation and body
+    bodies: Vec<(ElementKind, usize, usize)>,
+}
+
+impl StepFile {
+    /// Add an entity and return its instance number
+    fn add(&mut self, entity: String) -> usize {
+        self.entities.push(entity);
+        self.entities.len()
+    }
+
+    /// Add the project, units, contexts, site, building and storey
+    /// Returns the storey's instance number
+    fn spatial_structure(&mut self, settings: &IfcExportSettings) -> usize {
+        let point = self.add("IFCCARTESIANPOINT((0.,0.,0.))".to_string());
+        let up = self.add("IFCDIRECTION((0.,0.,1.))".to_string());
+        let east = self.add("IFCDIRECTION((1.,0.,0.))".to_string());
+        self.origin = self.add(format!("IFCAXIS2PLACEMENT3D(#{point},#{up},#{east})"));
+        let context = self.add(format!(
+            "IFCGEOMETRICREPRESENTATIONCONTEXT($,'Model',3,1.E-05,#{},$)",
+            self.origin
+        ));
+        self.body_context = self.add(format!(
+            "IFCGEOMETRICREPRESENTATIONSUBCONTEXT('Body','Model',*,*,*,*,#{context},$,.MODEL_VIEW.,$)"
+        ));
+
+        let units = [
+            ".LENGTHUNIT.,$,.METRE.",
+            ".AREAUNIT.,$,.SQUARE_METRE.",
+            ".VOLUMEUNIT.,$,.CUBIC_METRE.",
+            ".PLANEANGLEUNIT.,$,.RADIAN.",
+        ]
+        .map(|unit| self.add(format!("IFCSIUNIT(*,{unit})")));
+        let unit_assignment = self.add(format!("IFCUNITASSIGNMENT({})", refs(&units)));
+        let project = self.add(format!(
+            "IFCPROJECT({},$,{},$,$,$,$,(#{context}),#{unit_assignment})",
+            new_guid(),
+            step_string(&settings.project_name)
+        ));
+        if let Some(georeference) = &settings.georeference {
+            self.map_conversion(context, georeference);
+        }
+
+        let site_placement = self.add(format!("IFCLOCALPLACEMENT($,#{})", self.origin));
+        let reference = settings.georeference.map_or_else(
+            || "$,$,$".to_string(),
+            |georeference| {
+                format!(
+                    "{},{},{}",
+                    georeference
+                        .latitude
+                        .map_or("$".to_string(), compound_angle),
+                    georeference
+                        .longitude
+                        .map_or("$".to_string(), compound_angle),
+                    step_real(georeference.elevation)
+                )
+            },
+        );
+        let site = self.add(format!(
+            "IFCSITE({},$,'Site',$,$,#{site_placement},$,$,.ELEMENT.,{reference},$,$)",
+            new_guid()
+        ));
+        let building_placement = self.add(format!(
+            "IFCLOCALPLACEMENT(#{site_placement},#{})",
+            self.origin
+        ));
+        let building = self.add(format!(
+            "IFCBUILDING({},$,'Building',$,$,#{building_placement},$,$,.ELEMENT.,$,$,$)",
+            new_guid()
+        ));
+        self.storey_placement = self.add(format!(
+            "IFCLOCALPLACEMENT(#{building_placement},#{})",
+            self.origin
+        ));
+        let storey = self.add(format!(
+            "IFCBUILDINGSTOREY({},$,'Level 0',$,$,#{},$,$,.ELEMENT.,0.)",
+            new_guid(),
+            self.storey_placement
+        ));
+
+        for (parent, child) in [(project, site), (site, building), (building, storey)] {
+            self.add(format!(
+                "IFCRELAGGREGATES({},$,$,$,#{parent},(#{child}))",
+                new_guid()
+            ));
+        }
+        storey
+    }
+
+    /// Add the map conversion from the model context to the projected CRS
+    fn map_conversion(&mut self, context: usize, georeference: &Georeference) {
+        let name = georeference
+            .crs_name()
+            .unwrap_or_else(|| "Unknown".to_string());
+        let crs = self.add(format!(
+            "IFCPROJECTEDCRS({},$,$,$,$,$,$)",
+            step_string(&name)
+        ));
+        let (abscissa, ordinate) = georeference.x_axis();
+        self.add(format!(
+            "IFCMAPCONVERSION(#{context},#{crs},{},{},{},{},{},1.)",
+            step_real(georeference.easting),
+            step_real(georeference.northing),
+            step_real(georeference.elevation),
+            step_real(abscissa),
+            step_real(ordinate)
+        ));
+    }
+
+    /// Add a solid as a building product with its body, quantities and
+    /// common property set, named by its `GlobalId`
+    /// Returns None if the solid's geometry is missing
+    fn product(
+        &mut self,
+        geometry_registry: &GeometryRegistry,
+        solid_id: &Uuid,
+        phase: Phase,
+        element: Option<&Element>,
+        guid: &str,
+    ) -> Option<usize> {
+        let body = self.brep(geometry_registry, solid_id)?;
+        let loops = geometry_registry.solid_loops(solid_id)?;
+        let points: Vec<[f32; 3]> = loops.iter().flatten().map(ifc_coordinates).collect();
+        let extent = |axis: usize| {
+            let values = points.iter().map(|point| point[axis]);
+            values.clone().fold(f32::MIN, f32::max) - values.fold(f32::MAX, f32::min)
+        };
+        let (length, width) = (extent(0).max(extent(1)), extent(0).min(extent(1)));
+        let height = extent(2);
+
+        let kind = element.map_or(ElementKind::Generic, |element| element.kind);
+        let name = element.map_or("Solid", |element| element.name.as_str());
+        let placement = self.add(format!(
+            "IFCLOCALPLACEMENT(#{},#{})",
+            self.storey_placement, self.origin
+        ));
+        let shape = self.add(format!(
+            "IFCSHAPEREPRESENTATION(#{},'Body','Brep',(#{body}))",
+            self.body_context
+        ));
+        self.bodies.push((kind, shape, body));
+        let definition = self.add(format!("IFCPRODUCTDEFINITIONSHAPE($,$,(#{shape}))"));
+        let common = format!(
+            "'{guid}',$,{},$,$,#{placement},#{definition},$",
+            step_string(name)
+        );
+        let (entity, pset) = product_entity(kind, &common, height, length);
+        let product = self.add(entity);
+
+        let quantities = [
+            format!("IFCQUANTITYLENGTH('Length',$,$,{},$)", step_real(length)),
+            format!("IFCQUANTITYLENGTH('Width',$,$,{},$)", step_real(width)),
+            format!("IFCQUANTITYLENGTH('Height',$,$,{},$)", step_real(height)),
+            format!(
+                "IFCQUANTITYAREA('GrossSurfaceArea',$,$,{},$)",
+                step_real(loops.iter().map(|l| polygon_area(l)).sum::<f32>())
+            ),
+            format!(
+                "IFCQUANTITYVOLUME('GrossVolume',$,$,{},$)",
+                step_real(signed_volume(&loops).abs())
+            ),
+        ]
+        .map(|quantity| self.add(quantity));
+        let quantity_set = self.add(format!(
+            "IFCELEMENTQUANTITY({},$,'BaseQuantities',$,$,{})",
+            new_guid(),
+            refs(&quantities)
+        ));
+        self.add(format!(
+            "IFCRELDEFINESBYPROPERTIES({},$,$,$,(#{product}),#{quantity_set})",
+            new_guid()
+        ));
+
+        let status = match phase {
+            Phase::Existing => "EXISTING",
+            Phase::Demolished => "DEMOLISH",
+            Phase::New => "NEW",
+        };
+        let status = self.add(format!(
+            "IFCPROPERTYENUMERATEDVALUE('Status',$,(IFCLABEL('{status}')),$)"
+        ));
+        let property_set = self.add(format!(
+            "IFCPROPERTYSET({},$,'{pset}',$,(#{status}))",
+            new_guid()
+        ));
+        self.add(format!(
+            "IFCRELDEFINESBYPROPERTIES({},$,$,$,(#{product}),#{property_set})",
+            new_guid()
+        ));
+        Some(product)
+    }
+
+    /// Add the faceted B-rep of a solid, sharing points between faces
+    /// Returns None if the solid or any of its geometry is missing
+    fn brep(&mut self, geometry_registry: &GeometryRegistry, solid_id: &Uuid) -> Option<usize> {
+        let solid = geometry_registry.solids.get(solid_id)?;
+        let mut points: HashMap<Uuid, usize> = HashMap::new();
+        let mut faces = Vec::new();
+        for polygon_id in &solid.polygons {
+            let vertex_loop = geometry_registry
+                .polygons
+                .get(polygon_id)?
+                .vertex_loop(&geometry_registry.segments)?;
+            let mut loop_points = Vec::new();
+            for vertex_id in &vertex_loop {
+                if let Some(point) = points.get(vertex_id) {
+                    loop_points.push(*point);
+                    continue;
+                }
+                let [x, y, z] =
+                    ifc_coordinates(&geometry_registry.vertices.get(vertex_id)?.position);
+                let point = self.add(format!(
+                    "IFCCARTESIANPOINT(({},{},{}))",
+                    step_real(x),
+                    step_real(y),
+                    step_real(z)
+                ));
+                points.insert(*vertex_id, point);
+                loop_points.push(point);
+            }
+            let poly_loop = self.add(format!("IFCPOLYLOOP({})", refs(&loop_points)));
+            let bound = self.add(format!("IFCFACEOUTERBOUND(#{poly_loop},.T.)"));
+            faces.push(self.add(format!("IFCFACE((#{bound}))")));
+        }
+        let shell = self.add(format!("IFCCLOSEDSHELL({})", refs(&faces)));
+        Some(self.add(format!("IFCFACETEDBREP(#{shell})")))
+    }
+
+    /// Assign the bodies to their layers and style those of kinds with a
+    /// standard color
+    fn presentation(&mut self, standards: &OfficeStandards) {
+        let mut layers: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+        let mut styles: HashMap<ElementKind, usize> = HashMap::new();
+        for (kind, shape, body) in std::mem::take(&mut self.bodies) {
+            layers
+                .entry(standards.layer_for(kind))
+                .or_default()
+                .push(shape);
+            let Some([r, g, b]) = standards.color_for(kind) else {
+                continue;
+            };
+            let style = *styles.entry(kind).or_insert_with(|| {
+                let colour = self.add(format!(
+                    "IFCCOLOURRGB($,{},{},{})",
+                    step_real(r),
+                    step_real(g),
+                    step_real(b)
+                ));
+                let shading = self.add(format!("IFCSURFACESTYLESHADING(#{colour},0.)"));
+                self.add(format!(
+                    "IFCSURFACESTYLE({},.BOTH.,(#{shading}))",
+                    step_string(kind.label())
+                ))
+            });
+            self.add(format!("IFCSTYLEDITEM(#{body},(#{style}),$)"));
+        }
+        for (layer, shapes) in layers {
+            self.add(format!(
+                "IFCPRESENTATIONLAYERASSIGNMENT({},$,{},$)",
+                step_string(&layer),
+                refs(&shapes)
+            ));
+        }
+    }
+
+    /// Wrap the entities in the header and data sections
+    fn finish(self, settings: &IfcExportSettings) -> String {
+        let mut text = String::from("ISO-10303-21;\nHEADER;\n");
+        text.push_str("FILE_DESCRIPTION(('ViewDefinition [ReferenceView_V1.2]'),'2;1');\n");
+        let _ = writeln!(
+            text,
+            "FILE_NAME({},{},({}),(''),'HarmonyArch','HarmonyArch','');",
+            step_string(&settings.project_name),
+            step_string(&settings.timestamp),
+            step_string(&settings.author)
+        );
+        text.push_str("FILE_SCHEMA(('IFC4'));\nENDSEC;\nDATA;\n");
+        for (index, entity) in self.entities.iter().enumerate() {
+            let _ = writeln!(text, "#{}={entity};", index + 1);
+        }
+        text.push_str("ENDSEC;\nEND-ISO-10303-21;\n");
+        text
+    }
+}
+
+/// Convert a Y-up model point to IFC's Z-up axes
+///
+/// A rotation about X, so face windings keep their orientation.
+fn ifc_coordinates(point: &crate::domain::Point) -> [f32; 3] {
+    [point.x, -point.z, point.y]
+}
+
+/// A parenthesised list of instance references
+fn refs(instances: &[usize]) -> String {
+    let list: Vec<String> = instances.iter().map(|id| format!("#{id}")).collect();
+    format!("({})", list.join(","))
+}
+
+/// The IFC entity of a product of an element kind and the name of its
+/// common property set, from the product's shared attributes and its
+/// height and length
+fn product_entity(
+    kind: ElementKind,
+    common: &str,
+    height: f32,
+    length: f32,
+) -> (String, &'static str) {
+    match kind {
+        ElementKind::Wall => (format!("IFCWALL({common},.NOTDEFINED.)"), "Pset_WallCommon"),
+        ElementKind::Slab => (format!("IFCSLAB({common},.NOTDEFINED.)"), "Pset_SlabCommon"),
+        ElementKind::Column => (
+            format!("IFCCOLUMN({common},.NOTDEFINED.)"),
+            "Pset_ColumnCommon",
+        ),
+        ElementKind::Beam => (format!("IFCBEAM({common},.NOTDEFINED.)"), "Pset_BeamCommon"),
+        ElementKind::Door => (
+            format!(
+                "IFCDOOR({common},{},{},.NOTDEFINED.,.NOTDEFINED.,$)",
+                step_real(height),
+                step_real(length)
+            ),
+            "Pset_DoorCommon",
+        ),
+        ElementKind::Window => (
+            format!(
+                "IFCWINDOW({common},{},{},.NOTDEFINED.,.NOTDEFINED.,$)",
+                step_real(height),
+                step_real(length)
+            ),
+            "Pset_WindowCommon",
+        ),
+        ElementKind::Space => (
+            format!("IFCSPACE({common},.ELEMENT.,.INTERNAL.,$)"),
+            "Pset_SpaceCommon",
+        ),
+        ElementKind::Duct => (
+            format!("IFCDUCTSEGMENT({common},.NOTDEFINED.)"),
+            "Pset_DuctSegmentTypeCommon",
+        ),
+        ElementKind::Pipe => (
+            format!("IFCPIPESEGMENT({common},.NOTDEFINED.)"),
+            "Pset_PipeSegmentTypeCommon",
+        ),
+        ElementKind::Conduit => (
+            format!("IFCCABLECARRIERSEGMENT({common},.CONDUITSEGMENT.)"),
+            "Pset_CableCarrierSegmentTypeCommon",
+        ),
+        ElementKind::Generic => (
+            format!("IFCBUILDINGELEMENTPROXY({common},.NOTDEFINED.)"),
+            "Pset_BuildingElementProxyCommon",
+        ),
+    }
+}
+
+/// A STEP real, which always carries a decimal point
+fn step_real(value: impl Into<f64>) -> String {
+    format!("{:.6}", value.into())
+        .trim_end_matches('0')
+        .to_string()
+}
+
+/// An angle in degrees as an IFC compound plane angle
+///
+/// Degrees, minutes, seconds and millionths of a second, all carrying the
+/// angle's sign.
+fn compound_angle(degrees: f64) -> String {
+    #[allow(clippy::cast_possible_truncation)]
+    let millionths = (degrees.abs() * 3_600_000_000.0).round() as i64;
+    let sign = if degrees < 0.0 { -1 } else { 1 };
+    format!(
+        "({},{},{},{})",
+        sign * (millionths / 3_600_000_000),
+        sign * (millionths / 60_000_000 % 60),
+        sign * (millionths / 1_000_000 % 60),
+        sign * (millionths % 1_000_000)
+    )
+}
+
+/// A quoted STEP string
+///
+/// Quotes and backslashes are doubled; characters outside printable ASCII
+/// are written as `\X2\` hex escapes.
+fn step_string(value: &str) -> String {
+    let mut text = String::from("'");
+    for character in value.chars() {
+        match character {
+            '\'' => text.push_str("''"),
+            '\\' => text.push_str("\\\\"),
+            ' '..='~' => text.push(character),
+            _ => {
+                text.push_str("\\X2\\");
+                for unit in character.encode_utf16(&mut [0; 2]) {
+                    let _ = write!(text, "{unit:04X}");
+                }
+                text.push_str("\\X0\\");
+            }
+        }
+    }
+    text.push('\'');
+    text
+}
+
+/// A fresh IFC GUID, quoted
+fn new_guid() -> String {
+    format!("'{}'", ifc_guid(&new_detached_id()))
+}
+
+/// Digits of the compressed IFC GUID form, in order of value
+const GUID_ALPHABET: &[u8; 64] =
+    b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz_$";
+
+/// The 22-character compressed IFC form of a UUID
+#[must_use]
+pub fn ifc_guid(id: &Uuid) -> String {
+    let value = id.as_u128();
+    (0..22)
+        .map(|index| {
+            let shift = 6 * (21 - index);
+            // The first character holds only the top two bits
+            let digit = (value >> shift) & if index == 0 { 0x3 } else { 0x3f };
+            char::from(GUID_ALPHABET[usize::try_from(digit).unwrap_or(0)])
+        })
+        .collect()
+}
+
+/// The UUID a compressed IFC GUID stands for, or None if the text is not
+/// one
+#[must_use]
+pub fn uuid_from_ifc_guid(guid: &str) -> Option<Uuid> {
+    if guid.len() != 22 {
+        return None;
+    }
+    let value = guid.bytes().try_fold(0u128, |value, character| {
+        let digit = GUID_ALPHABET.iter().position(|digit| *digit == character)?;
+        Some((value << 6) | digit as u128)
+    })?;
+    // Only two bits fit in the first character
+    (guid.as_bytes()[0] <= b'3').then(|| Uuid::from_u128(value))
+}
@@ -0,0 +1,91 @@
+/// Component library folder scanning
+///
+/// A component library is a folder of OBJ files. Files directly in the
+/// folder are listed under `General`; files in a subfolder are listed
+/// under that subfolder's name, so `components/Doors/single.obj` becomes
+/// the `single` component in the `Doors` category.
+///
+/// A definition's ID is drawn from its path under the folder, so a file
+/// gets the same ID every run and recorded sessions that place it replay.
+use crate::domain::{
+    new_component_definition, splitmix64, with_deterministic_ids, ComponentLibrary,
+};
+use crate::infrastructure::obj::{read_obj, ObjError};
+use std::path::{Path, PathBuf};
+
+/// Category for components directly in the library folder
+const GENERAL_CATEGORY: &str = "General";
+
+/// What a library scan found
+pub struct LibraryScan {
+    /// The components that were read
+    pub library: ComponentLibrary,
+    /// Files that could not be read, with the reason
+    pub failed: Vec<(PathBuf, ObjError)>,
+}
+
+/// Read every OBJ file under a folder into a component library
+///
+/// A missing folder gives an empty library.
+#[must_use]
+#[tracing::instrument(skip_all, fields(root = %root.display()))]
+pub fn scan_component_library(root: &Path) -> LibraryScan {
+    let mut scan = LibraryScan {
+        library: ComponentLibrary::create_new(),
+        failed: Vec::new(),
+    };
+    for path in obj_files(root) {
+        let category = path
+            .parent()
+            .filter(|parent| *parent != root)
+            .and_then(Path::file_name)
+            .map_or_else(
+                || GENERAL_CATEGORY.to_string(),
+                |name| name.to_string_lossy().into_owned(),
+            );
+        let name = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        match read_obj(&path) {
+            Ok(loops) => {
+                let seed = path_seed(path.strip_prefix(root).unwrap_or(&path));
+                scan.library.store(with_deterministic_ids(seed, || {
+                    new_component_definition(&name, &category, loops)
+                }));
+            }
+            Err(error) => scan.failed.push((path, error)),
+        }
+    }
+    scan
+}
+
+/// A seed drawn from every byte of a path
+fn path_seed(path: &Path) -> u64 {
+    path.to_string_lossy()
+        .bytes()
+        .fold(0, |seed, byte| splitmix64(seed ^ u64::from(byte)))
+}
+
+/// OBJ files under a folder and its subfolders, in path order
+fn obj_files(folder: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(folder) else {
+        return Vec::new();
+    };
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|entry| Some(entry.ok()?.path()))
+        .collect();
+    paths.sort();
+    let mut files = Vec::new();
+    for path in paths {
+        if path.is_dir() {
+            files.extend(obj_files(&path));
+        } else if path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("obj"))
+        {
+            files.push(path);
+        }
+    }
+    files
+}
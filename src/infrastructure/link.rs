@@ -0,0 +1,214 @@
+/// Loading and reloading linked models
+///
+/// Reads the geometry of a linked file into its own registry and reloads
+/// it when the file's modification time changes. Project files and DXF
+/// line work can be linked.
+///
+/// DXF line work can also be imported into the model itself, where it can
+/// be edited. Refreshing such an import merges the drawing's changes into
+/// the model rather than replacing it, keeping local edits.
+use crate::domain::{
+    new_linked_model, ExternalIdMap, GeometryRegistry, LinkRegistry, LinkTransform, TierRegistry,
+};
+use crate::infrastructure::dxf::{import_dxf, read_dxf, DxfError, DxfImport, DxfImportSettings};
+use crate::infrastructure::project::{read_project, ProjectError, PROJECT_EXTENSION};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use uuid::Uuid;
+
+/// Errors raised while loading a linked model
+#[derive(Debug)]
+pub enum LinkError {
+    /// The file could not be read
+    Io(std::io::Error),
+    /// The project file could not be parsed
+    Project(ProjectError),
+    /// The DXF file could not be parsed
+    Dxf(DxfError),
+    /// The file type cannot be linked
+    Unsupported(String),
+}
+
+impl std::fmt::Display for LinkError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LinkError::Io(error) => write!(f, "Could not read linked model: {error}"),
+            LinkError::Project(error) => write!(f, "Could not read linked model: {error}"),
+            LinkError::Dxf(error) => write!(f, "Could not read linked model: {error}"),
+            LinkError::Unsupported(message) => write!(f, "Cannot link model: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for LinkError {}
+
+impl From<std::io::Error> for LinkError {
+    fn from(error: std::io::Error) -> Self {
+        LinkError::Io(error)
+    }
+}
+
+impl From<ProjectError> for LinkError {
+    fn from(error: ProjectError) -> Self {
+        LinkError::Project(error)
+    }
+}
+
+impl From<DxfError> for LinkError {
+    fn from(error: DxfError) -> Self {
+        LinkError::Dxf(error)
+    }
+}
+
+/// What a refresh of the linked models did
+#[derive(Debug, Default)]
+pub struct LinkRefresh {
+    /// Links whose files changed and were read again
+    pub reloaded: Vec<Uuid>,
+    /// Links whose files changed but could not be read; they keep their
+    /// previous geometry
+    pub failed: Vec<(Uuid, LinkError)>,
+}
+
+/// Read the geometry of a file to be linked, choosing the format by
+/// extension
+///
+/// # Errors
+/// Returns an error if the file cannot be read or its type cannot be
+/// linked.
+#[tracing::instrument(skip_all, fields(path = %path.display()))]
+pub fn read_linked_geometry(path: &Path) -> Result<GeometryRegistry, LinkError> {
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    match extension.as_str() {
+        "dxf" => {
+            let drawing = read_dxf(path)?;
+            let mut geometry = GeometryRegistry::create_new();
+            let mut tiers = TierRegistry::create_new();
+            import_dxf(
+                &mut geometry,
+                &mut tiers,
+                &drawing,
+                &DxfImportSettings::default(),
+                &mut ExternalIdMap::create_new(),
+            );
+            Ok(geometry)
+        }
+        PROJECT_EXTENSION => Ok(read_project(path)?.geometry),
+        _ => Err(LinkError::Unsupported(format!(
+            "unknown file type \"{extension}\""
+        ))),
+    }
+}
+
+/// Import a DXF drawing into the model, or refresh an earlier import of it
+///
+/// The drawing's path keys its entities in the external ID table, so
+/// importing the same file again updates the segments it made last time
+/// where they are unedited and reports conflicts where they were edited
+/// here; see [`import_dxf`]. Each DXF layer goes into the tier `layers`
+/// maps it to, or is skipped if mapped to None.
+///
+/// # Errors
+/// Returns an error if the file cannot be read or is not a DXF drawing.
+#[tracing::instrument(skip_all, fields(path = %path.display()))]
+pub fn refresh_import(
+    path: &Path,
+    geometry_registry: &mut GeometryRegistry,
+    tier_registry: &mut TierRegistry,
+    external_ids: &mut ExternalIdMap,
+    layers: &BTreeMap<String, Option<String>>,
+) -> Result<DxfImport, LinkError> {
+    let is_dxf = path
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("dxf"));
+    if !is_dxf {
+        return Err(LinkError::Unsupported(format!(
+            "{} is not a DXF drawing",
+            path.display()
+        )));
+    }
+    let drawing = read_dxf(path)?;
+    let settings = DxfImportSettings {
+        file: path.display().to_string(),
+        layers: layers
+            .iter()
+            .map(|(layer, tier)| (layer.clone(), tier.clone()))
+            .collect(),
+        ..DxfImportSettings::default()
+    };
+    let import = import_dxf(
+        geometry_registry,
+        tier_registry,
+        &drawing,
+        &settings,
+        external_ids,
+    );
+    tracing::info!(
+        added = import.segments.len(),
+        updated = import.updated.len(),
+        removed = import.removed.len(),
+        conflicts = import.conflicts.len(),
+        "refreshed imported drawing"
+    );
+    Ok(import)
+}
+
+/// Link a file as a read-only reference model and return the link's ID
+///
+/// # Errors
+/// Returns an error if the file cannot be read or its type cannot be
+/// linked.
+pub fn link_model(
+    registry: &mut LinkRegistry,
+    path: PathBuf,
+    transform: LinkTransform,
+) -> Result<Uuid, LinkError> {
+    let modified = modification_time(&path);
+    let geometry = read_linked_geometry(&path)?;
+    let name = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let mut link = new_linked_model(&name, path, transform, geometry);
+    link.modified = modified;
+    Ok(registry.store(link))
+}
+
+/// Reload every linked model whose file changed since it was last read
+#[tracing::instrument(skip_all)]
+pub fn refresh_links(registry: &mut LinkRegistry) -> LinkRefresh {
+    let mut refresh = LinkRefresh::default();
+    let mut links: Vec<_> = registry.links.values_mut().collect();
+    links.sort_by_key(|link| link.id);
+    for link in links {
+        let modified = modification_time(&link.path);
+        if modified.is_none() || modified == link.modified {
+            continue;
+        }
+        match read_linked_geometry(&link.path) {
+            Ok(geometry) => {
+                link.replace_geometry(geometry);
+                link.modified = modified;
+                refresh.reloaded.push(link.id);
+                tracing::info!(link = %link.id, "reloaded linked model");
+            }
+            Err(error) => {
+                tracing::warn!(link = %link.id, %error, "could not reload linked model");
+                refresh.failed.push((link.id, error));
+            }
+        }
+    }
+    refresh
+}
+
+/// Modification time of a file, if it can be read
+fn modification_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
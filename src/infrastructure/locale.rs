@@ -0,0 +1,164 @@
+/// Translation catalogs for the interface
+///
+/// The interface is written in English, and a catalog maps its English
+/// strings to another language. Catalogs are JSON files named for their
+/// language code, such as `de.json`:
+///
+/// ```json
+/// {
+///     "name": "Deutsch",
+///     "strings": { "Settings": "Einstellungen", "Save": "Speichern" }
+/// }
+/// ```
+///
+/// Strings missing from a catalog stay in English.
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Code of the language the interface is written in
+pub const ENGLISH: &str = "en";
+
+/// Errors raised while reading a catalog
+#[derive(Debug)]
+pub enum CatalogError {
+    /// The file could not be read
+    Io(std::io::Error),
+    /// The file is not a catalog
+    Parse(String),
+}
+
+impl std::fmt::Display for CatalogError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CatalogError::Io(error) => write!(f, "Could not read catalog: {error}"),
+            CatalogError::Parse(message) => write!(f, "Invalid catalog: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for CatalogError {}
+
+impl From<std::io::Error> for CatalogError {
+    fn from(error: std::io::Error) -> Self {
+        CatalogError::Io(error)
+    }
+}
+
+/// A language's translations of the interface's English strings
+#[derive(Debug, Clone, PartialEq)]
+pub struct Catalog {
+    /// Language code, such as `de`
+    pub code: String,
+    /// Name of the language in itself, shown in the settings
+    pub name: String,
+    /// Translations by English string
+    pub strings: HashMap<String, String>,
+}
+
+impl Catalog {
+    /// The catalog of the interface's own language, translating nothing
+    #[must_use]
+    pub fn english() -> Self {
+        Self {
+            code: ENGLISH.to_string(),
+            name: "English".to_string(),
+            strings: HashMap::new(),
+        }
+    }
+
+    /// Parse the text of a catalog file
+    ///
+    /// # Errors
+    /// Returns an error if the text is not JSON or has no `strings` object.
+    pub fn parse(code: &str, text: &str) -> Result<Self, CatalogError> {
+        let document: Value =
+            serde_json::from_str(text).map_err(|error| CatalogError::Parse(error.to_string()))?;
+        let strings = document
+            .get("strings")
+            .and_then(Value::as_object)
+            .ok_or_else(|| CatalogError::Parse("missing the strings object".to_string()))?
+            .iter()
+            .filter_map(|(english, translated)| {
+                Some((english.clone(), translated.as_str()?.to_string()))
+            })
+            .collect();
+        Ok(Self {
+            code: code.to_string(),
+            name: document
+                .get("name")
+                .and_then(Value::as_str)
+                .unwrap_or(code)
+                .to_string(),
+            strings,
+        })
+    }
+
+    /// Translate interface text line by line
+    ///
+    /// A line found in the catalog is replaced whole. Otherwise a line of
+    /// the form `Label: value` has its label and value translated on their
+    /// own, so status lines such as `Show Outlines: ON` need no entry for
+    /// each value.
+    #[must_use]
+    pub fn translate(&self, text: &str) -> String {
+        if self.strings.is_empty() {
+            return text.to_string();
+        }
+        text.split('\n')
+            .map(|line| match self.strings.get(line) {
+                Some(translated) => translated.clone(),
+                None => match line.split_once(": ") {
+                    Some((label, value)) => format!("{}: {}", self.word(label), self.word(value)),
+                    None => line.to_string(),
+                },
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// The translation of a single word or phrase, itself if it has none
+    fn word<'a>(&'a self, part: &'a str) -> &'a str {
+        self.strings.get(part).map_or(part, String::as_str)
+    }
+}
+
+/// Read every catalog in a folder, English first and the rest in code
+/// order, with the files that could not be read
+///
+/// A missing folder gives English alone.
+#[must_use]
+pub fn scan_catalogs(folder: &Path) -> (Vec<Catalog>, Vec<(PathBuf, CatalogError)>) {
+    let mut catalogs = vec![Catalog::english()];
+    let mut failed = Vec::new();
+    let mut paths: Vec<PathBuf> = std::fs::read_dir(folder)
+        .map(|entries| {
+            entries
+                .filter_map(Result::ok)
+                .map(|entry| entry.path())
+                .filter(|path| {
+                    path.extension()
+                        .is_some_and(|extension| extension == "json")
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    paths.sort();
+    for path in paths {
+        let code = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        if code == ENGLISH {
+            continue;
+        }
+        let catalog = std::fs::read_to_string(&path)
+            .map_err(CatalogError::from)
+            .and_then(|text| Catalog::parse(&code, &text));
+        match catalog {
+            Ok(catalog) => catalogs.push(catalog),
+            Err(error) => failed.push((path, error)),
+        }
+    }
+    (catalogs, failed)
+}
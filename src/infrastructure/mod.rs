@@ -1,2 +1,102 @@
 /// Infrastructure layer for the application
 pub use uuid::Uuid;
+
+/// Batch export of several formats with a manifest
+pub mod batch_export;
+/// BCF coordination issue export
+pub mod bcf;
+/// Material carbon import and carbon report export
+pub mod carbon;
+/// COLLADA file export
+pub mod collada;
+/// Cost rates import and cost estimate export
+pub mod cost;
+/// CSV fields and rows shared by the table imports and the reports
+pub(crate) mod csv;
+/// DXF line work import
+pub mod dxf;
+/// DXF plan export
+pub mod dxf_plans;
+/// gbXML energy model export
+pub mod gbxml;
+/// glTF file export
+pub mod gltf;
+/// IFC file export
+pub mod ifc;
+/// Component library folder scanning
+pub mod library;
+/// Linked reference model loading and reloading
+#[cfg(feature = "serde")]
+pub mod link;
+/// Translation catalogs for the interface
+pub mod locale;
+/// Wavefront OBJ import
+pub mod obj;
+/// PDF review set export
+pub mod pdf;
+/// User preferences
+pub mod preferences;
+/// Space program import and report export
+pub mod program;
+/// Project file reading and writing
+#[cfg(feature = "serde")]
+pub mod project;
+/// Recently opened projects
+pub mod recent;
+/// Rule file import and rule report export
+pub mod rules;
+/// Editing session recording and replay files
+#[cfg(feature = "serde")]
+pub mod session;
+/// Office standards for layer names, colors and drawing styles
+pub mod standards;
+/// STL mesh import and export
+pub mod stl;
+/// Structural analysis model export
+pub mod structural;
+/// Survey point import from CSV and JSON
+pub mod survey;
+/// Quantity takeoff export
+pub mod takeoff;
+/// Project templates and the folder they are kept in
+#[cfg(feature = "serde")]
+pub mod templates;
+/// Zip archive writing
+pub mod zip;
+
+/// The folder holding this application's settings, such as
+/// `~/.config/harmony_arch` on Linux
+/// Returns None if the platform has no config folder
+pub(crate) fn config_dir() -> Option<std::path::PathBuf> {
+    dirs::config_dir().map(|folder| folder.join("harmony_arch"))
+}
+
+/// The current UTC time as an ISO 8601 string
+pub(crate) fn current_timestamp() -> String {
+    let seconds = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+    let days = i64::try_from(seconds / 86_400).unwrap_or(0);
+    let time = seconds % 86_400;
+    // Civil date from days since the epoch (Howard Hinnant's algorithm)
+    let shifted = days + 719_468;
+    let era = shifted.div_euclid(146_097);
+    let day_of_era = shifted.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}",
+        time / 3600,
+        time % 3600 / 60,
+        time % 60
+    )
+}
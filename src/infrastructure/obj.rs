@@ -0,0 +1,175 @@
+/// Wavefront OBJ import and export
+///
+/// Reads the faces of an OBJ file as point loops, ready for
+/// `GeometryRegistry::create_solid_from_loops`. Positions are taken as
+/// meters in the model's Y-up axes, which is how most modeling tools
+/// write OBJ. Texture coordinates, normals, groups and materials are
+/// ignored. Export writes each solid as an object named after its
+/// element, one polygon face per solid face with its winding kept.
+use crate::domain::{
+    collect_export_solids, ElementRegistry, ExportFilter, GeometryRegistry, Point,
+};
+use std::fmt::Write as _;
+use std::path::Path;
+
+/// Errors raised while reading an OBJ file
+#[derive(Debug)]
+pub enum ObjError {
+    /// The file could not be read
+    Io(std::io::Error),
+    /// A line could not be parsed
+    Parse {
+        /// The 1-based line number
+        line: usize,
+        /// What was wrong with it
+        message: String,
+    },
+}
+
+impl std::fmt::Display for ObjError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ObjError::Io(error) => write!(f, "Could not read OBJ file: {error}"),
+            ObjError::Parse { line, message } => write!(f, "OBJ line {line}: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for ObjError {}
+
+impl From<std::io::Error> for ObjError {
+    fn from(error: std::io::Error) -> Self {
+        ObjError::Io(error)
+    }
+}
+
+/// Read the faces of an OBJ file as point loops
+///
+/// # Errors
+/// Returns an error if the file cannot be read or parsed.
+#[tracing::instrument(skip_all, fields(path = %path.display()))]
+pub fn read_obj(path: &Path) -> Result<Vec<Vec<Point>>, ObjError> {
+    let loops = parse_obj(&std::fs::read_to_string(path)?)?;
+    tracing::info!(faces = loops.len(), "read OBJ file");
+    Ok(loops)
+}
+
+/// Parse the text of an OBJ file into one point loop per face
+///
+/// Face indices may be 1-based or negative (counting back from the latest
+/// vertex), with or without texture and normal indices.
+///
+/// # Errors
+/// Returns an error naming the first vertex or face that cannot be parsed.
+pub fn parse_obj(text: &str) -> Result<Vec<Vec<Point>>, ObjError> {
+    let mut positions: Vec<Point> = Vec::new();
+    let mut faces = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+        let parse_error = |message: String| ObjError::Parse {
+            line: index + 1,
+            message,
+        };
+        let mut words = line.split_whitespace();
+        match words.next() {
+            Some("v") => {
+                let coordinates: Result<Vec<f32>, _> = words.take(3).map(str::parse).collect();
+                match coordinates {
+                    Ok(values) if values.len() == 3 => positions.push(Point {
+                        x: values[0],
+                        y: values[1],
+                        z: values[2],
+                    }),
+                    _ => return Err(parse_error(format!("expected x y z, found \"{line}\""))),
+                }
+            }
+            Some("f") => {
+                let face = words
+                    .map(|word| {
+                        let reference = word.split('/').next().unwrap_or_default();
+                        let position = reference
+                            .parse::<i64>()
+                            .ok()
+                            .and_then(|number| resolve_index(number, positions.len()))
+                            .ok_or_else(|| {
+                                parse_error(format!("invalid vertex reference \"{word}\""))
+                            })?;
+                        Ok(positions[position].clone())
+                    })
+                    .collect::<Result<Vec<Point>, ObjError>>()?;
+                faces.push(face);
+            }
+            _ => {}
+        }
+    }
+    Ok(faces)
+}
+
+/// Zero-based position index of a 1-based or negative OBJ reference
+fn resolve_index(number: i64, count: usize) -> Option<usize> {
+    let count = i64::try_from(count).ok()?;
+    let index = if number < 0 {
+        count + number
+    } else {
+        number - 1
+    };
+    usize::try_from(index)
+        .ok()
+        .filter(|_| (0..count).contains(&index))
+}
+
+/// Write an OBJ file of the solids in the registry
+///
+/// # Errors
+/// Returns an error if the file cannot be written.
+#[tracing::instrument(skip_all, fields(path = %path.display()))]
+pub fn write_obj(
+    path: &Path,
+    geometry_registry: &GeometryRegistry,
+    element_registry: &ElementRegistry,
+    filter: &ExportFilter,
+) -> std::io::Result<()> {
+    std::fs::write(
+        path,
+        export_obj(geometry_registry, element_registry, filter),
+    )?;
+    tracing::info!(
+        solids = geometry_registry.solids.solids.len(),
+        "wrote OBJ file"
+    );
+    Ok(())
+}
+
+/// Build the text of an OBJ file of the solids the filter includes
+#[must_use]
+pub fn export_obj(
+    geometry_registry: &GeometryRegistry,
+    element_registry: &ElementRegistry,
+    filter: &ExportFilter,
+) -> String {
+    let mut text = String::from("# HarmonyArch\n");
+    let mut count = 0;
+    for export in collect_export_solids(geometry_registry, element_registry, filter) {
+        // Object names end at the line, and whitespace would split them
+        let name: String = export
+            .name()
+            .chars()
+            .map(|c| if c.is_whitespace() { '_' } else { c })
+            .collect();
+        let _ = writeln!(text, "o {name}");
+        for points in geometry_registry
+            .solid_loops(&export.solid.id)
+            .unwrap_or_default()
+        {
+            for point in &points {
+                let _ = writeln!(text, "v {} {} {}", point.x, point.y, point.z);
+            }
+            text.push('f');
+            for index in count + 1..=count + points.len() {
+                let _ = write!(text, " {index}");
+            }
+            text.push('\n');
+            count += points.len();
+        }
+    }
+    text
+}
@@ -0,0 +1,160 @@
+/// PDF review set export
+///
+/// Writes a review set as a PDF with one landscape A3 page per captured
+/// view: the view's picture fitted inside the margins under its title,
+/// set in the office standards text style.
+/// Pictures are embedded as uncompressed RGB samples, so no image codec is
+/// needed; the files are large but open in any PDF reader.
+use crate::infrastructure::standards::TextStyle;
+use std::path::Path;
+
+/// Width of an A3 landscape page in points
+const PAGE_WIDTH: f32 = 1190.55;
+/// Height of an A3 landscape page in points
+const PAGE_HEIGHT: f32 = 841.89;
+/// Margin around the page contents in points
+const MARGIN: f32 = 36.0;
+/// Objects before the first page's: the catalog, page tree and font
+const SHARED_OBJECTS: usize = 3;
+
+/// A captured view for a review set
+#[derive(Debug, Clone)]
+pub struct ReviewPage {
+    /// Title printed above the picture
+    pub title: String,
+    /// Width of the picture in pixels
+    pub width: u32,
+    /// Height of the picture in pixels
+    pub height: u32,
+    /// Red, green and blue samples row by row from the top-left pixel
+    pub rgb: Vec<u8>,
+}
+
+/// Write a review set as a PDF file
+///
+/// # Errors
+/// Returns an error if the file cannot be written.
+#[tracing::instrument(skip_all, fields(path = %path.display()))]
+pub fn write_review_set(
+    path: &Path,
+    pages: &[ReviewPage],
+    style: &TextStyle,
+) -> std::io::Result<()> {
+    std::fs::write(path, export_review_set(pages, style))?;
+    tracing::info!(pages = pages.len(), "wrote review set");
+    Ok(())
+}
+
+/// Build the bytes of a review set PDF
+///
+/// A picture with fewer samples than its size needs is padded with black.
+#[must_use]
+pub fn export_review_set(pages: &[ReviewPage], style: &TextStyle) -> Vec<u8> {
+    let page_number = |index: usize| SHARED_OBJECTS + 1 + index * 3;
+    let kids: Vec<String> = (0..pages.len())
+        .map(|index| format!("{} 0 R", page_number(index)))
+        .collect();
+    let mut objects: Vec<Vec<u8>> = vec![
+        b"<< /Type /Catalog /Pages 2 0 R >>".to_vec(),
+        format!(
+            "<< /Type /Pages /Kids [{}] /Count {} >>",
+            kids.join(" "),
+            pages.len()
+        )
+        .into_bytes(),
+        format!(
+            "<< /Type /Font /Subtype /Type1 /BaseFont /{} >>",
+            style.font
+        )
+        .into_bytes(),
+    ];
+    for (index, page) in pages.iter().enumerate() {
+        let (content, image) = (page_number(index) + 1, page_number(index) + 2);
+        objects.push(
+            format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {PAGE_WIDTH} {PAGE_HEIGHT}] \
+                 /Resources << /Font << /F1 3 0 R >> /XObject << /View {image} 0 R >> >> \
+                 /Contents {content} 0 R >>"
+            )
+            .into_bytes(),
+        );
+        objects.push(stream("", page_contents(page, style.size).as_bytes()));
+
+        let samples =
+            usize::try_from(u64::from(page.width) * u64::from(page.height) * 3).unwrap_or_default();
+        let mut rgb = page.rgb.clone();
+        rgb.resize(samples, 0);
+        objects.push(stream(
+            &format!(
+                "/Type /XObject /Subtype /Image /Width {} /Height {} \
+                 /ColorSpace /DeviceRGB /BitsPerComponent 8 ",
+                page.width, page.height
+            ),
+            &rgb,
+        ));
+    }
+
+    let mut pdf = b"%PDF-1.4\n%\xE2\xE3\xCF\xD3\n".to_vec();
+    let mut offsets = Vec::new();
+    for (index, object) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        pdf.extend(format!("{} 0 obj\n", index + 1).into_bytes());
+        pdf.extend(object);
+        pdf.extend(b"\nendobj\n");
+    }
+    let xref = pdf.len();
+    pdf.extend(format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).into_bytes());
+    for offset in offsets {
+        pdf.extend(format!("{offset:010} 00000 n \n").into_bytes());
+    }
+    pdf.extend(
+        format!(
+            "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{xref}\n%%EOF\n",
+            objects.len() + 1
+        )
+        .into_bytes(),
+    );
+    pdf
+}
+
+/// The drawing commands of a page: its picture fitted under its title,
+/// set at a size in points
+fn page_contents(page: &ReviewPage, title_size: f32) -> String {
+    #[allow(clippy::cast_precision_loss)]
+    let (width, height) = (page.width.max(1) as f32, page.height.max(1) as f32);
+    let title_top = PAGE_HEIGHT - MARGIN - title_size;
+    let scale =
+        ((PAGE_WIDTH - 2.0 * MARGIN) / width).min((title_top - title_size / 2.0 - MARGIN) / height);
+    let (shown_width, shown_height) = (width * scale, height * scale);
+    let left = (PAGE_WIDTH - shown_width) / 2.0;
+    format!(
+        "q {shown_width:.2} 0 0 {shown_height:.2} {left:.2} {MARGIN:.2} cm /View Do Q\n\
+         BT /F1 {title_size:.1} Tf {MARGIN:.2} {title_top:.2} Td ({}) Tj ET\n",
+        pdf_text(&page.title)
+    )
+}
+
+/// A stream object with extra dictionary entries before its length
+fn stream(entries: &str, data: &[u8]) -> Vec<u8> {
+    let mut object = format!("<< {entries}/Length {} >>\nstream\n", data.len()).into_bytes();
+    object.extend(data);
+    object.extend(b"\nendstream");
+    object
+}
+
+/// Text escaped for a PDF string, with characters the built-in font
+/// cannot show replaced by `?`
+fn pdf_text(text: &str) -> String {
+    let mut escaped = String::new();
+    for character in text.chars() {
+        match character {
+            '(' | ')' | '\\' => {
+                escaped.push('\\');
+                escaped.push(character);
+            }
+            ' '..='~' => escaped.push(character),
+            _ => escaped.push('?'),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,346 @@
+/// User preferences
+///
+/// Preferences live in `preferences.json` in the config folder. Each
+/// setting is read on its own, so a missing or malformed entry falls back
+/// to its default without discarding the rest of the file.
+use crate::domain::{Phase, DEFAULT_LINEAR_TOLERANCE};
+use crate::infrastructure::config_dir;
+use crate::infrastructure::locale::ENGLISH;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+
+/// Meters in one foot
+const METERS_PER_FOOT: f32 = 0.3048;
+
+/// Units lengths are typed and shown in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum UnitSystem {
+    /// Meters
+    #[default]
+    Metric,
+    /// Decimal feet
+    Imperial,
+}
+
+impl UnitSystem {
+    /// Every unit system, in menu order
+    pub const ALL: [UnitSystem; 2] = [UnitSystem::Metric, UnitSystem::Imperial];
+
+    /// Name used in the preferences file and the settings dialog
+    #[must_use]
+    pub fn label(self) -> &'static str {
+        match self {
+            UnitSystem::Metric => "Metric",
+            UnitSystem::Imperial => "Imperial",
+        }
+    }
+
+    /// Symbol of the length unit
+    #[must_use]
+    pub fn length_symbol(self) -> &'static str {
+        match self {
+            UnitSystem::Metric => "m",
+            UnitSystem::Imperial => "ft",
+        }
+    }
+
+    /// Convert a length in this system to meters
+    #[must_use]
+    pub fn to_meters(self, length: f32) -> f32 {
+        match self {
+            UnitSystem::Metric => length,
+            UnitSystem::Imperial => length * METERS_PER_FOOT,
+        }
+    }
+
+    /// Convert a length in meters to this system
+    #[must_use]
+    pub fn from_meters(self, meters: f32) -> f32 {
+        match self {
+            UnitSystem::Metric => meters,
+            UnitSystem::Imperial => meters / METERS_PER_FOOT,
+        }
+    }
+}
+
+/// Color scheme of the interface
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Theme {
+    /// Light panels over a dark background
+    #[default]
+    Dark,
+    /// Dark panels over a light background
+    Light,
+}
+
+impl Theme {
+    /// Every theme, in menu order
+    pub const ALL: [Theme; 2] = [Theme::Dark, Theme::Light];
+
+    /// Name used in the preferences file and the settings dialog
+    #[must_use]
+    pub fn label(self) -> &'static str {
+        match self {
+            Theme::Dark => "Dark",
+            Theme::Light => "Light",
+        }
+    }
+}
+
+/// Lighting and background the model is shown in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ScenePreset {
+    /// Bright sun under a blue sky
+    Day,
+    /// Dim moonlight and a lamp under a dark sky
+    Night,
+    /// Even, neutral light over the theme's background
+    #[default]
+    Studio,
+}
+
+impl ScenePreset {
+    /// Every preset, in menu order
+    pub const ALL: [ScenePreset; 3] = [ScenePreset::Day, ScenePreset::Night, ScenePreset::Studio];
+
+    /// Name used in the preferences file and the scene menu
+    #[must_use]
+    pub fn label(self) -> &'static str {
+        match self {
+            ScenePreset::Day => "Day",
+            ScenePreset::Night => "Night",
+            ScenePreset::Studio => "Studio",
+        }
+    }
+}
+
+/// Actions that can be bound to a key
+pub const KEY_ACTIONS: [&str; 10] = [
+    "move_forward",
+    "move_back",
+    "move_left",
+    "move_right",
+    "move_up",
+    "move_down",
+    "orbit_left",
+    "orbit_right",
+    "orbit_up",
+    "orbit_down",
+];
+
+/// The user's preferences
+#[derive(Debug, Clone, PartialEq)]
+pub struct Preferences {
+    /// Units lengths are typed and shown in
+    pub units: UnitSystem,
+    /// Linear tolerance in meters for new models
+    pub linear_tolerance: f32,
+    /// Color scheme of the interface
+    pub theme: Theme,
+    /// Code of the interface language, such as `en`
+    pub language: String,
+    /// Lighting and background the model is shown in
+    pub scene: ScenePreset,
+    /// Camera movement speed in meters per second
+    pub movement_speed: f32,
+    /// Camera orbit speed in radians per second
+    pub rotation_speed: f32,
+    /// Width of segment outlines in pixels
+    pub outline_width: f32,
+    /// Whether each phase's outlines show through the geometry in front
+    /// of them, rather than being hidden behind it
+    pub outlines_on_top: HashMap<Phase, bool>,
+    /// Factor the interface's text and panels are drawn at, 1 for their
+    /// normal size
+    pub text_scale: f32,
+    /// Key bound to each action in `KEY_ACTIONS`, by key name such as `KeyW`
+    pub keymap: BTreeMap<String, String>,
+    /// Minutes between autosaves, or 0 to disable them
+    pub autosave_minutes: u32,
+    /// Name comments are raised under
+    pub author: String,
+}
+
+impl Default for Preferences {
+    fn default() -> Self {
+        let keys = [
+            "KeyW",
+            "KeyS",
+            "KeyA",
+            "KeyD",
+            "KeyQ",
+            "KeyE",
+            "ArrowLeft",
+            "ArrowRight",
+            "ArrowUp",
+            "ArrowDown",
+        ];
+        Self {
+            units: UnitSystem::default(),
+            linear_tolerance: DEFAULT_LINEAR_TOLERANCE,
+            theme: Theme::default(),
+            language: ENGLISH.to_string(),
+            scene: ScenePreset::default(),
+            movement_speed: 2.0,
+            rotation_speed: 3.0,
+            outline_width: 1.5,
+            outlines_on_top: Phase::ALL.into_iter().map(|phase| (phase, true)).collect(),
+            text_scale: 1.0,
+            keymap: KEY_ACTIONS
+                .iter()
+                .zip(keys)
+                .map(|(action, key)| ((*action).to_string(), key.to_string()))
+                .collect(),
+            autosave_minutes: 5,
+            author: std::env::var("USER")
+                .or_else(|_| std::env::var("USERNAME"))
+                .unwrap_or_default(),
+        }
+    }
+}
+
+impl Preferences {
+    /// Whether a phase's outlines are drawn on top, as they are unless
+    /// the preferences say otherwise
+    #[must_use]
+    pub fn outline_on_top(&self, phase: Phase) -> bool {
+        self.outlines_on_top.get(&phase).copied().unwrap_or(true)
+    }
+
+    /// Load the preferences from the config folder
+    #[must_use]
+    pub fn load() -> Self {
+        preferences_file()
+            .and_then(|file| std::fs::read_to_string(file).ok())
+            .map_or_else(Self::default, |text| Self::parse(&text))
+    }
+
+    /// Save the preferences to the config folder
+    ///
+    /// # Errors
+    /// Returns an error if the folder or file cannot be written.
+    pub fn save(&self) -> std::io::Result<()> {
+        let Some(file) = preferences_file() else {
+            return Ok(());
+        };
+        if let Some(folder) = file.parent() {
+            std::fs::create_dir_all(folder)?;
+        }
+        std::fs::write(file, self.export())
+    }
+
+    /// Build the text of a preferences file
+    #[must_use]
+    pub fn export(&self) -> String {
+        let document = json!({
+            "units": self.units.label(),
+            "linear_tolerance": self.linear_tolerance,
+            "theme": self.theme.label(),
+            "language": self.language,
+            "scene": self.scene.label(),
+            "movement_speed": self.movement_speed,
+            "rotation_speed": self.rotation_speed,
+            "outline_width": self.outline_width,
+            "outlines_on_top": Phase::ALL
+                .into_iter()
+                .map(|phase| (phase.label(), self.outline_on_top(phase)))
+                .collect::<BTreeMap<_, _>>(),
+            "text_scale": self.text_scale,
+            "keymap": self.keymap,
+            "autosave_minutes": self.autosave_minutes,
+            "author": self.author,
+        });
+        serde_json::to_string_pretty(&document).unwrap_or_default()
+    }
+
+    /// Parse the text of a preferences file, keeping the default for any
+    /// setting that is missing or malformed
+    #[must_use]
+    pub fn parse(text: &str) -> Self {
+        let mut preferences = Self::default();
+        let Ok(document) = serde_json::from_str::<Value>(text) else {
+            return preferences;
+        };
+        let text_of = |key: &str| document.get(key).and_then(Value::as_str);
+        let positive = |key: &str| {
+            document
+                .get(key)
+                .and_then(Value::as_f64)
+                .filter(|value| *value > 0.0)
+        };
+
+        if let Some(units) = UnitSystem::ALL
+            .into_iter()
+            .find(|units| text_of("units") == Some(units.label()))
+        {
+            preferences.units = units;
+        }
+        if let Some(theme) = Theme::ALL
+            .into_iter()
+            .find(|theme| text_of("theme") == Some(theme.label()))
+        {
+            preferences.theme = theme;
+        }
+        if let Some(language) = text_of("language").filter(|code| !code.is_empty()) {
+            preferences.language = language.to_string();
+        }
+        if let Some(author) = text_of("author").filter(|name| !name.is_empty()) {
+            preferences.author = author.to_string();
+        }
+        if let Some(scene) = ScenePreset::ALL
+            .into_iter()
+            .find(|scene| text_of("scene") == Some(scene.label()))
+        {
+            preferences.scene = scene;
+        }
+        #[allow(clippy::cast_possible_truncation)]
+        {
+            if let Some(value) = positive("linear_tolerance") {
+                preferences.linear_tolerance = value as f32;
+            }
+            if let Some(value) = positive("movement_speed") {
+                preferences.movement_speed = value as f32;
+            }
+            if let Some(value) = positive("rotation_speed") {
+                preferences.rotation_speed = value as f32;
+            }
+            if let Some(value) = positive("outline_width") {
+                preferences.outline_width = value as f32;
+            }
+            if let Some(value) = positive("text_scale") {
+                preferences.text_scale = value as f32;
+            }
+        }
+        if let Some(minutes) = document
+            .get("autosave_minutes")
+            .and_then(Value::as_u64)
+            .and_then(|minutes| u32::try_from(minutes).ok())
+        {
+            preferences.autosave_minutes = minutes;
+        }
+        if let Some(on_top) = document.get("outlines_on_top").and_then(Value::as_object) {
+            for phase in Phase::ALL {
+                if let Some(value) = on_top.get(phase.label()).and_then(Value::as_bool) {
+                    preferences.outlines_on_top.insert(phase, value);
+                }
+            }
+        }
+        if let Some(keymap) = document.get("keymap").and_then(Value::as_object) {
+            for (action, key) in keymap {
+                if let (Some(key), Some(binding)) =
+                    (key.as_str(), preferences.keymap.get_mut(action))
+                {
+                    *binding = key.to_string();
+                }
+            }
+        }
+        preferences
+    }
+}
+
+/// Path of the preferences file
+fn preferences_file() -> Option<PathBuf> {
+    config_dir().map(|folder| folder.join("preferences.json"))
+}
@@ -0,0 +1,160 @@
+/// Space program import and program report export
+///
+/// Programs are CSV rows of `name,target_area[,count]`, areas in square
+/// meters. Reports are written back as CSV with one row per program room
+/// and one row per space that matched no room, for use in spreadsheets.
+use crate::domain::{ProgramReport, ProgramRoom, Space, SpaceProgram};
+use crate::infrastructure::csv::{csv_field, csv_rows};
+use std::path::Path;
+
+/// Errors raised while reading a program
+#[derive(Debug)]
+pub enum ProgramError {
+    /// The file could not be read
+    Io(std::io::Error),
+    /// A row could not be parsed
+    Parse {
+        /// The 1-based line number
+        line: usize,
+        /// What was wrong with it
+        message: String,
+    },
+}
+
+impl std::fmt::Display for ProgramError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProgramError::Io(error) => write!(f, "Could not read program file: {error}"),
+            ProgramError::Parse { line, message } => write!(f, "Program line {line}: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for ProgramError {}
+
+impl From<std::io::Error> for ProgramError {
+    fn from(error: std::io::Error) -> Self {
+        ProgramError::Io(error)
+    }
+}
+
+/// Read a program from a CSV file
+///
+/// # Errors
+/// Returns an error if the file cannot be read or parsed.
+#[tracing::instrument(skip_all, fields(path = %path.display()))]
+pub fn read_program(path: &Path) -> Result<SpaceProgram, ProgramError> {
+    let program = parse_program_csv(&std::fs::read_to_string(path)?)?;
+    tracing::info!(rooms = program.rooms.len(), "read space program");
+    Ok(program)
+}
+
+/// Parse CSV program rows of `name,target_area` with an optional count
+///
+/// Blank lines and lines starting with `#` are skipped, as is a first row
+/// whose area is not a number (a header). Names may be quoted, so a name
+/// holding a comma survives a report being read back. The count defaults
+/// to one.
+///
+/// # Errors
+/// Returns an error naming the first row that cannot be parsed.
+pub fn parse_program_csv(text: &str) -> Result<SpaceProgram, ProgramError> {
+    let mut program = SpaceProgram::default();
+    for row in csv_rows(text) {
+        let area = row.fields.get(1).map(|field| field.parse::<f32>());
+        let count = row
+            .fields
+            .get(2)
+            .map_or(Ok(1), |field| field.parse::<usize>());
+        match (row.field(0), area, count) {
+            (name, Some(Ok(target_area)), Ok(count)) if !name.is_empty() && target_area >= 0.0 => {
+                program.rooms.push(ProgramRoom {
+                    name: name.to_string(),
+                    target_area,
+                    count,
+                });
+            }
+            (_, Some(Err(_)), _) if row.first => {}
+            _ => {
+                return Err(ProgramError::Parse {
+                    line: row.line,
+                    message: format!("expected name,area[,count], found \"{}\"", row.text),
+                })
+            }
+        }
+    }
+    Ok(program)
+}
+
+/// Build the CSV text of a program report
+///
+/// Spaces are looked up to name the ones that matched no program room.
+#[must_use]
+pub fn export_program_report(report: &ProgramReport, spaces: &[Space]) -> String {
+    let mut rows =
+        vec!["room,required,found,target_area,actual_area,difference,status".to_string()];
+    for line in &report.lines {
+        rows.push(format!(
+            "{},{},{},{:.2},{:.2},{:.2},{}",
+            csv_field(&line.name),
+            line.required,
+            line.found,
+            line.target_area,
+            line.actual_area,
+            line.difference(),
+            line.status.label()
+        ));
+    }
+    for space in spaces
+        .iter()
+        .filter(|space| report.unassigned.contains(&space.element))
+    {
+        rows.push(format!(
+            "{},0,1,0.00,{:.2},{:.2},Unassigned",
+            csv_field(&space.name),
+            space.floor_area,
+            space.floor_area
+        ));
+    }
+    rows.push(String::new());
+    rows.join("\n")
+}
+
+/// Write a program report as a CSV file
+///
+/// # Errors
+/// Returns an error if the file cannot be written.
+#[tracing::instrument(skip_all, fields(path = %path.display()))]
+pub fn write_program_report(
+    path: &Path,
+    report: &ProgramReport,
+    spaces: &[Space],
+) -> std::io::Result<()> {
+    std::fs::write(path, export_program_report(report, spaces))?;
+    tracing::info!(rooms = report.lines.len(), "wrote program report");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quoted_names_are_read_back() {
+        let text = format!(
+            "name,area,count\n{},40,2\n{},12.5\n",
+            csv_field("Office, north"),
+            csv_field("The \"quiet\" room")
+        );
+        let program = parse_program_csv(&text).unwrap();
+        let rooms: Vec<(&str, f32, usize)> = program
+            .rooms
+            .iter()
+            .map(|room| (room.name.as_str(), room.target_area, room.count))
+            .collect();
+        assert_eq!(
+            rooms,
+            [("Office, north", 40.0, 2), ("The \"quiet\" room", 12.5, 1)]
+        );
+    }
+}
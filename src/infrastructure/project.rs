@@ -0,0 +1,486 @@
+/// Project files
+///
+/// A project file is the JSON form of the model's registries, as derived
+/// for the `serde` feature, beside the format name and version: the
+/// geometry, the elements, hosted doors and windows, finishes, service
+/// runs, curtain grids and components kept on floors and walls giving it
+/// meaning, the history of the operations that made it, the
+/// review markups, the coordination comments and the external IDs of
+/// imported items. Every item keeps its ID, so references survive a save
+/// and reload. Objects are written with their keys sorted, so saving an
+/// unchanged model gives an identical file.
+///
+/// The model's constraints are written too, so it stays parametric after
+/// a reload: the constraint set on the model as a whole, and the tiers,
+/// each with the tolerance and solver settings it requests and its own
+/// constraint set, with the tolerance the solver works to by default.
+///
+/// The levels and structural grids with their layouts follow, then the
+/// project's standards: the units lengths are shown in, the materials
+/// offered with their colors, and the tier each layer of an imported
+/// drawing goes into. Last comes the georeference placing the model on
+/// the map, if it has one.
+/// Templates preset these. Anything but the geometry may be missing, and
+/// loads empty.
+use crate::domain::solver::ConstraintSet;
+use crate::domain::{
+    CommentRegistry, CurtainGridRegistry, ElementRegistry, ExternalIdMap, FamilyRegistry,
+    FinishRegistry, GeometryRegistry, Georeference, GridRegistry, HostedPlacementRegistry,
+    LevelRegistry, MarkupRegistry, ProvenanceGraph, ServiceRegistry, TierRegistry,
+};
+use crate::infrastructure::config_dir;
+use crate::infrastructure::preferences::UnitSystem;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+/// Extension of project files
+pub const PROJECT_EXTENSION: &str = "harmony";
+/// Format name written to every project file
+const FORMAT: &str = "harmony-project";
+/// The format version this build writes and reads
+const VERSION: u64 = 2;
+
+/// Errors raised while reading or writing a project file
+#[derive(Debug)]
+pub enum ProjectError {
+    /// The file could not be read or written
+    Io(std::io::Error),
+    /// The file is not a valid project
+    Parse(String),
+    /// The model could not be written as JSON
+    Serialize(serde_json::Error),
+}
+
+impl std::fmt::Display for ProjectError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProjectError::Io(error) => write!(f, "Could not access project file: {error}"),
+            ProjectError::Parse(message) => write!(f, "Invalid project file: {message}"),
+            ProjectError::Serialize(error) => write!(f, "Could not write project: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for ProjectError {}
+
+impl From<std::io::Error> for ProjectError {
+    fn from(error: std::io::Error) -> Self {
+        ProjectError::Io(error)
+    }
+}
+
+impl From<serde_json::Error> for ProjectError {
+    fn from(error: serde_json::Error) -> Self {
+        ProjectError::Serialize(error)
+    }
+}
+
+/// What a project file holds
+#[derive(Deserialize)]
+pub struct Project {
+    /// The model's geometry
+    pub geometry: GeometryRegistry,
+    /// The elements giving the solids their meaning
+    #[serde(default = "ElementRegistry::create_new")]
+    pub elements: ElementRegistry,
+    /// The doors and windows hosted in walls
+    #[serde(default = "FamilyRegistry::create_new")]
+    pub families: FamilyRegistry,
+    /// The floor finishes and ceilings generated from spaces
+    #[serde(default = "FinishRegistry::create_new")]
+    pub finishes: FinishRegistry,
+    /// The ducts, pipes and conduits routed through the model
+    #[serde(default = "ServiceRegistry::create_new")]
+    pub services: ServiceRegistry,
+    /// The faces divided into curtain walls
+    #[serde(default = "CurtainGridRegistry::create_new")]
+    pub curtain_grids: CurtainGridRegistry,
+    /// The component instances kept on floors and walls
+    #[serde(default = "HostedPlacementRegistry::create_new")]
+    pub hosted_placements: HostedPlacementRegistry,
+    /// The record of how generated geometry was made
+    #[serde(default = "ProvenanceGraph::create_new")]
+    pub provenance: ProvenanceGraph,
+    /// The review markups drawn over the model
+    #[serde(default = "MarkupRegistry::create_new")]
+    pub markups: MarkupRegistry,
+    /// The coordination comments raised on the model
+    #[serde(default = "CommentRegistry::create_new")]
+    pub comments: CommentRegistry,
+    /// The IDs imported items have in the models they came from
+    #[serde(default = "ExternalIdMap::create_new")]
+    pub external_ids: ExternalIdMap,
+    /// The constraints on the model as a whole
+    #[serde(default)]
+    pub constraints: ConstraintSet,
+    /// The tiers, and the tolerance the solver works to by default
+    #[serde(default = "TierRegistry::create_new")]
+    pub tiers: TierRegistry,
+    /// Each tier's own constraints, by tier ID
+    #[serde(default)]
+    pub tier_constraints: HashMap<Uuid, ConstraintSet>,
+    /// The levels the building is organised by
+    #[serde(default = "LevelRegistry::create_new")]
+    pub levels: LevelRegistry,
+    /// The structural grids and the layouts generated from them
+    #[serde(default = "GridRegistry::create_new")]
+    pub grids: GridRegistry,
+    /// The units, materials and layers the project works to
+    #[serde(default)]
+    pub standards: ProjectStandards,
+    /// Where the model sits on the map, if it is georeferenced
+    #[serde(default)]
+    pub georeference: Option<Georeference>,
+}
+
+/// A project file as written, borrowing what it holds from the model
+#[derive(Serialize)]
+struct ProjectFile<'a> {
+    format: &'static str,
+    version: u64,
+    #[serde(flatten)]
+    model: ProjectModel<'a>,
+    markups: &'a MarkupRegistry,
+    comments: &'a CommentRegistry,
+    external_ids: &'a ExternalIdMap,
+    #[serde(flatten)]
+    constraints: ProjectConstraints<'a>,
+    #[serde(flatten)]
+    setup: ProjectSetup<'a>,
+}
+
+/// A material a project offers, with the color it is shown in
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProjectMaterial {
+    /// The material name, matched ignoring case
+    pub name: String,
+    /// Red, green and blue, each from 0 to 1
+    pub color: [f32; 3],
+}
+
+/// The units, materials and layers a project works to, usually preset by
+/// the template it was started from
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ProjectStandards {
+    /// Units lengths are typed and shown in, or None to keep the user's
+    /// preference
+    pub units: Option<UnitSystem>,
+    /// The materials offered, shown in their colors
+    pub materials: Vec<ProjectMaterial>,
+    /// Tier name for each layer of an imported drawing; `None` skips the
+    /// layer, and unmapped layers go into a tier named after the layer
+    pub layers: BTreeMap<String, Option<String>>,
+}
+
+impl Project {
+    /// An empty project
+    #[must_use]
+    pub fn create_new() -> Self {
+        Self {
+            geometry: GeometryRegistry::create_new(),
+            elements: ElementRegistry::create_new(),
+            families: FamilyRegistry::create_new(),
+            finishes: FinishRegistry::create_new(),
+            services: ServiceRegistry::create_new(),
+            curtain_grids: CurtainGridRegistry::create_new(),
+            hosted_placements: HostedPlacementRegistry::create_new(),
+            provenance: ProvenanceGraph::create_new(),
+            markups: MarkupRegistry::create_new(),
+            comments: CommentRegistry::create_new(),
+            external_ids: ExternalIdMap::create_new(),
+            constraints: ConstraintSet::default(),
+            tiers: TierRegistry::create_new(),
+            tier_constraints: HashMap::new(),
+            levels: LevelRegistry::create_new(),
+            grids: GridRegistry::create_new(),
+            standards: ProjectStandards::default(),
+            georeference: None,
+        }
+    }
+
+    /// The text of the project's file
+    ///
+    /// # Errors
+    /// Returns an error if a registry cannot be written as JSON.
+    pub fn export(&self) -> Result<String, serde_json::Error> {
+        export_project(
+            ProjectModel {
+                geometry: &self.geometry,
+                elements: &self.elements,
+                families: &self.families,
+                finishes: &self.finishes,
+                services: &self.services,
+                curtain_grids: &self.curtain_grids,
+                hosted_placements: &self.hosted_placements,
+                provenance: &self.provenance,
+            },
+            &self.markups,
+            &self.comments,
+            &self.external_ids,
+            ProjectConstraints {
+                constraints: &self.constraints,
+                tiers: &self.tiers,
+                tier_constraints: &self.tier_constraints,
+            },
+            ProjectSetup {
+                levels: &self.levels,
+                grids: &self.grids,
+                standards: &self.standards,
+                georeference: self.georeference.as_ref(),
+            },
+        )
+    }
+}
+
+/// The geometry and the registries giving it meaning to write to a
+/// project file
+#[derive(Clone, Copy, Serialize)]
+pub struct ProjectModel<'a> {
+    /// The model's geometry
+    pub geometry: &'a GeometryRegistry,
+    /// The elements giving the solids their meaning
+    pub elements: &'a ElementRegistry,
+    /// The doors and windows hosted in walls
+    pub families: &'a FamilyRegistry,
+    /// The floor finishes and ceilings generated from spaces
+    pub finishes: &'a FinishRegistry,
+    /// The ducts, pipes and conduits routed through the model
+    pub services: &'a ServiceRegistry,
+    /// The faces divided into curtain walls
+    pub curtain_grids: &'a CurtainGridRegistry,
+    /// The component instances kept on floors and walls
+    pub hosted_placements: &'a HostedPlacementRegistry,
+    /// The record of how generated geometry was made
+    pub provenance: &'a ProvenanceGraph,
+}
+
+/// The constraints to write to a project file and the tiers they are
+/// solved in
+#[derive(Clone, Copy, Serialize)]
+pub struct ProjectConstraints<'a> {
+    /// The constraints on the model as a whole
+    pub constraints: &'a ConstraintSet,
+    /// The tiers, and the tolerance the solver works to by default
+    pub tiers: &'a TierRegistry,
+    /// Each tier's own constraints, by tier ID
+    pub tier_constraints: &'a HashMap<Uuid, ConstraintSet>,
+}
+
+/// The levels, grids, standards and georeference to write to a project
+/// file
+#[derive(Clone, Copy, Serialize)]
+pub struct ProjectSetup<'a> {
+    /// The levels the building is organised by
+    pub levels: &'a LevelRegistry,
+    /// The structural grids and their layouts
+    pub grids: &'a GridRegistry,
+    /// The units, materials and layers the project works to
+    pub standards: &'a ProjectStandards,
+    /// Where the model sits on the map, if it is georeferenced
+    pub georeference: Option<&'a Georeference>,
+}
+
+/// Where to autosave a project
+///
+/// A saved project autosaves beside itself as `<name>.autosave.harmony`,
+/// leaving the file the user saved untouched. An untitled project
+/// autosaves to the config folder.
+#[must_use]
+pub fn autosave_path(project: Option<&Path>) -> Option<PathBuf> {
+    match project {
+        Some(path) => {
+            let stem = path.file_stem()?.to_string_lossy();
+            Some(path.with_file_name(format!("{stem}.autosave.{PROJECT_EXTENSION}")))
+        }
+        None => config_dir().map(|folder| folder.join(format!("autosave.{PROJECT_EXTENSION}"))),
+    }
+}
+
+/// Write a project file
+///
+/// The file is left untouched if the model cannot be written as JSON.
+///
+/// # Errors
+/// Returns an error if the model cannot be written as JSON or the file
+/// cannot be written.
+#[tracing::instrument(skip_all, fields(path = %path.display()))]
+pub fn write_project(
+    path: &Path,
+    model: ProjectModel,
+    markup_registry: &MarkupRegistry,
+    comment_registry: &CommentRegistry,
+    external_ids: &ExternalIdMap,
+    constraints: ProjectConstraints,
+    setup: ProjectSetup,
+) -> Result<(), ProjectError> {
+    let text = export_project(
+        model,
+        markup_registry,
+        comment_registry,
+        external_ids,
+        constraints,
+        setup,
+    )?;
+    std::fs::write(path, text)?;
+    tracing::info!(
+        solids = model.geometry.solids.solids.len(),
+        "wrote project file"
+    );
+    Ok(())
+}
+
+/// Read a project file
+///
+/// # Errors
+/// Returns an error if the file cannot be read or is not a valid project.
+#[tracing::instrument(skip_all, fields(path = %path.display()))]
+pub fn read_project(path: &Path) -> Result<Project, ProjectError> {
+    let project = parse_project(&std::fs::read_to_string(path)?)?;
+    tracing::info!(
+        solids = project.geometry.solids.solids.len(),
+        "read project file"
+    );
+    Ok(project)
+}
+
+/// Build the text of a project file
+///
+/// # Errors
+/// Returns an error if a registry cannot be written as JSON.
+pub fn export_project(
+    model: ProjectModel,
+    markup_registry: &MarkupRegistry,
+    comment_registry: &CommentRegistry,
+    external_ids: &ExternalIdMap,
+    constraints: ProjectConstraints,
+    setup: ProjectSetup,
+) -> Result<String, serde_json::Error> {
+    let document = ProjectFile {
+        format: FORMAT,
+        version: VERSION,
+        model,
+        markups: markup_registry,
+        comments: comment_registry,
+        external_ids,
+        constraints,
+        setup,
+    };
+    // Through a value, whose objects keep their keys sorted
+    serde_json::to_value(&document).and_then(|document| serde_json::to_string_pretty(&document))
+}
+
+/// Parse the text of a project file
+///
+/// # Errors
+/// Returns an error if the text is not a project of a version this build
+/// can read.
+pub fn parse_project(text: &str) -> Result<Project, ProjectError> {
+    let document: Value =
+        serde_json::from_str(text).map_err(|error| ProjectError::Parse(error.to_string()))?;
+    if document.get("format").and_then(Value::as_str) != Some(FORMAT) {
+        return Err(ProjectError::Parse("not a project file".to_string()));
+    }
+    let version = document.get("version").and_then(Value::as_u64);
+    if version != Some(VERSION) {
+        return Err(ProjectError::Parse(format!(
+            "unsupported version {}",
+            version.map_or_else(|| "?".to_string(), |v| v.to_string())
+        )));
+    }
+    let project =
+        Project::deserialize(&document).map_err(|error| ProjectError::Parse(error.to_string()))?;
+    if let Some(tier) = project.tiers.sorted().into_iter().find(|tier| {
+        tier.parent
+            .is_some_and(|parent| project.tiers.get(&parent).is_none())
+    }) {
+        return Err(ProjectError::Parse(format!(
+            "tier {} has a parent that is not in the file",
+            tier.id
+        )));
+    }
+    Ok(project)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::solver::{Constraint, ConstraintKind, SolverOverride};
+    use crate::domain::{
+        CurtainGrid, CurtainGridSettings, FaceOffset, HostedPlacement, LinkTransform,
+    };
+    use crate::infrastructure::templates::builtin_templates;
+
+    #[test]
+    fn saved_projects_read_back_unchanged() {
+        for (_, mut project) in builtin_templates() {
+            let parent = project.tiers.create_and_store("Site", None, None);
+            let tier = project.tiers.create_and_store("Core", Some(parent), None);
+            if let Some(tier) = project.tiers.get_mut(&tier) {
+                tier.solver = SolverOverride {
+                    max_iterations: Some(40),
+                    ..SolverOverride::default()
+                };
+            }
+            let mut constraints = ConstraintSet::default();
+            constraints.opt_out.plumb_enabled = false;
+            constraints.explicit.push(Constraint {
+                kind: ConstraintKind::Parallel,
+                targets: vec![Uuid::from_u128(1), Uuid::from_u128(2)],
+                reference: None,
+            });
+            project.tier_constraints.insert(tier, constraints);
+            let grid = project.curtain_grids.store(CurtainGrid {
+                id: Uuid::from_u128(3),
+                face: Uuid::from_u128(4),
+                settings: CurtainGridSettings::default(),
+                built_for: [0.0, 0.0, 0.0, 1.0, 0.0, 6.0, 3.5],
+                solids: vec![Uuid::from_u128(5)],
+                definitions: vec![Uuid::from_u128(6)],
+            });
+            let placement = project.hosted_placements.store(HostedPlacement {
+                id: Uuid::from_u128(7),
+                solid: Uuid::from_u128(8),
+                host: Uuid::from_u128(9),
+                face: Uuid::from_u128(4),
+                normal: [0.0, 0.0, 1.0],
+                offset: FaceOffset {
+                    along: 1.5,
+                    ..FaceOffset::default()
+                },
+                built_for: LinkTransform::default(),
+            });
+            project.georeference = Some(Georeference {
+                easting: 530_000.25,
+                northing: 180_000.5,
+                rotation: 0.1,
+                epsg: Some(27700),
+                ..Georeference::default()
+            });
+
+            let text = project.export().unwrap();
+            let read = parse_project(&text).unwrap();
+            assert_eq!(read.export().unwrap(), text);
+            assert_eq!(read.georeference, project.georeference);
+            assert_eq!(
+                read.curtain_grids.get(&grid),
+                project.curtain_grids.get(&grid)
+            );
+            assert_eq!(
+                read.hosted_placements.get(&placement),
+                project.hosted_placements.get(&placement)
+            );
+            assert!(!read.tier_constraints[&tier].opt_out.plumb_enabled);
+            assert_eq!(read.tiers.get(&tier).unwrap().parent, Some(parent));
+        }
+    }
+
+    #[test]
+    fn other_formats_and_versions_are_rejected() {
+        assert!(parse_project(r#"{"format":"other","version":2}"#).is_err());
+        let old = r#"{"format":"harmony-project","version":1,"vertices":[]}"#;
+        assert!(parse_project(old).is_err());
+    }
+}
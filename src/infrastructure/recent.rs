@@ -0,0 +1,69 @@
+/// Recently opened projects
+///
+/// The list is kept most recent first in `recent.json` in the config
+/// folder. A missing or unreadable list is treated as empty, since losing
+/// it only costs the user a shortcut.
+use crate::infrastructure::config_dir;
+use std::path::{Path, PathBuf};
+
+/// Most projects remembered
+const MAX_RECENT: usize = 10;
+
+/// The recently opened projects, most recent first
+#[derive(Debug, Clone, Default)]
+pub struct RecentProjects {
+    /// Paths of the projects
+    pub paths: Vec<PathBuf>,
+}
+
+impl RecentProjects {
+    /// Load the list from the config folder
+    #[must_use]
+    pub fn load() -> Self {
+        let paths = recent_file()
+            .and_then(|file| std::fs::read_to_string(file).ok())
+            .and_then(|text| serde_json::from_str::<Vec<String>>(&text).ok())
+            .unwrap_or_default()
+            .into_iter()
+            .map(PathBuf::from)
+            .collect();
+        Self { paths }
+    }
+
+    /// Save the list to the config folder
+    ///
+    /// # Errors
+    /// Returns an error if the folder or file cannot be written.
+    pub fn save(&self) -> std::io::Result<()> {
+        let Some(file) = recent_file() else {
+            return Ok(());
+        };
+        if let Some(folder) = file.parent() {
+            std::fs::create_dir_all(folder)?;
+        }
+        let paths: Vec<String> = self
+            .paths
+            .iter()
+            .map(|path| path.to_string_lossy().into_owned())
+            .collect();
+        std::fs::write(file, serde_json::to_string_pretty(&paths)?)
+    }
+
+    /// Move a project to the top of the list, dropping the oldest beyond
+    /// the limit
+    pub fn add(&mut self, path: &Path) {
+        self.paths.retain(|existing| existing != path);
+        self.paths.insert(0, path.to_path_buf());
+        self.paths.truncate(MAX_RECENT);
+    }
+
+    /// Forget a project, such as one that no longer exists
+    pub fn remove(&mut self, path: &Path) {
+        self.paths.retain(|existing| existing != path);
+    }
+}
+
+/// Path of the recent projects list
+fn recent_file() -> Option<PathBuf> {
+    config_dir().map(|folder| folder.join("recent.json"))
+}
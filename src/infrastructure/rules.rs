@@ -0,0 +1,193 @@
+/// Rule file import and rule report export
+///
+/// Rule files are TOML with one `[[rule]]` table per property rule:
+///
+/// ```toml
+/// [[rule]]
+/// id = "office-area"
+/// description = "Offices are at least 9 m²"
+/// applies_to = "space"
+/// name_contains = "office"
+/// property = "floor_area"
+/// min = 9.0
+/// severity = "fail"
+/// ```
+///
+/// `applies_to` is an element kind, `property` one of `floor_area`,
+/// `volume`, `height`, `width` or `length`, and `severity` `warn` or
+/// `fail` (the default). Reports are written as CSV with one row per
+/// finding, for use in spreadsheets.
+use crate::domain::rules::{PropertyRule, RuleOutcome, RuleProperty, RuleReport};
+use crate::domain::ElementKind;
+use crate::infrastructure::csv::csv_field;
+use std::path::Path;
+use toml_edit::{ImDocument, Item, Table};
+
+/// Errors raised while reading a rule file
+#[derive(Debug)]
+pub enum RuleFileError {
+    /// The file could not be read
+    Io(std::io::Error),
+    /// The file is not valid TOML or a rule could not be parsed
+    Parse {
+        /// The 1-based line number
+        line: usize,
+        /// What was wrong with it
+        message: String,
+    },
+}
+
+impl std::fmt::Display for RuleFileError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RuleFileError::Io(error) => write!(f, "Could not read rule file: {error}"),
+            RuleFileError::Parse { line, message } => write!(f, "Rule file line {line}: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for RuleFileError {}
+
+impl From<std::io::Error> for RuleFileError {
+    fn from(error: std::io::Error) -> Self {
+        RuleFileError::Io(error)
+    }
+}
+
+/// Read property rules from a TOML rule file
+///
+/// # Errors
+/// Returns an error if the file cannot be read or parsed.
+#[tracing::instrument(skip_all, fields(path = %path.display()))]
+pub fn read_rules(path: &Path) -> Result<Vec<PropertyRule>, RuleFileError> {
+    let rules = parse_rules_toml(&std::fs::read_to_string(path)?)?;
+    tracing::info!(rules = rules.len(), "read rule file");
+    Ok(rules)
+}
+
+/// The 1-based line a byte offset falls on
+fn line_of(text: &str, offset: usize) -> usize {
+    text[..offset.min(text.len())].matches('\n').count() + 1
+}
+
+/// A number field, accepting integers as well as floats
+#[allow(clippy::cast_precision_loss, clippy::cast_possible_truncation)]
+fn number(table: &Table, key: &str) -> Result<Option<f32>, String> {
+    match table.get(key) {
+        None => Ok(None),
+        Some(item) => item
+            .as_float()
+            .or_else(|| item.as_integer().map(|value| value as f64))
+            .map(|value| Some(value as f32))
+            .ok_or_else(|| format!("{key} must be a number")),
+    }
+}
+
+/// A required string field
+fn text<'a>(table: &'a Table, key: &str) -> Result<&'a str, String> {
+    table
+        .get(key)
+        .and_then(Item::as_str)
+        .ok_or_else(|| format!("{key} must be given as a string"))
+}
+
+/// Parse one `[[rule]]` table
+fn parse_rule(table: &Table) -> Result<PropertyRule, String> {
+    let id = text(table, "id")?.to_string();
+    let applies_to = text(table, "applies_to")?;
+    let applies_to = ElementKind::from_label(applies_to)
+        .ok_or_else(|| format!("unknown element kind \"{applies_to}\""))?;
+    let property = text(table, "property")?;
+    let property = RuleProperty::from_key(property)
+        .ok_or_else(|| format!("unknown property \"{property}\""))?;
+    let severity = match table.get("severity").map(Item::as_str) {
+        None | Some(Some("fail")) => RuleOutcome::Fail,
+        Some(Some("warn")) => RuleOutcome::Warn,
+        Some(_) => return Err("severity must be \"warn\" or \"fail\"".to_string()),
+    };
+    let (min, max) = (number(table, "min")?, number(table, "max")?);
+    if min.is_none() && max.is_none() {
+        return Err(format!("rule \"{id}\" needs a min or a max"));
+    }
+    let name_contains = match table.get("name_contains") {
+        None => None,
+        Some(_) => Some(text(table, "name_contains")?.to_string()),
+    };
+    let description = match table.get("description") {
+        None => format!("{} {} within limits", applies_to.label(), property.key()),
+        Some(_) => text(table, "description")?.to_string(),
+    };
+    Ok(PropertyRule {
+        id,
+        description,
+        applies_to,
+        name_contains,
+        property,
+        min,
+        max,
+        severity,
+    })
+}
+
+/// Parse the `[[rule]]` tables of a TOML rule file
+///
+/// A file without rules parses to an empty list.
+///
+/// # Errors
+/// Returns an error naming the line of invalid TOML or of the first rule
+/// that cannot be parsed.
+pub fn parse_rules_toml(text: &str) -> Result<Vec<PropertyRule>, RuleFileError> {
+    let document = ImDocument::parse(text).map_err(|error| RuleFileError::Parse {
+        line: error.span().map_or(1, |span| line_of(text, span.start)),
+        message: error.message().to_string(),
+    })?;
+    let Some(rules) = document.get("rule") else {
+        return Ok(Vec::new());
+    };
+    let Some(rules) = rules.as_array_of_tables() else {
+        return Err(RuleFileError::Parse {
+            line: rules.span().map_or(1, |span| line_of(text, span.start)),
+            message: "rules must be [[rule]] tables".to_string(),
+        });
+    };
+    rules
+        .iter()
+        .map(|table| {
+            parse_rule(table).map_err(|message| RuleFileError::Parse {
+                line: table.span().map_or(1, |span| line_of(text, span.start)),
+                message,
+            })
+        })
+        .collect()
+}
+
+/// Build the CSV text of a rule report
+///
+/// Entity IDs are joined with semicolons in one column.
+#[must_use]
+pub fn export_rule_report(report: &RuleReport) -> String {
+    let mut rows = vec!["rule,outcome,entities,message".to_string()];
+    for finding in &report.findings {
+        let entities: Vec<String> = finding.entities.iter().map(ToString::to_string).collect();
+        rows.push(format!(
+            "{},{},{},{}",
+            csv_field(&finding.rule),
+            finding.outcome.label(),
+            entities.join(";"),
+            csv_field(&finding.message)
+        ));
+    }
+    rows.push(String::new());
+    rows.join("\n")
+}
+
+/// Write a rule report as a CSV file
+///
+/// # Errors
+/// Returns an error if the file cannot be written.
+#[tracing::instrument(skip_all, fields(path = %path.display()))]
+pub fn write_rule_report(path: &Path, report: &RuleReport) -> std::io::Result<()> {
+    std::fs::write(path, export_rule_report(report))?;
+    tracing::info!(findings = report.findings.len(), "wrote rule report");
+    Ok(())
+}
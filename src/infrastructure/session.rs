@@ -0,0 +1,570 @@
+/// Session recordings
+///
+/// A session recording logs every model command given while it runs, so
+/// that a bug can be reproduced or a sequence of edits played back as a
+/// macro. It is a JSON Lines file: a header line holding the format, the
+/// seed new IDs were drawn from and the project the session started from,
+/// as a project file holds it, then one line per command with the seconds
+/// since recording started, the frame it was carried out in, its name and
+/// its fields, as derived for the `serde` feature. Lines are written as
+/// the session goes, so a recording survives the crash it is meant to
+/// reproduce.
+///
+/// Commands refer to the geometry and elements they work on by ID, and
+/// those made during the session have IDs drawn from the seeded sequence,
+/// so replaying the commands on the starting project with the same seed
+/// makes the same IDs and every reference holds.
+use crate::domain::geometry::Plane;
+use crate::domain::solver::Constraint;
+use crate::domain::{
+    CurtainGridSettings, FaceOffset, FamilyParameters, FinishLayer, GridDatum, GridEdit,
+    GridLayout, LinkTransform, MassConversionSettings, MassFaceRole, OperationChange, PhaseFilter,
+    Point, ServiceKind, ServiceSection, WallAssembly,
+};
+use crate::infrastructure::config_dir;
+use crate::infrastructure::project::{parse_project, Project, ProjectError};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+/// Extension of session recordings
+pub const SESSION_EXTENSION: &str = "harmony-session";
+/// Format name written to every session header
+const FORMAT: &str = "harmony-session";
+/// The format version this build writes and reads
+const VERSION: u64 = 2;
+
+/// Errors raised while reading or writing a session recording
+#[derive(Debug)]
+pub enum SessionError {
+    /// The file could not be read or written
+    Io(std::io::Error),
+    /// The file is not a valid recording
+    Parse(String),
+}
+
+impl std::fmt::Display for SessionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SessionError::Io(error) => write!(f, "Could not access session recording: {error}"),
+            SessionError::Parse(message) => write!(f, "Invalid session recording: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for SessionError {}
+
+impl From<std::io::Error> for SessionError {
+    fn from(error: std::io::Error) -> Self {
+        SessionError::Io(error)
+    }
+}
+
+impl From<ProjectError> for SessionError {
+    fn from(error: ProjectError) -> Self {
+        match error {
+            ProjectError::Io(error) => SessionError::Io(error),
+            ProjectError::Parse(message) => SessionError::Parse(message),
+            ProjectError::Serialize(error) => SessionError::Io(error.into()),
+        }
+    }
+}
+
+/// A recorded model command, with the fields of the command event it was
+/// given as
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "command")]
+pub enum SessionCommand {
+    /// Build a straight wall rising from a line
+    CreateWall {
+        /// Start of the wall's center line, at its base
+        start: Point,
+        /// End of the wall's center line
+        end: Point,
+        /// Wall thickness in meters, unless built of an assembly
+        thickness: f32,
+        /// Wall height in meters
+        height: f32,
+        /// The layers to build the wall of, if any
+        assembly: Option<WallAssembly>,
+        /// Whether to model each layer as its own solid
+        per_layer: bool,
+    },
+    /// Place a door or window in a wall
+    PlaceFamily {
+        /// The wall element to host it
+        host: Uuid,
+        /// Distance of the opening's center from the wall's start
+        offset: f32,
+        /// The parameters to generate it from
+        parameters: FamilyParameters,
+    },
+    /// Generate floor finishes or ceilings for a space
+    GenerateFinishes {
+        /// The space element
+        space: Uuid,
+        /// The finish
+        layer: FinishLayer,
+    },
+    /// Route a duct, pipe or conduit along a centerline
+    RouteService {
+        /// What the run carries
+        kind: ServiceKind,
+        /// Its cross-section
+        section: ServiceSection,
+        /// The centerline, from start to end
+        path: Vec<Point>,
+    },
+    /// Convert a massing solid into walls, roofs and floors
+    ConvertMass {
+        /// The massing solid
+        solid: Uuid,
+        /// Roles of faces tagged by hand
+        tags: BTreeMap<Uuid, MassFaceRole>,
+        /// What to build the elements of
+        settings: MassConversionSettings,
+    },
+    /// Add a structural grid
+    CreateGrid {
+        /// The grid
+        grid: GridDatum,
+    },
+    /// Generate columns or walls from a grid
+    AddGridLayout {
+        /// The grid
+        grid: Uuid,
+        /// What to generate
+        layout: GridLayout,
+    },
+    /// Edit a grid
+    EditGrid {
+        /// The grid
+        grid: Uuid,
+        /// The edit
+        edit: GridEdit,
+    },
+    /// Sweep a face along its normal into a solid
+    ExtrudeFace {
+        /// The face
+        face: Uuid,
+        /// How far to sweep it
+        depth: f32,
+    },
+    /// Keep the part of a solid on one side of a plane
+    CutSolid {
+        /// The solid
+        solid: Uuid,
+        /// The plane
+        plane: Plane,
+        /// Whether to keep the part in front of the plane
+        keep_front: bool,
+    },
+    /// Divide a vertical face into a curtain wall
+    CreateCurtainGrid {
+        /// The face
+        face: Uuid,
+        /// How to divide it
+        settings: CurtainGridSettings,
+    },
+    /// Place a component where no floor or wall holds it
+    PlaceComponent {
+        /// The component definition
+        definition: Uuid,
+        /// Where the instance goes
+        placement: LinkTransform,
+    },
+    /// Place a component on a floor or a wall
+    PlaceOnFace {
+        /// The component definition
+        definition: Uuid,
+        /// A face of the floor or wall
+        face: Uuid,
+        /// Where it sits in the host's frame
+        offset: FaceOffset,
+    },
+    /// Place copies of a component along a floor or a wall
+    DistributeOnFace {
+        /// The component definition
+        definition: Uuid,
+        /// A face of the floor or wall
+        face: Uuid,
+        /// How many copies to place
+        count: usize,
+        /// Where each copy sits in the host's frame, apart from along it
+        offset: FaceOffset,
+    },
+    /// Run an extension's command
+    RunExtensionCommand {
+        /// The command's name
+        name: String,
+        /// Its arguments, as they were given
+        arguments: String,
+    },
+    /// Change a past operation
+    EditOperation {
+        /// The operation's record
+        record: Uuid,
+        /// The change
+        change: OperationChange,
+    },
+    /// Draw a path of sketch segments through points
+    AddSketchPath {
+        /// The points the path joins in order
+        points: Vec<Point>,
+    },
+    /// Move a vertex
+    MoveVertex {
+        /// The vertex
+        vertex: Uuid,
+        /// Where it goes
+        position: Point,
+    },
+    /// Transform a set of vertices
+    TransformVertices {
+        /// The vertices
+        vertices: Vec<Uuid>,
+        /// The transform's matrix, column by column
+        transform: [f32; 16],
+    },
+    /// Add an explicit constraint
+    AddConstraint {
+        /// The constraint
+        constraint: Constraint,
+    },
+    /// Remove an explicit constraint
+    RemoveConstraint {
+        /// The constraint
+        constraint: Constraint,
+    },
+    /// Add or move a level
+    SetLevel {
+        /// The level's name
+        name: String,
+        /// Its height in meters
+        elevation: f32,
+    },
+    /// Keep a property equal to an expression
+    AddExpressionConstraint {
+        /// The constraint as written
+        text: String,
+    },
+    /// Give one face of a solid's element its own material
+    SetFaceMaterial {
+        /// The solid the face belongs to
+        solid: Uuid,
+        /// The face
+        polygon: Uuid,
+        /// The material, or None for the element's
+        material: Option<String>,
+    },
+    /// Write the model's solids to an STL file
+    ExportStl {
+        /// The file to write
+        path: PathBuf,
+        /// The phases to include
+        phases: PhaseFilter,
+    },
+}
+
+impl SessionCommand {
+    /// The name of the command event, as written to the recording
+    #[must_use]
+    pub fn name(&self) -> &'static str {
+        match self {
+            SessionCommand::CreateWall { .. } => "CreateWall",
+            SessionCommand::PlaceFamily { .. } => "PlaceFamily",
+            SessionCommand::GenerateFinishes { .. } => "GenerateFinishes",
+            SessionCommand::RouteService { .. } => "RouteService",
+            SessionCommand::ConvertMass { .. } => "ConvertMass",
+            SessionCommand::CreateGrid { .. } => "CreateGrid",
+            SessionCommand::AddGridLayout { .. } => "AddGridLayout",
+            SessionCommand::EditGrid { .. } => "EditGrid",
+            SessionCommand::ExtrudeFace { .. } => "ExtrudeFace",
+            SessionCommand::CutSolid { .. } => "CutSolid",
+            SessionCommand::CreateCurtainGrid { .. } => "CreateCurtainGrid",
+            SessionCommand::PlaceComponent { .. } => "PlaceComponent",
+            SessionCommand::PlaceOnFace { .. } => "PlaceOnFace",
+            SessionCommand::DistributeOnFace { .. } => "DistributeOnFace",
+            SessionCommand::RunExtensionCommand { .. } => "RunExtensionCommand",
+            SessionCommand::EditOperation { .. } => "EditOperation",
+            SessionCommand::AddSketchPath { .. } => "AddSketchPath",
+            SessionCommand::MoveVertex { .. } => "MoveVertex",
+            SessionCommand::TransformVertices { .. } => "TransformVertices",
+            SessionCommand::AddConstraint { .. } => "AddConstraint",
+            SessionCommand::RemoveConstraint { .. } => "RemoveConstraint",
+            SessionCommand::SetLevel { .. } => "SetLevel",
+            SessionCommand::AddExpressionConstraint { .. } => "AddExpressionConstraint",
+            SessionCommand::SetFaceMaterial { .. } => "SetFaceMaterial",
+            SessionCommand::ExportStl { .. } => "ExportStl",
+        }
+    }
+}
+
+/// One recorded command and when it was given
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionEntry {
+    /// Seconds since recording started
+    pub time: f64,
+    /// The frame since recording started the command was carried out in;
+    /// commands of the same frame are replayed together
+    pub frame: u64,
+    /// The command
+    #[serde(flatten)]
+    pub command: SessionCommand,
+}
+
+/// What a session recording holds
+pub struct Session {
+    /// The seed the session's new IDs were drawn from
+    pub seed: u64,
+    /// The project the session started from
+    pub project: Project,
+    /// The commands, in the order they were given
+    pub entries: Vec<SessionEntry>,
+}
+
+/// Where to record a session started at a time, in seconds since the Unix
+/// epoch
+///
+/// A saved project's sessions are recorded beside it as
+/// `<name>.<time>.harmony-session`; an untitled project's go in the
+/// `sessions` folder of the config folder.
+#[must_use]
+pub fn session_path(project: Option<&Path>, time: u64) -> Option<PathBuf> {
+    match project {
+        Some(path) => {
+            let stem = path.file_stem()?.to_string_lossy();
+            Some(path.with_file_name(format!("{stem}.{time}.{SESSION_EXTENSION}")))
+        }
+        None => config_dir().map(|folder| {
+            folder
+                .join("sessions")
+                .join(format!("session.{time}.{SESSION_EXTENSION}"))
+        }),
+    }
+}
+
+/// A session recording being written
+pub struct SessionWriter {
+    file: std::io::BufWriter<std::fs::File>,
+}
+
+impl SessionWriter {
+    /// Start a recording, writing its header
+    ///
+    /// `project` is the text of a project file holding the model the
+    /// session starts from.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be written or the project text
+    /// is not JSON.
+    #[tracing::instrument(skip(project), fields(path = %path.display()))]
+    pub fn create(path: &Path, seed: u64, project: &str) -> Result<Self, SessionError> {
+        let project: Value = serde_json::from_str(project)
+            .map_err(|error| SessionError::Parse(error.to_string()))?;
+        if let Some(folder) = path.parent() {
+            std::fs::create_dir_all(folder)?;
+        }
+        let mut writer = Self {
+            file: std::io::BufWriter::new(std::fs::File::create(path)?),
+        };
+        writer.line(&json!({
+            "format": FORMAT,
+            "version": VERSION,
+            "seed": seed,
+            "project": project,
+        }))?;
+        writer.flush()?;
+        Ok(writer)
+    }
+
+    /// Append a command to the recording
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be written.
+    pub fn write(&mut self, entry: &SessionEntry) -> Result<(), SessionError> {
+        self.line(entry)
+    }
+
+    /// Write what has been appended through to the file
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be written.
+    pub fn flush(&mut self) -> Result<(), SessionError> {
+        Ok(self.file.flush()?)
+    }
+
+    /// Write one line of the file
+    fn line(&mut self, value: &impl Serialize) -> Result<(), SessionError> {
+        serde_json::to_writer(&mut self.file, value).map_err(std::io::Error::from)?;
+        writeln!(self.file)?;
+        Ok(())
+    }
+}
+
+/// Read a session recording
+///
+/// # Errors
+/// Returns an error if the file cannot be read or is not a valid
+/// recording.
+#[tracing::instrument(skip_all, fields(path = %path.display()))]
+pub fn read_session(path: &Path) -> Result<Session, SessionError> {
+    let session = parse_session(&std::fs::read_to_string(path)?)?;
+    tracing::info!(commands = session.entries.len(), "read session recording");
+    Ok(session)
+}
+
+/// Parse the text of a session recording
+///
+/// # Errors
+/// Returns an error if the text is not a valid recording.
+pub fn parse_session(text: &str) -> Result<Session, SessionError> {
+    let mut lines = text
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty());
+    let (_, header) = lines
+        .next()
+        .ok_or_else(|| SessionError::Parse("the file is empty".to_string()))?;
+    let header: Value = parse_line(header, 0)?;
+    if header.get("format").and_then(Value::as_str) != Some(FORMAT) {
+        return Err(SessionError::Parse("not a session recording".to_string()));
+    }
+    let version = header.get("version").and_then(Value::as_u64);
+    if version != Some(VERSION) {
+        return Err(SessionError::Parse(format!(
+            "unsupported version {}",
+            version.map_or_else(|| "?".to_string(), |v| v.to_string())
+        )));
+    }
+    let seed = header
+        .get("seed")
+        .and_then(Value::as_u64)
+        .ok_or_else(|| SessionError::Parse("the header has no seed".to_string()))?;
+    let project = parse_project(&field(&header, "project")?.to_string())?;
+    let entries = lines
+        .map(|(index, line)| parse_line(line, index))
+        .collect::<Result<_, SessionError>>()?;
+    Ok(Session {
+        seed,
+        project,
+        entries,
+    })
+}
+
+/// Parse one line of a recording
+fn parse_line<T: DeserializeOwned>(line: &str, index: usize) -> Result<T, SessionError> {
+    serde_json::from_str(line)
+        .map_err(|error| SessionError::Parse(format!("line {}: {error}", index + 1)))
+}
+
+/// A field of an object, which must be present
+fn field<'a>(item: &'a Value, key: &str) -> Result<&'a Value, SessionError> {
+    item.get(key)
+        .ok_or_else(|| SessionError::Parse(format!("missing \"{key}\"")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{new_id, standard_wall_assemblies, GridFamily, Operation};
+
+    fn point(x: f32, y: f32, z: f32) -> Point {
+        Point { x, y, z }
+    }
+
+    #[test]
+    fn recorded_commands_replay_unchanged() {
+        let commands = vec![
+            SessionCommand::CreateWall {
+                start: point(0.0, 0.0, 0.0),
+                end: point(4.0, 0.0, 0.0),
+                thickness: 0.2,
+                height: 3.0,
+                assembly: standard_wall_assemblies().into_iter().next(),
+                per_layer: true,
+            },
+            SessionCommand::RouteService {
+                kind: ServiceKind::Duct,
+                section: ServiceSection::Round { diameter: 0.3 },
+                path: vec![point(0.0, 0.0, 2.5), point(3.0, 0.0, 2.5)],
+            },
+            SessionCommand::ConvertMass {
+                solid: new_id(),
+                tags: BTreeMap::from([(new_id(), MassFaceRole::Roof)]),
+                settings: MassConversionSettings::default(),
+            },
+            SessionCommand::EditGrid {
+                grid: new_id(),
+                edit: GridEdit::Spacings {
+                    family: GridFamily::Lettered,
+                    spacings: vec![6.0, 7.5],
+                },
+            },
+            SessionCommand::EditOperation {
+                record: new_id(),
+                change: OperationChange::Parameters(Operation::Extrude { depth: -0.5 }),
+            },
+            SessionCommand::DistributeOnFace {
+                definition: new_id(),
+                face: new_id(),
+                count: 3,
+                offset: FaceOffset {
+                    standoff: 0.1,
+                    ..FaceOffset::default()
+                },
+            },
+            SessionCommand::TransformVertices {
+                vertices: vec![new_id()],
+                transform: std::array::from_fn(|index| index as f32),
+            },
+            SessionCommand::ExportStl {
+                path: PathBuf::from("model.stl"),
+                phases: PhaseFilter {
+                    existing: false,
+                    ..PhaseFilter::default()
+                },
+            },
+        ];
+        let entries: Vec<SessionEntry> = commands
+            .into_iter()
+            .enumerate()
+            .map(|(frame, command)| SessionEntry {
+                time: frame as f64 * 0.5,
+                frame: frame as u64,
+                command,
+            })
+            .collect();
+
+        let folder = tempfile::tempdir().unwrap();
+        let path = folder.path().join(format!("test.{SESSION_EXTENSION}"));
+        let mut writer =
+            SessionWriter::create(&path, 42, &Project::create_new().export().unwrap()).unwrap();
+        for entry in &entries {
+            writer.write(entry).unwrap();
+        }
+        writer.flush().unwrap();
+
+        let session = read_session(&path).unwrap();
+        assert_eq!(session.seed, 42);
+        let as_json = |entries: &[SessionEntry]| serde_json::to_value(entries).unwrap();
+        assert_eq!(as_json(&session.entries), as_json(&entries));
+    }
+
+    #[test]
+    fn other_formats_and_versions_are_rejected() {
+        let project: Value =
+            serde_json::from_str(&Project::create_new().export().unwrap()).unwrap();
+        let header = |format: &str, version: u64| {
+            json!({ "format": format, "version": version, "seed": 1, "project": project })
+                .to_string()
+        };
+        assert!(parse_session(&header(FORMAT, VERSION)).is_ok());
+        assert!(parse_session(&header("other", VERSION)).is_err());
+        assert!(parse_session(&header(FORMAT, 1)).is_err());
+    }
+}
@@ -0,0 +1,366 @@
+/// Office standards files
+///
+/// An office standards file is TOML kept beside the settings, describing
+/// how a firm's drawings and exports look:
+///
+/// ```toml
+/// layer_pattern = "A-{KIND}"
+///
+/// [layers]
+/// column = "S-COLS"
+///
+/// [colors]
+/// wall = [0.85, 0.82, 0.76]
+///
+/// [dimensions]
+/// decimals = 2
+/// show_units = false
+///
+/// [text]
+/// font = "Helvetica"
+/// size = 12.0
+/// ```
+///
+/// Layers are named by `layer_pattern`, where `{KIND}` and `{kind}` stand
+/// for the element kind in upper and lower case, unless `[layers]` names
+/// the kind's layer outright. `[colors]` gives red, green and blue from 0
+/// to 1 per element kind. The dimension style sets how lengths read in
+/// on-screen dimensions, and the text style the font and size of drawing
+/// titles. A missing file or table keeps the built-in standards.
+use crate::domain::ElementKind;
+use crate::infrastructure::config_dir;
+use crate::infrastructure::preferences::UnitSystem;
+use std::collections::HashMap;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use toml_edit::{ImDocument, Item, Table, Value};
+
+/// Name of the office standards file in the settings folder
+const STANDARDS_FILE: &str = "standards.toml";
+
+/// Errors raised while reading an office standards file
+#[derive(Debug)]
+pub enum StandardsFileError {
+    /// The file could not be read
+    Io(std::io::Error),
+    /// The file is not valid TOML or a setting could not be parsed
+    Parse {
+        /// The 1-based line number
+        line: usize,
+        /// What was wrong with it
+        message: String,
+    },
+}
+
+impl std::fmt::Display for StandardsFileError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StandardsFileError::Io(error) => write!(f, "Could not read standards file: {error}"),
+            StandardsFileError::Parse { line, message } => {
+                write!(f, "Standards file line {line}: {message}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for StandardsFileError {}
+
+impl From<std::io::Error> for StandardsFileError {
+    fn from(error: std::io::Error) -> Self {
+        StandardsFileError::Io(error)
+    }
+}
+
+/// How lengths read in dimensions
+#[derive(Debug, Clone, PartialEq)]
+pub struct DimensionStyle {
+    /// Digits after the decimal point
+    pub decimals: usize,
+    /// Whether the unit symbol follows the value
+    pub show_units: bool,
+}
+
+impl Default for DimensionStyle {
+    fn default() -> Self {
+        Self {
+            decimals: 3,
+            show_units: true,
+        }
+    }
+}
+
+impl DimensionStyle {
+    /// A length in meters as dimension text in the given units
+    #[must_use]
+    pub fn format(&self, meters: f32, units: UnitSystem) -> String {
+        let value = units.from_meters(meters);
+        if self.show_units {
+            format!("{value:.*} {}", self.decimals, units.length_symbol())
+        } else {
+            format!("{value:.*}", self.decimals)
+        }
+    }
+}
+
+/// The font and size of drawing text
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextStyle {
+    /// One of the standard PDF fonts in `TextStyle::FONTS`
+    pub font: String,
+    /// Text height in points
+    pub size: f32,
+}
+
+impl Default for TextStyle {
+    fn default() -> Self {
+        Self {
+            font: "Helvetica".to_string(),
+            size: 14.0,
+        }
+    }
+}
+
+impl TextStyle {
+    /// Fonts every PDF reader has built in
+    pub const FONTS: [&'static str; 6] = [
+        "Helvetica",
+        "Helvetica-Bold",
+        "Times-Roman",
+        "Times-Bold",
+        "Courier",
+        "Courier-Bold",
+    ];
+}
+
+/// A firm's layer names, element colors and drawing styles
+#[derive(Debug, Clone, PartialEq)]
+pub struct OfficeStandards {
+    /// Layer name for kinds without their own, with `{KIND}` or `{kind}`
+    /// standing for the element kind
+    pub layer_pattern: String,
+    /// Layer names by element kind
+    pub layers: HashMap<ElementKind, String>,
+    /// Default colors by element kind
+    pub colors: HashMap<ElementKind, [f32; 3]>,
+    /// How lengths read in dimensions
+    pub dimensions: DimensionStyle,
+    /// The font and size of drawing text
+    pub text: TextStyle,
+}
+
+impl Default for OfficeStandards {
+    /// The US National CAD Standard layer names, without colors
+    fn default() -> Self {
+        let layers = [
+            (ElementKind::Wall, "A-WALL"),
+            (ElementKind::Slab, "A-FLOR"),
+            (ElementKind::Column, "S-COLS"),
+            (ElementKind::Beam, "S-BEAM"),
+            (ElementKind::Door, "A-DOOR"),
+            (ElementKind::Window, "A-GLAZ"),
+            (ElementKind::Space, "A-AREA"),
+            (ElementKind::Duct, "M-DUCT"),
+            (ElementKind::Pipe, "P-PIPE"),
+            (ElementKind::Conduit, "E-COND"),
+        ];
+        Self {
+            layer_pattern: "A-{KIND}".to_string(),
+            layers: layers
+                .into_iter()
+                .map(|(kind, layer)| (kind, layer.to_string()))
+                .collect(),
+            colors: HashMap::new(),
+            dimensions: DimensionStyle::default(),
+            text: TextStyle::default(),
+        }
+    }
+}
+
+impl OfficeStandards {
+    /// The layer elements of a kind go on
+    #[must_use]
+    pub fn layer_for(&self, kind: ElementKind) -> String {
+        self.layers.get(&kind).cloned().unwrap_or_else(|| {
+            self.layer_pattern
+                .replace("{KIND}", &kind.label().to_uppercase())
+                .replace("{kind}", &kind.label().to_lowercase())
+        })
+    }
+
+    /// The default color of elements of a kind, if the standards set one
+    #[must_use]
+    pub fn color_for(&self, kind: ElementKind) -> Option<[f32; 3]> {
+        self.colors.get(&kind).copied()
+    }
+}
+
+/// The office standards file in the settings folder, or None if the
+/// platform has no config folder
+#[must_use]
+pub fn standards_path() -> Option<PathBuf> {
+    config_dir().map(|folder| folder.join(STANDARDS_FILE))
+}
+
+/// Read the office standards file in the settings folder, keeping the
+/// built-in standards when there is none
+///
+/// # Errors
+/// Returns an error if the file exists but cannot be read or parsed.
+pub fn load_office_standards() -> Result<OfficeStandards, StandardsFileError> {
+    match standards_path() {
+        Some(path) if path.exists() => read_office_standards(&path),
+        _ => Ok(OfficeStandards::default()),
+    }
+}
+
+/// Read an office standards file
+///
+/// # Errors
+/// Returns an error if the file cannot be read or parsed.
+#[tracing::instrument(skip_all, fields(path = %path.display()))]
+pub fn read_office_standards(path: &Path) -> Result<OfficeStandards, StandardsFileError> {
+    let standards = parse_office_standards(&std::fs::read_to_string(path)?)?;
+    tracing::info!(
+        layers = standards.layers.len(),
+        colors = standards.colors.len(),
+        "read standards file"
+    );
+    Ok(standards)
+}
+
+/// The 1-based line a byte offset falls on
+fn line_of(text: &str, offset: usize) -> usize {
+    text[..offset.min(text.len())].matches('\n').count() + 1
+}
+
+/// A setting that could not be parsed: where it is and what was wrong
+type Failure = (Option<Range<usize>>, String);
+
+/// A number, accepting integers as well as floats
+#[allow(clippy::cast_precision_loss, clippy::cast_possible_truncation)]
+fn number(value: &Value) -> Option<f32> {
+    value
+        .as_float()
+        .or_else(|| value.as_integer().map(|value| value as f64))
+        .map(|value| value as f32)
+}
+
+/// An element kind from a table key
+fn kind(key: &str) -> Result<ElementKind, String> {
+    ElementKind::from_label(key).ok_or_else(|| format!("unknown element kind \"{key}\""))
+}
+
+/// A red, green and blue color from 0 to 1
+fn color(item: &Item) -> Option<[f32; 3]> {
+    let values: Vec<f32> = item.as_array()?.iter().map(number).collect::<Option<_>>()?;
+    let color: [f32; 3] = values.try_into().ok()?;
+    color
+        .iter()
+        .all(|channel| (0.0..=1.0).contains(channel))
+        .then_some(color)
+}
+
+/// Read the `[layers]` and `[colors]` tables into the standards
+fn parse_kinds(table: &Table, standards: &mut OfficeStandards) -> Result<(), Failure> {
+    if let Some(layers) = table.get("layers") {
+        let Some(layers) = layers.as_table() else {
+            return Err((layers.span(), "layers must be a table".to_string()));
+        };
+        for (key, item) in layers {
+            let kind = kind(key).map_err(|message| (item.span(), message))?;
+            let Some(layer) = item.as_str() else {
+                return Err((item.span(), format!("layer of {key} must be a string")));
+            };
+            standards.layers.insert(kind, layer.to_string());
+        }
+    }
+    if let Some(colors) = table.get("colors") {
+        let Some(colors) = colors.as_table() else {
+            return Err((colors.span(), "colors must be a table".to_string()));
+        };
+        for (key, item) in colors {
+            let kind = kind(key).map_err(|message| (item.span(), message))?;
+            let color = color(item).ok_or_else(|| {
+                (
+                    item.span(),
+                    format!("color of {key} must be three numbers from 0 to 1"),
+                )
+            })?;
+            standards.colors.insert(kind, color);
+        }
+    }
+    Ok(())
+}
+
+/// Read the `[dimensions]` and `[text]` tables into the standards
+fn parse_styles(table: &Table, standards: &mut OfficeStandards) -> Result<(), Failure> {
+    if let Some(dimensions) = table.get("dimensions").and_then(Item::as_table) {
+        if let Some(item) = dimensions.get("decimals") {
+            standards.dimensions.decimals = item
+                .as_integer()
+                .and_then(|value| usize::try_from(value).ok())
+                .filter(|value| *value <= 6)
+                .ok_or_else(|| (item.span(), "decimals must be from 0 to 6".to_string()))?;
+        }
+        if let Some(item) = dimensions.get("show_units") {
+            standards.dimensions.show_units = item
+                .as_bool()
+                .ok_or_else(|| (item.span(), "show_units must be true or false".to_string()))?;
+        }
+    }
+    if let Some(text) = table.get("text").and_then(Item::as_table) {
+        if let Some(item) = text.get("font") {
+            let font = item
+                .as_str()
+                .filter(|font| TextStyle::FONTS.contains(font))
+                .ok_or_else(|| {
+                    let fonts = TextStyle::FONTS.join(", ");
+                    (item.span(), format!("font must be one of {fonts}"))
+                })?;
+            standards.text.font = font.to_string();
+        }
+        if let Some(item) = text.get("size") {
+            standards.text.size = item
+                .as_value()
+                .and_then(number)
+                .filter(|size| *size > 0.0)
+                .ok_or_else(|| (item.span(), "size must be a positive number".to_string()))?;
+        }
+    }
+    Ok(())
+}
+
+/// Parse the text of an office standards file
+///
+/// Settings the file leaves out keep their built-in values, and layers it
+/// names are added to the built-in ones.
+///
+/// # Errors
+/// Returns an error naming the line of invalid TOML or of the first
+/// setting that cannot be parsed.
+pub fn parse_office_standards(text: &str) -> Result<OfficeStandards, StandardsFileError> {
+    let document = ImDocument::parse(text).map_err(|error| StandardsFileError::Parse {
+        line: error.span().map_or(1, |span| line_of(text, span.start)),
+        message: error.message().to_string(),
+    })?;
+    let table = document.as_table();
+    let mut standards = OfficeStandards::default();
+    let parsed = match table.get("layer_pattern") {
+        Some(item) => match item.as_str() {
+            Some(pattern) => {
+                standards.layer_pattern = pattern.to_string();
+                Ok(())
+            }
+            None => Err((item.span(), "layer_pattern must be a string".to_string())),
+        },
+        None => Ok(()),
+    };
+    parsed
+        .and_then(|()| parse_kinds(table, &mut standards))
+        .and_then(|()| parse_styles(table, &mut standards))
+        .map_err(|(span, message)| StandardsFileError::Parse {
+            line: span.map_or(1, |span| line_of(text, span.start)),
+            message,
+        })?;
+    Ok(standards)
+}
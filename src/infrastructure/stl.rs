@@ -0,0 +1,193 @@
+/// STL import and export
+///
+/// Reads the triangles of an ASCII or binary STL file as point loops,
+/// ready for `GeometryRegistry::create_solid_from_loops`. STL carries no
+/// units or up axis; positions are taken as meters in the model's Y-up
+/// axes, like OBJ. Stored facet normals are ignored in favor of the
+/// triangles' winding. Export writes ASCII STL in the same axes, each
+/// polygon fanned into triangles with its winding kept.
+use crate::domain::geometry::{fan_triangles, newell_normal};
+use crate::domain::{
+    collect_export_solids, ElementRegistry, ExportFilter, GeometryRegistry, Point,
+};
+use std::fmt::Write as _;
+use std::path::Path;
+
+/// Size of the binary header before the triangle count
+const BINARY_HEADER: usize = 80;
+/// Size of one binary triangle record
+const BINARY_TRIANGLE: usize = 50;
+
+/// Errors raised while reading an STL file
+#[derive(Debug)]
+pub enum StlError {
+    /// The file could not be read
+    Io(std::io::Error),
+    /// The contents could not be parsed
+    Parse(String),
+}
+
+impl std::fmt::Display for StlError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StlError::Io(error) => write!(f, "Could not read STL file: {error}"),
+            StlError::Parse(message) => write!(f, "Could not parse STL file: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for StlError {}
+
+impl From<std::io::Error> for StlError {
+    fn from(error: std::io::Error) -> Self {
+        StlError::Io(error)
+    }
+}
+
+/// Read the triangles of an STL file as point loops
+///
+/// # Errors
+/// Returns an error if the file cannot be read or parsed.
+#[tracing::instrument(skip_all, fields(path = %path.display()))]
+pub fn read_stl(path: &Path) -> Result<Vec<Vec<Point>>, StlError> {
+    let loops = parse_stl(&std::fs::read(path)?)?;
+    tracing::info!(triangles = loops.len(), "read STL file");
+    Ok(loops)
+}
+
+/// Parse STL bytes, telling ASCII from binary
+///
+/// Some binary files also start with `solid`, so a file is only read as
+/// binary when its size matches its triangle count.
+///
+/// # Errors
+/// Returns an error if the bytes are neither valid binary nor ASCII STL.
+pub fn parse_stl(bytes: &[u8]) -> Result<Vec<Vec<Point>>, StlError> {
+    if let Some(count) = binary_triangle_count(bytes) {
+        return Ok(parse_binary(bytes, count));
+    }
+    let text = std::str::from_utf8(bytes)
+        .map_err(|_| StlError::Parse("neither binary nor ASCII STL".to_string()))?;
+    parse_ascii(text)
+}
+
+/// The triangle count of a well-formed binary STL, if the bytes are one
+fn binary_triangle_count(bytes: &[u8]) -> Option<usize> {
+    let count_bytes = bytes.get(BINARY_HEADER..BINARY_HEADER + 4)?;
+    let count = usize::try_from(u32::from_le_bytes(count_bytes.try_into().ok()?)).ok()?;
+    (bytes.len() == BINARY_HEADER + 4 + count * BINARY_TRIANGLE).then_some(count)
+}
+
+/// Triangles of a binary STL whose size has been checked
+fn parse_binary(bytes: &[u8], count: usize) -> Vec<Vec<Point>> {
+    let float = |offset: usize| {
+        let mut value = [0; 4];
+        value.copy_from_slice(&bytes[offset..offset + 4]);
+        f32::from_le_bytes(value)
+    };
+    (0..count)
+        .map(|triangle| {
+            // Skip the 12-byte facet normal at the start of each record
+            let start = BINARY_HEADER + 4 + triangle * BINARY_TRIANGLE + 12;
+            (0..3)
+                .map(|corner| {
+                    let offset = start + corner * 12;
+                    Point {
+                        x: float(offset),
+                        y: float(offset + 4),
+                        z: float(offset + 8),
+                    }
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Triangles of an ASCII STL, one loop per facet
+fn parse_ascii(text: &str) -> Result<Vec<Vec<Point>>, StlError> {
+    let mut loops = Vec::new();
+    let mut current: Vec<Point> = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+        let mut words = line.split_whitespace();
+        match words.next() {
+            Some("vertex") => {
+                let coordinates: Result<Vec<f32>, _> = words.take(3).map(str::parse).collect();
+                match coordinates {
+                    Ok(values) if values.len() == 3 => current.push(Point {
+                        x: values[0],
+                        y: values[1],
+                        z: values[2],
+                    }),
+                    _ => {
+                        return Err(StlError::Parse(format!(
+                            "line {}: expected vertex x y z",
+                            index + 1
+                        )))
+                    }
+                }
+            }
+            Some("endloop") => loops.push(std::mem::take(&mut current)),
+            _ => {}
+        }
+    }
+    if loops.is_empty() {
+        return Err(StlError::Parse("no facets found".to_string()));
+    }
+    Ok(loops)
+}
+
+/// Write an ASCII STL file of the solids in the registry
+///
+/// # Errors
+/// Returns an error if the file cannot be written.
+#[tracing::instrument(skip_all, fields(path = %path.display()))]
+pub fn write_stl(
+    path: &Path,
+    geometry_registry: &GeometryRegistry,
+    element_registry: &ElementRegistry,
+    filter: &ExportFilter,
+) -> std::io::Result<()> {
+    std::fs::write(
+        path,
+        export_stl(geometry_registry, element_registry, filter),
+    )?;
+    tracing::info!(
+        solids = geometry_registry.solids.solids.len(),
+        "wrote STL file"
+    );
+    Ok(())
+}
+
+/// Build the text of an ASCII STL file of the solids the filter includes
+#[must_use]
+pub fn export_stl(
+    geometry_registry: &GeometryRegistry,
+    element_registry: &ElementRegistry,
+    filter: &ExportFilter,
+) -> String {
+    let mut text = String::from("solid HarmonyArch\n");
+    for export in collect_export_solids(geometry_registry, element_registry, filter) {
+        for points in geometry_registry
+            .solid_loops(&export.solid.id)
+            .unwrap_or_default()
+        {
+            let normal = newell_normal(&points)
+                .normalized()
+                .unwrap_or_else(|| newell_normal(&points));
+            for triangle in fan_triangles(points.len()) {
+                let _ = writeln!(
+                    text,
+                    "  facet normal {} {} {}\n    outer loop",
+                    normal.x, normal.y, normal.z
+                );
+                for index in triangle {
+                    let point = &points[index];
+                    let _ = writeln!(text, "      vertex {} {} {}", point.x, point.y, point.z);
+                }
+                text.push_str("    endloop\n  endfacet\n");
+            }
+        }
+    }
+    text.push_str("endsolid HarmonyArch\n");
+    text
+}
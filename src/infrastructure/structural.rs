@@ -0,0 +1,118 @@
+/// Structural analysis model export
+///
+/// Writes the analytical model as JSON modelled on the Structural Analysis
+/// Format: nodes, rectangular cross-sections, line members between nodes
+/// and shells on node outlines, each referring to the others by ID. Like
+/// SAF the coordinates are Z-up in meters, so points are rotated like the
+/// IFC export; every member and shell keeps the UUID of its element.
+use crate::domain::{
+    derive_structural_model, ElementRegistry, GeometryRegistry, StructuralSettings,
+};
+use serde_json::{json, Value};
+use std::path::Path;
+
+/// Write a structural model file of the columns, beams and slabs
+///
+/// # Errors
+/// Returns an error if the file cannot be written.
+#[tracing::instrument(skip_all, fields(path = %path.display()))]
+pub fn write_structural_model(
+    path: &Path,
+    geometry_registry: &GeometryRegistry,
+    element_registry: &ElementRegistry,
+    settings: &StructuralSettings,
+) -> std::io::Result<()> {
+    std::fs::write(
+        path,
+        export_structural_model(geometry_registry, element_registry, settings),
+    )?;
+    tracing::info!("wrote structural model");
+    Ok(())
+}
+
+/// Build the JSON text of a structural model
+#[must_use]
+pub fn export_structural_model(
+    geometry_registry: &GeometryRegistry,
+    element_registry: &ElementRegistry,
+    settings: &StructuralSettings,
+) -> String {
+    let model = derive_structural_model(element_registry, geometry_registry, settings);
+    let node_id = |index: usize| format!("N{}", index + 1);
+
+    let nodes: Vec<Value> = model
+        .nodes
+        .iter()
+        .enumerate()
+        .map(|(index, node)| {
+            // Y-up model to Z-up, a rotation about X
+            let point = &node.position;
+            json!({
+                "id": node_id(index),
+                "x": point.x,
+                "y": -point.z,
+                "z": point.y,
+                "support": node.support.then_some("fixed"),
+            })
+        })
+        .collect();
+    let sections: Vec<Value> = model
+        .sections
+        .iter()
+        .enumerate()
+        .map(|(index, section)| {
+            json!({
+                "id": format!("CS{}", index + 1),
+                "shape": "rectangle",
+                "width": section.width,
+                "depth": section.depth,
+                "area": section.area(),
+                "material": section.material,
+            })
+        })
+        .collect();
+    let members: Vec<Value> = model
+        .members
+        .iter()
+        .enumerate()
+        .map(|(index, member)| {
+            json!({
+                "id": format!("M{}", index + 1),
+                "type": member.kind.label().to_lowercase(),
+                "element": member.element.to_string(),
+                "start": node_id(member.start),
+                "end": node_id(member.end),
+                "section": format!("CS{}", member.section + 1),
+            })
+        })
+        .collect();
+    let shells: Vec<Value> = model
+        .shells
+        .iter()
+        .enumerate()
+        .map(|(index, shell)| {
+            json!({
+                "id": format!("S{}", index + 1),
+                "element": shell.element.to_string(),
+                "thickness": shell.thickness,
+                "material": shell.material,
+                "nodes": shell.nodes.iter().map(|&node| node_id(node)).collect::<Vec<_>>(),
+                "connected": shell.connected.iter().map(|&node| node_id(node)).collect::<Vec<_>>(),
+            })
+        })
+        .collect();
+
+    let document = json!({
+        "format": "harmonyarch-structural",
+        "version": 1,
+        "units": { "length": "m", "area": "m2" },
+        "axes": "z-up",
+        "nodes": nodes,
+        "sections": sections,
+        "members": members,
+        "shells": shells,
+    });
+    let mut text = serde_json::to_string_pretty(&document).unwrap_or_default();
+    text.push('\n');
+    text
+}
@@ -0,0 +1,254 @@
+/// Survey point import
+///
+/// Reads surveyed points from CSV (`x,y,z[,code]` rows) or JSON (an array
+/// of `{"x", "y", "z", "code"}` objects) and stores them in a dedicated
+/// survey tier. Survey coordinates are map easting, northing and elevation;
+/// the project georeference places them in the model's Y-up axes. Points whose
+/// code marks a breakline are joined in file order into segments, one
+/// string per code.
+use crate::domain::geometry::PointIndex;
+use crate::domain::{GeometryRegistry, Georeference, Point, TierRegistry};
+use crate::infrastructure::csv::csv_rows;
+use std::collections::HashMap;
+use std::path::Path;
+use uuid::Uuid;
+
+/// A surveyed point with its field code
+#[derive(Debug, Clone)]
+pub struct SurveyPoint {
+    /// Position in model axes
+    pub position: Point,
+    /// The field code, empty if none was recorded
+    pub code: String,
+}
+
+/// Errors raised while reading survey data
+#[derive(Debug)]
+pub enum SurveyError {
+    /// The file could not be read
+    Io(std::io::Error),
+    /// A row or record could not be parsed
+    Parse {
+        /// The 1-based line or record number
+        line: usize,
+        /// What was wrong with it
+        message: String,
+    },
+}
+
+impl std::fmt::Display for SurveyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SurveyError::Io(error) => write!(f, "Could not read survey file: {error}"),
+            SurveyError::Parse { line, message } => {
+                write!(f, "Survey record {line}: {message}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for SurveyError {}
+
+impl From<std::io::Error> for SurveyError {
+    fn from(error: std::io::Error) -> Self {
+        SurveyError::Io(error)
+    }
+}
+
+/// Settings for a survey import
+#[derive(Debug, Clone)]
+pub struct SurveyImportSettings {
+    /// Name of the tier the points are stored in, created if missing
+    pub tier_name: String,
+    /// Codes starting with this prefix (ignoring case) are breaklines
+    pub breakline_prefix: String,
+}
+
+impl Default for SurveyImportSettings {
+    fn default() -> Self {
+        Self {
+            tier_name: "Survey".to_string(),
+            breakline_prefix: "BL".to_string(),
+        }
+    }
+}
+
+/// What a survey import added
+#[derive(Debug, Clone, Default)]
+pub struct SurveyImport {
+    /// The survey tier
+    pub tier: Uuid,
+    /// One vertex per distinct surveyed position
+    pub vertices: Vec<Uuid>,
+    /// Breakline segments, in file order
+    pub breaklines: Vec<Uuid>,
+    /// The code recorded at each vertex
+    pub codes: HashMap<Uuid, String>,
+}
+
+/// Read survey points from a file, choosing the format by extension
+///
+/// Files ending in `.json` are read as JSON, anything else as CSV.
+///
+/// # Errors
+/// Returns an error if the file cannot be read or parsed.
+#[tracing::instrument(skip_all, fields(path = %path.display()))]
+pub fn read_survey(
+    path: &Path,
+    georeference: &Georeference,
+) -> Result<Vec<SurveyPoint>, SurveyError> {
+    let text = std::fs::read_to_string(path)?;
+    let is_json = path
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("json"));
+    let points = if is_json {
+        parse_survey_json(&text, georeference)?
+    } else {
+        parse_survey_csv(&text, georeference)?
+    };
+    tracing::info!(points = points.len(), "read survey file");
+    Ok(points)
+}
+
+/// Parse CSV survey rows of `x,y,z` with an optional trailing code
+///
+/// Blank lines and lines starting with `#` are skipped, as is a first row
+/// whose coordinates are not numbers (a header).
+///
+/// # Errors
+/// Returns an error naming the first row that cannot be parsed.
+pub fn parse_survey_csv(
+    text: &str,
+    georeference: &Georeference,
+) -> Result<Vec<SurveyPoint>, SurveyError> {
+    let mut points = Vec::new();
+    for row in csv_rows(text) {
+        let coordinates: Result<Vec<f64>, _> = row
+            .fields
+            .iter()
+            .take(3)
+            .map(|field| field.parse())
+            .collect();
+        match coordinates {
+            Ok(values) if values.len() == 3 => points.push(SurveyPoint {
+                position: georeference.to_model(values[0], values[1], values[2]),
+                code: row.field(3).to_string(),
+            }),
+            Err(_) if row.first => {}
+            _ => {
+                return Err(SurveyError::Parse {
+                    line: row.line,
+                    message: format!("expected x,y,z[,code], found \"{}\"", row.text),
+                })
+            }
+        }
+    }
+    Ok(points)
+}
+
+/// Parse a JSON array of survey objects with numeric `x`, `y`, `z` and an
+/// optional string `code`
+///
+/// # Errors
+/// Returns an error if the text is not such an array.
+pub fn parse_survey_json(
+    text: &str,
+    georeference: &Georeference,
+) -> Result<Vec<SurveyPoint>, SurveyError> {
+    let value: serde_json::Value =
+        serde_json::from_str(text).map_err(|error| SurveyError::Parse {
+            line: error.line(),
+            message: error.to_string(),
+        })?;
+    let records = value.as_array().ok_or_else(|| SurveyError::Parse {
+        line: 1,
+        message: "expected an array of points".to_string(),
+    })?;
+    records
+        .iter()
+        .enumerate()
+        .map(|(index, record)| {
+            let coordinate = |key: &str| record.get(key)?.as_f64();
+            let (Some(x), Some(y), Some(z)) = (coordinate("x"), coordinate("y"), coordinate("z"))
+            else {
+                return Err(SurveyError::Parse {
+                    line: index + 1,
+                    message: "expected numeric x, y and z".to_string(),
+                });
+            };
+            Ok(SurveyPoint {
+                position: georeference.to_model(x, y, z),
+                code: record
+                    .get("code")
+                    .and_then(serde_json::Value::as_str)
+                    .unwrap_or_default()
+                    .to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Store survey points in the survey tier
+///
+/// Points within the tier's linear tolerance of each other become one
+/// vertex. Breakline strings skip repeated vertices, so a shot taken twice
+/// does not make a zero-length segment.
+#[tracing::instrument(skip_all, fields(points = points.len()))]
+pub fn import_survey(
+    geometry_registry: &mut GeometryRegistry,
+    tier_registry: &mut TierRegistry,
+    points: &[SurveyPoint],
+    settings: &SurveyImportSettings,
+) -> SurveyImport {
+    let existing = tier_registry
+        .sorted()
+        .into_iter()
+        .find(|tier| tier.name == settings.tier_name)
+        .map(|tier| tier.id);
+    let tier =
+        existing.unwrap_or_else(|| tier_registry.create_and_store(&settings.tier_name, None, None));
+    let mut import = SurveyImport {
+        tier,
+        ..SurveyImport::default()
+    };
+
+    let mut index = PointIndex::new(tier_registry.tolerance(&tier).linear);
+    let prefix = settings.breakline_prefix.to_ascii_uppercase();
+    let mut strings: Vec<(String, Vec<Uuid>)> = Vec::new();
+    for point in points {
+        let slot = index.insert(&point.position);
+        if slot == import.vertices.len() {
+            let vertex = geometry_registry
+                .vertices
+                .create_and_store(point.position.clone());
+            import.vertices.push(vertex);
+            import.codes.insert(vertex, point.code.clone());
+        }
+        let vertex = import.vertices[slot];
+
+        if prefix.is_empty() || !point.code.to_ascii_uppercase().starts_with(&prefix) {
+            continue;
+        }
+        match strings.iter_mut().find(|(code, _)| *code == point.code) {
+            Some((_, string)) if string.last() != Some(&vertex) => string.push(vertex),
+            Some(_) => {}
+            None => strings.push((point.code.clone(), vec![vertex])),
+        }
+    }
+
+    for (_, string) in &strings {
+        for pair in string.windows(2) {
+            import.breaklines.push(
+                geometry_registry
+                    .segments
+                    .create_and_store(&pair[0], &pair[1]),
+            );
+        }
+    }
+
+    if let Some(tier) = tier_registry.get_mut(&tier) {
+        tier.geometry.extend(&import.vertices);
+        tier.geometry.extend(&import.breaklines);
+    }
+    import
+}
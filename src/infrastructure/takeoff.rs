@@ -0,0 +1,87 @@
+/// Quantity takeoff export
+///
+/// Writes one CSV row per solid with its element, office standards layer,
+/// material and phase, and its enclosed volume in cubic meters and surface
+/// area in square meters, followed by a total row, for use in
+/// spreadsheets. Solids with missing geometry are listed with no
+/// quantities.
+use crate::domain::geometry::{polygon_area, signed_volume};
+use crate::domain::{collect_export_solids, ElementRegistry, ExportFilter, GeometryRegistry};
+use crate::infrastructure::csv::csv_field;
+use crate::infrastructure::standards::OfficeStandards;
+use std::path::Path;
+
+/// Settings for a quantity takeoff export
+#[derive(Debug, Clone, Default)]
+pub struct TakeoffSettings {
+    /// Which solids to export
+    pub filter: ExportFilter,
+    /// Layer names for element kinds
+    pub standards: OfficeStandards,
+}
+
+/// Write a quantity takeoff as a CSV file
+///
+/// # Errors
+/// Returns an error if the file cannot be written.
+#[tracing::instrument(skip_all, fields(path = %path.display()))]
+pub fn write_takeoff(
+    path: &Path,
+    geometry_registry: &GeometryRegistry,
+    element_registry: &ElementRegistry,
+    settings: &TakeoffSettings,
+) -> std::io::Result<()> {
+    std::fs::write(
+        path,
+        export_takeoff(geometry_registry, element_registry, settings),
+    )?;
+    tracing::info!(
+        solids = geometry_registry.solids.solids.len(),
+        "wrote quantity takeoff"
+    );
+    Ok(())
+}
+
+/// Build the CSV text of a quantity takeoff
+#[must_use]
+pub fn export_takeoff(
+    geometry_registry: &GeometryRegistry,
+    element_registry: &ElementRegistry,
+    settings: &TakeoffSettings,
+) -> String {
+    let mut rows =
+        vec!["solid,element,name,kind,layer,material,phase,volume,surface_area".to_string()];
+    let (mut total_volume, mut total_area) = (0.0, 0.0);
+    for export in collect_export_solids(geometry_registry, element_registry, &settings.filter) {
+        let (solid, element, kind) = (export.solid, export.element, export.kind());
+        let quantities = geometry_registry.solid_loops(&solid.id).map_or_else(
+            || (String::new(), String::new()),
+            |loops| {
+                let volume = signed_volume(&loops).abs();
+                let area: f32 = loops.iter().map(|l| polygon_area(l)).sum();
+                total_volume += volume;
+                total_area += area;
+                (format!("{volume:.3}"), format!("{area:.3}"))
+            },
+        );
+        rows.push(format!(
+            "{},{},{},{},{},{},{},{},{}",
+            solid.id,
+            element.map_or_else(String::new, |element| element.id.to_string()),
+            csv_field(export.name()),
+            kind.label(),
+            csv_field(&settings.standards.layer_for(kind)),
+            csv_field(
+                element
+                    .and_then(|e| e.material.as_deref())
+                    .unwrap_or_default()
+            ),
+            solid.phase.label(),
+            quantities.0,
+            quantities.1
+        ));
+    }
+    rows.push(format!(",,Total,,,,,{total_volume:.3},{total_area:.3}"));
+    rows.push(String::new());
+    rows.join("\n")
+}
@@ -0,0 +1,212 @@
+/// Project templates
+///
+/// A template is a regular project file in the templates folder beside the
+/// settings. Starting a project from one copies it, giving its levels and
+/// grids new IDs so projects started from the same template do not share
+/// them. The built-in templates, residential and commercial in metric and
+/// imperial units, are written to the folder when they are missing, so a
+/// firm can edit them or add its own beside them.
+use crate::domain::{new_grid_datum, new_id, GridRegistry, LevelRegistry, LinkTransform};
+use crate::infrastructure::config_dir;
+use crate::infrastructure::preferences::UnitSystem;
+use crate::infrastructure::project::{
+    read_project, Project, ProjectError, ProjectMaterial, ProjectStandards, PROJECT_EXTENSION,
+};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// A template a project can be started from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProjectTemplate {
+    /// The template's name, from its file name
+    pub name: String,
+    /// The template's project file
+    pub path: PathBuf,
+}
+
+/// The folder templates are kept in, or None if the platform has no
+/// config folder
+#[must_use]
+pub fn templates_dir() -> Option<PathBuf> {
+    config_dir().map(|folder| folder.join("templates"))
+}
+
+/// The built-in templates, by file stem
+#[must_use]
+pub fn builtin_templates() -> Vec<(String, Project)> {
+    let mut templates = Vec::new();
+    for units in UnitSystem::ALL {
+        let suffix = units.label().to_lowercase();
+        templates.push((format!("residential-{suffix}"), residential_template(units)));
+        templates.push((format!("commercial-{suffix}"), commercial_template(units)));
+    }
+    templates
+}
+
+/// A house: three levels, a light grid, and timber and masonry finishes
+fn residential_template(units: UnitSystem) -> Project {
+    // Imperial dimensions are round numbers of feet, not converted meters
+    let (story, bay, span) = match units {
+        UnitSystem::Metric => (3.0, 4.8, 6.0),
+        UnitSystem::Imperial => (10.0, 16.0, 20.0),
+    };
+    let story = units.to_meters(story);
+    template(
+        units,
+        &[("Ground", 0.0), ("First", story), ("Roof", 2.0 * story)],
+        vec![units.to_meters(bay); 2],
+        vec![units.to_meters(span)],
+        &[
+            ("timber", [0.72, 0.53, 0.33]),
+            ("brick", [0.66, 0.33, 0.24]),
+            ("gypsum", [0.92, 0.91, 0.88]),
+            ("insulation", [0.95, 0.85, 0.35]),
+        ],
+    )
+}
+
+/// An office block: five levels, a wide structural grid, and concrete,
+/// steel and glass
+fn commercial_template(units: UnitSystem) -> Project {
+    let (ground, story, bay) = match units {
+        UnitSystem::Metric => (4.5, 4.0, 8.4),
+        UnitSystem::Imperial => (15.0, 13.0, 30.0),
+    };
+    let (ground, story) = (units.to_meters(ground), units.to_meters(story));
+    template(
+        units,
+        &[
+            ("Ground", 0.0),
+            ("Level 1", ground),
+            ("Level 2", ground + story),
+            ("Level 3", ground + 2.0 * story),
+            ("Roof", ground + 3.0 * story),
+        ],
+        vec![units.to_meters(bay); 4],
+        vec![units.to_meters(bay); 3],
+        &[
+            ("reinforced concrete", [0.58, 0.58, 0.57]),
+            ("steel", [0.55, 0.57, 0.6]),
+            ("glass", [0.6, 0.75, 0.8]),
+            ("gypsum", [0.92, 0.91, 0.88]),
+        ],
+    )
+}
+
+/// An empty model with levels, one grid, materials and the usual drawing
+/// layers
+fn template(
+    units: UnitSystem,
+    levels: &[(&str, f32)],
+    numbered: Vec<f32>,
+    lettered: Vec<f32>,
+    materials: &[(&str, [f32; 3])],
+) -> Project {
+    let mut project = Project::create_new();
+    for (name, elevation) in levels {
+        project.levels.create_and_store(name, *elevation);
+    }
+    project.grids.store_grid(new_grid_datum(
+        "Structural grid",
+        LinkTransform::default(),
+        numbered,
+        lettered,
+    ));
+    let layers = [
+        ("A-WALL", Some("Walls")),
+        ("A-DOOR", Some("Openings")),
+        ("A-GLAZ", Some("Openings")),
+        ("S-GRID", Some("Grids")),
+        ("A-ANNO-TEXT", None),
+        ("A-ANNO-DIMS", None),
+    ];
+    project.standards = ProjectStandards {
+        units: Some(units),
+        materials: materials
+            .iter()
+            .map(|(name, color)| ProjectMaterial {
+                name: (*name).to_string(),
+                color: *color,
+            })
+            .collect(),
+        layers: layers
+            .into_iter()
+            .map(|(layer, tier)| (layer.to_string(), tier.map(str::to_string)))
+            .collect::<BTreeMap<_, _>>(),
+    };
+    project
+}
+
+/// Write the built-in templates the folder is missing
+///
+/// # Errors
+/// Returns an error if the folder or a template cannot be written.
+pub fn install_builtin_templates(folder: &Path) -> Result<(), ProjectError> {
+    std::fs::create_dir_all(folder)?;
+    for (name, project) in builtin_templates() {
+        let path = folder.join(format!("{name}.{PROJECT_EXTENSION}"));
+        if path.exists() {
+            continue;
+        }
+        std::fs::write(path, project.export()?)?;
+    }
+    Ok(())
+}
+
+/// The templates in a folder, by name
+///
+/// # Errors
+/// Returns an error if the folder cannot be read.
+pub fn list_templates(folder: &Path) -> Result<Vec<ProjectTemplate>, ProjectError> {
+    let mut templates: Vec<ProjectTemplate> = std::fs::read_dir(folder)?
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == PROJECT_EXTENSION)
+        })
+        .filter_map(|path| {
+            let name = path.file_stem()?.to_string_lossy().into_owned();
+            Some(ProjectTemplate { name, path })
+        })
+        .collect();
+    templates.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(templates)
+}
+
+/// The templates in the templates folder, writing the built-in ones first
+/// if they are missing
+///
+/// # Errors
+/// Returns an error if the folder cannot be written or read.
+pub fn load_templates() -> Result<Vec<ProjectTemplate>, ProjectError> {
+    let Some(folder) = templates_dir() else {
+        return Ok(Vec::new());
+    };
+    install_builtin_templates(&folder)?;
+    list_templates(&folder)
+}
+
+/// Start a project from a template
+///
+/// # Errors
+/// Returns an error if the template cannot be read or is not a valid
+/// project.
+pub fn project_from_template(path: &Path) -> Result<Project, ProjectError> {
+    let mut project = read_project(path)?;
+    let mut levels = LevelRegistry::create_new();
+    for level in project.levels.sorted() {
+        let mut level = level.clone();
+        level.id = new_id();
+        levels.levels.insert(level.id, level);
+    }
+    project.levels = levels;
+    let mut grids = GridRegistry::create_new();
+    for grid in project.grids.sorted_grids() {
+        let mut grid = grid.clone();
+        grid.id = new_id();
+        grids.store_grid(grid);
+    }
+    project.grids = grids;
+    Ok(project)
+}
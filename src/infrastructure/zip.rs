@@ -0,0 +1,200 @@
+/// Zip archives
+///
+/// Reads and writes the small archives exchange formats such as BCF are
+/// packaged in. Entries are written stored, without compression: the files
+/// inside are a few kilobytes of XML, and any zip reader opens stored
+/// entries. Reading takes stored and deflated entries, which between them
+/// cover the archives other tools write.
+use crc32fast::hash as crc32;
+use miniz_oxide::inflate::decompress_to_vec_with_limit;
+use std::io::{Error, ErrorKind};
+
+/// Signature opening each local file header
+const LOCAL_HEADER: u32 = 0x0403_4b50;
+/// Signature opening each central directory entry
+const CENTRAL_HEADER: u32 = 0x0201_4b50;
+/// Signature opening the end of central directory record
+const END_OF_DIRECTORY: u32 = 0x0605_4b50;
+/// Zip version 2.0, needed to extract entries in folders
+const VERSION: u16 = 20;
+/// General purpose flag marking entry names as UTF-8
+const UTF8_NAMES: u16 = 1 << 11;
+/// Compression method of stored entries
+const STORED: u16 = 0;
+/// Compression method of deflated entries
+const DEFLATED: u16 = 8;
+/// Length of the end of central directory record without its comment
+const END_RECORD_LENGTH: usize = 22;
+/// Length of a central directory entry before its name
+const CENTRAL_ENTRY_LENGTH: usize = 46;
+/// Length of a local file header before its name
+const LOCAL_HEADER_LENGTH: usize = 30;
+
+/// Build a zip archive of named entries, stored without compression
+///
+/// Names use `/` between folders. Archives and entries past 4 GiB, which
+/// need the zip64 extensions, are not supported; their sizes wrap.
+#[must_use]
+#[allow(clippy::cast_possible_truncation)]
+pub fn zip_archive(entries: &[(String, Vec<u8>)]) -> Vec<u8> {
+    let mut archive = Vec::new();
+    let mut directory = Vec::new();
+    for (name, data) in entries {
+        let offset = archive.len() as u32;
+        let crc = crc32(data);
+        let (size, name_length) = (data.len() as u32, name.len() as u16);
+
+        push_u32(&mut archive, LOCAL_HEADER);
+        push_entry_fields(&mut archive, crc, size, name_length);
+        archive.extend(name.as_bytes());
+        archive.extend(data);
+
+        push_u32(&mut directory, CENTRAL_HEADER);
+        push_u16(&mut directory, VERSION);
+        push_entry_fields(&mut directory, crc, size, name_length);
+        // Comment length, disk number, internal and external attributes
+        push_u16(&mut directory, 0);
+        push_u16(&mut directory, 0);
+        push_u16(&mut directory, 0);
+        push_u32(&mut directory, 0);
+        push_u32(&mut directory, offset);
+        directory.extend(name.as_bytes());
+    }
+    let (directory_offset, directory_size) = (archive.len() as u32, directory.len() as u32);
+    archive.extend(directory);
+    push_u32(&mut archive, END_OF_DIRECTORY);
+    // This disk and the disk the directory starts on
+    push_u16(&mut archive, 0);
+    push_u16(&mut archive, 0);
+    push_u16(&mut archive, entries.len() as u16);
+    push_u16(&mut archive, entries.len() as u16);
+    push_u32(&mut archive, directory_size);
+    push_u32(&mut archive, directory_offset);
+    // Archive comment length
+    push_u16(&mut archive, 0);
+    archive
+}
+
+/// Read the named entries of a zip archive
+///
+/// Folders are left out. Names use `/` between folders, as they are
+/// stored.
+///
+/// # Errors
+/// Returns an `InvalidData` error if the bytes are not a zip archive, an
+/// entry is compressed with a method other than deflate, or an entry's
+/// data does not match its checksum.
+pub fn read_zip_archive(bytes: &[u8]) -> std::io::Result<Vec<(String, Vec<u8>)>> {
+    let invalid = |message: &str| Error::new(ErrorKind::InvalidData, message.to_string());
+    // The end record is last, before a comment of up to 64 KiB
+    let end = (0..=bytes.len().saturating_sub(END_RECORD_LENGTH))
+        .rev()
+        .take(END_RECORD_LENGTH + usize::from(u16::MAX))
+        .find(|offset| read_u32(bytes, *offset) == Some(END_OF_DIRECTORY))
+        .ok_or_else(|| invalid("not a zip archive"))?;
+    let count = read_u16(bytes, end + 10).ok_or_else(|| invalid("truncated zip archive"))?;
+    let mut offset = read_u32(bytes, end + 16)
+        .and_then(|offset| usize::try_from(offset).ok())
+        .ok_or_else(|| invalid("truncated zip archive"))?;
+    let mut entries = Vec::new();
+    for _ in 0..count {
+        if read_u32(bytes, offset) != Some(CENTRAL_HEADER) {
+            return Err(invalid("damaged zip directory"));
+        }
+        let field = |at: usize| read_u16(bytes, offset + at).map(usize::from);
+        let (Some(method), Some(name_length), Some(extra_length), Some(comment_length)) = (
+            read_u16(bytes, offset + 10),
+            field(28),
+            field(30),
+            field(32),
+        ) else {
+            return Err(invalid("damaged zip directory"));
+        };
+        let number =
+            |at: usize| read_u32(bytes, offset + at).and_then(|value| usize::try_from(value).ok());
+        let (Some(crc), Some(compressed), Some(size), Some(local)) = (
+            read_u32(bytes, offset + 16),
+            number(20),
+            number(24),
+            number(42),
+        ) else {
+            return Err(invalid("damaged zip directory"));
+        };
+        let name_start = offset + CENTRAL_ENTRY_LENGTH;
+        let name = bytes
+            .get(name_start..name_start + name_length)
+            .map(|name| String::from_utf8_lossy(name).into_owned())
+            .ok_or_else(|| invalid("damaged zip directory"))?;
+        offset = name_start + name_length + extra_length + comment_length;
+        if name.ends_with('/') {
+            continue;
+        }
+
+        // The local header repeats the name and has its own extra field
+        let (Some(local_name_length), Some(local_extra_length)) = (
+            read_u16(bytes, local + 26).map(usize::from),
+            read_u16(bytes, local + 28).map(usize::from),
+        ) else {
+            return Err(invalid("damaged zip entry"));
+        };
+        let start = local + LOCAL_HEADER_LENGTH + local_name_length + local_extra_length;
+        let data = bytes
+            .get(start..start + compressed)
+            .ok_or_else(|| invalid("damaged zip entry"))?;
+        let data = match method {
+            STORED => data.to_vec(),
+            DEFLATED => decompress_to_vec_with_limit(data, size)
+                .map_err(|_| invalid(&format!("could not inflate {name}")))?,
+            _ => {
+                return Err(invalid(&format!(
+                    "{name} is compressed in an unsupported way"
+                )))
+            }
+        };
+        if crc32(&data) != crc {
+            return Err(invalid(&format!("{name} fails its checksum")));
+        }
+        entries.push((name, data));
+    }
+    Ok(entries)
+}
+
+/// The fields local headers and central directory entries share, from the
+/// version needed to the extra field length
+fn push_entry_fields(bytes: &mut Vec<u8>, crc: u32, size: u32, name_length: u16) {
+    push_u16(bytes, VERSION);
+    push_u16(bytes, UTF8_NAMES);
+    push_u16(bytes, STORED);
+    // At midnight on 1 January 1980, the earliest time zip has
+    push_u16(bytes, 0);
+    push_u16(bytes, (1 << 5) | 1);
+    push_u32(bytes, crc);
+    push_u32(bytes, size);
+    push_u32(bytes, size);
+    push_u16(bytes, name_length);
+    push_u16(bytes, 0);
+}
+
+/// The little-endian 16-bit number at an offset
+fn read_u16(bytes: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(
+        bytes.get(offset..offset + 2)?.try_into().ok()?,
+    ))
+}
+
+/// The little-endian 32-bit number at an offset
+fn read_u32(bytes: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(
+        bytes.get(offset..offset + 4)?.try_into().ok()?,
+    ))
+}
+
+/// Append a little-endian 16-bit number
+fn push_u16(bytes: &mut Vec<u8>, value: u16) {
+    bytes.extend(value.to_le_bytes());
+}
+
+/// Append a little-endian 32-bit number
+fn push_u32(bytes: &mut Vec<u8>, value: u32) {
+    bytes.extend(value.to_le_bytes());
+}
@@ -0,0 +1,302 @@
+use bevy::asset::RenderAssetUsages;
+use bevy::prelude::*;
+use bevy::render::camera::RenderTarget;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat, TextureUsages};
+use bevy::render::view::RenderLayers;
+use std::collections::HashMap;
+use std::path::Path;
+use uuid::Uuid;
+
+use crate::application::create_mesh_from_solid;
+use crate::application::selection::SelectionType;
+use crate::domain::{
+    ComponentDefinition, ComponentLibrary, FaceOffset, GeometryRegistry, LinkTransform, Tolerance,
+};
+use crate::infrastructure::library::scan_component_library;
+use crate::interface::placement::{PlaceComponent, PlaceOnFace};
+use crate::interface::selection::SelectionState;
+use crate::interface::theme::UiTheme;
+
+/// Folder scanned for component files at startup
+const COMPONENT_FOLDER: &str = "assets/components";
+/// Width and height of a thumbnail in pixels
+const THUMBNAIL_SIZE: u32 = 96;
+/// Frames a thumbnail scene is kept before it is despawned
+const THUMBNAIL_FRAMES: u8 = 3;
+
+/// Resource holding the component library
+#[derive(Resource)]
+pub struct ComponentLibraryResource {
+    pub library: ComponentLibrary,
+}
+
+/// Component for a button that places a component definition
+#[derive(Component)]
+pub struct PlaceComponentButton(pub Uuid);
+
+/// Component for the entities of an offscreen thumbnail render
+///
+/// Each thumbnail is drawn by its own camera on its own render layer, so
+/// the main camera never sees it; the scene is removed once rendered.
+#[derive(Component)]
+pub struct ThumbnailScene {
+    pub frames_left: u8,
+}
+
+/// Scan the component folder, render thumbnails and build the browser
+/// panel at the bottom left of the screen
+pub fn setup_asset_browser(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut images: ResMut<Assets<Image>>,
+    theme: Res<UiTheme>,
+) {
+    let scan = scan_component_library(Path::new(COMPONENT_FOLDER));
+    for (path, error) in &scan.failed {
+        warn!("Skipped component {}: {error}", path.display());
+    }
+
+    let mut definitions: Vec<&ComponentDefinition> = scan.library.definitions.values().collect();
+    definitions.sort_by_key(|definition| definition.id);
+    let layers: Vec<usize> = (1..=definitions.len()).collect();
+    let thumbnail_material = materials.add(StandardMaterial {
+        base_color: Color::srgb(0.8, 0.8, 0.8),
+        perceptual_roughness: 0.6,
+        ..Default::default()
+    });
+    let mut thumbnails = HashMap::new();
+    for (definition, layer) in definitions.into_iter().zip(layers.iter().copied()) {
+        let handle = images.add(thumbnail_target());
+        spawn_thumbnail_scene(
+            &mut commands,
+            meshes.add(definition_mesh(definition)),
+            thumbnail_material.clone(),
+            definition,
+            handle.clone(),
+            layer,
+        );
+        thumbnails.insert(definition.id, handle);
+    }
+    if !layers.is_empty() {
+        commands.spawn((
+            DirectionalLight {
+                shadows_enabled: false,
+                illuminance: 2000.0,
+                ..default()
+            },
+            Transform::from_xyz(3.0, 5.0, 4.0).looking_at(Vec3::ZERO, Vec3::Y),
+            RenderLayers::from_layers(&layers),
+            ThumbnailScene {
+                frames_left: THUMBNAIL_FRAMES,
+            },
+        ));
+    }
+
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(10.0),
+                left: Val::Px(10.0),
+                max_height: Val::Percent(45.0),
+                flex_direction: FlexDirection::Column,
+                padding: UiRect::all(Val::Px(theme.panel_padding)),
+                overflow: Overflow::clip_y(),
+                ..default()
+            },
+            BackgroundColor(theme.panel),
+        ))
+        .with_children(|parent| {
+            parent.spawn(Text::new("Components"));
+            let categories = scan.library.by_category();
+            if categories.is_empty() {
+                parent.spawn((
+                    Text::new(format!("No components in {COMPONENT_FOLDER}")),
+                    TextFont {
+                        font_size: theme.small_font_size,
+                        ..default()
+                    },
+                ));
+            }
+            for (category, definitions) in categories {
+                parent.spawn((
+                    Text::new(category),
+                    TextFont {
+                        font_size: theme.small_font_size,
+                        ..default()
+                    },
+                ));
+                parent
+                    .spawn(Node {
+                        flex_direction: FlexDirection::Row,
+                        flex_wrap: FlexWrap::Wrap,
+                        margin: UiRect::bottom(Val::Px(5.0)),
+                        ..default()
+                    })
+                    .with_children(|parent| {
+                        for definition in definitions {
+                            spawn_component_button(parent, definition, &thumbnails, &theme);
+                        }
+                    });
+            }
+        });
+
+    commands.insert_resource(ComponentLibraryResource {
+        library: scan.library,
+    });
+}
+
+/// Spawn a thumbnail button for a component definition
+fn spawn_component_button(
+    parent: &mut ChildSpawnerCommands,
+    definition: &ComponentDefinition,
+    thumbnails: &HashMap<Uuid, Handle<Image>>,
+    theme: &UiTheme,
+) {
+    parent
+        .spawn((
+            Button,
+            PlaceComponentButton(definition.id),
+            Node {
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                padding: UiRect::all(Val::Px(3.0)),
+                margin: UiRect::all(Val::Px(2.0)),
+                ..default()
+            },
+            BackgroundColor(theme.button),
+        ))
+        .with_children(|parent| {
+            if let Some(thumbnail) = thumbnails.get(&definition.id) {
+                #[allow(clippy::cast_precision_loss)]
+                parent.spawn((
+                    ImageNode::new(thumbnail.clone()),
+                    Node {
+                        width: Val::Px(THUMBNAIL_SIZE as f32),
+                        height: Val::Px(THUMBNAIL_SIZE as f32),
+                        ..default()
+                    },
+                ));
+            }
+            parent.spawn((
+                Text::new(definition.name.clone()),
+                TextFont {
+                    font_size: 12.0,
+                    ..default()
+                },
+            ));
+        });
+}
+
+/// An image a camera can render a thumbnail into
+fn thumbnail_target() -> Image {
+    let size = Extent3d {
+        width: THUMBNAIL_SIZE,
+        height: THUMBNAIL_SIZE,
+        ..default()
+    };
+    let mut image = Image::new_fill(
+        size,
+        TextureDimension::D2,
+        &[0, 0, 0, 0],
+        TextureFormat::Bgra8UnormSrgb,
+        RenderAssetUsages::default(),
+    );
+    image.texture_descriptor.usage =
+        TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST | TextureUsages::RENDER_ATTACHMENT;
+    image
+}
+
+/// Mesh of a component definition in its own coordinates
+fn definition_mesh(definition: &ComponentDefinition) -> Mesh {
+    let mut geometry = GeometryRegistry::create_new();
+    let solid_id = geometry.create_solid_from_loops(&definition.loops, &Tolerance::default());
+    match geometry.solids.get(&solid_id) {
+        Some(solid) => create_mesh_from_solid(solid, &geometry),
+        None => Mesh::from(Cuboid::default()),
+    }
+}
+
+/// Spawn a component and a camera framing it from above and to the side,
+/// both on their own render layer
+fn spawn_thumbnail_scene(
+    commands: &mut Commands,
+    mesh: Handle<Mesh>,
+    material: Handle<StandardMaterial>,
+    definition: &ComponentDefinition,
+    target: Handle<Image>,
+    layer: usize,
+) {
+    let (center, radius) = definition.bounds().map_or((Vec3::ZERO, 1.0), |bounds| {
+        let min = Vec3::new(bounds.min.x, bounds.min.y, bounds.min.z);
+        let max = Vec3::new(bounds.max.x, bounds.max.y, bounds.max.z);
+        ((min + max) / 2.0, (max - min).length().max(0.1) / 2.0)
+    });
+    let frames_left = THUMBNAIL_FRAMES;
+    commands.spawn((
+        Mesh3d(mesh),
+        MeshMaterial3d(material),
+        Transform::default(),
+        RenderLayers::layer(layer),
+        ThumbnailScene { frames_left },
+    ));
+    let eye = center + Vec3::new(1.0, 0.8, 1.2).normalize() * radius * 2.8;
+    commands.spawn((
+        Camera3d::default(),
+        Camera {
+            target: RenderTarget::Image(target.into()),
+            clear_color: ClearColorConfig::Custom(Color::srgba(0.2, 0.2, 0.2, 1.0)),
+            order: -1,
+            ..default()
+        },
+        Transform::from_translation(eye).looking_at(center, Vec3::Y),
+        RenderLayers::layer(layer),
+        ThumbnailScene { frames_left },
+    ));
+}
+
+/// Despawn thumbnail scenes once their images have been rendered
+pub fn expire_thumbnail_scenes(
+    mut commands: Commands,
+    mut scenes: Query<(Entity, &mut ThumbnailScene)>,
+) {
+    for (entity, mut scene) in &mut scenes {
+        if scene.frames_left == 0 {
+            commands.entity(entity).despawn();
+        } else {
+            scene.frames_left -= 1;
+        }
+    }
+}
+
+/// Place a component instance when its button is pressed: on the selected
+/// face, kept on its floor or wall, or at the origin if no face is selected
+pub fn handle_place_component_buttons(
+    interaction_query: Query<(&Interaction, &PlaceComponentButton), Changed<Interaction>>,
+    selection_state: Res<SelectionState>,
+    mut place_on_face: EventWriter<PlaceOnFace>,
+    mut place_component: EventWriter<PlaceComponent>,
+) {
+    for (interaction, button) in &interaction_query {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        if let Some(selection) = selection_state
+            .selected
+            .as_ref()
+            .filter(|selection| selection.selection_type == SelectionType::Polygon)
+        {
+            place_on_face.write(PlaceOnFace {
+                definition: button.0,
+                face: selection.id,
+                offset: FaceOffset::default(),
+            });
+        } else {
+            place_component.write(PlaceComponent {
+                definition: button.0,
+                placement: LinkTransform::default(),
+            });
+        }
+    }
+}
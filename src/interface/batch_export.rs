@@ -0,0 +1,456 @@
+use bevy::prelude::*;
+use std::path::Path;
+use uuid::Uuid;
+
+use crate::domain::{ElementKind, ExportFilter};
+use crate::infrastructure::batch_export::{BatchExport, BatchModel, BatchOutput, BatchReport};
+use crate::infrastructure::collada::ColladaExportSettings;
+use crate::infrastructure::dxf_plans::DxfPlanSettings;
+use crate::infrastructure::gbxml::GbxmlExportSettings;
+use crate::infrastructure::gltf::GltfExportSettings;
+use crate::infrastructure::ifc::IfcExportSettings;
+use crate::infrastructure::takeoff::TakeoffSettings;
+use crate::interface::command_bus::{LevelRegistryResource, TierRegistryResource};
+use crate::interface::file_menu::{GeoreferenceResource, ProjectState};
+use crate::interface::isolation::HiddenSolids;
+use crate::interface::render_export::export_path;
+use crate::interface::segment_outlines::{ElementRegistryResource, GeometryRegistryResource};
+use crate::interface::selection::SelectionState;
+use crate::interface::settings::OfficeStandardsResource;
+use crate::interface::theme::UiTheme;
+use crate::interface::ui::UiState;
+use crate::interface::ViewColumn;
+
+/// The formats offered, in the order they are written
+const BATCH_FORMATS: [&str; 8] = [
+    "STL",
+    "OBJ",
+    "glTF",
+    "DXF plans",
+    "CSV takeoff",
+    "IFC",
+    "COLLADA",
+    "gbXML",
+];
+
+/// Which solids a batch exports, before its layer and kind
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExportScope {
+    /// Every solid in the phases shown
+    #[default]
+    All,
+    /// The solids shown in the viewport: in the phases shown and not hidden
+    Visible,
+    /// The selected solid
+    Selection,
+}
+
+impl ExportScope {
+    /// The scope after this one, wrapping around
+    fn next(self) -> Self {
+        match self {
+            ExportScope::All => ExportScope::Visible,
+            ExportScope::Visible => ExportScope::Selection,
+            ExportScope::Selection => ExportScope::All,
+        }
+    }
+
+    /// The name shown for the scope
+    fn label(self) -> &'static str {
+        match self {
+            ExportScope::All => "All",
+            ExportScope::Visible => "Visible",
+            ExportScope::Selection => "Selection",
+        }
+    }
+}
+
+/// A batch being written, one output per frame
+struct PendingBatch {
+    batch: BatchExport,
+    /// The next output to write
+    next: usize,
+    report: BatchReport,
+}
+
+/// Resource holding the batch export panel's choices and the batch in
+/// progress
+#[derive(Resource)]
+pub struct BatchExportState {
+    /// Whether each of `BATCH_FORMATS` is written
+    pub formats: [bool; 8],
+    /// Whether DXF plans are split by level
+    pub per_level: bool,
+    /// Which solids are exported
+    pub scope: ExportScope,
+    /// The layer (tier) exported with the layers below it, or None for
+    /// every layer
+    pub layer: Option<Uuid>,
+    /// The element kind exported, or None for every kind
+    pub kind: Option<ElementKind>,
+    pub message: String,
+    pending: Option<PendingBatch>,
+}
+
+impl Default for BatchExportState {
+    fn default() -> Self {
+        Self {
+            formats: [true, false, true, true, true, false, false, false],
+            per_level: true,
+            scope: ExportScope::All,
+            layer: None,
+            kind: None,
+            message: String::new(),
+            pending: None,
+        }
+    }
+}
+
+/// Marker component for the batch export panel buttons
+#[derive(Component, Clone, Copy)]
+pub enum BatchExportButton {
+    /// Toggle one of `BATCH_FORMATS`
+    Format(usize),
+    PerLevel,
+    /// Cycle the export scope
+    Scope,
+    /// Cycle the layer exported
+    Layer,
+    /// Cycle the element kind exported
+    Kind,
+    Export,
+}
+
+/// Marker component for the batch export panel text
+#[derive(Component)]
+pub struct BatchExportText;
+
+/// Setup the batch export panel in the view column
+pub fn setup_batch_export(
+    mut commands: Commands,
+    column_query: Query<Entity, With<ViewColumn>>,
+    theme: Res<UiTheme>,
+) {
+    let Ok(column) = column_query.single() else {
+        return;
+    };
+    let buttons = (0..BATCH_FORMATS.len())
+        .map(BatchExportButton::Format)
+        .chain([
+            BatchExportButton::PerLevel,
+            BatchExportButton::Scope,
+            BatchExportButton::Layer,
+            BatchExportButton::Kind,
+            BatchExportButton::Export,
+        ]);
+    commands.entity(column).with_children(|parent| {
+        parent
+            .spawn((
+                Node {
+                    flex_direction: FlexDirection::Column,
+                    padding: UiRect::all(Val::Px(theme.panel_padding)),
+                    row_gap: Val::Px(5.0),
+                    ..default()
+                },
+                BackgroundColor(theme.panel),
+            ))
+            .with_children(|parent| {
+                parent
+                    .spawn(Node {
+                        flex_direction: FlexDirection::Row,
+                        flex_wrap: FlexWrap::Wrap,
+                        ..default()
+                    })
+                    .with_children(|parent| {
+                        for button in buttons {
+                            parent
+                                .spawn((
+                                    Button,
+                                    button,
+                                    Node {
+                                        padding: UiRect::all(Val::Px(theme.button_padding)),
+                                        margin: UiRect::new(
+                                            Val::ZERO,
+                                            Val::Px(3.0),
+                                            Val::ZERO,
+                                            Val::Px(3.0),
+                                        ),
+                                        ..default()
+                                    },
+                                    BackgroundColor(theme.button),
+                                ))
+                                .with_children(|parent| {
+                                    parent.spawn(Text::new("Export"));
+                                });
+                        }
+                    });
+
+                parent.spawn((
+                    Text::new(""),
+                    TextFont {
+                        font_size: theme.small_font_size,
+                        ..default()
+                    },
+                    BatchExportText,
+                ));
+            });
+    });
+}
+
+/// The solids the panel's scope, layer and kind include, in the phases
+/// shown in the viewport
+/// Returns None if the scope is the selection and nothing is selected
+fn export_filter(
+    state: &BatchExportState,
+    ui_state: &UiState,
+    hidden: &HiddenSolids,
+    selection: &SelectionState,
+    tier_registry: &TierRegistryResource,
+) -> Option<ExportFilter> {
+    let mut filter = ExportFilter::from(ui_state.phase_filter);
+    match state.scope {
+        ExportScope::All => {}
+        ExportScope::Visible => filter.exclude(hidden.hidden.iter().copied()),
+        ExportScope::Selection => filter.only([selection.solid?]),
+    }
+    if let Some(layer) = state.layer {
+        filter.on_layers(&tier_registry.registry, &[layer]);
+    }
+    filter.kinds = state.kind.map(|kind| vec![kind]);
+    Some(filter)
+}
+
+/// The outputs chosen in the panel, for the solids the filter includes,
+/// named and colored by the office standards and placed on the map by
+/// the project's georeference
+fn batch_outputs(
+    state: &BatchExportState,
+    filter: &ExportFilter,
+    standards: &OfficeStandardsResource,
+    georeference: &GeoreferenceResource,
+) -> Vec<BatchOutput> {
+    let georeference = georeference.georeference;
+    let standards = &standards.standards;
+    let outputs = [
+        BatchOutput::Stl(filter.clone()),
+        BatchOutput::Obj(filter.clone()),
+        BatchOutput::Gltf(GltfExportSettings {
+            filter: filter.clone(),
+            standards: standards.clone(),
+        }),
+        BatchOutput::DxfPlans(DxfPlanSettings {
+            filter: filter.clone(),
+            standards: standards.clone(),
+            per_level: state.per_level,
+        }),
+        BatchOutput::Takeoff(TakeoffSettings {
+            filter: filter.clone(),
+            standards: standards.clone(),
+        }),
+        BatchOutput::Ifc(IfcExportSettings {
+            filter: filter.clone(),
+            standards: standards.clone(),
+            georeference,
+            ..default()
+        }),
+        BatchOutput::Collada(ColladaExportSettings {
+            filter: filter.clone(),
+            standards: standards.clone(),
+            ..default()
+        }),
+        BatchOutput::Gbxml(GbxmlExportSettings {
+            georeference,
+            ..default()
+        }),
+    ];
+    outputs
+        .into_iter()
+        .zip(state.formats)
+        .filter_map(|(output, chosen)| chosen.then_some(output))
+        .collect()
+}
+
+/// Handle the batch export panel buttons: choose the formats and the
+/// solids, and start a batch beside the project
+#[allow(clippy::too_many_arguments)]
+pub fn handle_batch_export_buttons(
+    button_query: Query<(&Interaction, &BatchExportButton), Changed<Interaction>>,
+    project: Res<ProjectState>,
+    ui_state: Res<UiState>,
+    hidden: Res<HiddenSolids>,
+    selection: Res<SelectionState>,
+    tier_registry: Res<TierRegistryResource>,
+    standards: Res<OfficeStandardsResource>,
+    georeference: Res<GeoreferenceResource>,
+    mut state: ResMut<BatchExportState>,
+) {
+    for (interaction, button) in &button_query {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        match *button {
+            BatchExportButton::Format(index) => {
+                if let Some(chosen) = state.formats.get_mut(index) {
+                    *chosen = !*chosen;
+                }
+            }
+            BatchExportButton::PerLevel => state.per_level = !state.per_level,
+            BatchExportButton::Scope => state.scope = state.scope.next(),
+            BatchExportButton::Layer => {
+                // No layer, then each layer in turn
+                let layers: Vec<Uuid> = tier_registry
+                    .registry
+                    .sorted()
+                    .iter()
+                    .map(|tier| tier.id)
+                    .collect();
+                let next = state
+                    .layer
+                    .and_then(|layer| layers.iter().position(|id| *id == layer))
+                    .map_or(0, |index| index + 1);
+                state.layer = layers.get(next).copied();
+            }
+            BatchExportButton::Kind => {
+                let next = state
+                    .kind
+                    .and_then(|kind| ElementKind::ALL.iter().position(|k| *k == kind))
+                    .map_or(0, |index| index + 1);
+                state.kind = ElementKind::ALL.get(next).copied();
+            }
+            BatchExportButton::Export => {
+                if state.pending.is_some() {
+                    continue;
+                }
+                let Some(filter) =
+                    export_filter(&state, &ui_state, &hidden, &selection, &tier_registry)
+                else {
+                    state.message = "Select a solid to export first".to_string();
+                    continue;
+                };
+                let outputs = batch_outputs(&state, &filter, &standards, &georeference);
+                if outputs.is_empty() {
+                    state.message = "Choose a format to export first".to_string();
+                    continue;
+                }
+                let path = export_path(&project, "export", "json");
+                let folder = path.parent().map(Path::to_path_buf).unwrap_or_default();
+                let stem = path
+                    .file_stem()
+                    .map_or_else(String::new, |stem| stem.to_string_lossy().into_owned());
+                state.message = format!("Exporting {} formats", outputs.len());
+                state.pending = Some(PendingBatch {
+                    batch: BatchExport {
+                        folder,
+                        stem,
+                        outputs,
+                    },
+                    next: 0,
+                    report: BatchReport::default(),
+                });
+            }
+        }
+    }
+}
+
+/// Write the next output of the batch in progress, so the panel shows
+/// progress between outputs, and the manifest once all are written
+pub fn run_batch_exports(
+    mut state: ResMut<BatchExportState>,
+    geometry_registry: Res<GeometryRegistryResource>,
+    element_registry: Res<ElementRegistryResource>,
+    level_registry: Res<LevelRegistryResource>,
+) {
+    let Some(mut pending) = state.pending.take() else {
+        return;
+    };
+    let model = BatchModel {
+        geometry: &geometry_registry.registry,
+        elements: &element_registry.registry,
+        levels: &level_registry.registry,
+    };
+    let total = pending.batch.outputs.len();
+    if pending
+        .batch
+        .write_output(pending.next, model, &mut pending.report)
+        .is_some()
+    {
+        pending.next += 1;
+        let format = pending.batch.outputs[pending.next - 1].label();
+        state.message = format!("Exported {format} ({} of {total})", pending.next);
+        state.pending = Some(pending);
+        return;
+    }
+
+    let report = &pending.report;
+    for (format, error) in &report.failures {
+        error!("Could not export {format}: {error}");
+    }
+    state.message = match pending.batch.write_manifest(report) {
+        Ok(manifest) => {
+            info!(
+                "Exported {} files, listed in {}",
+                report.files.len(),
+                manifest.display()
+            );
+            let failed = match report.failures.len() {
+                0 => String::new(),
+                failures => format!(", {failures} formats failed"),
+            };
+            format!(
+                "Exported {} files{failed}, listed in {}",
+                report.files.len(),
+                manifest.display()
+            )
+        }
+        Err(error) => format!("Could not write the export manifest: {error}"),
+    };
+}
+
+/// Show the chosen formats and the batch's progress
+pub fn update_batch_export_panel(
+    state: Res<BatchExportState>,
+    mut button_query: Query<(&BatchExportButton, &Children, &mut BackgroundColor)>,
+    mut text_query: Query<&mut Text>,
+    status_query: Query<Entity, With<BatchExportText>>,
+    tier_registry: Res<TierRegistryResource>,
+    theme: Res<UiTheme>,
+) {
+    if !state.is_changed() {
+        return;
+    }
+    for (button, children, mut background_color) in &mut button_query {
+        let (label, active) = match *button {
+            BatchExportButton::Format(index) => {
+                (BATCH_FORMATS[index].to_string(), state.formats[index])
+            }
+            BatchExportButton::PerLevel => ("Plan per level".to_string(), state.per_level),
+            BatchExportButton::Scope => (
+                format!("Scope: {}", state.scope.label()),
+                state.scope != ExportScope::All,
+            ),
+            BatchExportButton::Layer => {
+                let layer = state
+                    .layer
+                    .and_then(|layer| tier_registry.registry.get(&layer))
+                    .map_or("All", |tier| tier.name.as_str());
+                (format!("Layer: {layer}"), state.layer.is_some())
+            }
+            BatchExportButton::Kind => {
+                let kind = state.kind.map_or("All", |kind| kind.label());
+                (format!("Kind: {kind}"), state.kind.is_some())
+            }
+            BatchExportButton::Export => ("Export".to_string(), state.pending.is_some()),
+        };
+        *background_color = theme.button_color(active).into();
+        for child in children {
+            if let Ok(mut text) = text_query.get_mut(*child) {
+                text.0.clone_from(&label);
+            }
+        }
+    }
+    for status in &status_query {
+        if let Ok(mut text) = text_query.get_mut(status) {
+            text.0.clone_from(&state.message);
+        }
+    }
+}
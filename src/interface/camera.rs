@@ -1,11 +1,19 @@
+use crate::domain::geometry::Bounds;
+use crate::domain::{GeometryRegistry, Point, WalkFace, Walker};
+use crate::interface::segment_outlines::{GeometryRegistryResource, SolidId};
 use crate::interface::ui::{CameraViewEvent, UiState};
 use bevy::prelude::*;
 use bevy::render::camera::ScalingMode;
+use std::collections::HashMap;
 
 /// Zoom speed for orthographic view (viewport height units per second)
 const ORTHO_ZOOM_SPEED: f32 = 5.0;
 /// Default distance from origin for camera views
 const CAMERA_VIEW_DISTANCE: f32 = 8.0;
+/// Key turning walk mode on and off
+const WALK_KEY: KeyCode = KeyCode::KeyV;
+/// Key letting the walker fly through the model while walking
+const FLY_KEY: KeyCode = KeyCode::KeyF;
 
 /// Camera configuration and setup
 #[derive(Resource, Clone)]
@@ -15,6 +23,39 @@ pub struct CameraConfig {
     pub up_direction: Vec3,
     pub movement_speed: f32,
     pub rotation_speed: f32,
+    pub keys: CameraKeys,
+}
+
+/// Keys that move and orbit the camera
+#[derive(Clone)]
+pub struct CameraKeys {
+    pub move_forward: KeyCode,
+    pub move_back: KeyCode,
+    pub move_left: KeyCode,
+    pub move_right: KeyCode,
+    pub move_up: KeyCode,
+    pub move_down: KeyCode,
+    pub orbit_left: KeyCode,
+    pub orbit_right: KeyCode,
+    pub orbit_up: KeyCode,
+    pub orbit_down: KeyCode,
+}
+
+impl Default for CameraKeys {
+    fn default() -> Self {
+        Self {
+            move_forward: KeyCode::KeyW,
+            move_back: KeyCode::KeyS,
+            move_left: KeyCode::KeyA,
+            move_right: KeyCode::KeyD,
+            move_up: KeyCode::KeyQ,
+            move_down: KeyCode::KeyE,
+            orbit_left: KeyCode::ArrowLeft,
+            orbit_right: KeyCode::ArrowRight,
+            orbit_up: KeyCode::ArrowUp,
+            orbit_down: KeyCode::ArrowDown,
+        }
+    }
 }
 
 impl Default for CameraConfig {
@@ -25,13 +66,90 @@ impl Default for CameraConfig {
             up_direction: Vec3::Y,
             movement_speed: 2.0,
             rotation_speed: 3.0,
+            keys: CameraKeys::default(),
+        }
+    }
+}
+
+/// Resource holding the walkthrough camera's state
+///
+/// While walking, the camera is the eyes of a walker that falls under
+/// gravity, stands on floors and stairs and is stopped by walls; flying
+/// moves it freely through the model instead.
+#[derive(Resource)]
+pub struct WalkState {
+    /// Whether the camera walks rather than flies around the model
+    pub walking: bool,
+    /// Whether a walking camera ignores gravity and collisions
+    pub flying: bool,
+    /// The walker's body
+    pub walker: Walker,
+    /// Height of the eyes above the feet
+    pub eye_height: f32,
+    /// Downward acceleration in meters per second squared
+    pub gravity: f32,
+    /// Current vertical speed, negative while falling
+    pub vertical_speed: f32,
+    /// Each solid's faces and their bounds, in the solid's own coordinates
+    faces: HashMap<uuid::Uuid, (Vec<WalkFace>, Bounds)>,
+}
+
+impl Default for WalkState {
+    fn default() -> Self {
+        Self {
+            walking: false,
+            flying: false,
+            walker: Walker::default(),
+            eye_height: 1.6,
+            gravity: 9.81,
+            vertical_speed: 0.0,
+            faces: HashMap::new(),
         }
     }
 }
 
+impl WalkState {
+    /// Rebuild the solids' faces from the registry
+    fn refresh_faces(&mut self, registry: &GeometryRegistry) {
+        self.faces = registry
+            .solids
+            .solids
+            .keys()
+            .filter_map(|solid_id| {
+                let loops = registry.solid_loops(solid_id)?;
+                let bounds = Bounds::from_points(&loops.concat())?;
+                let faces = loops.into_iter().filter_map(WalkFace::new).collect();
+                Some((*solid_id, (faces, bounds)))
+            })
+            .collect();
+    }
+}
+
+/// A stored position as a world-space vector
+fn to_vec3(point: &Point) -> Vec3 {
+    Vec3::new(point.x, point.y, point.z)
+}
+
+/// A world-space vector as a stored position
+fn to_point(vector: Vec3) -> Point {
+    Point {
+        x: vector.x,
+        y: vector.y,
+        z: vector.z,
+    }
+}
+
+/// Marker component for the camera the user looks through and moves
+///
+/// Other cameras, such as thumbnail cameras and the stereo right eye,
+/// render without it.
+#[derive(Component)]
+pub struct MainCamera;
+
 /// Spawns the main camera with the given configuration
 pub fn spawn_camera(commands: &mut Commands, config: &CameraConfig) {
     commands.spawn((
+        MainCamera,
         Camera::default(),
         Camera3d::default(),
         Projection::Perspective(PerspectiveProjection::default()),
@@ -44,22 +162,27 @@ pub fn spawn_camera(commands: &mut Commands, config: &CameraConfig) {
 /// Camera controls system for movement, rotation, and orthographic zoom
 pub fn camera_controls(
     keyboard_input: Res<ButtonInput<KeyCode>>,
-    mut query: Query<&mut Transform, With<Camera>>,
+    mut query: Query<&mut Transform, With<MainCamera>>,
     time: Res<Time>,
     config: Res<CameraConfig>,
     mut ui_state: ResMut<UiState>,
+    walk: Res<WalkState>,
 ) {
+    // A walking camera is moved by `walk_camera`
+    if walk.walking && !ui_state.isometric_view {
+        return;
+    }
     if let Ok(mut camera_transform) = query.single_mut() {
         if ui_state.isometric_view {
-            // In orthographic view, W/S control zoom instead of forward/back movement
+            // In orthographic view, forward/back keys control zoom instead of forward/back movement
             let zoom_delta = ORTHO_ZOOM_SPEED * time.delta_secs();
-            if keyboard_input.pressed(KeyCode::KeyW) {
+            if keyboard_input.pressed(config.keys.move_forward) {
                 ui_state.ortho_zoom -= zoom_delta;
             }
-            if keyboard_input.pressed(KeyCode::KeyS) {
+            if keyboard_input.pressed(config.keys.move_back) {
                 ui_state.ortho_zoom += zoom_delta;
             }
-            // Still allow lateral movement (A/D) and vertical (Q/E)
+            // Still allow lateral and vertical movement
             handle_lateral_movement(&mut camera_transform, &keyboard_input, &time, &config);
         } else {
             // In perspective view, normal movement controls
@@ -79,27 +202,27 @@ fn handle_movement(
     let delta_time = time.delta_secs();
     let speed = config.movement_speed * delta_time;
 
-    if keyboard_input.pressed(KeyCode::KeyW) {
+    if keyboard_input.pressed(config.keys.move_forward) {
         let forward = camera_transform.forward();
         camera_transform.translation += forward * speed;
     }
-    if keyboard_input.pressed(KeyCode::KeyS) {
+    if keyboard_input.pressed(config.keys.move_back) {
         let back = camera_transform.back();
         camera_transform.translation += back * speed;
     }
-    if keyboard_input.pressed(KeyCode::KeyA) {
+    if keyboard_input.pressed(config.keys.move_left) {
         let left = camera_transform.left();
         camera_transform.translation += left * speed;
     }
-    if keyboard_input.pressed(KeyCode::KeyD) {
+    if keyboard_input.pressed(config.keys.move_right) {
         let right = camera_transform.right();
         camera_transform.translation += right * speed;
     }
-    if keyboard_input.pressed(KeyCode::KeyQ) {
+    if keyboard_input.pressed(config.keys.move_up) {
         let up = camera_transform.up();
         camera_transform.translation += up * speed;
     }
-    if keyboard_input.pressed(KeyCode::KeyE) {
+    if keyboard_input.pressed(config.keys.move_down) {
         let down = camera_transform.down();
         camera_transform.translation += down * speed;
     }
@@ -115,19 +238,19 @@ fn handle_lateral_movement(
     let delta_time = time.delta_secs();
     let speed = config.movement_speed * delta_time;
 
-    if keyboard_input.pressed(KeyCode::KeyA) {
+    if keyboard_input.pressed(config.keys.move_left) {
         let left = camera_transform.left();
         camera_transform.translation += left * speed;
     }
-    if keyboard_input.pressed(KeyCode::KeyD) {
+    if keyboard_input.pressed(config.keys.move_right) {
         let right = camera_transform.right();
         camera_transform.translation += right * speed;
     }
-    if keyboard_input.pressed(KeyCode::KeyQ) {
+    if keyboard_input.pressed(config.keys.move_up) {
         let up = camera_transform.up();
         camera_transform.translation += up * speed;
     }
-    if keyboard_input.pressed(KeyCode::KeyE) {
+    if keyboard_input.pressed(config.keys.move_down) {
         let down = camera_transform.down();
         camera_transform.translation += down * speed;
     }
@@ -143,25 +266,186 @@ fn handle_rotation(
     let delta_time = time.delta_secs();
     let speed = config.rotation_speed * delta_time;
 
-    if keyboard_input.pressed(KeyCode::ArrowLeft) {
+    if keyboard_input.pressed(config.keys.orbit_left) {
         camera_transform.rotate_around(Vec3::ZERO, Quat::from_axis_angle(Vec3::Y, speed));
     }
-    if keyboard_input.pressed(KeyCode::ArrowRight) {
+    if keyboard_input.pressed(config.keys.orbit_right) {
         camera_transform.rotate_around(Vec3::ZERO, Quat::from_axis_angle(Vec3::Y, -speed));
     }
-    if keyboard_input.pressed(KeyCode::ArrowUp) {
+    if keyboard_input.pressed(config.keys.orbit_up) {
         let right = camera_transform.right();
         camera_transform.rotate_around(Vec3::ZERO, Quat::from_axis_angle(*right, speed));
     }
-    if keyboard_input.pressed(KeyCode::ArrowDown) {
+    if keyboard_input.pressed(config.keys.orbit_down) {
         let right = camera_transform.right();
         camera_transform.rotate_around(Vec3::ZERO, Quat::from_axis_angle(*right, -speed));
     }
 }
 
+/// The world-space faces of visible solids whose bounds come within reach
+/// of the feet
+fn nearby_faces<'a>(
+    walk: &WalkState,
+    solids: impl Iterator<Item = (&'a SolidId, &'a GlobalTransform, &'a InheritedVisibility)>,
+    feet: Vec3,
+    reach: f32,
+) -> Vec<WalkFace> {
+    let mut nearby = Vec::new();
+    for (solid_id, transform, _) in solids.filter(|(_, _, visibility)| visibility.get()) {
+        let Some((faces, bounds)) = walk.faces.get(&solid_id.0) else {
+            continue;
+        };
+        let corners = [
+            (bounds.min.x, bounds.min.y, bounds.min.z),
+            (bounds.max.x, bounds.max.y, bounds.max.z),
+            (bounds.min.x, bounds.min.y, bounds.max.z),
+            (bounds.min.x, bounds.max.y, bounds.min.z),
+            (bounds.max.x, bounds.min.y, bounds.min.z),
+            (bounds.min.x, bounds.max.y, bounds.max.z),
+            (bounds.max.x, bounds.min.y, bounds.max.z),
+            (bounds.max.x, bounds.max.y, bounds.min.z),
+        ]
+        .map(|(x, y, z)| transform.transform_point(Vec3::new(x, y, z)));
+        let (min, max) = corners
+            .iter()
+            .fold((Vec3::MAX, Vec3::MIN), |(min, max), corner| {
+                (min.min(*corner), max.max(*corner))
+            });
+        if (feet.clamp(min, max) - feet).length() > reach {
+            continue;
+        }
+        nearby.extend(faces.iter().filter_map(|face| {
+            let points = face
+                .points
+                .iter()
+                .map(|point| to_point(transform.transform_point(to_vec3(point))))
+                .collect();
+            WalkFace::new(points)
+        }));
+    }
+    nearby
+}
+
+/// Turn a walking camera in place: left and right around the vertical,
+/// up and down around its own horizontal axis
+fn turn_in_place(
+    camera_transform: &mut Transform,
+    keyboard_input: &ButtonInput<KeyCode>,
+    speed: f32,
+    keys: &CameraKeys,
+) {
+    if keyboard_input.pressed(keys.orbit_left) {
+        camera_transform.rotate_y(speed);
+    }
+    if keyboard_input.pressed(keys.orbit_right) {
+        camera_transform.rotate_y(-speed);
+    }
+    if keyboard_input.pressed(keys.orbit_up) {
+        camera_transform.rotate_local_x(speed);
+    }
+    if keyboard_input.pressed(keys.orbit_down) {
+        camera_transform.rotate_local_x(-speed);
+    }
+}
+
+/// Walk the camera through the model: V turns walking on and off and F
+/// toggles flying
+///
+/// Forward, back and sideways keys walk level whichever way the camera
+/// looks; the walker falls onto floors and stairs and slides along walls.
+/// While flying, the up and down keys move it vertically too and nothing
+/// stops it.
+#[allow(clippy::too_many_arguments)]
+pub fn walk_camera(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    time: Res<Time>,
+    config: Res<CameraConfig>,
+    ui_state: Res<UiState>,
+    geometry_registry: Res<GeometryRegistryResource>,
+    solid_query: Query<(&SolidId, &GlobalTransform, &InheritedVisibility)>,
+    mut walk: ResMut<WalkState>,
+    mut camera_query: Query<&mut Transform, With<MainCamera>>,
+) {
+    if keyboard_input.just_pressed(WALK_KEY) {
+        walk.walking = !walk.walking;
+        walk.vertical_speed = 0.0;
+        info!("Walk mode {}", if walk.walking { "on" } else { "off" });
+    }
+    if walk.walking && keyboard_input.just_pressed(FLY_KEY) {
+        walk.flying = !walk.flying;
+        walk.vertical_speed = 0.0;
+        info!("Flying {}", if walk.flying { "on" } else { "off" });
+    }
+    let Ok(mut camera_transform) = camera_query.single_mut() else {
+        return;
+    };
+    if !walk.walking || ui_state.isometric_view {
+        return;
+    }
+    if geometry_registry.is_changed() || walk.faces.is_empty() {
+        walk.refresh_faces(&geometry_registry.registry);
+    }
+    let delta_time = time.delta_secs();
+    let keys = &config.keys;
+    turn_in_place(
+        &mut camera_transform,
+        &keyboard_input,
+        config.rotation_speed * delta_time,
+        keys,
+    );
+
+    // Level directions, so looking up or down does not climb or dig
+    let forward =
+        (camera_transform.forward().as_vec3() * Vec3::new(1.0, 0.0, 1.0)).normalize_or_zero();
+    let right = Vec3::new(-forward.z, 0.0, forward.x);
+    let mut step = Vec3::ZERO;
+    for (key, direction) in [
+        (keys.move_forward, forward),
+        (keys.move_back, -forward),
+        (keys.move_right, right),
+        (keys.move_left, -right),
+    ] {
+        if keyboard_input.pressed(key) {
+            step += direction;
+        }
+    }
+    let step = step.normalize_or_zero() * config.movement_speed * delta_time;
+
+    if walk.flying {
+        let mut rise = 0.0;
+        if keyboard_input.pressed(keys.move_up) {
+            rise += config.movement_speed * delta_time;
+        }
+        if keyboard_input.pressed(keys.move_down) {
+            rise -= config.movement_speed * delta_time;
+        }
+        camera_transform.translation += step + Vec3::Y * rise;
+        return;
+    }
+
+    let eyes = Vec3::Y * walk.eye_height;
+    let feet = camera_transform.translation - eyes;
+    let reach = step.length()
+        + walk.vertical_speed.abs() * delta_time
+        + walk.walker.height
+        + walk.walker.radius;
+    let faces = nearby_faces(&walk, solid_query.iter(), feet, reach);
+    let walker = &walk.walker;
+    let moved = walker.slide(&to_point(feet), step.x, step.z, &faces);
+    let (landed, vertical_speed) = walker.fall(
+        &moved,
+        walk.vertical_speed,
+        walk.gravity,
+        delta_time,
+        &faces,
+    );
+    camera_transform.translation = to_vec3(&landed) + eyes;
+    walk.vertical_speed = vertical_speed;
+}
+
 /// Update camera projection based on UI state
 pub fn update_camera_projection(
-    mut camera_query: Query<(&mut Projection, &GlobalTransform), With<Camera>>,
+    mut camera_query: Query<(&mut Projection, &GlobalTransform), With<MainCamera>>,
     mut ui_state: ResMut<UiState>,
 ) {
     let Ok((mut projection, global_transform)) = camera_query.single_mut() else {
@@ -204,7 +488,7 @@ pub fn update_camera_projection(
 
 /// Handle camera view change events
 pub fn handle_camera_view_events(
-    mut camera_query: Query<&mut Transform, With<Camera>>,
+    mut camera_query: Query<&mut Transform, With<MainCamera>>,
     mut camera_view_events: EventReader<CameraViewEvent>,
 ) {
     let Ok(mut camera_transform) = camera_query.single_mut() else {
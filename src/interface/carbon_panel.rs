@@ -0,0 +1,272 @@
+use bevy::input::keyboard::KeyboardInput;
+use bevy::prelude::*;
+use std::path::PathBuf;
+
+use crate::domain::{calculate_carbon, CarbonFactors, CarbonReport};
+use crate::infrastructure::carbon::{read_carbon_factors, write_carbon_report};
+use crate::interface::heat_map::HeatMap;
+use crate::interface::prompt::{edit_buffer, PromptAction};
+use crate::interface::segment_outlines::{ElementRegistryResource, GeometryRegistryResource};
+use crate::interface::theme::UiTheme;
+use crate::interface::AnalysisColumn;
+
+/// Which path the prompt is asking for
+#[derive(Clone, Copy, PartialEq)]
+pub enum CarbonPrompt {
+    Load,
+    Export,
+}
+
+/// Resource holding the material carbon definitions and latest report
+#[derive(Resource)]
+pub struct CarbonState {
+    pub factors: CarbonFactors,
+    pub report: CarbonReport,
+    /// Whether the report is kept up to date and the model shaded
+    pub enabled: bool,
+    pub prompt: Option<CarbonPrompt>,
+    pub entry: String,
+    pub message: String,
+}
+
+impl Default for CarbonState {
+    fn default() -> Self {
+        Self {
+            factors: CarbonFactors::with_typical_materials(),
+            report: CarbonReport::default(),
+            enabled: false,
+            prompt: None,
+            entry: String::new(),
+            message: String::new(),
+        }
+    }
+}
+
+/// What a carbon panel button does
+#[derive(Component, Clone, Copy)]
+pub enum CarbonButton {
+    Toggle,
+    Load,
+    Export,
+}
+
+/// Marker component for the carbon results text
+#[derive(Component)]
+pub struct CarbonText;
+
+/// Setup the carbon panel in the analysis column
+pub fn setup_carbon_panel(
+    mut commands: Commands,
+    column_query: Query<Entity, With<AnalysisColumn>>,
+    theme: Res<UiTheme>,
+) {
+    let Ok(column) = column_query.single() else {
+        return;
+    };
+    commands.entity(column).with_children(|parent| {
+        parent
+            .spawn((
+                Node {
+                    flex_direction: FlexDirection::Column,
+                    padding: UiRect::all(Val::Px(theme.panel_padding)),
+                    ..default()
+                },
+                BackgroundColor(theme.panel),
+            ))
+            .with_children(|parent| {
+                parent
+                    .spawn(Node {
+                        flex_direction: FlexDirection::Row,
+                        margin: UiRect::bottom(Val::Px(5.0)),
+                        ..default()
+                    })
+                    .with_children(|parent| {
+                        for (button, label) in [
+                            (CarbonButton::Toggle, "Carbon"),
+                            (CarbonButton::Load, "Load materials"),
+                            (CarbonButton::Export, "Export report"),
+                        ] {
+                            parent
+                                .spawn((
+                                    Button,
+                                    button,
+                                    Node {
+                                        padding: UiRect::all(Val::Px(theme.button_padding)),
+                                        margin: UiRect::right(Val::Px(3.0)),
+                                        ..default()
+                                    },
+                                    BackgroundColor(theme.button),
+                                ))
+                                .with_children(|parent| {
+                                    parent.spawn(Text::new(label));
+                                });
+                        }
+                    });
+
+                parent.spawn((
+                    Text::new("Carbon: off"),
+                    TextFont {
+                        font_size: theme.small_font_size,
+                        ..default()
+                    },
+                    CarbonText,
+                ));
+            });
+    });
+}
+
+/// Handle the carbon panel buttons
+pub fn handle_carbon_buttons(
+    interaction_query: Query<(&Interaction, &CarbonButton), Changed<Interaction>>,
+    mut state: ResMut<CarbonState>,
+) {
+    for (interaction, button) in &interaction_query {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        match button {
+            CarbonButton::Toggle => state.enabled = !state.enabled,
+            CarbonButton::Load => {
+                state.prompt = Some(CarbonPrompt::Load);
+                state.entry.clear();
+                state.message.clear();
+            }
+            CarbonButton::Export => {
+                state.prompt = Some(CarbonPrompt::Export);
+                state.entry.clear();
+                state.message.clear();
+            }
+        }
+    }
+}
+
+/// Type a path into the prompt; Enter confirms and Escape cancels
+pub fn handle_carbon_prompt(
+    mut keyboard_events: EventReader<KeyboardInput>,
+    mut state: ResMut<CarbonState>,
+) {
+    let Some(prompt) = state.prompt else {
+        keyboard_events.clear();
+        return;
+    };
+    for event in keyboard_events.read() {
+        match edit_buffer(&mut state.entry, event) {
+            PromptAction::Continue => {}
+            PromptAction::Cancel => {
+                state.prompt = None;
+                state.message.clear();
+            }
+            PromptAction::Submit => {
+                let path = PathBuf::from(state.entry.trim());
+                state.prompt = None;
+                state.message = match prompt {
+                    CarbonPrompt::Load => match read_carbon_factors(&path) {
+                        Ok(factors) => {
+                            let message = format!("Loaded {} material(s)", factors.materials.len());
+                            for definition in factors.materials {
+                                state.factors.add(definition);
+                            }
+                            state.enabled = true;
+                            message
+                        }
+                        Err(error) => error.to_string(),
+                    },
+                    CarbonPrompt::Export => {
+                        let path = path.with_extension("csv");
+                        match write_carbon_report(&path, &state.report) {
+                            Ok(()) => format!("Exported {}", path.display()),
+                            Err(error) => format!("Could not export carbon report: {error}"),
+                        }
+                    }
+                };
+                return;
+            }
+        }
+    }
+}
+
+/// Recalculate the report when it is enabled and the model or materials
+/// change
+pub fn calculate_model_carbon(
+    geometry_registry: Res<GeometryRegistryResource>,
+    element_registry: Res<ElementRegistryResource>,
+    mut state: ResMut<CarbonState>,
+) {
+    let changed =
+        state.is_changed() || geometry_registry.is_changed() || element_registry.is_changed();
+    if !changed {
+        return;
+    }
+    if !state.enabled {
+        if !state.report.lines.is_empty() || !state.report.unrated.is_empty() {
+            state.report = CarbonReport::default();
+        }
+        return;
+    }
+    let report = calculate_carbon(
+        &element_registry.registry,
+        &geometry_registry.registry,
+        &state.factors,
+    );
+    state.report = report;
+}
+
+/// Refresh the results text when the report changes
+pub fn update_carbon_panel(
+    state: Res<CarbonState>,
+    mut text_query: Query<&mut Text, With<CarbonText>>,
+) {
+    if !state.is_changed() {
+        return;
+    }
+    let report = &state.report;
+    let mut lines = if state.enabled {
+        vec![format!(
+            "Carbon: {:.0} kgCO2e over {} element(s), {} unrated",
+            report.total(),
+            report.lines.len(),
+            report.unrated.len()
+        )]
+    } else {
+        vec![format!(
+            "Carbon: off ({} material(s))",
+            state.factors.materials.len()
+        )]
+    };
+    match state.prompt {
+        Some(CarbonPrompt::Load) => lines.push(format!("Material file: {}_", state.entry)),
+        Some(CarbonPrompt::Export) => lines.push(format!("Export to: {}_", state.entry)),
+        None => {}
+    }
+    if !state.message.is_empty() {
+        lines.push(state.message.clone());
+    }
+    for (kind, emissions) in report.by_kind() {
+        lines.push(format!("{}: {emissions:.0} kgCO2e", kind.label()));
+    }
+    let status = lines.join("\n");
+    for mut text in &mut text_query {
+        text.0.clone_from(&status);
+    }
+}
+
+/// Title of the carbon heat map's legend
+const HEAT_MAP_TITLE: &str = "Embodied carbon";
+
+/// Shade the rated solids by their emissions while the report is on, and
+/// take the shading off when it is turned off
+pub fn show_carbon_heat_map(state: Res<CarbonState>, mut heat_map: ResMut<HeatMap>) {
+    if !state.is_changed() {
+        return;
+    }
+    if state.enabled {
+        heat_map.show_solids(
+            HEAT_MAP_TITLE,
+            "lowest",
+            "highest emissions",
+            state.report.intensities(),
+        );
+    } else if heat_map.title == HEAT_MAP_TITLE {
+        heat_map.clear();
+    }
+}
@@ -0,0 +1,1012 @@
+use bevy::prelude::*;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::PathBuf;
+use uuid::Uuid;
+
+use crate::application::commands::{
+    add_constraint, create_layered_wall, create_sketch_path, create_wall, move_vertex,
+    remove_constraint, transform_vertices,
+};
+use crate::application::create_mesh_from_solid;
+use crate::application::expressions::{solve_expressions, ExpressionModel};
+use crate::application::families::{place_family, update_hosted_families};
+use crate::application::finishes::{generate_space_finishes, update_space_finishes};
+use crate::application::grids::{add_grid_layout, edit_grid};
+use crate::application::massing::convert_mass;
+use crate::application::provenance::{
+    delete_operation, edit_operation, move_operation, run_operation, suppress_operation,
+    update_operations,
+};
+use crate::application::services::{route_service, service_clashes};
+use crate::application::wall_joins::join_walls;
+use crate::domain::geometry::Plane;
+use crate::domain::solver::{
+    order_expression_constraints, Constraint, ConstraintSet, ExpressionConstraint,
+};
+use crate::domain::{
+    ExportFilter, FamilyParameters, FamilyRegistry, FinishLayer, FinishRegistry, GridDatum,
+    GridEdit, GridLayout, GridRegistry, LevelRegistry, MassConversionSettings, MassFaceRole,
+    Operation, OperationChange, PhaseFilter, Point, ProvenanceGraph, ServiceKind, ServiceRegistry,
+    ServiceSection, TierRegistry, WallAssembly,
+};
+use crate::infrastructure::stl::write_stl;
+use crate::interface::issues_panel::ValidationState;
+use crate::interface::segment_outlines::{
+    ElementRegistryResource, GeometryRegistryResource, SolidId,
+};
+use crate::interface::settings::OfficeStandardsResource;
+use crate::interface::ui::ToggleableMesh;
+
+/// Command to build a straight wall rising from a line
+#[derive(Event, Clone)]
+pub struct CreateWall {
+    /// Start of the wall's center line, at its base
+    pub start: Point,
+    /// End of the wall's center line
+    pub end: Point,
+    /// Wall thickness in meters, unless built of an assembly
+    pub thickness: f32,
+    /// Wall height in meters
+    pub height: f32,
+    /// The layers to build the wall of, which set its thickness, if any
+    pub assembly: Option<WallAssembly>,
+    /// Whether to model each of the assembly's layers as its own solid
+    pub per_layer: bool,
+}
+
+/// Command to place a door or window in a wall
+#[derive(Event, Clone)]
+pub struct PlaceFamily {
+    /// The wall element to host it
+    pub host: Uuid,
+    /// Distance of the opening's center from the start of the wall's
+    /// center line
+    pub offset: f32,
+    /// The parameters to generate it from
+    pub parameters: FamilyParameters,
+}
+
+/// Command to generate floor finishes or ceilings for a space
+#[derive(Event, Clone)]
+pub struct GenerateFinishes {
+    /// The space element
+    pub space: Uuid,
+    /// The finish to lay over its floor or hang below its ceiling
+    pub layer: FinishLayer,
+}
+
+/// Command to route a duct, pipe or conduit along a centerline
+#[derive(Event, Clone)]
+pub struct RouteService {
+    /// What the run carries
+    pub kind: ServiceKind,
+    /// Its cross-section
+    pub section: ServiceSection,
+    /// The centerline, from start to end
+    pub path: Vec<Point>,
+}
+
+/// Command to convert a massing solid into walls, roofs and floors
+#[derive(Event, Clone)]
+pub struct ConvertMass {
+    /// The massing solid
+    pub solid: Uuid,
+    /// Roles of faces that do not take the role their facing gives them
+    pub tags: BTreeMap<Uuid, MassFaceRole>,
+    /// What to build the elements of
+    pub settings: MassConversionSettings,
+}
+
+/// Command to add a structural grid to the model
+#[derive(Event, Clone)]
+pub struct CreateGrid {
+    /// The grid, with the ID layouts and edits will refer to it by
+    pub grid: GridDatum,
+}
+
+/// Command to generate columns or walls from a grid, kept following it
+#[derive(Event, Clone)]
+pub struct AddGridLayout {
+    /// The grid
+    pub grid: Uuid,
+    /// What to generate
+    pub layout: GridLayout,
+}
+
+/// Command to edit a grid, regenerating the layouts derived from it
+#[derive(Event, Clone)]
+pub struct EditGrid {
+    /// The grid
+    pub grid: Uuid,
+    /// The edit
+    pub edit: GridEdit,
+}
+
+/// Command to sweep a face along its normal into a solid, run again
+/// whenever the face moves
+#[derive(Event, Clone)]
+pub struct ExtrudeFace {
+    /// The face
+    pub face: Uuid,
+    /// How far to sweep it, negative to sweep it backward
+    pub depth: f32,
+}
+
+/// Command to keep the part of a solid on one side of a plane, cut again
+/// whenever the solid changes
+#[derive(Event, Clone)]
+pub struct CutSolid {
+    /// The solid
+    pub solid: Uuid,
+    /// The plane
+    pub plane: Plane,
+    /// Whether to keep the part the plane's normal points into, rather
+    /// than the part behind it
+    pub keep_front: bool,
+}
+
+/// Command to change a past operation, running everything downstream of
+/// it again
+#[derive(Event, Clone)]
+pub struct EditOperation {
+    /// The operation's record
+    pub record: Uuid,
+    /// The change
+    pub change: OperationChange,
+}
+
+/// Command to draw a path of sketch segments through points, such as a
+/// pen stroke
+#[derive(Event, Clone)]
+pub struct AddSketchPath {
+    /// The points the path joins in order
+    pub points: Vec<Point>,
+}
+
+/// Command to move a vertex to a new position
+#[derive(Event, Clone)]
+pub struct MoveVertex {
+    /// The vertex to move
+    pub vertex: Uuid,
+    /// Where it goes
+    pub position: Point,
+}
+
+/// Command to transform a set of vertices
+#[derive(Event, Clone)]
+pub struct TransformVertices {
+    /// The vertices to transform
+    pub vertices: Vec<Uuid>,
+    /// The transform, in the coordinates the vertices are stored in
+    pub transform: Mat4,
+}
+
+/// Command to add an explicit constraint to the model
+#[derive(Event, Clone)]
+pub struct AddConstraint {
+    /// The constraint to add
+    pub constraint: Constraint,
+}
+
+/// Command to remove an explicit constraint from the model, relaxing it
+#[derive(Event, Clone)]
+pub struct RemoveConstraint {
+    /// The constraint to remove
+    pub constraint: Constraint,
+}
+
+/// Command to add a level, or move the level with the name to an
+/// elevation
+#[derive(Event, Clone)]
+pub struct SetLevel {
+    /// The level's name
+    pub name: String,
+    /// Its height in meters
+    pub elevation: f32,
+}
+
+/// Command to keep a property equal to an expression, written as
+/// `object.property = expression`
+#[derive(Event, Clone)]
+pub struct AddExpressionConstraint {
+    /// The constraint as written
+    pub text: String,
+}
+
+/// Command to give one face of a solid's element its own material, or
+/// with no material to return it to the element's
+#[derive(Event, Clone)]
+pub struct SetFaceMaterial {
+    /// The solid the face belongs to
+    pub solid: Uuid,
+    /// The face
+    pub polygon: Uuid,
+    /// The material, matched ignoring case
+    pub material: Option<String>,
+}
+
+/// Command to write the model's solids to an STL file
+#[derive(Event, Clone)]
+pub struct ExportStl {
+    /// The file to write
+    pub path: PathBuf,
+    /// The phases to include
+    pub phases: PhaseFilter,
+}
+
+/// Sent after commands change the shape of solids, or create them
+#[derive(Event, Clone)]
+pub struct SolidsEdited {
+    /// The solids whose meshes are out of date
+    pub solids: Vec<Uuid>,
+}
+
+/// Resource holding the model's explicit constraints
+#[derive(Resource, Default)]
+pub struct ConstraintSetResource {
+    /// The constraints added so far
+    pub constraints: ConstraintSet,
+}
+
+/// Resource holding the model's tiers and each tier's own constraints
+#[derive(Resource)]
+pub struct TierRegistryResource {
+    /// The tiers
+    pub registry: TierRegistry,
+    /// Each tier's own constraints, by tier ID
+    pub constraints: HashMap<Uuid, ConstraintSet>,
+}
+
+/// Resource holding the levels expressions can refer to
+#[derive(Resource)]
+pub struct LevelRegistryResource {
+    /// The levels
+    pub registry: LevelRegistry,
+}
+
+/// Resource holding the doors and windows hosted in walls
+#[derive(Resource)]
+pub struct FamilyRegistryResource {
+    /// The hosted families
+    pub registry: FamilyRegistry,
+}
+
+/// Resource holding the floor finishes and ceilings generated from spaces
+#[derive(Resource)]
+pub struct FinishRegistryResource {
+    /// The generated finishes
+    pub registry: FinishRegistry,
+}
+
+/// Resource holding the ducts, pipes and conduits routed through the model
+#[derive(Resource)]
+pub struct ServiceRegistryResource {
+    /// The service runs
+    pub registry: ServiceRegistry,
+}
+
+/// Resource holding the structural grids and the layouts generated from
+/// them
+#[derive(Resource)]
+pub struct GridRegistryResource {
+    /// The grids and layouts
+    pub registry: GridRegistry,
+}
+
+/// Resource holding the record of how generated geometry was made
+#[derive(Resource)]
+pub struct ProvenanceResource {
+    /// The recorded operations
+    pub graph: ProvenanceGraph,
+}
+
+/// Build the walls asked for and join them to the walls they meet
+///
+/// The constraints keeping each join closed are added to the model's.
+/// Walls modelled per layer are left unjoined, since their layers would
+/// each be joined to every layer of the other wall.
+pub fn create_walls(
+    mut events: EventReader<CreateWall>,
+    mut geometry_registry: ResMut<GeometryRegistryResource>,
+    mut element_registry: ResMut<ElementRegistryResource>,
+    mut constraint_set: ResMut<ConstraintSetResource>,
+    validation_state: Res<ValidationState>,
+    mut edited: EventWriter<SolidsEdited>,
+) {
+    let tolerance = &validation_state.pipeline.config.tolerance;
+    for event in events.read() {
+        let walls = match &event.assembly {
+            Some(assembly) => create_layered_wall(
+                &mut geometry_registry.registry,
+                &mut element_registry.registry,
+                &event.start,
+                &event.end,
+                assembly,
+                event.height,
+                event.per_layer,
+                tolerance,
+            ),
+            None => create_wall(
+                &mut geometry_registry.registry,
+                &mut element_registry.registry,
+                &event.start,
+                &event.end,
+                event.thickness,
+                event.height,
+                tolerance,
+            )
+            .map(|element| vec![element]),
+        };
+        let Some(walls) = walls else {
+            warn!("Could not create a wall: the line is too short or a dimension too small");
+            continue;
+        };
+        let mut solids = BTreeSet::new();
+        if walls.len() == 1 {
+            let joins = join_walls(
+                &mut geometry_registry.registry,
+                &element_registry.registry,
+                &walls,
+                tolerance,
+            );
+            for constraint in joins.constraints {
+                add_constraint(&mut constraint_set.constraints, constraint);
+            }
+            solids.extend(joins.solids);
+        }
+        solids.extend(
+            walls
+                .iter()
+                .filter_map(|wall| element_registry.registry.get(wall))
+                .map(|element| element.solid),
+        );
+        edited.write(SolidsEdited {
+            solids: solids.into_iter().collect(),
+        });
+    }
+}
+
+/// Place the doors and windows asked for in their walls
+pub fn place_families(
+    mut events: EventReader<PlaceFamily>,
+    mut geometry_registry: ResMut<GeometryRegistryResource>,
+    mut element_registry: ResMut<ElementRegistryResource>,
+    mut family_registry: ResMut<FamilyRegistryResource>,
+    validation_state: Res<ValidationState>,
+    mut edited: EventWriter<SolidsEdited>,
+) {
+    for event in events.read() {
+        match place_family(
+            &mut geometry_registry.registry,
+            &mut element_registry.registry,
+            &mut family_registry.registry,
+            &event.host,
+            event.offset,
+            &event.parameters,
+            &validation_state.pipeline.config.tolerance,
+        ) {
+            Ok(placed) => {
+                edited.write(SolidsEdited {
+                    solids: placed.solids,
+                });
+            }
+            Err(error) => warn!(
+                "Could not place a {}: {error}",
+                event.parameters.kind.label()
+            ),
+        }
+    }
+}
+
+/// Move hosted doors and windows back into place in their walls after the
+/// model changes
+pub fn keep_families_hosted(
+    mut geometry_registry: ResMut<GeometryRegistryResource>,
+    element_registry: Res<ElementRegistryResource>,
+    mut family_registry: ResMut<FamilyRegistryResource>,
+    validation_state: Res<ValidationState>,
+    mut edited: EventWriter<SolidsEdited>,
+) {
+    if !geometry_registry.is_changed() || family_registry.registry.families.is_empty() {
+        return;
+    }
+    let solids = update_hosted_families(
+        &mut geometry_registry.registry,
+        &element_registry.registry,
+        &mut family_registry.registry,
+        &validation_state.pipeline.config.tolerance,
+    );
+    if !solids.is_empty() {
+        edited.write(SolidsEdited { solids });
+    }
+}
+
+/// Generate the floor finishes and ceilings asked for
+pub fn generate_finishes(
+    mut events: EventReader<GenerateFinishes>,
+    mut geometry_registry: ResMut<GeometryRegistryResource>,
+    mut element_registry: ResMut<ElementRegistryResource>,
+    mut finish_registry: ResMut<FinishRegistryResource>,
+    validation_state: Res<ValidationState>,
+    mut edited: EventWriter<SolidsEdited>,
+) {
+    for event in events.read() {
+        match generate_space_finishes(
+            &mut geometry_registry.registry,
+            &mut element_registry.registry,
+            &mut finish_registry.registry,
+            &event.space,
+            &event.layer,
+            &validation_state.pipeline.config.tolerance,
+        ) {
+            Ok(made) => {
+                let solids = made
+                    .iter()
+                    .filter_map(|id| element_registry.registry.get(id))
+                    .map(|element| element.solid)
+                    .collect();
+                edited.write(SolidsEdited { solids });
+            }
+            Err(error) => warn!("Could not generate a {}: {error}", event.layer.kind.label()),
+        }
+    }
+}
+
+/// Rebuild floor finishes and ceilings whose spaces or walls changed after
+/// the model changes
+pub fn keep_finishes_bounded(
+    mut geometry_registry: ResMut<GeometryRegistryResource>,
+    mut element_registry: ResMut<ElementRegistryResource>,
+    mut finish_registry: ResMut<FinishRegistryResource>,
+    validation_state: Res<ValidationState>,
+    mut edited: EventWriter<SolidsEdited>,
+) {
+    if !geometry_registry.is_changed() || finish_registry.registry.finishes.is_empty() {
+        return;
+    }
+    // Marked changed only when a finish is rebuilt, so that the other
+    // systems watching the model do not run again every frame
+    let solids = update_space_finishes(
+        &mut geometry_registry.bypass_change_detection().registry,
+        &mut element_registry.bypass_change_detection().registry,
+        &mut finish_registry.registry,
+        &validation_state.pipeline.config.tolerance,
+    );
+    if !solids.is_empty() {
+        geometry_registry.set_changed();
+        element_registry.set_changed();
+        edited.write(SolidsEdited { solids });
+    }
+}
+
+/// Route the ducts, pipes and conduits asked for, warning of what each
+/// new run clashes with
+pub fn route_services(
+    mut events: EventReader<RouteService>,
+    mut geometry_registry: ResMut<GeometryRegistryResource>,
+    mut element_registry: ResMut<ElementRegistryResource>,
+    mut service_registry: ResMut<ServiceRegistryResource>,
+    validation_state: Res<ValidationState>,
+    mut edited: EventWriter<SolidsEdited>,
+) {
+    let tolerance = &validation_state.pipeline.config.tolerance;
+    for event in events.read() {
+        let routed = match route_service(
+            &mut geometry_registry.registry,
+            &mut element_registry.registry,
+            &mut service_registry.registry,
+            event.kind,
+            event.section,
+            event.path.clone(),
+            tolerance,
+        ) {
+            Ok(routed) => routed,
+            Err(error) => {
+                warn!("Could not route a {}: {error}", event.kind.label());
+                continue;
+            }
+        };
+        let elements = &element_registry.registry;
+        let new_elements = service_registry
+            .registry
+            .get(&routed.run)
+            .map(|run| run.elements.clone())
+            .unwrap_or_default();
+        for clash in service_clashes(
+            &geometry_registry.registry,
+            elements,
+            &service_registry.registry,
+            tolerance.linear,
+        )
+        .into_iter()
+        .filter(|clash| new_elements.contains(&clash.service))
+        {
+            let name = |id: &Uuid| {
+                elements
+                    .get(id)
+                    .map_or("?", |element| element.name.as_str())
+            };
+            warn!(
+                "{} clashes with {}",
+                name(&clash.service),
+                name(&clash.other)
+            );
+        }
+        edited.write(SolidsEdited {
+            solids: routed.solids,
+        });
+    }
+}
+
+/// Convert the massing solids asked for into walls, roofs and floors
+pub fn convert_masses(
+    mut events: EventReader<ConvertMass>,
+    mut geometry_registry: ResMut<GeometryRegistryResource>,
+    mut element_registry: ResMut<ElementRegistryResource>,
+    mut constraint_set: ResMut<ConstraintSetResource>,
+    validation_state: Res<ValidationState>,
+    mut edited: EventWriter<SolidsEdited>,
+) {
+    for event in events.read() {
+        match convert_mass(
+            &mut geometry_registry.registry,
+            &mut element_registry.registry,
+            &event.solid,
+            &event.tags,
+            &event.settings,
+            &validation_state.pipeline.config.tolerance,
+        ) {
+            Ok(converted) => {
+                info!(
+                    "Converted the mass into {} walls, {} roofs and {} floors",
+                    converted.walls.len(),
+                    converted.roofs.len(),
+                    converted.floors.len()
+                );
+                for constraint in converted.constraints {
+                    add_constraint(&mut constraint_set.constraints, constraint);
+                }
+                edited.write(SolidsEdited {
+                    solids: converted.solids,
+                });
+            }
+            Err(error) => warn!("Could not convert the mass: {error}"),
+        }
+    }
+}
+
+/// Add the grids asked for, then generate the layouts asked for, then
+/// carry out the grid edits asked for
+#[allow(clippy::too_many_arguments)]
+pub fn edit_grids(
+    mut created: EventReader<CreateGrid>,
+    mut layouts: EventReader<AddGridLayout>,
+    mut edits: EventReader<EditGrid>,
+    mut geometry_registry: ResMut<GeometryRegistryResource>,
+    mut element_registry: ResMut<ElementRegistryResource>,
+    mut grid_registry: ResMut<GridRegistryResource>,
+    validation_state: Res<ValidationState>,
+    mut edited: EventWriter<SolidsEdited>,
+) {
+    let tolerance = &validation_state.pipeline.config.tolerance;
+    for event in created.read() {
+        grid_registry.registry.store_grid(event.grid.clone());
+    }
+    for event in layouts.read() {
+        match add_grid_layout(
+            &mut geometry_registry.registry,
+            &mut element_registry.registry,
+            &mut grid_registry.registry,
+            &event.grid,
+            event.layout.clone(),
+            tolerance,
+        ) {
+            Ok((_, solids)) => {
+                edited.write(SolidsEdited { solids });
+            }
+            Err(error) => warn!("Could not generate the grid layout: {error}"),
+        }
+    }
+    for event in edits.read() {
+        match edit_grid(
+            &mut geometry_registry.registry,
+            &mut element_registry.registry,
+            &mut grid_registry.registry,
+            &event.grid,
+            &event.edit,
+            tolerance,
+        ) {
+            Ok(solids) => {
+                edited.write(SolidsEdited { solids });
+            }
+            Err(error) => warn!("Could not edit the grid: {error}"),
+        }
+    }
+}
+
+/// Run the extrusions and cuts asked for, recording how their solids were
+/// made
+pub fn run_operations(
+    mut extrusions: EventReader<ExtrudeFace>,
+    mut cuts: EventReader<CutSolid>,
+    mut geometry_registry: ResMut<GeometryRegistryResource>,
+    mut provenance: ResMut<ProvenanceResource>,
+    validation_state: Res<ValidationState>,
+    mut edited: EventWriter<SolidsEdited>,
+) {
+    let tolerance = &validation_state.pipeline.config.tolerance;
+    let operations = extrusions
+        .read()
+        .map(|event| (Operation::Extrude { depth: event.depth }, event.face))
+        .chain(cuts.read().map(|event| {
+            let operation = Operation::Cut {
+                plane: event.plane.clone(),
+                keep_front: event.keep_front,
+            };
+            (operation, event.solid)
+        }))
+        .collect::<Vec<_>>();
+    for (operation, input) in operations {
+        let label = operation.label().to_string();
+        match run_operation(
+            &mut geometry_registry.registry,
+            &mut provenance.graph,
+            operation,
+            vec![input],
+            tolerance,
+        ) {
+            Ok((_, solids)) => {
+                edited.write(SolidsEdited { solids });
+            }
+            Err(error) => warn!("Could not run the {label}: {error}"),
+        }
+    }
+}
+
+/// Carry out the changes to past operations asked for
+pub fn change_operations(
+    mut events: EventReader<EditOperation>,
+    mut geometry_registry: ResMut<GeometryRegistryResource>,
+    mut provenance: ResMut<ProvenanceResource>,
+    validation_state: Res<ValidationState>,
+    mut edited: EventWriter<SolidsEdited>,
+) {
+    let tolerance = &validation_state.pipeline.config.tolerance;
+    for event in events.read() {
+        let geometry = &mut geometry_registry.registry;
+        let graph = &mut provenance.graph;
+        let solids = match &event.change {
+            OperationChange::Suppress(suppressed) => {
+                suppress_operation(geometry, graph, &event.record, *suppressed, tolerance)
+                    .map(|update| update.solids)
+            }
+            OperationChange::Parameters(operation) => {
+                edit_operation(geometry, graph, &event.record, operation.clone(), tolerance)
+                    .map(|update| update.solids)
+            }
+            OperationChange::Move(place) => {
+                move_operation(graph, &event.record, *place).map(|()| Vec::new())
+            }
+            OperationChange::Delete => delete_operation(geometry, graph, &event.record),
+        };
+        match solids {
+            Ok(solids) => {
+                edited.write(SolidsEdited { solids });
+            }
+            Err(error) => warn!("Could not change the operation: {error}"),
+        }
+    }
+}
+
+/// Run recorded operations again when the geometry they were made from
+/// changes
+pub fn keep_operations_current(
+    mut geometry_registry: ResMut<GeometryRegistryResource>,
+    mut provenance: ResMut<ProvenanceResource>,
+    validation_state: Res<ValidationState>,
+    mut edited: EventWriter<SolidsEdited>,
+) {
+    if !geometry_registry.is_changed() || provenance.graph.records.is_empty() {
+        return;
+    }
+    // Marked changed only when an operation is run again, so that the
+    // other systems watching the model do not run again every frame
+    let update = update_operations(
+        &mut geometry_registry.bypass_change_detection().registry,
+        &mut provenance.graph,
+        &validation_state.pipeline.config.tolerance,
+    );
+    for generator in &update.stale_generators {
+        if let Some(record) = provenance.graph.get(generator) {
+            info!(
+                "{} is out of date with its inputs",
+                record.operation.label()
+            );
+        }
+    }
+    if !update.solids.is_empty() {
+        geometry_registry.set_changed();
+        edited.write(SolidsEdited {
+            solids: update.solids,
+        });
+    }
+}
+
+/// Draw the sketch paths asked for
+pub fn add_sketch_paths(
+    mut events: EventReader<AddSketchPath>,
+    mut geometry_registry: ResMut<GeometryRegistryResource>,
+    validation_state: Res<ValidationState>,
+) {
+    for event in events.read() {
+        let segments = create_sketch_path(
+            &mut geometry_registry.registry,
+            &event.points,
+            &validation_state.pipeline.config.tolerance,
+        );
+        if segments.is_empty() {
+            warn!("Could not sketch a path: it is shorter than the tolerance");
+        }
+    }
+}
+
+/// Move the vertices asked for
+pub fn move_vertices(
+    mut events: EventReader<MoveVertex>,
+    mut geometry_registry: ResMut<GeometryRegistryResource>,
+    mut edited: EventWriter<SolidsEdited>,
+) {
+    for event in events.read() {
+        if let Some(solids) = move_vertex(
+            &mut geometry_registry.registry,
+            &event.vertex,
+            &event.position,
+        ) {
+            edited.write(SolidsEdited { solids });
+        } else {
+            warn!("No vertex {} to move", event.vertex);
+        }
+    }
+}
+
+/// Apply the vertex transforms asked for
+pub fn apply_vertex_transforms(
+    mut events: EventReader<TransformVertices>,
+    mut geometry_registry: ResMut<GeometryRegistryResource>,
+    mut edited: EventWriter<SolidsEdited>,
+) {
+    for event in events.read() {
+        let solids =
+            transform_vertices(&mut geometry_registry.registry, &event.vertices, |point| {
+                let moved = event
+                    .transform
+                    .transform_point3(Vec3::new(point.x, point.y, point.z));
+                Point {
+                    x: moved.x,
+                    y: moved.y,
+                    z: moved.z,
+                }
+            });
+        edited.write(SolidsEdited { solids });
+    }
+}
+
+/// Remove the constraints asked to be relaxed, then add the constraints
+/// asked for, skipping duplicates
+pub fn add_constraints(
+    mut removals: EventReader<RemoveConstraint>,
+    mut events: EventReader<AddConstraint>,
+    mut constraints: ResMut<ConstraintSetResource>,
+) {
+    for event in removals.read() {
+        if !remove_constraint(&mut constraints.constraints, &event.constraint) {
+            info!("No {:?} constraint to remove", event.constraint.kind);
+        }
+    }
+    for event in events.read() {
+        if !add_constraint(&mut constraints.constraints, event.constraint.clone()) {
+            info!("Skipped a duplicate {:?} constraint", event.constraint.kind);
+        }
+    }
+}
+
+/// Set the levels asked for, then add the expression constraints asked
+/// for, which are solved once the model next changes
+pub fn add_expression_constraints(
+    mut levels: EventReader<SetLevel>,
+    mut expressions: EventReader<AddExpressionConstraint>,
+    mut level_registry: ResMut<LevelRegistryResource>,
+    mut constraints: ResMut<ConstraintSetResource>,
+) {
+    for event in levels.read() {
+        let registry = &mut level_registry.registry;
+        let existing = registry
+            .sorted()
+            .into_iter()
+            .find(|level| level.name == event.name)
+            .map(|level| level.id);
+        match existing.and_then(|id| registry.get_mut(&id)) {
+            Some(level) => level.elevation = event.elevation,
+            None => {
+                registry.create_and_store(&event.name, event.elevation);
+            }
+        }
+    }
+    for event in expressions.read() {
+        let constraint = match ExpressionConstraint::parse(&event.text) {
+            Ok(constraint) => constraint,
+            Err(error) => {
+                warn!("Could not read the constraint {}: {error}", event.text);
+                continue;
+            }
+        };
+        let mut proposed = constraints.constraints.expressions.clone();
+        proposed.push(constraint.clone());
+        match order_expression_constraints(&proposed) {
+            Ok(_) => constraints.constraints.expressions.push(constraint),
+            Err(error) => warn!("Skipped the constraint {}: {error}", event.text),
+        }
+    }
+}
+
+/// Solve the expression constraints again after the model, the levels or
+/// the constraints change
+pub fn keep_expressions_satisfied(
+    mut geometry_registry: ResMut<GeometryRegistryResource>,
+    element_registry: Res<ElementRegistryResource>,
+    mut family_registry: ResMut<FamilyRegistryResource>,
+    mut level_registry: ResMut<LevelRegistryResource>,
+    constraints: Res<ConstraintSetResource>,
+    validation_state: Res<ValidationState>,
+    mut edited: EventWriter<SolidsEdited>,
+) {
+    let changed =
+        geometry_registry.is_changed() || level_registry.is_changed() || constraints.is_changed();
+    if !changed || constraints.constraints.expressions.is_empty() {
+        return;
+    }
+    // Marked changed only when a property is set, so that solving a
+    // satisfied model does not run it again every frame
+    let mut model = ExpressionModel {
+        geometry: &mut geometry_registry.bypass_change_detection().registry,
+        elements: &element_registry.registry,
+        families: &mut family_registry.bypass_change_detection().registry,
+        levels: &mut level_registry.bypass_change_detection().registry,
+    };
+    let update = match solve_expressions(
+        &mut model,
+        &constraints.constraints.expressions,
+        &validation_state.pipeline.config.tolerance,
+    ) {
+        Ok(update) => update,
+        Err(error) => {
+            warn!("Could not solve the expression constraints: {error}");
+            return;
+        }
+    };
+    for (_, error) in &update.errors {
+        warn!("Could not satisfy an expression constraint: {error}");
+    }
+    if update.set.is_empty() {
+        return;
+    }
+    level_registry.set_changed();
+    family_registry.set_changed();
+    if !update.solids.is_empty() {
+        geometry_registry.set_changed();
+        edited.write(SolidsEdited {
+            solids: update.solids,
+        });
+    }
+}
+
+/// Set the face materials asked for on the solids' elements
+pub fn set_face_materials(
+    mut events: EventReader<SetFaceMaterial>,
+    geometry_registry: Res<GeometryRegistryResource>,
+    mut element_registry: ResMut<ElementRegistryResource>,
+) {
+    for event in events.read() {
+        let on_solid = geometry_registry
+            .registry
+            .solids
+            .get(&event.solid)
+            .is_some_and(|solid| solid.polygons.contains(&event.polygon));
+        if !on_solid {
+            warn!("Solid {} has no face {}", event.solid, event.polygon);
+            continue;
+        }
+        let Some(element) = element_registry.registry.element_of_solid_mut(&event.solid) else {
+            warn!(
+                "Solid {} is not an element, so its faces have no material",
+                event.solid
+            );
+            continue;
+        };
+        match &event.material {
+            Some(material) => {
+                element
+                    .face_materials
+                    .insert(event.polygon, material.clone());
+            }
+            None => {
+                element.face_materials.remove(&event.polygon);
+            }
+        }
+    }
+}
+
+/// Write the STL files asked for
+pub fn export_stl_files(
+    mut events: EventReader<ExportStl>,
+    geometry_registry: Res<GeometryRegistryResource>,
+    element_registry: Res<ElementRegistryResource>,
+) {
+    for event in events.read() {
+        let filter = ExportFilter::from(event.phases);
+        match write_stl(
+            &event.path,
+            &geometry_registry.registry,
+            &element_registry.registry,
+            &filter,
+        ) {
+            Ok(()) => info!("Exported {}", event.path.display()),
+            Err(error) => error!("Could not export {}: {error}", event.path.display()),
+        }
+    }
+}
+
+/// Rebuild the meshes of edited solids, spawning entities for new ones
+/// and despawning those of solids that were removed
+///
+/// New solids of an element kind with a standard color are shown in it.
+#[allow(clippy::too_many_arguments)]
+pub fn refresh_edited_meshes(
+    mut commands: Commands,
+    mut events: EventReader<SolidsEdited>,
+    geometry_registry: Res<GeometryRegistryResource>,
+    element_registry: Res<ElementRegistryResource>,
+    standards: Res<OfficeStandardsResource>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut solid_query: Query<(Entity, &SolidId, &mut Mesh3d)>,
+) {
+    let registry = &geometry_registry.registry;
+    let edited: BTreeSet<Uuid> = events
+        .read()
+        .flat_map(|event| event.solids.iter().copied())
+        .collect();
+    for solid_id in &edited {
+        let Some(solid) = registry.solids.get(solid_id) else {
+            for (entity, id, _) in &solid_query {
+                if id.0 == *solid_id {
+                    commands.entity(entity).despawn();
+                }
+            }
+            continue;
+        };
+        let mesh = meshes.add(create_mesh_from_solid(solid, registry));
+        if let Some((_, _, mut existing)) =
+            solid_query.iter_mut().find(|(_, id, _)| id.0 == *solid_id)
+        {
+            existing.0 = mesh;
+            continue;
+        }
+        let [r, g, b] = element_registry
+            .registry
+            .element_of_solid(solid_id)
+            .and_then(|element| standards.standards.color_for(element.kind))
+            .unwrap_or([0.75, 0.75, 0.75]);
+        commands.spawn((
+            Mesh3d(mesh),
+            MeshMaterial3d(materials.add(StandardMaterial {
+                base_color: Color::srgb(r, g, b),
+                perceptual_roughness: 0.6,
+                ..Default::default()
+            })),
+            Transform::default(),
+            ToggleableMesh,
+            SolidId(*solid_id),
+        ));
+    }
+}
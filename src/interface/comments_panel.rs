@@ -0,0 +1,454 @@
+use bevy::input::keyboard::KeyboardInput;
+use bevy::prelude::*;
+use bevy::render::camera::ScalingMode;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+use crate::application::selection::{Selection, SelectionType};
+use crate::domain::{
+    new_comment, CommentRegistry, CommentStatus, ElementRegistry, Point, Vector, ViewProjection,
+    Viewpoint,
+};
+use crate::infrastructure::bcf::{read_bcf, write_bcf};
+use crate::infrastructure::current_timestamp;
+use crate::interface::camera::MainCamera;
+use crate::interface::file_menu::ProjectState;
+use crate::interface::prompt::{edit_buffer, PromptAction};
+use crate::interface::render_export::export_path;
+use crate::interface::segment_outlines::ElementRegistryResource;
+use crate::interface::selection::SelectionState;
+use crate::interface::settings::PreferencesResource;
+use crate::interface::theme::UiTheme;
+use crate::interface::ui::UiState;
+use crate::interface::ViewColumn;
+
+/// Most comments listed in the panel, newest first
+const LISTED_COMMENTS: usize = 8;
+
+/// Resource holding the coordination comments, saved with the project
+#[derive(Resource)]
+pub struct CommentRegistryResource {
+    pub registry: CommentRegistry,
+}
+
+/// What the prompt is asking for
+#[derive(Clone, Copy, PartialEq)]
+pub enum CommentPrompt {
+    /// The text of a new comment
+    Comment,
+    /// The path of a BCF archive to import
+    Import,
+}
+
+/// Resource tracking the comment being typed and the one opened
+#[derive(Resource, Default)]
+pub struct CommentsState {
+    pub prompt: Option<CommentPrompt>,
+    pub entry: String,
+    /// The comment last opened or raised, which the status button changes
+    pub current: Option<Uuid>,
+    pub message: String,
+}
+
+/// What a comments panel button does
+#[derive(Component, Clone, Copy)]
+pub enum CommentButton {
+    New,
+    Status,
+    Import,
+    Export,
+}
+
+/// A listed comment, opened by pressing it
+#[derive(Component)]
+pub struct CommentListButton(pub Uuid);
+
+/// Marker component for the list of comments
+#[derive(Component)]
+pub struct CommentList;
+
+/// Marker component for the comments panel text
+#[derive(Component)]
+pub struct CommentsText;
+
+/// Setup the comments panel in the view column
+pub fn setup_comments_panel(
+    mut commands: Commands,
+    column_query: Query<Entity, With<ViewColumn>>,
+    theme: Res<UiTheme>,
+) {
+    let Ok(column) = column_query.single() else {
+        return;
+    };
+    commands.entity(column).with_children(|parent| {
+        parent
+            .spawn((
+                Node {
+                    flex_direction: FlexDirection::Column,
+                    padding: UiRect::all(Val::Px(theme.panel_padding)),
+                    row_gap: Val::Px(5.0),
+                    ..default()
+                },
+                BackgroundColor(theme.panel),
+            ))
+            .with_children(|parent| {
+                parent
+                    .spawn(Node {
+                        flex_direction: FlexDirection::Row,
+                        ..default()
+                    })
+                    .with_children(|parent| {
+                        for (button, label) in [
+                            (CommentButton::New, "Comment"),
+                            (CommentButton::Status, "Status"),
+                            (CommentButton::Import, "Import BCF"),
+                            (CommentButton::Export, "Export BCF"),
+                        ] {
+                            parent
+                                .spawn((
+                                    Button,
+                                    button,
+                                    Node {
+                                        padding: UiRect::all(Val::Px(theme.button_padding)),
+                                        margin: UiRect::right(Val::Px(3.0)),
+                                        ..default()
+                                    },
+                                    BackgroundColor(theme.button),
+                                ))
+                                .with_children(|parent| {
+                                    parent.spawn(Text::new(label));
+                                });
+                        }
+                    });
+
+                parent.spawn((
+                    Text::new(""),
+                    TextFont {
+                        font_size: theme.small_font_size,
+                        ..default()
+                    },
+                    CommentsText,
+                ));
+                parent.spawn((
+                    Node {
+                        flex_direction: FlexDirection::Column,
+                        ..default()
+                    },
+                    CommentList,
+                ));
+            });
+    });
+}
+
+/// Handle the comments panel buttons
+pub fn handle_comment_buttons(
+    button_query: Query<(&Interaction, &CommentButton), Changed<Interaction>>,
+    project: Res<ProjectState>,
+    mut state: ResMut<CommentsState>,
+    mut comment_registry: ResMut<CommentRegistryResource>,
+) {
+    for (interaction, button) in &button_query {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        match button {
+            CommentButton::New => {
+                state.prompt = Some(CommentPrompt::Comment);
+                state.entry.clear();
+                state.message.clear();
+            }
+            CommentButton::Import => {
+                state.prompt = Some(CommentPrompt::Import);
+                state.entry.clear();
+                state.message.clear();
+            }
+            CommentButton::Status => {
+                let Some(comment) = state
+                    .current
+                    .and_then(|id| comment_registry.registry.get_mut(&id))
+                else {
+                    state.message = "Open a comment first".to_string();
+                    continue;
+                };
+                let index = CommentStatus::ALL
+                    .iter()
+                    .position(|status| *status == comment.status)
+                    .unwrap_or(0);
+                comment.status = CommentStatus::ALL[(index + 1) % CommentStatus::ALL.len()];
+            }
+            CommentButton::Export => {
+                let path = export_path(&project, "issues", "bcfzip");
+                state.message = match write_bcf(&path, &comment_registry.registry) {
+                    Ok(()) => format!("Exported {}", path.display()),
+                    Err(error) => format!("Could not export BCF: {error}"),
+                };
+            }
+        }
+    }
+}
+
+/// Type a new comment or the path of a BCF archive; Enter raises the
+/// comment on the selected element from the current view, or imports the
+/// archive, and Escape cancels
+#[allow(clippy::too_many_arguments)]
+pub fn handle_comment_prompt(
+    mut keyboard_events: EventReader<KeyboardInput>,
+    camera_query: Query<(&Transform, &Projection), With<MainCamera>>,
+    ui_state: Res<UiState>,
+    selection: Res<SelectionState>,
+    element_registry: Res<ElementRegistryResource>,
+    preferences: Res<PreferencesResource>,
+    mut state: ResMut<CommentsState>,
+    mut comment_registry: ResMut<CommentRegistryResource>,
+) {
+    let Some(prompt) = state.prompt else {
+        keyboard_events.clear();
+        return;
+    };
+    for event in keyboard_events.read() {
+        match edit_buffer(&mut state.entry, event) {
+            PromptAction::Continue => {}
+            PromptAction::Cancel => state.prompt = None,
+            PromptAction::Submit => {
+                state.prompt = None;
+                if prompt == CommentPrompt::Import {
+                    let path = PathBuf::from(state.entry.trim());
+                    state.message = import_comments(
+                        &path,
+                        &element_registry.registry,
+                        &mut comment_registry.registry,
+                    );
+                    return;
+                }
+                let Ok((transform, projection)) = camera_query.single() else {
+                    return;
+                };
+                let element = selection
+                    .solid
+                    .and_then(|solid| element_registry.registry.element_of_solid(&solid))
+                    .map(|element| element.id);
+                let comment = new_comment(
+                    state.entry.trim(),
+                    &preferences.preferences.author,
+                    &current_timestamp(),
+                    element,
+                    camera_viewpoint(transform, projection, &ui_state),
+                );
+                state.current = Some(comment_registry.registry.store(comment));
+                state.message = "Comment raised".to_string();
+                return;
+            }
+        }
+    }
+}
+
+/// Import the topics of a BCF archive as comments, returning the message
+/// to show
+///
+/// Topics already imported are updated. A topic about an element that is
+/// not in the model is kept without its element.
+fn import_comments(
+    path: &Path,
+    element_registry: &ElementRegistry,
+    comment_registry: &mut CommentRegistry,
+) -> String {
+    let comments = match read_bcf(path) {
+        Ok(comments) => comments,
+        Err(error) => return error.to_string(),
+    };
+    let count = comments.len();
+    let mut unmatched = 0;
+    for mut comment in comments {
+        if comment
+            .element
+            .is_some_and(|element| element_registry.get(&element).is_none())
+        {
+            comment.element = None;
+            unmatched += 1;
+        }
+        comment_registry.store(comment);
+    }
+    if unmatched == 0 {
+        format!("Imported {count} topic(s)")
+    } else {
+        format!("Imported {count} topic(s); {unmatched} about elements not in the model")
+    }
+}
+
+/// The viewpoint the camera shows
+fn camera_viewpoint(
+    transform: &Transform,
+    projection: &Projection,
+    ui_state: &UiState,
+) -> Viewpoint {
+    let (position, direction, up) = (
+        transform.translation,
+        transform.forward().as_vec3(),
+        transform.up().as_vec3(),
+    );
+    Viewpoint {
+        position: Point {
+            x: position.x,
+            y: position.y,
+            z: position.z,
+        },
+        direction: Vector {
+            x: direction.x,
+            y: direction.y,
+            z: direction.z,
+        },
+        up: Vector {
+            x: up.x,
+            y: up.y,
+            z: up.z,
+        },
+        projection: match projection {
+            Projection::Perspective(perspective) => ViewProjection::Perspective {
+                field_of_view: perspective.fov.to_degrees(),
+            },
+            _ => ViewProjection::Orthographic {
+                view_height: ui_state.ortho_zoom,
+            },
+        },
+    }
+}
+
+/// Open a listed comment: restore the view it was raised from and select
+/// the element it is about
+pub fn open_comments(
+    list_query: Query<(&Interaction, &CommentListButton), Changed<Interaction>>,
+    mut camera_query: Query<(&mut Transform, &mut Projection), With<MainCamera>>,
+    comment_registry: Res<CommentRegistryResource>,
+    element_registry: Res<ElementRegistryResource>,
+    mut ui_state: ResMut<UiState>,
+    mut selection: ResMut<SelectionState>,
+    mut state: ResMut<CommentsState>,
+) {
+    for (interaction, button) in &list_query {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        let Some(comment) = comment_registry.registry.get(&button.0) else {
+            continue;
+        };
+        state.current = Some(comment.id);
+        let Ok((mut transform, mut projection)) = camera_query.single_mut() else {
+            continue;
+        };
+        let view = &comment.viewpoint;
+        transform.translation = Vec3::new(view.position.x, view.position.y, view.position.z);
+        transform.look_to(
+            Vec3::new(view.direction.x, view.direction.y, view.direction.z),
+            Vec3::new(view.up.x, view.up.y, view.up.z),
+        );
+        match view.projection {
+            ViewProjection::Perspective { field_of_view } => {
+                ui_state.isometric_view = false;
+                *projection = Projection::Perspective(PerspectiveProjection {
+                    fov: field_of_view.to_radians(),
+                    ..default()
+                });
+            }
+            ViewProjection::Orthographic { view_height } => {
+                // Switching the projection here keeps the view height,
+                // which toggling to orthographic would work out afresh
+                ui_state.isometric_view = true;
+                ui_state.ortho_zoom = view_height;
+                *projection = Projection::Orthographic(OrthographicProjection {
+                    near: 0.1,
+                    far: 100.0,
+                    scaling_mode: ScalingMode::FixedVertical {
+                        viewport_height: view_height,
+                    },
+                    ..OrthographicProjection::default_3d()
+                });
+            }
+        }
+        match comment
+            .element
+            .and_then(|element| element_registry.registry.get(&element))
+        {
+            Some(element) => selection.set_candidates(vec![(
+                Selection {
+                    id: element.solid,
+                    selection_type: SelectionType::Solid,
+                },
+                element.solid,
+            )]),
+            None => selection.clear(),
+        }
+    }
+}
+
+/// Refresh the status text and the list of comments
+pub fn update_comments_panel(
+    mut commands: Commands,
+    state: Res<CommentsState>,
+    comment_registry: Res<CommentRegistryResource>,
+    mut status_query: Query<&mut Text, With<CommentsText>>,
+    list_query: Query<Entity, With<CommentList>>,
+    theme: Res<UiTheme>,
+) {
+    if !state.is_changed() && !comment_registry.is_changed() {
+        return;
+    }
+    let comments = comment_registry.registry.by_time();
+    let open = comments
+        .iter()
+        .filter(|comment| {
+            matches!(
+                comment.status,
+                CommentStatus::Open | CommentStatus::InProgress
+            )
+        })
+        .count();
+    let mut lines = vec![format!("Comments: {} ({open} open)", comments.len())];
+    match state.prompt {
+        Some(CommentPrompt::Comment) => lines.push(format!("Comment: {}_", state.entry)),
+        Some(CommentPrompt::Import) => lines.push(format!("BCF file: {}_", state.entry)),
+        None => {}
+    }
+    if !state.message.is_empty() {
+        lines.push(state.message.clone());
+    }
+    let status = lines.join("\n");
+    for mut text in &mut status_query {
+        text.0.clone_from(&status);
+    }
+
+    for list in &list_query {
+        commands
+            .entity(list)
+            .despawn_related::<Children>()
+            .with_children(|parent| {
+                for comment in comments.iter().rev().take(LISTED_COMMENTS) {
+                    let label = format!(
+                        "[{}] {} ({})",
+                        comment.status.label(),
+                        comment.title(),
+                        comment.author
+                    );
+                    parent
+                        .spawn((
+                            Button,
+                            CommentListButton(comment.id),
+                            Node {
+                                padding: UiRect::all(Val::Px(3.0)),
+                                margin: UiRect::top(Val::Px(2.0)),
+                                ..default()
+                            },
+                            BackgroundColor(theme.button_color(state.current == Some(comment.id))),
+                        ))
+                        .with_children(|parent| {
+                            parent.spawn((
+                                Text::new(label),
+                                TextFont {
+                                    font_size: theme.small_font_size,
+                                    ..default()
+                                },
+                            ));
+                        });
+                }
+            });
+    }
+}
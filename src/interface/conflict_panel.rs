@@ -0,0 +1,282 @@
+use bevy::prelude::*;
+
+use crate::domain::solver::{find_conflict, ConstraintConflict, TierContext};
+use crate::interface::command_bus::{ConstraintSetResource, RemoveConstraint};
+use crate::interface::issues_panel::ValidationState;
+use crate::interface::segment_outlines::GeometryRegistryResource;
+use crate::interface::theme::UiTheme;
+use crate::interface::ViewColumn;
+
+/// Color of the edges as they would be with the picked constraint relaxed
+const PREVIEW_COLOR: Color = Color::srgb(1.0, 0.6, 0.1);
+
+/// Resource holding the latest constraint conflict and the constraint
+/// picked to relax
+#[derive(Resource, Default)]
+pub struct ConflictState {
+    pub conflict: Option<ConstraintConflict>,
+    /// Index into the conflict's relaxations of the one previewed
+    pub selected: usize,
+}
+
+/// What a conflict panel button does
+#[derive(Component, Clone, Copy)]
+pub enum ConflictButton {
+    Relax,
+    Dismiss,
+}
+
+/// A listed constraint, picked by pressing it
+#[derive(Component)]
+pub struct ConflictRowButton(pub usize);
+
+/// Marker component for the conflict panel, shown while there is a conflict
+#[derive(Component)]
+pub struct ConflictPanel;
+
+/// Marker component for the list of conflicting constraints
+#[derive(Component)]
+pub struct ConflictList;
+
+/// Marker component for the conflict panel text
+#[derive(Component)]
+pub struct ConflictText;
+
+/// Setup the conflict panel in the view column, hidden until constraints
+/// conflict
+pub fn setup_conflict_panel(
+    mut commands: Commands,
+    column_query: Query<Entity, With<ViewColumn>>,
+    theme: Res<UiTheme>,
+) {
+    let Ok(column) = column_query.single() else {
+        return;
+    };
+    commands.entity(column).with_children(|parent| {
+        parent
+            .spawn((
+                Node {
+                    flex_direction: FlexDirection::Column,
+                    padding: UiRect::all(Val::Px(theme.panel_padding)),
+                    row_gap: Val::Px(5.0),
+                    display: Display::None,
+                    ..default()
+                },
+                BackgroundColor(theme.panel),
+                ConflictPanel,
+            ))
+            .with_children(|parent| {
+                parent.spawn((
+                    Text::new(""),
+                    TextFont {
+                        font_size: theme.small_font_size,
+                        ..default()
+                    },
+                    ConflictText,
+                ));
+                parent.spawn((
+                    Node {
+                        flex_direction: FlexDirection::Column,
+                        ..default()
+                    },
+                    ConflictList,
+                ));
+                parent
+                    .spawn(Node {
+                        flex_direction: FlexDirection::Row,
+                        ..default()
+                    })
+                    .with_children(|parent| {
+                        for (button, label) in [
+                            (ConflictButton::Relax, "Relax picked"),
+                            (ConflictButton::Dismiss, "Dismiss"),
+                        ] {
+                            parent
+                                .spawn((
+                                    Button,
+                                    button,
+                                    Node {
+                                        padding: UiRect::all(Val::Px(theme.button_padding)),
+                                        margin: UiRect::right(Val::Px(3.0)),
+                                        ..default()
+                                    },
+                                    BackgroundColor(theme.button),
+                                ))
+                                .with_children(|parent| {
+                                    parent.spawn(Text::new(label));
+                                });
+                        }
+                    });
+            });
+    });
+}
+
+/// Look for a conflict among the explicit constraints whenever they change
+///
+/// Each trial solve is undone, so the geometry is not marked changed.
+pub fn find_constraint_conflicts(
+    mut geometry_registry: ResMut<GeometryRegistryResource>,
+    constraints: Res<ConstraintSetResource>,
+    validation_state: Res<ValidationState>,
+    mut state: ResMut<ConflictState>,
+) {
+    if !constraints.is_changed() {
+        return;
+    }
+    let context = TierContext::new(
+        constraints.constraints.clone(),
+        validation_state.pipeline.config.tolerance,
+        None,
+        None,
+    );
+    let conflict = find_conflict(
+        &mut geometry_registry.bypass_change_detection().registry,
+        &context,
+    );
+    if let Some(conflict) = &conflict {
+        warn!("{}", conflict.error());
+    }
+    if conflict.is_some() || state.conflict.is_some() {
+        state.conflict = conflict;
+        state.selected = 0;
+    }
+}
+
+/// Pick the constraint pressed in the list, relax the picked constraint or
+/// dismiss the conflict
+pub fn handle_conflict_buttons(
+    row_query: Query<(&Interaction, &ConflictRowButton), Changed<Interaction>>,
+    button_query: Query<(&Interaction, &ConflictButton), Changed<Interaction>>,
+    mut state: ResMut<ConflictState>,
+    mut removals: EventWriter<RemoveConstraint>,
+) {
+    for (interaction, row) in &row_query {
+        if *interaction == Interaction::Pressed {
+            state.selected = row.0;
+        }
+    }
+    for (interaction, button) in &button_query {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        if let ConflictButton::Relax = button {
+            let relaxation = state
+                .conflict
+                .as_ref()
+                .and_then(|conflict| conflict.relaxations.get(state.selected));
+            if let Some(relaxation) = relaxation {
+                removals.write(RemoveConstraint {
+                    constraint: relaxation.constraint.clone(),
+                });
+            }
+        }
+        state.conflict = None;
+    }
+}
+
+/// Show the panel while there is a conflict, listing each constraint in
+/// it with the effect of relaxing it
+pub fn update_conflict_panel(
+    mut commands: Commands,
+    state: Res<ConflictState>,
+    mut panel_query: Query<&mut Node, With<ConflictPanel>>,
+    mut text_query: Query<&mut Text, With<ConflictText>>,
+    list_query: Query<Entity, With<ConflictList>>,
+    theme: Res<UiTheme>,
+) {
+    if !state.is_changed() {
+        return;
+    }
+    for mut node in &mut panel_query {
+        node.display = if state.conflict.is_some() {
+            Display::Flex
+        } else {
+            Display::None
+        };
+    }
+    let Some(conflict) = &state.conflict else {
+        return;
+    };
+    let status = format!(
+        "Constraint conflict: these {} cannot all be satisfied.\n\
+         Pick one to preview relaxing it.",
+        conflict.relaxations.len()
+    );
+    for mut text in &mut text_query {
+        text.0.clone_from(&status);
+    }
+    for list in &list_query {
+        commands
+            .entity(list)
+            .despawn_related::<Children>()
+            .with_children(|parent| {
+                for (index, relaxation) in conflict.relaxations.iter().enumerate() {
+                    let effect = match (relaxation.resolves, relaxation.moves.len()) {
+                        (false, _) => "others still conflict".to_string(),
+                        (true, 0) => "resolves it, moving nothing".to_string(),
+                        (true, moved) => format!(
+                            "resolves it, moving {moved} vertices up to {:.3} m",
+                            relaxation.largest_move
+                        ),
+                    };
+                    parent
+                        .spawn((
+                            Button,
+                            ConflictRowButton(index),
+                            Node {
+                                padding: UiRect::all(Val::Px(3.0)),
+                                margin: UiRect::top(Val::Px(2.0)),
+                                ..default()
+                            },
+                            BackgroundColor(theme.button_color(state.selected == index)),
+                        ))
+                        .with_children(|parent| {
+                            parent.spawn((
+                                Text::new(format!("{}: {effect}", relaxation.constraint.summary())),
+                                TextFont {
+                                    font_size: theme.small_font_size,
+                                    ..default()
+                                },
+                            ));
+                        });
+                }
+            });
+    }
+}
+
+/// Draw the edges the picked relaxation moves where solving would put them
+pub fn draw_conflict_preview(
+    mut gizmos: Gizmos,
+    state: Res<ConflictState>,
+    geometry_registry: Res<GeometryRegistryResource>,
+) {
+    let Some(relaxation) = state
+        .conflict
+        .as_ref()
+        .and_then(|conflict| conflict.relaxations.get(state.selected))
+    else {
+        return;
+    };
+    let registry = &geometry_registry.registry;
+    let point = |vertex_id| {
+        relaxation
+            .moves
+            .get(vertex_id)
+            .or_else(|| {
+                registry
+                    .vertices
+                    .get(vertex_id)
+                    .map(|vertex| &vertex.position)
+            })
+            .map(|position| Vec3::new(position.x, position.y, position.z))
+    };
+    for segment in registry.segments.segments.values() {
+        let [start, end] = &segment.vertices;
+        if !relaxation.moves.contains_key(start) && !relaxation.moves.contains_key(end) {
+            continue;
+        }
+        if let (Some(start), Some(end)) = (point(start), point(end)) {
+            gizmos.line(start, end, PREVIEW_COLOR);
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use bevy::prelude::*;
+use uuid::Uuid;
+
+use crate::application::curtain_wall::{create_curtain_grid, regenerate_curtain_grids};
+use crate::application::selection::SelectionType;
+use crate::domain::{CurtainGridRegistry, CurtainGridSettings};
+use crate::interface::asset_browser::ComponentLibraryResource;
+use crate::interface::command_bus::SolidsEdited;
+use crate::interface::issues_panel::ValidationState;
+use crate::interface::segment_outlines::GeometryRegistryResource;
+use crate::interface::selection::SelectionState;
+
+/// Key dividing the selected face into a curtain wall
+const CURTAIN_GRID_KEY: KeyCode = KeyCode::KeyU;
+
+/// Command to divide a vertical face into a curtain wall
+#[derive(Event, Clone)]
+pub struct CreateCurtainGrid {
+    /// The face to divide
+    pub face: Uuid,
+    /// How to divide it
+    pub settings: CurtainGridSettings,
+}
+
+/// Resource holding the faces divided into curtain walls
+#[derive(Resource)]
+pub struct CurtainGridResource {
+    /// The curtain grids
+    pub registry: CurtainGridRegistry,
+}
+
+/// Divide the selected face into a curtain wall with the default grid when
+/// its key is pressed
+pub fn send_curtain_grid_shortcut(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    selection_state: Res<SelectionState>,
+    mut commands: EventWriter<CreateCurtainGrid>,
+) {
+    if !keyboard_input.just_pressed(CURTAIN_GRID_KEY) {
+        return;
+    }
+    match &selection_state.selected {
+        Some(selection) if selection.selection_type == SelectionType::Polygon => {
+            commands.write(CreateCurtainGrid {
+                face: selection.id,
+                settings: CurtainGridSettings::default(),
+            });
+        }
+        _ => info!("Select a face to divide it into a curtain wall"),
+    }
+}
+
+/// Divide the faces asked for into curtain walls
+pub fn create_curtain_grids(
+    mut events: EventReader<CreateCurtainGrid>,
+    mut geometry_registry: ResMut<GeometryRegistryResource>,
+    mut component_library: ResMut<ComponentLibraryResource>,
+    mut curtain_grids: ResMut<CurtainGridResource>,
+    validation_state: Res<ValidationState>,
+    mut edited: EventWriter<SolidsEdited>,
+) {
+    for event in events.read() {
+        match create_curtain_grid(
+            &mut geometry_registry.registry,
+            &mut component_library.library,
+            &mut curtain_grids.registry,
+            &event.face,
+            event.settings.clone(),
+            &validation_state.pipeline.config.tolerance,
+        ) {
+            Ok(grid) => {
+                let solids = curtain_grids
+                    .registry
+                    .get(&grid)
+                    .map(|grid| grid.solids.clone())
+                    .unwrap_or_default();
+                info!("Divided the face into {} curtain wall pieces", solids.len());
+                edited.write(SolidsEdited { solids });
+            }
+            Err(error) => warn!("Could not divide the face: {error}"),
+        }
+    }
+}
+
+/// Rebuild the curtain walls whose faces changed after the model changes
+pub fn regenerate_changed_curtain_grids(
+    mut geometry_registry: ResMut<GeometryRegistryResource>,
+    mut component_library: ResMut<ComponentLibraryResource>,
+    mut curtain_grids: ResMut<CurtainGridResource>,
+    validation_state: Res<ValidationState>,
+    mut edited: EventWriter<SolidsEdited>,
+) {
+    if !geometry_registry.is_changed() || curtain_grids.registry.grids.is_empty() {
+        return;
+    }
+    // Marked changed only when a grid is rebuilt, so that the other
+    // systems watching the geometry do not run again every frame
+    let solids = regenerate_curtain_grids(
+        &mut geometry_registry.bypass_change_detection().registry,
+        &mut component_library.bypass_change_detection().library,
+        &mut curtain_grids.registry,
+        &validation_state.pipeline.config.tolerance,
+    );
+    if !solids.is_empty() {
+        geometry_registry.set_changed();
+        component_library.set_changed();
+        edited.write(SolidsEdited { solids });
+    }
+}
@@ -0,0 +1,225 @@
+use bevy::prelude::*;
+
+use crate::domain::{check_daylight, DaylightCheck, DaylightSettings};
+use crate::interface::segment_outlines::{
+    ElementRegistryResource, GeometryRegistryResource, SolidId,
+};
+use crate::interface::theme::UiTheme;
+use crate::interface::AnalysisColumn;
+
+/// Change in the minimum ratio per button press
+const RATIO_STEP: f32 = 0.01;
+
+/// Resource holding the daylight settings and latest check
+#[derive(Resource, Default)]
+pub struct DaylightState {
+    pub settings: DaylightSettings,
+    pub checks: Vec<DaylightCheck>,
+    /// Whether the check runs and failing rooms are tinted
+    pub enabled: bool,
+}
+
+/// What a daylight panel button does
+#[derive(Component, Clone, Copy)]
+pub enum DaylightButton {
+    Toggle,
+    Ratio(bool),
+}
+
+/// Marker component for the daylight results text
+#[derive(Component)]
+pub struct DaylightText;
+
+/// The material a tinted room had before it failed the check
+#[derive(Component)]
+pub struct DaylightTint(pub Handle<StandardMaterial>);
+
+/// Setup the daylight panel in the analysis column
+pub fn setup_daylight_panel(
+    mut commands: Commands,
+    column_query: Query<Entity, With<AnalysisColumn>>,
+    theme: Res<UiTheme>,
+) {
+    let Ok(column) = column_query.single() else {
+        return;
+    };
+    commands.entity(column).with_children(|parent| {
+        parent
+            .spawn((
+                Node {
+                    flex_direction: FlexDirection::Column,
+                    padding: UiRect::all(Val::Px(theme.panel_padding)),
+                    ..default()
+                },
+                BackgroundColor(theme.panel),
+            ))
+            .with_children(|parent| {
+                parent
+                    .spawn(Node {
+                        flex_direction: FlexDirection::Row,
+                        margin: UiRect::bottom(Val::Px(5.0)),
+                        ..default()
+                    })
+                    .with_children(|parent| {
+                        for (button, label) in [
+                            (DaylightButton::Toggle, "Daylight"),
+                            (DaylightButton::Ratio(false), "Ratio -"),
+                            (DaylightButton::Ratio(true), "Ratio +"),
+                        ] {
+                            parent
+                                .spawn((
+                                    Button,
+                                    button,
+                                    Node {
+                                        padding: UiRect::all(Val::Px(theme.button_padding)),
+                                        margin: UiRect::right(Val::Px(3.0)),
+                                        ..default()
+                                    },
+                                    BackgroundColor(theme.button),
+                                ))
+                                .with_children(|parent| {
+                                    parent.spawn(Text::new(label));
+                                });
+                        }
+                    });
+
+                parent.spawn((
+                    Text::new("Daylight: off"),
+                    TextFont {
+                        font_size: theme.small_font_size,
+                        ..default()
+                    },
+                    DaylightText,
+                ));
+            });
+    });
+}
+
+/// Handle the daylight panel buttons
+pub fn handle_daylight_buttons(
+    interaction_query: Query<(&Interaction, &DaylightButton), Changed<Interaction>>,
+    mut state: ResMut<DaylightState>,
+) {
+    for (interaction, button) in &interaction_query {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        match button {
+            DaylightButton::Toggle => state.enabled = !state.enabled,
+            DaylightButton::Ratio(up) => {
+                let step = if *up { RATIO_STEP } else { -RATIO_STEP };
+                state.settings.min_ratio = (state.settings.min_ratio + step).clamp(0.0, 1.0);
+            }
+        }
+    }
+}
+
+/// Re-run the check when it is enabled and the model or settings change
+pub fn check_model_daylight(
+    geometry_registry: Res<GeometryRegistryResource>,
+    element_registry: Res<ElementRegistryResource>,
+    mut state: ResMut<DaylightState>,
+) {
+    let changed =
+        state.is_changed() || geometry_registry.is_changed() || element_registry.is_changed();
+    if !changed {
+        return;
+    }
+    if !state.enabled {
+        if !state.checks.is_empty() {
+            state.checks.clear();
+        }
+        return;
+    }
+    let checks = check_daylight(
+        &element_registry.registry,
+        &geometry_registry.registry,
+        &state.settings,
+    );
+    state.checks = checks;
+}
+
+/// Refresh the results text when the check changes
+pub fn update_daylight_panel(
+    state: Res<DaylightState>,
+    mut text_query: Query<(&mut Text, &mut TextColor), With<DaylightText>>,
+) {
+    if !state.is_changed() {
+        return;
+    }
+    let minimum = state.settings.min_ratio * 100.0;
+    let failing = state.checks.iter().filter(|check| !check.passes).count();
+    let mut lines = if state.enabled {
+        vec![format!(
+            "Daylight: {failing} of {} room(s) below {minimum:.0}%",
+            state.checks.len()
+        )]
+    } else {
+        vec![format!("Daylight: off (minimum {minimum:.0}%)")]
+    };
+    for check in &state.checks {
+        lines.push(format!(
+            "{}: {:.1} m² glazing / {:.1} m² floor = {:.1}%",
+            check.name,
+            check.window_area,
+            check.floor_area,
+            check.ratio * 100.0
+        ));
+    }
+    let color = if failing > 0 {
+        Color::srgb(1.0, 0.4, 0.4)
+    } else {
+        Color::WHITE
+    };
+    let status = lines.join("\n");
+    for (mut text, mut text_color) in &mut text_query {
+        text.0.clone_from(&status);
+        text_color.0 = color;
+    }
+}
+
+/// Tint the rooms failing the check, restoring the others' materials
+pub fn tint_daylight_failures(
+    mut commands: Commands,
+    state: Res<DaylightState>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut solid_query: Query<(
+        Entity,
+        &SolidId,
+        &mut MeshMaterial3d<StandardMaterial>,
+        Option<&DaylightTint>,
+    )>,
+    mut tint: Local<Option<Handle<StandardMaterial>>>,
+) {
+    if !state.is_changed() {
+        return;
+    }
+    let tint = tint
+        .get_or_insert_with(|| {
+            materials.add(StandardMaterial {
+                base_color: Color::srgba(0.9, 0.25, 0.2, 0.85),
+                alpha_mode: AlphaMode::Blend,
+                ..Default::default()
+            })
+        })
+        .clone();
+    for (entity, solid_id, mut material, original) in &mut solid_query {
+        let failing = state
+            .checks
+            .iter()
+            .any(|check| !check.passes && check.solid == solid_id.0);
+        match (failing, original) {
+            (true, None) => {
+                commands
+                    .entity(entity)
+                    .insert(DaylightTint(material.0.clone()));
+                material.0 = tint.clone();
+            }
+            (false, Some(original)) => {
+                material.0 = original.0.clone();
+                commands.entity(entity).remove::<DaylightTint>();
+            }
+            _ => {}
+        }
+    }
+}
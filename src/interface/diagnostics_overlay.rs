@@ -0,0 +1,267 @@
+use bevy::diagnostic::{DiagnosticsStore, FrameTimeDiagnosticsPlugin};
+use bevy::ecs::entity::Entities;
+use bevy::log::tracing_subscriber::layer::Context;
+use bevy::log::tracing_subscriber::{Layer, Registry};
+use bevy::log::BoxedLayer;
+use bevy::prelude::*;
+use bevy::render::mesh::Indices;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+
+/// Key that shows and hides the overlay
+const OVERLAY_KEY: KeyCode = KeyCode::F3;
+/// Time between refreshes of the overlay text
+const REFRESH_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Which timed operation a span belongs to
+#[derive(Clone, Copy, PartialEq)]
+pub enum TimedOperation {
+    MeshBuild,
+    Solve,
+}
+
+impl TimedOperation {
+    /// The operation timed by spans with this name, if any
+    fn from_span_name(name: &str) -> Option<Self> {
+        match name {
+            "create_mesh_from_solid" => Some(TimedOperation::MeshBuild),
+            "apply_constraints" | "propagate_deltas" | "solve_incrementally" => {
+                Some(TimedOperation::Solve)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// One timed span, sent from the tracing layer
+pub struct TimingSample {
+    pub operation: TimedOperation,
+    pub duration: Duration,
+    pub iterations: Option<u64>,
+}
+
+/// Resource holding the latest timings for the overlay
+#[derive(Resource, Default)]
+pub struct PerformanceStats {
+    pub last_mesh_build: Option<Duration>,
+    pub meshes_built: usize,
+    pub last_solve: Option<Duration>,
+    pub last_solve_iterations: Option<u64>,
+    pub visible: bool,
+}
+
+/// Resource receiving samples from the timing layer
+#[derive(Resource)]
+pub struct TimingReceiver(Mutex<Receiver<TimingSample>>);
+
+/// Span extension recording when a timed span was created
+struct SpanTiming {
+    started: Instant,
+    iterations: Option<u64>,
+}
+
+/// Tracing layer timing mesh build and solver spans
+struct TimingLayer {
+    sender: Sender<TimingSample>,
+}
+
+/// Reads the `iterations` field of a solver span
+struct IterationVisitor<'a>(&'a mut Option<u64>);
+
+impl Visit for IterationVisitor<'_> {
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        if field.name() == "iterations" {
+            *self.0 = Some(value);
+        }
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        if field.name() == "iterations" {
+            *self.0 = u64::try_from(value).ok();
+        }
+    }
+
+    fn record_debug(&mut self, _field: &Field, _value: &dyn std::fmt::Debug) {}
+}
+
+impl Layer<Registry> for TimingLayer {
+    fn on_new_span(&self, attributes: &Attributes<'_>, id: &Id, context: Context<'_, Registry>) {
+        let Some(span) = context.span(id) else {
+            return;
+        };
+        if TimedOperation::from_span_name(span.name()).is_none() {
+            return;
+        }
+        let mut iterations = None;
+        attributes.record(&mut IterationVisitor(&mut iterations));
+        span.extensions_mut().insert(SpanTiming {
+            started: Instant::now(),
+            iterations,
+        });
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, context: Context<'_, Registry>) {
+        let Some(span) = context.span(id) else {
+            return;
+        };
+        let mut extensions = span.extensions_mut();
+        if let Some(timing) = extensions.get_mut::<SpanTiming>() {
+            values.record(&mut IterationVisitor(&mut timing.iterations));
+        }
+    }
+
+    fn on_close(&self, id: Id, context: Context<'_, Registry>) {
+        let Some(span) = context.span(&id) else {
+            return;
+        };
+        let Some(operation) = TimedOperation::from_span_name(span.name()) else {
+            return;
+        };
+        let extensions = span.extensions();
+        if let Some(timing) = extensions.get::<SpanTiming>() {
+            // The receiver only goes away when the app shuts down
+            let _ = self.sender.send(TimingSample {
+                operation,
+                duration: timing.started.elapsed(),
+                iterations: timing.iterations,
+            });
+        }
+    }
+}
+
+/// Tracing layer that feeds the profiling overlay
+///
+/// Only spans the log filter lets through are timed, so the crate's debug
+/// spans must be enabled.
+pub fn timing_layer(app: &mut App) -> BoxedLayer {
+    let (sender, receiver) = channel();
+    app.insert_resource(TimingReceiver(Mutex::new(receiver)));
+    Box::new(TimingLayer { sender })
+}
+
+/// Marker component for the overlay text
+#[derive(Component)]
+pub struct DiagnosticsText;
+
+/// Setup the hidden profiling overlay
+pub fn setup_diagnostics_overlay(mut commands: Commands) {
+    commands.spawn((
+        Text::new(""),
+        TextFont {
+            font_size: 13.0,
+            ..default()
+        },
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(10.0),
+            left: Val::Percent(55.0),
+            padding: UiRect::all(Val::Px(6.0)),
+            display: Display::None,
+            ..default()
+        },
+        BackgroundColor(Color::srgba(0.05, 0.05, 0.05, 0.8)),
+        DiagnosticsText,
+    ));
+}
+
+/// Move timing samples from the layer into the stats
+pub fn collect_timing_samples(
+    receiver: Option<Res<TimingReceiver>>,
+    mut stats: ResMut<PerformanceStats>,
+) {
+    let Some(receiver) = receiver else {
+        return;
+    };
+    let Ok(receiver) = receiver.0.lock() else {
+        return;
+    };
+    for sample in receiver.try_iter() {
+        match sample.operation {
+            TimedOperation::MeshBuild => {
+                stats.last_mesh_build = Some(sample.duration);
+                stats.meshes_built += 1;
+            }
+            TimedOperation::Solve => {
+                stats.last_solve = Some(sample.duration);
+                stats.last_solve_iterations = sample.iterations;
+            }
+        }
+    }
+}
+
+/// Show or hide the overlay with its hotkey
+pub fn toggle_diagnostics_overlay(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut stats: ResMut<PerformanceStats>,
+    mut text_query: Query<&mut Node, With<DiagnosticsText>>,
+) {
+    if !keyboard_input.just_pressed(OVERLAY_KEY) {
+        return;
+    }
+    stats.visible = !stats.visible;
+    for mut node in &mut text_query {
+        node.display = if stats.visible {
+            Display::Flex
+        } else {
+            Display::None
+        };
+    }
+}
+
+/// Refresh the overlay text while it is shown
+pub fn update_diagnostics_overlay(
+    stats: Res<PerformanceStats>,
+    diagnostics: Res<DiagnosticsStore>,
+    meshes: Res<Assets<Mesh>>,
+    mesh_query: Query<&Mesh3d>,
+    entities: &Entities,
+    mut text_query: Query<&mut Text, With<DiagnosticsText>>,
+    mut last_refresh: Local<Option<Instant>>,
+) {
+    let due = last_refresh.is_none_or(|refreshed| refreshed.elapsed() >= REFRESH_INTERVAL);
+    if !stats.visible || !due {
+        return;
+    }
+    *last_refresh = Some(Instant::now());
+
+    let fps = diagnostics
+        .get(&FrameTimeDiagnosticsPlugin::FPS)
+        .and_then(bevy::diagnostic::Diagnostic::smoothed)
+        .map_or_else(|| "-".to_string(), |fps| format!("{fps:.0}"));
+    let milliseconds = |duration: Option<Duration>| {
+        duration.map_or_else(
+            || "none yet".to_string(),
+            |duration| format!("{:.2} ms", duration.as_secs_f64() * 1000.0),
+        )
+    };
+    let triangles: usize = mesh_query
+        .iter()
+        .filter_map(|mesh| meshes.get(&mesh.0))
+        .map(|mesh| mesh.indices().map_or(mesh.count_vertices(), Indices::len) / 3)
+        .sum();
+    let iterations = stats
+        .last_solve_iterations
+        .map_or_else(|| "-".to_string(), |iterations| iterations.to_string());
+
+    let contents = [
+        format!("FPS: {fps}"),
+        format!(
+            "Mesh build: {} ({} built)",
+            milliseconds(stats.last_mesh_build),
+            stats.meshes_built
+        ),
+        format!(
+            "Last solve: {}, {iterations} iterations",
+            milliseconds(stats.last_solve)
+        ),
+        format!("Triangles: {triangles}"),
+        format!("Entities: {}", entities.len()),
+    ]
+    .join("\n");
+    for mut text in &mut text_query {
+        text.0.clone_from(&contents);
+    }
+}
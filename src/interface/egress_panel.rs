@@ -0,0 +1,211 @@
+use bevy::prelude::*;
+
+use crate::domain::{analyze_egress, EgressReport, EgressSettings, ElementKind};
+use crate::interface::segment_outlines::{ElementRegistryResource, GeometryRegistryResource};
+use crate::interface::theme::UiTheme;
+use crate::interface::AnalysisColumn;
+
+/// Change in the travel distance limit per button press, in meters
+const LIMIT_STEP: f32 = 5.0;
+/// Height paths are drawn above the floor, so they are not hidden by it
+const PATH_LIFT: f32 = 0.05;
+
+/// Resource holding the egress settings and latest analysis
+#[derive(Resource, Default)]
+pub struct EgressState {
+    pub settings: EgressSettings,
+    pub report: EgressReport,
+    /// Whether the analysis runs and its paths are drawn
+    pub enabled: bool,
+}
+
+/// What an egress panel button does
+#[derive(Component, Clone, Copy)]
+pub enum EgressButton {
+    Toggle,
+    Limit(bool),
+}
+
+/// Marker component for the egress results text
+#[derive(Component)]
+pub struct EgressText;
+
+/// Setup the egress panel in the analysis column
+pub fn setup_egress_panel(
+    mut commands: Commands,
+    column_query: Query<Entity, With<AnalysisColumn>>,
+    theme: Res<UiTheme>,
+) {
+    let Ok(column) = column_query.single() else {
+        return;
+    };
+    commands.entity(column).with_children(|parent| {
+        parent
+            .spawn((
+                Node {
+                    flex_direction: FlexDirection::Column,
+                    padding: UiRect::all(Val::Px(theme.panel_padding)),
+                    ..default()
+                },
+                BackgroundColor(theme.panel),
+            ))
+            .with_children(|parent| {
+                parent
+                    .spawn(Node {
+                        flex_direction: FlexDirection::Row,
+                        margin: UiRect::bottom(Val::Px(5.0)),
+                        ..default()
+                    })
+                    .with_children(|parent| {
+                        for (button, label) in [
+                            (EgressButton::Toggle, "Egress"),
+                            (EgressButton::Limit(false), "Limit -"),
+                            (EgressButton::Limit(true), "Limit +"),
+                        ] {
+                            parent
+                                .spawn((
+                                    Button,
+                                    button,
+                                    Node {
+                                        padding: UiRect::all(Val::Px(theme.button_padding)),
+                                        margin: UiRect::right(Val::Px(3.0)),
+                                        ..default()
+                                    },
+                                    BackgroundColor(theme.button),
+                                ))
+                                .with_children(|parent| {
+                                    parent.spawn(Text::new(label));
+                                });
+                        }
+                    });
+
+                parent.spawn((
+                    Text::new("Egress: off"),
+                    TextFont {
+                        font_size: theme.small_font_size,
+                        ..default()
+                    },
+                    EgressText,
+                ));
+            });
+    });
+}
+
+/// Handle the egress panel buttons
+pub fn handle_egress_buttons(
+    interaction_query: Query<(&Interaction, &EgressButton), Changed<Interaction>>,
+    mut state: ResMut<EgressState>,
+) {
+    for (interaction, button) in &interaction_query {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        match button {
+            EgressButton::Toggle => state.enabled = !state.enabled,
+            EgressButton::Limit(up) => {
+                let step = if *up { LIMIT_STEP } else { -LIMIT_STEP };
+                state.settings.max_travel_distance =
+                    (state.settings.max_travel_distance + step).max(LIMIT_STEP);
+            }
+        }
+    }
+}
+
+/// Re-run the analysis when it is enabled and the model or settings change
+///
+/// Doors whose name contains "exit" are the designated exits.
+pub fn analyze_model_egress(
+    geometry_registry: Res<GeometryRegistryResource>,
+    element_registry: Res<ElementRegistryResource>,
+    mut state: ResMut<EgressState>,
+) {
+    let changed =
+        state.is_changed() || geometry_registry.is_changed() || element_registry.is_changed();
+    if !state.enabled || !changed {
+        return;
+    }
+    let mut exits: Vec<_> = element_registry
+        .registry
+        .elements
+        .values()
+        .filter(|element| {
+            element.kind == ElementKind::Door && element.name.to_lowercase().contains("exit")
+        })
+        .map(|element| element.id)
+        .collect();
+    exits.sort();
+    state.settings.exits = exits;
+    let report = analyze_egress(
+        &element_registry.registry,
+        &geometry_registry.registry,
+        &state.settings,
+    );
+    state.report = report;
+}
+
+/// Refresh the results text when the analysis changes
+pub fn update_egress_panel(
+    state: Res<EgressState>,
+    mut text_query: Query<(&mut Text, &mut TextColor), With<EgressText>>,
+) {
+    if !state.is_changed() {
+        return;
+    }
+    let report = &state.report;
+    let failures = report.failures().count();
+    let mut lines = if state.enabled {
+        vec![format!(
+            "Egress: {} space(s), {failures} over the {:.0} m limit, {} exit(s)",
+            report.routes.len(),
+            state.settings.max_travel_distance,
+            state.settings.exits.len()
+        )]
+    } else {
+        vec![format!(
+            "Egress: off (limit {:.0} m)",
+            state.settings.max_travel_distance
+        )]
+    };
+    if state.enabled {
+        if state.settings.exits.is_empty() {
+            lines.push("Name a door \"Exit\" to make it an exit".to_string());
+        }
+        for route in &report.routes {
+            lines.push(match route.distance {
+                Some(distance) => format!("{}: {distance:.1} m", route.name),
+                None => format!("{}: no route to an exit", route.name),
+            });
+        }
+    }
+    let color = if state.enabled && failures > 0 {
+        Color::srgb(1.0, 0.4, 0.4)
+    } else {
+        Color::WHITE
+    };
+    let status = lines.join("\n");
+    for (mut text, mut text_color) in &mut text_query {
+        text.0.clone_from(&status);
+        text_color.0 = color;
+    }
+}
+
+/// Draw each space's egress path, green within the limit and red beyond
+pub fn draw_egress_paths(mut gizmos: Gizmos, state: Res<EgressState>) {
+    if !state.enabled {
+        return;
+    }
+    for route in &state.report.routes {
+        let color = if route.within_limit {
+            Color::srgb(0.3, 0.9, 0.4)
+        } else {
+            Color::srgb(1.0, 0.3, 0.3)
+        };
+        gizmos.linestrip(
+            route
+                .path
+                .iter()
+                .map(|point| Vec3::new(point.x, point.y + PATH_LIFT, point.z)),
+            color,
+        );
+    }
+}
@@ -0,0 +1,243 @@
+use bevy::prelude::*;
+use bevy::ui::RelativeCursorPosition;
+use std::collections::HashMap;
+
+use crate::interface::keyboard_navigation::AccessibleName;
+use crate::interface::segment_outlines::{GeometryRegistryResource, SolidId};
+use crate::interface::theme::UiTheme;
+use crate::interface::ui::ToggleableMesh;
+use crate::interface::ViewColumn;
+
+/// Distance at the right end of the slider, in meters
+const MAX_EXPLODE_DISTANCE: f32 = 10.0;
+/// Width of the slider track in pixels
+const SLIDER_WIDTH: f32 = 160.0;
+
+/// Resource holding the exploded view's settings
+///
+/// Exploding only offsets the solids' entities; the registries and the
+/// solids' vertices are never changed.
+#[derive(Resource)]
+pub struct ExplodedView {
+    /// Whether solids are pushed apart
+    pub enabled: bool,
+    /// How far each solid is pushed from the model's centroid, in meters
+    pub distance: f32,
+    /// The offset applied to each entity so far, taken off again before
+    /// the next one is applied
+    applied: HashMap<Entity, Vec3>,
+}
+
+impl Default for ExplodedView {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            distance: 2.0,
+            applied: HashMap::new(),
+        }
+    }
+}
+
+/// Marker component for the exploded view toggle button
+#[derive(Component)]
+pub struct ExplodeToggleButton;
+
+/// Marker component for the explode distance slider's track
+#[derive(Component)]
+pub struct ExplodeSlider;
+
+/// Marker component for the filled part of the slider
+#[derive(Component)]
+pub struct ExplodeSliderFill;
+
+/// Marker component for the explode distance text
+#[derive(Component)]
+pub struct ExplodeText;
+
+/// Setup the exploded view controls in the view column
+pub fn setup_exploded_view(
+    mut commands: Commands,
+    column_query: Query<Entity, With<ViewColumn>>,
+    theme: Res<UiTheme>,
+) {
+    let Ok(column) = column_query.single() else {
+        return;
+    };
+    commands.entity(column).with_children(|parent| {
+        parent
+            .spawn((
+                Node {
+                    flex_direction: FlexDirection::Column,
+                    padding: UiRect::all(Val::Px(theme.panel_padding)),
+                    row_gap: Val::Px(5.0),
+                    ..default()
+                },
+                BackgroundColor(theme.panel),
+            ))
+            .with_children(|parent| {
+                parent
+                    .spawn((
+                        Button,
+                        ExplodeToggleButton,
+                        Node {
+                            padding: UiRect::all(Val::Px(theme.button_padding)),
+                            ..default()
+                        },
+                        BackgroundColor(theme.button),
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn(Text::new("Exploded View"));
+                    });
+
+                parent
+                    .spawn((
+                        Button,
+                        ExplodeSlider,
+                        AccessibleName("Explosion amount".to_string()),
+                        RelativeCursorPosition::default(),
+                        Node {
+                            width: Val::Px(SLIDER_WIDTH),
+                            height: Val::Px(10.0),
+                            ..default()
+                        },
+                        BackgroundColor(Color::srgba(0.25, 0.25, 0.25, 0.8)),
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn((
+                            Node {
+                                width: Val::Percent(0.0),
+                                height: Val::Percent(100.0),
+                                ..default()
+                            },
+                            BackgroundColor(Color::srgb(0.4, 0.6, 0.9)),
+                            ExplodeSliderFill,
+                        ));
+                    });
+
+                parent.spawn((
+                    Text::new(""),
+                    TextFont {
+                        font_size: theme.small_font_size,
+                        ..default()
+                    },
+                    ExplodeText,
+                ));
+            });
+    });
+}
+
+/// Toggle the exploded view, and set its distance while the slider is held
+pub fn handle_exploded_view_controls(
+    toggle_query: Query<&Interaction, (Changed<Interaction>, With<ExplodeToggleButton>)>,
+    slider_query: Query<(&Interaction, &RelativeCursorPosition), With<ExplodeSlider>>,
+    mut view: ResMut<ExplodedView>,
+) {
+    if toggle_query
+        .iter()
+        .any(|interaction| *interaction == Interaction::Pressed)
+    {
+        view.enabled = !view.enabled;
+    }
+    for (interaction, cursor) in &slider_query {
+        let Some(position) = cursor.normalized else {
+            continue;
+        };
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        let distance = position.x.clamp(0.0, 1.0) * MAX_EXPLODE_DISTANCE;
+        if (view.distance - distance).abs() > f32::EPSILON {
+            view.distance = distance;
+            view.enabled = true;
+        }
+    }
+}
+
+/// Offset each solid's entity along the line from the model's centroid
+/// through its own, or take the offsets off when the view is turned off
+pub fn explode_solids(
+    mut view: ResMut<ExplodedView>,
+    geometry_registry: Res<GeometryRegistryResource>,
+    mut solid_query: Query<(Entity, &SolidId, &mut Transform), With<ToggleableMesh>>,
+) {
+    let registry = &geometry_registry.registry;
+    // Where each solid sits without the exploded offset
+    let centers: Vec<(Entity, Vec3)> = solid_query
+        .iter()
+        .filter_map(|(entity, solid_id, transform)| {
+            let positions: Vec<Vec3> = registry
+                .solid_vertices(&solid_id.0)
+                .iter()
+                .filter_map(|id| registry.vertices.get(id))
+                .map(|vertex| {
+                    let position = &vertex.position;
+                    Vec3::new(position.x, position.y, position.z)
+                })
+                .collect();
+            if positions.is_empty() {
+                return None;
+            }
+            #[allow(clippy::cast_precision_loss)]
+            let local = positions.iter().sum::<Vec3>() / positions.len() as f32;
+            let mut unexploded = *transform;
+            unexploded.translation -= view.applied.get(&entity).copied().unwrap_or(Vec3::ZERO);
+            Some((entity, unexploded.transform_point(local)))
+        })
+        .collect();
+    // Forget the offsets of entities that are gone
+    if view
+        .applied
+        .keys()
+        .any(|entity| centers.iter().all(|(other, _)| other != entity))
+    {
+        view.applied
+            .retain(|entity, _| centers.iter().any(|(other, _)| other == entity));
+    }
+    if centers.is_empty() {
+        return;
+    }
+    #[allow(clippy::cast_precision_loss)]
+    let centroid = centers.iter().map(|(_, center)| *center).sum::<Vec3>() / centers.len() as f32;
+    let (enabled, distance) = (view.enabled, view.distance);
+    for (entity, center) in centers {
+        let offset = if enabled {
+            (center - centroid).normalize_or_zero() * distance
+        } else {
+            Vec3::ZERO
+        };
+        let previous = view.applied.get(&entity).copied().unwrap_or(Vec3::ZERO);
+        if offset == previous {
+            continue;
+        }
+        if let Ok((_, _, mut transform)) = solid_query.get_mut(entity) {
+            transform.translation += offset - previous;
+        }
+        if offset == Vec3::ZERO {
+            view.applied.remove(&entity);
+        } else {
+            view.applied.insert(entity, offset);
+        }
+    }
+}
+
+/// Show the exploded view's state on its controls
+pub fn update_exploded_view_controls(
+    view: Res<ExplodedView>,
+    mut toggle_query: Query<&mut BackgroundColor, With<ExplodeToggleButton>>,
+    mut fill_query: Query<&mut Node, With<ExplodeSliderFill>>,
+    mut text_query: Query<&mut Text, With<ExplodeText>>,
+    theme: Res<UiTheme>,
+) {
+    if !view.is_changed() {
+        return;
+    }
+    for mut background_color in &mut toggle_query {
+        *background_color = theme.button_color(view.enabled).into();
+    }
+    for mut node in &mut fill_query {
+        node.width = Val::Percent(view.distance / MAX_EXPLODE_DISTANCE * 100.0);
+    }
+    for mut text in &mut text_query {
+        text.0 = format!("Distance: {:.1} m", view.distance);
+    }
+}
@@ -0,0 +1,430 @@
+use bevy::ecs::event::EventCursor;
+use bevy::prelude::*;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::domain::rules::Rule;
+use crate::domain::{ElementRegistry, GeometryRegistry, Point};
+use crate::interface::file_menu::ProjectState;
+use crate::interface::render_export::export_path;
+use crate::interface::rules_panel::RuleState;
+use crate::interface::segment_outlines::{ElementRegistryResource, GeometryRegistryResource};
+use crate::interface::theme::UiTheme;
+use crate::interface::ViewColumn;
+
+/// A third-party tool, adding commands, importers, exporters, rules and
+/// panels to the application
+///
+/// Firms extend the application without forking the crate by implementing
+/// this trait and adding it with [`AddExtension::add_extension`], usually
+/// from the Bevy plugin that ships their tool. Extensions are compiled in
+/// with the application; they are not loaded from dynamic libraries.
+pub trait Extension: Send + Sync + 'static {
+    /// The extension's name, listed in the extensions panel
+    fn name(&self) -> &str;
+
+    /// Add what the extension provides to the registry
+    fn register(&self, registry: &mut ExtensionRegistry);
+}
+
+/// A command an extension adds, run by name
+pub trait ExtensionCommand: Send + Sync {
+    /// The command's name, which is also its button label
+    fn name(&self) -> &str;
+
+    /// Run the command on the world, returning a message for the user
+    ///
+    /// Commands that edit the model should send the model command events,
+    /// so that the edit is solved, validated and recorded like any other.
+    ///
+    /// # Errors
+    /// Returns a message saying why the command could not run
+    fn run(&self, world: &mut World, arguments: &str) -> Result<String, String>;
+}
+
+/// A file format an extension imports as solids
+pub trait ExtensionImporter: Send + Sync {
+    /// The format's name
+    fn name(&self) -> &str;
+
+    /// The file extensions it reads, in lowercase without the dot
+    fn extensions(&self) -> &[&str];
+
+    /// Read a file's faces, each a loop of points, to build a solid from
+    ///
+    /// # Errors
+    /// Returns a message saying why the file could not be read
+    fn read(&self, path: &Path) -> Result<Vec<Vec<Point>>, String>;
+}
+
+/// The model as an exporter sees it
+pub struct ExportModel<'a> {
+    /// The model's geometry
+    pub geometry: &'a GeometryRegistry,
+    /// The model's elements
+    pub elements: &'a ElementRegistry,
+}
+
+/// A file format an extension exports the model to
+pub trait ExtensionExporter: Send + Sync {
+    /// The format's name
+    fn name(&self) -> &str;
+
+    /// The extension of the files it writes, without the dot
+    fn extension(&self) -> &str;
+
+    /// The contents of the file the model is exported to
+    ///
+    /// # Errors
+    /// Returns a message saying why the model could not be exported
+    fn export(&self, model: &ExportModel) -> Result<Vec<u8>, String>;
+}
+
+/// A panel an extension shows in the extensions section
+pub trait ExtensionPanel: Send + Sync {
+    /// The panel's heading
+    fn title(&self) -> &str;
+
+    /// The panel's text, read from the world every frame
+    fn text(&self, world: &World) -> String;
+}
+
+/// Resource holding everything the extensions added
+///
+/// A command, importer, exporter or panel replaces any added before it
+/// with the same name, as rules with the same ID do.
+#[derive(Resource, Default)]
+pub struct ExtensionRegistry {
+    /// The names of the extensions added, in the order they were added
+    pub extensions: Vec<String>,
+    commands: Vec<Arc<dyn ExtensionCommand>>,
+    importers: Vec<Arc<dyn ExtensionImporter>>,
+    exporters: Vec<Arc<dyn ExtensionExporter>>,
+    panels: Vec<Arc<dyn ExtensionPanel>>,
+    /// Rules waiting to join the rule set at startup
+    rules: Vec<Box<dyn Rule>>,
+}
+
+impl ExtensionRegistry {
+    /// Add a command
+    pub fn add_command(&mut self, command: impl ExtensionCommand + 'static) {
+        self.commands
+            .retain(|existing| existing.name() != command.name());
+        self.commands.push(Arc::new(command));
+    }
+
+    /// Add an importer
+    pub fn add_importer(&mut self, importer: impl ExtensionImporter + 'static) {
+        self.importers
+            .retain(|existing| existing.name() != importer.name());
+        self.importers.push(Arc::new(importer));
+    }
+
+    /// Add an exporter
+    pub fn add_exporter(&mut self, exporter: impl ExtensionExporter + 'static) {
+        self.exporters
+            .retain(|existing| existing.name() != exporter.name());
+        self.exporters.push(Arc::new(exporter));
+    }
+
+    /// Add a panel
+    pub fn add_panel(&mut self, panel: impl ExtensionPanel + 'static) {
+        self.panels
+            .retain(|existing| existing.title() != panel.title());
+        self.panels.push(Arc::new(panel));
+    }
+
+    /// Add a validation rule, run with the built-in rules
+    pub fn add_rule(&mut self, rule: impl Rule + 'static) {
+        self.rules.push(Box::new(rule));
+    }
+
+    /// The command with a name
+    #[must_use]
+    pub fn command(&self, name: &str) -> Option<Arc<dyn ExtensionCommand>> {
+        self.commands
+            .iter()
+            .find(|command| command.name() == name)
+            .cloned()
+    }
+
+    /// The importer reading files with an extension, matched ignoring case
+    #[must_use]
+    pub fn importer_for(&self, extension: &str) -> Option<Arc<dyn ExtensionImporter>> {
+        self.importers
+            .iter()
+            .find(|importer| {
+                importer
+                    .extensions()
+                    .iter()
+                    .any(|known| known.eq_ignore_ascii_case(extension))
+            })
+            .cloned()
+    }
+
+    /// The exporter with a name
+    #[must_use]
+    pub fn exporter(&self, name: &str) -> Option<Arc<dyn ExtensionExporter>> {
+        self.exporters
+            .iter()
+            .find(|exporter| exporter.name() == name)
+            .cloned()
+    }
+
+    /// The commands, in the order they were added
+    pub fn commands(&self) -> impl Iterator<Item = &dyn ExtensionCommand> {
+        self.commands.iter().map(AsRef::as_ref)
+    }
+
+    /// The exporters, in the order they were added
+    pub fn exporters(&self) -> impl Iterator<Item = &dyn ExtensionExporter> {
+        self.exporters.iter().map(AsRef::as_ref)
+    }
+
+    /// Whether no extension added anything to show in the panel
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty() && self.exporters.is_empty() && self.panels.is_empty()
+    }
+}
+
+/// Adding extensions to an app
+pub trait AddExtension {
+    /// Add an extension, registering what it provides
+    fn add_extension(&mut self, extension: impl Extension) -> &mut Self;
+}
+
+impl AddExtension for App {
+    fn add_extension(&mut self, extension: impl Extension) -> &mut Self {
+        let mut registry = self.world_mut().get_resource_or_init::<ExtensionRegistry>();
+        registry.extensions.push(extension.name().to_string());
+        extension.register(&mut registry);
+        self
+    }
+}
+
+/// Command to run an extension's command
+#[derive(Event, Clone)]
+pub struct RunExtensionCommand {
+    /// The command's name
+    pub name: String,
+    /// Free-form arguments, passed to the command as they are
+    pub arguments: String,
+}
+
+/// Command to export the model with an extension's exporter
+#[derive(Event, Clone)]
+pub struct ExportWithExtension {
+    /// The exporter's name
+    pub exporter: String,
+    /// The file to write
+    pub path: PathBuf,
+}
+
+/// What an extensions panel button does
+#[derive(Component, Clone)]
+pub enum ExtensionButton {
+    Run(String),
+    Export(String),
+}
+
+/// Marker component for the extensions panel, shown once an extension
+/// adds something to it
+#[derive(Component)]
+pub struct ExtensionsPanel;
+
+/// The text of an extension's panel, by its place among the panels
+#[derive(Component)]
+pub struct ExtensionPanelText(pub usize);
+
+/// Move the rules the extensions added into the rule set
+pub fn install_extension_rules(
+    mut registry: ResMut<ExtensionRegistry>,
+    mut rule_state: ResMut<RuleState>,
+) {
+    for rule in std::mem::take(&mut registry.rules) {
+        info!("Added rule {} from an extension", rule.id());
+        rule_state.rules.add(rule);
+    }
+}
+
+/// Run the extension commands requested, with the whole world to work on
+///
+/// The requests are read rather than drained, so that the session
+/// recorder sees them too.
+pub fn run_extension_commands(
+    world: &mut World,
+    mut cursor: Local<EventCursor<RunExtensionCommand>>,
+) {
+    let requests: Vec<RunExtensionCommand> = cursor
+        .read(world.resource::<Events<RunExtensionCommand>>())
+        .cloned()
+        .collect();
+    for request in requests {
+        let Some(command) = world.resource::<ExtensionRegistry>().command(&request.name) else {
+            warn!("No extension command named {}", request.name);
+            continue;
+        };
+        match command.run(world, &request.arguments) {
+            Ok(message) => info!("{}: {message}", request.name),
+            Err(message) => error!("{} failed: {message}", request.name),
+        }
+    }
+}
+
+/// Write the model with the extension exporters requested
+pub fn export_with_extensions(
+    mut events: EventReader<ExportWithExtension>,
+    registry: Res<ExtensionRegistry>,
+    geometry_registry: Res<GeometryRegistryResource>,
+    element_registry: Res<ElementRegistryResource>,
+) {
+    for event in events.read() {
+        let Some(exporter) = registry.exporter(&event.exporter) else {
+            warn!("No extension exporter named {}", event.exporter);
+            continue;
+        };
+        let model = ExportModel {
+            geometry: &geometry_registry.registry,
+            elements: &element_registry.registry,
+        };
+        let written = exporter.export(&model).and_then(|bytes| {
+            std::fs::write(&event.path, bytes).map_err(|error| error.to_string())
+        });
+        match written {
+            Ok(()) => info!("Exported {}", event.path.display()),
+            Err(error) => error!("Could not export {}: {error}", event.path.display()),
+        }
+    }
+}
+
+/// Setup the extensions panel in the view column, with a button for each
+/// extension command and exporter and a text for each extension panel
+pub fn setup_extensions_panel(
+    mut commands: Commands,
+    column_query: Query<Entity, With<ViewColumn>>,
+    registry: Res<ExtensionRegistry>,
+    theme: Res<UiTheme>,
+) {
+    let Ok(column) = column_query.single() else {
+        return;
+    };
+    if registry.is_empty() {
+        return;
+    }
+    let buttons: Vec<(ExtensionButton, String)> = registry
+        .commands()
+        .map(|command| {
+            let name = command.name().to_string();
+            (ExtensionButton::Run(name.clone()), name)
+        })
+        .chain(registry.exporters().map(|exporter| {
+            let name = exporter.name().to_string();
+            (
+                ExtensionButton::Export(name.clone()),
+                format!("Export {name}"),
+            )
+        }))
+        .collect();
+    commands.entity(column).with_children(|parent| {
+        parent
+            .spawn((
+                Node {
+                    flex_direction: FlexDirection::Column,
+                    padding: UiRect::all(Val::Px(theme.panel_padding)),
+                    row_gap: Val::Px(5.0),
+                    ..default()
+                },
+                BackgroundColor(theme.panel),
+                ExtensionsPanel,
+            ))
+            .with_children(|parent| {
+                parent.spawn((
+                    Text::new(format!("Extensions: {}", registry.extensions.join(", "))),
+                    TextFont {
+                        font_size: theme.small_font_size,
+                        ..default()
+                    },
+                ));
+                for index in 0..registry.panels.len() {
+                    parent.spawn((
+                        Text::new(""),
+                        TextFont {
+                            font_size: theme.small_font_size,
+                            ..default()
+                        },
+                        ExtensionPanelText(index),
+                    ));
+                }
+                for (button, label) in buttons {
+                    parent
+                        .spawn((
+                            Button,
+                            button,
+                            Node {
+                                padding: UiRect::all(Val::Px(theme.button_padding)),
+                                ..default()
+                            },
+                            BackgroundColor(theme.button),
+                        ))
+                        .with_children(|parent| {
+                            parent.spawn(Text::new(label));
+                        });
+                }
+            });
+    });
+}
+
+/// Run the command or export pressed in the extensions panel, exporting
+/// beside the project
+pub fn handle_extension_buttons(
+    interaction_query: Query<(&Interaction, &ExtensionButton), Changed<Interaction>>,
+    registry: Res<ExtensionRegistry>,
+    project: Res<ProjectState>,
+    mut runs: EventWriter<RunExtensionCommand>,
+    mut exports: EventWriter<ExportWithExtension>,
+) {
+    for (interaction, button) in &interaction_query {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        match button {
+            ExtensionButton::Run(name) => {
+                runs.write(RunExtensionCommand {
+                    name: name.clone(),
+                    arguments: String::new(),
+                });
+            }
+            ExtensionButton::Export(name) => {
+                let Some(exporter) = registry.exporter(name) else {
+                    continue;
+                };
+                let kind = name.to_lowercase().replace(' ', "-");
+                exports.write(ExportWithExtension {
+                    exporter: name.clone(),
+                    path: export_path(&project, &kind, exporter.extension()),
+                });
+            }
+        }
+    }
+}
+
+/// Refresh the text of each extension panel from the world
+pub fn update_extension_panels(world: &mut World) {
+    let panels = world.resource::<ExtensionRegistry>().panels.clone();
+    if panels.is_empty() {
+        return;
+    }
+    let texts: Vec<String> = panels
+        .iter()
+        .map(|panel| format!("{}\n{}", panel.title(), panel.text(world)))
+        .collect();
+    let mut text_query = world.query::<(&ExtensionPanelText, &mut Text)>();
+    for (panel, mut text) in text_query.iter_mut(world) {
+        if let Some(new_text) = texts.get(panel.0) {
+            if text.0 != *new_text {
+                text.0.clone_from(new_text);
+            }
+        }
+    }
+}
@@ -0,0 +1,312 @@
+use bevy::input::keyboard::KeyboardInput;
+use bevy::prelude::*;
+use uuid::Uuid;
+
+use crate::domain::{Operation, OperationChange};
+use crate::interface::command_bus::{EditOperation, ProvenanceResource};
+use crate::interface::prompt::{edit_buffer, PromptAction};
+use crate::interface::theme::UiTheme;
+use crate::interface::ViewColumn;
+
+/// Most operations listed in the tree, from the first
+const LISTED_OPERATIONS: usize = 16;
+
+/// Resource tracking the operation picked in the history tree and the
+/// parameter being typed for it
+#[derive(Resource, Default)]
+pub struct FeatureTreeState {
+    /// The operation the buttons act on
+    pub selected: Option<Uuid>,
+    /// Whether a new parameter is being typed
+    pub editing: bool,
+    pub entry: String,
+    pub message: String,
+}
+
+/// What a history tree button does
+#[derive(Component, Clone, Copy)]
+pub enum FeatureTreeButton {
+    Up,
+    Down,
+    Suppress,
+    Edit,
+    Delete,
+}
+
+/// A listed operation, picked by pressing it
+#[derive(Component)]
+pub struct FeatureRowButton(pub Uuid);
+
+/// Marker component for the list of operations
+#[derive(Component)]
+pub struct FeatureList;
+
+/// Marker component for the history tree text
+#[derive(Component)]
+pub struct FeatureTreeText;
+
+/// Setup the history tree panel in the view column
+pub fn setup_feature_tree_panel(
+    mut commands: Commands,
+    column_query: Query<Entity, With<ViewColumn>>,
+    theme: Res<UiTheme>,
+) {
+    let Ok(column) = column_query.single() else {
+        return;
+    };
+    commands.entity(column).with_children(|parent| {
+        parent
+            .spawn((
+                Node {
+                    flex_direction: FlexDirection::Column,
+                    padding: UiRect::all(Val::Px(theme.panel_padding)),
+                    row_gap: Val::Px(5.0),
+                    ..default()
+                },
+                BackgroundColor(theme.panel),
+            ))
+            .with_children(|parent| {
+                parent
+                    .spawn(Node {
+                        flex_direction: FlexDirection::Row,
+                        ..default()
+                    })
+                    .with_children(|parent| {
+                        for (button, label) in [
+                            (FeatureTreeButton::Up, "Up"),
+                            (FeatureTreeButton::Down, "Down"),
+                            (FeatureTreeButton::Suppress, "Suppress"),
+                            (FeatureTreeButton::Edit, "Edit"),
+                            (FeatureTreeButton::Delete, "Delete"),
+                        ] {
+                            parent
+                                .spawn((
+                                    Button,
+                                    button,
+                                    Node {
+                                        padding: UiRect::all(Val::Px(theme.button_padding)),
+                                        margin: UiRect::right(Val::Px(3.0)),
+                                        ..default()
+                                    },
+                                    BackgroundColor(theme.button),
+                                ))
+                                .with_children(|parent| {
+                                    parent.spawn(Text::new(label));
+                                });
+                        }
+                    });
+
+                parent.spawn((
+                    Text::new(""),
+                    TextFont {
+                        font_size: theme.small_font_size,
+                        ..default()
+                    },
+                    FeatureTreeText,
+                ));
+                parent.spawn((
+                    Node {
+                        flex_direction: FlexDirection::Column,
+                        ..default()
+                    },
+                    FeatureList,
+                ));
+            });
+    });
+}
+
+/// The parameter the edit prompt changes, as it is now
+fn parameter(operation: &Operation) -> Option<f32> {
+    match operation {
+        Operation::Extrude { depth } => Some(*depth),
+        Operation::Cut { plane, .. } => Some(plane.offset()),
+        Operation::Generator { .. } => None,
+    }
+}
+
+/// The operation with the edit prompt's parameter changed
+fn with_parameter(operation: &Operation, value: f32) -> Option<Operation> {
+    match operation {
+        Operation::Extrude { .. } => Some(Operation::Extrude { depth: value }),
+        Operation::Cut { plane, keep_front } => Some(Operation::Cut {
+            plane: plane.with_offset(value),
+            keep_front: *keep_front,
+        }),
+        Operation::Generator { .. } => None,
+    }
+}
+
+/// Handle the history tree buttons, acting on the picked operation
+pub fn handle_feature_tree_buttons(
+    button_query: Query<(&Interaction, &FeatureTreeButton), Changed<Interaction>>,
+    provenance: Res<ProvenanceResource>,
+    mut state: ResMut<FeatureTreeState>,
+    mut changes: EventWriter<EditOperation>,
+) {
+    for (interaction, button) in &button_query {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        let graph = &provenance.graph;
+        let Some(record) = state.selected.and_then(|id| graph.get(&id)) else {
+            state.message = "Pick an operation first".to_string();
+            continue;
+        };
+        let place = graph
+            .order
+            .iter()
+            .position(|id| *id == record.id)
+            .unwrap_or_default();
+        let change = match button {
+            FeatureTreeButton::Up if place == 0 => continue,
+            FeatureTreeButton::Up => OperationChange::Move(place - 1),
+            FeatureTreeButton::Down => OperationChange::Move(place + 1),
+            FeatureTreeButton::Suppress => OperationChange::Suppress(!record.suppressed),
+            FeatureTreeButton::Delete => {
+                state.selected = None;
+                OperationChange::Delete
+            }
+            FeatureTreeButton::Edit => {
+                match parameter(&record.operation) {
+                    Some(value) => {
+                        state.editing = true;
+                        state.entry = format!("{value:.2}");
+                        state.message.clear();
+                    }
+                    None => {
+                        state.message = "Generators are edited where they were made".to_string();
+                    }
+                }
+                continue;
+            }
+        };
+        state.message.clear();
+        changes.write(EditOperation {
+            record: record.id,
+            change,
+        });
+    }
+}
+
+/// Type the picked operation's new depth or offset; Enter runs it again
+/// with it and Escape cancels
+pub fn handle_feature_tree_prompt(
+    mut keyboard_events: EventReader<KeyboardInput>,
+    provenance: Res<ProvenanceResource>,
+    mut state: ResMut<FeatureTreeState>,
+    mut changes: EventWriter<EditOperation>,
+) {
+    if !state.editing {
+        keyboard_events.clear();
+        return;
+    }
+    for event in keyboard_events.read() {
+        match edit_buffer(&mut state.entry, event) {
+            PromptAction::Continue => {}
+            PromptAction::Cancel => state.editing = false,
+            PromptAction::Submit => {
+                state.editing = false;
+                let Ok(value) = state.entry.trim().parse::<f32>() else {
+                    state.message = format!("Not a number: {}", state.entry.trim());
+                    return;
+                };
+                let Some((record, operation)) = state
+                    .selected
+                    .and_then(|id| provenance.graph.get(&id))
+                    .and_then(|record| {
+                        Some((record.id, with_parameter(&record.operation, value)?))
+                    })
+                else {
+                    return;
+                };
+                changes.write(EditOperation {
+                    record,
+                    change: OperationChange::Parameters(operation),
+                });
+                return;
+            }
+        }
+    }
+}
+
+/// Pick the operation pressed in the tree
+pub fn pick_feature_rows(
+    row_query: Query<(&Interaction, &FeatureRowButton), Changed<Interaction>>,
+    mut state: ResMut<FeatureTreeState>,
+) {
+    for (interaction, row) in &row_query {
+        if *interaction == Interaction::Pressed {
+            state.selected = Some(row.0);
+            state.editing = false;
+        }
+    }
+}
+
+/// Refresh the status text and the tree of operations
+pub fn update_feature_tree_panel(
+    mut commands: Commands,
+    state: Res<FeatureTreeState>,
+    provenance: Res<ProvenanceResource>,
+    mut status_query: Query<&mut Text, With<FeatureTreeText>>,
+    list_query: Query<Entity, With<FeatureList>>,
+    theme: Res<UiTheme>,
+) {
+    if !state.is_changed() && !provenance.is_changed() {
+        return;
+    }
+    let graph = &provenance.graph;
+    let history = graph.history();
+    let mut lines = vec![format!("History: {} operation(s)", history.len())];
+    if state.editing {
+        lines.push(format!("Value: {}_", state.entry));
+    }
+    if !state.message.is_empty() {
+        lines.push(state.message.clone());
+    }
+    let status = lines.join("\n");
+    for mut text in &mut status_query {
+        text.0.clone_from(&status);
+    }
+
+    for list in &list_query {
+        commands
+            .entity(list)
+            .despawn_related::<Children>()
+            .with_children(|parent| {
+                for (index, record) in history.iter().enumerate().take(LISTED_OPERATIONS) {
+                    let mut label = format!(
+                        "{}. {}{}",
+                        index + 1,
+                        "  ".repeat(graph.depth_of(&record.id)),
+                        record.operation.summary()
+                    );
+                    if record.suppressed {
+                        label.push_str(" (suppressed)");
+                    }
+                    if let Some(error) = &record.error {
+                        label = format!("{label} - {error}");
+                    }
+                    parent
+                        .spawn((
+                            Button,
+                            FeatureRowButton(record.id),
+                            Node {
+                                padding: UiRect::all(Val::Px(3.0)),
+                                margin: UiRect::top(Val::Px(2.0)),
+                                ..default()
+                            },
+                            BackgroundColor(theme.button_color(state.selected == Some(record.id))),
+                        ))
+                        .with_children(|parent| {
+                            parent.spawn((
+                                Text::new(label),
+                                TextFont {
+                                    font_size: theme.small_font_size,
+                                    ..default()
+                                },
+                            ));
+                        });
+                }
+            });
+    }
+}
@@ -0,0 +1,276 @@
+use bevy::prelude::*;
+use std::path::{Path, PathBuf};
+
+use crate::application::create_mesh_from_solid;
+use crate::domain::geometry::Bounds;
+use crate::domain::{ExternalSource, Point, TierRegistry};
+use crate::infrastructure::link::refresh_import;
+use crate::infrastructure::obj::read_obj;
+use crate::infrastructure::project::PROJECT_EXTENSION;
+use crate::infrastructure::session::SESSION_EXTENSION;
+use crate::infrastructure::stl::read_stl;
+use crate::infrastructure::survey::{import_survey, read_survey, SurveyImportSettings};
+use crate::interface::camera::MainCamera;
+use crate::interface::command_bus::TierRegistryResource;
+use crate::interface::extensions::ExtensionRegistry;
+use crate::interface::file_menu::{
+    ExternalIdResource, GeoreferenceResource, ProjectCommand, ProjectStandardsResource,
+    ProjectState,
+};
+use crate::interface::issues_panel::ValidationState;
+use crate::interface::segment_outlines::{GeometryRegistryResource, SolidId};
+use crate::interface::session_recorder::ReplaySession;
+use crate::interface::ui::ToggleableMesh;
+use crate::interface::underlay::ImportUnderlayEvent;
+
+/// Event requesting a mesh file be imported as a solid
+#[derive(Event)]
+pub struct ImportModelEvent {
+    pub path: PathBuf,
+}
+
+/// Event requesting a DXF drawing be imported into the model, or an
+/// earlier import of it refreshed
+#[derive(Event)]
+pub struct ImportDrawingEvent {
+    pub path: PathBuf,
+}
+
+/// Event requesting surveyed points be imported into the survey tier
+#[derive(Event)]
+pub struct ImportSurveyEvent {
+    pub path: PathBuf,
+}
+
+/// System that routes files dropped on the window to their importer,
+/// including those extensions add, and session recordings to be replayed
+/// a frame at a time
+#[allow(clippy::too_many_arguments)]
+pub fn handle_dropped_files(
+    mut drops: EventReader<FileDragAndDrop>,
+    mut model_events: EventWriter<ImportModelEvent>,
+    mut underlay_events: EventWriter<ImportUnderlayEvent>,
+    mut drawing_events: EventWriter<ImportDrawingEvent>,
+    mut survey_events: EventWriter<ImportSurveyEvent>,
+    mut project: ResMut<ProjectState>,
+    mut project_commands: EventWriter<ProjectCommand>,
+    mut replay_events: EventWriter<ReplaySession>,
+    extensions: Res<ExtensionRegistry>,
+) {
+    for drop in drops.read() {
+        let FileDragAndDrop::DroppedFile { path_buf, .. } = drop else {
+            continue;
+        };
+        let extension = path_buf
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        match extension.as_str() {
+            "obj" | "stl" => {
+                model_events.write(ImportModelEvent {
+                    path: path_buf.clone(),
+                });
+            }
+            "dxf" => {
+                drawing_events.write(ImportDrawingEvent {
+                    path: path_buf.clone(),
+                });
+            }
+            "csv" | "json" => {
+                survey_events.write(ImportSurveyEvent {
+                    path: path_buf.clone(),
+                });
+            }
+            "png" | "jpg" | "jpeg" => {
+                underlay_events.write(ImportUnderlayEvent {
+                    path: path_buf.clone(),
+                    center: Point {
+                        x: 0.0,
+                        y: 0.0,
+                        z: 0.0,
+                    },
+                });
+            }
+            "ifc" => warn!("IFC files can be exported but not yet imported"),
+            SESSION_EXTENSION => {
+                replay_events.write(ReplaySession {
+                    path: path_buf.clone(),
+                    step: true,
+                });
+            }
+            PROJECT_EXTENSION => {
+                if let Some(command) = project.request(ProjectCommand::Open(path_buf.clone())) {
+                    project_commands.write(command);
+                }
+            }
+            _ if extensions.importer_for(&extension).is_some() => {
+                model_events.write(ImportModelEvent {
+                    path: path_buf.clone(),
+                });
+            }
+            _ => warn!("No importer for {}", path_buf.display()),
+        }
+    }
+}
+
+/// System that imports mesh files, and files extensions read, as solids
+/// and frames the camera on them
+#[allow(clippy::too_many_arguments)]
+pub fn import_models(
+    mut commands: Commands,
+    mut events: EventReader<ImportModelEvent>,
+    mut geometry_registry: ResMut<GeometryRegistryResource>,
+    validation_state: Res<ValidationState>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut camera_query: Query<&mut Transform, With<MainCamera>>,
+    extensions: Res<ExtensionRegistry>,
+) {
+    for event in events.read() {
+        let loops = match read_model(&event.path, &extensions) {
+            Ok(loops) => loops,
+            Err(message) => {
+                error!("{message}");
+                continue;
+            }
+        };
+        let registry = &mut geometry_registry.registry;
+        let solid_id =
+            registry.create_solid_from_loops(&loops, &validation_state.pipeline.config.tolerance);
+        let Some(solid) = registry.solids.get(&solid_id) else {
+            continue;
+        };
+        commands.spawn((
+            Mesh3d(meshes.add(create_mesh_from_solid(solid, registry))),
+            MeshMaterial3d(materials.add(StandardMaterial {
+                base_color: Color::srgb(0.75, 0.75, 0.75),
+                perceptual_roughness: 0.6,
+                ..Default::default()
+            })),
+            Transform::default(),
+            ToggleableMesh,
+            SolidId(solid_id),
+        ));
+        info!("Imported {} as solid {solid_id}", event.path.display());
+
+        let points: Vec<Point> = loops.into_iter().flatten().collect();
+        if let (Some(bounds), Ok(mut camera)) =
+            (Bounds::from_points(&points), camera_query.single_mut())
+        {
+            frame_bounds(&mut camera, &bounds);
+        }
+    }
+}
+
+/// System that imports DXF drawings as sketch segments, merging a drawing
+/// imported before into what it made last time
+///
+/// Segments edited here that the drawing also changed are left alone and
+/// reported in the file menu. Layers go into tiers as the project's
+/// standards map them.
+pub fn import_drawings(
+    mut events: EventReader<ImportDrawingEvent>,
+    mut geometry_registry: ResMut<GeometryRegistryResource>,
+    mut external_ids: ResMut<ExternalIdResource>,
+    mut project: ResMut<ProjectState>,
+    standards: Res<ProjectStandardsResource>,
+) {
+    for event in events.read() {
+        let refreshed = external_ids
+            .registry
+            .files(ExternalSource::Dxf)
+            .contains(&event.path.display().to_string().as_str());
+        let result = refresh_import(
+            &event.path,
+            &mut geometry_registry.registry,
+            &mut TierRegistry::create_new(),
+            &mut external_ids.registry,
+            &standards.standards.layers,
+        );
+        let name = event
+            .path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        project.message = match result {
+            Ok(import) if refreshed => {
+                for conflict in &import.conflicts {
+                    warn!("Segment {conflict} was edited here and changed in {name}");
+                }
+                format!(
+                    "Refreshed {name}: {} added, {} updated, {} removed, {} conflicts kept local",
+                    import.segments.len(),
+                    import.updated.len(),
+                    import.removed.len(),
+                    import.conflicts.len()
+                )
+            }
+            Ok(import) => format!("Imported {name}: {} segments", import.segments.len()),
+            Err(error) => error.to_string(),
+        };
+    }
+}
+
+/// System that imports surveyed points into the survey tier, placed in
+/// the model by the project's georeference
+pub fn import_surveys(
+    mut events: EventReader<ImportSurveyEvent>,
+    mut geometry_registry: ResMut<GeometryRegistryResource>,
+    mut tier_registry: ResMut<TierRegistryResource>,
+    mut project: ResMut<ProjectState>,
+    georeference: Res<GeoreferenceResource>,
+) {
+    for event in events.read() {
+        let name = event
+            .path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let georeference = georeference.georeference.unwrap_or_default();
+        project.message = match read_survey(&event.path, &georeference) {
+            Ok(points) => {
+                let import = import_survey(
+                    &mut geometry_registry.registry,
+                    &mut tier_registry.registry,
+                    &points,
+                    &SurveyImportSettings::default(),
+                );
+                format!(
+                    "Imported {name}: {} points, {} breaklines",
+                    import.vertices.len(),
+                    import.breaklines.len()
+                )
+            }
+            Err(error) => error.to_string(),
+        };
+    }
+}
+
+/// Read the faces of an OBJ or STL file, or a file an extension reads
+fn read_model(path: &Path, extensions: &ExtensionRegistry) -> Result<Vec<Vec<Point>>, String> {
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or_default();
+    if extension.eq_ignore_ascii_case("stl") {
+        read_stl(path).map_err(|error| error.to_string())
+    } else if let Some(importer) = extensions.importer_for(extension) {
+        importer.read(path)
+    } else {
+        read_obj(path).map_err(|error| error.to_string())
+    }
+}
+
+/// Move the camera back along its view direction until the bounds fit
+fn frame_bounds(camera: &mut Transform, bounds: &Bounds) {
+    let to_vec = |point: &Point| Vec3::new(point.x, point.y, point.z);
+    let (min, max) = (to_vec(&bounds.min), to_vec(&bounds.max));
+    let center = (min + max) / 2.0;
+    let radius = ((max - min).length() / 2.0).max(0.5);
+    // Enough distance for a sphere of this radius to fill a 45 degree view
+    let distance = radius / (std::f32::consts::FRAC_PI_8).sin();
+    let direction = camera.forward();
+    camera.translation = center - direction * distance;
+    camera.look_at(center, Vec3::Y);
+}
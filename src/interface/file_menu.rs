@@ -0,0 +1,819 @@
+use bevy::ecs::system::SystemParam;
+use bevy::input::keyboard::KeyboardInput;
+use bevy::prelude::*;
+use bevy::window::WindowCloseRequested;
+use std::path::{Path, PathBuf};
+
+use crate::application::create_mesh_from_solid;
+use crate::application::curtain_wall::CURTAIN_WALL_CATEGORY;
+use crate::domain::{
+    ExternalIdMap, ExternalSource, GeometryRegistry, Georeference, UnderlayRegistry,
+};
+use crate::infrastructure::project::{
+    autosave_path, export_project, read_project, write_project, Project, ProjectConstraints,
+    ProjectError, ProjectModel, ProjectSetup, ProjectStandards, PROJECT_EXTENSION,
+};
+use crate::infrastructure::recent::RecentProjects;
+use crate::infrastructure::templates::{load_templates, project_from_template, ProjectTemplate};
+use crate::interface::asset_browser::ComponentLibraryResource;
+use crate::interface::command_bus::{
+    ConstraintSetResource, FamilyRegistryResource, FinishRegistryResource, GridRegistryResource,
+    LevelRegistryResource, ProvenanceResource, ServiceRegistryResource, TierRegistryResource,
+};
+use crate::interface::comments_panel::CommentRegistryResource;
+use crate::interface::curtain_wall::CurtainGridResource;
+use crate::interface::file_drop::ImportDrawingEvent;
+use crate::interface::isolation::HiddenSolids;
+use crate::interface::markup::MarkupRegistryResource;
+use crate::interface::materials::MaterialLibrary;
+use crate::interface::placement::HostedPlacementResource;
+use crate::interface::prompt::{edit_buffer, PromptAction};
+use crate::interface::segment_outlines::{
+    ElementRegistryResource, GeometryRegistryResource, SolidId,
+};
+use crate::interface::settings::PreferencesResource;
+use crate::interface::theme::UiTheme;
+use crate::interface::ui::ToggleableMesh;
+use crate::interface::underlay::{UnderlayId, UnderlayRegistryResource};
+
+/// Most recent projects listed in the menu
+const LISTED_RECENT: usize = 5;
+
+/// Resource holding the IDs imported items have in the models they came
+/// from, saved with the project
+#[derive(Resource)]
+pub struct ExternalIdResource {
+    pub registry: ExternalIdMap,
+}
+
+/// Resource holding the units, materials and layers the project works to,
+/// saved with the project
+#[derive(Resource, Default)]
+pub struct ProjectStandardsResource {
+    pub standards: ProjectStandards,
+}
+
+/// Resource holding where the model sits on the map, saved with the
+/// project and written to the exports that place it
+#[derive(Resource, Default)]
+pub struct GeoreferenceResource {
+    /// The georeference, or None if the model is not placed on the map
+    pub georeference: Option<Georeference>,
+}
+
+/// Everything saved with the project
+#[derive(SystemParam)]
+pub struct ProjectResources<'w> {
+    geometry: Res<'w, GeometryRegistryResource>,
+    elements: Res<'w, ElementRegistryResource>,
+    families: Res<'w, FamilyRegistryResource>,
+    finishes: Res<'w, FinishRegistryResource>,
+    services: Res<'w, ServiceRegistryResource>,
+    curtain_grids: Res<'w, CurtainGridResource>,
+    hosted_placements: Res<'w, HostedPlacementResource>,
+    provenance: Res<'w, ProvenanceResource>,
+    markups: Res<'w, MarkupRegistryResource>,
+    comments: Res<'w, CommentRegistryResource>,
+    external_ids: Res<'w, ExternalIdResource>,
+    constraint_set: Res<'w, ConstraintSetResource>,
+    tiers: Res<'w, TierRegistryResource>,
+    levels: Res<'w, LevelRegistryResource>,
+    grids: Res<'w, GridRegistryResource>,
+    standards: Res<'w, ProjectStandardsResource>,
+    georeference: Res<'w, GeoreferenceResource>,
+}
+
+impl ProjectResources<'_> {
+    /// The geometry and the registries giving it meaning, as written to
+    /// the project file
+    fn model(&self) -> ProjectModel<'_> {
+        ProjectModel {
+            geometry: &self.geometry.registry,
+            elements: &self.elements.registry,
+            families: &self.families.registry,
+            finishes: &self.finishes.registry,
+            services: &self.services.registry,
+            curtain_grids: &self.curtain_grids.registry,
+            hosted_placements: &self.hosted_placements.registry,
+            provenance: &self.provenance.graph,
+        }
+    }
+
+    /// The model's constraints and tiers, as written to the project file
+    fn constraints(&self) -> ProjectConstraints<'_> {
+        ProjectConstraints {
+            constraints: &self.constraint_set.constraints,
+            tiers: &self.tiers.registry,
+            tier_constraints: &self.tiers.constraints,
+        }
+    }
+
+    /// The levels, grids, standards and georeference, as written to the
+    /// project file
+    fn setup(&self) -> ProjectSetup<'_> {
+        ProjectSetup {
+            levels: &self.levels.registry,
+            grids: &self.grids.registry,
+            standards: &self.standards.standards,
+            georeference: self.georeference.georeference.as_ref(),
+        }
+    }
+
+    /// The text of the project file
+    ///
+    /// # Errors
+    /// Returns an error if a registry cannot be written as JSON.
+    pub fn export(&self) -> Result<String, serde_json::Error> {
+        export_project(
+            self.model(),
+            &self.markups.registry,
+            &self.comments.registry,
+            &self.external_ids.registry,
+            self.constraints(),
+            self.setup(),
+        )
+    }
+
+    /// Write the project file, leaving it untouched if the model cannot
+    /// be written as JSON
+    ///
+    /// # Errors
+    /// Returns an error if the model cannot be written as JSON or the file
+    /// cannot be written.
+    pub fn write(&self, path: &Path) -> Result<(), ProjectError> {
+        write_project(
+            path,
+            self.model(),
+            &self.markups.registry,
+            &self.comments.registry,
+            &self.external_ids.registry,
+            self.constraints(),
+            self.setup(),
+        )
+    }
+
+    /// Whether any of them changed other than by being replaced
+    pub fn changed(&self) -> bool {
+        fn edited<T: Resource>(resource: &Res<T>) -> bool {
+            resource.is_changed() && !resource.is_added()
+        }
+        edited(&self.geometry)
+            || edited(&self.elements)
+            || edited(&self.families)
+            || edited(&self.finishes)
+            || edited(&self.services)
+            || edited(&self.curtain_grids)
+            || edited(&self.hosted_placements)
+            || edited(&self.provenance)
+            || edited(&self.markups)
+            || edited(&self.comments)
+            || edited(&self.external_ids)
+            || edited(&self.constraint_set)
+            || edited(&self.tiers)
+            || edited(&self.levels)
+            || edited(&self.grids)
+            || edited(&self.standards)
+            || edited(&self.georeference)
+    }
+}
+
+/// A project operation, applied by `apply_project_commands`
+#[derive(Event, Clone, PartialEq)]
+pub enum ProjectCommand {
+    /// Start a project, empty or as a copy of a template file
+    New(Option<PathBuf>),
+    /// Replace the model with a project file
+    Open(PathBuf),
+    /// Save to the current project file
+    Save,
+    /// Save to a new project file, which becomes the current one
+    SaveAs(PathBuf),
+    /// Close a window, ending the session
+    Close(Entity),
+}
+
+impl ProjectCommand {
+    /// Whether the command throws away the current model
+    fn discards_model(&self) -> bool {
+        matches!(
+            self,
+            ProjectCommand::New(_) | ProjectCommand::Open(_) | ProjectCommand::Close(_)
+        )
+    }
+}
+
+/// Which path the prompt is asking for
+#[derive(Clone, Copy, PartialEq)]
+pub enum PathPrompt {
+    Open,
+    SaveAs,
+}
+
+/// Resource tracking the open project and the file menu
+///
+/// Commands that would throw away unsaved changes must be given twice in
+/// a row: the first only warns.
+#[derive(Resource)]
+pub struct ProjectState {
+    pub path: Option<PathBuf>,
+    pub dirty: bool,
+    pub recent: RecentProjects,
+    pub prompt: Option<PathPrompt>,
+    pub entry: String,
+    pub message: String,
+    /// The templates a new project can be started from
+    pub templates: Vec<ProjectTemplate>,
+    /// The template picked for new projects, or None for an empty project
+    pub template: Option<usize>,
+    confirm: Option<ProjectCommand>,
+}
+
+impl Default for ProjectState {
+    fn default() -> Self {
+        Self {
+            path: None,
+            dirty: false,
+            recent: RecentProjects::load(),
+            prompt: None,
+            entry: String::new(),
+            message: String::new(),
+            templates: load_templates().unwrap_or_else(|error| {
+                warn!("Could not load project templates: {error}");
+                Vec::new()
+            }),
+            template: None,
+            confirm: None,
+        }
+    }
+}
+
+impl ProjectState {
+    /// Let a command through, unless it would discard unsaved changes and
+    /// has not been confirmed by repeating it
+    pub fn request(&mut self, command: ProjectCommand) -> Option<ProjectCommand> {
+        if command.discards_model() && self.dirty && self.confirm.as_ref() != Some(&command) {
+            self.message = "Unsaved changes: repeat to discard them, or save first".to_string();
+            self.confirm = Some(command);
+            return None;
+        }
+        self.confirm = None;
+        Some(command)
+    }
+
+    /// Pick the next template for new projects, after the last going back
+    /// to an empty project
+    fn next_template(&mut self) {
+        self.template = match self.template {
+            None if !self.templates.is_empty() => Some(0),
+            Some(index) if index + 1 < self.templates.len() => Some(index + 1),
+            _ => None,
+        };
+    }
+
+    /// The name of the template picked for new projects
+    fn template_name(&self) -> &str {
+        self.template
+            .and_then(|index| self.templates.get(index))
+            .map_or("empty", |template| template.name.as_str())
+    }
+
+    /// Ask for a path in the menu's prompt
+    fn ask(&mut self, prompt: PathPrompt) {
+        self.prompt = Some(prompt);
+        self.entry.clear();
+        self.message.clear();
+    }
+}
+
+/// Marker component for the file menu buttons
+#[derive(Component, Clone, Copy)]
+pub enum FileMenuButton {
+    New,
+    Template,
+    Open,
+    Save,
+    SaveAs,
+    Refresh,
+}
+
+/// Component for a button that opens a recent project
+#[derive(Component)]
+pub struct RecentProjectButton(pub PathBuf);
+
+/// Marker component for the file menu status text
+#[derive(Component)]
+pub struct FileStatusText;
+
+/// Marker component for the node holding the recent project buttons
+#[derive(Component)]
+pub struct RecentProjectList;
+
+/// Setup the file menu at the bottom right of the screen
+pub fn setup_file_menu(mut commands: Commands, theme: Res<UiTheme>) {
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(10.0),
+                right: Val::Px(10.0),
+                max_width: Val::Px(420.0),
+                flex_direction: FlexDirection::Column,
+                padding: UiRect::all(Val::Px(theme.panel_padding)),
+                ..default()
+            },
+            BackgroundColor(theme.panel),
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn(Node {
+                    flex_direction: FlexDirection::Row,
+                    margin: UiRect::bottom(Val::Px(5.0)),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    for (button, label) in [
+                        (FileMenuButton::New, "New"),
+                        (FileMenuButton::Template, "Template"),
+                        (FileMenuButton::Open, "Open"),
+                        (FileMenuButton::Save, "Save"),
+                        (FileMenuButton::SaveAs, "Save As"),
+                        (FileMenuButton::Refresh, "Refresh Imports"),
+                    ] {
+                        parent
+                            .spawn((
+                                Button,
+                                button,
+                                Node {
+                                    padding: UiRect::all(Val::Px(theme.button_padding)),
+                                    margin: UiRect::right(Val::Px(3.0)),
+                                    ..default()
+                                },
+                                BackgroundColor(theme.button),
+                            ))
+                            .with_children(|parent| {
+                                parent.spawn(Text::new(label));
+                            });
+                    }
+                });
+
+            parent.spawn((
+                Text::new(""),
+                TextFont {
+                    font_size: theme.small_font_size,
+                    ..default()
+                },
+                FileStatusText,
+            ));
+
+            parent.spawn((
+                Node {
+                    flex_direction: FlexDirection::Column,
+                    ..default()
+                },
+                RecentProjectList,
+            ));
+        });
+}
+
+/// Handle the file menu and recent project buttons
+///
+/// New starts a project from the template picked with Template, which
+/// cycles through the templates folder and an empty project. Refresh
+/// Imports imports every DXF drawing the model took items from again,
+/// merging what changed.
+pub fn handle_file_menu_buttons(
+    menu_query: Query<(&Interaction, &FileMenuButton), Changed<Interaction>>,
+    recent_query: Query<(&Interaction, &RecentProjectButton), Changed<Interaction>>,
+    external_ids: Res<ExternalIdResource>,
+    mut project: ResMut<ProjectState>,
+    mut project_commands: EventWriter<ProjectCommand>,
+    mut drawing_events: EventWriter<ImportDrawingEvent>,
+) {
+    for (interaction, button) in &menu_query {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        let command = match button {
+            FileMenuButton::New => {
+                let template = project
+                    .template
+                    .and_then(|index| project.templates.get(index))
+                    .map(|template| template.path.clone());
+                project.request(ProjectCommand::New(template))
+            }
+            FileMenuButton::Template => {
+                project.next_template();
+                None
+            }
+            FileMenuButton::Open => {
+                project.ask(PathPrompt::Open);
+                None
+            }
+            FileMenuButton::Save if project.path.is_none() => {
+                project.ask(PathPrompt::SaveAs);
+                None
+            }
+            FileMenuButton::Save => Some(ProjectCommand::Save),
+            FileMenuButton::SaveAs => {
+                project.ask(PathPrompt::SaveAs);
+                None
+            }
+            FileMenuButton::Refresh => {
+                let files = external_ids.registry.files(ExternalSource::Dxf);
+                if files.is_empty() {
+                    project.message = "No imported drawings to refresh".to_string();
+                }
+                for file in files {
+                    drawing_events.write(ImportDrawingEvent {
+                        path: PathBuf::from(file),
+                    });
+                }
+                None
+            }
+        };
+        if let Some(command) = command {
+            project_commands.write(command);
+        }
+    }
+    for (interaction, button) in &recent_query {
+        if *interaction == Interaction::Pressed {
+            if let Some(command) = project.request(ProjectCommand::Open(button.0.clone())) {
+                project_commands.write(command);
+            }
+        }
+    }
+}
+
+/// Type a path into the prompt; Enter confirms and Escape cancels
+pub fn handle_path_prompt(
+    mut keyboard_events: EventReader<KeyboardInput>,
+    mut project: ResMut<ProjectState>,
+    mut project_commands: EventWriter<ProjectCommand>,
+) {
+    let Some(prompt) = project.prompt else {
+        keyboard_events.clear();
+        return;
+    };
+    for event in keyboard_events.read() {
+        match edit_buffer(&mut project.entry, event) {
+            PromptAction::Continue => {}
+            PromptAction::Cancel => {
+                project.prompt = None;
+                project.message.clear();
+            }
+            PromptAction::Submit => {
+                let path = PathBuf::from(project.entry.trim());
+                let command = match prompt {
+                    PathPrompt::Open => project.request(ProjectCommand::Open(path)),
+                    PathPrompt::SaveAs => Some(ProjectCommand::SaveAs(path)),
+                };
+                if let Some(command) = command {
+                    project.prompt = None;
+                    project_commands.write(command);
+                }
+            }
+        }
+    }
+}
+
+/// Warn before closing a window with unsaved changes
+pub fn handle_window_close(
+    mut close_events: EventReader<WindowCloseRequested>,
+    mut project: ResMut<ProjectState>,
+    mut project_commands: EventWriter<ProjectCommand>,
+) {
+    for event in close_events.read() {
+        if let Some(command) = project.request(ProjectCommand::Close(event.window)) {
+            project_commands.write(command);
+        }
+    }
+}
+
+/// Apply project commands: replace, save or close the model
+#[allow(clippy::too_many_arguments)]
+pub fn apply_project_commands(
+    mut commands: Commands,
+    mut project_commands: EventReader<ProjectCommand>,
+    resources: ProjectResources,
+    mut project: ResMut<ProjectState>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    solid_entities: Query<Entity, With<SolidId>>,
+) {
+    for command in project_commands.read() {
+        let replacement = match command {
+            ProjectCommand::New(None) => {
+                project.path = None;
+                project.message = "New project".to_string();
+                Some(Project::create_new())
+            }
+            ProjectCommand::New(Some(template)) => match project_from_template(template) {
+                Ok(started) => {
+                    project.path = None;
+                    project.message = format!("New project from {}", template.display());
+                    Some(started)
+                }
+                Err(error) => {
+                    project.message = error.to_string();
+                    None
+                }
+            },
+            ProjectCommand::Open(path) => match read_project(path) {
+                Ok(opened) => {
+                    project.path = Some(path.clone());
+                    project.recent.add(path);
+                    project.message = format!("Opened {}", path.display());
+                    Some(opened)
+                }
+                Err(error) => {
+                    if !path.exists() {
+                        project.recent.remove(path);
+                    }
+                    project.message = error.to_string();
+                    None
+                }
+            },
+            ProjectCommand::Save | ProjectCommand::SaveAs(_) => {
+                let path = match command {
+                    ProjectCommand::SaveAs(path) => path.with_extension(PROJECT_EXTENSION),
+                    _ => project.path.clone().unwrap_or_default(),
+                };
+                match resources.write(&path) {
+                    Ok(()) => {
+                        project.dirty = false;
+                        project.recent.add(&path);
+                        project.message = format!("Saved {}", path.display());
+                        project.path = Some(path);
+                    }
+                    Err(error) => project.message = error.to_string(),
+                }
+                None
+            }
+            ProjectCommand::Close(window) => {
+                commands.entity(*window).despawn();
+                None
+            }
+        };
+        if let Err(error) = project.recent.save() {
+            warn!("Could not save recent projects: {error}");
+        }
+
+        let Some(replacement) = replacement else {
+            continue;
+        };
+        replace_project(
+            &mut commands,
+            replacement,
+            &solid_entities,
+            &mut meshes,
+            &mut materials,
+        );
+        project.dirty = false;
+    }
+}
+
+/// Work to the standards of a project that has replaced the model: show
+/// lengths in its units and its materials in its colors
+pub fn apply_project_standards(
+    standards: Res<ProjectStandardsResource>,
+    mut preferences: ResMut<PreferencesResource>,
+    mut library: ResMut<MaterialLibrary>,
+) {
+    if !standards.is_changed() {
+        return;
+    }
+    let standards = &standards.standards;
+    if let Some(units) = standards.units {
+        if preferences.preferences.units != units {
+            preferences.preferences.units = units;
+        }
+    }
+    for material in &standards.materials {
+        let [red, green, blue] = material.color;
+        library.set_color(&material.name, Color::srgb(red, green, blue));
+    }
+}
+
+/// Replace everything saved with the project by a project's contents,
+/// respawning the solids' meshes
+///
+/// What belonged to the last model but is not saved with a project is
+/// cleared: the underlays, the hidden solids, the placed component
+/// instances and the definitions curtain walls made, leaving the
+/// definitions read from the component folder. Replaced resources read
+/// as added, so they do not count as edits.
+pub(crate) fn replace_project(
+    commands: &mut Commands,
+    project: Project,
+    solid_entities: &Query<Entity, With<SolidId>>,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+) {
+    for entity in solid_entities {
+        commands.entity(entity).despawn();
+    }
+    spawn_solid_meshes(commands, &project.geometry, meshes, materials);
+    commands.insert_resource(GeometryRegistryResource {
+        registry: project.geometry,
+    });
+    commands.insert_resource(ElementRegistryResource {
+        registry: project.elements,
+    });
+    commands.insert_resource(FamilyRegistryResource {
+        registry: project.families,
+    });
+    commands.insert_resource(FinishRegistryResource {
+        registry: project.finishes,
+    });
+    commands.insert_resource(ServiceRegistryResource {
+        registry: project.services,
+    });
+    commands.insert_resource(CurtainGridResource {
+        registry: project.curtain_grids,
+    });
+    commands.insert_resource(HostedPlacementResource {
+        registry: project.hosted_placements,
+    });
+    commands.insert_resource(ProvenanceResource {
+        graph: project.provenance,
+    });
+    commands.insert_resource(MarkupRegistryResource {
+        registry: project.markups,
+    });
+    commands.insert_resource(CommentRegistryResource {
+        registry: project.comments,
+    });
+    commands.insert_resource(ExternalIdResource {
+        registry: project.external_ids,
+    });
+    commands.insert_resource(ConstraintSetResource {
+        constraints: project.constraints,
+    });
+    commands.insert_resource(TierRegistryResource {
+        registry: project.tiers,
+        constraints: project.tier_constraints,
+    });
+    commands.insert_resource(LevelRegistryResource {
+        registry: project.levels,
+    });
+    commands.insert_resource(GridRegistryResource {
+        registry: project.grids,
+    });
+    commands.insert_resource(ProjectStandardsResource {
+        standards: project.standards,
+    });
+    commands.insert_resource(GeoreferenceResource {
+        georeference: project.georeference,
+    });
+    commands.insert_resource(UnderlayRegistryResource {
+        registry: UnderlayRegistry::create_new(),
+    });
+    commands.insert_resource(HiddenSolids::default());
+    commands.queue(|world: &mut World| {
+        let underlays: Vec<Entity> = world
+            .query_filtered::<Entity, With<UnderlayId>>()
+            .iter(world)
+            .collect();
+        for entity in underlays {
+            world.despawn(entity);
+        }
+        if let Some(mut library) = world.get_resource_mut::<ComponentLibraryResource>() {
+            library.library.instances.clear();
+            library
+                .library
+                .definitions
+                .retain(|_, definition| definition.category != CURTAIN_WALL_CATEGORY);
+        }
+    });
+}
+
+/// Spawn a mesh for every solid of a model that has replaced the last
+pub(crate) fn spawn_solid_meshes(
+    commands: &mut Commands,
+    registry: &GeometryRegistry,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+) {
+    let material = materials.add(StandardMaterial {
+        base_color: Color::srgb(0.75, 0.75, 0.75),
+        perceptual_roughness: 0.6,
+        ..Default::default()
+    });
+    let mut solids: Vec<_> = registry.solids.solids.values().collect();
+    solids.sort_by_key(|solid| solid.id);
+    for solid in solids {
+        commands.spawn((
+            Mesh3d(meshes.add(create_mesh_from_solid(solid, registry))),
+            MeshMaterial3d(material.clone()),
+            Transform::default(),
+            ToggleableMesh,
+            SolidId(solid.id),
+        ));
+    }
+}
+
+/// Mark the project dirty when anything saved with it changes outside of
+/// loading
+pub fn track_unsaved_changes(resources: ProjectResources, mut project: ResMut<ProjectState>) {
+    if resources.changed() && !project.dirty {
+        project.dirty = true;
+    }
+}
+
+/// Write unsaved changes to the autosave file at the preferred interval
+pub fn autosave_project(
+    time: Res<Time>,
+    preferences: Res<PreferencesResource>,
+    resources: ProjectResources,
+    mut project: ResMut<ProjectState>,
+    mut seconds_since_save: Local<f64>,
+) {
+    let minutes = preferences.preferences.autosave_minutes;
+    if minutes == 0 || !project.dirty {
+        *seconds_since_save = 0.0;
+        return;
+    }
+    *seconds_since_save += time.delta_secs_f64();
+    if *seconds_since_save < f64::from(minutes) * 60.0 {
+        return;
+    }
+    *seconds_since_save = 0.0;
+    let Some(path) = autosave_path(project.path.as_deref()) else {
+        return;
+    };
+    if let Some(folder) = path.parent() {
+        // A failure here surfaces as the write error below
+        let _ = std::fs::create_dir_all(folder);
+    }
+    project.message = match resources.write(&path) {
+        Ok(()) => format!("Autosaved to {}", path.display()),
+        Err(error) => format!("Autosave failed: {error}"),
+    };
+}
+
+/// Refresh the status text and recent project buttons
+pub fn update_file_menu(
+    mut commands: Commands,
+    project: Res<ProjectState>,
+    mut status_query: Query<&mut Text, With<FileStatusText>>,
+    list_query: Query<Entity, With<RecentProjectList>>,
+    theme: Res<UiTheme>,
+) {
+    if !project.is_changed() {
+        return;
+    }
+    let name = project
+        .path
+        .as_ref()
+        .map_or_else(|| "Untitled".to_string(), |path| path.display().to_string());
+    let mut lines = vec![format!("{name}{}", if project.dirty { " *" } else { "" })];
+    match project.prompt {
+        Some(PathPrompt::Open) => lines.push(format!("Open: {}_", project.entry)),
+        Some(PathPrompt::SaveAs) => lines.push(format!("Save as: {}_", project.entry)),
+        None => lines.push(format!(
+            "New projects start from {}",
+            project.template_name()
+        )),
+    }
+    if !project.message.is_empty() {
+        lines.push(project.message.clone());
+    }
+    let status = lines.join("\n");
+    for mut text in &mut status_query {
+        text.0.clone_from(&status);
+    }
+
+    for list in &list_query {
+        commands
+            .entity(list)
+            .despawn_related::<Children>()
+            .with_children(|parent| {
+                for path in project.recent.paths.iter().take(LISTED_RECENT) {
+                    let label = path.file_name().map_or_else(
+                        || path.display().to_string(),
+                        |name| name.to_string_lossy().into_owned(),
+                    );
+                    parent
+                        .spawn((
+                            Button,
+                            RecentProjectButton(path.clone()),
+                            Node {
+                                padding: UiRect::all(Val::Px(3.0)),
+                                margin: UiRect::top(Val::Px(2.0)),
+                                ..default()
+                            },
+                            BackgroundColor(theme.button),
+                        ))
+                        .with_children(|parent| {
+                            parent.spawn((
+                                Text::new(label),
+                                TextFont {
+                                    font_size: 12.0,
+                                    ..default()
+                                },
+                            ));
+                        });
+                }
+            });
+    }
+}
@@ -0,0 +1,267 @@
+use bevy::prelude::*;
+use std::collections::HashMap;
+
+use crate::domain::{
+    CommentRegistry, ComponentLibrary, CurtainGridRegistry, ElementRegistry, ExternalIdMap,
+    FamilyRegistry, FinishRegistry, GeometryRegistry, GridRegistry, HostedPlacementRegistry,
+    LevelRegistry, MarkupRegistry, ProvenanceGraph, ServiceRegistry, TierRegistry,
+    UnderlayRegistry,
+};
+use crate::interface::asset_browser::ComponentLibraryResource;
+use crate::interface::carbon_panel::{calculate_model_carbon, CarbonState};
+use crate::interface::command_bus::{
+    add_constraints, add_expression_constraints, add_sketch_paths, apply_vertex_transforms,
+    change_operations, convert_masses, create_walls, edit_grids, export_stl_files,
+    generate_finishes, keep_expressions_satisfied, keep_families_hosted, keep_finishes_bounded,
+    keep_operations_current, move_vertices, place_families, route_services, run_operations,
+    set_face_materials, AddConstraint, AddExpressionConstraint, AddGridLayout, AddSketchPath,
+    ConstraintSetResource, ConvertMass, CreateGrid, CreateWall, CutSolid, EditGrid, EditOperation,
+    ExportStl, ExtrudeFace, FamilyRegistryResource, FinishRegistryResource, GenerateFinishes,
+    GridRegistryResource, LevelRegistryResource, MoveVertex, PlaceFamily, ProvenanceResource,
+    RemoveConstraint, RouteService, ServiceRegistryResource, SetFaceMaterial, SetLevel,
+    SolidsEdited, TierRegistryResource, TransformVertices,
+};
+use crate::interface::comments_panel::CommentRegistryResource;
+use crate::interface::curtain_wall::{
+    create_curtain_grids, regenerate_changed_curtain_grids, CreateCurtainGrid, CurtainGridResource,
+};
+use crate::interface::daylight_panel::{check_model_daylight, DaylightState};
+use crate::interface::egress_panel::{analyze_model_egress, EgressState};
+use crate::interface::extensions::{
+    export_with_extensions, install_extension_rules, run_extension_commands, ExportWithExtension,
+    ExtensionRegistry, RunExtensionCommand,
+};
+use crate::interface::file_menu::{
+    ExternalIdResource, GeoreferenceResource, ProjectStandardsResource,
+};
+use crate::interface::issues_panel::{validate_after_edits, ValidationState};
+use crate::interface::markup::MarkupRegistryResource;
+use crate::interface::placement::{
+    keep_components_hosted, place_components, place_components_on_faces, DistributeOnFace,
+    HostedPlacementResource, PlaceComponent, PlaceOnFace,
+};
+use crate::interface::program_panel::{check_program, ProgramState};
+use crate::interface::rules_panel::{run_rules, RuleState};
+use crate::interface::segment_outlines::{ElementRegistryResource, GeometryRegistryResource};
+use crate::interface::underlay::UnderlayRegistryResource;
+
+/// The systems that carry out model commands
+///
+/// Whatever sends a command, whether a button, a shortcut, a script or the
+/// network API, sends the same event, and these systems handle it before
+/// the model is analyzed.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ModelCommandSet;
+
+/// The systems that check and analyze the model after it changes
+///
+/// Interface systems that feed these run before the set, and the panels
+/// showing their results run after it.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ModelAnalysisSet;
+
+/// A plugin for the model resources and the systems that work on them,
+/// with no window, rendering or UI
+///
+/// `InterfacePlugin` builds on it. On its own it needs nothing beyond
+/// `App::new()`, or `MinimalPlugins` for time, so tests can fill the
+/// registries, call `App::update` and check the resources afterwards.
+pub struct HarmonyHeadlessPlugin;
+
+impl Plugin for HarmonyHeadlessPlugin {
+    fn build(&self, app: &mut App) {
+        add_model_command_events(app);
+        insert_model_resources(app);
+        app.init_resource::<ExtensionRegistry>()
+            .configure_sets(Update, ModelCommandSet.before(ModelAnalysisSet))
+            .add_systems(Startup, install_extension_rules)
+            .add_systems(Update, run_extension_commands.before(ModelCommandSet))
+            .add_systems(
+                Update,
+                (
+                    create_walls,
+                    place_families,
+                    generate_finishes,
+                    route_services,
+                    convert_masses,
+                    edit_grids,
+                    run_operations,
+                    change_operations,
+                    add_sketch_paths,
+                    move_vertices,
+                    apply_vertex_transforms,
+                    keep_families_hosted,
+                    keep_finishes_bounded,
+                    keep_operations_current,
+                    add_constraints,
+                    add_expression_constraints,
+                    keep_expressions_satisfied,
+                    set_face_materials,
+                    export_stl_files,
+                    export_with_extensions,
+                )
+                    .chain()
+                    .in_set(ModelCommandSet),
+            )
+            .add_systems(
+                Update,
+                (
+                    create_curtain_grids,
+                    regenerate_changed_curtain_grids,
+                    place_components,
+                    place_components_on_faces,
+                    keep_components_hosted,
+                )
+                    .chain()
+                    .after(export_with_extensions)
+                    .in_set(ModelCommandSet),
+            )
+            .add_systems(
+                Update,
+                (
+                    validate_after_edits,
+                    check_program,
+                    analyze_model_egress,
+                    check_model_daylight,
+                    run_rules,
+                    calculate_model_carbon,
+                )
+                    .in_set(ModelAnalysisSet),
+            );
+    }
+}
+
+/// Insert the model's registries, empty, and the state the analyses keep
+fn insert_model_resources(app: &mut App) {
+    app.insert_resource(GeometryRegistryResource {
+        registry: GeometryRegistry::create_new(),
+    })
+    .insert_resource(ElementRegistryResource {
+        registry: ElementRegistry::create_new(),
+    })
+    .insert_resource(UnderlayRegistryResource {
+        registry: UnderlayRegistry::create_new(),
+    })
+    .insert_resource(MarkupRegistryResource {
+        registry: MarkupRegistry::create_new(),
+    })
+    .insert_resource(CommentRegistryResource {
+        registry: CommentRegistry::create_new(),
+    })
+    .insert_resource(ExternalIdResource {
+        registry: ExternalIdMap::create_new(),
+    })
+    .insert_resource(ProjectStandardsResource::default())
+    .insert_resource(GeoreferenceResource::default())
+    .insert_resource(ValidationState::default())
+    .insert_resource(ProgramState::default())
+    .insert_resource(EgressState::default())
+    .insert_resource(DaylightState::default())
+    .insert_resource(RuleState::default())
+    .insert_resource(CarbonState::default())
+    .insert_resource(ConstraintSetResource::default())
+    .insert_resource(FamilyRegistryResource {
+        registry: FamilyRegistry::create_new(),
+    })
+    .insert_resource(FinishRegistryResource {
+        registry: FinishRegistry::create_new(),
+    })
+    .insert_resource(ServiceRegistryResource {
+        registry: ServiceRegistry::create_new(),
+    })
+    .insert_resource(GridRegistryResource {
+        registry: GridRegistry::create_new(),
+    })
+    .insert_resource(ComponentLibraryResource {
+        library: ComponentLibrary::create_new(),
+    })
+    .insert_resource(CurtainGridResource {
+        registry: CurtainGridRegistry::create_new(),
+    })
+    .insert_resource(HostedPlacementResource {
+        registry: HostedPlacementRegistry::create_new(),
+    })
+    .insert_resource(ProvenanceResource {
+        graph: ProvenanceGraph::create_new(),
+    })
+    .insert_resource(LevelRegistryResource {
+        registry: LevelRegistry::create_new(),
+    })
+    .insert_resource(TierRegistryResource {
+        registry: TierRegistry::create_new(),
+        constraints: HashMap::new(),
+    });
+}
+
+/// Register the events the model commands are sent as
+fn add_model_command_events(app: &mut App) {
+    app.add_event::<CreateWall>()
+        .add_event::<PlaceFamily>()
+        .add_event::<GenerateFinishes>()
+        .add_event::<RouteService>()
+        .add_event::<ConvertMass>()
+        .add_event::<CreateGrid>()
+        .add_event::<AddGridLayout>()
+        .add_event::<EditGrid>()
+        .add_event::<ExtrudeFace>()
+        .add_event::<CutSolid>()
+        .add_event::<EditOperation>()
+        .add_event::<AddSketchPath>()
+        .add_event::<MoveVertex>()
+        .add_event::<TransformVertices>()
+        .add_event::<RemoveConstraint>()
+        .add_event::<AddConstraint>()
+        .add_event::<SetLevel>()
+        .add_event::<AddExpressionConstraint>()
+        .add_event::<SetFaceMaterial>()
+        .add_event::<CreateCurtainGrid>()
+        .add_event::<PlaceComponent>()
+        .add_event::<PlaceOnFace>()
+        .add_event::<DistributeOnFace>()
+        .add_event::<ExportStl>()
+        .add_event::<RunExtensionCommand>()
+        .add_event::<ExportWithExtension>()
+        .add_event::<SolidsEdited>();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::Point;
+
+    #[test]
+    fn commands_change_the_registries_without_a_window() {
+        let mut app = App::new();
+        app.add_plugins(HarmonyHeadlessPlugin);
+        app.update();
+        let solids = |app: &App| {
+            app.world()
+                .resource::<GeometryRegistryResource>()
+                .registry
+                .solids
+                .solids
+                .len()
+        };
+        let before = solids(&app);
+
+        app.world_mut().send_event(CreateWall {
+            start: Point {
+                x: 0.0,
+                y: 0.0,
+                z: 0.0,
+            },
+            end: Point {
+                x: 4.0,
+                y: 0.0,
+                z: 0.0,
+            },
+            thickness: 0.2,
+            height: 3.0,
+            assembly: None,
+            per_layer: false,
+        });
+        app.update();
+
+        assert_eq!(solids(&app), before + 1);
+    }
+}
@@ -0,0 +1,226 @@
+use bevy::prelude::*;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::application::create_colored_mesh_from_solid;
+use crate::interface::segment_outlines::{GeometryRegistryResource, SolidId};
+use crate::interface::theme::UiTheme;
+use crate::interface::ViewColumn;
+
+/// Number of swatches in the legend's color scale
+const LEGEND_STEPS: usize = 8;
+/// Depth bias drawing the heat map over the solids' own faces
+const HEAT_MAP_DEPTH_BIAS: f32 = 50.0;
+
+/// The heat map color of a value from zero to one, green for low through
+/// red for high
+#[must_use]
+pub fn heat_color(value: f32) -> Color {
+    let t = value.clamp(0.0, 1.0);
+    Color::srgb((2.0 * t).min(1.0), (2.0 - 2.0 * t).min(1.0), 0.2)
+}
+
+/// Resource holding the values an analysis shades the model with
+///
+/// One heat map is shown at a time: an analysis fills it with values from
+/// zero to one, per solid or per face, and clears it when it is turned
+/// off. The solids keep their meshes and materials; the heat map is drawn
+/// over them in vertex-colored copies.
+#[derive(Resource, Default)]
+pub struct HeatMap {
+    /// What the colors show, the legend's title
+    pub title: String,
+    /// Labels of the low and high ends of the legend
+    pub low_label: String,
+    pub high_label: String,
+    /// Values of whole solids
+    pub solids: HashMap<Uuid, f32>,
+    /// Values of single faces by polygon, winning over their solid's
+    pub faces: HashMap<Uuid, f32>,
+}
+
+impl HeatMap {
+    /// Show values of whole solids, replacing whatever was shown
+    pub fn show_solids(
+        &mut self,
+        title: &str,
+        low_label: &str,
+        high_label: &str,
+        solids: impl IntoIterator<Item = (Uuid, f32)>,
+    ) {
+        self.title = title.to_string();
+        self.low_label = low_label.to_string();
+        self.high_label = high_label.to_string();
+        self.solids = solids.into_iter().collect();
+        self.faces.clear();
+    }
+
+    /// Stop showing values
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Whether there are values to show
+    #[must_use]
+    pub fn is_shown(&self) -> bool {
+        !self.solids.is_empty() || !self.faces.is_empty()
+    }
+
+    /// The value of a face of a solid, if it or its solid has one
+    #[must_use]
+    pub fn face_value(&self, solid_id: &Uuid, polygon_id: &Uuid) -> Option<f32> {
+        self.faces
+            .get(polygon_id)
+            .or_else(|| self.solids.get(solid_id))
+            .copied()
+    }
+}
+
+/// Marker component for an entity drawing a solid's heat map colors
+#[derive(Component)]
+pub struct HeatMapOverlay;
+
+/// Marker component for the heat map legend
+#[derive(Component)]
+pub struct HeatMapLegend;
+
+/// Marker component for the legend's title and labels
+#[derive(Component)]
+pub struct HeatMapLegendText;
+
+/// Setup the hidden heat map legend in the view column
+pub fn setup_heat_map_legend(
+    mut commands: Commands,
+    column_query: Query<Entity, With<ViewColumn>>,
+    theme: Res<UiTheme>,
+) {
+    let Ok(column) = column_query.single() else {
+        return;
+    };
+    commands.entity(column).with_children(|parent| {
+        parent
+            .spawn((
+                Node {
+                    display: Display::None,
+                    flex_direction: FlexDirection::Column,
+                    padding: UiRect::all(Val::Px(theme.panel_padding)),
+                    row_gap: Val::Px(4.0),
+                    ..default()
+                },
+                BackgroundColor(theme.panel),
+                HeatMapLegend,
+            ))
+            .with_children(|parent| {
+                parent
+                    .spawn(Node {
+                        flex_direction: FlexDirection::Row,
+                        ..default()
+                    })
+                    .with_children(|parent| {
+                        for step in 0..LEGEND_STEPS {
+                            #[allow(clippy::cast_precision_loss)]
+                            let value = step as f32 / (LEGEND_STEPS - 1) as f32;
+                            parent.spawn((
+                                Node {
+                                    width: Val::Px(20.0),
+                                    height: Val::Px(12.0),
+                                    ..default()
+                                },
+                                BackgroundColor(heat_color(value)),
+                            ));
+                        }
+                    });
+
+                parent.spawn((
+                    Text::new(""),
+                    TextFont {
+                        font_size: theme.small_font_size,
+                        ..default()
+                    },
+                    HeatMapLegendText,
+                ));
+            });
+    });
+}
+
+/// Draw the heat map over the solids it has values for
+///
+/// The colored copies are children of the solids' entities, so they move,
+/// explode and hide with them, and are rebuilt when the heat map or a
+/// solid's mesh changes.
+#[allow(clippy::too_many_arguments)]
+pub fn draw_heat_map(
+    mut commands: Commands,
+    heat_map: Res<HeatMap>,
+    geometry_registry: Res<GeometryRegistryResource>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    solid_query: Query<(Entity, &SolidId)>,
+    edited_query: Query<(), (With<SolidId>, Changed<Mesh3d>)>,
+    overlay_query: Query<Entity, With<HeatMapOverlay>>,
+    mut material: Local<Option<Handle<StandardMaterial>>>,
+) {
+    if !heat_map.is_changed() && edited_query.is_empty() {
+        return;
+    }
+    for overlay in &overlay_query {
+        commands.entity(overlay).despawn();
+    }
+    if !heat_map.is_shown() {
+        return;
+    }
+    // White, so the vertex colors show as they are
+    let material = material
+        .get_or_insert_with(|| {
+            materials.add(StandardMaterial {
+                base_color: Color::WHITE,
+                perceptual_roughness: 0.9,
+                depth_bias: HEAT_MAP_DEPTH_BIAS,
+                ..Default::default()
+            })
+        })
+        .clone();
+    let registry = &geometry_registry.registry;
+    for (entity, solid_id) in &solid_query {
+        let Some(solid) = registry.solids.get(&solid_id.0) else {
+            continue;
+        };
+        let Some(mesh) = create_colored_mesh_from_solid(solid, registry, |polygon_id| {
+            let value = heat_map.face_value(&solid_id.0, polygon_id)?;
+            Some(heat_color(value).to_linear().to_f32_array())
+        }) else {
+            continue;
+        };
+        commands.spawn((
+            HeatMapOverlay,
+            Mesh3d(meshes.add(mesh)),
+            MeshMaterial3d(material.clone()),
+            Transform::default(),
+            ChildOf(entity),
+        ));
+    }
+}
+
+/// Show the legend while a heat map is shown
+pub fn update_heat_map_legend(
+    heat_map: Res<HeatMap>,
+    mut legend_query: Query<&mut Node, With<HeatMapLegend>>,
+    mut text_query: Query<&mut Text, With<HeatMapLegendText>>,
+) {
+    if !heat_map.is_changed() {
+        return;
+    }
+    for mut node in &mut legend_query {
+        node.display = if heat_map.is_shown() {
+            Display::Flex
+        } else {
+            Display::None
+        };
+    }
+    for mut text in &mut text_query {
+        text.0 = format!(
+            "{}\n{} to {}",
+            heat_map.title, heat_map.low_label, heat_map.high_label
+        );
+    }
+}
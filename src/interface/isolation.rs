@@ -0,0 +1,128 @@
+use bevy::prelude::*;
+use std::collections::BTreeSet;
+use uuid::Uuid;
+
+use crate::interface::segment_outlines::GeometryRegistryResource;
+use crate::interface::selection::SelectionState;
+
+/// Key hiding the selected solid, or with Alt showing every hidden solid
+const HIDE_KEY: KeyCode = KeyCode::KeyH;
+/// Key isolating the selected solid, or with Alt ending the isolation
+const ISOLATE_KEY: KeyCode = KeyCode::KeyI;
+
+/// A change to which solids are hidden, applied by
+/// `apply_visibility_commands`
+#[derive(Event, Clone, Copy, PartialEq, Eq)]
+pub enum VisibilityCommand {
+    /// Hide every solid but the selected one
+    Isolate,
+    /// Return to what was hidden before the latest isolation
+    EndIsolation,
+    /// Hide the selected solid
+    HideSelected,
+    /// Show every hidden solid, ending any isolation
+    UnhideAll,
+}
+
+/// Resource holding the solids hidden on top of the surface and phase
+/// toggles
+///
+/// Hiding is temporary view state: it is not saved with the project and
+/// never touches the registries.
+#[derive(Resource, Default)]
+pub struct HiddenSolids {
+    /// The solids hidden now
+    pub hidden: BTreeSet<Uuid>,
+    /// What was hidden before each isolation, the latest last
+    pub isolations: Vec<BTreeSet<Uuid>>,
+}
+
+impl HiddenSolids {
+    /// Whether a solid is shown, as far as hiding goes
+    #[must_use]
+    pub fn shows(&self, solid_id: &Uuid) -> bool {
+        !self.hidden.contains(solid_id)
+    }
+
+    /// Hide every solid but one, remembering what was hidden before
+    pub fn isolate(&mut self, keep: Uuid, solids: impl Iterator<Item = Uuid>) {
+        let others = solids.filter(|solid_id| *solid_id != keep).collect();
+        self.isolations
+            .push(std::mem::replace(&mut self.hidden, others));
+    }
+
+    /// Restore what was hidden before the latest isolation
+    ///
+    /// Returns false if nothing is isolated.
+    pub fn end_isolation(&mut self) -> bool {
+        let Some(previous) = self.isolations.pop() else {
+            return false;
+        };
+        self.hidden = previous;
+        true
+    }
+
+    /// Show everything and forget every isolation
+    pub fn unhide_all(&mut self) {
+        self.hidden.clear();
+        self.isolations.clear();
+    }
+}
+
+/// Send visibility commands for their keys: H hides the selection, Alt+H
+/// shows everything, I isolates the selection and Alt+I ends the isolation
+pub fn send_visibility_shortcuts(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut commands: EventWriter<VisibilityCommand>,
+) {
+    let alt = keyboard_input.any_pressed([KeyCode::AltLeft, KeyCode::AltRight]);
+    if keyboard_input.just_pressed(HIDE_KEY) {
+        commands.write(if alt {
+            VisibilityCommand::UnhideAll
+        } else {
+            VisibilityCommand::HideSelected
+        });
+    }
+    if keyboard_input.just_pressed(ISOLATE_KEY) {
+        commands.write(if alt {
+            VisibilityCommand::EndIsolation
+        } else {
+            VisibilityCommand::Isolate
+        });
+    }
+}
+
+/// Apply the visibility commands sent this frame
+///
+/// Commands about the selection act on the solid it was picked through, so
+/// a selected face isolates or hides its whole solid. Hiding the selection
+/// also clears it.
+pub fn apply_visibility_commands(
+    mut events: EventReader<VisibilityCommand>,
+    geometry_registry: Res<GeometryRegistryResource>,
+    mut selection: ResMut<SelectionState>,
+    mut hidden: ResMut<HiddenSolids>,
+) {
+    for command in events.read() {
+        let selected = selection.solid;
+        match (command, selected) {
+            (VisibilityCommand::Isolate, Some(solid_id)) => {
+                let solids = geometry_registry.registry.solids.solids.keys().copied();
+                hidden.isolate(solid_id, solids);
+            }
+            (VisibilityCommand::HideSelected, Some(solid_id)) => {
+                hidden.hidden.insert(solid_id);
+                selection.clear();
+            }
+            (VisibilityCommand::Isolate | VisibilityCommand::HideSelected, None) => {
+                info!("Select a solid first");
+            }
+            (VisibilityCommand::EndIsolation, _) => {
+                if !hidden.end_isolation() {
+                    info!("Nothing is isolated");
+                }
+            }
+            (VisibilityCommand::UnhideAll, _) => hidden.unhide_all(),
+        }
+    }
+}
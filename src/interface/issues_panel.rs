@@ -0,0 +1,210 @@
+use bevy::prelude::*;
+
+use crate::domain::validation::{
+    orient_solid_outward, repair_degenerate_features, Severity, ValidationPipeline,
+    ValidationReport,
+};
+use crate::interface::segment_outlines::GeometryRegistryResource;
+use crate::interface::theme::UiTheme;
+
+/// Resource holding the validation pipeline and its latest report
+#[derive(Resource, Default)]
+pub struct ValidationState {
+    /// The configured validation pipeline
+    pub pipeline: ValidationPipeline,
+    /// The report from the most recent run
+    pub report: ValidationReport,
+}
+
+/// Marker component for the issues panel, which other checks add sections to
+#[derive(Component)]
+pub struct IssuesPanel;
+
+/// Marker component for the validate button
+#[derive(Component)]
+pub struct ValidateButton;
+
+/// Marker component for the repair button
+#[derive(Component)]
+pub struct RepairButton;
+
+/// Marker component for the issues list text
+#[derive(Component)]
+pub struct IssuesText;
+
+/// Maximum number of issues listed in the panel
+const MAX_LISTED_ISSUES: usize = 12;
+
+/// Setup the issues panel on the right side of the screen
+pub fn setup_issues_panel(mut commands: Commands, theme: Res<UiTheme>) {
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(10.0),
+                right: Val::Px(10.0),
+                max_width: Val::Px(420.0),
+                flex_direction: FlexDirection::Column,
+                padding: UiRect::all(Val::Px(theme.panel_padding)),
+                ..default()
+            },
+            BackgroundColor(theme.panel),
+            IssuesPanel,
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn((
+                    Node {
+                        flex_direction: FlexDirection::Row,
+                        margin: UiRect::bottom(Val::Px(5.0)),
+                        ..default()
+                    },
+                    BackgroundColor(Color::NONE),
+                ))
+                .with_children(|parent| {
+                    parent
+                        .spawn((
+                            Button,
+                            ValidateButton,
+                            Node {
+                                padding: UiRect::all(Val::Px(theme.button_padding)),
+                                margin: UiRect::right(Val::Px(3.0)),
+                                ..default()
+                            },
+                            BackgroundColor(theme.button),
+                        ))
+                        .with_children(|parent| {
+                            parent.spawn(Text::new("Validate"));
+                        });
+
+                    parent
+                        .spawn((
+                            Button,
+                            RepairButton,
+                            Node {
+                                padding: UiRect::all(Val::Px(theme.button_padding)),
+                                ..default()
+                            },
+                            BackgroundColor(theme.button),
+                        ))
+                        .with_children(|parent| {
+                            parent.spawn(Text::new("Repair"));
+                        });
+                });
+
+            parent.spawn((
+                Text::new("Issues: not validated"),
+                TextFont {
+                    font_size: theme.small_font_size,
+                    ..default()
+                },
+                IssuesText,
+            ));
+        });
+}
+
+/// Run validation when the validate button is pressed
+pub fn handle_validate_button(
+    mut interaction_query: Query<&Interaction, (Changed<Interaction>, With<ValidateButton>)>,
+    geometry_registry: Res<GeometryRegistryResource>,
+    mut validation_state: ResMut<ValidationState>,
+) {
+    for interaction in &mut interaction_query {
+        if *interaction == Interaction::Pressed {
+            validation_state.report = validation_state.pipeline.run(&geometry_registry.registry);
+        }
+    }
+}
+
+/// Weld short segments, collapse slivers and orient normals outward when the
+/// repair button is pressed
+///
+/// The registry change triggers re-validation on the next frame.
+pub fn handle_repair_button(
+    mut interaction_query: Query<&Interaction, (Changed<Interaction>, With<RepairButton>)>,
+    mut geometry_registry: ResMut<GeometryRegistryResource>,
+    validation_state: Res<ValidationState>,
+) {
+    for interaction in &mut interaction_query {
+        if *interaction == Interaction::Pressed {
+            let summary = repair_degenerate_features(
+                &mut geometry_registry.registry,
+                &validation_state.pipeline.config.tolerance,
+            );
+            info!(
+                "Repair welded {} vertices, removed {} segments and {} polygons",
+                summary.welded_vertices, summary.removed_segments, summary.removed_polygons
+            );
+
+            let solid_ids: Vec<_> = geometry_registry
+                .registry
+                .solids
+                .solids
+                .keys()
+                .copied()
+                .collect();
+            for solid_id in solid_ids {
+                match orient_solid_outward(&mut geometry_registry.registry, &solid_id) {
+                    Ok(orientation) if orientation.flipped_polygons > 0 => info!(
+                        "Flipped {} polygon(s) of solid {solid_id}",
+                        orientation.flipped_polygons
+                    ),
+                    Ok(_) => {}
+                    Err(err) => warn!("Could not orient solid {solid_id}: {err}"),
+                }
+            }
+        }
+    }
+}
+
+/// Re-run validation whenever the geometry registry changes
+pub fn validate_after_edits(
+    geometry_registry: Res<GeometryRegistryResource>,
+    mut validation_state: ResMut<ValidationState>,
+) {
+    if validation_state.pipeline.config.run_after_edits && geometry_registry.is_changed() {
+        validation_state.report = validation_state.pipeline.run(&geometry_registry.registry);
+    }
+}
+
+/// Rebuild the issues list when the report changes
+pub fn update_issues_text(
+    validation_state: Res<ValidationState>,
+    mut text_query: Query<(&mut Text, &mut TextColor), With<IssuesText>>,
+) {
+    if !validation_state.is_changed() {
+        return;
+    }
+    let report = &validation_state.report;
+
+    let color = if report.has_errors() {
+        Color::srgb(1.0, 0.4, 0.4)
+    } else if report.count(Severity::Warning) > 0 {
+        Color::srgb(1.0, 0.8, 0.3)
+    } else {
+        Color::WHITE
+    };
+
+    let mut lines = vec![format!(
+        "Issues: {} error(s), {} warning(s), {} info",
+        report.count(Severity::Error),
+        report.count(Severity::Warning),
+        report.count(Severity::Info)
+    )];
+    for issue in report.issues.iter().take(MAX_LISTED_ISSUES) {
+        lines.push(format!(
+            "[{}] {}: {}",
+            issue.severity.label(),
+            issue.kind.label(),
+            issue.message
+        ));
+    }
+    if report.len() > MAX_LISTED_ISSUES {
+        lines.push(format!("... and {} more", report.len() - MAX_LISTED_ISSUES));
+    }
+
+    for (mut text, mut text_color) in &mut text_query {
+        *text = Text::new(lines.join("\n"));
+        *text_color = TextColor(color);
+    }
+}
@@ -0,0 +1,178 @@
+use bevy::a11y::AccessibilityNode;
+use bevy::input_focus::InputFocus;
+use bevy::prelude::*;
+use bevy::ui::RelativeCursorPosition;
+
+use crate::interface::localization::Localization;
+
+/// Key moving focus into the panels, or out of them
+const FOCUS_KEY: KeyCode = KeyCode::F6;
+/// Width of the ring drawn around the focused button, in pixels
+const FOCUS_RING_WIDTH: f32 = 2.0;
+/// Color of the ring drawn around the focused button
+const FOCUS_RING_COLOR: Color = Color::srgb(1.0, 0.8, 0.2);
+
+/// Component naming a control for screen readers when its text alone does
+/// not say what it does, such as a `+` button
+#[derive(Component)]
+pub struct AccessibleName(pub String);
+
+/// Marker component for the button drawn with the focus ring
+#[derive(Component)]
+pub struct FocusRing;
+
+/// The buttons that can take focus in reading order: top to bottom, then
+/// left to right
+///
+/// Hidden buttons are left out, and so are sliders, which follow the
+/// cursor and are left to the mouse.
+#[allow(clippy::type_complexity)]
+fn focus_order(
+    button_query: &Query<
+        (Entity, &ComputedNode, &GlobalTransform),
+        (With<Button>, Without<RelativeCursorPosition>),
+    >,
+) -> Vec<Entity> {
+    let mut buttons: Vec<(Entity, Vec2)> = button_query
+        .iter()
+        .filter(|(_, node, _)| node.size().cmpgt(Vec2::ZERO).all())
+        .map(|(entity, _, transform)| (entity, transform.translation().truncate()))
+        .collect();
+    buttons.sort_by(|(_, a), (_, b)| {
+        a.y.round()
+            .total_cmp(&b.y.round())
+            .then(a.x.total_cmp(&b.x))
+    });
+    buttons.into_iter().map(|(entity, _)| entity).collect()
+}
+
+/// Move focus between the panels' buttons from the keyboard
+///
+/// F6 focuses the first button, or leaves the panels; while a button has
+/// focus, Tab and Shift+Tab move through them, Enter or Space presses the
+/// focused one and Escape leaves. The focus is the one screen readers
+/// follow.
+#[allow(clippy::type_complexity)]
+pub fn navigate_ui_focus(
+    mut pressed: Local<Option<Entity>>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut focus: ResMut<InputFocus>,
+    button_query: Query<
+        (Entity, &ComputedNode, &GlobalTransform),
+        (With<Button>, Without<RelativeCursorPosition>),
+    >,
+    mut interaction_query: Query<&mut Interaction>,
+) {
+    // Release the button pressed from the keyboard last frame, as letting
+    // go of the mouse would
+    if let Some(entity) = pressed.take() {
+        if let Ok(mut interaction) = interaction_query.get_mut(entity) {
+            if *interaction == Interaction::Pressed {
+                *interaction = Interaction::None;
+            }
+        }
+    }
+    let order = focus_order(&button_query);
+    if keyboard_input.just_pressed(FOCUS_KEY) {
+        if focus.0.is_some() {
+            focus.clear();
+        } else if let Some(first) = order.first() {
+            focus.set(*first);
+        }
+        return;
+    }
+    let Some(focused) = focus.0 else {
+        return;
+    };
+    let Some(index) = order.iter().position(|entity| *entity == focused) else {
+        // The focused button was hidden or removed
+        focus.clear();
+        return;
+    };
+    if keyboard_input.just_pressed(KeyCode::Escape) {
+        focus.clear();
+    } else if keyboard_input.just_pressed(KeyCode::Tab) {
+        let step = if keyboard_input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+            order.len() - 1
+        } else {
+            1
+        };
+        focus.set(order[(index + step) % order.len()]);
+    } else if keyboard_input.any_just_pressed([
+        KeyCode::Enter,
+        KeyCode::NumpadEnter,
+        KeyCode::Space,
+    ]) {
+        // The panels' handlers react to the press as to a click
+        if let Ok(mut interaction) = interaction_query.get_mut(focused) {
+            *interaction = Interaction::Pressed;
+            *pressed = Some(focused);
+        }
+    }
+}
+
+/// Draw the focus ring around the focused button
+pub fn draw_focus_ring(
+    mut commands: Commands,
+    focus: Res<InputFocus>,
+    ring_query: Query<Entity, With<FocusRing>>,
+) {
+    if !focus.is_changed() {
+        return;
+    }
+    for entity in &ring_query {
+        commands.entity(entity).remove::<(FocusRing, Outline)>();
+    }
+    if let Some(focused) = focus.0 {
+        if let Ok(mut entity) = commands.get_entity(focused) {
+            entity.insert((
+                FocusRing,
+                Outline::new(Val::Px(FOCUS_RING_WIDTH), Val::ZERO, FOCUS_RING_COLOR),
+            ));
+        }
+    }
+}
+
+/// Name each button for screen readers after its text, or its accessible
+/// name if it has one, whenever the text changes
+///
+/// Bevy names a button once, when it is spawned, but most panels set their
+/// buttons' text afterwards. Accessible names are read in the interface
+/// language, as the texts are.
+#[allow(clippy::type_complexity)]
+pub fn label_controls(
+    localization: Res<Localization>,
+    mut button_query: Query<
+        (
+            Option<&Children>,
+            Option<Ref<AccessibleName>>,
+            &mut AccessibilityNode,
+        ),
+        With<Button>,
+    >,
+    text_query: Query<Ref<Text>>,
+) {
+    for (children, name, mut accessible) in &mut button_query {
+        let texts: Vec<Ref<Text>> = children
+            .into_iter()
+            .flatten()
+            .filter_map(|child| text_query.get(*child).ok())
+            .collect();
+        let changed = accessible.is_added()
+            || localization.is_changed()
+            || name.as_ref().is_some_and(DetectChanges::is_changed)
+            || texts.iter().any(DetectChanges::is_changed);
+        if !changed {
+            continue;
+        }
+        let label = match name {
+            Some(name) => localization.catalog().translate(&name.0),
+            None => texts
+                .iter()
+                .map(|text| text.0.as_str())
+                .collect::<Vec<_>>()
+                .join(" "),
+        };
+        accessible.set_label(label);
+    }
+}
@@ -1,11 +1,84 @@
 use bevy::prelude::*;
+use bevy::render::camera::Exposure;
 
+use crate::infrastructure::preferences::{ScenePreset, Theme};
+use crate::interface::camera::MainCamera;
+use crate::interface::settings::PreferencesResource;
+
+/// Marker component for the directional light standing in for the sun
+#[derive(Component)]
+pub struct SunLight;
+
+/// Marker component for the point light filling the sun's shadows
+#[derive(Component)]
+pub struct FillLight;
+
+/// The background, lights and exposure of a scene preset
+pub struct SceneEnvironment {
+    pub background: Color,
+    /// Sun illuminance in lux
+    pub sun_illuminance: f32,
+    pub sun_color: Color,
+    /// Fill light intensity in lumens
+    pub fill_intensity: f32,
+    pub fill_color: Color,
+    pub ambient_color: Color,
+    pub ambient_brightness: f32,
+    /// Camera exposure as an EV100 value
+    pub exposure: f32,
+}
+
+impl SceneEnvironment {
+    /// The environment of a preset; the studio's background follows the
+    /// interface theme
+    #[must_use]
+    pub fn of(preset: ScenePreset, theme: Theme) -> Self {
+        match preset {
+            ScenePreset::Day => Self {
+                background: Color::srgb(0.53, 0.72, 0.92),
+                sun_illuminance: 2500.0,
+                sun_color: Color::srgb(1.0, 0.97, 0.9),
+                fill_intensity: 1000.0,
+                fill_color: Color::WHITE,
+                ambient_color: Color::srgb(0.6, 0.7, 0.9),
+                ambient_brightness: 200.0,
+                exposure: 11.0,
+            },
+            ScenePreset::Night => Self {
+                background: Color::srgb(0.02, 0.03, 0.08),
+                sun_illuminance: 20.0,
+                sun_color: Color::srgb(0.7, 0.8, 1.0),
+                fill_intensity: 1200.0,
+                fill_color: Color::srgb(1.0, 0.8, 0.55),
+                ambient_color: Color::srgb(0.2, 0.25, 0.4),
+                ambient_brightness: 2.0,
+                exposure: Exposure::EV100_INDOOR,
+            },
+            ScenePreset::Studio => Self {
+                background: match theme {
+                    Theme::Dark => Color::srgb(0.12, 0.12, 0.14),
+                    Theme::Light => Color::srgb(0.82, 0.84, 0.88),
+                },
+                sun_illuminance: 400.0,
+                sun_color: Color::WHITE,
+                fill_intensity: 2000.0,
+                fill_color: Color::WHITE,
+                ambient_color: Color::srgb(0.3, 0.3, 0.35),
+                ambient_brightness: 0.4,
+                exposure: Exposure::EV100_BLENDER,
+            },
+        }
+    }
+}
+
+/// Spawn the sun and fill lights; their settings come from the scene
+/// preset, applied by `apply_scene_preset`
 pub fn spawn_lights(commands: &mut Commands) {
     // Main directional light - shadows disabled to avoid artifacts
     commands.spawn((
+        SunLight,
         DirectionalLight {
             shadows_enabled: false,
-            illuminance: 400.0,
             ..default()
         },
         Transform::from_xyz(5.0, 2.0, 3.0).looking_at(Vec3::ZERO, Vec3::Y),
@@ -14,19 +87,43 @@ pub fn spawn_lights(commands: &mut Commands) {
 
     // Fill light
     commands.spawn((
+        FillLight,
         PointLight {
             shadows_enabled: false,
-            intensity: 2000.0,
             ..default()
         },
         Transform::from_xyz(-2.0, -1.0, -4.0),
         GlobalTransform::default(),
     ));
+}
 
-    // Ambient lighting
-    commands.insert_resource(AmbientLight {
-        color: Color::srgb(0.3, 0.3, 0.35),
-        brightness: 0.4,
-        affects_lightmapped_meshes: false,
-    });
+/// Light the scene and color its background for the preferred preset
+pub fn apply_scene_preset(
+    preferences: Res<PreferencesResource>,
+    mut clear_color: ResMut<ClearColor>,
+    mut ambient_light: ResMut<AmbientLight>,
+    mut sun_query: Query<&mut DirectionalLight, With<SunLight>>,
+    mut fill_query: Query<&mut PointLight, With<FillLight>>,
+    mut camera_query: Query<&mut Exposure, With<MainCamera>>,
+) {
+    if !preferences.is_changed() {
+        return;
+    }
+    let preferences = &preferences.preferences;
+    let environment = SceneEnvironment::of(preferences.scene, preferences.theme);
+    clear_color.0 = environment.background;
+    for mut sun in &mut sun_query {
+        sun.illuminance = environment.sun_illuminance;
+        sun.color = environment.sun_color;
+    }
+    for mut fill in &mut fill_query {
+        fill.intensity = environment.fill_intensity;
+        fill.color = environment.fill_color;
+    }
+    ambient_light.color = environment.ambient_color;
+    ambient_light.brightness = environment.ambient_brightness;
+    ambient_light.affects_lightmapped_meshes = false;
+    for mut exposure in &mut camera_query {
+        exposure.ev100 = environment.exposure;
+    }
 }
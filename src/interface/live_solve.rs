@@ -0,0 +1,258 @@
+use bevy::prelude::*;
+use std::collections::{BTreeMap, BTreeSet};
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+use crate::domain::solver::{constraint_vertices, solve_incrementally, Constraint, TierContext};
+use crate::domain::Point;
+use crate::interface::command_bus::{ConstraintSetResource, MoveVertex};
+use crate::interface::issues_panel::ValidationState;
+use crate::interface::segment_outlines::GeometryRegistryResource;
+use crate::interface::transform_gizmo::GizmoState;
+
+/// Longest a live solve may take each frame
+const FRAME_BUDGET: Duration = Duration::from_millis(8);
+/// Times a second violated constraints flash
+const FLASH_RATE: f32 = 3.0;
+/// Color of the edges a live solve moved
+const SOLVED_COLOR: Color = Color::srgb(0.3, 0.85, 1.0);
+/// Color of the geometry of violated constraints
+const VIOLATED_COLOR: Color = Color::srgb(1.0, 0.1, 0.1);
+
+/// A gizmo drag, in a solid's own coordinates
+#[derive(Clone, PartialEq)]
+pub struct LiveEdit {
+    /// Where the solid was shown when the drag started, from its own
+    /// coordinates to the world's
+    pub origin: Mat4,
+    /// The vertices dragged
+    pub vertices: Vec<Uuid>,
+    /// The drag so far
+    pub transform: Mat4,
+}
+
+/// A drag solved against the model's constraints
+pub struct LiveSolvePreview {
+    /// The drag solved
+    edit: LiveEdit,
+    /// Where the drag and the solve put the vertices they moved
+    positions: BTreeMap<Uuid, Point>,
+    /// The constraints still unsatisfied
+    violated: Vec<Constraint>,
+    /// The vertices of the unsatisfied constraints
+    violated_vertices: BTreeSet<Uuid>,
+}
+
+/// Resource holding the solve-on-edit mode and the drag it is solving
+///
+/// While the mode is on, every frame of a gizmo drag is solved against
+/// the model's constraints, as far as the frame budget allows, and
+/// nothing is written to the model until the drag is released and
+/// committed.
+#[derive(Resource, Default)]
+pub struct LiveSolveState {
+    pub enabled: bool,
+    /// The drag to solve, set by the gizmo every frame of a drag
+    pub edit: Option<LiveEdit>,
+    /// Whether the drag has been released and waits to be committed or
+    /// reverted
+    pub released: bool,
+    pub preview: Option<LiveSolvePreview>,
+}
+
+impl LiveSolveState {
+    /// Drop the drag and its preview
+    pub fn clear(&mut self) {
+        self.edit = None;
+        self.released = false;
+        self.preview = None;
+    }
+
+    /// Whether a released drag waits to be committed or reverted
+    #[must_use]
+    pub fn awaiting_choice(&self) -> bool {
+        self.released && self.edit.is_some()
+    }
+
+    /// Constraints the last solve left unsatisfied
+    #[must_use]
+    pub fn violated(&self) -> usize {
+        self.preview
+            .as_ref()
+            .map_or(0, |preview| preview.violated.len())
+    }
+}
+
+/// Toggle solving on edit with L, except during a drag
+pub fn toggle_live_solve(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    gizmo: Res<GizmoState>,
+    mut live: ResMut<LiveSolveState>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::KeyL) || gizmo.drag.is_some() {
+        return;
+    }
+    live.enabled = !live.enabled;
+    live.clear();
+    info!(
+        "Solving on edit {}",
+        if live.enabled { "on" } else { "off" }
+    );
+}
+
+/// Solve the drag whenever it changes
+///
+/// The drag and the solve are made on the model itself, without marking
+/// it changed, and every vertex they moved is put back once the solved
+/// positions are kept, so each solve starts afresh from the model rather
+/// than from the last frame's. The solve stops once the frame budget is
+/// spent.
+pub fn solve_live_drag(
+    mut geometry_registry: ResMut<GeometryRegistryResource>,
+    constraints: Res<ConstraintSetResource>,
+    validation_state: Res<ValidationState>,
+    mut live: ResMut<LiveSolveState>,
+) {
+    let Some(edit) = live.edit.clone() else {
+        return;
+    };
+    if live
+        .preview
+        .as_ref()
+        .is_some_and(|preview| preview.edit == edit)
+    {
+        return;
+    }
+    let deadline = Instant::now() + FRAME_BUDGET;
+    let registry = &mut geometry_registry.bypass_change_detection().registry;
+    let mut original = BTreeMap::new();
+    for vertex_id in &edit.vertices {
+        if let Some(vertex) = registry.vertices.get_mut(vertex_id) {
+            let position = &vertex.position;
+            let dragged = edit
+                .transform
+                .transform_point3(Vec3::new(position.x, position.y, position.z));
+            original.insert(*vertex_id, position.clone());
+            vertex.position = Point {
+                x: dragged.x,
+                y: dragged.y,
+                z: dragged.z,
+            };
+        }
+    }
+    let context = TierContext::new(
+        constraints.constraints.clone(),
+        validation_state.pipeline.config.tolerance,
+        None,
+        None,
+    );
+    let solve = solve_incrementally(registry, &context, &edit.vertices, Some(deadline));
+    let violated_vertices = solve
+        .violated
+        .iter()
+        .flat_map(|constraint| constraint_vertices(registry, constraint))
+        .collect();
+    // Vertices the drag moved keep their position from before the drag
+    for (vertex_id, position) in solve.moved {
+        original.entry(vertex_id).or_insert(position);
+    }
+    let mut positions = BTreeMap::new();
+    for (vertex_id, position) in original {
+        if let Some(vertex) = registry.vertices.get_mut(&vertex_id) {
+            positions.insert(vertex_id, std::mem::replace(&mut vertex.position, position));
+        }
+    }
+    live.preview = Some(LiveSolvePreview {
+        edit,
+        positions,
+        violated: solve.violated,
+        violated_vertices,
+    });
+}
+
+/// Commit a released drag with Enter, writing the solved positions as
+/// vertex moves, or revert it with Escape
+///
+/// A drag is reverted too if the model changes while it waits.
+pub fn resolve_live_drag(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    geometry_registry: Res<GeometryRegistryResource>,
+    mut live: ResMut<LiveSolveState>,
+    mut gizmo: ResMut<GizmoState>,
+    mut moves: EventWriter<MoveVertex>,
+) {
+    if !live.awaiting_choice() {
+        return;
+    }
+    let commit = keyboard_input.any_just_pressed([KeyCode::Enter, KeyCode::NumpadEnter]);
+    if commit {
+        if let Some(preview) = &live.preview {
+            for (vertex_id, position) in &preview.positions {
+                moves.write(MoveVertex {
+                    vertex: *vertex_id,
+                    position: position.clone(),
+                });
+            }
+        }
+    }
+    if commit || keyboard_input.just_pressed(KeyCode::Escape) || geometry_registry.is_changed() {
+        live.clear();
+        gizmo.readout.clear();
+        return;
+    }
+    let label = match live.violated() {
+        0 => "Solved".to_string(),
+        1 => "1 constraint violated".to_string(),
+        violated => format!("{violated} constraints violated"),
+    };
+    gizmo.readout = format!("{label}: Enter commits, Escape reverts");
+}
+
+/// Draw the solved drag's moved edges, flashing the edges of violated
+/// constraints red
+pub fn draw_live_solve(
+    mut gizmos: Gizmos,
+    live: Res<LiveSolveState>,
+    geometry_registry: Res<GeometryRegistryResource>,
+    time: Res<Time>,
+) {
+    let Some(preview) = &live.preview else {
+        return;
+    };
+    let registry = &geometry_registry.registry;
+    let world = |vertex_id: &Uuid| {
+        preview
+            .positions
+            .get(vertex_id)
+            .or_else(|| {
+                registry
+                    .vertices
+                    .get(vertex_id)
+                    .map(|vertex| &vertex.position)
+            })
+            .map(|position| {
+                let point = Vec3::new(position.x, position.y, position.z);
+                preview.edit.origin.transform_point3(point)
+            })
+    };
+    let flash = (time.elapsed_secs() * FLASH_RATE).fract() < 0.5;
+    for segment in registry.segments.segments.values() {
+        let vertices = &segment.vertices;
+        let color = if vertices
+            .iter()
+            .all(|id| preview.violated_vertices.contains(id))
+        {
+            if !flash {
+                continue;
+            }
+            VIOLATED_COLOR
+        } else if vertices.iter().any(|id| preview.positions.contains_key(id)) {
+            SOLVED_COLOR
+        } else {
+            continue;
+        };
+        if let (Some(start), Some(end)) = (world(&vertices[0]), world(&vertices[1])) {
+            gizmos.line(start, end, color);
+        }
+    }
+}
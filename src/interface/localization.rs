@@ -0,0 +1,112 @@
+use bevy::prelude::*;
+use std::path::Path;
+
+use crate::infrastructure::locale::{scan_catalogs, Catalog};
+use crate::interface::settings::PreferencesResource;
+
+/// Folder the translation catalogs are read from
+const LOCALE_FOLDER: &str = "assets/locales";
+
+/// Resource holding the interface languages and the one in use
+#[derive(Resource)]
+pub struct Localization {
+    /// English first, then the catalogs found in the locale folder
+    pub catalogs: Vec<Catalog>,
+    /// Index into `catalogs` of the language in use
+    pub current: usize,
+}
+
+impl Localization {
+    /// Read the catalogs in the locale folder, starting in English
+    #[must_use]
+    pub fn load() -> Self {
+        let (catalogs, failed) = scan_catalogs(Path::new(LOCALE_FOLDER));
+        for (path, error) in &failed {
+            warn!("Skipped catalog {}: {error}", path.display());
+        }
+        Self {
+            catalogs,
+            current: 0,
+        }
+    }
+
+    /// The catalog of the language in use
+    #[must_use]
+    pub fn catalog(&self) -> &Catalog {
+        &self.catalogs[self.current]
+    }
+
+    /// The code of the language after the one given, wrapping back to the
+    /// first
+    #[must_use]
+    pub fn next_language(&self, code: &str) -> String {
+        let index = self
+            .catalogs
+            .iter()
+            .position(|catalog| catalog.code == code)
+            .map_or(0, |index| (index + 1) % self.catalogs.len());
+        self.catalogs[index].code.clone()
+    }
+
+    /// The name of a language, or its code if it has no catalog
+    #[must_use]
+    pub fn language_name<'a>(&'a self, code: &'a str) -> &'a str {
+        self.catalogs
+            .iter()
+            .find(|catalog| catalog.code == code)
+            .map_or(code, |catalog| catalog.name.as_str())
+    }
+}
+
+/// Component recording the English a text entity was given and the
+/// translation shown in its place
+#[derive(Component)]
+pub struct SourceText {
+    pub english: String,
+    pub shown: String,
+}
+
+/// Show every text in the preferred language
+///
+/// Panels keep writing English; any text they change is translated before
+/// it is laid out, and switching the language translates every text again
+/// from the English it was given.
+pub fn localize_texts(
+    mut commands: Commands,
+    preferences: Res<PreferencesResource>,
+    mut localization: ResMut<Localization>,
+    mut text_query: Query<(Entity, &mut Text, Option<&mut SourceText>)>,
+) {
+    let wanted = localization
+        .catalogs
+        .iter()
+        .position(|catalog| catalog.code == preferences.preferences.language)
+        .unwrap_or(0);
+    let switched = wanted != localization.current;
+    if switched {
+        localization.current = wanted;
+    }
+    let catalog = localization.catalog();
+    for (entity, mut text, source) in &mut text_query {
+        let Some(mut source) = source else {
+            let english = text.0.clone();
+            let shown = catalog.translate(&english);
+            if shown != english {
+                text.0.clone_from(&shown);
+            }
+            commands
+                .entity(entity)
+                .insert(SourceText { english, shown });
+            continue;
+        };
+        if text.is_changed() && text.0 != source.shown {
+            source.english.clone_from(&text.0);
+        } else if !switched {
+            continue;
+        }
+        source.shown = catalog.translate(&source.english);
+        if text.0 != source.shown {
+            text.0.clone_from(&source.shown);
+        }
+    }
+}
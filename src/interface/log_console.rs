@@ -0,0 +1,248 @@
+use crate::interface::theme::UiTheme;
+use bevy::log::tracing_subscriber::layer::Context;
+use bevy::log::tracing_subscriber::Layer;
+use bevy::log::{BoxedLayer, Level};
+use bevy::prelude::*;
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::Mutex;
+use tracing::field::{Field, Visit};
+
+/// Most entries kept in the console
+const MAX_ENTRIES: usize = 500;
+/// Most entries shown at once, newest last
+const SHOWN_ENTRIES: usize = 20;
+/// Levels offered by the filter buttons, most severe first
+const FILTER_LEVELS: [Level; 4] = [Level::ERROR, Level::WARN, Level::INFO, Level::DEBUG];
+
+/// One captured log event
+pub struct LogEntry {
+    pub level: Level,
+    pub target: String,
+    pub message: String,
+}
+
+/// Resource holding recent log entries and the console's settings
+#[derive(Resource)]
+pub struct LogConsole {
+    pub entries: VecDeque<LogEntry>,
+    /// Least severe level shown
+    pub filter: Level,
+    pub open: bool,
+}
+
+impl Default for LogConsole {
+    fn default() -> Self {
+        Self {
+            entries: VecDeque::new(),
+            filter: Level::INFO,
+            open: false,
+        }
+    }
+}
+
+/// Resource receiving events from the console layer
+#[derive(Resource)]
+pub struct LogReceiver(Mutex<Receiver<LogEntry>>);
+
+/// Tracing layer forwarding every event to the console
+struct ConsoleLayer {
+    sender: Sender<LogEntry>,
+}
+
+impl<S: tracing::Subscriber> Layer<S> for ConsoleLayer {
+    fn on_event(&self, event: &tracing::Event<'_>, _context: Context<'_, S>) {
+        let mut visitor = EventVisitor::default();
+        event.record(&mut visitor);
+        let metadata = event.metadata();
+        // The receiver only goes away when the app shuts down
+        let _ = self.sender.send(LogEntry {
+            level: *metadata.level(),
+            target: metadata.target().to_string(),
+            message: visitor.message + &visitor.fields,
+        });
+    }
+}
+
+/// Collects an event's message and its other fields separately, since
+/// fields may be recorded before the message
+#[derive(Default)]
+struct EventVisitor {
+    message: String,
+    fields: String,
+}
+
+impl Visit for EventVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message.push_str(value);
+        } else {
+            let _ = write!(self.fields, " {}={value}", field.name());
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.message, "{value:?}");
+        } else {
+            let _ = write!(self.fields, " {}={value:?}", field.name());
+        }
+    }
+}
+
+/// Tracing layer that feeds the in-app log console
+pub fn log_console_layer(app: &mut App) -> BoxedLayer {
+    let (sender, receiver) = channel();
+    app.insert_resource(LogReceiver(Mutex::new(receiver)));
+    Box::new(ConsoleLayer { sender })
+}
+
+/// Marker component for the console toggle button
+#[derive(Component)]
+pub struct LogToggleButton;
+
+/// Component for a button that sets the level filter
+#[derive(Component)]
+pub struct LogFilterButton(pub Level);
+
+/// Marker component for the console's entry text
+#[derive(Component)]
+pub struct LogText;
+
+/// Setup the log console at the bottom center of the screen
+pub fn setup_log_console(mut commands: Commands, theme: Res<UiTheme>) {
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(10.0),
+                left: Val::Percent(30.0),
+                width: Val::Percent(40.0),
+                flex_direction: FlexDirection::Column,
+                ..default()
+            },
+            BackgroundColor(Color::NONE),
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn(Node {
+                    flex_direction: FlexDirection::Row,
+                    ..default()
+                })
+                .with_children(|parent| {
+                    let button_node = || Node {
+                        padding: UiRect::axes(Val::Px(6.0), Val::Px(2.0)),
+                        margin: UiRect::right(Val::Px(3.0)),
+                        ..default()
+                    };
+                    parent
+                        .spawn((
+                            Button,
+                            LogToggleButton,
+                            button_node(),
+                            BackgroundColor(theme.button),
+                        ))
+                        .with_children(|parent| {
+                            parent.spawn(Text::new("Log"));
+                        });
+                    for level in FILTER_LEVELS {
+                        parent
+                            .spawn((
+                                Button,
+                                LogFilterButton(level),
+                                button_node(),
+                                BackgroundColor(theme.button),
+                            ))
+                            .with_children(|parent| {
+                                parent.spawn(Text::new(level.as_str()));
+                            });
+                    }
+                });
+
+            parent.spawn((
+                Text::new(""),
+                TextFont {
+                    font_size: 12.0,
+                    ..default()
+                },
+                Node {
+                    display: Display::None,
+                    padding: UiRect::all(Val::Px(6.0)),
+                    ..default()
+                },
+                BackgroundColor(Color::srgba(0.05, 0.05, 0.05, 0.85)),
+                LogText,
+            ));
+        });
+}
+
+/// Move captured events into the console, dropping the oldest
+pub fn collect_log_entries(receiver: Option<Res<LogReceiver>>, mut console: ResMut<LogConsole>) {
+    let Some(receiver) = receiver else {
+        return;
+    };
+    let Ok(receiver) = receiver.0.lock() else {
+        return;
+    };
+    let entries: Vec<LogEntry> = receiver.try_iter().collect();
+    if entries.is_empty() {
+        return;
+    }
+    console.entries.extend(entries);
+    let excess = console.entries.len().saturating_sub(MAX_ENTRIES);
+    console.entries.drain(..excess);
+}
+
+/// Handle the console toggle and level filter buttons
+pub fn handle_log_console_buttons(
+    toggle_query: Query<&Interaction, (Changed<Interaction>, With<LogToggleButton>)>,
+    filter_query: Query<(&Interaction, &LogFilterButton), Changed<Interaction>>,
+    mut console: ResMut<LogConsole>,
+) {
+    for interaction in &toggle_query {
+        if *interaction == Interaction::Pressed {
+            console.open = !console.open;
+        }
+    }
+    for (interaction, button) in &filter_query {
+        if *interaction == Interaction::Pressed {
+            console.filter = button.0;
+            console.open = true;
+        }
+    }
+}
+
+/// Show the newest entries at or above the filter level
+pub fn update_log_console(
+    console: Res<LogConsole>,
+    mut text_query: Query<(&mut Text, &mut Node), With<LogText>>,
+    mut filter_buttons: Query<(&LogFilterButton, &mut BackgroundColor)>,
+    theme: Res<UiTheme>,
+) {
+    if !console.is_changed() {
+        return;
+    }
+    for (button, mut background_color) in &mut filter_buttons {
+        *background_color = theme.button_color(button.0 == console.filter).into();
+    }
+
+    let mut shown: Vec<String> = console
+        .entries
+        .iter()
+        .rev()
+        .filter(|entry| entry.level <= console.filter)
+        .take(SHOWN_ENTRIES)
+        .map(|entry| format!("{:5} {}: {}", entry.level, entry.target, entry.message))
+        .collect();
+    shown.reverse();
+    let contents = shown.join("\n");
+    for (mut text, mut node) in &mut text_query {
+        node.display = if console.open {
+            Display::Flex
+        } else {
+            Display::None
+        };
+        text.0.clone_from(&contents);
+    }
+}
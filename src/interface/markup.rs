@@ -0,0 +1,464 @@
+use bevy::prelude::*;
+use bevy::render::render_resource::TextureFormat;
+use bevy::render::view::screenshot::{Screenshot, ScreenshotCaptured};
+use uuid::Uuid;
+
+use crate::application::selection::SelectionType;
+use crate::domain::{markup_lines, new_markup, MarkupPoints, MarkupRegistry, MarkupShape, Point};
+use crate::infrastructure::current_timestamp;
+use crate::infrastructure::pdf::{write_review_set, ReviewPage};
+use crate::interface::camera::MainCamera;
+use crate::interface::file_menu::ProjectState;
+use crate::interface::render_export::export_path;
+use crate::interface::segment_outlines::{GeometryRegistryResource, SolidId};
+use crate::interface::selection::pick_candidates;
+use crate::interface::settings::OfficeStandardsResource;
+use crate::interface::theme::UiTheme;
+use crate::interface::ViewColumn;
+
+/// Shortest distance in pixels between recorded points of a stroke
+const MARKUP_SPACING: f32 = 3.0;
+/// Distance in front of the camera markups are drawn at
+const MARKUP_DEPTH: f32 = 1.0;
+/// Distance from the camera of model markups drawn over empty space
+const FALLBACK_ANCHOR_DISTANCE: f32 = 8.0;
+/// Width of markup lines in pixels
+const MARKUP_WIDTH: f32 = 2.5;
+/// Color of markups
+const MARKUP_COLOR: Color = Color::srgb(0.95, 0.15, 0.15);
+
+/// Gizmo group for markups, drawn over the model
+#[derive(Default, Reflect, GizmoConfigGroup)]
+pub struct MarkupGizmos;
+
+/// Resource holding the review markups, saved with the project
+#[derive(Resource)]
+pub struct MarkupRegistryResource {
+    pub registry: MarkupRegistry,
+}
+
+/// Resource tracking the markup mode and the review set being collected
+#[derive(Resource, Default)]
+pub struct MarkupState {
+    /// Whether left-dragging draws markups instead of selecting
+    pub active: bool,
+    /// The shape new markups draw
+    pub shape: MarkupShape,
+    /// Whether new markups follow the model rather than stay on screen
+    pub anchor_to_model: bool,
+    /// Cursor positions of the markup being drawn
+    pub drawing: Vec<Vec2>,
+    /// Markups drawn this session, newest last, for undoing
+    pub drawn: Vec<Uuid>,
+    /// Views captured for the next review set
+    pub pages: Vec<ReviewPage>,
+    pub message: String,
+}
+
+/// What a markup panel button does
+#[derive(Component, Clone, Copy)]
+pub enum MarkupButton {
+    Toggle,
+    Shape,
+    Anchor,
+    Undo,
+    Clear,
+    AddPage,
+    ExportPdf,
+}
+
+/// Marker component for the markup panel text
+#[derive(Component)]
+pub struct MarkupText;
+
+/// Setup the markup panel in the view column
+pub fn setup_markup_panel(
+    mut commands: Commands,
+    column_query: Query<Entity, With<ViewColumn>>,
+    theme: Res<UiTheme>,
+) {
+    let Ok(column) = column_query.single() else {
+        return;
+    };
+    let rows = [
+        vec![
+            MarkupButton::Toggle,
+            MarkupButton::Shape,
+            MarkupButton::Anchor,
+            MarkupButton::Undo,
+            MarkupButton::Clear,
+        ],
+        vec![MarkupButton::AddPage, MarkupButton::ExportPdf],
+    ];
+    commands.entity(column).with_children(|parent| {
+        parent
+            .spawn((
+                Node {
+                    flex_direction: FlexDirection::Column,
+                    padding: UiRect::all(Val::Px(theme.panel_padding)),
+                    row_gap: Val::Px(5.0),
+                    ..default()
+                },
+                BackgroundColor(theme.panel),
+            ))
+            .with_children(|parent| {
+                for row in rows {
+                    parent
+                        .spawn(Node {
+                            flex_direction: FlexDirection::Row,
+                            ..default()
+                        })
+                        .with_children(|parent| {
+                            for button in row {
+                                parent
+                                    .spawn((
+                                        Button,
+                                        button,
+                                        Node {
+                                            padding: UiRect::all(Val::Px(theme.button_padding)),
+                                            margin: UiRect::right(Val::Px(3.0)),
+                                            ..default()
+                                        },
+                                        BackgroundColor(theme.button),
+                                    ))
+                                    .with_children(|parent| {
+                                        parent.spawn(Text::new("Markup"));
+                                    });
+                            }
+                        });
+                }
+
+                parent.spawn((
+                    Text::new(""),
+                    TextFont {
+                        font_size: theme.small_font_size,
+                        ..default()
+                    },
+                    MarkupText,
+                ));
+            });
+    });
+}
+
+/// Handle the markup panel buttons
+pub fn handle_markup_buttons(
+    mut commands: Commands,
+    button_query: Query<(&Interaction, &MarkupButton), Changed<Interaction>>,
+    project: Res<ProjectState>,
+    standards: Res<OfficeStandardsResource>,
+    mut state: ResMut<MarkupState>,
+    mut markup_registry: ResMut<MarkupRegistryResource>,
+) {
+    for (interaction, button) in &button_query {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        match button {
+            MarkupButton::Toggle => {
+                state.active = !state.active;
+                state.drawing.clear();
+            }
+            MarkupButton::Shape => {
+                let index = MarkupShape::ALL
+                    .iter()
+                    .position(|shape| *shape == state.shape)
+                    .unwrap_or(0);
+                state.shape = MarkupShape::ALL[(index + 1) % MarkupShape::ALL.len()];
+            }
+            MarkupButton::Anchor => state.anchor_to_model = !state.anchor_to_model,
+            MarkupButton::Undo => {
+                if let Some(id) = state.drawn.pop() {
+                    markup_registry.registry.remove(&id);
+                }
+            }
+            MarkupButton::Clear => {
+                markup_registry.registry.markups.clear();
+                state.drawn.clear();
+                state.message = "Markups cleared".to_string();
+            }
+            MarkupButton::AddPage => {
+                commands
+                    .spawn(Screenshot::primary_window())
+                    .observe(add_review_page);
+            }
+            MarkupButton::ExportPdf => {
+                if state.pages.is_empty() {
+                    state.message = "Add a page to the review set first".to_string();
+                    continue;
+                }
+                let path = export_path(&project, "review", "pdf");
+                state.message =
+                    match write_review_set(&path, &state.pages, &standards.standards.text) {
+                        Ok(()) => {
+                            format!("Saved {} pages to {}", state.pages.len(), path.display())
+                        }
+                        Err(error) => format!("Could not save review set: {error}"),
+                    };
+            }
+        }
+    }
+}
+
+/// Add a captured view, with its markups, to the review set
+fn add_review_page(trigger: Trigger<ScreenshotCaptured>, mut state: ResMut<MarkupState>) {
+    let image = &trigger.event().0;
+    let Some(data) = image.data.as_ref() else {
+        return;
+    };
+    let bgra = matches!(
+        image.texture_descriptor.format,
+        TextureFormat::Bgra8Unorm | TextureFormat::Bgra8UnormSrgb
+    );
+    let rgb = data
+        .chunks_exact(4)
+        .flat_map(|pixel| {
+            if bgra {
+                [pixel[2], pixel[1], pixel[0]]
+            } else {
+                [pixel[0], pixel[1], pixel[2]]
+            }
+        })
+        .collect();
+    let number = state.pages.len() + 1;
+    state.pages.push(ReviewPage {
+        title: format!("View {number}, {}", current_timestamp()),
+        width: image.width(),
+        height: image.height(),
+        rgb,
+    });
+    state.message = format!("Review set: {number} pages");
+}
+
+/// Where a drag on the screen lies in the model: on the plane facing the
+/// camera through the solid under its first point, or the ground, or a
+/// fixed distance away over empty sky
+fn model_points(
+    drag: &[Vec2],
+    camera: &Camera,
+    camera_transform: &GlobalTransform,
+    anchor_distance: Option<f32>,
+) -> Option<Vec<Point>> {
+    let first = camera
+        .viewport_to_world(camera_transform, *drag.first()?)
+        .ok()?;
+    let distance = anchor_distance
+        .or_else(|| first.intersect_plane(Vec3::ZERO, InfinitePlane3d::new(Vec3::Y)))
+        .unwrap_or(FALLBACK_ANCHOR_DISTANCE);
+    let origin = first.get_point(distance);
+    let plane = InfinitePlane3d::new(camera_transform.forward());
+    drag.iter()
+        .map(|position| {
+            let ray = camera.viewport_to_world(camera_transform, *position).ok()?;
+            let point = ray.get_point(ray.intersect_plane(origin, plane)?);
+            Some(Point {
+                x: point.x,
+                y: point.y,
+                z: point.z,
+            })
+        })
+        .collect()
+}
+
+/// Draw markups with the left mouse button while markup mode is on
+///
+/// A drag starting on a panel is left to the panel.
+#[allow(clippy::too_many_arguments)]
+pub fn record_markups(
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    windows: Query<&Window>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    ui_query: Query<&Interaction>,
+    solid_query: Query<(&SolidId, &GlobalTransform, &InheritedVisibility)>,
+    geometry_registry: Res<GeometryRegistryResource>,
+    mut state: ResMut<MarkupState>,
+    mut markup_registry: ResMut<MarkupRegistryResource>,
+) {
+    if !state.active {
+        return;
+    }
+    let (Ok(window), Ok((camera, camera_transform))) = (windows.single(), camera_query.single())
+    else {
+        return;
+    };
+    let cursor = window.cursor_position();
+    if mouse_input.just_pressed(MouseButton::Left) {
+        let on_panel = ui_query
+            .iter()
+            .any(|interaction| *interaction != Interaction::None);
+        state.drawing = cursor.filter(|_| !on_panel).into_iter().collect();
+    } else if mouse_input.pressed(MouseButton::Left) && !state.drawing.is_empty() {
+        if let Some(cursor) = cursor.filter(|cursor| {
+            state
+                .drawing
+                .last()
+                .is_some_and(|last| last.distance(*cursor) >= MARKUP_SPACING)
+        }) {
+            state.drawing.push(cursor);
+        }
+    }
+    if !mouse_input.just_released(MouseButton::Left) {
+        return;
+    }
+    let drag = std::mem::take(&mut state.drawing);
+    if drag.len() < 2 {
+        return;
+    }
+    let points = if state.anchor_to_model {
+        let eye = camera_transform.translation();
+        let to_screen = |point: Vec3| {
+            camera
+                .world_to_viewport(camera_transform, point)
+                .ok()
+                .map(|screen| (screen, eye.distance(point)))
+        };
+        let Ok(ray) = camera.viewport_to_world(camera_transform, drag[0]) else {
+            return;
+        };
+        let anchor_distance = pick_candidates(
+            SelectionType::Solid,
+            ray,
+            drag[0],
+            &to_screen,
+            &geometry_registry.registry,
+            solid_query.iter(),
+        )
+        .first()
+        .map(|(_, _, distance)| *distance);
+        let Some(points) = model_points(&drag, camera, camera_transform, anchor_distance) else {
+            return;
+        };
+        MarkupPoints::Model(points)
+    } else {
+        let Some(size) = camera.logical_viewport_size() else {
+            return;
+        };
+        MarkupPoints::Screen(
+            drag.iter()
+                .map(|position| [position.x / size.x, position.y / size.y])
+                .collect(),
+        )
+    };
+    let id = markup_registry
+        .registry
+        .store(new_markup(state.shape, points));
+    state.drawn.push(id);
+}
+
+/// Draw markups over the model on top of everything else
+pub fn configure_markup_gizmos(mut config_store: ResMut<GizmoConfigStore>) {
+    let (config, _) = config_store.config_mut::<MarkupGizmos>();
+    config.line.width = MARKUP_WIDTH;
+    config.depth_bias = -1.0;
+}
+
+/// Draw the markups and the one being drawn
+///
+/// Each markup's shape is worked out on the screen and drawn just in front
+/// of the camera, so arrows and clouds keep their size as the view zooms.
+pub fn draw_markups(
+    mut gizmos: Gizmos<MarkupGizmos>,
+    state: Res<MarkupState>,
+    markup_registry: Res<MarkupRegistryResource>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+) {
+    let Ok((camera, camera_transform)) = camera_query.single() else {
+        return;
+    };
+    let Some(size) = camera.logical_viewport_size() else {
+        return;
+    };
+    let on_screen = |markup_points: &MarkupPoints| -> Option<Vec<[f32; 2]>> {
+        match markup_points {
+            MarkupPoints::Screen(points) => Some(
+                points
+                    .iter()
+                    .map(|[x, y]| [x * size.x, y * size.y])
+                    .collect(),
+            ),
+            MarkupPoints::Model(points) => points
+                .iter()
+                .map(|point| {
+                    let world = Vec3::new(point.x, point.y, point.z);
+                    let screen = camera.world_to_viewport(camera_transform, world).ok()?;
+                    Some([screen.x, screen.y])
+                })
+                .collect(),
+        }
+    };
+    let drawing: Vec<[f32; 2]> = state
+        .drawing
+        .iter()
+        .map(|point| [point.x, point.y])
+        .collect();
+    let shapes = markup_registry
+        .registry
+        .markups
+        .values()
+        .filter_map(|markup| Some((markup.shape, on_screen(&markup.points)?)))
+        .chain((drawing.len() > 1).then_some((state.shape, drawing)));
+    let in_front = |[x, y]: [f32; 2]| {
+        camera
+            .viewport_to_world(camera_transform, Vec2::new(x, y))
+            .ok()
+            .map(|ray| ray.get_point(MARKUP_DEPTH))
+    };
+    for (shape, points) in shapes {
+        for [start, end] in markup_lines(shape, &points) {
+            if let (Some(start), Some(end)) = (in_front(start), in_front(end)) {
+                gizmos.line(start, end, MARKUP_COLOR);
+            }
+        }
+    }
+}
+
+/// Show the markup settings and the review set on the panel
+pub fn update_markup_panel(
+    state: Res<MarkupState>,
+    markup_registry: Res<MarkupRegistryResource>,
+    mut button_query: Query<(&MarkupButton, &Children, &mut BackgroundColor)>,
+    mut text_query: Query<&mut Text>,
+    status_query: Query<Entity, With<MarkupText>>,
+    theme: Res<UiTheme>,
+) {
+    if !state.is_changed() && !markup_registry.is_changed() {
+        return;
+    }
+    for (button, children, mut background_color) in &mut button_query {
+        let (label, active) = match button {
+            MarkupButton::Toggle => ("Markup", state.active),
+            MarkupButton::Shape => (state.shape.label(), false),
+            MarkupButton::Anchor => {
+                if state.anchor_to_model {
+                    ("On model", true)
+                } else {
+                    ("On screen", false)
+                }
+            }
+            MarkupButton::Undo => ("Undo", false),
+            MarkupButton::Clear => ("Clear", false),
+            MarkupButton::AddPage => ("Add page", false),
+            MarkupButton::ExportPdf => ("Export PDF", false),
+        };
+        *background_color = theme.button_color(active).into();
+        for child in children {
+            if let Ok(mut text) = text_query.get_mut(*child) {
+                if text.0 != label {
+                    text.0 = label.to_string();
+                }
+            }
+        }
+    }
+    let mut status = format!(
+        "Markups: {}\nReview set: {} pages",
+        markup_registry.registry.markups.len(),
+        state.pages.len()
+    );
+    if !state.message.is_empty() {
+        status = format!("{status}\n{}", state.message);
+    }
+    for entity in &status_query {
+        if let Ok(mut text) = text_query.get_mut(entity) {
+            text.0.clone_from(&status);
+        }
+    }
+}
@@ -0,0 +1,96 @@
+use bevy::prelude::*;
+use std::collections::BTreeMap;
+use uuid::Uuid;
+
+use crate::application::selection::SelectionType;
+use crate::domain::{MassConversionSettings, MassFaceRole};
+use crate::interface::command_bus::ConvertMass;
+use crate::interface::segment_outlines::GeometryRegistryResource;
+use crate::interface::selection::SelectionState;
+
+/// Key cycling the role the selected face of a mass is tagged with
+const MASS_TAG_KEY: KeyCode = KeyCode::F7;
+/// Key converting the selected mass into walls, roofs and floors
+const CONVERT_MASS_KEY: KeyCode = KeyCode::F8;
+
+/// Resource holding the roles faces of masses are tagged with, for faces
+/// that should not take the role their facing gives them
+#[derive(Resource, Default)]
+pub struct MassTagResource {
+    /// The roles, by polygon ID
+    pub tags: BTreeMap<Uuid, MassFaceRole>,
+}
+
+/// Cycle the selected face's tag through the roles and back to untagged
+/// when its key is pressed
+pub fn cycle_mass_face_tag(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    selection_state: Res<SelectionState>,
+    mut mass_tags: ResMut<MassTagResource>,
+) {
+    if !keyboard_input.just_pressed(MASS_TAG_KEY) {
+        return;
+    }
+    let Some(face) = selection_state
+        .selected
+        .as_ref()
+        .filter(|selection| selection.selection_type == SelectionType::Polygon)
+        .map(|selection| selection.id)
+    else {
+        info!("Select a face of a mass to tag it");
+        return;
+    };
+    let next = match mass_tags.tags.get(&face) {
+        None => Some(MassFaceRole::ALL[0]),
+        Some(role) => MassFaceRole::ALL
+            .iter()
+            .position(|other| other == role)
+            .and_then(|index| MassFaceRole::ALL.get(index + 1))
+            .copied(),
+    };
+    if let Some(role) = next {
+        mass_tags.tags.insert(face, role);
+        info!("Tagged the face: {}", role.label());
+    } else {
+        mass_tags.tags.remove(&face);
+        info!("Untagged the face; it takes the role its facing gives it");
+    }
+}
+
+/// Convert the selected mass, or the mass of the selected face, into walls,
+/// roofs and floors when its key is pressed
+pub fn send_convert_mass_shortcut(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    selection_state: Res<SelectionState>,
+    geometry_registry: Res<GeometryRegistryResource>,
+    mut mass_tags: ResMut<MassTagResource>,
+    mut commands: EventWriter<ConvertMass>,
+) {
+    if !keyboard_input.just_pressed(CONVERT_MASS_KEY) {
+        return;
+    }
+    let solids = &geometry_registry.registry.solids;
+    let solid = match &selection_state.selected {
+        Some(selection) if selection.selection_type == SelectionType::Solid => Some(selection.id),
+        Some(selection) if selection.selection_type == SelectionType::Polygon => solids
+            .sorted()
+            .into_iter()
+            .find(|solid| solid.polygons.contains(&selection.id))
+            .map(|solid| solid.id),
+        _ => None,
+    };
+    let Some(mass) = solid.and_then(|solid| solids.get(&solid)) else {
+        info!("Select a mass, or a face of one, to convert it");
+        return;
+    };
+    let tags = mass
+        .polygons
+        .iter()
+        .filter_map(|face| Some((*face, mass_tags.tags.remove(face)?)))
+        .collect();
+    commands.write(ConvertMass {
+        solid: mass.id,
+        tags,
+        settings: MassConversionSettings::default(),
+    });
+}
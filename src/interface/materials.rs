@@ -0,0 +1,396 @@
+use bevy::image::{ImageAddressMode, ImageLoaderSettings, ImageSampler, ImageSamplerDescriptor};
+use bevy::math::Affine2;
+use bevy::prelude::*;
+use std::collections::{BTreeMap, HashMap};
+use uuid::Uuid;
+
+use crate::application::{create_mesh_from_solid, create_mesh_sections_from_solid};
+use crate::interface::daylight_panel::DaylightTint;
+use crate::interface::segment_outlines::{
+    ElementRegistryResource, GeometryRegistryResource, SolidId,
+};
+
+/// How a material looks in the viewport
+#[derive(Debug, Clone)]
+pub struct MaterialAppearance {
+    /// The material name, matched ignoring case
+    pub material: String,
+    pub base_color: Color,
+    pub perceptual_roughness: f32,
+    pub metallic: f32,
+    /// Image tiled over the material's faces, relative to the assets folder
+    pub texture: Option<String>,
+    /// Width and height one copy of the texture covers, in meters
+    pub tile_size: Vec2,
+}
+
+/// Resource holding the appearance of each material and the render
+/// materials made from them
+///
+/// Render materials are made the first time a material is used. Their
+/// textures load in the background and are added once loaded, so a missing
+/// texture file leaves the plain color rather than an unrenderable
+/// material.
+#[derive(Resource)]
+pub struct MaterialLibrary {
+    /// The appearances, earlier ones winning over later duplicates
+    pub appearances: Vec<MaterialAppearance>,
+    /// Render materials by lowercase material name
+    handles: HashMap<String, Handle<StandardMaterial>>,
+    /// Render materials waiting for their texture, with its tile size
+    loading: Vec<(Handle<StandardMaterial>, Handle<Image>, Vec2)>,
+}
+
+impl MaterialLibrary {
+    /// Appearances for the materials the carbon and cost tables know, with
+    /// textures for the ones usually seen
+    #[must_use]
+    pub fn with_typical_materials() -> Self {
+        let appearances = [
+            (
+                "concrete",
+                Color::srgb(0.62, 0.62, 0.6),
+                0.9,
+                0.0,
+                Some(("concrete", 2.0, 2.0)),
+            ),
+            (
+                "reinforced concrete",
+                Color::srgb(0.58, 0.58, 0.57),
+                0.9,
+                0.0,
+                Some(("concrete", 2.0, 2.0)),
+            ),
+            (
+                "masonry",
+                Color::srgb(0.7, 0.66, 0.58),
+                0.85,
+                0.0,
+                Some(("masonry", 1.2, 0.6)),
+            ),
+            (
+                "brick",
+                Color::srgb(0.66, 0.33, 0.24),
+                0.85,
+                0.0,
+                Some(("brick", 0.9, 0.45)),
+            ),
+            ("steel", Color::srgb(0.55, 0.57, 0.6), 0.35, 0.9, None),
+            (
+                "timber",
+                Color::srgb(0.72, 0.53, 0.33),
+                0.7,
+                0.0,
+                Some(("timber", 1.0, 1.0)),
+            ),
+            (
+                "clt",
+                Color::srgb(0.85, 0.72, 0.52),
+                0.7,
+                0.0,
+                Some(("timber", 1.0, 1.0)),
+            ),
+            ("glass", Color::srgba(0.6, 0.75, 0.8, 0.35), 0.05, 0.0, None),
+            ("gypsum", Color::srgb(0.92, 0.91, 0.88), 0.9, 0.0, None),
+            ("insulation", Color::srgb(0.95, 0.85, 0.35), 1.0, 0.0, None),
+        ]
+        .into_iter()
+        .map(
+            |(material, base_color, perceptual_roughness, metallic, texture)| MaterialAppearance {
+                material: material.to_string(),
+                base_color,
+                perceptual_roughness,
+                metallic,
+                texture: texture.map(|(name, _, _)| format!("textures/{name}.png")),
+                tile_size: texture.map_or(Vec2::ONE, |(_, width, height)| Vec2::new(width, height)),
+            },
+        )
+        .collect();
+        Self {
+            appearances,
+            handles: HashMap::new(),
+            loading: Vec::new(),
+        }
+    }
+
+    /// The appearance of a material
+    #[must_use]
+    pub fn appearance(&self, material: &str) -> Option<&MaterialAppearance> {
+        self.appearances
+            .iter()
+            .find(|appearance| appearance.material.eq_ignore_ascii_case(material))
+    }
+
+    /// Show a material in a color, keeping the rest of its appearance and
+    /// its transparency, or with a plain appearance if it has none
+    pub fn set_color(&mut self, material: &str, color: Color) {
+        self.handles.remove(&material.to_lowercase());
+        if let Some(appearance) = self
+            .appearances
+            .iter_mut()
+            .find(|appearance| appearance.material.eq_ignore_ascii_case(material))
+        {
+            appearance.base_color = color.with_alpha(appearance.base_color.alpha());
+            return;
+        }
+        self.appearances.push(MaterialAppearance {
+            material: material.to_string(),
+            base_color: color,
+            perceptual_roughness: 0.8,
+            metallic: 0.0,
+            texture: None,
+            tile_size: Vec2::ONE,
+        });
+    }
+
+    /// The render material for a material, made and its texture sent for
+    /// loading the first time it is asked for
+    ///
+    /// Returns None for materials with no appearance.
+    pub fn render_material(
+        &mut self,
+        material: &str,
+        materials: &mut Assets<StandardMaterial>,
+        asset_server: &AssetServer,
+    ) -> Option<Handle<StandardMaterial>> {
+        let key = material.to_lowercase();
+        if let Some(handle) = self.handles.get(&key) {
+            return Some(handle.clone());
+        }
+        let appearance = self.appearance(material)?.clone();
+        let handle = materials.add(StandardMaterial {
+            base_color: appearance.base_color,
+            perceptual_roughness: appearance.perceptual_roughness,
+            metallic: appearance.metallic,
+            alpha_mode: if appearance.base_color.alpha() < 1.0 {
+                AlphaMode::Blend
+            } else {
+                AlphaMode::Opaque
+            },
+            ..Default::default()
+        });
+        if let Some(texture) = &appearance.texture {
+            // Repeat the texture so world-scale coordinates tile it
+            let image = asset_server.load_with_settings(
+                texture.clone(),
+                |settings: &mut ImageLoaderSettings| {
+                    settings.sampler = ImageSampler::Descriptor(ImageSamplerDescriptor {
+                        address_mode_u: ImageAddressMode::Repeat,
+                        address_mode_v: ImageAddressMode::Repeat,
+                        ..ImageSamplerDescriptor::linear()
+                    });
+                },
+            );
+            self.loading
+                .push((handle.clone(), image, appearance.tile_size));
+        }
+        self.handles.insert(key, handle.clone());
+        Some(handle)
+    }
+}
+
+/// Component recording the material applied to a solid's entity and the
+/// render material it had before
+#[derive(Component)]
+pub struct AppliedMaterial {
+    pub material: String,
+    pub original: Handle<StandardMaterial>,
+}
+
+/// Give each solid the render material of its element's material, and
+/// restore the original when the material is cleared
+///
+/// A solid drawn in sections takes the material of the section its own
+/// entity draws. Tinted solids are left alone until their tint is taken off.
+#[allow(clippy::too_many_arguments)]
+pub fn apply_element_materials(
+    mut commands: Commands,
+    element_registry: Res<ElementRegistryResource>,
+    asset_server: Res<AssetServer>,
+    mut library: ResMut<MaterialLibrary>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut solid_query: Query<(
+        Entity,
+        &SolidId,
+        &mut MeshMaterial3d<StandardMaterial>,
+        Option<&AppliedMaterial>,
+    )>,
+    sections_query: Query<&FaceSections>,
+    daylight_tints: Query<(), With<DaylightTint>>,
+) {
+    for (entity, solid_id, mut material, applied) in &mut solid_query {
+        if daylight_tints.contains(entity) {
+            continue;
+        }
+        let element_material = element_registry
+            .registry
+            .element_of_solid(&solid_id.0)
+            .and_then(|element| element.material.as_deref());
+        let wanted = sections_query
+            .get(entity)
+            .ok()
+            .and_then(|sections| sections.base_material.as_deref())
+            .or(element_material)
+            .filter(|name| library.appearance(name).is_some());
+        match (wanted, applied) {
+            (Some(name), applied) if applied.is_none_or(|applied| applied.material != name) => {
+                let Some(handle) = library.render_material(name, &mut materials, &asset_server)
+                else {
+                    continue;
+                };
+                let original =
+                    applied.map_or_else(|| material.0.clone(), |applied| applied.original.clone());
+                material.0 = handle;
+                commands.entity(entity).insert(AppliedMaterial {
+                    material: name.to_string(),
+                    original,
+                });
+            }
+            (None, Some(applied)) => {
+                material.0 = applied.original.clone();
+                commands.entity(entity).remove::<AppliedMaterial>();
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Component for a solid's entity drawn in sections, one per material,
+/// because some of its faces have their own
+///
+/// The solid's entity draws the faces with the element's material, or if
+/// every face has its own, those of the first section; child entities
+/// draw the rest.
+#[derive(Component)]
+pub struct FaceSections {
+    /// The face materials the sections were built for
+    overrides: BTreeMap<Uuid, String>,
+    /// The material of the faces the solid's entity draws, if not the
+    /// element's
+    base_material: Option<String>,
+    /// The mesh given to the solid's entity
+    base_mesh: Handle<Mesh>,
+    /// The entities drawing the other sections
+    children: Vec<Entity>,
+}
+
+/// Marker component for an entity drawing a section of a solid's faces
+#[derive(Component)]
+pub struct FaceSection;
+
+/// Split the meshes of solids whose faces have their own materials into
+/// a section per material, and join them again when the overrides go
+///
+/// Sections are rebuilt when the overrides change or an edit gives the
+/// solid a new mesh. Face materials with no appearance, or the element's
+/// own, are drawn with the rest of the element.
+#[allow(clippy::too_many_arguments)]
+pub fn split_face_materials(
+    mut commands: Commands,
+    geometry_registry: Res<GeometryRegistryResource>,
+    element_registry: Res<ElementRegistryResource>,
+    asset_server: Res<AssetServer>,
+    mut library: ResMut<MaterialLibrary>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut solid_query: Query<(Entity, &SolidId, &mut Mesh3d, Option<&FaceSections>)>,
+) {
+    let registry = &geometry_registry.registry;
+    for (entity, solid_id, mut mesh, sections) in &mut solid_query {
+        let element = element_registry.registry.element_of_solid(&solid_id.0);
+        let overrides: BTreeMap<Uuid, String> = element
+            .map(|element| {
+                element
+                    .face_materials
+                    .iter()
+                    .filter(|(_, material)| {
+                        library.appearance(material).is_some()
+                            && !element
+                                .material
+                                .as_ref()
+                                .is_some_and(|own| own.eq_ignore_ascii_case(material))
+                    })
+                    .map(|(polygon, material)| (*polygon, material.clone()))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let current = match sections {
+            Some(sections) => sections.overrides == overrides && sections.base_mesh == mesh.0,
+            None => overrides.is_empty(),
+        };
+        if current {
+            continue;
+        }
+        let Some(solid) = registry.solids.get(&solid_id.0) else {
+            continue;
+        };
+        for child in sections.iter().flat_map(|sections| &sections.children) {
+            commands.entity(*child).despawn();
+        }
+        if overrides.is_empty() {
+            mesh.0 = meshes.add(create_mesh_from_solid(solid, registry));
+            commands.entity(entity).remove::<FaceSections>();
+            continue;
+        }
+        let mut parts = create_mesh_sections_from_solid(solid, registry, |polygon| {
+            overrides.get(polygon).map(String::as_str)
+        })
+        .into_iter();
+        let Some((base_material, base_mesh)) = parts.next() else {
+            continue;
+        };
+        mesh.0 = meshes.add(base_mesh);
+        let mut children = Vec::new();
+        for (material, section_mesh) in parts {
+            let Some(material) = material
+                .and_then(|name| library.render_material(&name, &mut materials, &asset_server))
+            else {
+                continue;
+            };
+            let child = commands
+                .spawn((
+                    FaceSection,
+                    Mesh3d(meshes.add(section_mesh)),
+                    MeshMaterial3d(material),
+                    Transform::default(),
+                    ChildOf(entity),
+                ))
+                .id();
+            children.push(child);
+        }
+        commands.entity(entity).insert(FaceSections {
+            overrides,
+            base_material,
+            base_mesh: mesh.0.clone(),
+            children,
+        });
+    }
+}
+
+/// Add each texture to its render material once it has loaded
+pub fn attach_loaded_textures(
+    asset_server: Res<AssetServer>,
+    mut library: ResMut<MaterialLibrary>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    if library.loading.is_empty() {
+        return;
+    }
+    library.loading.retain(|(material, image, tile_size)| {
+        let state = asset_server.load_state(image);
+        if state.is_failed() {
+            warn!("Could not load material texture {:?}", image.path());
+            return false;
+        }
+        if !state.is_loaded() {
+            return true;
+        }
+        if let Some(material) = materials.get_mut(material) {
+            // The texture carries the color; keep only the base color's alpha
+            material.base_color = Color::WHITE.with_alpha(material.base_color.alpha());
+            material.base_color_texture = Some(image.clone());
+            material.uv_transform = Affine2::from_scale(tile_size.recip());
+        }
+        false
+    });
+}
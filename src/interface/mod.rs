@@ -1,55 +1,773 @@
 /// Interface layer for the application
 /// This module sets up the world and the camera
+use bevy::diagnostic::FrameTimeDiagnosticsPlugin;
+use bevy::input_focus::InputFocus;
+use bevy::log::BoxedLayer;
 use bevy::pbr::*;
 use bevy::prelude::*;
+use bevy::ui::UiSystem;
 
 use crate::application::{create_mesh_from_solid, create_rectangular_solid};
-use crate::domain::GeometryRegistry;
+use crate::domain::{ElementKind, ElementRegistry, GeometryRegistry, Point, Tin, Tolerance};
+use crate::infrastructure::preferences::Preferences;
 
+mod asset_browser;
+mod batch_export;
 mod camera;
+mod carbon_panel;
+mod command_bus;
+mod comments_panel;
+mod conflict_panel;
+mod curtain_wall;
+mod daylight_panel;
+mod diagnostics_overlay;
+mod egress_panel;
+mod exploded_view;
+mod extensions;
+mod feature_tree;
+mod file_drop;
+mod file_menu;
+mod headless;
+mod heat_map;
+mod isolation;
+mod issues_panel;
+mod keyboard_navigation;
 mod lighting;
+mod live_solve;
+mod localization;
+mod log_console;
+mod markup;
+mod massing;
+mod materials;
 mod mesh_creation;
+mod placement;
+mod program_panel;
+mod prompt;
+mod recovery;
+mod render_export;
+mod rules_panel;
+mod scene_menu;
 mod segment_outlines;
+mod selection;
+mod session_recorder;
+mod settings;
+mod stereo;
+mod theme;
+mod touch_input;
+mod transform_gizmo;
 mod ui;
+mod underlay;
 
+use asset_browser::{expire_thumbnail_scenes, handle_place_component_buttons, setup_asset_browser};
+use batch_export::{
+    handle_batch_export_buttons, run_batch_exports, setup_batch_export, update_batch_export_panel,
+    BatchExportState,
+};
 use camera::{
     camera_controls, handle_camera_view_events, spawn_camera, update_camera_projection,
-    CameraConfig,
+    walk_camera, CameraConfig, WalkState,
+};
+use carbon_panel::{
+    handle_carbon_buttons, handle_carbon_prompt, setup_carbon_panel, show_carbon_heat_map,
+    update_carbon_panel,
+};
+use command_bus::refresh_edited_meshes;
+use comments_panel::{
+    handle_comment_buttons, handle_comment_prompt, open_comments, setup_comments_panel,
+    update_comments_panel, CommentsState,
+};
+use conflict_panel::{
+    draw_conflict_preview, find_constraint_conflicts, handle_conflict_buttons,
+    setup_conflict_panel, update_conflict_panel, ConflictState,
+};
+use curtain_wall::send_curtain_grid_shortcut;
+use daylight_panel::{
+    handle_daylight_buttons, setup_daylight_panel, tint_daylight_failures, update_daylight_panel,
+};
+use diagnostics_overlay::{
+    collect_timing_samples, setup_diagnostics_overlay, timing_layer, toggle_diagnostics_overlay,
+    update_diagnostics_overlay, PerformanceStats,
+};
+use egress_panel::{
+    draw_egress_paths, handle_egress_buttons, setup_egress_panel, update_egress_panel,
+};
+use exploded_view::{
+    explode_solids, handle_exploded_view_controls, setup_exploded_view,
+    update_exploded_view_controls, ExplodedView,
+};
+use extensions::{handle_extension_buttons, setup_extensions_panel, update_extension_panels};
+use feature_tree::{
+    handle_feature_tree_buttons, handle_feature_tree_prompt, pick_feature_rows,
+    setup_feature_tree_panel, update_feature_tree_panel, FeatureTreeState,
+};
+use file_drop::{
+    handle_dropped_files, import_drawings, import_models, import_surveys, ImportDrawingEvent,
+    ImportModelEvent, ImportSurveyEvent,
+};
+use file_menu::{
+    apply_project_commands, apply_project_standards, autosave_project, handle_file_menu_buttons,
+    handle_path_prompt, handle_window_close, setup_file_menu, track_unsaved_changes,
+    update_file_menu, ProjectCommand, ProjectState,
+};
+use heat_map::{draw_heat_map, setup_heat_map_legend, update_heat_map_legend, HeatMap};
+use isolation::{
+    apply_visibility_commands, send_visibility_shortcuts, HiddenSolids, VisibilityCommand,
+};
+use issues_panel::{
+    handle_repair_button, handle_validate_button, setup_issues_panel, update_issues_text,
+};
+use keyboard_navigation::{draw_focus_ring, label_controls, navigate_ui_focus};
+use lighting::{apply_scene_preset, spawn_lights};
+use live_solve::{
+    draw_live_solve, resolve_live_drag, solve_live_drag, toggle_live_solve, LiveSolveState,
+};
+use localization::{localize_texts, Localization};
+use log_console::{
+    collect_log_entries, handle_log_console_buttons, log_console_layer, setup_log_console,
+    update_log_console, LogConsole,
+};
+use markup::{
+    configure_markup_gizmos, draw_markups, handle_markup_buttons, record_markups,
+    setup_markup_panel, update_markup_panel, MarkupGizmos, MarkupState,
+};
+use massing::{cycle_mass_face_tag, send_convert_mass_shortcut, MassTagResource};
+use materials::{
+    apply_element_materials, attach_loaded_textures, split_face_materials, MaterialLibrary,
 };
-use lighting::spawn_lights;
 use mesh_creation::MeshConfig;
-use segment_outlines::{render_segment_outlines_2d, GeometryRegistryResource, SolidId};
+use program_panel::{
+    handle_program_buttons, handle_program_prompt, setup_program_panel, update_program_panel,
+};
+use prompt::not_typing;
+use recovery::{offer_recovered_work, snapshot_for_recovery};
+use render_export::{
+    capture_render_exports, handle_render_buttons, setup_render_export, start_render_exports,
+    update_render_panel, RenderExport, RenderExportState,
+};
+use rules_panel::{handle_rule_buttons, handle_rule_prompt, setup_rules_panel, update_rules_panel};
+use scene_menu::{handle_scene_menu, setup_scene_menu, update_scene_menu, SceneMenuState};
+use segment_outlines::{
+    configure_outline_gizmos, render_segment_outlines_2d, update_solid_outlines, OutlineGizmos,
+    OutlineStyles,
+};
+use selection::{
+    cycle_selection_candidates, draw_selection_highlight, pick_selection, switch_selection_mode,
+    update_selection_mode_buttons, SelectionState,
+};
+use session_recorder::{
+    record_session_commands, replay_session_commands, start_session_replay,
+    toggle_session_recording, SessionState,
+};
+use settings::{
+    apply_preferences, handle_settings_buttons, rebind_keys, setup_settings, update_settings_panel,
+    OfficeStandardsResource, PreferencesResource, SettingsState,
+};
+use stereo::{aim_laser_pointer, fit_stereo_viewports, toggle_stereo, StereoState};
+use theme::{apply_ui_theme, UiTheme};
+use touch_input::{
+    configure_pen_gizmos, draw_pen_strokes, record_pen_strokes, touch_camera_gestures, PenGizmos,
+    PenSketch,
+};
+use transform_gizmo::{
+    drag_transform_gizmo, draw_transform_gizmo, setup_gizmo_readout, switch_gizmo_mode,
+    update_gizmo_readout, GizmoState,
+};
 use ui::{
     handle_camera_view_buttons, handle_ui_interactions, setup_ui, toggle_mesh_visibility,
     update_button_appearance, CameraViewEvent, ToggleableMesh, UiState,
 };
+use underlay::{calibrate_underlays, import_underlays, ImportUnderlayEvent, UnderlayCalibration};
+
+pub use command_bus::{
+    AddConstraint, AddExpressionConstraint, AddGridLayout, AddSketchPath, ConstraintSetResource,
+    ConvertMass, CreateGrid, CreateWall, CutSolid, EditGrid, EditOperation, ExportStl, ExtrudeFace,
+    FamilyRegistryResource, FinishRegistryResource, GenerateFinishes, GridRegistryResource,
+    LevelRegistryResource, MoveVertex, PlaceFamily, ProvenanceResource, RemoveConstraint,
+    RouteService, ServiceRegistryResource, SetFaceMaterial, SetLevel, SolidsEdited,
+    TierRegistryResource, TransformVertices,
+};
+pub use extensions::{
+    AddExtension, ExportModel, ExportWithExtension, Extension, ExtensionCommand, ExtensionExporter,
+    ExtensionImporter, ExtensionPanel, ExtensionRegistry, RunExtensionCommand,
+};
+pub use headless::{HarmonyHeadlessPlugin, ModelAnalysisSet, ModelCommandSet};
+pub use issues_panel::ValidationState;
+pub use recovery::install_panic_hook;
+pub use segment_outlines::{ElementRegistryResource, GeometryRegistryResource, SolidId};
+pub use session_recorder::ReplaySession;
+
+/// Custom layers for `LogPlugin`, feeding the log console and the
+/// profiling overlay
+pub fn interface_log_layers(app: &mut App) -> Option<BoxedLayer> {
+    let layers = vec![log_console_layer(app), timing_layer(app)];
+    Some(Box::new(layers))
+}
 
 /// A plugin for the interface
 pub struct InterfacePlugin;
 
 impl Plugin for InterfacePlugin {
     fn build(&self, app: &mut App) {
-        app.insert_resource(CameraConfig::default())
+        app.add_plugins(HarmonyHeadlessPlugin)
+            .insert_resource(CameraConfig::default())
             .insert_resource(MeshConfig::default())
             .insert_resource(UiState::default())
-            .add_systems(Startup, (setup_world, setup_ui))
+            .insert_resource(UnderlayCalibration::default())
+            .insert_resource(ProjectState::default())
+            .insert_resource(PreferencesResource {
+                preferences: Preferences::load(),
+            })
+            .insert_resource(SettingsState::default())
+            .insert_resource(LogConsole::default())
+            .insert_resource(PerformanceStats::default())
+            .add_plugins(FrameTimeDiagnosticsPlugin::default())
+            .add_systems(
+                Startup,
+                (
+                    setup_world,
+                    setup_ui,
+                    setup_issues_panel,
+                    setup_asset_browser,
+                    setup_file_menu,
+                    setup_settings,
+                    setup_log_console,
+                    setup_diagnostics_overlay,
+                    offer_recovered_work,
+                ),
+            )
             .add_event::<CameraViewEvent>()
+            .add_event::<ImportUnderlayEvent>()
+            .add_event::<ProjectCommand>()
             .add_systems(
                 Update,
                 (
-                    camera_controls,
-                    render_segment_outlines_2d,
+                    camera_controls.run_if(not_typing),
                     handle_ui_interactions,
                     handle_camera_view_buttons,
                     handle_camera_view_events,
                     update_button_appearance,
                     toggle_mesh_visibility,
                     update_camera_projection,
+                    handle_validate_button,
+                    handle_repair_button,
+                    update_issues_text,
+                    import_underlays,
+                    calibrate_underlays,
+                    expire_thumbnail_scenes,
+                    handle_place_component_buttons,
                 ),
+            )
+            .add_systems(
+                Update,
+                (
+                    handle_file_menu_buttons,
+                    handle_path_prompt,
+                    handle_window_close,
+                    apply_project_commands,
+                    track_unsaved_changes,
+                    autosave_project,
+                    snapshot_for_recovery,
+                    update_file_menu,
+                )
+                    .chain(),
+            )
+            .add_systems(
+                Update,
+                (
+                    handle_settings_buttons,
+                    rebind_keys,
+                    apply_preferences,
+                    update_settings_panel,
+                )
+                    .chain(),
+            )
+            .add_systems(
+                Update,
+                (
+                    collect_log_entries,
+                    handle_log_console_buttons,
+                    update_log_console,
+                )
+                    .chain(),
+            )
+            .add_systems(
+                Update,
+                (
+                    collect_timing_samples,
+                    toggle_diagnostics_overlay.run_if(not_typing),
+                    update_diagnostics_overlay,
+                )
+                    .chain(),
             );
+        app.add_systems(Update, refresh_edited_meshes.after(ModelCommandSet));
+        add_import_systems(app);
+        add_selection_systems(app);
+        add_material_systems(app);
+        add_outline_systems(app);
+        add_view_mode_systems(app);
+        add_analysis_systems(app);
+        add_design_tool_systems(app);
+        add_session_systems(app);
     }
 }
 
+/// Add recording the model commands of a session and replaying them,
+/// around the systems carrying them out
+fn add_session_systems(app: &mut App) {
+    app.init_resource::<SessionState>()
+        .add_event::<ReplaySession>()
+        .add_systems(
+            Update,
+            (
+                toggle_session_recording.run_if(not_typing),
+                record_session_commands,
+                start_session_replay,
+                replay_session_commands,
+            )
+                .chain()
+                .before(ModelCommandSet),
+        );
+}
+
+/// Add the tools that build elements from other geometry: curtain walls,
+/// converted masses and the history of operations, resolving constraint
+/// conflicts, and the tools extensions add
+fn add_design_tool_systems(app: &mut App) {
+    add_curtain_wall_systems(app);
+    add_massing_systems(app);
+    add_feature_tree_systems(app);
+    add_conflict_systems(app);
+    add_extension_systems(app);
+}
+
+/// Add importing dropped files: mesh files as solids, and DXF drawings as
+/// sketch segments, refreshed on request from the file menu
+fn add_import_systems(app: &mut App) {
+    app.add_event::<ImportModelEvent>()
+        .add_event::<ImportDrawingEvent>()
+        .add_event::<ImportSurveyEvent>()
+        .add_systems(
+            Update,
+            (
+                handle_dropped_files,
+                import_models,
+                import_drawings,
+                import_surveys,
+            ),
+        );
+}
+
+/// Add picking, the selection highlight, the transform gizmo with its
+/// solving on edit, and hiding or isolating the selection
+fn add_selection_systems(app: &mut App) {
+    app.insert_resource(SelectionState::default())
+        .insert_resource(GizmoState::default())
+        .insert_resource(LiveSolveState::default())
+        .insert_resource(HiddenSolids::default())
+        .add_event::<VisibilityCommand>()
+        .add_systems(Startup, setup_gizmo_readout)
+        .add_systems(
+            Update,
+            (
+                switch_selection_mode.run_if(not_typing),
+                cycle_selection_candidates.run_if(not_typing),
+                switch_gizmo_mode.run_if(not_typing),
+                toggle_live_solve.run_if(not_typing),
+                drag_transform_gizmo,
+                solve_live_drag,
+                resolve_live_drag.run_if(not_typing),
+                pick_selection,
+                draw_selection_highlight,
+                draw_live_solve,
+                draw_transform_gizmo,
+                update_gizmo_readout,
+                update_selection_mode_buttons,
+            )
+                .chain()
+                .before(ModelCommandSet),
+        )
+        .add_systems(
+            Update,
+            (
+                send_visibility_shortcuts.run_if(not_typing),
+                apply_visibility_commands,
+            )
+                .chain()
+                .before(toggle_mesh_visibility),
+        );
+}
+
+/// Add the element and face materials and their textures
+///
+/// Materials are applied after the model commands and the mesh refresh,
+/// so a solid picks up a new material in the frame it is assigned, and
+/// only when the elements or a mesh changed.
+/// A project's standards recolor its materials once the project has
+/// replaced the model, and the office standards color new solids by
+/// element kind.
+fn add_material_systems(app: &mut App) {
+    app.insert_resource(MaterialLibrary::with_typical_materials())
+        .insert_resource(OfficeStandardsResource::load())
+        .add_systems(
+            Update,
+            apply_project_standards
+                .after(apply_project_commands)
+                .before(split_face_materials),
+        )
+        .add_systems(
+            Update,
+            (
+                (split_face_materials, apply_element_materials)
+                    .chain()
+                    .run_if(
+                        resource_changed::<ElementRegistryResource>
+                            .or(any_match_filter::<Changed<Mesh3d>>),
+                    ),
+                attach_loaded_textures,
+            )
+                .chain()
+                .after(refresh_edited_meshes),
+        );
+}
+
+/// Add caching the solids' outlines and drawing them
+fn add_outline_systems(app: &mut App) {
+    app.insert_resource(OutlineStyles::default())
+        .init_gizmo_group::<OutlineGizmos<false, false>>()
+        .init_gizmo_group::<OutlineGizmos<false, true>>()
+        .init_gizmo_group::<OutlineGizmos<true, false>>()
+        .init_gizmo_group::<OutlineGizmos<true, true>>()
+        .add_systems(
+            Update,
+            (
+                configure_outline_gizmos.after(apply_preferences),
+                (update_solid_outlines, render_segment_outlines_2d)
+                    .chain()
+                    .after(refresh_edited_meshes),
+            ),
+        );
+}
+
+/// Marker component for the column on the right holding the view panels
+#[derive(Component)]
+pub struct ViewColumn;
+
+/// Setup the column the view panels stack in
+fn setup_view_column(mut commands: Commands) {
+    commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Percent(50.0),
+            right: Val::Px(10.0),
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::End,
+            row_gap: Val::Px(6.0),
+            ..default()
+        },
+        ViewColumn,
+    ));
+}
+
+/// Add the interface theme and language, the scene presets, the exploded
+/// view, the walkthrough camera, the stereo view, rendering the view to
+/// an image and batch export of the model
+///
+/// The walkthrough camera takes over from the free camera while walking.
+fn add_view_mode_systems(app: &mut App) {
+    app.insert_resource(ExplodedView::default())
+        .insert_resource(WalkState::default())
+        .insert_resource(StereoState::default())
+        .insert_resource(RenderExportState::default())
+        .insert_resource(SceneMenuState::default())
+        .insert_resource(UiTheme::default())
+        .insert_resource(Localization::load())
+        .init_resource::<InputFocus>()
+        .add_event::<RenderExport>()
+        .add_systems(
+            Startup,
+            (
+                setup_view_column,
+                setup_scene_menu,
+                setup_exploded_view,
+                setup_render_export,
+            )
+                .chain(),
+        )
+        .add_systems(
+            Update,
+            (
+                handle_scene_menu,
+                apply_scene_preset,
+                apply_ui_theme,
+                update_scene_menu,
+            )
+                .chain()
+                .after(apply_preferences),
+        )
+        .add_systems(
+            Update,
+            (
+                handle_exploded_view_controls,
+                explode_solids,
+                update_exploded_view_controls,
+            )
+                .chain(),
+        )
+        .add_systems(
+            PreUpdate,
+            navigate_ui_focus.run_if(not_typing).after(UiSystem::Focus),
+        )
+        .add_systems(Update, draw_focus_ring)
+        .add_systems(
+            PostUpdate,
+            (localize_texts, label_controls)
+                .chain()
+                .before(UiSystem::Content),
+        )
+        .add_systems(
+            Update,
+            walk_camera.run_if(not_typing).before(camera_controls),
+        )
+        .add_systems(Update, touch_camera_gestures.before(camera_controls))
+        .add_systems(
+            Update,
+            (
+                toggle_stereo.run_if(not_typing),
+                fit_stereo_viewports,
+                aim_laser_pointer,
+            )
+                .chain(),
+        )
+        .add_systems(
+            Update,
+            (
+                handle_render_buttons,
+                start_render_exports,
+                capture_render_exports,
+                update_render_panel,
+            )
+                .chain(),
+        );
+    add_review_systems(app);
+    add_batch_export_systems(app);
+}
+
+/// Add sketching with a pen, review markups and coordination comments,
+/// whose panels follow the render panel
+fn add_review_systems(app: &mut App) {
+    app.insert_resource(PenSketch::default())
+        .init_gizmo_group::<PenGizmos<0>>()
+        .init_gizmo_group::<PenGizmos<1>>()
+        .init_gizmo_group::<PenGizmos<2>>()
+        .insert_resource(MarkupState::default())
+        .insert_resource(CommentsState::default())
+        .init_gizmo_group::<MarkupGizmos>()
+        .add_systems(
+            Startup,
+            (setup_markup_panel, setup_comments_panel)
+                .chain()
+                .after(setup_render_export),
+        )
+        .add_systems(Startup, (configure_pen_gizmos, configure_markup_gizmos))
+        .add_systems(
+            Update,
+            (
+                handle_markup_buttons,
+                record_markups.before(pick_selection),
+                draw_markups,
+                update_markup_panel,
+            )
+                .chain(),
+        )
+        .add_systems(
+            Update,
+            (
+                handle_comment_buttons,
+                handle_comment_prompt,
+                open_comments,
+                update_comments_panel,
+            )
+                .chain(),
+        )
+        .add_systems(
+            Update,
+            (record_pen_strokes.before(ModelCommandSet), draw_pen_strokes).chain(),
+        );
+}
+
+/// Add the batch export panel below the render panel, writing one output
+/// per frame so its progress shows
+fn add_batch_export_systems(app: &mut App) {
+    app.insert_resource(BatchExportState::default())
+        .add_systems(
+            Startup,
+            setup_batch_export
+                .after(setup_render_export)
+                .before(setup_markup_panel),
+        )
+        .add_systems(
+            Update,
+            (
+                handle_batch_export_buttons,
+                run_batch_exports,
+                update_batch_export_panel,
+            )
+                .chain(),
+        );
+}
+
+/// Marker component for the column on the left holding the analysis panels
+#[derive(Component)]
+pub struct AnalysisColumn;
+
+/// Setup the column the analysis panels stack in
+fn setup_analysis_column(mut commands: Commands) {
+    commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Percent(35.0),
+            left: Val::Px(10.0),
+            max_width: Val::Px(460.0),
+            flex_direction: FlexDirection::Column,
+            row_gap: Val::Px(6.0),
+            ..default()
+        },
+        AnalysisColumn,
+    ));
+}
+
+/// Add the design analysis panels and their systems
+///
+/// The checks themselves run in `ModelAnalysisSet`, added by the headless
+/// plugin; the buttons and prompts feeding them run before it and the
+/// panels showing their results after it.
+fn add_analysis_systems(app: &mut App) {
+    app.insert_resource(HeatMap::default());
+    app.add_systems(
+        Startup,
+        (
+            setup_analysis_column,
+            setup_program_panel,
+            setup_egress_panel,
+            setup_daylight_panel,
+            setup_carbon_panel,
+        )
+            .chain(),
+    )
+    .add_systems(Startup, setup_rules_panel.after(setup_issues_panel))
+    .add_systems(Startup, setup_heat_map_legend.after(setup_render_export))
+    .add_systems(
+        Update,
+        (
+            (handle_program_buttons, handle_program_prompt).chain(),
+            handle_egress_buttons,
+            handle_daylight_buttons,
+            (handle_rule_buttons, handle_rule_prompt).chain(),
+            (handle_carbon_buttons, handle_carbon_prompt).chain(),
+        )
+            .before(ModelAnalysisSet),
+    )
+    .add_systems(
+        Update,
+        (
+            update_program_panel,
+            (update_egress_panel, draw_egress_paths).chain(),
+            (update_daylight_panel, tint_daylight_failures).chain(),
+            update_rules_panel,
+            (update_carbon_panel, show_carbon_heat_map).chain(),
+        )
+            .after(ModelAnalysisSet),
+    )
+    .add_systems(
+        Update,
+        (draw_heat_map, update_heat_map_legend)
+            .after(show_carbon_heat_map)
+            .after(refresh_edited_meshes),
+    );
+}
+
+/// Add the shortcut dividing the selected face into a curtain wall
+///
+/// Dividing faces and rebuilding the grids when their faces change are
+/// model commands, added by `HarmonyHeadlessPlugin`.
+fn add_curtain_wall_systems(app: &mut App) {
+    app.add_systems(
+        Update,
+        send_curtain_grid_shortcut
+            .run_if(not_typing)
+            .before(ModelCommandSet),
+    );
+}
+
+/// Add tagging the faces of masses and converting masses into walls,
+/// roofs and floors
+fn add_massing_systems(app: &mut App) {
+    app.init_resource::<MassTagResource>().add_systems(
+        Update,
+        (
+            cycle_mass_face_tag.run_if(not_typing),
+            send_convert_mass_shortcut.run_if(not_typing),
+        )
+            .chain()
+            .before(ModelCommandSet),
+    );
+}
+
+/// Add the history tree of recorded operations, whose edits run before
+/// the model commands carrying them out
+fn add_feature_tree_systems(app: &mut App) {
+    app.init_resource::<FeatureTreeState>()
+        .add_systems(
+            Startup,
+            setup_feature_tree_panel.after(setup_comments_panel),
+        )
+        .add_systems(
+            Update,
+            (
+                pick_feature_rows,
+                handle_feature_tree_buttons,
+                handle_feature_tree_prompt,
+            )
+                .chain()
+                .before(ModelCommandSet),
+        )
+        .add_systems(Update, update_feature_tree_panel.after(ModelCommandSet));
+}
+
+/// Add the constraint conflict panel, which looks for a conflict whenever
+/// the constraints change and relaxes the constraint picked
+fn add_conflict_systems(app: &mut App) {
+    app.init_resource::<ConflictState>()
+        .add_systems(
+            Startup,
+            setup_conflict_panel.after(setup_feature_tree_panel),
+        )
+        .add_systems(Update, handle_conflict_buttons.before(ModelCommandSet))
+        .add_systems(
+            Update,
+            (
+                find_constraint_conflicts,
+                update_conflict_panel,
+                draw_conflict_preview,
+            )
+                .chain()
+                .after(ModelCommandSet),
+        );
+}
+
+/// Add the extensions panel, listing the extensions with their commands,
+/// exporters and panels
+fn add_extension_systems(app: &mut App) {
+    app.add_systems(Startup, setup_extensions_panel.after(setup_conflict_panel))
+        .add_systems(
+            Update,
+            (
+                handle_extension_buttons.before(ModelCommandSet),
+                update_extension_panels.after(ModelAnalysisSet),
+            ),
+        );
+}
+
 /// Bevy system to setup the world with our cube
 fn setup_world(
     mut commands: Commands,
@@ -88,10 +806,25 @@ fn setup_world(
         (solid.id, mesh_handle)
     };
 
+    // Create a gently undulating site just below the cubes
+    let terrain_id = create_sample_terrain(&mut geometry_registry);
+    let terrain_mesh = geometry_registry
+        .solids
+        .get(&terrain_id)
+        .map(|terrain| meshes.add(create_mesh_from_solid(terrain, &geometry_registry)));
+
+    // Treat the cubes as rooms so the program panel has spaces to check
+    let mut element_registry = ElementRegistry::create_new();
+    element_registry.create_and_store(ElementKind::Space, &solid_id1, "Office");
+    element_registry.create_and_store(ElementKind::Space, &solid_id2, "Meeting room");
+
     // Store geometry registry for 2D overlay rendering
     commands.insert_resource(GeometryRegistryResource {
         registry: geometry_registry,
     });
+    commands.insert_resource(ElementRegistryResource {
+        registry: element_registry,
+    });
 
     // Create materials with different colors
     let material1 = StandardMaterial {
@@ -128,11 +861,49 @@ fn setup_world(
         SolidId(solid_id2),
     ));
 
+    // Spawn the terrain under the building
+    if let Some(terrain_mesh) = terrain_mesh {
+        let terrain_material = materials.add(StandardMaterial {
+            base_color: Color::srgb(0.35, 0.5, 0.3), // Grass green
+            perceptual_roughness: 0.9,
+            ..Default::default()
+        });
+        commands.spawn((
+            Mesh3d(terrain_mesh),
+            MeshMaterial3d(terrain_material),
+            Transform::from_xyz(0.0, 0.0, 0.0),
+            ToggleableMesh,
+            SolidId(terrain_id),
+        ));
+    }
+
     // Spawn camera and lighting
     spawn_lights(&mut commands);
     spawn_camera(&mut commands, &camera_config);
 
-    println!("Created cubes from domain objects:");
-    println!("  Solid 1 ID: {}", solid1);
-    println!("  Solid 2 ID: {}", solid2);
+    info!("Created cubes from domain objects: {solid1} and {solid2}");
+}
+
+/// Build a sample terrain grid whose highest point sits just below the cubes
+fn create_sample_terrain(geometry_registry: &mut GeometryRegistry) -> uuid::Uuid {
+    let size: u16 = 13;
+    let spacing = 1.5;
+    let heights: Vec<Vec<f32>> = (0..size)
+        .map(|row| {
+            (0..size)
+                .map(|column| {
+                    let (x, z) = (f32::from(column) * 0.5, f32::from(row) * 0.4);
+                    0.15 * (x.sin() + z.cos()) - 0.3
+                })
+                .collect()
+        })
+        .collect();
+    let half = f32::from(size - 1) * spacing / 2.0;
+    let origin = Point {
+        x: -half,
+        y: -1.3,
+        z: -half,
+    };
+    let tin = Tin::from_grid(&origin, spacing, &heights);
+    geometry_registry.create_terrain(&tin, &Tolerance::default())
 }
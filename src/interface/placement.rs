@@ -0,0 +1,153 @@
+use bevy::prelude::*;
+use uuid::Uuid;
+
+use crate::application::placement::{distribute_on_face, place_on_face, update_hosted_placements};
+use crate::domain::{FaceOffset, HostedPlacementRegistry, LinkTransform};
+use crate::interface::asset_browser::ComponentLibraryResource;
+use crate::interface::command_bus::SolidsEdited;
+use crate::interface::issues_panel::ValidationState;
+use crate::interface::segment_outlines::GeometryRegistryResource;
+
+/// Command to place a component on a floor or a wall
+#[derive(Event, Clone)]
+pub struct PlaceOnFace {
+    /// The component definition to place
+    pub definition: Uuid,
+    /// A face of the floor or wall to place it on
+    pub face: Uuid,
+    /// Where it sits in the host's frame
+    pub offset: FaceOffset,
+}
+
+/// Command to place copies of a component along a floor or a wall at
+/// equal spacing
+#[derive(Event, Clone)]
+pub struct DistributeOnFace {
+    /// The component definition to place
+    pub definition: Uuid,
+    /// A face of the floor or wall to place the copies on
+    pub face: Uuid,
+    /// How many copies to place
+    pub count: usize,
+    /// Where each copy sits in the host's frame, apart from along it
+    pub offset: FaceOffset,
+}
+
+/// Command to place a component where no floor or wall holds it
+#[derive(Event, Clone)]
+pub struct PlaceComponent {
+    /// The component definition to place
+    pub definition: Uuid,
+    /// Where the instance goes in the model
+    pub placement: LinkTransform,
+}
+
+/// Resource holding the component instances kept on floors and walls
+#[derive(Resource)]
+pub struct HostedPlacementResource {
+    /// The hosted placements
+    pub registry: HostedPlacementRegistry,
+}
+
+/// Place the components asked for where no floor or wall holds them
+pub fn place_components(
+    mut events: EventReader<PlaceComponent>,
+    mut geometry_registry: ResMut<GeometryRegistryResource>,
+    mut component_library: ResMut<ComponentLibraryResource>,
+    validation_state: Res<ValidationState>,
+    mut edited: EventWriter<SolidsEdited>,
+) {
+    for event in events.read() {
+        let Some(solid) = component_library.library.place(
+            &event.definition,
+            event.placement,
+            &mut geometry_registry.registry,
+            &validation_state.pipeline.config.tolerance,
+        ) else {
+            warn!("No component {} in the library", event.definition);
+            continue;
+        };
+        edited.write(SolidsEdited {
+            solids: vec![solid],
+        });
+    }
+}
+
+/// Place the components asked for on their floors and walls
+pub fn place_components_on_faces(
+    mut place_events: EventReader<PlaceOnFace>,
+    mut distribute_events: EventReader<DistributeOnFace>,
+    mut geometry_registry: ResMut<GeometryRegistryResource>,
+    mut component_library: ResMut<ComponentLibraryResource>,
+    mut placements: ResMut<HostedPlacementResource>,
+    validation_state: Res<ValidationState>,
+    mut edited: EventWriter<SolidsEdited>,
+) {
+    let tolerance = &validation_state.pipeline.config.tolerance;
+    let mut requests: Vec<(Uuid, Uuid, Vec<FaceOffset>)> = place_events
+        .read()
+        .map(|event| (event.definition, event.face, vec![event.offset]))
+        .collect();
+    for event in distribute_events.read() {
+        match distribute_on_face(
+            &geometry_registry.registry,
+            &component_library.library,
+            &event.definition,
+            &event.face,
+            event.count,
+            event.offset,
+            tolerance,
+        ) {
+            Ok(offsets) => requests.push((event.definition, event.face, offsets)),
+            Err(error) => warn!("Could not distribute the component: {error}"),
+        }
+    }
+    for (definition, face, offsets) in requests {
+        let mut solids = Vec::new();
+        for offset in offsets {
+            match place_on_face(
+                &mut geometry_registry.registry,
+                &mut component_library.library,
+                &mut placements.registry,
+                &definition,
+                &face,
+                offset,
+                tolerance,
+            ) {
+                Ok(solid) => solids.push(solid),
+                Err(error) => warn!("Could not place the component: {error}"),
+            }
+        }
+        if !solids.is_empty() {
+            info!("Placed {} component instance(s) on the face", solids.len());
+            edited.write(SolidsEdited { solids });
+        }
+    }
+}
+
+/// Move the components whose floors or walls moved after the model
+/// changes
+pub fn keep_components_hosted(
+    mut geometry_registry: ResMut<GeometryRegistryResource>,
+    mut component_library: ResMut<ComponentLibraryResource>,
+    mut placements: ResMut<HostedPlacementResource>,
+    validation_state: Res<ValidationState>,
+    mut edited: EventWriter<SolidsEdited>,
+) {
+    if !geometry_registry.is_changed() || placements.registry.placements.is_empty() {
+        return;
+    }
+    // Marked changed only when an instance is moved, so that the other
+    // systems watching the geometry do not run again every frame
+    let solids = update_hosted_placements(
+        &mut geometry_registry.bypass_change_detection().registry,
+        &mut component_library.bypass_change_detection().library,
+        &mut placements.registry,
+        &validation_state.pipeline.config.tolerance,
+    );
+    if !solids.is_empty() {
+        geometry_registry.set_changed();
+        component_library.set_changed();
+        edited.write(SolidsEdited { solids });
+    }
+}
@@ -0,0 +1,316 @@
+use bevy::input::keyboard::KeyboardInput;
+use bevy::prelude::*;
+use std::path::PathBuf;
+
+use crate::domain::{
+    detect_spaces, validate_program, ProgramReport, ProgramStatus, Space, SpaceProgram,
+};
+use crate::infrastructure::program::{read_program, write_program_report};
+use crate::interface::prompt::{edit_buffer, PromptAction};
+use crate::interface::segment_outlines::{ElementRegistryResource, GeometryRegistryResource};
+use crate::interface::theme::UiTheme;
+use crate::interface::AnalysisColumn;
+
+/// Column headings of the program table
+const TABLE_HEADINGS: [&str; 6] = [
+    "Room",
+    "Count",
+    "Target m²",
+    "Actual m²",
+    "Diff m²",
+    "Status",
+];
+
+/// Which path the prompt is asking for
+#[derive(Clone, Copy, PartialEq)]
+pub enum ProgramPrompt {
+    Load,
+    Export,
+}
+
+/// Resource holding the loaded program and its latest check
+#[derive(Resource, Default)]
+pub struct ProgramState {
+    pub program: Option<SpaceProgram>,
+    pub report: ProgramReport,
+    /// The spaces the report was checked against
+    pub spaces: Vec<Space>,
+    pub prompt: Option<ProgramPrompt>,
+    pub entry: String,
+    pub message: String,
+}
+
+/// What a program panel button does
+#[derive(Component, Clone, Copy)]
+pub enum ProgramButton {
+    Load,
+    Check,
+    Export,
+}
+
+/// Marker component for the program status text
+#[derive(Component)]
+pub struct ProgramStatusText;
+
+/// Marker component for the grid holding the program table
+#[derive(Component)]
+pub struct ProgramTable;
+
+/// Setup the program panel in the analysis column
+pub fn setup_program_panel(
+    mut commands: Commands,
+    column_query: Query<Entity, With<AnalysisColumn>>,
+    theme: Res<UiTheme>,
+) {
+    let Ok(column) = column_query.single() else {
+        return;
+    };
+    commands.entity(column).with_children(|parent| {
+        parent
+            .spawn((
+                Node {
+                    flex_direction: FlexDirection::Column,
+                    padding: UiRect::all(Val::Px(theme.panel_padding)),
+                    ..default()
+                },
+                BackgroundColor(theme.panel),
+            ))
+            .with_children(|parent| {
+                parent
+                    .spawn(Node {
+                        flex_direction: FlexDirection::Row,
+                        margin: UiRect::bottom(Val::Px(5.0)),
+                        ..default()
+                    })
+                    .with_children(|parent| {
+                        for (button, label) in [
+                            (ProgramButton::Load, "Load program"),
+                            (ProgramButton::Check, "Check"),
+                            (ProgramButton::Export, "Export CSV"),
+                        ] {
+                            parent
+                                .spawn((
+                                    Button,
+                                    button,
+                                    Node {
+                                        padding: UiRect::all(Val::Px(theme.button_padding)),
+                                        margin: UiRect::right(Val::Px(3.0)),
+                                        ..default()
+                                    },
+                                    BackgroundColor(theme.button),
+                                ))
+                                .with_children(|parent| {
+                                    parent.spawn(Text::new(label));
+                                });
+                        }
+                    });
+
+                parent.spawn((
+                    Text::new("Program: none loaded"),
+                    TextFont {
+                        font_size: theme.small_font_size,
+                        ..default()
+                    },
+                    ProgramStatusText,
+                ));
+
+                parent.spawn((
+                    Node {
+                        display: Display::Grid,
+                        grid_template_columns: RepeatedGridTrack::auto(6),
+                        column_gap: Val::Px(8.0),
+                        margin: UiRect::top(Val::Px(5.0)),
+                        ..default()
+                    },
+                    ProgramTable,
+                ));
+            });
+    });
+}
+
+/// Handle the program panel buttons
+pub fn handle_program_buttons(
+    interaction_query: Query<(&Interaction, &ProgramButton), Changed<Interaction>>,
+    mut state: ResMut<ProgramState>,
+) {
+    for (interaction, button) in &interaction_query {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        match button {
+            ProgramButton::Load => {
+                state.prompt = Some(ProgramPrompt::Load);
+                state.entry.clear();
+                state.message.clear();
+            }
+            ProgramButton::Check if state.program.is_none() => {
+                state.message = "Load a program first".to_string();
+            }
+            // Marking the state changed makes `check_program` run again
+            ProgramButton::Check => state.set_changed(),
+            ProgramButton::Export => {
+                state.prompt = Some(ProgramPrompt::Export);
+                state.entry.clear();
+                state.message.clear();
+            }
+        }
+    }
+}
+
+/// Type a path into the prompt; Enter confirms and Escape cancels
+pub fn handle_program_prompt(
+    mut keyboard_events: EventReader<KeyboardInput>,
+    mut state: ResMut<ProgramState>,
+) {
+    let Some(prompt) = state.prompt else {
+        keyboard_events.clear();
+        return;
+    };
+    for event in keyboard_events.read() {
+        match edit_buffer(&mut state.entry, event) {
+            PromptAction::Continue => {}
+            PromptAction::Cancel => {
+                state.prompt = None;
+                state.message.clear();
+            }
+            PromptAction::Submit => {
+                let path = PathBuf::from(state.entry.trim());
+                state.prompt = None;
+                state.message = match prompt {
+                    ProgramPrompt::Load => match read_program(&path) {
+                        Ok(program) => {
+                            let message = format!("Loaded {} rooms", program.rooms.len());
+                            state.program = Some(program);
+                            message
+                        }
+                        Err(error) => error.to_string(),
+                    },
+                    ProgramPrompt::Export => {
+                        let path = path.with_extension("csv");
+                        match write_program_report(&path, &state.report, &state.spaces) {
+                            Ok(()) => format!("Exported {}", path.display()),
+                            Err(error) => format!("Could not export report: {error}"),
+                        }
+                    }
+                };
+                return;
+            }
+        }
+    }
+}
+
+/// Check the model against the program when either changes
+pub fn check_program(
+    geometry_registry: Res<GeometryRegistryResource>,
+    element_registry: Res<ElementRegistryResource>,
+    mut state: ResMut<ProgramState>,
+) {
+    let changed =
+        state.is_changed() || geometry_registry.is_changed() || element_registry.is_changed();
+    if !changed {
+        return;
+    }
+    let Some(program) = &state.program else {
+        return;
+    };
+    let spaces = detect_spaces(&element_registry.registry, &geometry_registry.registry);
+    let report = validate_program(program, &spaces);
+    state.report = report;
+    state.spaces = spaces;
+}
+
+/// Rebuild the status text and table when the check changes
+pub fn update_program_panel(
+    mut commands: Commands,
+    state: Res<ProgramState>,
+    mut status_query: Query<&mut Text, With<ProgramStatusText>>,
+    table_query: Query<Entity, With<ProgramTable>>,
+) {
+    if !state.is_changed() {
+        return;
+    }
+    let mut lines = vec![match &state.program {
+        None => "Program: none loaded".to_string(),
+        Some(_) if state.report.is_met() => "Program: all rooms met".to_string(),
+        Some(_) => {
+            let unmet = state
+                .report
+                .lines
+                .iter()
+                .filter(|line| line.status != ProgramStatus::Met)
+                .count();
+            format!("Program: {unmet} room type(s) not met")
+        }
+    }];
+    match state.prompt {
+        Some(ProgramPrompt::Load) => lines.push(format!("Program file: {}_", state.entry)),
+        Some(ProgramPrompt::Export) => lines.push(format!("Export to: {}_", state.entry)),
+        None => {}
+    }
+    if !state.message.is_empty() {
+        lines.push(state.message.clone());
+    }
+    let status = lines.join("\n");
+    for mut text in &mut status_query {
+        text.0.clone_from(&status);
+    }
+
+    let mut cells: Vec<(String, Color)> = TABLE_HEADINGS
+        .iter()
+        .map(|heading| ((*heading).to_string(), Color::srgb(0.7, 0.7, 0.7)))
+        .collect();
+    for line in &state.report.lines {
+        let color = match line.status {
+            ProgramStatus::Met => Color::WHITE,
+            ProgramStatus::Overage => Color::srgb(1.0, 0.8, 0.3),
+            ProgramStatus::Shortfall | ProgramStatus::Missing => Color::srgb(1.0, 0.4, 0.4),
+        };
+        cells.extend(
+            [
+                line.name.clone(),
+                format!("{}/{}", line.found, line.required),
+                format!("{:.1}", line.target_area),
+                format!("{:.1}", line.actual_area),
+                format!("{:+.1}", line.difference()),
+                line.status.label().to_string(),
+            ]
+            .map(|cell| (cell, color)),
+        );
+    }
+    for space in state
+        .spaces
+        .iter()
+        .filter(|space| state.report.unassigned.contains(&space.element))
+    {
+        let color = Color::srgb(0.6, 0.6, 0.9);
+        cells.extend(
+            [
+                space.name.clone(),
+                "1/0".to_string(),
+                "-".to_string(),
+                format!("{:.1}", space.floor_area),
+                format!("{:+.1}", space.floor_area),
+                "Unassigned".to_string(),
+            ]
+            .map(|cell| (cell, color)),
+        );
+    }
+
+    for table in &table_query {
+        commands
+            .entity(table)
+            .despawn_related::<Children>()
+            .with_children(|parent| {
+                for (cell, color) in &cells {
+                    parent.spawn((
+                        Text::new(cell.clone()),
+                        TextFont {
+                            font_size: 12.0,
+                            ..default()
+                        },
+                        TextColor(*color),
+                    ));
+                }
+            });
+    }
+}
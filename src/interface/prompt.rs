@@ -0,0 +1,95 @@
+use bevy::ecs::system::SystemParam;
+use bevy::input::keyboard::{Key, KeyboardInput};
+use bevy::input::ButtonState;
+use bevy::prelude::*;
+
+use crate::interface::carbon_panel::CarbonState;
+use crate::interface::comments_panel::CommentsState;
+use crate::interface::feature_tree::FeatureTreeState;
+use crate::interface::file_menu::ProjectState;
+use crate::interface::program_panel::ProgramState;
+use crate::interface::rules_panel::RuleState;
+use crate::interface::underlay::UnderlayCalibration;
+
+/// The prompts that take typed text, each of which may be missing
+#[derive(SystemParam)]
+pub struct OpenPrompts<'w> {
+    project: Option<Res<'w, ProjectState>>,
+    program: Option<Res<'w, ProgramState>>,
+    rules: Option<Res<'w, RuleState>>,
+    carbon: Option<Res<'w, CarbonState>>,
+    comments: Option<Res<'w, CommentsState>>,
+    feature_tree: Option<Res<'w, FeatureTreeState>>,
+    underlay: Option<Res<'w, UnderlayCalibration>>,
+}
+
+impl OpenPrompts<'_> {
+    /// Whether any prompt is taking typed text
+    fn any(&self) -> bool {
+        self.project
+            .as_ref()
+            .is_some_and(|state| state.prompt.is_some())
+            || self
+                .program
+                .as_ref()
+                .is_some_and(|state| state.prompt.is_some())
+            || self
+                .rules
+                .as_ref()
+                .is_some_and(|state| state.prompt.is_some())
+            || self
+                .carbon
+                .as_ref()
+                .is_some_and(|state| state.prompt.is_some())
+            || self
+                .comments
+                .as_ref()
+                .is_some_and(|state| state.prompt.is_some())
+            || self
+                .feature_tree
+                .as_ref()
+                .is_some_and(|state| state.editing)
+            || self.underlay.as_ref().is_some_and(|calibration| {
+                calibration.underlay.is_some() && calibration.picks.len() == 2
+            })
+    }
+}
+
+/// Run condition for the hotkey systems: whether no prompt is taking
+/// typed text, so that typing a path or a name does not also move the
+/// camera or switch modes
+pub fn not_typing(prompts: OpenPrompts) -> bool {
+    !prompts.any()
+}
+
+/// What a key press asks of a prompt
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PromptAction {
+    /// Nothing more than the edit made to the entry, if any
+    Continue,
+    /// Close the prompt, on Escape
+    Cancel,
+    /// Act on the entry, on Enter with something typed
+    Submit,
+}
+
+/// Apply a key press to the text typed into a prompt
+///
+/// Characters and spaces are added to the entry and Backspace removes the
+/// last; releases and other keys are ignored.
+pub fn edit_buffer(entry: &mut String, event: &KeyboardInput) -> PromptAction {
+    if event.state != ButtonState::Pressed {
+        return PromptAction::Continue;
+    }
+    match &event.logical_key {
+        Key::Character(text) => entry.push_str(text),
+        Key::Space => entry.push(' '),
+        Key::Backspace => {
+            entry.pop();
+        }
+        Key::Escape => return PromptAction::Cancel,
+        Key::Enter if !entry.trim().is_empty() => return PromptAction::Submit,
+        _ => {}
+    }
+    PromptAction::Continue
+}
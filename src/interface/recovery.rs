@@ -0,0 +1,155 @@
+use bevy::prelude::*;
+use std::backtrace::Backtrace;
+use std::panic::PanicHookInfo;
+use std::path::PathBuf;
+use std::sync::{Mutex, PoisonError};
+
+use crate::infrastructure::project::PROJECT_EXTENSION;
+use crate::infrastructure::{config_dir, current_timestamp};
+use crate::interface::file_menu::{ProjectResources, ProjectState};
+
+/// Seconds between snapshots while the model keeps changing
+const SNAPSHOT_INTERVAL: f32 = 2.0;
+
+/// The latest model snapshot, written out if the app panics
+///
+/// The panic hook cannot reach the ECS world, so the model is serialized
+/// ahead of time by `snapshot_for_recovery`.
+static SNAPSHOT: Mutex<Option<RecoverySnapshot>> = Mutex::new(None);
+
+/// A serialized model and where it was last saved
+struct RecoverySnapshot {
+    project_text: String,
+    project_path: Option<PathBuf>,
+}
+
+/// Install a panic hook that writes the latest snapshot to a recovery file
+/// and a crash report to the config folder before the default hook runs
+pub fn install_panic_hook() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        write_crash_files(info);
+        default_hook(info);
+    }));
+}
+
+/// Path of the recovery file a crash leaves behind
+fn recovery_path() -> Option<PathBuf> {
+    config_dir().map(|folder| folder.join(format!("recovery.{PROJECT_EXTENSION}")))
+}
+
+/// Write the recovery file and crash report, announcing both on stderr
+fn write_crash_files(info: &PanicHookInfo<'_>) {
+    let Some(folder) = config_dir() else {
+        return;
+    };
+    let reports = folder.join("crash-reports");
+    if std::fs::create_dir_all(&reports).is_err() {
+        return;
+    }
+
+    // The panic may have happened while the snapshot was being replaced
+    let guard = match SNAPSHOT.try_lock() {
+        Ok(guard) => Some(guard),
+        Err(std::sync::TryLockError::Poisoned(poisoned)) => Some(poisoned.into_inner()),
+        Err(std::sync::TryLockError::WouldBlock) => None,
+    };
+    let snapshot = guard.as_ref().and_then(|guard| guard.as_ref());
+    let recovered = snapshot.zip(recovery_path()).and_then(|(snapshot, path)| {
+        std::fs::write(&path, &snapshot.project_text)
+            .is_ok()
+            .then_some(path)
+    });
+
+    let payload = info
+        .payload()
+        .downcast_ref::<&str>()
+        .map(|message| (*message).to_string())
+        .or_else(|| info.payload().downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic".to_string());
+    let location = info.location().map_or_else(
+        || "unknown location".to_string(),
+        |location| format!("{}:{}", location.file(), location.line()),
+    );
+    let project = snapshot
+        .and_then(|snapshot| snapshot.project_path.as_ref())
+        .map_or_else(|| "Untitled".to_string(), |path| path.display().to_string());
+    let recovery = recovered
+        .as_ref()
+        .map_or_else(|| "none".to_string(), |path| path.display().to_string());
+    let timestamp = current_timestamp();
+    let report = format!(
+        "HarmonyArch crash report\n\
+         Time: {timestamp}\n\
+         Version: {}\n\
+         Project: {project}\n\
+         Panic: {payload}\n\
+         Location: {location}\n\
+         Recovery file: {recovery}\n\n\
+         Backtrace:\n{}\n",
+        env!("CARGO_PKG_VERSION"),
+        Backtrace::force_capture()
+    );
+    let report_path = reports.join(format!("crash-{}.txt", timestamp.replace(':', "-")));
+    let written = std::fs::write(&report_path, report).is_ok();
+
+    eprintln!("HarmonyArch crashed: {payload}");
+    if let Some(path) = &recovered {
+        eprintln!("Unsaved work was written to {}", path.display());
+    }
+    if written {
+        eprintln!("A crash report was written to {}", report_path.display());
+    }
+}
+
+/// Keep the panic hook's snapshot of the model current
+pub fn snapshot_for_recovery(
+    time: Res<Time>,
+    resources: ProjectResources,
+    project: Res<ProjectState>,
+    mut pending: Local<bool>,
+    mut seconds_since_snapshot: Local<f32>,
+) {
+    *pending |= resources.changed() || project.is_changed();
+    *seconds_since_snapshot += time.delta_secs();
+    if !*pending || *seconds_since_snapshot < SNAPSHOT_INTERVAL {
+        return;
+    }
+    *pending = false;
+    *seconds_since_snapshot = 0.0;
+    let project_text = match resources.export() {
+        Ok(text) => text,
+        Err(error) => {
+            warn!("Could not snapshot the model for recovery: {error}");
+            return;
+        }
+    };
+    let snapshot = RecoverySnapshot {
+        project_text,
+        project_path: project.path.clone(),
+    };
+    *SNAPSHOT.lock().unwrap_or_else(PoisonError::into_inner) = Some(snapshot);
+}
+
+/// Offer work recovered from a previous crash through the recent list
+///
+/// The recovery file is renamed so the next crash cannot overwrite it and
+/// the offer is only made once.
+pub fn offer_recovered_work(mut project: ResMut<ProjectState>) {
+    let Some(path) = recovery_path().filter(|path| path.exists()) else {
+        return;
+    };
+    let stamp = current_timestamp().replace(':', "-");
+    let recovered = path.with_file_name(format!("recovered-{stamp}.{PROJECT_EXTENSION}"));
+    if std::fs::rename(&path, &recovered).is_err() {
+        return;
+    }
+    project.recent.add(&recovered);
+    if let Err(error) = project.recent.save() {
+        warn!("Could not save recent projects: {error}");
+    }
+    project.message = format!(
+        "Work from a crash was recovered to {}; open it from the recent list",
+        recovered.display()
+    );
+}
@@ -0,0 +1,327 @@
+use bevy::prelude::*;
+use bevy::render::camera::{Exposure, RenderTarget};
+use bevy::render::render_asset::RenderAssetUsages;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat, TextureUsages};
+use bevy::render::view::screenshot::{save_to_disk, Screenshot, ScreenshotCaptured};
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::interface::camera::MainCamera;
+use crate::interface::file_menu::ProjectState;
+use crate::interface::theme::UiTheme;
+use crate::interface::ViewColumn;
+
+/// The resolutions offered, as a label with width and height in pixels
+pub const RENDER_RESOLUTIONS: [(&str, u32, u32); 4] = [
+    ("1080p", 1920, 1080),
+    ("1440p", 2560, 1440),
+    ("4K", 3840, 2160),
+    ("8K", 7680, 4320),
+];
+/// Frames the offscreen camera renders before it is captured, so the UI
+/// has been laid out for its size
+const FRAMES_BEFORE_CAPTURE: u8 = 2;
+
+/// A request to render the current view to a PNG file
+#[derive(Event, Clone)]
+pub struct RenderExport {
+    pub path: PathBuf,
+    pub width: u32,
+    pub height: u32,
+    /// Whether the UI panels are drawn over the model
+    pub include_ui: bool,
+}
+
+/// A render waiting to be captured or saved
+struct PendingRender {
+    /// The offscreen camera drawing the view
+    camera: Entity,
+    image: Handle<Image>,
+    path: PathBuf,
+    /// Frames left before the capture is requested; None once it has been
+    frames_left: Option<u8>,
+    /// The UI roots moved onto the offscreen camera
+    ui_roots: Vec<Entity>,
+}
+
+/// Resource holding the render panel's settings and the render in progress
+#[derive(Resource)]
+pub struct RenderExportState {
+    /// Index into `RENDER_RESOLUTIONS`
+    pub resolution: usize,
+    pub include_ui: bool,
+    pub message: String,
+    pending: Option<PendingRender>,
+}
+
+impl Default for RenderExportState {
+    fn default() -> Self {
+        Self {
+            resolution: 2,
+            include_ui: false,
+            message: String::new(),
+            pending: None,
+        }
+    }
+}
+
+/// Marker component for the render panel buttons
+#[derive(Component, Clone, Copy)]
+pub enum RenderButton {
+    Resolution,
+    IncludeUi,
+    Render,
+}
+
+/// Marker component for the render panel text
+#[derive(Component)]
+pub struct RenderText;
+
+/// Setup the render panel in the view column
+pub fn setup_render_export(
+    mut commands: Commands,
+    column_query: Query<Entity, With<ViewColumn>>,
+    theme: Res<UiTheme>,
+) {
+    let Ok(column) = column_query.single() else {
+        return;
+    };
+    commands.entity(column).with_children(|parent| {
+        parent
+            .spawn((
+                Node {
+                    flex_direction: FlexDirection::Column,
+                    padding: UiRect::all(Val::Px(theme.panel_padding)),
+                    row_gap: Val::Px(5.0),
+                    ..default()
+                },
+                BackgroundColor(theme.panel),
+            ))
+            .with_children(|parent| {
+                parent
+                    .spawn(Node {
+                        flex_direction: FlexDirection::Row,
+                        ..default()
+                    })
+                    .with_children(|parent| {
+                        for button in [
+                            RenderButton::Resolution,
+                            RenderButton::IncludeUi,
+                            RenderButton::Render,
+                        ] {
+                            parent
+                                .spawn((
+                                    Button,
+                                    button,
+                                    Node {
+                                        padding: UiRect::all(Val::Px(theme.button_padding)),
+                                        margin: UiRect::right(Val::Px(3.0)),
+                                        ..default()
+                                    },
+                                    BackgroundColor(theme.button),
+                                ))
+                                .with_children(|parent| {
+                                    parent.spawn(Text::new("Render"));
+                                });
+                        }
+                    });
+
+                parent.spawn((
+                    Text::new(""),
+                    TextFont {
+                        font_size: theme.small_font_size,
+                        ..default()
+                    },
+                    RenderText,
+                ));
+            });
+    });
+}
+
+/// Where an export is saved: beside the project if it has been saved,
+/// otherwise in the working directory, named for its kind and the time it
+/// was made, such as `render-1700000000.png`
+pub fn export_path(project: &ProjectState, kind: &str, extension: &str) -> PathBuf {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+    let name = format!("{kind}-{seconds}.{extension}");
+    project
+        .path
+        .as_ref()
+        .and_then(|path| path.parent())
+        .map_or_else(|| PathBuf::from(&name), |directory| directory.join(&name))
+}
+
+/// Handle the render panel buttons: cycle the resolution, toggle the UI
+/// and request a render
+pub fn handle_render_buttons(
+    button_query: Query<(&Interaction, &RenderButton), Changed<Interaction>>,
+    project: Res<ProjectState>,
+    mut state: ResMut<RenderExportState>,
+    mut exports: EventWriter<RenderExport>,
+) {
+    for (interaction, button) in &button_query {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        match button {
+            RenderButton::Resolution => {
+                state.resolution = (state.resolution + 1) % RENDER_RESOLUTIONS.len();
+            }
+            RenderButton::IncludeUi => state.include_ui = !state.include_ui,
+            RenderButton::Render => {
+                let (_, width, height) = RENDER_RESOLUTIONS[state.resolution];
+                exports.write(RenderExport {
+                    path: export_path(&project, "render", "png"),
+                    width,
+                    height,
+                    include_ui: state.include_ui,
+                });
+            }
+        }
+    }
+}
+
+/// An image the offscreen camera can draw into and the screenshot can
+/// copy out of
+fn render_target(width: u32, height: u32) -> Image {
+    let mut image = Image::new_fill(
+        Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        &[0, 0, 0, 0],
+        TextureFormat::Bgra8UnormSrgb,
+        RenderAssetUsages::default(),
+    );
+    image.texture_descriptor.usage = TextureUsages::TEXTURE_BINDING
+        | TextureUsages::COPY_SRC
+        | TextureUsages::COPY_DST
+        | TextureUsages::RENDER_ATTACHMENT;
+    image
+}
+
+/// Start rendering each requested view on an offscreen camera placed like
+/// the main camera
+///
+/// Including the UI moves the UI roots onto the offscreen camera until
+/// the render is saved, so the panels leave the window for a few frames.
+pub fn start_render_exports(
+    mut commands: Commands,
+    mut exports: EventReader<RenderExport>,
+    mut state: ResMut<RenderExportState>,
+    mut images: ResMut<Assets<Image>>,
+    camera_query: Query<(&GlobalTransform, &Projection, &Exposure), With<MainCamera>>,
+    ui_roots: Query<Entity, (With<Node>, Without<ChildOf>)>,
+) {
+    for export in exports.read() {
+        if state.pending.is_some() {
+            state.message = "A render is already in progress".to_string();
+            continue;
+        }
+        let Ok((transform, projection, exposure)) = camera_query.single() else {
+            continue;
+        };
+        let image = images.add(render_target(export.width, export.height));
+        let camera = commands
+            .spawn((
+                Camera3d::default(),
+                Camera {
+                    target: RenderTarget::Image(image.clone().into()),
+                    ..default()
+                },
+                projection.clone(),
+                *exposure,
+                transform.compute_transform(),
+            ))
+            .id();
+        let ui_roots: Vec<Entity> = if export.include_ui {
+            ui_roots.iter().collect()
+        } else {
+            Vec::new()
+        };
+        for root in &ui_roots {
+            commands.entity(*root).insert(UiTargetCamera(camera));
+        }
+        state.message = format!("Rendering {}x{}...", export.width, export.height);
+        state.pending = Some(PendingRender {
+            camera,
+            image,
+            path: export.path.clone(),
+            frames_left: Some(FRAMES_BEFORE_CAPTURE),
+            ui_roots,
+        });
+    }
+}
+
+/// Capture the offscreen camera's image once it has been drawn, saving it
+/// to disk and then removing the camera
+pub fn capture_render_exports(mut commands: Commands, mut state: ResMut<RenderExportState>) {
+    let Some(pending) = state.pending.as_mut() else {
+        return;
+    };
+    match pending.frames_left {
+        Some(0) => {
+            pending.frames_left = None;
+            commands
+                .spawn(Screenshot::image(pending.image.clone()))
+                .observe(save_to_disk(pending.path.clone()))
+                .observe(finish_render_export);
+        }
+        Some(frames) => pending.frames_left = Some(frames - 1),
+        None => {}
+    }
+}
+
+/// Remove the offscreen camera once its image is captured, handing the UI
+/// back to the window
+fn finish_render_export(
+    _trigger: Trigger<ScreenshotCaptured>,
+    mut commands: Commands,
+    mut state: ResMut<RenderExportState>,
+) {
+    let Some(pending) = state.pending.take() else {
+        return;
+    };
+    for root in pending.ui_roots {
+        if let Ok(mut entity) = commands.get_entity(root) {
+            entity.remove::<UiTargetCamera>();
+        }
+    }
+    commands.entity(pending.camera).despawn();
+    state.message = format!("Saved {}", pending.path.display());
+}
+
+/// Show the render settings and progress on the panel
+pub fn update_render_panel(
+    state: Res<RenderExportState>,
+    mut button_query: Query<(&RenderButton, &Children, &mut BackgroundColor)>,
+    mut text_query: Query<&mut Text>,
+    status_query: Query<Entity, With<RenderText>>,
+    theme: Res<UiTheme>,
+) {
+    if !state.is_changed() {
+        return;
+    }
+    for (button, children, mut background_color) in &mut button_query {
+        let (label, active) = match button {
+            RenderButton::Resolution => (RENDER_RESOLUTIONS[state.resolution].0.to_string(), false),
+            RenderButton::IncludeUi => ("With UI".to_string(), state.include_ui),
+            RenderButton::Render => ("Render PNG".to_string(), state.pending.is_some()),
+        };
+        *background_color = theme.button_color(active).into();
+        for child in children {
+            if let Ok(mut text) = text_query.get_mut(*child) {
+                text.0.clone_from(&label);
+            }
+        }
+    }
+    for status in &status_query {
+        if let Ok(mut text) = text_query.get_mut(status) {
+            text.0.clone_from(&state.message);
+        }
+    }
+}
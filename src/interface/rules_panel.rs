@@ -0,0 +1,257 @@
+use bevy::input::keyboard::KeyboardInput;
+use bevy::prelude::*;
+use std::path::PathBuf;
+
+use crate::domain::rules::{RuleContext, RuleOutcome, RuleReport, RuleSet};
+use crate::infrastructure::rules::{read_rules, write_rule_report};
+use crate::interface::issues_panel::IssuesPanel;
+use crate::interface::prompt::{edit_buffer, PromptAction};
+use crate::interface::segment_outlines::{ElementRegistryResource, GeometryRegistryResource};
+use crate::interface::theme::UiTheme;
+
+/// Maximum number of findings listed in the panel
+const MAX_LISTED_FINDINGS: usize = 12;
+
+/// Which path the prompt is asking for
+#[derive(Clone, Copy, PartialEq)]
+pub enum RulePrompt {
+    Load,
+    Export,
+}
+
+/// Resource holding the rule set and its latest findings
+#[derive(Resource)]
+pub struct RuleState {
+    pub rules: RuleSet,
+    pub report: RuleReport,
+    /// Whether the rules have been run, after which they rerun on every edit
+    pub enabled: bool,
+    pub prompt: Option<RulePrompt>,
+    pub entry: String,
+    pub message: String,
+}
+
+impl Default for RuleState {
+    fn default() -> Self {
+        Self {
+            rules: RuleSet::with_builtin_rules(),
+            report: RuleReport::default(),
+            enabled: false,
+            prompt: None,
+            entry: String::new(),
+            message: String::new(),
+        }
+    }
+}
+
+/// What a rules button does
+#[derive(Component, Clone, Copy)]
+pub enum RuleButton {
+    Check,
+    Load,
+    Export,
+}
+
+/// Marker component for the findings text
+#[derive(Component)]
+pub struct RulesText;
+
+/// Add the rules section to the issues panel
+pub fn setup_rules_panel(
+    mut commands: Commands,
+    panel_query: Query<Entity, With<IssuesPanel>>,
+    theme: Res<UiTheme>,
+) {
+    let Ok(panel) = panel_query.single() else {
+        return;
+    };
+    commands.entity(panel).with_children(|parent| {
+        parent
+            .spawn(Node {
+                flex_direction: FlexDirection::Row,
+                margin: UiRect::vertical(Val::Px(5.0)),
+                ..default()
+            })
+            .with_children(|parent| {
+                for (button, label) in [
+                    (RuleButton::Check, "Check rules"),
+                    (RuleButton::Load, "Load rules"),
+                    (RuleButton::Export, "Export findings"),
+                ] {
+                    parent
+                        .spawn((
+                            Button,
+                            button,
+                            Node {
+                                padding: UiRect::all(Val::Px(theme.button_padding)),
+                                margin: UiRect::right(Val::Px(3.0)),
+                                ..default()
+                            },
+                            BackgroundColor(theme.button),
+                        ))
+                        .with_children(|parent| {
+                            parent.spawn(Text::new(label));
+                        });
+                }
+            });
+
+        parent.spawn((
+            Text::new("Rules: not checked"),
+            TextFont {
+                font_size: theme.small_font_size,
+                ..default()
+            },
+            RulesText,
+        ));
+    });
+}
+
+/// Handle the rules buttons
+pub fn handle_rule_buttons(
+    interaction_query: Query<(&Interaction, &RuleButton), Changed<Interaction>>,
+    mut state: ResMut<RuleState>,
+) {
+    for (interaction, button) in &interaction_query {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        match button {
+            RuleButton::Check => state.enabled = true,
+            RuleButton::Load => {
+                state.prompt = Some(RulePrompt::Load);
+                state.entry.clear();
+                state.message.clear();
+            }
+            RuleButton::Export => {
+                state.prompt = Some(RulePrompt::Export);
+                state.entry.clear();
+                state.message.clear();
+            }
+        }
+    }
+}
+
+/// Type a path into the prompt; Enter confirms and Escape cancels
+pub fn handle_rule_prompt(
+    mut keyboard_events: EventReader<KeyboardInput>,
+    mut state: ResMut<RuleState>,
+) {
+    let Some(prompt) = state.prompt else {
+        keyboard_events.clear();
+        return;
+    };
+    for event in keyboard_events.read() {
+        match edit_buffer(&mut state.entry, event) {
+            PromptAction::Continue => {}
+            PromptAction::Cancel => {
+                state.prompt = None;
+                state.message.clear();
+            }
+            PromptAction::Submit => {
+                let path = PathBuf::from(state.entry.trim());
+                state.prompt = None;
+                state.message = match prompt {
+                    RulePrompt::Load => match read_rules(&path) {
+                        Ok(rules) => {
+                            let message = format!("Loaded {} rule(s)", rules.len());
+                            for rule in rules {
+                                state.rules.add(Box::new(rule));
+                            }
+                            state.enabled = true;
+                            message
+                        }
+                        Err(error) => error.to_string(),
+                    },
+                    RulePrompt::Export => {
+                        let path = path.with_extension("csv");
+                        match write_rule_report(&path, &state.report) {
+                            Ok(()) => format!("Exported {}", path.display()),
+                            Err(error) => format!("Could not export findings: {error}"),
+                        }
+                    }
+                };
+                return;
+            }
+        }
+    }
+}
+
+/// Run the rules once checked, and again after every edit
+pub fn run_rules(
+    geometry_registry: Res<GeometryRegistryResource>,
+    element_registry: Res<ElementRegistryResource>,
+    mut state: ResMut<RuleState>,
+) {
+    let changed =
+        state.is_changed() || geometry_registry.is_changed() || element_registry.is_changed();
+    if !state.enabled || !changed {
+        return;
+    }
+    // The interface keeps no explicit constraints yet, so constraint rules
+    // only see what a caller passes in
+    let context = RuleContext::new(&geometry_registry.registry, &element_registry.registry, &[]);
+    let report = state.rules.run(&context);
+    state.report = report;
+}
+
+/// Refresh the findings text when the rules or findings change
+pub fn update_rules_panel(
+    state: Res<RuleState>,
+    mut text_query: Query<(&mut Text, &mut TextColor), With<RulesText>>,
+) {
+    if !state.is_changed() {
+        return;
+    }
+    let report = &state.report;
+    let mut lines = vec![if state.enabled {
+        format!(
+            "Rules: {} rule(s), {} fail, {} warn, {} pass",
+            state.rules.rules.len(),
+            report.count(RuleOutcome::Fail),
+            report.count(RuleOutcome::Warn),
+            report.count(RuleOutcome::Pass)
+        )
+    } else {
+        format!("Rules: {} rule(s), not checked", state.rules.rules.len())
+    }];
+    match state.prompt {
+        Some(RulePrompt::Load) => lines.push(format!("Rule file: {}_", state.entry)),
+        Some(RulePrompt::Export) => lines.push(format!("Export to: {}_", state.entry)),
+        None => {}
+    }
+    if !state.message.is_empty() {
+        lines.push(state.message.clone());
+    }
+    let listed: Vec<_> = report
+        .findings
+        .iter()
+        .filter(|finding| finding.outcome != RuleOutcome::Pass)
+        .collect();
+    for finding in listed.iter().take(MAX_LISTED_FINDINGS) {
+        lines.push(format!(
+            "[{}] {}: {}",
+            finding.outcome.label(),
+            finding.rule,
+            finding.message
+        ));
+    }
+    if listed.len() > MAX_LISTED_FINDINGS {
+        lines.push(format!(
+            "... and {} more",
+            listed.len() - MAX_LISTED_FINDINGS
+        ));
+    }
+
+    let color = if report.has_failures() {
+        Color::srgb(1.0, 0.4, 0.4)
+    } else if report.count(RuleOutcome::Warn) > 0 {
+        Color::srgb(1.0, 0.8, 0.3)
+    } else {
+        Color::WHITE
+    };
+    let status = lines.join("\n");
+    for (mut text, mut text_color) in &mut text_query {
+        text.0.clone_from(&status);
+        text_color.0 = color;
+    }
+}
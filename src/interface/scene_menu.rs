@@ -0,0 +1,149 @@
+use bevy::prelude::*;
+
+use crate::infrastructure::preferences::ScenePreset;
+use crate::interface::settings::PreferencesResource;
+use crate::interface::theme::UiTheme;
+use crate::interface::ViewColumn;
+
+/// Resource tracking whether the scene dropdown is open
+#[derive(Resource, Default)]
+pub struct SceneMenuState {
+    pub open: bool,
+}
+
+/// Marker component for the button opening the scene dropdown
+#[derive(Component)]
+pub struct SceneMenuButton;
+
+/// Marker component for the list of presets under the button
+#[derive(Component)]
+pub struct SceneMenuList;
+
+/// Component for a button choosing a preset
+#[derive(Component)]
+pub struct ScenePresetButton(pub ScenePreset);
+
+/// Setup the scene dropdown in the view column
+pub fn setup_scene_menu(
+    mut commands: Commands,
+    column_query: Query<Entity, With<ViewColumn>>,
+    theme: Res<UiTheme>,
+) {
+    let Ok(column) = column_query.single() else {
+        return;
+    };
+    commands.entity(column).with_children(|parent| {
+        parent
+            .spawn((
+                Node {
+                    flex_direction: FlexDirection::Column,
+                    padding: UiRect::all(Val::Px(theme.panel_padding)),
+                    ..default()
+                },
+                BackgroundColor(theme.panel),
+            ))
+            .with_children(|parent| {
+                parent
+                    .spawn((
+                        Button,
+                        SceneMenuButton,
+                        Node {
+                            padding: UiRect::all(Val::Px(theme.button_padding)),
+                            ..default()
+                        },
+                        BackgroundColor(theme.button),
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn(Text::new("Scene"));
+                    });
+
+                parent
+                    .spawn((
+                        Node {
+                            display: Display::None,
+                            flex_direction: FlexDirection::Column,
+                            ..default()
+                        },
+                        SceneMenuList,
+                    ))
+                    .with_children(|parent| {
+                        for preset in ScenePreset::ALL {
+                            parent
+                                .spawn((
+                                    Button,
+                                    ScenePresetButton(preset),
+                                    Node {
+                                        padding: UiRect::all(Val::Px(theme.button_padding)),
+                                        margin: UiRect::top(Val::Px(2.0)),
+                                        ..default()
+                                    },
+                                    BackgroundColor(theme.button),
+                                ))
+                                .with_children(|parent| {
+                                    parent.spawn(Text::new(preset.label()));
+                                });
+                        }
+                    });
+            });
+    });
+}
+
+/// Open and close the dropdown, and switch to the preset chosen from it
+///
+/// The choice applies at once and is written to disk with the settings
+/// dialog's Save, like any other preference.
+pub fn handle_scene_menu(
+    menu_query: Query<&Interaction, (Changed<Interaction>, With<SceneMenuButton>)>,
+    preset_query: Query<(&Interaction, &ScenePresetButton), Changed<Interaction>>,
+    mut menu: ResMut<SceneMenuState>,
+    mut preferences: ResMut<PreferencesResource>,
+) {
+    if menu_query
+        .iter()
+        .any(|interaction| *interaction == Interaction::Pressed)
+    {
+        menu.open = !menu.open;
+    }
+    for (interaction, button) in &preset_query {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        menu.open = false;
+        if preferences.preferences.scene != button.0 {
+            preferences.preferences.scene = button.0;
+        }
+    }
+}
+
+/// Show the current preset on the button and the list while it is open
+pub fn update_scene_menu(
+    menu: Res<SceneMenuState>,
+    preferences: Res<PreferencesResource>,
+    button_query: Query<&Children, With<SceneMenuButton>>,
+    mut list_query: Query<&mut Node, With<SceneMenuList>>,
+    mut preset_query: Query<(&ScenePresetButton, &mut BackgroundColor)>,
+    mut text_query: Query<&mut Text>,
+    theme: Res<UiTheme>,
+) {
+    if !menu.is_changed() && !preferences.is_changed() {
+        return;
+    }
+    let scene = preferences.preferences.scene;
+    for children in &button_query {
+        for child in children {
+            if let Ok(mut text) = text_query.get_mut(*child) {
+                text.0 = format!("Scene: {} ▾", scene.label());
+            }
+        }
+    }
+    for mut node in &mut list_query {
+        node.display = if menu.open {
+            Display::Flex
+        } else {
+            Display::None
+        };
+    }
+    for (button, mut background_color) in &mut preset_query {
+        *background_color = theme.button_color(button.0 == scene).into();
+    }
+}
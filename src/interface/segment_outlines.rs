@@ -1,92 +1,260 @@
+use bevy::gizmos::config::{GizmoConfigGroup, GizmoConfigStore, GizmoLineStyle};
 use bevy::prelude::*;
+use bevy::render::primitives::{Aabb, Frustum};
+use std::collections::{BTreeSet, HashMap, HashSet};
 
-use crate::domain::GeometryRegistry;
+use crate::domain::{ElementRegistry, GeometryRegistry, Phase};
+use crate::interface::camera::MainCamera;
+use crate::interface::command_bus::SolidsEdited;
 
 /// Resource to store geometry registry for access in update systems
 #[derive(Resource)]
 pub struct GeometryRegistryResource {
+    /// The model's geometry
     pub registry: GeometryRegistry,
 }
 
+/// Resource to store the elements giving solids their meaning
+#[derive(Resource)]
+pub struct ElementRegistryResource {
+    /// The model's elements
+    pub registry: ElementRegistry,
+}
+
 /// Component to track which solid this entity represents
 #[derive(Component)]
 pub struct SolidId(pub uuid::Uuid);
 
-/// System that renders segment outlines as 2px white lines locked to the 3D geometry
-/// Draws lines in world space at the actual segment positions, transformed by entity transforms
+/// Component caching the outline of a solid's entity: the segments of
+/// its solid, in the solid's coordinates
+///
+/// Built when the entity appears and rebuilt only when its solid is edited
+/// or the registry changes outside the model commands, so drawing the
+/// outlines does not walk the registry every frame.
+#[derive(Component, Default)]
+pub struct SolidOutline {
+    /// Each segment's end points
+    pub lines: Vec<[Vec3; 2]>,
+    /// Box around the segments, to skip solids out of view
+    pub bounds: Aabb,
+}
+
+impl SolidOutline {
+    /// The outline of a solid, each segment once
+    #[must_use]
+    pub fn of_solid(solid_id: &uuid::Uuid, registry: &GeometryRegistry) -> Self {
+        let Some(solid) = registry.solids.get(solid_id) else {
+            return Self::default();
+        };
+        let segments: BTreeSet<uuid::Uuid> = solid
+            .polygons
+            .iter()
+            .filter_map(|polygon_id| registry.polygons.get(polygon_id))
+            .flat_map(|polygon| polygon.segments.iter().copied())
+            .collect();
+        let position = |vertex_id| {
+            let vertex = registry.vertices.get(vertex_id)?;
+            let position = &vertex.position;
+            Some(Vec3::new(position.x, position.y, position.z))
+        };
+        let lines = segments
+            .iter()
+            .filter_map(|segment_id| registry.segments.get(segment_id))
+            .filter_map(|segment| {
+                Some([
+                    position(&segment.vertices[0])?,
+                    position(&segment.vertices[1])?,
+                ])
+            })
+            .collect::<Vec<_>>();
+        let bounds = Aabb::enclosing(lines.iter().flatten().copied()).unwrap_or_default();
+        Self { lines, bounds }
+    }
+}
+
+/// Build the outlines of new solid entities and rebuild those of edited
+/// solids
+///
+/// A registry change with no edit events, such as a repair or an import,
+/// rebuilds every outline.
+pub fn update_solid_outlines(
+    mut commands: Commands,
+    mut edits: EventReader<SolidsEdited>,
+    geometry_registry: Res<GeometryRegistryResource>,
+    new_query: Query<(Entity, &SolidId), Without<SolidOutline>>,
+    mut outline_query: Query<(&SolidId, &mut SolidOutline)>,
+) {
+    let registry = &geometry_registry.registry;
+    for (entity, solid_id) in &new_query {
+        commands
+            .entity(entity)
+            .insert(SolidOutline::of_solid(&solid_id.0, registry));
+    }
+    let edited: HashSet<uuid::Uuid> = edits
+        .read()
+        .flat_map(|event| event.solids.iter().copied())
+        .collect();
+    if edited.is_empty() && !geometry_registry.is_changed() {
+        return;
+    }
+    for (solid_id, mut outline) in &mut outline_query {
+        if edited.is_empty() || edited.contains(&solid_id.0) {
+            *outline = SolidOutline::of_solid(&solid_id.0, registry);
+        }
+    }
+}
+
+/// Gizmo group drawing outlines, one for each combination of dashes and
+/// depth testing since the group holds the line style and depth bias
+#[derive(Default, Reflect, GizmoConfigGroup)]
+pub struct OutlineGizmos<const DASHED: bool, const ON_TOP: bool>;
+
+/// Depth bias of depth-tested outlines, pulling them just in front of the
+/// faces they lie on so they do not flicker
+const DEPTH_TESTED_BIAS: f32 = -0.001;
+
+/// How the outlines of the solids in one construction phase are drawn
+#[derive(Debug, Clone, Copy)]
+pub struct PhaseOutlineStyle {
+    /// Color of the lines
+    pub color: Color,
+    /// Whether the lines are dashed rather than solid
+    pub dashed: bool,
+    /// Whether the outlines show through the geometry in front of them,
+    /// rather than being hidden behind it
+    pub on_top: bool,
+}
+
+/// Resource holding how segment outlines are drawn
+///
+/// Outlines are drawn as screen-space quads of the given width, smoothed by
+/// the camera's multisampling, and each construction phase has its own color,
+/// dash and depth testing. The width and depth testing follow the
+/// preferences.
+#[derive(Resource)]
+pub struct OutlineStyles {
+    /// Line width in pixels
+    pub width: f32,
+    /// Style of each phase's outlines
+    pub phases: HashMap<Phase, PhaseOutlineStyle>,
+}
+
+impl Default for OutlineStyles {
+    fn default() -> Self {
+        let phases = [
+            (Phase::Existing, Color::srgb(0.65, 0.65, 0.65), false),
+            (Phase::Demolished, Color::srgb(0.9, 0.3, 0.25), true),
+            (Phase::New, Color::WHITE, false),
+        ]
+        .into_iter()
+        .map(|(phase, color, dashed)| {
+            let style = PhaseOutlineStyle {
+                color,
+                dashed,
+                on_top: true,
+            };
+            (phase, style)
+        })
+        .collect();
+        Self { width: 1.5, phases }
+    }
+}
+
+impl OutlineStyles {
+    /// The style of a phase's outlines, solid white on top if it has none
+    #[must_use]
+    pub fn phase(&self, phase: Phase) -> PhaseOutlineStyle {
+        self.phases
+            .get(&phase)
+            .copied()
+            .unwrap_or(PhaseOutlineStyle {
+                color: Color::WHITE,
+                dashed: false,
+                on_top: true,
+            })
+    }
+}
+
+/// Give one outline gizmo group the configured width, its dash and its
+/// depth testing
+fn configure_outline_group<const DASHED: bool, const ON_TOP: bool>(
+    config_store: &mut GizmoConfigStore,
+    width: f32,
+) {
+    let (config, _) = config_store.config_mut::<OutlineGizmos<DASHED, ON_TOP>>();
+    config.line.width = width;
+    if DASHED {
+        config.line.style = GizmoLineStyle::Dashed {
+            gap_scale: 3.0,
+            line_scale: 6.0,
+        };
+    }
+    config.depth_bias = if ON_TOP { -1.0 } else { DEPTH_TESTED_BIAS };
+}
+
+/// Give the outline gizmo groups the configured width
+pub fn configure_outline_gizmos(
+    styles: Res<OutlineStyles>,
+    mut config_store: ResMut<GizmoConfigStore>,
+) {
+    if !styles.is_changed() {
+        return;
+    }
+    configure_outline_group::<false, false>(&mut config_store, styles.width);
+    configure_outline_group::<false, true>(&mut config_store, styles.width);
+    configure_outline_group::<true, false>(&mut config_store, styles.width);
+    configure_outline_group::<true, true>(&mut config_store, styles.width);
+}
+
+/// System that renders segment outlines locked to the 3D geometry
+///
+/// Draws each solid's cached outline moved with its entity, in its phase's
+/// style, skipping solids out of the camera's view.
+#[allow(clippy::too_many_arguments)]
 pub fn render_segment_outlines_2d(
-    mut gizmos: Gizmos,
-    camera_query: Query<(&Camera, &GlobalTransform), With<Camera3d>>,
+    mut tested_gizmos: Gizmos<OutlineGizmos<false, false>>,
+    mut top_gizmos: Gizmos<OutlineGizmos<false, true>>,
+    mut dashed_tested_gizmos: Gizmos<OutlineGizmos<true, false>>,
+    mut dashed_top_gizmos: Gizmos<OutlineGizmos<true, true>>,
+    styles: Res<OutlineStyles>,
+    camera_query: Query<&Frustum, With<MainCamera>>,
     geometry_registry: Res<GeometryRegistryResource>,
     ui_state: Res<crate::interface::ui::UiState>,
-    mesh_entities: Query<(&GlobalTransform, &SolidId), With<crate::interface::ui::ToggleableMesh>>,
+    hidden: Res<crate::interface::isolation::HiddenSolids>,
+    mesh_entities: Query<
+        (&GlobalTransform, &SolidId, &SolidOutline),
+        With<crate::interface::ui::ToggleableMesh>,
+    >,
 ) {
     // Only render if outlines are enabled
     if !ui_state.show_outlines {
         return;
     }
-    let Ok((camera, camera_transform)) = camera_query.single() else {
+    let Ok(frustum) = camera_query.single() else {
         return;
     };
-
-    let segment_registry = &geometry_registry.registry.segments;
-    let vertex_registry = &geometry_registry.registry.vertices;
-    let polygon_registry = &geometry_registry.registry.polygons;
     let solid_registry = &geometry_registry.registry.solids;
 
-    // For each mesh entity, render its solid's segments at the entity's transform
-    for (entity_transform, solid_id_component) in mesh_entities.iter() {
-        let solid_id = &solid_id_component.0;
-
-        // Get the solid
-        let Some(solid) = solid_registry.get(solid_id) else {
+    for (entity_transform, solid_id, outline) in &mesh_entities {
+        let Some(solid) = solid_registry.get(&solid_id.0) else {
             continue;
         };
-
-        // Get all segments for this solid by iterating through its polygons
-        let mut solid_segments = std::collections::HashSet::new();
-        for polygon_id in &solid.polygons {
-            if let Some(polygon) = polygon_registry.get(polygon_id) {
-                for segment_id in &polygon.segments {
-                    solid_segments.insert(*segment_id);
-                }
-            }
+        if !ui_state.phase_filter.includes(solid.phase) || !hidden.shows(&solid_id.0) {
+            continue;
         }
-
-        // Draw each segment belonging to this solid, transformed by the entity's transform
-        for segment_id in solid_segments {
-            let Some(segment) = segment_registry.get(&segment_id) else {
-                continue;
-            };
-
-            // Get vertex positions in local space
-            let vertex1 = match vertex_registry.get(&segment.vertices[0]) {
-                Some(v) => v,
-                None => continue,
-            };
-            let pos1_local = Vec3::new(vertex1.position.x, vertex1.position.y, vertex1.position.z);
-
-            let vertex2 = match vertex_registry.get(&segment.vertices[1]) {
-                Some(v) => v,
-                None => continue,
-            };
-            let pos2_local = Vec3::new(vertex2.position.x, vertex2.position.y, vertex2.position.z);
-
-            // Transform to world space using the entity's transform
-            let pos1_world = entity_transform.transform_point(pos1_local);
-            let pos2_world = entity_transform.transform_point(pos2_local);
-
-            // Check if both points are visible on screen (optional optimization)
-            let pos1_visible = camera
-                .world_to_viewport(camera_transform, pos1_world)
-                .is_ok();
-            let pos2_visible = camera
-                .world_to_viewport(camera_transform, pos2_world)
-                .is_ok();
-
-            // Draw the line if at least one point is visible
-            if pos1_visible || pos2_visible {
-                gizmos.line(pos1_world, pos2_world, Color::WHITE);
+        let world_from_local = entity_transform.affine();
+        if !frustum.intersects_obb(&outline.bounds, &world_from_local, true, true) {
+            continue;
+        }
+        let style = styles.phase(solid.phase);
+        for [start, end] in &outline.lines {
+            let start = world_from_local.transform_point3(*start);
+            let end = world_from_local.transform_point3(*end);
+            match (style.dashed, style.on_top) {
+                (false, false) => tested_gizmos.line(start, end, style.color),
+                (false, true) => top_gizmos.line(start, end, style.color),
+                (true, false) => dashed_tested_gizmos.line(start, end, style.color),
+                (true, true) => dashed_top_gizmos.line(start, end, style.color),
             }
         }
     }
@@ -0,0 +1,455 @@
+use bevy::input_focus::InputFocus;
+use bevy::prelude::*;
+use std::collections::BTreeSet;
+use uuid::Uuid;
+
+use crate::application::selection::{Selection, SelectionType};
+use crate::domain::{GeometryRegistry, Point, Solid, Vector};
+use crate::interface::camera::MainCamera;
+use crate::interface::markup::MarkupState;
+use crate::interface::segment_outlines::{GeometryRegistryResource, SolidId};
+use crate::interface::theme::UiTheme;
+use crate::interface::transform_gizmo::GizmoState;
+use crate::interface::underlay::UnderlayCalibration;
+
+/// Color of the selected geometry's outline
+const HIGHLIGHT_COLOR: Color = Color::srgb(1.0, 0.85, 0.2);
+/// How near the cursor an edge or vertex must be drawn to be picked, in
+/// pixels
+const PICK_RADIUS: f32 = 8.0;
+/// Radius of the selected vertex's marker as a share of its distance from
+/// the camera
+const VERTEX_MARKER_SCALE: f32 = 0.008;
+
+/// The selection modes in switcher order, with keys 1 to 4
+pub const SELECTION_MODES: [SelectionType; 4] = [
+    SelectionType::Solid,
+    SelectionType::Polygon,
+    SelectionType::Segment,
+    SelectionType::Vertex,
+];
+
+/// Resource holding what the user has selected
+#[derive(Resource, Default)]
+pub struct SelectionState {
+    /// What picking selects
+    pub mode: SelectionType,
+    /// The current selection, if any
+    pub selected: Option<Selection>,
+    /// The solid the selection was picked through
+    pub solid: Option<Uuid>,
+    /// Everything under the cursor at the last pick, nearest first, each
+    /// with the solid it was picked through
+    pub candidates: Vec<(Selection, Uuid)>,
+    /// Which candidate is selected
+    pub cycle: usize,
+}
+
+impl SelectionState {
+    /// Select a candidate from the last pick
+    fn select_candidate(&mut self, index: usize) {
+        let candidate = self.candidates.get(index).copied();
+        self.cycle = index;
+        self.selected = candidate.map(|(selection, _)| selection);
+        self.solid = candidate.map(|(_, solid)| solid);
+    }
+
+    /// Replace the candidates with a new pick's and select the nearest
+    pub fn set_candidates(&mut self, candidates: Vec<(Selection, Uuid)>) {
+        self.candidates = candidates;
+        self.select_candidate(0);
+    }
+
+    /// Drop the selection and the candidates it came from
+    pub fn clear(&mut self) {
+        self.candidates.clear();
+        self.select_candidate(0);
+    }
+}
+
+/// Marker component for a selection mode button
+#[derive(Component)]
+pub struct SelectionModeButton(pub SelectionType);
+
+/// The name of a selection mode shown on its button
+#[must_use]
+pub fn selection_mode_label(mode: SelectionType) -> &'static str {
+    match mode {
+        SelectionType::Solid => "Solid",
+        SelectionType::Polygon => "Face",
+        SelectionType::Segment => "Edge",
+        SelectionType::Vertex => "Vertex",
+    }
+}
+
+/// The world-space ray under the cursor, if the cursor is in the window
+pub fn cursor_ray(window: &Window, camera: &Camera, transform: &GlobalTransform) -> Option<Ray3d> {
+    let cursor = window.cursor_position()?;
+    camera.viewport_to_world(transform, cursor).ok()
+}
+
+/// How far along a segment on screen the point nearest another lies, as a
+/// share of its length, and the distance between them
+#[must_use]
+pub fn screen_segment_closest(point: Vec2, start: Vec2, end: Vec2) -> (f32, f32) {
+    let along = end - start;
+    let t = if along.length_squared() > 0.0 {
+        ((point - start).dot(along) / along.length_squared()).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    (t, point.distance(start + along * t))
+}
+
+/// A stored position shown through an entity's transform
+fn world_point(transform: &GlobalTransform, position: &Point) -> Vec3 {
+    transform.transform_point(Vec3::new(position.x, position.y, position.z))
+}
+
+/// The solid, or the solid's faces, a world-space ray meets, with the
+/// distance along the ray
+fn ray_hits(
+    mode: SelectionType,
+    ray: Ray3d,
+    transform: &GlobalTransform,
+    registry: &GeometryRegistry,
+    solid: &Solid,
+) -> Vec<(Uuid, f32)> {
+    // Cast in the solid's own coordinates; the distance along the ray is
+    // the same in both, since the mapping is affine
+    let to_local = transform.affine().inverse();
+    let origin = to_local.transform_point3(ray.origin);
+    let direction = to_local.transform_vector3(*ray.direction);
+    let (origin, direction) = (
+        Point {
+            x: origin.x,
+            y: origin.y,
+            z: origin.z,
+        },
+        Vector {
+            x: direction.x,
+            y: direction.y,
+            z: direction.z,
+        },
+    );
+    if mode == SelectionType::Solid {
+        return registry
+            .ray_hit_solid(&solid.id, &origin, &direction)
+            .map(|distance| vec![(solid.id, distance)])
+            .unwrap_or_default();
+    }
+    solid
+        .polygons
+        .iter()
+        .filter_map(|polygon_id| {
+            registry
+                .ray_hit_polygon(polygon_id, &origin, &direction)
+                .map(|distance| (*polygon_id, distance))
+        })
+        .collect()
+}
+
+/// The solid's edges or vertices drawn near the cursor, with their
+/// distance from the camera
+fn screen_hits(
+    mode: SelectionType,
+    cursor: Vec2,
+    to_screen: &impl Fn(Vec3) -> Option<(Vec2, f32)>,
+    transform: &GlobalTransform,
+    registry: &GeometryRegistry,
+    solid: &Solid,
+) -> Vec<(Uuid, f32)> {
+    if mode == SelectionType::Vertex {
+        return registry
+            .solid_vertices(&solid.id)
+            .into_iter()
+            .filter_map(|vertex_id| {
+                let vertex = registry.vertices.get(&vertex_id)?;
+                let (screen, depth) = to_screen(world_point(transform, &vertex.position))?;
+                (screen.distance(cursor) <= PICK_RADIUS).then_some((vertex_id, depth))
+            })
+            .collect();
+    }
+    let segments: BTreeSet<Uuid> = solid
+        .polygons
+        .iter()
+        .filter_map(|polygon_id| registry.polygons.get(polygon_id))
+        .flat_map(|polygon| polygon.segments.iter().copied())
+        .collect();
+    segments
+        .into_iter()
+        .filter_map(|segment_id| {
+            let segment = registry.segments.get(&segment_id)?;
+            let (start, end) = (
+                registry.vertices.get(&segment.vertices[0])?,
+                registry.vertices.get(&segment.vertices[1])?,
+            );
+            let (start, start_depth) = to_screen(world_point(transform, &start.position))?;
+            let (end, end_depth) = to_screen(world_point(transform, &end.position))?;
+            let (t, distance) = screen_segment_closest(cursor, start, end);
+            (distance <= PICK_RADIUS).then_some((segment_id, start_depth.lerp(end_depth, t)))
+        })
+        .collect()
+}
+
+/// Everything of the mode's kind under the cursor on visible solids,
+/// nearest first, each with the solid it was picked through and its
+/// distance from the camera
+///
+/// Solids and faces are those the ray meets; edges and vertices are those
+/// drawn within a few pixels of the cursor. `to_screen` gives a world
+/// point's position in the viewport and its distance from the camera.
+pub fn pick_candidates<'a>(
+    mode: SelectionType,
+    ray: Ray3d,
+    cursor: Vec2,
+    to_screen: &impl Fn(Vec3) -> Option<(Vec2, f32)>,
+    registry: &GeometryRegistry,
+    solids: impl Iterator<Item = (&'a SolidId, &'a GlobalTransform, &'a InheritedVisibility)>,
+) -> Vec<(Selection, Uuid, f32)> {
+    let mut hits: Vec<(Uuid, Uuid, f32)> = Vec::new();
+    for (solid_id, transform, _) in solids.filter(|(_, _, visibility)| visibility.get()) {
+        let Some(solid) = registry.solids.get(&solid_id.0) else {
+            continue;
+        };
+        let found = match mode {
+            SelectionType::Solid | SelectionType::Polygon => {
+                ray_hits(mode, ray, transform, registry, solid)
+            }
+            SelectionType::Segment | SelectionType::Vertex => {
+                screen_hits(mode, cursor, to_screen, transform, registry, solid)
+            }
+        };
+        hits.extend(
+            found
+                .into_iter()
+                .map(|(id, distance)| (id, solid_id.0, distance)),
+        );
+    }
+    hits.sort_by(|a, b| a.2.total_cmp(&b.2).then(a.0.cmp(&b.0)).then(a.1.cmp(&b.1)));
+    // Geometry shared by several solids is listed once, where it is nearest
+    let mut seen = BTreeSet::new();
+    hits.into_iter()
+        .filter(|(id, _, _)| seen.insert(*id))
+        .map(|(id, solid, distance)| {
+            (
+                Selection {
+                    id,
+                    selection_type: mode,
+                },
+                solid,
+                distance,
+            )
+        })
+        .collect()
+}
+
+/// Switch what picking selects: 1 solids, 2 faces, 3 edges, 4 vertices,
+/// or the mode buttons
+pub fn switch_selection_mode(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    button_query: Query<(&Interaction, &SelectionModeButton), Changed<Interaction>>,
+    gizmo: Res<GizmoState>,
+    mut selection: ResMut<SelectionState>,
+) {
+    if gizmo.drag.is_some() {
+        return;
+    }
+    let keys = [
+        KeyCode::Digit1,
+        KeyCode::Digit2,
+        KeyCode::Digit3,
+        KeyCode::Digit4,
+    ];
+    let pressed = keys
+        .iter()
+        .zip(SELECTION_MODES)
+        .find(|(key, _)| keyboard_input.just_pressed(**key))
+        .map(|(_, mode)| mode);
+    let clicked = button_query
+        .iter()
+        .find(|(interaction, _)| **interaction == Interaction::Pressed)
+        .map(|(_, button)| button.0);
+    let Some(mode) = clicked.or(pressed) else {
+        return;
+    };
+    if selection.mode != mode {
+        selection.mode = mode;
+        selection.clear();
+    }
+}
+
+/// Step through everything that was under the cursor at the last pick:
+/// Tab for the next, Shift+Tab for the previous
+///
+/// Tab moves between the panels' buttons instead while one has focus.
+pub fn cycle_selection_candidates(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    gizmo: Res<GizmoState>,
+    focus: Res<InputFocus>,
+    mut selection: ResMut<SelectionState>,
+) {
+    let count = selection.candidates.len();
+    if count < 2
+        || gizmo.drag.is_some()
+        || focus.0.is_some()
+        || !keyboard_input.just_pressed(KeyCode::Tab)
+    {
+        return;
+    }
+    let step = if keyboard_input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+        count - 1
+    } else {
+        1
+    };
+    let index = (selection.cycle + step) % count;
+    selection.select_candidate(index);
+}
+
+/// Select the nearest geometry of the selection mode's kind under the
+/// cursor on a left click, or clear the selection when there is none
+#[allow(clippy::too_many_arguments)]
+pub fn pick_selection(
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    windows: Query<&Window>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    ui_query: Query<&Interaction>,
+    solid_query: Query<(&SolidId, &GlobalTransform, &InheritedVisibility)>,
+    geometry_registry: Res<GeometryRegistryResource>,
+    gizmo: Res<GizmoState>,
+    calibration: Res<UnderlayCalibration>,
+    markup: Res<MarkupState>,
+    mut selection: ResMut<SelectionState>,
+) {
+    if !mouse_input.just_pressed(MouseButton::Left)
+        || gizmo.hovered.is_some()
+        || gizmo.drag.is_some()
+        || calibration.underlay.is_some()
+        || markup.active
+        || ui_query
+            .iter()
+            .any(|interaction| *interaction != Interaction::None)
+    {
+        return;
+    }
+    let (Ok(window), Ok((camera, camera_transform))) = (windows.single(), camera_query.single())
+    else {
+        return;
+    };
+    let (Some(cursor), Some(ray)) = (
+        window.cursor_position(),
+        cursor_ray(window, camera, camera_transform),
+    ) else {
+        return;
+    };
+    let eye = camera_transform.translation();
+    let to_screen = |point: Vec3| {
+        camera
+            .world_to_viewport(camera_transform, point)
+            .ok()
+            .map(|screen| (screen, eye.distance(point)))
+    };
+    let candidates = pick_candidates(
+        selection.mode,
+        ray,
+        cursor,
+        &to_screen,
+        &geometry_registry.registry,
+        solid_query.iter(),
+    );
+    selection.set_candidates(
+        candidates
+            .into_iter()
+            .map(|(candidate, solid, _)| (candidate, solid))
+            .collect(),
+    );
+}
+
+/// Outline the selection, showing a drag of its faces, edges or vertices
+/// in progress
+pub fn draw_selection_highlight(
+    mut gizmos: Gizmos,
+    selection: Res<SelectionState>,
+    gizmo: Res<GizmoState>,
+    geometry_registry: Res<GeometryRegistryResource>,
+    solid_query: Query<(&SolidId, &GlobalTransform)>,
+    camera_query: Query<&GlobalTransform, With<MainCamera>>,
+) {
+    let (Some(selected), Some(solid_id)) = (selection.selected, selection.solid) else {
+        return;
+    };
+    let registry = &geometry_registry.registry;
+    let Some((_, transform)) = solid_query.iter().find(|(id, _)| id.0 == solid_id) else {
+        return;
+    };
+    let Some(solid) = registry.solids.get(&solid_id) else {
+        return;
+    };
+    let (moved, delta): (BTreeSet<Uuid>, Mat4) = gizmo
+        .outline_preview()
+        .map_or((BTreeSet::new(), Mat4::IDENTITY), |(vertices, delta)| {
+            (vertices.iter().copied().collect(), delta)
+        });
+    let world = |vertex_id: &Uuid| {
+        registry.vertices.get(vertex_id).map(|vertex| {
+            let point = world_point(transform, &vertex.position);
+            if moved.contains(vertex_id) {
+                delta.transform_point3(point)
+            } else {
+                point
+            }
+        })
+    };
+    let solid_segments: BTreeSet<Uuid> = solid
+        .polygons
+        .iter()
+        .filter_map(|id| registry.polygons.get(id))
+        .flat_map(|polygon| polygon.segments.iter().copied())
+        .collect();
+    let mut segments: BTreeSet<Uuid> = match selected.selection_type {
+        SelectionType::Solid => solid_segments.clone(),
+        SelectionType::Polygon => registry
+            .polygons
+            .get(&selected.id)
+            .map(|polygon| polygon.segments.iter().copied().collect())
+            .unwrap_or_default(),
+        SelectionType::Segment => BTreeSet::from([selected.id]),
+        SelectionType::Vertex => BTreeSet::new(),
+    };
+    // While dragging, the edges leading to the moved vertices stretch too
+    segments.extend(solid_segments.into_iter().filter(|id| {
+        registry
+            .segments
+            .get(id)
+            .is_some_and(|segment| segment.vertices.iter().any(|v| moved.contains(v)))
+    }));
+    for segment in segments.iter().filter_map(|id| registry.segments.get(id)) {
+        if let (Some(start), Some(end)) = (world(&segment.vertices[0]), world(&segment.vertices[1]))
+        {
+            gizmos.line(start, end, HIGHLIGHT_COLOR);
+        }
+    }
+    if selected.selection_type == SelectionType::Vertex {
+        if let (Some(point), Ok(camera)) = (world(&selected.id), camera_query.single()) {
+            let radius = camera.translation().distance(point) * VERTEX_MARKER_SCALE;
+            gizmos.sphere(Isometry3d::from_translation(point), radius, HIGHLIGHT_COLOR);
+        }
+    }
+}
+
+/// Show which selection mode is active on the mode buttons
+pub fn update_selection_mode_buttons(
+    selection: Res<SelectionState>,
+    mut button_query: Query<(&SelectionModeButton, &mut BackgroundColor)>,
+    theme: Res<UiTheme>,
+) {
+    if !selection.is_changed() {
+        return;
+    }
+    for (button, mut color) in &mut button_query {
+        let wanted = theme.button_color(button.0 == selection.mode);
+        if color.0 != wanted {
+            color.0 = wanted;
+        }
+    }
+}
@@ -0,0 +1,839 @@
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use std::path::PathBuf;
+
+use crate::domain::{begin_session_ids, end_session_ids, new_id};
+use crate::infrastructure::session::{
+    read_session, session_path, SessionCommand, SessionEntry, SessionWriter,
+};
+use crate::interface::command_bus::{
+    AddConstraint, AddExpressionConstraint, AddGridLayout, AddSketchPath, ConvertMass, CreateGrid,
+    CreateWall, CutSolid, EditGrid, EditOperation, ExportStl, ExtrudeFace, GenerateFinishes,
+    MoveVertex, PlaceFamily, RemoveConstraint, RouteService, SetFaceMaterial, SetLevel,
+    TransformVertices,
+};
+use crate::interface::curtain_wall::CreateCurtainGrid;
+use crate::interface::extensions::RunExtensionCommand;
+use crate::interface::file_menu::{replace_project, ProjectResources, ProjectState};
+use crate::interface::placement::{DistributeOnFace, PlaceComponent, PlaceOnFace};
+use crate::interface::segment_outlines::SolidId;
+
+/// Event requesting a session recording be replayed on a fresh model
+#[derive(Event, Clone)]
+pub struct ReplaySession {
+    /// The recording
+    pub path: PathBuf,
+    /// Whether to wait for a key press before each recorded frame's
+    /// commands, rather than replaying a recorded frame every frame
+    pub step: bool,
+}
+
+/// A session being recorded
+pub struct Recording {
+    writer: SessionWriter,
+    /// Where the recording is written
+    pub path: PathBuf,
+    /// When recording started, in seconds of app time
+    started: f64,
+    /// Frames since recording started
+    frame: u64,
+}
+
+/// A session being replayed
+pub struct Replay {
+    /// The recorded commands
+    entries: Vec<SessionEntry>,
+    /// Index of the next command to replay
+    next: usize,
+    /// Whether each recorded frame waits for a key press
+    pub step: bool,
+}
+
+/// Resource holding the session being recorded or replayed
+///
+/// While a session is recorded, every model command is appended to the
+/// recording, and new IDs are drawn from the recording's seeded sequence
+/// so that a replay makes the same ones. Edits made other than by
+/// commands, such as imports, comments and markups, are not recorded,
+/// and draw their IDs outside the sequence.
+#[derive(Resource, Default)]
+pub struct SessionState {
+    pub recording: Option<Recording>,
+    pub replay: Option<Replay>,
+}
+
+/// Every model command event, for reading them all in one system
+#[derive(SystemParam)]
+pub struct ModelCommandReaders<'w, 's> {
+    walls: EventReader<'w, 's, CreateWall>,
+    families: EventReader<'w, 's, PlaceFamily>,
+    finishes: EventReader<'w, 's, GenerateFinishes>,
+    services: EventReader<'w, 's, RouteService>,
+    masses: EventReader<'w, 's, ConvertMass>,
+    grids: EventReader<'w, 's, CreateGrid>,
+    layouts: EventReader<'w, 's, AddGridLayout>,
+    grid_edits: EventReader<'w, 's, EditGrid>,
+    extrusions: EventReader<'w, 's, ExtrudeFace>,
+    cuts: EventReader<'w, 's, CutSolid>,
+    operation_edits: EventReader<'w, 's, EditOperation>,
+    sketches: EventReader<'w, 's, AddSketchPath>,
+    moves: EventReader<'w, 's, MoveVertex>,
+    transforms: EventReader<'w, 's, TransformVertices>,
+    constraints: EventReader<'w, 's, AddConstraint>,
+    removals: EventReader<'w, 's, RemoveConstraint>,
+    levels: EventReader<'w, 's, SetLevel>,
+    expressions: EventReader<'w, 's, AddExpressionConstraint>,
+    materials: EventReader<'w, 's, SetFaceMaterial>,
+    exports: EventReader<'w, 's, ExportStl>,
+    curtain_grids: EventReader<'w, 's, CreateCurtainGrid>,
+    components: EventReader<'w, 's, PlaceComponent>,
+    placements: EventReader<'w, 's, PlaceOnFace>,
+    distributions: EventReader<'w, 's, DistributeOnFace>,
+    extension_commands: EventReader<'w, 's, RunExtensionCommand>,
+}
+
+impl ModelCommandReaders<'_, '_> {
+    /// The commands sent since the last read, grouped by command
+    fn read(&mut self) -> Vec<SessionCommand> {
+        let mut commands = Vec::new();
+        commands.extend(self.walls.read().map(|event| SessionCommand::CreateWall {
+            start: event.start.clone(),
+            end: event.end.clone(),
+            thickness: event.thickness,
+            height: event.height,
+            assembly: event.assembly.clone(),
+            per_layer: event.per_layer,
+        }));
+        commands.extend(
+            self.families
+                .read()
+                .map(|event| SessionCommand::PlaceFamily {
+                    host: event.host,
+                    offset: event.offset,
+                    parameters: event.parameters.clone(),
+                }),
+        );
+        commands.extend(
+            self.finishes
+                .read()
+                .map(|event| SessionCommand::GenerateFinishes {
+                    space: event.space,
+                    layer: event.layer.clone(),
+                }),
+        );
+        commands.extend(
+            self.services
+                .read()
+                .map(|event| SessionCommand::RouteService {
+                    kind: event.kind,
+                    section: event.section,
+                    path: event.path.clone(),
+                }),
+        );
+        commands.extend(self.masses.read().map(|event| SessionCommand::ConvertMass {
+            solid: event.solid,
+            tags: event.tags.clone(),
+            settings: event.settings.clone(),
+        }));
+        commands.extend(self.grids.read().map(|event| SessionCommand::CreateGrid {
+            grid: event.grid.clone(),
+        }));
+        commands.extend(
+            self.layouts
+                .read()
+                .map(|event| SessionCommand::AddGridLayout {
+                    grid: event.grid,
+                    layout: event.layout.clone(),
+                }),
+        );
+        commands.extend(
+            self.grid_edits
+                .read()
+                .map(|event| SessionCommand::EditGrid {
+                    grid: event.grid,
+                    edit: event.edit.clone(),
+                }),
+        );
+        commands.extend(
+            self.extrusions
+                .read()
+                .map(|event| SessionCommand::ExtrudeFace {
+                    face: event.face,
+                    depth: event.depth,
+                }),
+        );
+        commands.extend(self.cuts.read().map(|event| SessionCommand::CutSolid {
+            solid: event.solid,
+            plane: event.plane.clone(),
+            keep_front: event.keep_front,
+        }));
+        commands.extend(self.read_edits());
+        commands.extend(self.read_tools());
+        commands
+    }
+
+    /// The commands that edit existing geometry, constraints and levels,
+    /// and export it
+    fn read_edits(&mut self) -> Vec<SessionCommand> {
+        let mut commands = Vec::new();
+        commands.extend(
+            self.operation_edits
+                .read()
+                .map(|event| SessionCommand::EditOperation {
+                    record: event.record,
+                    change: event.change.clone(),
+                }),
+        );
+        commands.extend(
+            self.sketches
+                .read()
+                .map(|event| SessionCommand::AddSketchPath {
+                    points: event.points.clone(),
+                }),
+        );
+        commands.extend(self.moves.read().map(|event| SessionCommand::MoveVertex {
+            vertex: event.vertex,
+            position: event.position.clone(),
+        }));
+        commands.extend(
+            self.transforms
+                .read()
+                .map(|event| SessionCommand::TransformVertices {
+                    vertices: event.vertices.clone(),
+                    transform: event.transform.to_cols_array(),
+                }),
+        );
+        commands.extend(
+            self.removals
+                .read()
+                .map(|event| SessionCommand::RemoveConstraint {
+                    constraint: event.constraint.clone(),
+                }),
+        );
+        commands.extend(
+            self.constraints
+                .read()
+                .map(|event| SessionCommand::AddConstraint {
+                    constraint: event.constraint.clone(),
+                }),
+        );
+        commands.extend(self.levels.read().map(|event| SessionCommand::SetLevel {
+            name: event.name.clone(),
+            elevation: event.elevation,
+        }));
+        commands.extend(self.expressions.read().map(|event| {
+            SessionCommand::AddExpressionConstraint {
+                text: event.text.clone(),
+            }
+        }));
+        commands.extend(
+            self.materials
+                .read()
+                .map(|event| SessionCommand::SetFaceMaterial {
+                    solid: event.solid,
+                    polygon: event.polygon,
+                    material: event.material.clone(),
+                }),
+        );
+        commands.extend(self.exports.read().map(|event| SessionCommand::ExportStl {
+            path: event.path.clone(),
+            phases: event.phases,
+        }));
+        commands
+    }
+
+    /// The commands of the design tools: curtain walls, components and
+    /// the commands extensions add
+    fn read_tools(&mut self) -> Vec<SessionCommand> {
+        let mut commands = Vec::new();
+        commands.extend(
+            self.curtain_grids
+                .read()
+                .map(|event| SessionCommand::CreateCurtainGrid {
+                    face: event.face,
+                    settings: event.settings.clone(),
+                }),
+        );
+        commands.extend(
+            self.components
+                .read()
+                .map(|event| SessionCommand::PlaceComponent {
+                    definition: event.definition,
+                    placement: event.placement,
+                }),
+        );
+        commands.extend(
+            self.placements
+                .read()
+                .map(|event| SessionCommand::PlaceOnFace {
+                    definition: event.definition,
+                    face: event.face,
+                    offset: event.offset,
+                }),
+        );
+        commands.extend(
+            self.distributions
+                .read()
+                .map(|event| SessionCommand::DistributeOnFace {
+                    definition: event.definition,
+                    face: event.face,
+                    count: event.count,
+                    offset: event.offset,
+                }),
+        );
+        commands.extend(self.extension_commands.read().map(|event| {
+            SessionCommand::RunExtensionCommand {
+                name: event.name.clone(),
+                arguments: event.arguments.clone(),
+            }
+        }));
+        commands
+    }
+}
+
+/// Every model command event, for sending them all from one system
+#[derive(SystemParam)]
+pub struct ModelCommandWriters<'w> {
+    walls: EventWriter<'w, CreateWall>,
+    families: EventWriter<'w, PlaceFamily>,
+    finishes: EventWriter<'w, GenerateFinishes>,
+    services: EventWriter<'w, RouteService>,
+    masses: EventWriter<'w, ConvertMass>,
+    grids: EventWriter<'w, CreateGrid>,
+    layouts: EventWriter<'w, AddGridLayout>,
+    grid_edits: EventWriter<'w, EditGrid>,
+    extrusions: EventWriter<'w, ExtrudeFace>,
+    cuts: EventWriter<'w, CutSolid>,
+    operation_edits: EventWriter<'w, EditOperation>,
+    sketches: EventWriter<'w, AddSketchPath>,
+    moves: EventWriter<'w, MoveVertex>,
+    transforms: EventWriter<'w, TransformVertices>,
+    constraints: EventWriter<'w, AddConstraint>,
+    removals: EventWriter<'w, RemoveConstraint>,
+    levels: EventWriter<'w, SetLevel>,
+    expressions: EventWriter<'w, AddExpressionConstraint>,
+    materials: EventWriter<'w, SetFaceMaterial>,
+    exports: EventWriter<'w, ExportStl>,
+    curtain_grids: EventWriter<'w, CreateCurtainGrid>,
+    components: EventWriter<'w, PlaceComponent>,
+    placements: EventWriter<'w, PlaceOnFace>,
+    distributions: EventWriter<'w, DistributeOnFace>,
+    extension_commands: EventWriter<'w, RunExtensionCommand>,
+}
+
+impl ModelCommandWriters<'_> {
+    /// Send a recorded command as the event it was given as
+    fn write(&mut self, command: SessionCommand) {
+        match command {
+            SessionCommand::CreateWall {
+                start,
+                end,
+                thickness,
+                height,
+                assembly,
+                per_layer,
+            } => {
+                self.walls.write(CreateWall {
+                    start,
+                    end,
+                    thickness,
+                    height,
+                    assembly,
+                    per_layer,
+                });
+            }
+            SessionCommand::PlaceFamily {
+                host,
+                offset,
+                parameters,
+            } => {
+                self.families.write(PlaceFamily {
+                    host,
+                    offset,
+                    parameters,
+                });
+            }
+            SessionCommand::GenerateFinishes { space, layer } => {
+                self.finishes.write(GenerateFinishes { space, layer });
+            }
+            SessionCommand::RouteService {
+                kind,
+                section,
+                path,
+            } => {
+                self.services.write(RouteService {
+                    kind,
+                    section,
+                    path,
+                });
+            }
+            SessionCommand::ConvertMass {
+                solid,
+                tags,
+                settings,
+            } => {
+                self.masses.write(ConvertMass {
+                    solid,
+                    tags,
+                    settings,
+                });
+            }
+            SessionCommand::CreateGrid { grid } => {
+                self.grids.write(CreateGrid { grid });
+            }
+            SessionCommand::AddGridLayout { grid, layout } => {
+                self.layouts.write(AddGridLayout { grid, layout });
+            }
+            SessionCommand::EditGrid { grid, edit } => {
+                self.grid_edits.write(EditGrid { grid, edit });
+            }
+            SessionCommand::ExtrudeFace { face, depth } => {
+                self.extrusions.write(ExtrudeFace { face, depth });
+            }
+            SessionCommand::CutSolid {
+                solid,
+                plane,
+                keep_front,
+            } => {
+                self.cuts.write(CutSolid {
+                    solid,
+                    plane,
+                    keep_front,
+                });
+            }
+            command => self.write_edit(command),
+        }
+    }
+
+    /// Send a recorded command that edits existing geometry, constraints
+    /// or levels, or exports it
+    fn write_edit(&mut self, command: SessionCommand) {
+        match command {
+            SessionCommand::EditOperation { record, change } => {
+                self.operation_edits.write(EditOperation { record, change });
+            }
+            SessionCommand::AddSketchPath { points } => {
+                self.sketches.write(AddSketchPath { points });
+            }
+            SessionCommand::MoveVertex { vertex, position } => {
+                self.moves.write(MoveVertex { vertex, position });
+            }
+            SessionCommand::TransformVertices {
+                vertices,
+                transform,
+            } => {
+                self.transforms.write(TransformVertices {
+                    vertices,
+                    transform: Mat4::from_cols_array(&transform),
+                });
+            }
+            SessionCommand::AddConstraint { constraint } => {
+                self.constraints.write(AddConstraint { constraint });
+            }
+            SessionCommand::RemoveConstraint { constraint } => {
+                self.removals.write(RemoveConstraint { constraint });
+            }
+            SessionCommand::SetLevel { name, elevation } => {
+                self.levels.write(SetLevel { name, elevation });
+            }
+            SessionCommand::AddExpressionConstraint { text } => {
+                self.expressions.write(AddExpressionConstraint { text });
+            }
+            SessionCommand::SetFaceMaterial {
+                solid,
+                polygon,
+                material,
+            } => {
+                self.materials.write(SetFaceMaterial {
+                    solid,
+                    polygon,
+                    material,
+                });
+            }
+            SessionCommand::ExportStl { path, phases } => {
+                self.exports.write(ExportStl { path, phases });
+            }
+            command => self.write_tool(command),
+        }
+    }
+
+    /// Send a recorded command of a design tool
+    fn write_tool(&mut self, command: SessionCommand) {
+        match command {
+            SessionCommand::CreateCurtainGrid { face, settings } => {
+                self.curtain_grids
+                    .write(CreateCurtainGrid { face, settings });
+            }
+            SessionCommand::PlaceComponent {
+                definition,
+                placement,
+            } => {
+                self.components.write(PlaceComponent {
+                    definition,
+                    placement,
+                });
+            }
+            SessionCommand::PlaceOnFace {
+                definition,
+                face,
+                offset,
+            } => {
+                self.placements.write(PlaceOnFace {
+                    definition,
+                    face,
+                    offset,
+                });
+            }
+            SessionCommand::DistributeOnFace {
+                definition,
+                face,
+                count,
+                offset,
+            } => {
+                self.distributions.write(DistributeOnFace {
+                    definition,
+                    face,
+                    count,
+                    offset,
+                });
+            }
+            SessionCommand::RunExtensionCommand { name, arguments } => {
+                self.extension_commands
+                    .write(RunExtensionCommand { name, arguments });
+            }
+            // Commands that make or edit geometry are sent by `write` and
+            // `write_edit`
+            _ => {}
+        }
+    }
+}
+
+/// Start recording the session with F9, or stop recording it
+///
+/// The recording starts from the model as it is, written to its header as
+/// a project file would hold it.
+pub fn toggle_session_recording(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    resources: ProjectResources,
+    project: Res<ProjectState>,
+    time: Res<Time>,
+    mut state: ResMut<SessionState>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::F9) || state.replay.is_some() {
+        return;
+    }
+    if let Some(mut recording) = state.recording.take() {
+        end_session_ids();
+        if let Err(error) = recording.writer.flush() {
+            warn!("{error}");
+        }
+        info!("Stopped recording to {}", recording.path.display());
+        return;
+    }
+    let started = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+    let Some(path) = session_path(project.path.as_deref(), started) else {
+        warn!("No folder to record the session to");
+        return;
+    };
+    let text = match resources.export() {
+        Ok(text) => text,
+        Err(error) => {
+            warn!("Could not record the session: {error}");
+            return;
+        }
+    };
+    let seed = new_id().as_u64_pair().0;
+    match SessionWriter::create(&path, seed, &text) {
+        Ok(writer) => {
+            begin_session_ids(seed);
+            info!("Recording the session to {}", path.display());
+            state.recording = Some(Recording {
+                writer,
+                path,
+                started: time.elapsed_secs_f64(),
+                frame: 0,
+            });
+        }
+        Err(error) => warn!("{error}"),
+    }
+}
+
+/// Append the model commands given this frame to the recording
+///
+/// Commands are read every frame, recording or not, so a recording starts
+/// with the commands given after it.
+pub fn record_session_commands(
+    mut readers: ModelCommandReaders,
+    time: Res<Time>,
+    mut state: ResMut<SessionState>,
+) {
+    let commands = readers.read();
+    let Some(recording) = state.bypass_change_detection().recording.as_mut() else {
+        return;
+    };
+    let frame = recording.frame;
+    recording.frame += 1;
+    if commands.is_empty() {
+        return;
+    }
+    let elapsed = time.elapsed_secs_f64() - recording.started;
+    let written = commands.into_iter().try_for_each(|command| {
+        recording.writer.write(&SessionEntry {
+            time: elapsed,
+            frame,
+            command,
+        })
+    });
+    if let Err(error) = written.and_then(|()| recording.writer.flush()) {
+        warn!("Stopped recording: {error}");
+        end_session_ids();
+        state.recording = None;
+    }
+}
+
+/// Replace the model with a recording's starting project and start
+/// replaying its commands
+///
+/// The model is replaced as opening a project replaces it, clearing what
+/// a project file does not hold, before new IDs are drawn from the
+/// recording's seed.
+pub fn start_session_replay(
+    mut commands: Commands,
+    mut replays: EventReader<ReplaySession>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    solid_entities: Query<Entity, With<SolidId>>,
+    mut state: ResMut<SessionState>,
+) {
+    let Some(request) = replays.read().last() else {
+        return;
+    };
+    if state.recording.is_some() {
+        warn!("Stop recording before replaying a session");
+        return;
+    }
+    let session = match read_session(&request.path) {
+        Ok(session) => session,
+        Err(error) => {
+            warn!("{error}");
+            return;
+        }
+    };
+    replace_project(
+        &mut commands,
+        session.project,
+        &solid_entities,
+        &mut meshes,
+        &mut materials,
+    );
+    begin_session_ids(session.seed);
+    info!(
+        "Replaying {} commands from {}{}",
+        session.entries.len(),
+        request.path.display(),
+        if request.step {
+            ": F10 plays the next frame, Shift+F10 the rest"
+        } else {
+            ""
+        }
+    );
+    state.replay = Some(Replay {
+        entries: session.entries,
+        next: 0,
+        step: request.step,
+    });
+}
+
+/// Send the commands of the next recorded frame, every frame or, stepping,
+/// when F10 is pressed
+///
+/// Shift+F10 stops stepping and replays the rest. The replay ends the
+/// frame after its last commands, once they have been carried out.
+pub fn replay_session_commands(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut writers: ModelCommandWriters,
+    mut state: ResMut<SessionState>,
+) {
+    let Some(replay) = state.bypass_change_detection().replay.as_mut() else {
+        return;
+    };
+    let Some(frame) = replay.entries.get(replay.next).map(|entry| entry.frame) else {
+        end_session_ids();
+        info!("Replay finished");
+        state.replay = None;
+        return;
+    };
+    if replay.step {
+        if !keyboard_input.just_pressed(KeyCode::F10) {
+            return;
+        }
+        if keyboard_input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+            replay.step = false;
+        }
+    }
+    let count = replay.entries[replay.next..]
+        .iter()
+        .take_while(|entry| entry.frame == frame)
+        .count();
+    let entries = &replay.entries[replay.next..replay.next + count];
+    if replay.step {
+        let names: Vec<&str> = entries.iter().map(|entry| entry.command.name()).collect();
+        info!(
+            "Replayed frame {frame} at {:.2} s: {}",
+            entries[0].time,
+            names.join(", ")
+        );
+    }
+    for entry in entries {
+        writers.write(entry.command.clone());
+    }
+    replay.next += count;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::ecs::schedule::ExecutorKind;
+    use bevy::ecs::system::RunSystemOnce;
+    use std::sync::PoisonError;
+
+    use crate::domain::geometry::newell_normal;
+    use crate::domain::ids::SESSION_TESTS;
+    use crate::domain::{with_deterministic_ids, CurtainGridSettings, Point};
+    use crate::infrastructure::session::SESSION_EXTENSION;
+    use crate::interface::curtain_wall::CurtainGridResource;
+    use crate::interface::headless::{HarmonyHeadlessPlugin, ModelCommandSet};
+    use crate::interface::segment_outlines::GeometryRegistryResource;
+
+    /// A headless app that records and replays sessions
+    ///
+    /// Its systems run one at a time on the test's thread, so the IDs
+    /// they draw come from the test's own sequence and not from the
+    /// sessions of tests running beside it.
+    fn app() -> App {
+        let mut app = App::new();
+        app.add_plugins(HarmonyHeadlessPlugin)
+            .init_resource::<SessionState>()
+            .init_resource::<Time>()
+            .init_resource::<ButtonInput<KeyCode>>()
+            .init_resource::<Assets<Mesh>>()
+            .init_resource::<Assets<StandardMaterial>>()
+            .add_event::<ReplaySession>()
+            .add_systems(
+                Update,
+                (
+                    record_session_commands,
+                    start_session_replay,
+                    replay_session_commands,
+                )
+                    .chain()
+                    .before(ModelCommandSet),
+            )
+            .edit_schedule(Update, |schedule| {
+                schedule.set_executor_kind(ExecutorKind::SingleThreaded);
+            });
+        app.update();
+        app
+    }
+
+    /// The model as a project file holds it
+    fn project_text(app: &mut App) -> String {
+        app.world_mut()
+            .run_system_once(|resources: ProjectResources| resources.export().unwrap())
+            .unwrap()
+    }
+
+    fn point(x: f32, y: f32, z: f32) -> Point {
+        Point { x, y, z }
+    }
+
+    #[test]
+    fn a_replayed_session_rebuilds_the_recorded_model() {
+        let _lock = SESSION_TESTS.lock().unwrap_or_else(PoisonError::into_inner);
+        let folder = tempfile::tempdir().unwrap();
+        let path = folder.path().join(format!("test.{SESSION_EXTENSION}"));
+        let seed = 11;
+
+        let mut recorded = app();
+        recorded.world_mut().send_event(CreateWall {
+            start: point(0.0, 0.0, 0.0),
+            end: point(4.0, 0.0, 0.0),
+            thickness: 0.2,
+            height: 3.0,
+            assembly: None,
+            per_layer: false,
+        });
+        recorded.update();
+        let registry = &recorded
+            .world()
+            .resource::<GeometryRegistryResource>()
+            .registry;
+        let face = registry
+            .solids
+            .sorted()
+            .into_iter()
+            .flat_map(|solid| solid.polygons.iter())
+            .copied()
+            .find(|polygon| {
+                registry
+                    .polygon_points(polygon)
+                    .is_some_and(|points| newell_normal(&points).z.abs() > 0.5)
+            })
+            .unwrap();
+        let writer = SessionWriter::create(&path, seed, &project_text(&mut recorded)).unwrap();
+        recorded
+            .world_mut()
+            .resource_mut::<SessionState>()
+            .recording = Some(Recording {
+            writer,
+            path: path.clone(),
+            started: 0.0,
+            frame: 0,
+        });
+        let expected = with_deterministic_ids(seed, || {
+            recorded.world_mut().send_event(CreateCurtainGrid {
+                face,
+                settings: CurtainGridSettings::default(),
+            });
+            recorded.update();
+            let world = recorded.world();
+            let grids = &world.resource::<CurtainGridResource>().registry;
+            let piece = grids.sorted()[0].solids[0];
+            let registry = &world.resource::<GeometryRegistryResource>().registry;
+            let vertex = registry.solid_vertices(&piece)[0];
+            let position = registry.vertices.get(&vertex).unwrap().position.clone();
+            recorded.world_mut().send_event(MoveVertex {
+                vertex,
+                position: point(position.x, position.y, position.z + 0.1),
+            });
+            recorded.update();
+            project_text(&mut recorded)
+        });
+        recorded
+            .world_mut()
+            .resource_mut::<SessionState>()
+            .recording = None;
+
+        let mut replayed = app();
+        replayed
+            .world_mut()
+            .send_event(ReplaySession { path, step: true });
+        replayed.update();
+        let actual = with_deterministic_ids(seed, || {
+            let mut keys = replayed.world_mut().resource_mut::<ButtonInput<KeyCode>>();
+            keys.press(KeyCode::ShiftLeft);
+            keys.press(KeyCode::F10);
+            for _ in 0..3 {
+                replayed.update();
+            }
+            project_text(&mut replayed)
+        });
+
+        assert!(replayed.world().resource::<SessionState>().replay.is_none());
+        let grids = &replayed.world().resource::<CurtainGridResource>().registry;
+        assert_eq!(grids.grids.len(), 1);
+        assert_eq!(actual, expected);
+    }
+}
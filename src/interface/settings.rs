@@ -0,0 +1,575 @@
+use bevy::input::keyboard::KeyboardInput;
+use bevy::input::ButtonState;
+use bevy::prelude::*;
+
+use crate::domain::{Phase, Tolerance};
+use crate::infrastructure::preferences::{Preferences, Theme, UnitSystem, KEY_ACTIONS};
+use crate::infrastructure::standards::{load_office_standards, standards_path, OfficeStandards};
+use crate::interface::camera::{CameraConfig, CameraKeys};
+use crate::interface::issues_panel::ValidationState;
+use crate::interface::keyboard_navigation::AccessibleName;
+use crate::interface::localization::Localization;
+use crate::interface::segment_outlines::OutlineStyles;
+use crate::interface::theme::UiTheme;
+use crate::interface::underlay::UnderlayCalibration;
+
+/// Keys that can be bound to camera actions
+const BINDABLE_KEYS: [KeyCode; 36] = [
+    KeyCode::KeyA,
+    KeyCode::KeyB,
+    KeyCode::KeyC,
+    KeyCode::KeyD,
+    KeyCode::KeyE,
+    KeyCode::KeyF,
+    KeyCode::KeyG,
+    KeyCode::KeyH,
+    KeyCode::KeyI,
+    KeyCode::KeyJ,
+    KeyCode::KeyK,
+    KeyCode::KeyL,
+    KeyCode::KeyM,
+    KeyCode::KeyN,
+    KeyCode::KeyO,
+    KeyCode::KeyP,
+    KeyCode::KeyQ,
+    KeyCode::KeyR,
+    KeyCode::KeyS,
+    KeyCode::KeyT,
+    KeyCode::KeyU,
+    KeyCode::KeyV,
+    KeyCode::KeyW,
+    KeyCode::KeyX,
+    KeyCode::KeyY,
+    KeyCode::KeyZ,
+    KeyCode::ArrowLeft,
+    KeyCode::ArrowRight,
+    KeyCode::ArrowUp,
+    KeyCode::ArrowDown,
+    KeyCode::PageUp,
+    KeyCode::PageDown,
+    KeyCode::Home,
+    KeyCode::End,
+    KeyCode::Space,
+    KeyCode::ShiftLeft,
+];
+
+/// Resource holding the user's preferences
+#[derive(Resource)]
+pub struct PreferencesResource {
+    pub preferences: Preferences,
+}
+
+/// Resource holding the office standards and where they came from
+#[derive(Resource)]
+pub struct OfficeStandardsResource {
+    pub standards: OfficeStandards,
+    /// The standards file, or why the built-in standards are used
+    pub source: String,
+}
+
+impl OfficeStandardsResource {
+    /// Read the standards file in the settings folder, falling back to the
+    /// built-in standards if it is missing or invalid
+    pub fn load() -> Self {
+        match (load_office_standards(), standards_path()) {
+            (Ok(standards), Some(path)) if path.exists() => Self {
+                standards,
+                source: path.display().to_string(),
+            },
+            (Ok(standards), _) => Self {
+                standards,
+                source: "built-in".to_string(),
+            },
+            (Err(error), _) => {
+                warn!("{error}");
+                Self {
+                    standards: OfficeStandards::default(),
+                    source: format!("built-in ({error})"),
+                }
+            }
+        }
+    }
+}
+
+/// Resource tracking the settings dialog
+#[derive(Resource, Default)]
+pub struct SettingsState {
+    pub open: bool,
+    /// Index into `KEY_ACTIONS` of the action waiting for a key
+    pub rebinding: Option<usize>,
+    pub message: String,
+}
+
+/// What a settings dialog button does
+#[derive(Component, Clone, Copy)]
+pub enum SettingsButton {
+    Toggle,
+    CycleUnits,
+    CycleTheme,
+    CycleLanguage,
+    Tolerance(bool),
+    MovementSpeed(bool),
+    RotationSpeed(bool),
+    OutlineWidth(bool),
+    OutlineOnTop(Phase),
+    TextSize(bool),
+    Autosave(bool),
+    Rebind(usize),
+    Save,
+    Revert,
+}
+
+/// Marker component for the settings dialog body
+#[derive(Component)]
+pub struct SettingsPanel;
+
+/// Marker component for the settings values text
+#[derive(Component)]
+pub struct SettingsText;
+
+/// Find a bindable key by its name, such as `KeyW`
+fn key_code(name: &str) -> Option<KeyCode> {
+    BINDABLE_KEYS
+        .into_iter()
+        .find(|key| format!("{key:?}") == name)
+}
+
+/// Camera keys from a keymap, keeping defaults for unknown names
+fn camera_keys(preferences: &Preferences) -> CameraKeys {
+    let mut keys = CameraKeys::default();
+    for (action, name) in &preferences.keymap {
+        let Some(key) = key_code(name) else {
+            continue;
+        };
+        let slot = match action.as_str() {
+            "move_forward" => &mut keys.move_forward,
+            "move_back" => &mut keys.move_back,
+            "move_left" => &mut keys.move_left,
+            "move_right" => &mut keys.move_right,
+            "move_up" => &mut keys.move_up,
+            "move_down" => &mut keys.move_down,
+            "orbit_left" => &mut keys.orbit_left,
+            "orbit_right" => &mut keys.orbit_right,
+            "orbit_up" => &mut keys.orbit_up,
+            "orbit_down" => &mut keys.orbit_down,
+            _ => continue,
+        };
+        *slot = key;
+    }
+    keys
+}
+
+/// Spawn a button with a short label
+fn spawn_settings_button(
+    parent: &mut ChildSpawnerCommands,
+    action: SettingsButton,
+    label: &str,
+    theme: &UiTheme,
+) -> Entity {
+    parent
+        .spawn((
+            Button,
+            action,
+            Node {
+                padding: UiRect::axes(Val::Px(6.0), Val::Px(2.0)),
+                margin: UiRect::all(Val::Px(2.0)),
+                ..default()
+            },
+            BackgroundColor(theme.button),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new(label),
+                TextFont {
+                    font_size: theme.small_font_size,
+                    ..default()
+                },
+            ));
+        })
+        .id()
+}
+
+/// Setup the settings button and its hidden dialog at the top of the screen
+pub fn setup_settings(mut commands: Commands, theme: Res<UiTheme>) {
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(10.0),
+                left: Val::Percent(40.0),
+                flex_direction: FlexDirection::Column,
+                ..default()
+            },
+            BackgroundColor(Color::NONE),
+        ))
+        .with_children(|parent| {
+            spawn_settings_button(parent, SettingsButton::Toggle, "Settings", &theme);
+
+            parent
+                .spawn((
+                    Node {
+                        display: Display::None,
+                        flex_direction: FlexDirection::Column,
+                        padding: UiRect::all(Val::Px(theme.panel_padding)),
+                        ..default()
+                    },
+                    BackgroundColor(theme.panel),
+                    SettingsPanel,
+                ))
+                .with_children(|parent| spawn_settings_rows(parent, &theme));
+        });
+}
+
+/// Spawn the values text and the rows of buttons inside the dialog
+fn spawn_settings_rows(parent: &mut ChildSpawnerCommands, theme: &UiTheme) {
+    parent.spawn((
+        Text::new(""),
+        TextFont {
+            font_size: theme.small_font_size,
+            ..default()
+        },
+        SettingsText,
+    ));
+
+    let rows: [(&str, [(SettingsButton, &str); 2]); 6] = [
+        (
+            "Tolerance",
+            [
+                (SettingsButton::Tolerance(false), "Finer"),
+                (SettingsButton::Tolerance(true), "Coarser"),
+            ],
+        ),
+        (
+            "Move speed",
+            [
+                (SettingsButton::MovementSpeed(false), "-"),
+                (SettingsButton::MovementSpeed(true), "+"),
+            ],
+        ),
+        (
+            "Orbit speed",
+            [
+                (SettingsButton::RotationSpeed(false), "-"),
+                (SettingsButton::RotationSpeed(true), "+"),
+            ],
+        ),
+        (
+            "Outline width",
+            [
+                (SettingsButton::OutlineWidth(false), "-"),
+                (SettingsButton::OutlineWidth(true), "+"),
+            ],
+        ),
+        (
+            "Text size",
+            [
+                (SettingsButton::TextSize(false), "-"),
+                (SettingsButton::TextSize(true), "+"),
+            ],
+        ),
+        (
+            "Autosave",
+            [
+                (SettingsButton::Autosave(false), "-"),
+                (SettingsButton::Autosave(true), "+"),
+            ],
+        ),
+    ];
+    parent.spawn(settings_row()).with_children(|parent| {
+        spawn_settings_button(parent, SettingsButton::CycleUnits, "Units", theme);
+        spawn_settings_button(parent, SettingsButton::CycleTheme, "Theme", theme);
+        spawn_settings_button(parent, SettingsButton::CycleLanguage, "Language", theme);
+    });
+    for (label, buttons) in rows {
+        parent.spawn(settings_row()).with_children(|parent| {
+            parent.spawn((
+                Text::new(label),
+                TextFont {
+                    font_size: theme.small_font_size,
+                    ..default()
+                },
+            ));
+            for (action, text) in buttons {
+                let button = spawn_settings_button(parent, action, text, theme);
+                // Screen readers would otherwise read out a bare sign
+                let change = match text {
+                    "-" => "Decrease",
+                    "+" => "Increase",
+                    _ => continue,
+                };
+                parent
+                    .commands_mut()
+                    .entity(button)
+                    .insert(AccessibleName(format!("{change} {}", label.to_lowercase())));
+            }
+        });
+    }
+    spawn_outline_row(parent, theme);
+    parent.spawn(settings_row()).with_children(|parent| {
+        for (index, action) in KEY_ACTIONS.iter().enumerate() {
+            spawn_settings_button(
+                parent,
+                SettingsButton::Rebind(index),
+                &action.replace('_', " "),
+                theme,
+            );
+        }
+    });
+    parent.spawn(settings_row()).with_children(|parent| {
+        spawn_settings_button(parent, SettingsButton::Save, "Save", theme);
+        spawn_settings_button(parent, SettingsButton::Revert, "Revert", theme);
+    });
+}
+
+/// Layout of a row of the dialog
+fn settings_row() -> Node {
+    Node {
+        flex_direction: FlexDirection::Row,
+        flex_wrap: FlexWrap::Wrap,
+        align_items: AlignItems::Center,
+        ..default()
+    }
+}
+
+/// Spawn the row of buttons choosing which phases' outlines are drawn on
+/// top
+fn spawn_outline_row(parent: &mut ChildSpawnerCommands, theme: &UiTheme) {
+    parent.spawn(settings_row()).with_children(|parent| {
+        parent.spawn((
+            Text::new("Outlines on top"),
+            TextFont {
+                font_size: theme.small_font_size,
+                ..default()
+            },
+        ));
+        for phase in Phase::ALL {
+            spawn_settings_button(
+                parent,
+                SettingsButton::OutlineOnTop(phase),
+                phase.label(),
+                theme,
+            );
+        }
+    });
+}
+
+/// Edit preferences from the settings dialog; changes apply immediately
+/// and are written to disk with Save
+pub fn handle_settings_buttons(
+    interaction_query: Query<(&Interaction, &SettingsButton), Changed<Interaction>>,
+    mut preferences: ResMut<PreferencesResource>,
+    mut standards: ResMut<OfficeStandardsResource>,
+    mut settings: ResMut<SettingsState>,
+    localization: Res<Localization>,
+) {
+    for (interaction, button) in &interaction_query {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        let step = |up: bool| if up { 1.0 } else { -1.0 };
+        let preferences = &mut preferences.preferences;
+        match *button {
+            SettingsButton::Toggle => {
+                settings.open = !settings.open;
+                settings.rebinding = None;
+            }
+            SettingsButton::CycleUnits => {
+                let index = UnitSystem::ALL
+                    .iter()
+                    .position(|units| *units == preferences.units)
+                    .unwrap_or(0);
+                preferences.units = UnitSystem::ALL[(index + 1) % UnitSystem::ALL.len()];
+            }
+            SettingsButton::CycleTheme => {
+                let index = Theme::ALL
+                    .iter()
+                    .position(|theme| *theme == preferences.theme)
+                    .unwrap_or(0);
+                preferences.theme = Theme::ALL[(index + 1) % Theme::ALL.len()];
+            }
+            SettingsButton::CycleLanguage => {
+                preferences.language = localization.next_language(&preferences.language);
+            }
+            SettingsButton::Tolerance(up) => {
+                let factor = if up { 10.0 } else { 0.1 };
+                preferences.linear_tolerance =
+                    (preferences.linear_tolerance * factor).clamp(1e-6, 0.1);
+            }
+            SettingsButton::MovementSpeed(up) => {
+                preferences.movement_speed = (preferences.movement_speed + step(up) * 0.5).max(0.5);
+            }
+            SettingsButton::RotationSpeed(up) => {
+                preferences.rotation_speed = (preferences.rotation_speed + step(up) * 0.5).max(0.5);
+            }
+            SettingsButton::OutlineWidth(up) => {
+                preferences.outline_width =
+                    (preferences.outline_width + step(up) * 0.5).clamp(0.5, 8.0);
+            }
+            SettingsButton::OutlineOnTop(phase) => {
+                let on_top = preferences.outline_on_top(phase);
+                preferences.outlines_on_top.insert(phase, !on_top);
+            }
+            SettingsButton::TextSize(up) => {
+                // Rounded to tenths so repeated steps land on round sizes
+                let scale = (preferences.text_scale + step(up) * 0.1).clamp(0.7, 2.0);
+                preferences.text_scale = (scale * 10.0).round() / 10.0;
+            }
+            SettingsButton::Autosave(true) => preferences.autosave_minutes += 1,
+            SettingsButton::Autosave(false) => {
+                preferences.autosave_minutes = preferences.autosave_minutes.saturating_sub(1);
+            }
+            SettingsButton::Rebind(index) => {
+                settings.rebinding = Some(index);
+                settings.message = "Press a key, or Escape to cancel".to_string();
+            }
+            SettingsButton::Save => {
+                settings.message = match preferences.save() {
+                    Ok(()) => "Preferences saved".to_string(),
+                    Err(error) => format!("Could not save preferences: {error}"),
+                };
+            }
+            SettingsButton::Revert => {
+                *preferences = Preferences::load();
+                *standards = OfficeStandardsResource::load();
+                settings.message = "Preferences reverted and standards reloaded".to_string();
+            }
+        }
+    }
+}
+
+/// Bind the next key pressed to the action waiting for one
+pub fn rebind_keys(
+    mut keyboard_events: EventReader<KeyboardInput>,
+    mut preferences: ResMut<PreferencesResource>,
+    mut settings: ResMut<SettingsState>,
+) {
+    let Some(index) = settings.rebinding else {
+        keyboard_events.clear();
+        return;
+    };
+    for event in keyboard_events.read() {
+        if event.state != ButtonState::Pressed {
+            continue;
+        }
+        if event.key_code == KeyCode::Escape {
+            settings.rebinding = None;
+            settings.message.clear();
+            return;
+        }
+        if BINDABLE_KEYS.contains(&event.key_code) {
+            let action = KEY_ACTIONS[index];
+            preferences
+                .preferences
+                .keymap
+                .insert(action.to_string(), format!("{:?}", event.key_code));
+            settings.rebinding = None;
+            settings.message =
+                format!("Bound {} to {:?}", action.replace('_', " "), event.key_code);
+            return;
+        }
+    }
+}
+
+/// Push changed preferences into the resources that use them
+pub fn apply_preferences(
+    preferences: Res<PreferencesResource>,
+    mut camera_config: ResMut<CameraConfig>,
+    mut validation_state: ResMut<ValidationState>,
+    mut calibration: ResMut<UnderlayCalibration>,
+    mut outline_styles: ResMut<OutlineStyles>,
+    mut ui_scale: ResMut<UiScale>,
+) {
+    if !preferences.is_changed() {
+        return;
+    }
+    let preferences = &preferences.preferences;
+    camera_config.movement_speed = preferences.movement_speed;
+    camera_config.rotation_speed = preferences.rotation_speed;
+    camera_config.keys = camera_keys(preferences);
+    validation_state.pipeline.config.tolerance =
+        Tolerance::from_linear(preferences.linear_tolerance);
+    calibration.units = preferences.units;
+    outline_styles.width = preferences.outline_width;
+    for (phase, style) in &mut outline_styles.phases {
+        style.on_top = preferences.outline_on_top(*phase);
+    }
+    ui_scale.0 = preferences.text_scale;
+}
+
+/// The phases whose outlines are drawn on top, or `none`
+fn outlines_on_top(preferences: &Preferences) -> String {
+    let phases: Vec<&str> = Phase::ALL
+        .into_iter()
+        .filter(|phase| preferences.outline_on_top(*phase))
+        .map(|phase| phase.label())
+        .collect();
+    if phases.is_empty() {
+        "none".to_string()
+    } else {
+        phases.join(", ")
+    }
+}
+
+/// Show or hide the dialog and refresh its values
+pub fn update_settings_panel(
+    preferences: Res<PreferencesResource>,
+    standards: Res<OfficeStandardsResource>,
+    settings: Res<SettingsState>,
+    localization: Res<Localization>,
+    mut panel_query: Query<&mut Node, With<SettingsPanel>>,
+    mut text_query: Query<&mut Text, With<SettingsText>>,
+) {
+    if !preferences.is_changed() && !standards.is_changed() && !settings.is_changed() {
+        return;
+    }
+    for mut node in &mut panel_query {
+        node.display = if settings.open {
+            Display::Flex
+        } else {
+            Display::None
+        };
+    }
+
+    let preferences = &preferences.preferences;
+    let units = preferences.units;
+    let autosave = match preferences.autosave_minutes {
+        0 => "off".to_string(),
+        minutes => format!("every {minutes} min"),
+    };
+    let keys: Vec<String> = KEY_ACTIONS
+        .iter()
+        .map(|action| {
+            let key = preferences.keymap.get(*action).map_or("?", String::as_str);
+            format!("{}: {key}", action.replace('_', " "))
+        })
+        .collect();
+    let mut lines = vec![
+        format!("Units: {}", units.label()),
+        format!("Theme: {}", preferences.theme.label()),
+        format!(
+            "Language: {}",
+            localization.language_name(&preferences.language)
+        ),
+        format!("Scene: {}", preferences.scene.label()),
+        format!(
+            "Tolerance: {} {}",
+            units.from_meters(preferences.linear_tolerance),
+            units.length_symbol()
+        ),
+        format!("Move speed: {:.1} m/s", preferences.movement_speed),
+        format!("Orbit speed: {:.1} rad/s", preferences.rotation_speed),
+        format!("Outline width: {:.1} px", preferences.outline_width),
+        format!("Outlines on top: {}", outlines_on_top(preferences)),
+        format!("Text size: {:.0}%", preferences.text_scale * 100.0),
+        format!("Autosave: {autosave}"),
+        format!("Standards: {}", standards.source),
+        keys.join(", "),
+    ];
+    if !settings.message.is_empty() {
+        lines.push(settings.message.clone());
+    }
+    let status = lines.join("\n");
+    for mut text in &mut text_query {
+        text.0.clone_from(&status);
+    }
+}
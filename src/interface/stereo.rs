@@ -0,0 +1,201 @@
+use bevy::input_focus::InputFocus;
+use bevy::prelude::*;
+use bevy::render::camera::{Exposure, Viewport};
+
+use crate::interface::camera::MainCamera;
+use crate::interface::segment_outlines::{GeometryRegistryResource, SolidId};
+use crate::interface::selection::{pick_candidates, SelectionState};
+
+/// Key turning stereo on and off
+const STEREO_KEY: KeyCode = KeyCode::F2;
+/// Key selecting what the laser pointer points at
+const LASER_SELECT_KEY: KeyCode = KeyCode::Enter;
+/// Length of the laser when it points at nothing, in meters
+const LASER_LENGTH: f32 = 30.0;
+/// Where the laser starts, relative to the eyes: a hand held low and to
+/// the right
+const LASER_HAND: Vec3 = Vec3::new(0.15, -0.25, -0.1);
+/// Color of the laser pointer
+const LASER_COLOR: Color = Color::srgb(1.0, 0.2, 0.2);
+
+/// Resource holding the stereo view's state
+///
+/// In stereo the window is split in two: the main camera draws the left
+/// eye's view on the left half and a second camera, an eye's width to its
+/// right, draws the right eye's on the right half, for side-by-side
+/// headsets and viewers. A laser pointer from the viewer's hand follows
+/// their gaze and selects what it points at.
+#[derive(Resource)]
+pub struct StereoState {
+    /// Whether the view is split between two eyes
+    pub enabled: bool,
+    /// Distance between the eyes in meters
+    pub eye_separation: f32,
+}
+
+impl Default for StereoState {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            eye_separation: 0.064,
+        }
+    }
+}
+
+/// Marker component for the right eye's camera
+#[derive(Component)]
+pub struct StereoEye;
+
+/// Turn stereo on and off with F2, adding or removing the right eye
+pub fn toggle_stereo(
+    mut commands: Commands,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    main_query: Query<(Entity, &Exposure), With<MainCamera>>,
+    eye_query: Query<Entity, With<StereoEye>>,
+    mut state: ResMut<StereoState>,
+) {
+    if !keyboard_input.just_pressed(STEREO_KEY) {
+        return;
+    }
+    state.enabled = !state.enabled;
+    if !state.enabled {
+        for eye in &eye_query {
+            commands.entity(eye).despawn();
+        }
+        return;
+    }
+    let Ok((main, exposure)) = main_query.single() else {
+        return;
+    };
+    commands.entity(main).with_child((
+        StereoEye,
+        Camera3d::default(),
+        // Drawn before the main camera, so the main camera stays the one
+        // the UI is drawn with
+        Camera {
+            order: -1,
+            ..default()
+        },
+        Projection::Perspective(PerspectiveProjection::default()),
+        *exposure,
+        Transform::from_translation(Vec3::X * state.eye_separation),
+    ));
+}
+
+/// The viewport's position and size, for comparing viewports
+fn viewport_rect(camera: &Camera) -> Option<(UVec2, UVec2)> {
+    camera
+        .viewport
+        .as_ref()
+        .map(|viewport| (viewport.physical_position, viewport.physical_size))
+}
+
+/// Split the window between the eyes while stereo is on, keeping the right
+/// eye's projection and separation in step with the main camera
+pub fn fit_stereo_viewports(
+    state: Res<StereoState>,
+    windows: Query<&Window>,
+    mut main_query: Query<&mut Camera, (With<MainCamera>, Without<StereoEye>)>,
+    projection_query: Query<Ref<Projection>, (With<MainCamera>, Without<StereoEye>)>,
+    mut eye_query: Query<(&mut Camera, &mut Projection, &mut Transform), With<StereoEye>>,
+) {
+    let (Ok(window), Ok(mut main), Ok(projection)) = (
+        windows.single(),
+        main_query.single_mut(),
+        projection_query.single(),
+    ) else {
+        return;
+    };
+    let size = UVec2::new(window.physical_width(), window.physical_height());
+    let half = UVec2::new(size.x / 2, size.y);
+    let wanted = state.enabled.then_some((UVec2::ZERO, half));
+    if viewport_rect(&main) != wanted {
+        main.viewport = wanted.map(|(physical_position, physical_size)| Viewport {
+            physical_position,
+            physical_size,
+            ..default()
+        });
+    }
+    for (mut eye, mut eye_projection, mut transform) in &mut eye_query {
+        let right = (UVec2::new(half.x, 0), UVec2::new(size.x - half.x, size.y));
+        if viewport_rect(&eye) != Some(right) {
+            eye.viewport = Some(Viewport {
+                physical_position: right.0,
+                physical_size: right.1,
+                ..default()
+            });
+        }
+        if projection.is_changed() || eye_projection.is_added() {
+            *eye_projection = projection.clone();
+        }
+        let offset = Vec3::X * state.eye_separation;
+        if transform.translation != offset {
+            transform.translation = offset;
+        }
+    }
+}
+
+/// Draw the laser pointer along the viewer's gaze while stereo is on, and
+/// select the nearest thing it points at with Enter
+///
+/// What it selects follows the selection mode, as clicking does. Enter
+/// presses the focused button instead while a panel has focus.
+#[allow(clippy::too_many_arguments)]
+pub fn aim_laser_pointer(
+    mut gizmos: Gizmos,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    focus: Res<InputFocus>,
+    state: Res<StereoState>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    solid_query: Query<(&SolidId, &GlobalTransform, &InheritedVisibility)>,
+    geometry_registry: Res<GeometryRegistryResource>,
+    mut selection: ResMut<SelectionState>,
+) {
+    if !state.enabled {
+        return;
+    }
+    let Ok((camera, camera_transform)) = camera_query.single() else {
+        return;
+    };
+    let eye = camera_transform.translation();
+    let gaze = Ray3d::new(eye, camera_transform.forward());
+    let to_screen = |point: Vec3| {
+        camera
+            .world_to_viewport(camera_transform, point)
+            .ok()
+            .map(|screen| (screen, eye.distance(point)))
+    };
+    // Edges and vertices are picked near where the gaze meets the screen
+    let Some((aim, _)) = to_screen(gaze.get_point(LASER_LENGTH)) else {
+        return;
+    };
+    let candidates = pick_candidates(
+        selection.mode,
+        gaze,
+        aim,
+        &to_screen,
+        &geometry_registry.registry,
+        solid_query.iter(),
+    );
+    let distance = candidates
+        .first()
+        .map_or(LASER_LENGTH, |(_, _, distance)| *distance);
+    let hand = camera_transform.transform_point(LASER_HAND);
+    let target = gaze.get_point(distance);
+    gizmos.line(hand, target, LASER_COLOR);
+    if !candidates.is_empty() {
+        gizmos.sphere(
+            Isometry3d::from_translation(target),
+            0.02 * distance,
+            LASER_COLOR,
+        );
+    }
+    if focus.0.is_none() && keyboard_input.just_pressed(LASER_SELECT_KEY) {
+        selection.set_candidates(
+            candidates
+                .into_iter()
+                .map(|(candidate, solid, _)| (candidate, solid))
+                .collect(),
+        );
+    }
+}
@@ -0,0 +1,127 @@
+use bevy::prelude::*;
+
+use crate::infrastructure::preferences::Theme;
+use crate::interface::settings::PreferencesResource;
+
+/// Resource holding the colors, font sizes and spacing of the interface
+///
+/// Panels take their look from here rather than from colors of their own,
+/// so switching the theme in the preferences restyles every panel.
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub struct UiTheme {
+    /// Background of panels, and of the main toggles while off
+    pub panel: Color,
+    /// Background of buttons
+    pub button: Color,
+    /// Background of buttons that are on or chosen
+    pub active: Color,
+    pub text: Color,
+    /// Font size of status and readout text
+    pub small_font_size: f32,
+    /// Padding inside buttons in pixels
+    pub button_padding: f32,
+    /// Padding inside panels in pixels
+    pub panel_padding: f32,
+}
+
+impl Default for UiTheme {
+    fn default() -> Self {
+        Self::of(Theme::default())
+    }
+}
+
+impl UiTheme {
+    /// The interface's look for a theme
+    #[must_use]
+    pub fn of(theme: Theme) -> Self {
+        match theme {
+            Theme::Dark => Self {
+                panel: Color::srgba(0.1, 0.1, 0.1, 0.8),
+                button: Color::srgba(0.15, 0.15, 0.15, 0.8),
+                active: Color::srgba(0.2, 0.4, 0.2, 0.8),
+                text: Color::WHITE,
+                small_font_size: 13.0,
+                button_padding: 5.0,
+                panel_padding: 8.0,
+            },
+            Theme::Light => Self {
+                panel: Color::srgba(0.93, 0.93, 0.94, 0.9),
+                button: Color::srgba(0.82, 0.83, 0.85, 0.9),
+                active: Color::srgba(0.62, 0.8, 0.62, 0.9),
+                text: Color::srgb(0.1, 0.1, 0.12),
+                small_font_size: 13.0,
+                button_padding: 5.0,
+                panel_padding: 8.0,
+            },
+        }
+    }
+
+    /// Background of a button that is on or off
+    #[must_use]
+    pub fn button_color(&self, active: bool) -> Color {
+        if active {
+            self.active
+        } else {
+            self.button
+        }
+    }
+
+    /// Background of a main toggle, which sits on the panel color while off
+    #[must_use]
+    pub fn toggle_color(&self, active: bool) -> Color {
+        if active {
+            self.active
+        } else {
+            self.panel
+        }
+    }
+
+    /// The color in this theme standing for a color of another
+    fn restyle(&self, previous: &UiTheme, color: Color) -> Color {
+        [
+            (previous.panel, self.panel),
+            (previous.button, self.button),
+            (previous.active, self.active),
+            (previous.text, self.text),
+        ]
+        .into_iter()
+        .find(|(old, _)| *old == color)
+        .map_or(color, |(_, new)| new)
+    }
+}
+
+/// Switch to the preferred theme, restyling the panels already spawned,
+/// and give newly spawned text the theme's color
+///
+/// Text is spawned in Bevy's default white, which is the dark theme's text
+/// color, so only other themes need to recolor it.
+pub fn apply_ui_theme(
+    preferences: Res<PreferencesResource>,
+    mut theme: ResMut<UiTheme>,
+    mut background_query: Query<&mut BackgroundColor>,
+    mut text_query: Query<&mut TextColor>,
+    mut new_text_query: Query<&mut TextColor, Added<TextColor>>,
+) {
+    if preferences.is_changed() {
+        let wanted = UiTheme::of(preferences.preferences.theme);
+        if wanted != *theme {
+            let previous = *theme;
+            *theme = wanted;
+            for mut background in &mut background_query {
+                background.0 = theme.restyle(&previous, background.0);
+            }
+            for mut text in &mut text_query {
+                text.0 = theme.restyle(&previous, text.0);
+            }
+            return;
+        }
+    }
+    if theme.text == TextColor::default().0 {
+        return;
+    }
+    for mut text in &mut new_text_query {
+        if text.0 == TextColor::default().0 {
+            text.0 = theme.text;
+        }
+    }
+}
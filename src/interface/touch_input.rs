@@ -0,0 +1,216 @@
+use bevy::input::touch::{ForceTouch, Touch};
+use bevy::prelude::*;
+
+use crate::domain::Point;
+use crate::interface::camera::{MainCamera, WalkState};
+use crate::interface::command_bus::AddSketchPath;
+use crate::interface::ui::UiState;
+
+/// Meters the camera moves per pixel the fingers pinch together or apart
+const TOUCH_ZOOM_SPEED: f32 = 0.02;
+/// Meters the camera moves per pixel the fingers drag together
+const TOUCH_PAN_SPEED: f32 = 0.01;
+/// Smallest viewport height a pinch zooms the orthographic view to
+const MIN_ORTHO_ZOOM: f32 = 0.5;
+/// Pressure below which a pen is taken to be resting rather than drawing
+const PEN_MIN_PRESSURE: f32 = 0.05;
+/// Shortest distance in meters between recorded points of a stroke
+const PEN_SPACING: f32 = 0.02;
+/// Line width in pixels of strokes drawn lightly, firmly and heavily
+const PEN_WIDTHS: [f32; 3] = [1.0, 2.5, 4.0];
+/// Color of pen strokes
+const PEN_COLOR: Color = Color::srgb(0.2, 0.5, 1.0);
+
+/// Gizmo group for pen strokes drawn with one of the `PEN_WIDTHS`
+#[derive(Default, Reflect, GizmoConfigGroup)]
+pub struct PenGizmos<const WEIGHT: usize>;
+
+/// A pen stroke on the ground, with the pressure at each point
+pub struct PenStroke {
+    /// The touch drawing the stroke
+    touch: u64,
+    points: Vec<(Vec3, f32)>,
+}
+
+/// Resource holding the pen strokes sketched so far and the one being
+/// drawn
+///
+/// Finished strokes are also added to the model as sketch segments; these
+/// keep their pressure so they are drawn as they were sketched.
+#[derive(Resource, Default)]
+pub struct PenSketch {
+    pub stroke: Option<PenStroke>,
+    pub strokes: Vec<PenStroke>,
+}
+
+/// Pressure of a touch from 0 to 1, or None for a finger
+///
+/// Fingers report no force on the tablets the app is used on, while pens
+/// do, so the force is what tells a pen from a finger.
+fn pressure(force: Option<ForceTouch>) -> Option<f32> {
+    #[allow(clippy::cast_possible_truncation)]
+    let pressure = match force? {
+        ForceTouch::Calibrated {
+            force,
+            max_possible_force,
+            ..
+        } => (force / max_possible_force) as f32,
+        ForceTouch::Normalized(force) => force as f32,
+    };
+    Some(pressure.clamp(0.0, 1.0))
+}
+
+/// Index into `PEN_WIDTHS` of the line drawn at a pressure
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn weight(pressure: f32) -> usize {
+    ((pressure * 3.0) as usize).min(PEN_WIDTHS.len() - 1)
+}
+
+/// Move the camera with two fingers: pinch to zoom, drag to pan and twist
+/// to orbit around the vertical
+pub fn touch_camera_gestures(
+    touches: Res<Touches>,
+    mut camera_query: Query<&mut Transform, With<MainCamera>>,
+    mut ui_state: ResMut<UiState>,
+    walk: Res<WalkState>,
+) {
+    let fingers: Vec<&Touch> = touches
+        .iter()
+        .filter(|touch| pressure(touch.force()).is_none())
+        .collect();
+    let [first, second] = fingers[..] else {
+        return;
+    };
+    if walk.walking && !ui_state.isometric_view {
+        return;
+    }
+    let Ok(mut transform) = camera_query.single_mut() else {
+        return;
+    };
+    let (before, after) = (
+        second.previous_position() - first.previous_position(),
+        second.position() - first.position(),
+    );
+    let pinch = after.length() - before.length();
+    if ui_state.isometric_view {
+        ui_state.ortho_zoom = (ui_state.ortho_zoom - pinch * TOUCH_ZOOM_SPEED).max(MIN_ORTHO_ZOOM);
+    } else {
+        let forward = transform.forward().as_vec3();
+        transform.translation += forward * pinch * TOUCH_ZOOM_SPEED;
+    }
+    // Screen Y points down, so dragging down moves the view up
+    let drag = (first.delta() + second.delta()) / 2.0;
+    let (right, up) = (transform.right().as_vec3(), transform.up().as_vec3());
+    transform.translation += (up * drag.y - right * drag.x) * TOUCH_PAN_SPEED;
+    let twist = before.angle_to(after);
+    if twist.is_finite() {
+        transform.rotate_around(Vec3::ZERO, Quat::from_axis_angle(Vec3::Y, twist));
+    }
+}
+
+/// Record pen strokes on the ground and add each finished one to the
+/// model as a sketch path
+///
+/// A stroke starting on a panel is left to the panel, and points pressed
+/// too lightly are skipped.
+pub fn record_pen_strokes(
+    touches: Res<Touches>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    interaction_query: Query<&Interaction>,
+    mut sketch: ResMut<PenSketch>,
+    mut sketch_paths: EventWriter<AddSketchPath>,
+) {
+    if sketch.stroke.is_none() {
+        let on_panel = interaction_query
+            .iter()
+            .any(|interaction| *interaction != Interaction::None);
+        let pen = touches
+            .iter_just_pressed()
+            .find(|touch| pressure(touch.force()).is_some());
+        if let (Some(pen), false) = (pen, on_panel) {
+            sketch.stroke = Some(PenStroke {
+                touch: pen.id(),
+                points: Vec::new(),
+            });
+        }
+    }
+    let Some(touch) = sketch.stroke.as_ref().map(|stroke| stroke.touch) else {
+        return;
+    };
+    if touches.just_canceled(touch) {
+        sketch.stroke = None;
+        return;
+    }
+    if let Some(pen) = touches.get_pressed(touch) {
+        let pressure = pressure(pen.force()).unwrap_or(0.0);
+        let Ok((camera, camera_transform)) = camera_query.single() else {
+            return;
+        };
+        let Ok(ray) = camera.viewport_to_world(camera_transform, pen.position()) else {
+            return;
+        };
+        let Some(distance) = ray.intersect_plane(Vec3::ZERO, InfinitePlane3d::new(Vec3::Y)) else {
+            return;
+        };
+        let point = ray.get_point(distance);
+        if let Some(stroke) = sketch.stroke.as_mut() {
+            let spaced = stroke
+                .points
+                .last()
+                .is_none_or(|(last, _)| last.distance(point) >= PEN_SPACING);
+            if pressure >= PEN_MIN_PRESSURE && spaced {
+                stroke.points.push((point, pressure));
+            }
+        }
+    } else if touches.just_released(touch) {
+        let Some(stroke) = sketch.stroke.take() else {
+            return;
+        };
+        if stroke.points.len() < 2 {
+            return;
+        }
+        sketch_paths.write(AddSketchPath {
+            points: stroke
+                .points
+                .iter()
+                .map(|(point, _)| Point {
+                    x: point.x,
+                    y: point.y,
+                    z: point.z,
+                })
+                .collect(),
+        });
+        sketch.strokes.push(stroke);
+    }
+}
+
+/// Set the width of each pen stroke weight
+pub fn configure_pen_gizmos(mut config_store: ResMut<GizmoConfigStore>) {
+    let configure = |config: &mut GizmoConfig, width: f32| {
+        config.line.width = width;
+        // Lift the strokes off the ground they are drawn on
+        config.depth_bias = -0.001;
+    };
+    configure(config_store.config_mut::<PenGizmos<0>>().0, PEN_WIDTHS[0]);
+    configure(config_store.config_mut::<PenGizmos<1>>().0, PEN_WIDTHS[1]);
+    configure(config_store.config_mut::<PenGizmos<2>>().0, PEN_WIDTHS[2]);
+}
+
+/// Draw the pen strokes, each piece as thick as it was pressed
+pub fn draw_pen_strokes(
+    sketch: Res<PenSketch>,
+    mut light: Gizmos<PenGizmos<0>>,
+    mut firm: Gizmos<PenGizmos<1>>,
+    mut heavy: Gizmos<PenGizmos<2>>,
+) {
+    for stroke in sketch.strokes.iter().chain(&sketch.stroke) {
+        for pair in stroke.points.windows(2) {
+            let ((start, _), (end, pressure)) = (pair[0], pair[1]);
+            match weight(pressure) {
+                0 => light.line(start, end, PEN_COLOR),
+                1 => firm.line(start, end, PEN_COLOR),
+                _ => heavy.line(start, end, PEN_COLOR),
+            }
+        }
+    }
+}
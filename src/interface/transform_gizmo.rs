@@ -0,0 +1,641 @@
+use bevy::prelude::*;
+use uuid::Uuid;
+
+use crate::application::selection::SelectionType;
+use crate::domain::GeometryRegistry;
+use crate::interface::camera::MainCamera;
+use crate::interface::command_bus::TransformVertices;
+use crate::interface::live_solve::{LiveEdit, LiveSolveState};
+use crate::interface::segment_outlines::{GeometryRegistryResource, SolidId};
+use crate::interface::selection::{cursor_ray, screen_segment_closest, SelectionState};
+use crate::interface::settings::{OfficeStandardsResource, PreferencesResource};
+use crate::interface::theme::UiTheme;
+
+/// Gizmo arm length as a share of its distance from the camera
+const GIZMO_SCALE: f32 = 0.15;
+/// How near the cursor must be to a handle, in pixels
+const HANDLE_PICK_RADIUS: f32 = 8.0;
+/// Where the plane handles start and end along their two axes, as shares
+/// of the arm length
+const PLANE_HANDLE_SPAN: (f32, f32) = (0.25, 0.45);
+/// Radius of the rotation rings as a share of the arm length
+const RING_RADIUS: f32 = 0.8;
+/// Points sampled around a ring for hit testing
+const RING_SAMPLES: usize = 48;
+/// Rays this close to parallel with an axis cannot drag along it
+const PARALLEL_EPSILON: f32 = 1e-4;
+/// The world axes, in handle order
+const AXES: [Vec3; 3] = [Vec3::X, Vec3::Y, Vec3::Z];
+/// Axis names for the readout
+const AXIS_NAMES: [&str; 3] = ["X", "Y", "Z"];
+/// Color of the handle under the cursor or being dragged
+const ACTIVE_COLOR: Color = Color::srgb(1.0, 0.9, 0.1);
+
+/// What dragging the gizmo does
+#[derive(Clone, Copy, PartialEq, Eq, Default)]
+pub enum GizmoMode {
+    #[default]
+    Move,
+    Rotate,
+    Scale,
+}
+
+impl GizmoMode {
+    /// The name shown in the readout
+    fn label(self) -> &'static str {
+        match self {
+            GizmoMode::Move => "Move",
+            GizmoMode::Rotate => "Rotate",
+            GizmoMode::Scale => "Scale",
+        }
+    }
+
+    /// The handles the gizmo shows in this mode
+    fn handles(self) -> Vec<GizmoHandle> {
+        match self {
+            GizmoMode::Move | GizmoMode::Scale => (0..3)
+                .map(GizmoHandle::Axis)
+                .chain((0..3).map(GizmoHandle::Plane))
+                .collect(),
+            GizmoMode::Rotate => (0..3).map(GizmoHandle::Ring).collect(),
+        }
+    }
+}
+
+/// A part of the gizmo that can be dragged, by axis index
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum GizmoHandle {
+    /// An arm along an axis
+    Axis(usize),
+    /// A square in the plane across an axis
+    Plane(usize),
+    /// A ring around an axis
+    Ring(usize),
+}
+
+/// Snapping steps used while Ctrl is held
+pub struct GizmoSnap {
+    /// Move step in meters
+    pub distance: f32,
+    /// Rotation step in degrees
+    pub angle: f32,
+    /// Scale factor step
+    pub factor: f32,
+}
+
+impl Default for GizmoSnap {
+    fn default() -> Self {
+        Self {
+            distance: 0.1,
+            angle: 15.0,
+            factor: 0.1,
+        }
+    }
+}
+
+/// A drag in progress
+pub struct GizmoDrag {
+    handle: GizmoHandle,
+    /// The entity showing the solid the selection belongs to
+    entity: Entity,
+    /// Whether the entity is moved to preview the drag, rather than the
+    /// selection highlight
+    moves_entity: bool,
+    /// The entity's transform when the drag started
+    start_transform: Transform,
+    /// The vertices written back when the drag ends
+    vertices: Vec<Uuid>,
+    center: Vec3,
+    /// Where the cursor met the handle's axis or plane when the drag started
+    start_point: Vec3,
+    /// The world-space change so far
+    delta: Mat4,
+}
+
+/// Resource holding the transform gizmo's mode and drag
+#[derive(Resource, Default)]
+pub struct GizmoState {
+    pub mode: GizmoMode,
+    pub snap: GizmoSnap,
+    /// The handle under the cursor, when not dragging
+    pub hovered: Option<GizmoHandle>,
+    pub drag: Option<GizmoDrag>,
+    /// Center and arm length of the gizmo this frame, None when hidden
+    pub placement: Option<(Vec3, f32)>,
+    /// The numeric readout beside the gizmo
+    pub readout: String,
+}
+
+impl GizmoState {
+    /// The vertices being dragged and the world-space change so far, for
+    /// a drag the selection highlight previews
+    #[must_use]
+    pub fn outline_preview(&self) -> Option<(&[Uuid], Mat4)> {
+        self.drag
+            .as_ref()
+            .filter(|drag| !drag.moves_entity)
+            .map(|drag| (drag.vertices.as_slice(), drag.delta))
+    }
+}
+
+/// Marker component for the gizmo's numeric readout
+#[derive(Component)]
+pub struct GizmoReadout;
+
+/// Setup the readout that follows the gizmo
+pub fn setup_gizmo_readout(mut commands: Commands, theme: Res<UiTheme>) {
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                padding: UiRect::axes(Val::Px(6.0), Val::Px(2.0)),
+                display: Display::None,
+                ..default()
+            },
+            BackgroundColor(theme.panel),
+            GizmoReadout,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new(""),
+                TextFont {
+                    font_size: theme.small_font_size,
+                    ..default()
+                },
+            ));
+        });
+}
+
+/// Switch the gizmo mode: G to move, R to rotate, T to scale
+pub fn switch_gizmo_mode(keyboard_input: Res<ButtonInput<KeyCode>>, mut state: ResMut<GizmoState>) {
+    if state.drag.is_some() {
+        return;
+    }
+    let mode = if keyboard_input.just_pressed(KeyCode::KeyG) {
+        GizmoMode::Move
+    } else if keyboard_input.just_pressed(KeyCode::KeyR) {
+        GizmoMode::Rotate
+    } else if keyboard_input.just_pressed(KeyCode::KeyT) {
+        GizmoMode::Scale
+    } else {
+        return;
+    };
+    if state.mode != mode {
+        state.mode = mode;
+    }
+}
+
+/// The point where a ray meets a handle's axis or plane
+fn handle_point(handle: GizmoHandle, center: Vec3, ray: Ray3d) -> Option<Vec3> {
+    match handle {
+        GizmoHandle::Axis(axis) => {
+            // Closest point on the axis line to the ray
+            let direction = AXES[axis];
+            let offset = center - ray.origin;
+            let cosine = direction.dot(*ray.direction);
+            let denominator = 1.0 - cosine * cosine;
+            if denominator < PARALLEL_EPSILON {
+                return None;
+            }
+            let along = (cosine * ray.direction.dot(offset) - direction.dot(offset)) / denominator;
+            Some(center + direction * along)
+        }
+        GizmoHandle::Plane(axis) | GizmoHandle::Ring(axis) => ray
+            .intersect_plane(center, InfinitePlane3d::new(AXES[axis]))
+            .map(|distance| ray.get_point(distance)),
+    }
+}
+
+/// Round a value to the nearest multiple of a step
+fn snap_to(value: f32, step: f32) -> f32 {
+    if step > 0.0 {
+        (value / step).round() * step
+    } else {
+        value
+    }
+}
+
+/// The world-space change for dragging a handle from one point to
+/// another, and its readout with lengths written by `dimension`
+fn drag_delta(
+    state: &GizmoState,
+    drag: &GizmoDrag,
+    point: Vec3,
+    snapping: bool,
+    dimension: &dyn Fn(f32) -> String,
+) -> Option<(Mat4, String)> {
+    let snap = &state.snap;
+    let center = drag.center;
+    let (from, to) = (drag.start_point - center, point - center);
+    let about_center =
+        |change: Mat4| Mat4::from_translation(center) * change * Mat4::from_translation(-center);
+    match (state.mode, drag.handle) {
+        (GizmoMode::Move, GizmoHandle::Axis(axis)) => {
+            let mut distance = (to - from).dot(AXES[axis]);
+            if snapping {
+                distance = snap_to(distance, snap.distance);
+            }
+            Some((
+                Mat4::from_translation(AXES[axis] * distance),
+                format!("Move {} {}", AXIS_NAMES[axis], dimension(distance)),
+            ))
+        }
+        (GizmoMode::Move, GizmoHandle::Plane(axis)) => {
+            let mut offset = to - from;
+            if snapping {
+                offset = Vec3::new(
+                    snap_to(offset.x, snap.distance),
+                    snap_to(offset.y, snap.distance),
+                    snap_to(offset.z, snap.distance),
+                );
+            }
+            let (first, second) = ((axis + 1) % 3, (axis + 2) % 3);
+            let (first, second) = (first.min(second), first.max(second));
+            Some((
+                Mat4::from_translation(offset),
+                format!(
+                    "Move {}{} {}, {}",
+                    AXIS_NAMES[first],
+                    AXIS_NAMES[second],
+                    dimension(offset[first]),
+                    dimension(offset[second])
+                ),
+            ))
+        }
+        (GizmoMode::Rotate, GizmoHandle::Ring(axis)) => {
+            let mut angle = AXES[axis]
+                .dot(from.cross(to))
+                .atan2(from.dot(to))
+                .to_degrees();
+            if snapping {
+                angle = snap_to(angle, snap.angle);
+            }
+            Some((
+                about_center(Mat4::from_axis_angle(AXES[axis], angle.to_radians())),
+                format!("Rotate {} {angle:.1}°", AXIS_NAMES[axis]),
+            ))
+        }
+        (GizmoMode::Scale, GizmoHandle::Axis(axis)) => {
+            let start = from.dot(AXES[axis]);
+            if start.abs() < PARALLEL_EPSILON {
+                return None;
+            }
+            let mut factor = to.dot(AXES[axis]) / start;
+            if snapping {
+                factor = snap_to(factor, snap.factor);
+            }
+            let mut scale = Vec3::ONE;
+            scale[axis] = factor;
+            Some((
+                about_center(Mat4::from_scale(scale)),
+                format!("Scale {} ×{factor:.2}", AXIS_NAMES[axis]),
+            ))
+        }
+        (GizmoMode::Scale, GizmoHandle::Plane(axis)) => {
+            if from.length() < PARALLEL_EPSILON {
+                return None;
+            }
+            let mut factor = to.length() / from.length();
+            if snapping {
+                factor = snap_to(factor, snap.factor);
+            }
+            let mut scale = Vec3::splat(factor);
+            scale[axis] = 1.0;
+            Some((
+                about_center(Mat4::from_scale(scale)),
+                format!("Scale ×{factor:.2}"),
+            ))
+        }
+        _ => None,
+    }
+}
+
+/// The corners of a plane handle
+fn plane_corners(axis: usize, center: Vec3, length: f32) -> [Vec3; 4] {
+    let (first, second) = (AXES[(axis + 1) % 3], AXES[(axis + 2) % 3]);
+    let (near, far) = (PLANE_HANDLE_SPAN.0 * length, PLANE_HANDLE_SPAN.1 * length);
+    [
+        center + first * near + second * near,
+        center + first * far + second * near,
+        center + first * far + second * far,
+        center + first * near + second * far,
+    ]
+}
+
+/// Points around a rotation ring
+fn ring_points(axis: usize, center: Vec3, length: f32) -> Vec<Vec3> {
+    let rotation = Quat::from_rotation_arc(Vec3::Z, AXES[axis]);
+    #[allow(clippy::cast_precision_loss)]
+    (0..=RING_SAMPLES)
+        .map(|sample| {
+            let angle = std::f32::consts::TAU * sample as f32 / RING_SAMPLES as f32;
+            center + rotation * Vec3::new(angle.cos(), angle.sin(), 0.0) * length * RING_RADIUS
+        })
+        .collect()
+}
+
+/// Distance on screen from the cursor to a handle, zero inside a plane
+/// handle
+fn handle_distance(
+    handle: GizmoHandle,
+    cursor: Vec2,
+    center: Vec3,
+    length: f32,
+    to_screen: &impl Fn(Vec3) -> Option<Vec2>,
+) -> Option<f32> {
+    let polyline = |points: &[Vec3]| -> Option<f32> {
+        let screen: Option<Vec<Vec2>> = points.iter().map(|point| to_screen(*point)).collect();
+        screen?
+            .windows(2)
+            .map(|pair| screen_segment_closest(cursor, pair[0], pair[1]).1)
+            .min_by(f32::total_cmp)
+    };
+    match handle {
+        GizmoHandle::Axis(axis) => polyline(&[center, center + AXES[axis] * length]),
+        GizmoHandle::Ring(axis) => polyline(&ring_points(axis, center, length)),
+        GizmoHandle::Plane(axis) => {
+            let corners = plane_corners(axis, center, length);
+            let screen: Option<Vec<Vec2>> = corners.iter().map(|point| to_screen(*point)).collect();
+            let screen = screen?;
+            // Inside when the cursor is on the same side of every edge
+            let sides: Vec<f32> = (0..4)
+                .map(|index| {
+                    let (a, b) = (screen[index], screen[(index + 1) % 4]);
+                    (b - a).perp_dot(cursor - a)
+                })
+                .collect();
+            let inside =
+                sides.iter().all(|side| *side >= 0.0) || sides.iter().all(|side| *side <= 0.0);
+            Some(if inside { 0.0 } else { f32::INFINITY })
+        }
+    }
+}
+
+/// The average world position of a set of vertices shown through a
+/// transform
+fn vertex_center(
+    registry: &GeometryRegistry,
+    vertices: &[Uuid],
+    transform: &Transform,
+) -> Option<Vec3> {
+    let positions: Vec<Vec3> = vertices
+        .iter()
+        .filter_map(|id| registry.vertices.get(id))
+        .map(|vertex| {
+            let position = &vertex.position;
+            transform.transform_point(Vec3::new(position.x, position.y, position.z))
+        })
+        .collect();
+    #[allow(clippy::cast_precision_loss)]
+    (!positions.is_empty()).then(|| positions.iter().sum::<Vec3>() / positions.len() as f32)
+}
+
+/// Hover, drag and release the gizmo on the selection
+///
+/// A selected solid's entity is moved to preview a drag; faces, edges and
+/// vertices are previewed by the selection highlight. On release the
+/// change is sent as a vertex transform in the solid's own coordinates and
+/// the entity is put back; Escape cancels the drag. While solving on edit,
+/// each frame of the drag is handed to the live solve instead, and on
+/// release it waits to be committed or reverted there.
+#[allow(clippy::too_many_arguments)]
+pub fn drag_transform_gizmo(
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    windows: Query<&Window>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    mut solid_query: Query<(Entity, &SolidId, &mut Transform)>,
+    geometry_registry: Res<GeometryRegistryResource>,
+    selection: Res<SelectionState>,
+    preferences: Res<PreferencesResource>,
+    standards: Res<OfficeStandardsResource>,
+    mut state: ResMut<GizmoState>,
+    mut live: ResMut<LiveSolveState>,
+    mut transforms: EventWriter<TransformVertices>,
+) {
+    let target = selection
+        .selected
+        .zip(selection.solid)
+        .and_then(|(selected, solid)| {
+            solid_query
+                .iter()
+                .find(|(_, id, _)| id.0 == solid)
+                .map(|(entity, _, transform)| (entity, *transform, selected))
+        });
+    let (Ok(window), Ok((camera, camera_transform)), Some((entity, transform, selected))) =
+        (windows.single(), camera_query.single(), target)
+    else {
+        if let Some(drag) = state.drag.take() {
+            if let Ok((_, _, mut transform)) = solid_query.get_mut(drag.entity) {
+                *transform = drag.start_transform;
+            }
+        }
+        state.placement = None;
+        state.hovered = None;
+        return;
+    };
+    let ray = cursor_ray(window, camera, camera_transform);
+
+    if let Some(drag) = state.drag.take() {
+        let snapping = keyboard_input.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]);
+        let mut drag = drag;
+        let units = preferences.preferences.units;
+        let dimension = |meters| standards.standards.dimensions.format(meters, units);
+        if let Some((delta, readout)) = ray
+            .and_then(|ray| handle_point(drag.handle, drag.center, ray))
+            .and_then(|point| drag_delta(&state, &drag, point, snapping, &dimension))
+        {
+            drag.delta = delta;
+            state.readout = readout;
+        }
+        let start = drag.start_transform.compute_matrix();
+        let cancelled = keyboard_input.just_pressed(KeyCode::Escape);
+        if let (true, Ok((_, _, mut transform))) =
+            (drag.moves_entity, solid_query.get_mut(drag.entity))
+        {
+            *transform = if cancelled || !mouse_input.pressed(MouseButton::Left) {
+                drag.start_transform
+            } else {
+                Transform::from_matrix(drag.delta * start)
+            };
+        }
+        let length = camera_transform.translation().distance(drag.center) * GIZMO_SCALE;
+        state.placement = Some((drag.delta.transform_point3(drag.center), length));
+        let released = !mouse_input.pressed(MouseButton::Left);
+        if cancelled {
+            live.clear();
+        } else {
+            send_drag_change(&drag, start, released, &mut live, &mut transforms);
+        }
+        if released || cancelled {
+            state.readout.clear();
+        } else {
+            state.drag = Some(drag);
+        }
+        return;
+    }
+
+    let vertices = selected.vertices(&geometry_registry.registry);
+    let Some(center) = vertex_center(&geometry_registry.registry, &vertices, &transform) else {
+        state.placement = None;
+        return;
+    };
+    let length = camera_transform.translation().distance(center) * GIZMO_SCALE;
+    state.placement = Some((center, length));
+
+    let to_screen = |point: Vec3| camera.world_to_viewport(camera_transform, point).ok();
+    let hovered = window.cursor_position().and_then(|cursor| {
+        state
+            .mode
+            .handles()
+            .into_iter()
+            .filter_map(|handle| {
+                handle_distance(handle, cursor, center, length, &to_screen)
+                    .map(|distance| (handle, distance))
+            })
+            .filter(|(_, distance)| *distance <= HANDLE_PICK_RADIUS)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(handle, _)| handle)
+    });
+    if state.hovered != hovered {
+        state.hovered = hovered;
+    }
+
+    let pressed = mouse_input.just_pressed(MouseButton::Left) && !live.awaiting_choice();
+    if let (Some(handle), true) = (hovered, pressed) {
+        if let Some(start_point) = ray.and_then(|ray| handle_point(handle, center, ray)) {
+            state.drag = Some(GizmoDrag {
+                handle,
+                entity,
+                moves_entity: selected.selection_type == SelectionType::Solid,
+                start_transform: transform,
+                vertices,
+                center,
+                start_point,
+                delta: Mat4::IDENTITY,
+            });
+        }
+    }
+}
+
+/// Hand the change so far to the live solve while solving on edit, or
+/// otherwise send it as a vertex transform in the solid's own coordinates
+/// once the drag is released
+fn send_drag_change(
+    drag: &GizmoDrag,
+    start: Mat4,
+    released: bool,
+    live: &mut LiveSolveState,
+    transforms: &mut EventWriter<TransformVertices>,
+) {
+    let transform = start.inverse() * drag.delta * start;
+    if live.enabled {
+        live.edit = Some(LiveEdit {
+            origin: start,
+            vertices: drag.vertices.clone(),
+            transform,
+        });
+        live.released = released;
+    } else if released {
+        transforms.write(TransformVertices {
+            vertices: drag.vertices.clone(),
+            transform,
+        });
+    }
+}
+
+/// The color of a handle, brighter while hovered or dragged
+fn handle_color(state: &GizmoState, handle: GizmoHandle, axis: usize) -> Color {
+    let active = state.hovered == Some(handle)
+        || state
+            .drag
+            .as_ref()
+            .is_some_and(|drag| drag.handle == handle);
+    if active {
+        return ACTIVE_COLOR;
+    }
+    match axis {
+        0 => Color::srgb(0.9, 0.2, 0.2),
+        1 => Color::srgb(0.2, 0.8, 0.2),
+        _ => Color::srgb(0.2, 0.4, 0.9),
+    }
+}
+
+/// Draw the gizmo's handles for the current mode
+pub fn draw_transform_gizmo(mut gizmos: Gizmos, state: Res<GizmoState>) {
+    let Some((center, length)) = state.placement else {
+        return;
+    };
+    for handle in state.mode.handles() {
+        match handle {
+            GizmoHandle::Axis(axis) => {
+                let color = handle_color(&state, handle, axis);
+                let end = center + AXES[axis] * length;
+                if state.mode == GizmoMode::Scale {
+                    gizmos.line(center, end, color);
+                    gizmos.cuboid(
+                        Transform::from_translation(end).with_scale(Vec3::splat(length * 0.08)),
+                        color,
+                    );
+                } else {
+                    gizmos.arrow(center, end, color);
+                }
+            }
+            GizmoHandle::Plane(axis) => {
+                let color = handle_color(&state, handle, axis);
+                let corners = plane_corners(axis, center, length);
+                gizmos.linestrip(corners.iter().chain(corners.first()).copied(), color);
+            }
+            GizmoHandle::Ring(axis) => {
+                let color = handle_color(&state, handle, axis);
+                gizmos.linestrip(ring_points(axis, center, length), color);
+            }
+        }
+    }
+}
+
+/// Show the readout beside the gizmo, or the mode keys while idle
+pub fn update_gizmo_readout(
+    state: Res<GizmoState>,
+    live: Res<LiveSolveState>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    mut readout_query: Query<(&mut Node, &Children), With<GizmoReadout>>,
+    mut text_query: Query<&mut Text>,
+) {
+    let Ok((mut node, children)) = readout_query.single_mut() else {
+        return;
+    };
+    let screen = state.placement.and_then(|(center, _)| {
+        let (camera, transform) = camera_query.single().ok()?;
+        camera.world_to_viewport(transform, center).ok()
+    });
+    let Some(screen) = screen else {
+        if node.display != Display::None {
+            node.display = Display::None;
+        }
+        return;
+    };
+    node.display = Display::Flex;
+    node.left = Val::Px(screen.x + 16.0);
+    node.top = Val::Px(screen.y + 16.0);
+    let label = if state.readout.is_empty() {
+        format!(
+            "{}{} (G move, R rotate, T scale, L solve on edit, Ctrl snaps)",
+            state.mode.label(),
+            if live.enabled {
+                ", solving on edit"
+            } else {
+                ""
+            }
+        )
+    } else {
+        state.readout.clone()
+    };
+    for child in children {
+        if let Ok(mut text) = text_query.get_mut(*child) {
+            if text.0 != label {
+                text.0.clone_from(&label);
+            }
+        }
+    }
+}
@@ -1,5 +1,11 @@
 use bevy::prelude::*;
 
+use crate::domain::PhaseFilter;
+use crate::interface::isolation::HiddenSolids;
+use crate::interface::segment_outlines::{GeometryRegistryResource, SolidId};
+use crate::interface::selection::{selection_mode_label, SelectionModeButton, SELECTION_MODES};
+use crate::interface::theme::UiTheme;
+
 /// Resource to track UI state
 #[derive(Resource)]
 pub struct UiState {
@@ -11,15 +17,18 @@ pub struct UiState {
     pub isometric_view: bool,
     /// Orthographic viewport height (smaller = more zoomed in)
     pub ortho_zoom: f32,
+    /// Which construction phases are shown
+    pub phase_filter: PhaseFilter,
 }
 
 impl Default for UiState {
     fn default() -> Self {
         Self {
             show_outlines: false,
-            show_surfaces: true,   // Surfaces visible by default
-            isometric_view: false, // Perspective view by default
-            ortho_zoom: 8.5,       // Default viewport height
+            show_surfaces: true,                  // Surfaces visible by default
+            isometric_view: false,                // Perspective view by default
+            ortho_zoom: 8.5,                      // Default viewport height
+            phase_filter: PhaseFilter::default(), // All phases shown by default
         }
     }
 }
@@ -48,6 +57,14 @@ pub struct IsometricToggleButton;
 #[derive(Component)]
 pub struct IsometricButtonText;
 
+/// Marker component for the phase filter button
+#[derive(Component)]
+pub struct PhaseToggleButton;
+
+/// Marker component for the phase filter button text
+#[derive(Component)]
+pub struct PhaseButtonText;
+
 /// Marker components for camera view buttons
 #[derive(Component)]
 pub struct FrontViewButton;
@@ -72,7 +89,7 @@ pub struct BottomViewButton;
 pub struct ToggleableMesh;
 
 /// Setup the UI overlay
-pub fn setup_ui(mut commands: Commands) {
+pub fn setup_ui(mut commands: Commands, theme: Res<UiTheme>) {
     // Create a root node for the UI
     commands
         .spawn((
@@ -96,7 +113,7 @@ pub fn setup_ui(mut commands: Commands) {
                         margin: UiRect::bottom(Val::Px(5.0)),
                         ..default()
                     },
-                    BackgroundColor(Color::srgba(0.1, 0.1, 0.1, 0.8)),
+                    BackgroundColor(theme.panel),
                 ))
                 .with_children(|parent| {
                     parent.spawn((Text::new("Show Outlines: OFF"), OutlineButtonText));
@@ -112,7 +129,7 @@ pub fn setup_ui(mut commands: Commands) {
                         margin: UiRect::bottom(Val::Px(5.0)),
                         ..default()
                     },
-                    BackgroundColor(Color::srgba(0.2, 0.4, 0.2, 0.8)), // Green since default is ON
+                    BackgroundColor(theme.active), // Active since default is ON
                 ))
                 .with_children(|parent| {
                     parent.spawn((Text::new("Show Surfaces: ON"), SurfacesButtonText));
@@ -128,12 +145,28 @@ pub fn setup_ui(mut commands: Commands) {
                         margin: UiRect::bottom(Val::Px(5.0)),
                         ..default()
                     },
-                    BackgroundColor(Color::srgba(0.1, 0.1, 0.1, 0.8)),
+                    BackgroundColor(theme.panel),
                 ))
                 .with_children(|parent| {
                     parent.spawn((Text::new("Isometric: OFF"), IsometricButtonText));
                 });
 
+            // Button cycling the phase filter
+            parent
+                .spawn((
+                    Button,
+                    PhaseToggleButton,
+                    Node {
+                        padding: UiRect::all(Val::Px(10.0)),
+                        margin: UiRect::bottom(Val::Px(5.0)),
+                        ..default()
+                    },
+                    BackgroundColor(theme.panel),
+                ))
+                .with_children(|parent| {
+                    parent.spawn((Text::new("Phases: All"), PhaseButtonText));
+                });
+
             // Camera view buttons - tight grouping
             parent
                 .spawn((
@@ -151,11 +184,11 @@ pub fn setup_ui(mut commands: Commands) {
                             Button,
                             FrontViewButton,
                             Node {
-                                padding: UiRect::all(Val::Px(5.0)),
+                                padding: UiRect::all(Val::Px(theme.button_padding)),
                                 margin: UiRect::right(Val::Px(3.0)),
                                 ..default()
                             },
-                            BackgroundColor(Color::srgba(0.15, 0.15, 0.15, 0.8)),
+                            BackgroundColor(theme.button),
                         ))
                         .with_children(|parent| {
                             parent.spawn(Text::new("Front"));
@@ -167,11 +200,11 @@ pub fn setup_ui(mut commands: Commands) {
                             Button,
                             TopViewButton,
                             Node {
-                                padding: UiRect::all(Val::Px(5.0)),
+                                padding: UiRect::all(Val::Px(theme.button_padding)),
                                 margin: UiRect::right(Val::Px(3.0)),
                                 ..default()
                             },
-                            BackgroundColor(Color::srgba(0.15, 0.15, 0.15, 0.8)),
+                            BackgroundColor(theme.button),
                         ))
                         .with_children(|parent| {
                             parent.spawn(Text::new("Top"));
@@ -183,11 +216,11 @@ pub fn setup_ui(mut commands: Commands) {
                             Button,
                             LeftViewButton,
                             Node {
-                                padding: UiRect::all(Val::Px(5.0)),
+                                padding: UiRect::all(Val::Px(theme.button_padding)),
                                 margin: UiRect::right(Val::Px(3.0)),
                                 ..default()
                             },
-                            BackgroundColor(Color::srgba(0.15, 0.15, 0.15, 0.8)),
+                            BackgroundColor(theme.button),
                         ))
                         .with_children(|parent| {
                             parent.spawn(Text::new("Left"));
@@ -199,11 +232,11 @@ pub fn setup_ui(mut commands: Commands) {
                             Button,
                             RightViewButton,
                             Node {
-                                padding: UiRect::all(Val::Px(5.0)),
+                                padding: UiRect::all(Val::Px(theme.button_padding)),
                                 margin: UiRect::right(Val::Px(3.0)),
                                 ..default()
                             },
-                            BackgroundColor(Color::srgba(0.15, 0.15, 0.15, 0.8)),
+                            BackgroundColor(theme.button),
                         ))
                         .with_children(|parent| {
                             parent.spawn(Text::new("Right"));
@@ -215,11 +248,11 @@ pub fn setup_ui(mut commands: Commands) {
                             Button,
                             BackViewButton,
                             Node {
-                                padding: UiRect::all(Val::Px(5.0)),
+                                padding: UiRect::all(Val::Px(theme.button_padding)),
                                 margin: UiRect::right(Val::Px(3.0)),
                                 ..default()
                             },
-                            BackgroundColor(Color::srgba(0.15, 0.15, 0.15, 0.8)),
+                            BackgroundColor(theme.button),
                         ))
                         .with_children(|parent| {
                             parent.spawn(Text::new("Back"));
@@ -231,15 +264,44 @@ pub fn setup_ui(mut commands: Commands) {
                             Button,
                             BottomViewButton,
                             Node {
-                                padding: UiRect::all(Val::Px(5.0)),
+                                padding: UiRect::all(Val::Px(theme.button_padding)),
                                 ..default()
                             },
-                            BackgroundColor(Color::srgba(0.15, 0.15, 0.15, 0.8)),
+                            BackgroundColor(theme.button),
                         ))
                         .with_children(|parent| {
                             parent.spawn(Text::new("Bottom"));
                         });
                 });
+
+            // Selection mode buttons
+            parent
+                .spawn((
+                    Node {
+                        flex_direction: FlexDirection::Row,
+                        margin: UiRect::top(Val::Px(5.0)),
+                        ..default()
+                    },
+                    BackgroundColor(Color::NONE),
+                ))
+                .with_children(|parent| {
+                    for mode in SELECTION_MODES {
+                        parent
+                            .spawn((
+                                Button,
+                                SelectionModeButton(mode),
+                                Node {
+                                    padding: UiRect::all(Val::Px(theme.button_padding)),
+                                    margin: UiRect::right(Val::Px(3.0)),
+                                    ..default()
+                                },
+                                BackgroundColor(theme.button),
+                            ))
+                            .with_children(|parent| {
+                                parent.spawn(Text::new(selection_mode_label(mode)));
+                            });
+                    }
+                });
         });
 }
 
@@ -257,6 +319,10 @@ pub fn handle_ui_interactions(
         &Interaction,
         (Changed<Interaction>, With<IsometricToggleButton>),
     >,
+    mut phase_interaction_query: Query<
+        &Interaction,
+        (Changed<Interaction>, With<PhaseToggleButton>),
+    >,
     mut ui_state: ResMut<UiState>,
 ) {
     // Handle outline toggle
@@ -279,6 +345,31 @@ pub fn handle_ui_interactions(
             ui_state.isometric_view = !ui_state.isometric_view;
         }
     }
+
+    // Handle phase filter cycling
+    for interaction in &mut phase_interaction_query {
+        if *interaction == Interaction::Pressed {
+            ui_state.phase_filter = next_phase_filter(ui_state.phase_filter);
+        }
+    }
+}
+
+/// The phase filter views the button cycles through, with their labels
+fn phase_filter_views() -> [(PhaseFilter, &'static str); 3] {
+    [
+        (PhaseFilter::default(), "All"),
+        (PhaseFilter::proposed(), "Proposed"),
+        (PhaseFilter::survey(), "Survey"),
+    ]
+}
+
+/// The view after the current one, wrapping back to showing all phases
+fn next_phase_filter(current: PhaseFilter) -> PhaseFilter {
+    let views = phase_filter_views();
+    let index = views.iter().position(|(filter, _)| *filter == current);
+    index.map_or(PhaseFilter::default(), |index| {
+        views[(index + 1) % views.len()].0
+    })
 }
 
 /// Event to request a camera view change
@@ -343,34 +434,24 @@ pub fn update_button_appearance(
         Query<&mut Text, With<OutlineButtonText>>,
         Query<&mut Text, With<SurfacesButtonText>>,
         Query<&mut Text, With<IsometricButtonText>>,
+        Query<&mut Text, With<PhaseButtonText>>,
     )>,
     ui_state: Res<UiState>,
+    theme: Res<UiTheme>,
 ) {
     // Update outline button color
     for mut background_color in queries.p0().iter_mut() {
-        if ui_state.show_outlines {
-            *background_color = Color::srgba(0.2, 0.4, 0.2, 0.8).into();
-        } else {
-            *background_color = Color::srgba(0.1, 0.1, 0.1, 0.8).into();
-        }
+        *background_color = theme.toggle_color(ui_state.show_outlines).into();
     }
 
     // Update surfaces button color
     for mut background_color in queries.p1().iter_mut() {
-        if ui_state.show_surfaces {
-            *background_color = Color::srgba(0.2, 0.4, 0.2, 0.8).into();
-        } else {
-            *background_color = Color::srgba(0.1, 0.1, 0.1, 0.8).into();
-        }
+        *background_color = theme.toggle_color(ui_state.show_surfaces).into();
     }
 
     // Update isometric button color
     for mut background_color in queries.p2().iter_mut() {
-        if ui_state.isometric_view {
-            *background_color = Color::srgba(0.2, 0.4, 0.2, 0.8).into();
-        } else {
-            *background_color = Color::srgba(0.1, 0.1, 0.1, 0.8).into();
-        }
+        *background_color = theme.toggle_color(ui_state.isometric_view).into();
     }
 
     // Update outline button text
@@ -403,15 +484,33 @@ pub fn update_button_appearance(
         };
         *text = Text::new(new_text);
     }
+
+    // Update phase button text
+    for mut text in queries.p6().iter_mut() {
+        let label = phase_filter_views()
+            .into_iter()
+            .find(|(filter, _)| *filter == ui_state.phase_filter)
+            .map_or("Custom", |(_, label)| label);
+        *text = Text::new(format!("Phases: {label}"));
+    }
 }
 
 /// Toggle mesh visibility based on UI state
+/// Meshes whose solid's phase is filtered out, or which are hidden or
+/// isolated away, are hidden
 pub fn toggle_mesh_visibility(
-    mut mesh_query: Query<&mut Visibility, With<ToggleableMesh>>,
+    mut mesh_query: Query<(&mut Visibility, &SolidId), With<ToggleableMesh>>,
+    geometry_registry: Res<GeometryRegistryResource>,
     ui_state: Res<UiState>,
+    hidden: Res<HiddenSolids>,
 ) {
-    for mut visibility in &mut mesh_query {
-        if ui_state.show_surfaces {
+    for (mut visibility, solid_id) in &mut mesh_query {
+        let phase_shown = geometry_registry
+            .registry
+            .solids
+            .get(&solid_id.0)
+            .is_none_or(|solid| ui_state.phase_filter.includes(solid.phase));
+        if ui_state.show_surfaces && phase_shown && hidden.shows(&solid_id.0) {
             *visibility = Visibility::Visible;
         } else {
             *visibility = Visibility::Hidden;
@@ -0,0 +1,289 @@
+use bevy::asset::RenderAssetUsages;
+use bevy::image::{CompressedImageFormats, ImageSampler, ImageType};
+use bevy::input::keyboard::{Key, KeyboardInput};
+use bevy::input::ButtonState;
+use bevy::prelude::*;
+use bevy::render::mesh::{Indices, PrimitiveTopology};
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+use crate::domain::{new_underlay, Point, Underlay, UnderlayRegistry, UnderlaySource};
+use crate::infrastructure::preferences::UnitSystem;
+use crate::interface::camera::MainCamera;
+
+/// Scale of a freshly imported underlay, until it is calibrated
+const DEFAULT_METERS_PER_PIXEL: f32 = 0.01;
+
+/// Resource holding the underlays shown in the viewport
+#[derive(Resource)]
+pub struct UnderlayRegistryResource {
+    pub registry: UnderlayRegistry,
+}
+
+/// Component to track which underlay this entity shows
+#[derive(Component)]
+pub struct UnderlayId(pub Uuid);
+
+/// Event requesting an underlay import, centered on a work plane point
+#[derive(Event)]
+pub struct ImportUnderlayEvent {
+    pub path: PathBuf,
+    pub center: Point,
+}
+
+/// Resource tracking the two-point calibration of an underlay
+///
+/// After two points are picked on the underlay's work plane, the true
+/// distance between them is typed in the preferred units and applied with
+/// Enter; Escape cancels.
+#[derive(Resource, Default)]
+pub struct UnderlayCalibration {
+    pub underlay: Option<Uuid>,
+    pub picks: Vec<Point>,
+    pub entry: String,
+    pub units: UnitSystem,
+}
+
+impl UnderlayCalibration {
+    /// Start calibrating an underlay, discarding any calibration in progress
+    pub fn start(&mut self, underlay: Uuid) {
+        self.underlay = Some(underlay);
+        self.picks.clear();
+        self.entry.clear();
+    }
+}
+
+/// Errors raised while loading an underlay picture
+#[derive(Debug)]
+pub enum UnderlayError {
+    /// The file could not be read
+    Io(std::io::Error),
+    /// The file is not an image format that can be decoded
+    Decode(String),
+    /// PDF pages need rasterizing to an image first
+    PdfNotSupported,
+}
+
+impl std::fmt::Display for UnderlayError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UnderlayError::Io(error) => write!(f, "Could not read underlay: {error}"),
+            UnderlayError::Decode(message) => write!(f, "Could not decode underlay: {message}"),
+            UnderlayError::PdfNotSupported => {
+                write!(f, "PDF underlays must be exported as PNG or JPEG first")
+            }
+        }
+    }
+}
+
+impl std::error::Error for UnderlayError {}
+
+impl From<std::io::Error> for UnderlayError {
+    fn from(error: std::io::Error) -> Self {
+        UnderlayError::Io(error)
+    }
+}
+
+/// Decode an underlay picture, choosing the format by extension
+pub fn load_underlay_image(path: &Path) -> Result<Image, UnderlayError> {
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    if extension == "pdf" {
+        return Err(UnderlayError::PdfNotSupported);
+    }
+    let bytes = std::fs::read(path)?;
+    Image::from_buffer(
+        &bytes,
+        ImageType::Extension(&extension),
+        CompressedImageFormats::NONE,
+        true,
+        ImageSampler::linear(),
+        RenderAssetUsages::RENDER_WORLD,
+    )
+    .map_err(|error| UnderlayError::Decode(error.to_string()))
+}
+
+/// Build a quad one unit per pixel, lying in the XZ plane around its center
+///
+/// The top-left pixel is at -X -Z, matching the underlay's pixel axes.
+fn underlay_mesh(underlay: &Underlay) -> Mesh {
+    #[allow(clippy::cast_precision_loss)]
+    let (half_width, half_height) = (
+        underlay.pixel_size.0 as f32 / 2.0,
+        underlay.pixel_size.1 as f32 / 2.0,
+    );
+    Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::RENDER_WORLD,
+    )
+    .with_inserted_attribute(
+        Mesh::ATTRIBUTE_POSITION,
+        vec![
+            [-half_width, 0.0, -half_height],
+            [half_width, 0.0, -half_height],
+            [half_width, 0.0, half_height],
+            [-half_width, 0.0, half_height],
+        ],
+    )
+    .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, vec![[0.0, 1.0, 0.0]; 4])
+    .with_inserted_attribute(
+        Mesh::ATTRIBUTE_UV_0,
+        vec![[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 1.0]],
+    )
+    .with_inserted_indices(Indices::U32(vec![0, 3, 2, 0, 2, 1]))
+}
+
+/// Place the unit-per-pixel quad at the underlay's position and scale
+fn underlay_transform(underlay: &Underlay) -> Transform {
+    let center = underlay.center();
+    Transform::from_xyz(center.x, center.y, center.z)
+        .with_rotation(Quat::from_rotation_y(underlay.rotation))
+        .with_scale(Vec3::splat(underlay.meters_per_pixel))
+}
+
+/// System that imports requested underlays and starts their calibration
+pub fn import_underlays(
+    mut commands: Commands,
+    mut events: EventReader<ImportUnderlayEvent>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut images: ResMut<Assets<Image>>,
+    mut underlays: ResMut<UnderlayRegistryResource>,
+    mut calibration: ResMut<UnderlayCalibration>,
+) {
+    for event in events.read() {
+        let image = match load_underlay_image(&event.path) {
+            Ok(image) => image,
+            Err(error) => {
+                error!("{error}");
+                continue;
+            }
+        };
+        let (width, height) = (image.width(), image.height());
+        #[allow(clippy::cast_precision_loss)]
+        let origin = Point {
+            x: event.center.x - width as f32 * DEFAULT_METERS_PER_PIXEL / 2.0,
+            y: event.center.y,
+            z: event.center.z - height as f32 * DEFAULT_METERS_PER_PIXEL / 2.0,
+        };
+        let underlay = new_underlay(
+            UnderlaySource::Image(event.path.clone()),
+            (width, height),
+            origin,
+            DEFAULT_METERS_PER_PIXEL,
+        );
+
+        let material = materials.add(StandardMaterial {
+            base_color: Color::srgba(1.0, 1.0, 1.0, underlay.opacity),
+            base_color_texture: Some(images.add(image)),
+            alpha_mode: AlphaMode::Blend,
+            unlit: true,
+            double_sided: true,
+            cull_mode: None,
+            ..Default::default()
+        });
+        commands.spawn((
+            Mesh3d(meshes.add(underlay_mesh(&underlay))),
+            MeshMaterial3d(material),
+            underlay_transform(&underlay),
+            UnderlayId(underlay.id),
+        ));
+
+        info!(
+            "Imported underlay {}: pick two points, type their distance in {}, press Enter",
+            event.path.display(),
+            calibration.units.length_symbol()
+        );
+        calibration.start(underlays.registry.store(underlay));
+    }
+}
+
+/// System that picks calibration points and applies the typed distance
+pub fn calibrate_underlays(
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    mut keyboard_events: EventReader<KeyboardInput>,
+    windows: Query<&Window>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    mut underlays: ResMut<UnderlayRegistryResource>,
+    mut calibration: ResMut<UnderlayCalibration>,
+    mut underlay_entities: Query<(&UnderlayId, &mut Transform)>,
+) {
+    let Some(underlay_id) = calibration.underlay else {
+        keyboard_events.clear();
+        return;
+    };
+    let Some(underlay) = underlays.registry.get_mut(&underlay_id) else {
+        calibration.underlay = None;
+        return;
+    };
+
+    // Pick points where the cursor ray meets the work plane
+    if calibration.picks.len() < 2 && mouse_input.just_pressed(MouseButton::Left) {
+        let ray = windows
+            .single()
+            .ok()
+            .and_then(Window::cursor_position)
+            .zip(camera_query.single().ok())
+            .and_then(|(cursor, (camera, transform))| {
+                camera.viewport_to_world(transform, cursor).ok()
+            });
+        let plane_origin = Vec3::new(0.0, underlay.origin.y, 0.0);
+        let hit = ray.and_then(|ray| {
+            ray.intersect_plane(plane_origin, InfinitePlane3d::new(Vec3::Y))
+                .map(|distance| ray.get_point(distance))
+        });
+        if let Some(hit) = hit {
+            calibration.picks.push(Point {
+                x: hit.x,
+                y: hit.y,
+                z: hit.z,
+            });
+            info!("Calibration point {} picked", calibration.picks.len());
+        }
+    }
+
+    for event in keyboard_events.read() {
+        if event.state != ButtonState::Pressed {
+            continue;
+        }
+        match &event.logical_key {
+            Key::Escape => {
+                calibration.underlay = None;
+                info!("Underlay calibration cancelled");
+                return;
+            }
+            Key::Character(text) if calibration.picks.len() == 2 => {
+                let accepted = text.chars().filter(|c| c.is_ascii_digit() || *c == '.');
+                calibration.entry.extend(accepted);
+            }
+            Key::Backspace => {
+                calibration.entry.pop();
+            }
+            Key::Enter if calibration.picks.len() == 2 => {
+                let typed = calibration.entry.parse::<f32>().unwrap_or(0.0);
+                let distance = calibration.units.to_meters(typed);
+                let (first, second) = (&calibration.picks[0], &calibration.picks[1]);
+                if !underlay.calibrate(first, second, distance) {
+                    warn!("Enter a positive distance between two distinct points");
+                    calibration.entry.clear();
+                    continue;
+                }
+                for (id, mut transform) in &mut underlay_entities {
+                    if id.0 == underlay_id {
+                        *transform = underlay_transform(underlay);
+                    }
+                }
+                info!(
+                    "Underlay calibrated to {:.4} m per pixel",
+                    underlay.meters_per_pixel
+                );
+                calibration.underlay = None;
+                return;
+            }
+            _ => {}
+        }
+    }
+}
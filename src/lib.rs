@@ -18,4 +18,7 @@ pub mod domain;
 /// Infrastructure layer for the application
 pub mod infrastructure;
 /// Interface layer for the application
+#[cfg(feature = "interface")]
 pub mod interface;
+/// The types most library users need, in one import
+pub mod prelude;
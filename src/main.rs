@@ -1,13 +1,30 @@
+use bevy::log::{LogPlugin, DEFAULT_FILTER};
 use bevy::prelude::*;
-use harmony_arch::interface::InterfacePlugin;
+use harmony_arch::interface::{install_panic_hook, interface_log_layers, InterfacePlugin};
 
 fn main() {
     // Force higher shadow quality if possible
     std::env::set_var("BEVY_RENDER__SHADOW_MAP_SIZE", "4096");
     std::env::set_var("BEVY_RENDER__SHADOW_QUALITY", "high");
 
+    // Write unsaved work and a crash report if anything panics
+    install_panic_hook();
+
     App::new()
         .add_plugins(InterfacePlugin)
-        .add_plugins(DefaultPlugins)
+        // Close requests go through the file menu's unsaved-changes check
+        .add_plugins(
+            DefaultPlugins
+                .set(WindowPlugin {
+                    close_when_requested: false,
+                    ..default()
+                })
+                // The log console and profiling overlay need the model's debug spans
+                .set(LogPlugin {
+                    filter: format!("{DEFAULT_FILTER},harmony_arch=debug"),
+                    custom_layer: interface_log_layers,
+                    ..default()
+                }),
+        )
         .run();
 }
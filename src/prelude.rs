@@ -0,0 +1,14 @@
+/// The types most library users need, in one import
+///
+/// `use harmony_arch::prelude::*;` brings in the `Model` façade, the
+/// registries it owns, the geometry value types and the element, phase,
+/// tier and constraint types its methods take.
+pub use crate::application::Model;
+pub use crate::domain::geometry::Plane;
+pub use crate::domain::solver::{
+    Constraint, ConstraintError, ConstraintKind, ConstraintReference, ConstraintSet,
+};
+pub use crate::domain::{
+    new_id, with_deterministic_ids, Element, ElementKind, ElementRegistry, Garbage,
+    GeometryRegistry, Phase, Point, Tier, TierRegistry, Tolerance, Vector,
+};